@@ -0,0 +1,45 @@
+//! Bitmaps stored as bytes, with the lowest bit of each byte first, as used
+//! by the allocation maps of filesystems
+
+/// Check if a bit is set
+pub fn test_bit(map: &[u8], bit: usize) -> bool
+{
+    map[bit / 8] & (1 << (bit % 8)) != 0
+}
+
+/// Set a bit
+pub fn set_bit(map: &mut [u8], bit: usize)
+{
+    map[bit / 8] |= 1 << (bit % 8);
+}
+
+/// Clear a bit
+pub fn clear_bit(map: &mut [u8], bit: usize)
+{
+    map[bit / 8] &= !(1 << (bit % 8));
+}
+
+/// Find the first clear bit at or after `start`
+pub fn first_clear_bit(map: &[u8], start: usize) -> Option<usize>
+{
+    let mut bit = start;
+
+    while bit < map.len() * 8
+    {
+        // Skip over full bytes at once
+        if bit % 8 == 0 && map[bit / 8] == 0xFF
+        {
+            bit += 8;
+            continue;
+        }
+
+        if !test_bit(map, bit)
+        {
+            return Some(bit);
+        }
+
+        bit += 1;
+    }
+
+    None
+}
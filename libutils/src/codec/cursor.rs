@@ -0,0 +1,190 @@
+/// Reads fields in order from a byte buffer
+///
+/// Reads past the end of the buffer panic, callers check the length of the
+/// buffer before reading, as `FromBytes::from_bytes` does.
+#[derive(Debug, Clone)]
+pub struct Reader<'a>
+{
+    data: &'a [u8],
+    offset: usize
+}
+
+impl<'a> Reader<'a>
+{
+    /// Create a reader starting at the beginning of the buffer
+    pub fn new(data: &'a [u8]) -> Self
+    {
+        Self
+        {
+            data,
+            offset: 0
+        }
+    }
+
+    /// Get the offset of the next field
+    pub fn offset(&self) -> usize
+    {
+        self.offset
+    }
+
+    /// Get the number of bytes left to read
+    pub fn remaining(&self) -> usize
+    {
+        self.data.len().saturating_sub(self.offset)
+    }
+
+    /// Move to the given offset from the start of the buffer
+    pub fn seek(&mut self, offset: usize)
+    {
+        self.offset = offset;
+    }
+
+    /// Skip over padding or unused fields
+    pub fn skip(&mut self, count: usize)
+    {
+        self.offset += count;
+    }
+
+    /// Read a fixed size array of bytes
+    pub fn array<const N: usize>(&mut self) -> [u8; N]
+    {
+        let mut result = [0; N];
+        result.copy_from_slice(&self.data[self.offset..self.offset + N]);
+
+        self.offset += N;
+
+        result
+    }
+
+    /// Read a slice of bytes
+    pub fn bytes(&mut self, count: usize) -> &'a [u8]
+    {
+        let result = &self.data[self.offset..self.offset + count];
+
+        self.offset += count;
+
+        result
+    }
+
+    /// Read a byte
+    pub fn u8(&mut self) -> u8
+    {
+        u8::from_le_bytes(self.array())
+    }
+
+    /// Read a little endian u16
+    pub fn u16(&mut self) -> u16
+    {
+        u16::from_le_bytes(self.array())
+    }
+
+    /// Read a little endian u32
+    pub fn u32(&mut self) -> u32
+    {
+        u32::from_le_bytes(self.array())
+    }
+
+    /// Read a little endian u64
+    pub fn u64(&mut self) -> u64
+    {
+        u64::from_le_bytes(self.array())
+    }
+
+    /// Read a big endian u16, as used by network headers
+    pub fn u16_be(&mut self) -> u16
+    {
+        u16::from_be_bytes(self.array())
+    }
+
+    /// Read a big endian u32, as used by network headers
+    pub fn u32_be(&mut self) -> u32
+    {
+        u32::from_be_bytes(self.array())
+    }
+}
+
+/// Writes fields in order to a byte buffer
+///
+/// Writes past the end of the buffer panic, callers check the length of the
+/// buffer before writing, as `ToBytes::to_bytes` does.
+#[derive(Debug)]
+pub struct Writer<'a>
+{
+    data: &'a mut [u8],
+    offset: usize
+}
+
+impl<'a> Writer<'a>
+{
+    /// Create a writer starting at the beginning of the buffer
+    pub fn new(data: &'a mut [u8]) -> Self
+    {
+        Self
+        {
+            data,
+            offset: 0
+        }
+    }
+
+    /// Get the offset of the next field
+    pub fn offset(&self) -> usize
+    {
+        self.offset
+    }
+
+    /// Move to the given offset from the start of the buffer
+    pub fn seek(&mut self, offset: usize)
+    {
+        self.offset = offset;
+    }
+
+    /// Skip over padding or unused fields, leaving their contents as they are
+    pub fn skip(&mut self, count: usize)
+    {
+        self.offset += count;
+    }
+
+    /// Write a slice of bytes
+    pub fn bytes(&mut self, data: &[u8])
+    {
+        self.data[self.offset..self.offset + data.len()].copy_from_slice(data);
+
+        self.offset += data.len();
+    }
+
+    /// Write a byte
+    pub fn u8(&mut self, value: u8)
+    {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    /// Write a little endian u16
+    pub fn u16(&mut self, value: u16)
+    {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    /// Write a little endian u32
+    pub fn u32(&mut self, value: u32)
+    {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    /// Write a little endian u64
+    pub fn u64(&mut self, value: u64)
+    {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    /// Write a big endian u16, as used by network headers
+    pub fn u16_be(&mut self, value: u16)
+    {
+        self.bytes(&value.to_be_bytes());
+    }
+
+    /// Write a big endian u32, as used by network headers
+    pub fn u32_be(&mut self, value: u32)
+    {
+        self.bytes(&value.to_be_bytes());
+    }
+}
@@ -0,0 +1,11 @@
+//! Binary serialization helpers for on disk and on wire structures
+//!
+//! Structures are read and written field by field through a `Reader` or
+//! `Writer` instead of casting a buffer to the structure, which would depend
+//! on the alignment of the buffer and the byte order of the host.
+
+mod cursor;
+pub use cursor::*;
+
+mod traits;
+pub use traits::*;
@@ -0,0 +1,118 @@
+use super::{Reader, Writer};
+
+/// Error converting a structure to or from bytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CodecError
+{
+    // The buffer is shorter than the structure
+    ShortBuffer{ needed: usize, available: usize }
+}
+
+/// Check a buffer is large enough to hold a structure
+fn check_length(needed: usize, available: usize) -> Result<(), CodecError>
+{
+    if available < needed
+    {
+        Err(CodecError::ShortBuffer { needed, available })
+    }
+    else
+    {
+        Ok(())
+    }
+}
+
+/// Structure which can be parsed from a fixed size run of bytes
+pub trait FromBytes: Sized
+{
+    /// Number of bytes the structure takes up
+    const SIZE: usize;
+
+    /// Read the fields of the structure, the reader is guaranteed to hold at
+    /// least `SIZE` bytes
+    fn decode(reader: &mut Reader) -> Self;
+
+    /// Parse the structure from the start of the buffer, any bytes after the
+    /// structure are ignored
+    fn from_bytes(data: &[u8]) -> Result<Self, CodecError>
+    {
+        check_length(Self::SIZE, data.len())?;
+
+        Ok(Self::decode(&mut Reader::new(data)))
+    }
+}
+
+/// Structure which can be written as a fixed size run of bytes
+pub trait ToBytes
+{
+    /// Number of bytes the structure takes up
+    const SIZE: usize;
+
+    /// Write the fields of the structure, the writer is guaranteed to hold at
+    /// least `SIZE` bytes
+    fn encode(&self, writer: &mut Writer);
+
+    /// Write the structure to the start of the buffer, any bytes after the
+    /// structure are left as they are
+    fn to_bytes(&self, data: &mut [u8]) -> Result<(), CodecError>
+    {
+        check_length(Self::SIZE, data.len())?;
+
+        self.encode(&mut Writer::new(data));
+
+        Ok(())
+    }
+}
+
+/// Implement the codec traits for little endian integers
+macro_rules! integer_codec
+{
+    ($($t:ident),*) =>
+    {
+        $(
+            impl FromBytes for $t
+            {
+                const SIZE: usize = core::mem::size_of::<$t>();
+
+                fn decode(reader: &mut Reader) -> Self
+                {
+                    reader.$t()
+                }
+            }
+
+            impl ToBytes for $t
+            {
+                const SIZE: usize = core::mem::size_of::<$t>();
+
+                fn encode(&self, writer: &mut Writer)
+                {
+                    writer.$t(*self)
+                }
+            }
+        )*
+    };
+}
+
+integer_codec!(u8, u16, u32, u64);
+
+impl<T: FromBytes, const N: usize> FromBytes for [T; N]
+{
+    const SIZE: usize = T::SIZE * N;
+
+    fn decode(reader: &mut Reader) -> Self
+    {
+        core::array::from_fn(|_| T::decode(reader))
+    }
+}
+
+impl<T: ToBytes, const N: usize> ToBytes for [T; N]
+{
+    const SIZE: usize = T::SIZE * N;
+
+    fn encode(&self, writer: &mut Writer)
+    {
+        for item in self
+        {
+            item.encode(writer);
+        }
+    }
+}
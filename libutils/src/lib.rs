@@ -1,10 +1,19 @@
 //! Utilities Library for the Qor Kernel
-#![no_std]
+//!
+//! Everything here builds without the standard library for the kernel, and
+//! with the `std` feature for tools and tests which run on the host.
+#![cfg_attr(not(feature = "std"), no_std)]
 
+// The standard library re-exports everything in alloc, so the modules import
+// from alloc either way
 #[cfg(feature = "std")]
-extern crate std;
+extern crate std as alloc;
 
 #[cfg(not(feature = "std"))]
 extern crate alloc;
 
-pub mod paths;
\ No newline at end of file
+pub mod bitmap;
+pub mod codec;
+pub mod minix3;
+pub mod paths;
+pub mod ringbuffer;
@@ -0,0 +1,131 @@
+use crate::bitmap;
+use crate::codec::*;
+
+use super::*;
+
+// Number of bits in a block of the inode or zone map
+const BITS_PER_BLOCK: usize = BLOCK_SIZE * 8;
+
+// Largest file the superblock allows
+const MAX_FILE_SIZE: u32 = 0x7fff_ffff;
+
+// Mode of the root directory of a new filesystem, drwxr-xr-x
+const ROOT_MODE: u16 = 0x4000 | 0o755;
+
+/// Work out the layout of a new V3 filesystem on a device of the given number
+/// of blocks, with room for the given number of inodes, or one for every four
+/// blocks if zero is given. The inode table is rounded up to whole blocks.
+pub fn format_superblock(blocks: usize, inodes: usize) -> Result<Minix3SuperBlock, Minix3Error>
+{
+    let blocks = blocks.min(u32::MAX as usize);
+
+    let inodes = if inodes == 0 { blocks / 4 } else { inodes };
+    let inodes = inodes.max(1).div_ceil(INODES_PER_BLOCK) * INODES_PER_BLOCK;
+
+    // Inode zero is never used, but still has a bit in the map
+    let imap_blocks = (inodes + BITS_PER_BLOCK) / BITS_PER_BLOCK;
+    let zmap_blocks = blocks.div_ceil(BITS_PER_BLOCK);
+    let first_data_zone = 2 + imap_blocks + zmap_blocks + inodes / INODES_PER_BLOCK;
+
+    // The root directory takes up the first data zone
+    if first_data_zone >= blocks || first_data_zone > u16::MAX as usize || inodes > u32::MAX as usize
+    {
+        return Err(Minix3Error::TooSmall);
+    }
+
+    Ok(Minix3SuperBlock
+    {
+        ninodes:         inodes as u32,
+        pad0:            0,
+        imap_blocks:     imap_blocks as u16,
+        zmap_blocks:     zmap_blocks as u16,
+        first_data_zone: first_data_zone as u16,
+        log_zone_size:   0,
+        pad1:            0,
+        max_size:        MAX_FILE_SIZE,
+        zones:           blocks as u32,
+        magic:           MINIX3_MAGIC,
+        pad2:            0,
+        block_size:      BLOCK_SIZE as u16,
+        disk_version:    0,
+    })
+}
+
+/// Build a block of an allocation map, where the bits below `used` and at or
+/// above `end` are set
+fn map_block(index: usize, used: usize, end: usize) -> [u8; BLOCK_SIZE]
+{
+    let mut block = [0; BLOCK_SIZE];
+    let first = index * BITS_PER_BLOCK;
+
+    for bit in 0..BITS_PER_BLOCK
+    {
+        if first + bit < used || first + bit >= end
+        {
+            bitmap::set_bit(&mut block, bit);
+        }
+    }
+
+    block
+}
+
+/// Write a new filesystem laid out by `format_superblock`, holding an empty
+/// root directory, through `write_block`. Every block up to and including the
+/// root directory is written, the data zones after it are left as they are.
+/// Bits of the zone map are numbered by zone, as the kernel driver reads them,
+/// so the map marks everything before the first free zone as used.
+pub fn format<E>(superblock: &Minix3SuperBlock, time: u32, mut write_block: impl FnMut(usize, &[u8; BLOCK_SIZE]) -> Result<(), E>) -> Result<(), E>
+{
+    let inodes = superblock.ninodes as usize;
+    let imap_blocks = superblock.imap_blocks as usize;
+    let zmap_blocks = superblock.zmap_blocks as usize;
+    let root_zone = superblock.first_data_zone as usize;
+
+    // Boot block
+    write_block(0, &[0; BLOCK_SIZE])?;
+
+    let mut block = [0; BLOCK_SIZE];
+    superblock.encode(&mut Writer::new(&mut block));
+    write_block(SUPERBLOCK_OFFSET / BLOCK_SIZE, &block)?;
+
+    // Inode zero and the root inode are used, as are the bits past the end of
+    // the inode table
+    for i in 0..imap_blocks
+    {
+        write_block(2 + i, &map_block(i, 2, inodes + 1))?;
+    }
+
+    for i in 0..zmap_blocks
+    {
+        write_block(2 + imap_blocks + i, &map_block(i, root_zone + 1, superblock.zones as usize))?;
+    }
+
+    for block in 2 + imap_blocks + zmap_blocks..root_zone
+    {
+        write_block(block, &[0; BLOCK_SIZE])?;
+    }
+
+    let version = MinixVersion::V3;
+    let entry_size = version.dir_entry_size();
+
+    let mut root = Minix3Inode { mode: ROOT_MODE, nlinks: 2, size: 2 * entry_size as u32, atime: time, mtime: time, ctime: time, ..Default::default() };
+    root.zones[0] = root_zone as u32;
+
+    let (table, offset) = inode_position(superblock, 1);
+    let mut block = [0; BLOCK_SIZE];
+    root.encode(&mut Writer::new(&mut block[offset..]));
+    write_block(table, &block)?;
+
+    // The root directory is its own parent
+    let mut block = [0; BLOCK_SIZE];
+
+    for (i, name) in [".", ".."].iter().enumerate()
+    {
+        let mut entry = Minix3DirEntry { inode: 1, name: [0; 60] };
+        entry.name[..name.len()].copy_from_slice(name.as_bytes());
+
+        entry.encode(&mut Writer::new(&mut block[i * entry_size..]));
+    }
+
+    write_block(root_zone, &block)
+}
@@ -0,0 +1,123 @@
+use alloc::vec::Vec;
+
+use crate::codec::*;
+
+use super::*;
+
+/// Error reading a filesystem image
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Minix3Error
+{
+    // A structure did not fit where it was stored
+    Codec(CodecError),
+    // The superblock does not hold the magic number of a known version
+    BadMagic,
+    // A block past the end of the image was referenced
+    BlockOutOfRange(usize),
+    // An inode number of zero or past the end of the inode table
+    BadInode(usize),
+    NotADirectory,
+    NotFound,
+    // The device is too small to hold the layout asked for
+    TooSmall
+}
+
+impl From<CodecError> for Minix3Error
+{
+    fn from(error: CodecError) -> Self
+    {
+        Minix3Error::Codec(error)
+    }
+}
+
+/// Minix3 filesystem image held in memory
+pub struct Minix3Image<'a>
+{
+    data: &'a [u8],
+    superblock: Minix3SuperBlock,
+    version: MinixVersion
+}
+
+impl<'a> Minix3Image<'a>
+{
+    /// Open an image, checking its superblock
+    pub fn new(data: &'a [u8]) -> Result<Self, Minix3Error>
+    {
+        let superblock = Minix3SuperBlock::from_bytes(data.get(SUPERBLOCK_OFFSET..).unwrap_or(&[]))?;
+
+        let version = MinixVersion::from_magic(superblock.magic).ok_or(Minix3Error::BadMagic)?;
+
+        Ok(Self { data, superblock, version })
+    }
+
+    /// Get the superblock of the image
+    pub fn superblock(&self) -> &Minix3SuperBlock
+    {
+        &self.superblock
+    }
+
+    /// Get the version of the layout of the image
+    pub fn version(&self) -> MinixVersion
+    {
+        self.version
+    }
+
+    /// Read a block of the image
+    pub fn block(&self, index: usize) -> Result<[u8; BLOCK_SIZE], Minix3Error>
+    {
+        let start = index * BLOCK_SIZE;
+
+        let mut block = [0; BLOCK_SIZE];
+        block.copy_from_slice(self.data.get(start..start + BLOCK_SIZE).ok_or(Minix3Error::BlockOutOfRange(index))?);
+
+        Ok(block)
+    }
+
+    /// Read an inode, inodes are numbered from one
+    pub fn inode(&self, number: usize) -> Result<Minix3Inode, Minix3Error>
+    {
+        if number == 0 || number > self.superblock.ninodes as usize
+        {
+            return Err(Minix3Error::BadInode(number));
+        }
+
+        let (block, offset) = inode_position(&self.superblock, number);
+
+        Ok(Minix3Inode::from_bytes(&self.block(block)?[offset..])?)
+    }
+
+    /// Read the contents of a file
+    pub fn read(&self, inode: &Minix3Inode) -> Result<Vec<u8>, Minix3Error>
+    {
+        read_inode_data(inode, |zone| self.block(zone))
+    }
+
+    /// Read the entries of a directory
+    pub fn directory(&self, inode: &Minix3Inode) -> Result<Vec<Minix3DirEntry>, Minix3Error>
+    {
+        if inode.mode & 0x4000 == 0
+        {
+            return Err(Minix3Error::NotADirectory);
+        }
+
+        Ok(parse_directory(&self.read(inode)?, self.version)?)
+    }
+
+    /// Find the inode number of an absolute path
+    pub fn lookup(&self, path: &str) -> Result<usize, Minix3Error>
+    {
+        let mut current = 1;
+
+        for name in path.split('/').filter(|name| !name.is_empty())
+        {
+            let entries = self.directory(&self.inode(current)?)?;
+
+            current = entries.iter()
+                .find(|entry| entry.to_string() == name)
+                .map(|entry| entry.inode as usize)
+                .ok_or(Minix3Error::NotFound)?;
+        }
+
+        Ok(current)
+    }
+}
@@ -0,0 +1,179 @@
+//! Minix3 filesystem layout
+//!
+//! The on disk structures of the Minix3 filesystem and the parts of reading
+//! it which do not depend on where the blocks come from, shared by the kernel
+//! driver and by tools and tests which work on images held in memory.
+
+mod format;
+pub use format::*;
+
+mod image;
+pub use image::*;
+
+mod structures;
+pub use structures::*;
+
+use alloc::vec::Vec;
+
+use crate::codec::*;
+
+// Size of a block on disk
+pub const BLOCK_SIZE: usize = 1024;
+
+// Number of zone numbers in an indirect zone
+pub const ZONES_PER_BLOCK: usize = BLOCK_SIZE / 4;
+
+// Number of inodes stored in each block of the inode table
+pub const INODES_PER_BLOCK: usize = BLOCK_SIZE / 64;
+
+// Magic numbers of the superblock, V2 filesystems are made with either 14 or
+// 30 character names
+pub const MINIX3_MAGIC: u16 = 0x4d5a;
+pub const MINIX2_MAGIC: u16 = 0x2468;
+pub const MINIX2_MAGIC_LONG_NAMES: u16 = 0x2478;
+
+// Offset of the superblock from the start of the disk
+pub const SUPERBLOCK_OFFSET: usize = 1024;
+
+/// Version of the layout of a Minix filesystem, V2 and V3 share the layout of
+/// inodes but differ in their superblocks and directory entries
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MinixVersion
+{
+    V2,
+    V2LongNames,
+    V3
+}
+
+impl MinixVersion
+{
+    /// Get the version of a filesystem from the magic number of its superblock
+    pub fn from_magic(magic: u16) -> Option<Self>
+    {
+        match magic
+        {
+            MINIX2_MAGIC => Some(MinixVersion::V2),
+            MINIX2_MAGIC_LONG_NAMES => Some(MinixVersion::V2LongNames),
+            MINIX3_MAGIC => Some(MinixVersion::V3),
+            _ => None
+        }
+    }
+
+    /// Get the longest name a directory entry can hold
+    pub fn name_length(&self) -> usize
+    {
+        match self
+        {
+            MinixVersion::V2 => 14,
+            MinixVersion::V2LongNames => 30,
+            MinixVersion::V3 => 60
+        }
+    }
+
+    /// Get the size of a directory entry on disk, V2 entries hold 16 bit inode
+    /// numbers
+    pub fn dir_entry_size(&self) -> usize
+    {
+        match self
+        {
+            MinixVersion::V3 => <Minix3DirEntry as FromBytes>::SIZE,
+            _ => 2 + self.name_length()
+        }
+    }
+}
+
+/// Get the block holding an inode, and the offset of the inode within it,
+/// inodes are numbered from one
+pub fn inode_position(superblock: &Minix3SuperBlock, inode: usize) -> (usize, usize)
+{
+    let block = (inode - 1) / INODES_PER_BLOCK + 2 + superblock.imap_blocks as usize + superblock.zmap_blocks as usize;
+    let offset = ((inode - 1) % INODES_PER_BLOCK) * 64;
+
+    (block, offset)
+}
+
+/// Get the level of indirection of a zone slot in an inode, the first seven
+/// slots point at data and the last three at single, double and triple
+/// indirect zones
+pub fn zone_level(slot: usize) -> usize
+{
+    slot.max(6) - 6
+}
+
+/// Read the zone numbers held in an indirect zone
+pub fn read_zone_table(block: &[u8; BLOCK_SIZE]) -> [u32; ZONES_PER_BLOCK]
+{
+    <[u32; ZONES_PER_BLOCK]>::decode(&mut Reader::new(block))
+}
+
+/// Write the zone numbers of an indirect zone
+pub fn write_zone_table(block: &mut [u8; BLOCK_SIZE], zones: &[u32; ZONES_PER_BLOCK])
+{
+    zones.encode(&mut Writer::new(block));
+}
+
+/// Parse the entries of a directory from its contents
+pub fn parse_directory(data: &[u8], version: MinixVersion) -> Result<Vec<Minix3DirEntry>, CodecError>
+{
+    data.chunks_exact(version.dir_entry_size()).map(|chunk| Minix3DirEntry::read(chunk, version)).collect()
+}
+
+/// Read the zones of a file below a zone of the given level into `output`,
+/// until it holds `size` bytes
+fn read_zone<E>(zone: usize, level: usize, read_block: &mut impl FnMut(usize) -> Result<[u8; BLOCK_SIZE], E>, output: &mut Vec<u8>, size: usize) -> Result<(), E>
+{
+    if output.len() >= size
+    {
+        return Ok(());
+    }
+
+    let block = read_block(zone)?;
+
+    if level == 0
+    {
+        let count = (size - output.len()).min(BLOCK_SIZE);
+        output.extend_from_slice(&block[..count]);
+    }
+    else
+    {
+        for next in read_zone_table(&block)
+        {
+            // Empty slots are skipped
+            if next == 0
+            {
+                continue;
+            }
+
+            read_zone(next as usize, level - 1, read_block, output, size)?;
+
+            if output.len() >= size
+            {
+                break;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Read the contents of a file, fetching blocks through `read_block`
+pub fn read_inode_data<E>(inode: &Minix3Inode, mut read_block: impl FnMut(usize) -> Result<[u8; BLOCK_SIZE], E>) -> Result<Vec<u8>, E>
+{
+    let size = inode.size as usize;
+    let mut output = Vec::with_capacity(size);
+
+    for (slot, zone) in inode.zones.iter().enumerate()
+    {
+        if *zone == 0
+        {
+            continue;
+        }
+
+        read_zone(*zone as usize, zone_level(slot), &mut read_block, &mut output, size)?;
+    }
+
+    // A file with holes at the end reads as zeros
+    output.resize(size, 0);
+
+    Ok(output)
+}
@@ -0,0 +1,250 @@
+use alloc::string::String;
+
+use crate::codec::*;
+
+use super::{MinixVersion, BLOCK_SIZE, MINIX3_MAGIC};
+  
+/// Minix3 Superblock
+#[derive(Debug, Clone, Copy)]
+pub struct Minix3SuperBlock
+{
+  pub ninodes:         u32,
+  pub pad0:            u16,
+  pub imap_blocks:     u16,
+  pub zmap_blocks:     u16,
+  pub first_data_zone: u16,
+  pub log_zone_size:   u16,
+  pub pad1:            u16,
+  pub max_size:        u32,
+  pub zones:           u32,
+  pub magic:           u16,
+  pub pad2:            u16,
+  pub block_size:      u16,
+  pub disk_version:    u8,
+}
+
+impl FromBytes for Minix3SuperBlock
+{
+  const SIZE: usize = 32;
+
+  /// V2 superblocks are converted to the V3 layout, keeping their magic
+  /// number so the version can still be told apart
+  fn decode(reader: &mut Reader) -> Self
+  {
+    let mut peek = reader.clone();
+    peek.skip(16);
+    let v2_magic = peek.u16();
+    peek.skip(6);
+    let v3_magic = peek.u16();
+
+    if v3_magic != MINIX3_MAGIC && matches!(MinixVersion::from_magic(v2_magic), Some(MinixVersion::V2 | MinixVersion::V2LongNames))
+    {
+      return Self
+      {
+        ninodes:         reader.u16() as u32,
+        pad0:            reader.u16(),
+        imap_blocks:     reader.u16(),
+        zmap_blocks:     reader.u16(),
+        first_data_zone: reader.u16(),
+        log_zone_size:   reader.u16(),
+        pad1:            0,
+        max_size:        reader.u32(),
+        magic:           reader.u16(),
+        pad2:            reader.u16(),
+        zones:           reader.u32(),
+        block_size:      BLOCK_SIZE as u16,
+        disk_version:    0,
+      };
+    }
+
+    Self
+    {
+      ninodes:         reader.u32(),
+      pad0:            reader.u16(),
+      imap_blocks:     reader.u16(),
+      zmap_blocks:     reader.u16(),
+      first_data_zone: reader.u16(),
+      log_zone_size:   reader.u16(),
+      pad1:            reader.u16(),
+      max_size:        reader.u32(),
+      zones:           reader.u32(),
+      magic:           reader.u16(),
+      pad2:            reader.u16(),
+      block_size:      reader.u16(),
+      disk_version:    reader.u8(),
+    }
+  }
+}
+
+/// Superblocks are always written in the V3 layout
+impl ToBytes for Minix3SuperBlock
+{
+  const SIZE: usize = 32;
+
+  fn encode(&self, writer: &mut Writer)
+  {
+    writer.u32(self.ninodes);
+    writer.u16(self.pad0);
+    writer.u16(self.imap_blocks);
+    writer.u16(self.zmap_blocks);
+    writer.u16(self.first_data_zone);
+    writer.u16(self.log_zone_size);
+    writer.u16(self.pad1);
+    writer.u32(self.max_size);
+    writer.u32(self.zones);
+    writer.u16(self.magic);
+    writer.u16(self.pad2);
+    writer.u16(self.block_size);
+    writer.u8(self.disk_version);
+  }
+}
+
+/// Minix3 Inode
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct Minix3Inode
+{
+	pub mode:   u16,
+	pub nlinks: u16,
+	pub uid:    u16,
+	pub gid:    u16,
+	pub size:   u32,
+	pub atime:  u32,
+	pub mtime:  u32,
+	pub ctime:  u32,
+	pub zones:  [u32; 10]
+}
+
+impl FromBytes for Minix3Inode
+{
+  const SIZE: usize = 64;
+
+  fn decode(reader: &mut Reader) -> Self
+  {
+    Self
+    {
+      mode:   reader.u16(),
+      nlinks: reader.u16(),
+      uid:    reader.u16(),
+      gid:    reader.u16(),
+      size:   reader.u32(),
+      atime:  reader.u32(),
+      mtime:  reader.u32(),
+      ctime:  reader.u32(),
+      zones:  <[u32; 10]>::decode(reader)
+    }
+  }
+}
+
+impl ToBytes for Minix3Inode
+{
+  const SIZE: usize = 64;
+
+  fn encode(&self, writer: &mut Writer)
+  {
+    writer.u16(self.mode);
+    writer.u16(self.nlinks);
+    writer.u16(self.uid);
+    writer.u16(self.gid);
+    writer.u32(self.size);
+    writer.u32(self.atime);
+    writer.u32(self.mtime);
+    writer.u32(self.ctime);
+    self.zones.encode(writer);
+  }
+}
+
+/// Directory entry inode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Minix3DirEntry
+{
+  pub inode: u32,
+  pub name:  [u8; 60],
+}
+
+impl FromBytes for Minix3DirEntry
+{
+  const SIZE: usize = 64;
+
+  fn decode(reader: &mut Reader) -> Self
+  {
+    Self
+    {
+      inode: reader.u32(),
+      name:  reader.array()
+    }
+  }
+}
+
+impl ToBytes for Minix3DirEntry
+{
+  const SIZE: usize = 64;
+
+  fn encode(&self, writer: &mut Writer)
+  {
+    writer.u32(self.inode);
+    writer.bytes(&self.name);
+  }
+}
+
+impl Minix3DirEntry
+{
+  /// Read a directory entry laid out for the given version, names shorter
+  /// than those of V3 are padded out with zeros
+  pub fn read(data: &[u8], version: MinixVersion) -> Result<Self, CodecError>
+  {
+    if version == MinixVersion::V3
+    {
+      return Self::from_bytes(data);
+    }
+
+    if data.len() < version.dir_entry_size()
+    {
+      return Err(CodecError::ShortBuffer { needed: version.dir_entry_size(), available: data.len() });
+    }
+
+    let mut reader = Reader::new(data);
+
+    let inode = reader.u16() as u32;
+    let mut name = [0; 60];
+    name[..version.name_length()].copy_from_slice(reader.bytes(version.name_length()));
+
+    Ok(Self { inode, name })
+  }
+
+  /// Write a directory entry laid out for the given version, the name must
+  /// fit in the entry
+  pub fn write(&self, data: &mut [u8], version: MinixVersion) -> Result<(), CodecError>
+  {
+    if version == MinixVersion::V3
+    {
+      return self.to_bytes(data);
+    }
+
+    if data.len() < version.dir_entry_size()
+    {
+      return Err(CodecError::ShortBuffer { needed: version.dir_entry_size(), available: data.len() });
+    }
+
+    let mut writer = Writer::new(data);
+
+    writer.u16(self.inode as u16);
+    writer.bytes(&self.name[..version.name_length()]);
+
+    Ok(())
+  }
+
+  pub fn to_string(&self) -> String
+  {
+    let mut s = String::new();
+
+    for c in self.name
+    {
+        if c != 0
+        {
+            s.push(c as char);
+        }
+    }
+
+    s
+  }
+}
@@ -1,6 +1,6 @@
 use core::convert::Into;
 
-#[cfg(not(feature = "std"))]
+#[allow(unused_imports)]
 use alloc::{format, string::*};
 
 /// Owned Path Object
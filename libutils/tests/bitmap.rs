@@ -0,0 +1,25 @@
+extern crate libutils;
+
+use libutils::bitmap::*;
+
+#[test]
+pub fn test_bitmap()
+{
+    let mut map = [0u8; 4];
+
+    set_bit(&mut map, 0);
+    set_bit(&mut map, 9);
+    assert_eq!(map, [0x01, 0x02, 0, 0]);
+    assert!(test_bit(&map, 9));
+    assert!(!test_bit(&map, 8));
+
+    assert_eq!(first_clear_bit(&map, 0), Some(1));
+    assert_eq!(first_clear_bit(&map, 9), Some(10));
+
+    clear_bit(&mut map, 9);
+    assert!(!test_bit(&map, 9));
+
+    let full = [0xFFu8; 2];
+    assert_eq!(first_clear_bit(&full, 0), None);
+    assert_eq!(first_clear_bit(&map, 32), None);
+}
@@ -0,0 +1,89 @@
+extern crate libutils;
+
+use libutils::codec::*;
+
+/// Structure with fields of each size and padding between them
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Header
+{
+    kind: u8,
+    length: u16,
+    flags: u32,
+    offset: u64,
+    port: u16
+}
+
+impl FromBytes for Header
+{
+    const SIZE: usize = 18;
+
+    fn decode(reader: &mut Reader) -> Self
+    {
+        let kind = reader.u8();
+        reader.skip(1);
+
+        Self
+        {
+            kind,
+            length: reader.u16(),
+            flags: reader.u32(),
+            offset: reader.u64(),
+            port: reader.u16_be()
+        }
+    }
+}
+
+impl ToBytes for Header
+{
+    const SIZE: usize = 18;
+
+    fn encode(&self, writer: &mut Writer)
+    {
+        writer.u8(self.kind);
+        writer.skip(1);
+        writer.u16(self.length);
+        writer.u32(self.flags);
+        writer.u64(self.offset);
+        writer.u16_be(self.port);
+    }
+}
+
+/// Test reading fields in little and big endian order
+#[test]
+pub fn test_codec_decode()
+{
+    let data = [0x07, 0xFF, 0x34, 0x12, 0x78, 0x56, 0x34, 0x12, 1, 0, 0, 0, 0, 0, 0, 0x80, 0x1F, 0x90, 0xAA];
+
+    assert_eq!(Header::from_bytes(&data[..17]), Err(CodecError::ShortBuffer { needed: 18, available: 17 }));
+    assert_eq!(Header::from_bytes(&data), Ok(Header { kind: 7, length: 0x1234, flags: 0x12345678, offset: 0x8000000000000001, port: 0x1F90 }));
+
+    // Fields are read the same way from an unaligned buffer
+    let mut unaligned = [0u8; 20];
+    unaligned[1..].copy_from_slice(&data);
+
+    assert_eq!(Header::from_bytes(&unaligned[1..]), Header::from_bytes(&data));
+
+    assert_eq!(<[u16; 3]>::from_bytes(&[1, 0, 2, 0, 3, 0]), Ok([1, 2, 3]));
+}
+
+/// Test writing a structure and reading it back
+#[test]
+pub fn test_codec_round_trip()
+{
+    let header = Header { kind: 3, length: 512, flags: 0xDEADBEEF, offset: 42, port: 8080 };
+
+    let mut buffer = [0xEEu8; 20];
+    assert_eq!(header.to_bytes(&mut buffer[..17]), Err(CodecError::ShortBuffer { needed: 18, available: 17 }));
+    assert_eq!(header.to_bytes(&mut buffer), Ok(()));
+
+    // Padding and the bytes after the structure are left untouched
+    assert_eq!(buffer[1], 0xEE);
+    assert_eq!(&buffer[18..], &[0xEE, 0xEE]);
+
+    assert_eq!(Header::from_bytes(&buffer), Ok(header));
+
+    let mut table = [0u8; 8];
+    [0x01020304u32, 5].to_bytes(&mut table).unwrap();
+
+    assert_eq!(table, [4, 3, 2, 1, 5, 0, 0, 0]);
+}
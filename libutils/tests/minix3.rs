@@ -0,0 +1,203 @@
+extern crate libutils;
+
+use libutils::codec::*;
+use libutils::minix3::*;
+
+// Layout of the test image: boot block, superblock, one block each of inode
+// and zone map, one block of inodes, then data zones
+const INODE_TABLE: usize = 4;
+const ROOT_ZONE: usize = 5;
+const FILE_ZONE: usize = 6;
+const IMAGE_BLOCKS: usize = 8;
+
+/// Build a directory entry
+fn entry(inode: u32, name: &str) -> Minix3DirEntry
+{
+    let mut entry = Minix3DirEntry { inode, name: [0; 60] };
+    entry.name[..name.len()].copy_from_slice(name.as_bytes());
+
+    entry
+}
+
+/// Build an inode pointing at a single zone
+fn inode(mode: u16, size: u32, zone: usize) -> Minix3Inode
+{
+    let mut inode = Minix3Inode { mode, nlinks: 1, size, ..Default::default() };
+    inode.zones[0] = zone as u32;
+
+    inode
+}
+
+/// Build an image holding a root directory and the file `/hello`
+fn build_image() -> Vec<u8>
+{
+    let mut image = vec![0u8; IMAGE_BLOCKS * BLOCK_SIZE];
+
+    let superblock = &mut image[SUPERBLOCK_OFFSET..];
+    superblock[0..4].copy_from_slice(&16u32.to_le_bytes());
+    superblock[6..8].copy_from_slice(&1u16.to_le_bytes());
+    superblock[8..10].copy_from_slice(&1u16.to_le_bytes());
+    superblock[10..12].copy_from_slice(&(ROOT_ZONE as u16).to_le_bytes());
+    superblock[20..24].copy_from_slice(&(IMAGE_BLOCKS as u32).to_le_bytes());
+    superblock[24..26].copy_from_slice(&MINIX3_MAGIC.to_le_bytes());
+    superblock[28..30].copy_from_slice(&(BLOCK_SIZE as u16).to_le_bytes());
+
+    let entries = [entry(1, "."), entry(1, ".."), entry(2, "hello")];
+    let root = inode(0x4000, (entries.len() * 64) as u32, ROOT_ZONE);
+    let file = inode(0x8000, 12, FILE_ZONE);
+
+    let table = INODE_TABLE * BLOCK_SIZE;
+    root.to_bytes(&mut image[table..table + 64]).unwrap();
+    file.to_bytes(&mut image[table + 64..table + 128]).unwrap();
+
+    for (i, entry) in entries.iter().enumerate()
+    {
+        let start = ROOT_ZONE * BLOCK_SIZE + i * 64;
+        entry.to_bytes(&mut image[start..start + 64]).unwrap();
+    }
+
+    let start = FILE_ZONE * BLOCK_SIZE;
+    image[start..start + 12].copy_from_slice(b"Hello World\n");
+
+    image
+}
+
+#[test]
+pub fn test_inode_position()
+{
+    let image = build_image();
+    let image = Minix3Image::new(&image).unwrap();
+
+    assert_eq!(inode_position(image.superblock(), 1), (INODE_TABLE, 0));
+    assert_eq!(inode_position(image.superblock(), 2), (INODE_TABLE, 64));
+    assert_eq!(inode_position(image.superblock(), 17), (INODE_TABLE + 1, 0));
+}
+
+#[test]
+pub fn test_lookup_and_read()
+{
+    let image = build_image();
+    let image = Minix3Image::new(&image).unwrap();
+
+    assert_eq!(image.lookup("/"), Ok(1));
+    assert_eq!(image.lookup("/hello"), Ok(2));
+    assert_eq!(image.lookup("/missing"), Err(Minix3Error::NotFound));
+    assert_eq!(image.lookup("/hello/world"), Err(Minix3Error::NotADirectory));
+
+    let file = image.inode(2).unwrap();
+    assert_eq!(image.read(&file).unwrap(), b"Hello World\n");
+}
+
+#[test]
+pub fn test_directory()
+{
+    let image = build_image();
+    let image = Minix3Image::new(&image).unwrap();
+
+    let root = image.inode(1).unwrap();
+    let names: Vec<String> = image.directory(&root).unwrap().iter().map(|entry| entry.to_string()).collect();
+
+    assert_eq!(names, vec![".", "..", "hello"]);
+}
+
+#[test]
+pub fn test_bad_images()
+{
+    let mut image = build_image();
+
+    assert_eq!(Minix3Image::new(&image[..512]).err(), Some(Minix3Error::Codec(CodecError::ShortBuffer { needed: 32, available: 0 })));
+
+    // Point the file at a zone past the end of the image
+    let file = inode(0x8000, 12, IMAGE_BLOCKS + 1);
+    let table = INODE_TABLE * BLOCK_SIZE;
+    file.to_bytes(&mut image[table + 64..table + 128]).unwrap();
+
+    {
+        let image = Minix3Image::new(&image).unwrap();
+        let file = image.inode(2).unwrap();
+
+        assert_eq!(image.read(&file), Err(Minix3Error::BlockOutOfRange(IMAGE_BLOCKS + 1)));
+        assert_eq!(image.inode(0), Err(Minix3Error::BadInode(0)));
+        assert_eq!(image.inode(17), Err(Minix3Error::BadInode(17)));
+    }
+
+    image[SUPERBLOCK_OFFSET + 24] = 0;
+    assert_eq!(Minix3Image::new(&image).err(), Some(Minix3Error::BadMagic));
+}
+
+#[test]
+pub fn test_v2_image()
+{
+    let mut image = vec![0u8; IMAGE_BLOCKS * BLOCK_SIZE];
+
+    let superblock = &mut image[SUPERBLOCK_OFFSET..];
+    superblock[0..2].copy_from_slice(&16u16.to_le_bytes());
+    superblock[4..6].copy_from_slice(&1u16.to_le_bytes());
+    superblock[6..8].copy_from_slice(&1u16.to_le_bytes());
+    superblock[8..10].copy_from_slice(&(ROOT_ZONE as u16).to_le_bytes());
+    superblock[16..18].copy_from_slice(&MINIX2_MAGIC_LONG_NAMES.to_le_bytes());
+    superblock[20..24].copy_from_slice(&(IMAGE_BLOCKS as u32).to_le_bytes());
+
+    let long_name = "a_name_of_thirty_characters_xy";
+    let entries = [entry(1, "."), entry(1, ".."), entry(2, long_name)];
+    let root = inode(0x4000, (entries.len() * 32) as u32, ROOT_ZONE);
+
+    let table = INODE_TABLE * BLOCK_SIZE;
+    root.to_bytes(&mut image[table..table + 64]).unwrap();
+
+    for (i, entry) in entries.iter().enumerate()
+    {
+        let start = ROOT_ZONE * BLOCK_SIZE + i * 32;
+        entry.write(&mut image[start..start + 32], MinixVersion::V2LongNames).unwrap();
+    }
+
+    let image = Minix3Image::new(&image).unwrap();
+
+    assert_eq!(image.version(), MinixVersion::V2LongNames);
+    assert_eq!(image.superblock().ninodes, 16);
+    assert_eq!(image.superblock().zones, IMAGE_BLOCKS as u32);
+    assert_eq!(inode_position(image.superblock(), 2), (INODE_TABLE, 64));
+    assert_eq!(image.lookup(&("/".to_string() + long_name)), Ok(2));
+}
+
+#[test]
+pub fn test_format()
+{
+    let blocks = 4096;
+    let mut image = vec![0xffu8; blocks * BLOCK_SIZE];
+
+    assert_eq!(format_superblock(3, 0).err(), Some(Minix3Error::TooSmall));
+
+    let superblock = format_superblock(blocks, 100).unwrap();
+    assert_eq!(superblock.ninodes, 112);
+    assert_eq!(superblock.first_data_zone, 2 + 1 + 1 + 7);
+
+    format(&superblock, 1234, |index, block| -> Result<(), ()>
+    {
+        image[index * BLOCK_SIZE..(index + 1) * BLOCK_SIZE].copy_from_slice(block);
+        Ok(())
+    }).unwrap();
+
+    let image = Minix3Image::new(&image).unwrap();
+    assert_eq!(image.version(), MinixVersion::V3);
+    assert_eq!(image.superblock().zones, blocks as u32);
+
+    let root = image.inode(1).unwrap();
+    assert_eq!((root.nlinks, root.mtime), (2, 1234));
+
+    let entries: Vec<(u32, String)> = image.directory(&root).unwrap().iter().map(|entry| (entry.inode, entry.to_string())).collect();
+    assert_eq!(entries, vec![(1, String::from(".")), (1, String::from(".."))]);
+
+    // The rest of the inode table is cleared
+    assert_eq!(image.inode(2).unwrap(), Minix3Inode::default());
+
+    // Only inode zero and the root are taken, and the zones up to the root
+    // directory
+    let imap = image.block(2).unwrap();
+    assert_eq!(libutils::bitmap::first_clear_bit(&imap, 0), Some(2));
+    assert!(libutils::bitmap::test_bit(&imap, 113));
+
+    let zmap = image.block(3).unwrap();
+    assert_eq!(libutils::bitmap::first_clear_bit(&zmap, 0), Some(superblock.first_data_zone as usize + 1));
+    assert!(libutils::bitmap::test_bit(&zmap, blocks));
+}
@@ -1,84 +1,11 @@
-// Byte Buffer Size
-const BUFFER_SIZE: usize = 1024;
-
-/// Generic Byte Based Ring Buffer
-pub struct ByteRingBuffer
-{
-    data: [u8; BUFFER_SIZE],
-    start: usize,
-    end: usize
-}
-
-impl ByteRingBuffer
-{
-    /// Create a new, empty byte ring buffer
-    pub const fn new() -> Self
-    {
-        Self
-        {
-            data: [0u8; BUFFER_SIZE],
-            start: 0,
-            end: 0
-        }
-    }
-
-    /// Add a byte to the buffer
-    pub fn enqueue_byte(&mut self, byte: u8) -> bool
-    {
-        if (self.end + 1) % BUFFER_SIZE == self.start
-        {
-            return false;
-        }
-
-        self.data[self.end] = byte;
-        self.end = (self.end + 1) % BUFFER_SIZE;
-
-        true
-    }
-
-    /// Remove a byte from the buffer
-    pub fn dequeue_byte(&mut self) -> Option<u8>
-    {
-        if self.start == self.end
-        {
-            None
-        }
-        else
-        {
-            let data = Some(self.data[self.start]);
-
-            self.start = (self.start + 1) % BUFFER_SIZE;
-
-            data
-        }
-    }
-
-    /// Remove the data at the front of the buffer
-    pub fn pop_byte(&mut self) -> Option<u8>
-    {
-        if self.start == self.end
-        {
-            None
-        }
-        else
-        {
-            self.end = (self.end + BUFFER_SIZE - 1) % BUFFER_SIZE;
-
-            Some(self.data[self.end])
-        }
-    }
-
-    /// Return true if the buffer is empty
-    pub fn is_empty(&self) -> bool
-    {
-        self.start == self.end
-    }
-}
+extern crate libutils;
 
 /// Ring Buffer Test
-#[test_case]
-fn ring_buffer()
+#[test]
+pub fn test_ring_buffer()
 {
+    use libutils::ringbuffer::*;
+
     let mut buffer = ByteRingBuffer::new();
 
     // Ensure a fresh buffer contains no data
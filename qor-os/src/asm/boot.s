@@ -15,6 +15,9 @@ _start:
     # Make sure we are in machine mode
     csrw satp, zero
 
+    # Keep the address of the device tree, which is handed over in a1
+    mv s1, a1
+
     # Make sure only hart 0 will boot
     csrr t0, mhartid
     # If we are not on hart 0, we will jump to an infinite waiting loop
@@ -40,6 +43,9 @@ _start_zero_bss_loop:
 _start_init:
     # Initialize the stack pointer
     la sp, _stack_end
+
+    # Point tp at the per-CPU data of hart 0
+    la tp, PER_CPU_AREAS
     
     # Set up the machine status register
     li t0, 0b11 << 11 | (1 << 7) | (1 << 3)
@@ -59,6 +65,9 @@ _start_init:
     # Set up the return address for when kinit returns
     la ra, _start_kinit_return
 
+    # Pass the device tree to kinit
+    mv a0, s1
+
     # Call kinit
     mret
 
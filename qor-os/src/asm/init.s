@@ -0,0 +1,13 @@
+# Code run by the init process, which is copied into a page of its own so no
+# kernel memory needs to be mapped into its address space
+
+.option norvc
+
+.section .rodata
+.global INIT_CODE_START
+INIT_CODE_START:
+    # Wait forever, this process only exists as the parent of /bin/init
+1:
+    j 1b
+.global INIT_CODE_END
+INIT_CODE_END:
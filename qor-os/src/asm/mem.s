@@ -40,3 +40,9 @@ KERNEL_STACK_START: .dword _stack_start
 
 .global KERNEL_STACK_END
 KERNEL_STACK_END: .dword _stack_end
+
+.global TRAMPOLINE_START
+TRAMPOLINE_START: .dword _trampoline_start
+
+.global TRAMPOLINE_END
+TRAMPOLINE_END: .dword _trampoline_end
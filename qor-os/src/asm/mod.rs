@@ -5,3 +5,5 @@ use core::arch::global_asm;
 global_asm!(include_str!("boot.s"));
 global_asm!(include_str!("trap.s"));
 global_asm!(include_str!("mem.s"));
+global_asm!(include_str!("init.s"));
+global_asm!(include_str!("suspend.s"));
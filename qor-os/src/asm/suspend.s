@@ -0,0 +1,158 @@
+# Non-retentive suspend of the boot hart. The kernel runs in machine mode with
+# no firmware below it, so this performs the system suspend call itself: the
+# state of the hart is saved to the context in a0, the hart waits for a device
+# interrupt, and execution continues at the resume address with only the
+# context in a0, the same as a resume from the SBI. Nothing else is kept in
+# registers across the suspend. The layout of the context must be kept in
+# sync with SuspendContext in suspend.rs.
+
+.option norvc
+
+.section .text
+.global asm_system_suspend
+asm_system_suspend:
+    # Callee saved registers, along with everything the kernel expects to be
+    # left alone
+    sd ra, 0(a0)
+    sd sp, 8(a0)
+    sd gp, 16(a0)
+    sd tp, 24(a0)
+    sd s0, 32(a0)
+    sd s1, 40(a0)
+    sd s2, 48(a0)
+    sd s3, 56(a0)
+    sd s4, 64(a0)
+    sd s5, 72(a0)
+    sd s6, 80(a0)
+    sd s7, 88(a0)
+    sd s8, 96(a0)
+    sd s9, 104(a0)
+    sd s10, 112(a0)
+    sd s11, 120(a0)
+
+    # The floating point state is saved once the FPU is known to be on
+    csrr t0, mstatus
+    sd t0, 232(a0)
+    li t1, (3 << 13)
+    csrs mstatus, t1
+
+    fsd fs0, 128(a0)
+    fsd fs1, 136(a0)
+    fsd fs2, 144(a0)
+    fsd fs3, 152(a0)
+    fsd fs4, 160(a0)
+    fsd fs5, 168(a0)
+    fsd fs6, 176(a0)
+    fsd fs7, 184(a0)
+    fsd fs8, 192(a0)
+    fsd fs9, 200(a0)
+    fsd fs10, 208(a0)
+    fsd fs11, 216(a0)
+    frcsr t0
+    sd t0, 224(a0)
+
+    # Machine mode state, the trap frames stay in memory and mscratch says
+    # which is in use
+    csrr t0, mie
+    sd t0, 240(a0)
+    csrr t0, mtvec
+    sd t0, 248(a0)
+    csrr t0, mscratch
+    sd t0, 256(a0)
+    csrr t0, satp
+    sd t0, 264(a0)
+    csrr t0, mepc
+    sd t0, 272(a0)
+    csrr t0, pmpcfg0
+    sd t0, 280(a0)
+    csrr t0, pmpaddr0
+    sd t0, 288(a0)
+
+    # Only device interrupts wake the hart, they stay globally disabled so
+    # the wake up ends the wfi without being taken as a trap
+    li t0, 1 << 3
+    csrc mstatus, t0
+    li t0, 1 << 11
+    csrw mie, t0
+
+    mv s0, a0
+    la s1, asm_system_resume
+
+asm_system_suspend_wait:
+    wfi
+    csrr t0, mip
+    li t1, 1 << 11
+    and t0, t0, t1
+    beqz t0, asm_system_suspend_wait
+
+    # Enter the resume address as the hart would after losing its state,
+    # with nothing but the context
+    mv a0, s0
+    mv t0, s1
+    mv ra, zero
+    mv sp, zero
+    mv gp, zero
+    mv tp, zero
+    mv s0, zero
+    mv s1, zero
+    jr t0
+
+# Resume address of the suspend, the state of the hart is rebuilt from the
+# context in a0 and asm_system_suspend returns to its caller
+asm_system_resume:
+    ld t0, 288(a0)
+    csrw pmpaddr0, t0
+    ld t0, 280(a0)
+    csrw pmpcfg0, t0
+    ld t0, 264(a0)
+    csrw satp, t0
+    sfence.vma
+    ld t0, 248(a0)
+    csrw mtvec, t0
+    ld t0, 256(a0)
+    csrw mscratch, t0
+    ld t0, 272(a0)
+    csrw mepc, t0
+    ld t0, 240(a0)
+    csrw mie, t0
+
+    li t1, (3 << 13)
+    csrs mstatus, t1
+
+    fld fs0, 128(a0)
+    fld fs1, 136(a0)
+    fld fs2, 144(a0)
+    fld fs3, 152(a0)
+    fld fs4, 160(a0)
+    fld fs5, 168(a0)
+    fld fs6, 176(a0)
+    fld fs7, 184(a0)
+    fld fs8, 192(a0)
+    fld fs9, 200(a0)
+    fld fs10, 208(a0)
+    fld fs11, 216(a0)
+    ld t0, 224(a0)
+    fscsr t0
+
+    # mstatus goes last, as it may turn the FPU back off
+    ld t0, 232(a0)
+    csrw mstatus, t0
+
+    ld ra, 0(a0)
+    ld sp, 8(a0)
+    ld gp, 16(a0)
+    ld tp, 24(a0)
+    ld s0, 32(a0)
+    ld s1, 40(a0)
+    ld s2, 48(a0)
+    ld s3, 56(a0)
+    ld s4, 64(a0)
+    ld s5, 72(a0)
+    ld s6, 80(a0)
+    ld s7, 88(a0)
+    ld s8, 96(a0)
+    ld s9, 104(a0)
+    ld s10, 112(a0)
+    ld s11, 120(a0)
+
+    ret
@@ -1,4 +1,14 @@
-.section .text
+# The trap entry and exit code is placed in the trampoline page, which is
+# mapped at the same address in the kernel and every process address space.
+# Traps are taken in machine mode with translation off, so mtvec still holds
+# the physical address of the vector.
+#
+# On entry every general purpose register is saved to the frame in mscratch
+# and then cleared, the page table is switched to the kernel's, and the
+# kernel's own global pointer, thread pointer and stack are loaded. On exit
+# the page table of the interrupted code is switched back in and every
+# register is restored from the frame, so no values cross between the two.
+.section .trampoline, "ax"
 
 .option norvc
 
@@ -7,6 +17,7 @@
 .set NUM_FP_REGS, 32
 .set REG_SIZE, 8
 .set MAX_CPUS, 8
+.set PER_CPU_SHIFT, 8
 
 .macro save_gp i, basereg=t6
     sd x\i, ((\i)*REG_SIZE)(\basereg)
@@ -24,6 +35,10 @@
     fld f\i, ((NUM_GP_REGS + (\i))*REG_SIZE)(\basereg)
 .endm
 
+.macro clear_gp i
+    mv x\i, zero
+.endm
+
 .global asm_trap_vector
 asm_trap_vector:
     # The CPU was interrupted
@@ -41,7 +56,36 @@ asm_trap_vector:
     csrr t6, mscratch
     save_gp 31, t5
 
+    # Switch to the kernel's page table, keeping the interrupted one so it
+    # can be restored on the way out
+    csrr t0, satp
+    sd t0, 512(t5)
+    ld t0, 568(t5)
+    csrw satp, t0
+    sfence.vma
+
+    # Clear every register other than the frame pointer, their values have
+    # been saved and must not reach the kernel
+    .set i, 1
+    .rept 29
+        clear_gp %i
+        .set i, i + 1
+    .endr
+    clear_gp 31
+
+    # Load the kernel's global pointer
+    .option push
+    .option norelax
+    la gp, _global_pointer
+    .option pop
+
+    # Save mstatus so it can be restored on the way out, a nested trap will
+    # overwrite the previous privilege and interrupt enable bits
     csrr t1, mstatus
+    sd t1, 552(t5)
+
+    # Only save the floating point state if it was written since the last
+    # time it was restored
     srli t0, t1, 13
     andi t0, t0, 3
     li t3, 3
@@ -51,26 +95,60 @@ asm_trap_vector:
         save_fp %i, t5
         .set i, i+1
     .endr
+    frcsr t0
+    sd t0, 536(t5)
 
 skip_float_save:
 
-    csrw mscratch, t5
+    # Enable the FPU while in the kernel, the process state has been saved
+    li t0, (3 << 13)
+    csrs mstatus, t0
+
+    # Any trap taken while this one is being handled is saved to the nested
+    # frame, which has its own stack
+    ld t0, 560(t5)
+    csrw mscratch, t0
 
     # Set up the arguments for the m_trap function
     csrr a0, mepc
     csrr a1, mtval
     csrr a2, mcause
     csrr a3, mhartid
-    csrr a4, mstatus
+    mv a4, t1
     mv a5, t5
     ld sp, 520(a5)
 
+    # Keep the frame in a callee saved register, its value has already been
+    # stored in the frame
+    mv s1, t5
+
+    # Point tp at the per-CPU data of this hart, the value from the
+    # interrupted code has already been stored in the frame
+    la tp, PER_CPU_AREAS
+    slli t0, a3, PER_CPU_SHIFT
+    add tp, tp, t0
+
     # Call the m_trap function
     call m_trap
 
     # Restore Registers
     csrw mepc, a0
-    csrr t6, mscratch
+    mv t6, s1
+    csrw mscratch, t6
+
+    ld t0, 552(t6)
+    csrw mstatus, t0
+
+    j trap_return
+
+# Return to the code whose state is in the frame in t6, switching back to its
+# page table and restoring every register
+trap_return:
+    ld t0, 512(t6)
+    csrw satp, t0
+    sfence.vma
+
+    call restore_float_state
 
     .set i, 1
     .rept 31
@@ -81,13 +159,42 @@ skip_float_save:
     # Jump back to where the interrupt was triggered
     mret
 
+# Restore the floating point state from the frame in t6 if the process has
+# enabled the FPU, otherwise turn the FPU off so the first floating point
+# instruction traps and the state can be set up lazily
+restore_float_state:
+    li t1, (3 << 13)
+    csrs mstatus, t1
+
+    ld t0, 544(t6)
+    beqz t0, disable_float
+
+    .set i, 0
+    .rept 32
+        load_fp %i
+        .set i, i + 1
+    .endr
+    ld t0, 536(t6)
+    fscsr t0
+
+    # Mark the state as clean so it is only saved again if it is written
+    li t1, (1 << 13)
+    csrc mstatus, t1
+    ret
+
+disable_float:
+    csrc mstatus, t1
+    ret
+
 .global switch_to_user
 switch_to_user:
     csrw mscratch, a0
-    li t0, (1 << 7) | (1 << 5) | (1 << 13)
+    li t0, (1 << 7) | (1 << 5)
     csrw mstatus, t0
     csrw mepc, a1
-    csrw satp, a2
+
+    # The page table is switched to on the way out
+    sd a2, 512(a0)
 
     li t1, 0xaaa
     csrw mie, t1
@@ -95,26 +202,14 @@ switch_to_user:
     la t2, asm_trap_vector
     csrw mtvec, t2
 
-    sfence.vma
     mv t6, a0
-
-    .set i, 0
-    .rept 32
-        load_fp %i
-        .set i, i + 1
-    .endr
-
-    .set i, 1
-    .rept 31
-        load_gp %i, t6
-        .set i, i + 1
-    .endr
-
-    mret
+    j trap_return
 
 .globl asm_wait_for_int
 asm_wait_for_int:
+    # Wait with the kernel's page table, which has no user mappings
     csrw satp, a0
+    sfence.vma
 
     # Set up supervisor mode
     li t0, (1 << 11) | (1 << 5)
@@ -0,0 +1,141 @@
+//! Kernel Command Line
+//!
+//! The boot parameters are read from the `bootargs` property of the `/chosen`
+//! node of the device tree, which QEMU fills in from `-append`. The device tree
+//! is handed over in a1 at reset and may be overwritten once memory is handed
+//! out, so the parameters are copied out before anything is allocated.
+
+// Longest command line kept, anything after this is dropped
+const COMMAND_LINE_MAX: usize = 256;
+
+// Flattened device tree header
+const FDT_MAGIC: u32 = 0xd00d_feed;
+const FDT_HEADER_STRUCT_OFFSET: usize = 8;
+const FDT_HEADER_STRINGS_OFFSET: usize = 12;
+const FDT_HEADER_STRUCT_SIZE: usize = 36;
+
+// Flattened device tree structure tokens
+const FDT_BEGIN_NODE: u32 = 1;
+const FDT_END_NODE: u32 = 2;
+const FDT_PROP: u32 = 3;
+const FDT_NOP: u32 = 4;
+const FDT_END: u32 = 9;
+
+static mut COMMAND_LINE: [u8; COMMAND_LINE_MAX] = [0; COMMAND_LINE_MAX];
+static mut COMMAND_LINE_LENGTH: usize = 0;
+
+/// Read a big endian word from the device tree
+/// Safety: the address must be within the device tree
+unsafe fn read_word(addr: usize) -> u32
+{
+    u32::from_be((addr as *const u32).read_volatile())
+}
+
+/// Get the null terminated string at the given address
+/// Safety: the address must be within the device tree
+unsafe fn read_string(addr: usize) -> &'static [u8]
+{
+    let mut length = 0;
+
+    while ((addr + length) as *const u8).read_volatile() != 0
+    {
+        length += 1;
+    }
+
+    core::slice::from_raw_parts(addr as *const u8, length)
+}
+
+/// Find the value of the `bootargs` property in the `/chosen` node of the
+/// device tree, giving `None` if the device tree is not valid
+/// Safety: the address must be zero or point to a device tree
+unsafe fn find_bootargs(device_tree: usize) -> Option<&'static [u8]>
+{
+    if device_tree == 0 || read_word(device_tree) != FDT_MAGIC
+    {
+        return None;
+    }
+
+    let structure = device_tree + read_word(device_tree + FDT_HEADER_STRUCT_OFFSET) as usize;
+    let strings = device_tree + read_word(device_tree + FDT_HEADER_STRINGS_OFFSET) as usize;
+    let end = structure + read_word(device_tree + FDT_HEADER_STRUCT_SIZE) as usize;
+
+    let mut addr = structure;
+    let mut depth = 0;
+    let mut in_chosen = false;
+
+    while addr < end
+    {
+        let token = read_word(addr);
+        addr += 4;
+
+        match token
+        {
+            FDT_BEGIN_NODE =>
+            {
+                let name = read_string(addr);
+                addr = (addr + name.len() + 1 + 3) & !3;
+
+                depth += 1;
+                in_chosen = depth == 2 && name == b"chosen";
+            },
+            FDT_END_NODE =>
+            {
+                depth -= 1;
+                in_chosen = false;
+            },
+            FDT_PROP =>
+            {
+                let length = read_word(addr) as usize;
+                let name = read_string(strings + read_word(addr + 4) as usize);
+                let value = addr + 8;
+                addr = (value + length + 3) & !3;
+
+                if in_chosen && name == b"bootargs"
+                {
+                    // The value includes the null terminator
+                    return Some(read_string(value));
+                }
+            },
+            FDT_NOP => {},
+            FDT_END => break,
+            _ => return None
+        }
+    }
+
+    None
+}
+
+/// Copy the command line out of the device tree given at boot
+pub fn init(device_tree: usize)
+{
+    if let Some(args) = unsafe { find_bootargs(device_tree) }
+    {
+        let length = args.len().min(COMMAND_LINE_MAX);
+
+        unsafe
+        {
+            COMMAND_LINE[..length].copy_from_slice(&args[..length]);
+            COMMAND_LINE_LENGTH = length;
+        }
+    }
+}
+
+/// Get the kernel command line
+pub fn command_line() -> &'static str
+{
+    let bytes = unsafe { &COMMAND_LINE[..COMMAND_LINE_LENGTH] };
+
+    core::str::from_utf8(bytes).unwrap_or("")
+}
+
+/// Check if a flag was given on the command line
+pub fn has_flag(flag: &str) -> bool
+{
+    command_line().split_whitespace().any(|arg| arg == flag)
+}
+
+/// Get the value of a `name=value` parameter given on the command line
+pub fn value(name: &str) -> Option<&'static str>
+{
+    command_line().split_whitespace().find_map(|arg| arg.strip_prefix(name)?.strip_prefix('='))
+}
@@ -1,5 +1,7 @@
+use crate::*;
+
 /// Categories for debug prints
-#[derive(PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum DebugCategories
 {
     BlockDevice,
@@ -19,6 +21,70 @@ pub enum DebugCategories
     Other,
 }
 
+impl DebugCategories
+{
+    /// Get the subsystem tag printed in front of messages in this category
+    pub const fn tag(&self) -> &'static str
+    {
+        match self
+        {
+            DebugCategories::BlockDevice => "block",
+            DebugCategories::ByteMemoryAllocation => "kmalloc",
+            DebugCategories::Elf => "elf",
+            DebugCategories::Filesystem => "fs",
+            DebugCategories::Initialization => "init",
+            DebugCategories::Interrupts => "irq",
+            DebugCategories::KernelPageTable => "kpt",
+            DebugCategories::MemoryAllocation => "mem",
+            DebugCategories::MemoryMapping => "mmap",
+            DebugCategories::Processes => "proc",
+            DebugCategories::Scheduling => "sched",
+            DebugCategories::Signals => "signal",
+            DebugCategories::Syscalls => "syscall",
+            DebugCategories::VirtIO => "virtio",
+            DebugCategories::Other => "kernel"
+        }
+    }
+}
+
+/// How much is logged, set by the `quiet` and `verbose` boot parameters
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verbosity
+{
+    Quiet,
+    Normal,
+    Verbose
+}
+
+static mut VERBOSITY: Verbosity = Verbosity::Normal;
+
+/// Set the verbosity from the boot parameters, `quiet` leaves only warnings
+/// and errors, while `verbose` prints every debug category no matter the
+/// flags below
+pub fn init_verbosity()
+{
+    let verbosity = if bootargs::has_flag("quiet")
+    {
+        Verbosity::Quiet
+    }
+    else if bootargs::has_flag("verbose")
+    {
+        Verbosity::Verbose
+    }
+    else
+    {
+        Verbosity::Normal
+    };
+
+    unsafe { VERBOSITY = verbosity };
+}
+
+/// Get the verbosity set at boot
+pub fn verbosity() -> Verbosity
+{
+    unsafe { VERBOSITY }
+}
+
 // Flags for debug prints
 pub const ALL: bool = true;
 
@@ -120,4 +186,16 @@ pub const fn check_debug(cat: DebugCategories) -> bool
             DebugCategories::Other => true // This defaults to true to allow unspecified prints to pass
         }
     }
-}
\ No newline at end of file
+}
+
+/// Check if a debug print should occur, taking the boot verbosity into
+/// account
+pub fn should_log(cat: DebugCategories) -> bool
+{
+    match verbosity()
+    {
+        Verbosity::Quiet => false,
+        Verbosity::Normal => check_debug(cat),
+        Verbosity::Verbose => ALL
+    }
+}
@@ -0,0 +1,34 @@
+//! Block device ioctls
+
+use crate::*;
+
+use fs::ioctl::IOControlCommand;
+use process::process::Process;
+
+// Type of the commands handled here
+pub const BLOCK_IOCTL_TYPE: u8 = 0x12;
+
+// Qor specific command to write a fresh Minix3 filesystem to the device, the
+// argument is the number of inodes to make room for, or zero for the default
+const BLK_FORMAT_MINIX3: usize = 0x12FF;
+
+/// Handle the block device ioctls
+pub fn block_ioctl(proc: &mut Process, fd: usize, cmd: usize, args: usize) -> Result<Option<usize>, usize>
+{
+    let command = match cmd
+    {
+        BLK_FORMAT_MINIX3 =>
+        {
+            // Formatting wipes whatever was on the device
+            if !proc.is_privileged()
+            {
+                return Err(errno::EPERM);
+            }
+
+            IOControlCommand::BlockFormatMinix3{ inodes: args }
+        },
+        _ => return Ok(None)
+    };
+
+    Ok(Some(proc.exec_ioctl(fd, command)))
+}
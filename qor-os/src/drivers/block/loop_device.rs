@@ -0,0 +1,102 @@
+//! Loop Devices
+//!
+//! Block devices backed by a regular file on another filesystem, so an image
+//! of a filesystem can be mounted from a file as `mount -o loop` does on
+//! Linux. The contents of the file are read in when the device is attached and
+//! written back to the file when the device is flushed or detached.
+
+use crate::*;
+
+use fs::fstrait::Filesystem;
+use fs::structures::{FilesystemIndex, FilesystemResult};
+
+use super::*;
+
+// Size of the blocks loop devices are accessed in
+pub const LOOP_DEVICE_BLOCK_SIZE: usize = 512;
+
+/// Block device backed by a regular file
+pub struct LoopDevice
+{
+    inode: FilesystemIndex,
+    data: Vec<u8>,
+    dirty: bool
+}
+
+impl LoopDevice
+{
+    /// Attach a loop device to the regular file with the given inode, the
+    /// device covers the whole blocks of the file
+    pub fn new(vfs: &mut fs::vfs::FilesystemInterface, inode: FilesystemIndex) -> FilesystemResult<Self>
+    {
+        let mut data = vfs.read_inode(inode)?;
+        data.truncate(data.len() / LOOP_DEVICE_BLOCK_SIZE * LOOP_DEVICE_BLOCK_SIZE);
+
+        Ok(Self
+        {
+            inode,
+            data,
+            dirty: false
+        })
+    }
+}
+
+impl BlockDevice for LoopDevice
+{
+    fn block_size(&self) -> usize
+    {
+        LOOP_DEVICE_BLOCK_SIZE
+    }
+
+    fn size(&self) -> usize
+    {
+        self.data.len()
+    }
+
+    fn read(&mut self, offset: usize, buffer: &mut [u8]) -> BlockDeviceResult<()>
+    {
+        Self::check_access(self, offset, buffer.len())?;
+
+        buffer.copy_from_slice(&self.data[offset..offset + buffer.len()]);
+
+        Ok(())
+    }
+
+    fn write(&mut self, offset: usize, buffer: &[u8]) -> BlockDeviceResult<()>
+    {
+        Self::check_access(self, offset, buffer.len())?;
+
+        self.data[offset..offset + buffer.len()].copy_from_slice(buffer);
+        self.dirty = true;
+
+        Ok(())
+    }
+
+    fn flush(&mut self) -> BlockDeviceResult<()>
+    {
+        if self.dirty
+        {
+            let vfs = fs::vfs::get_vfs_reference().ok_or(BlockDeviceError::DeviceError)?;
+
+            // Writing back the whole file leaves any partial block past the
+            // end of the device as it was
+            let mut contents = vfs.read_inode(self.inode).map_err(|_| BlockDeviceError::DeviceError)?;
+            contents.resize(contents.len().max(self.data.len()), 0);
+            contents[..self.data.len()].copy_from_slice(&self.data);
+
+            vfs.write_inode(self.inode, &contents).map_err(|_| BlockDeviceError::DeviceError)?;
+
+            self.dirty = false;
+        }
+
+        Ok(())
+    }
+
+    fn remove(&mut self)
+    {
+        if self.flush().is_err()
+        {
+            kerrorln!("Unable to write back loop device to inode {:?}", self.inode);
+        }
+    }
+}
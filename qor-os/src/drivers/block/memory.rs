@@ -0,0 +1,144 @@
+//! In Memory Block Device
+
+use crate::*;
+
+use super::*;
+
+/// Block device backed by a buffer in kernel memory
+pub struct MemoryBlockDevice
+{
+    data: Vec<u8>,
+    block_size: usize
+}
+
+impl MemoryBlockDevice
+{
+    /// Create a zeroed device holding the given number of blocks
+    pub fn new(block_size: usize, blocks: usize) -> Self
+    {
+        Self
+        {
+            data: vec![0; block_size * blocks],
+            block_size
+        }
+    }
+
+    /// Create a device holding an existing image, the image is padded with
+    /// zeros out to a whole number of blocks
+    pub fn from_image(block_size: usize, mut data: Vec<u8>) -> Self
+    {
+        let blocks = (data.len() + block_size - 1) / block_size;
+        data.resize(blocks * block_size, 0);
+
+        Self
+        {
+            data,
+            block_size
+        }
+    }
+
+    /// Get the contents of the device
+    pub fn image(&self) -> &[u8]
+    {
+        &self.data
+    }
+}
+
+impl BlockDevice for MemoryBlockDevice
+{
+    fn block_size(&self) -> usize
+    {
+        self.block_size
+    }
+
+    fn size(&self) -> usize
+    {
+        self.data.len()
+    }
+
+    fn read(&mut self, offset: usize, buffer: &mut [u8]) -> BlockDeviceResult<()>
+    {
+        Self::check_access(self, offset, buffer.len())?;
+
+        buffer.copy_from_slice(&self.data[offset..offset + buffer.len()]);
+
+        Ok(())
+    }
+
+    fn write(&mut self, offset: usize, buffer: &[u8]) -> BlockDeviceResult<()>
+    {
+        Self::check_access(self, offset, buffer.len())?;
+
+        self.data[offset..offset + buffer.len()].copy_from_slice(buffer);
+
+        Ok(())
+    }
+
+    fn flush(&mut self) -> BlockDeviceResult<()>
+    {
+        Ok(())
+    }
+}
+
+/*
+    ===================== Tests for the Memory Block Device =====================
+*/
+
+/// Test Memory Block Device - Reads see earlier writes
+#[test_case]
+pub fn test_memory_block_device_read_write()
+{
+    let mut device = MemoryBlockDevice::new(512, 4);
+
+    let data = [0xA5u8; 1024];
+    device.write(512, &data).unwrap();
+
+    let mut buffer = [0u8; 1536];
+    device.read(0, &mut buffer).unwrap();
+
+    assert!(buffer[..512].iter().all(|b| *b == 0));
+    assert!(buffer[512..].iter().all(|b| *b == 0xA5));
+}
+
+/// Test Memory Block Device - Accesses outside the device or of partial
+/// blocks are rejected
+#[test_case]
+pub fn test_memory_block_device_bounds()
+{
+    let mut device = MemoryBlockDevice::new(512, 4);
+
+    let mut buffer = [0u8; 512];
+
+    assert_eq!(device.read(2048, &mut buffer), Err(BlockDeviceError::OutOfRange));
+    assert_eq!(device.read(1536, &mut [0u8; 1024]), Err(BlockDeviceError::OutOfRange));
+    assert_eq!(device.read(100, &mut buffer), Err(BlockDeviceError::Unaligned));
+    assert_eq!(device.write(0, &buffer[..100]), Err(BlockDeviceError::Unaligned));
+}
+
+/// Test Memory Block Device - A Minix3 filesystem formatted onto the device
+/// can be mounted, written and read back
+#[test_case]
+pub fn test_memory_block_device_minix3()
+{
+    use fs::fstrait::Filesystem;
+    use libutils::minix3;
+
+    let mut device = MemoryBlockDevice::new(minix3::BLOCK_SIZE, 64);
+
+    let superblock = minix3::format_superblock(64, 0).unwrap();
+    minix3::format(&superblock, 0, |index, block| device.write(index * minix3::BLOCK_SIZE, block)).unwrap();
+
+    let mut filesystem = Box::new(fs::minix3::Minix3Filesystem::new(Box::new(device)));
+    filesystem.init().unwrap();
+
+    let vfs = fs::vfs::get_vfs_reference().unwrap_or_else(fs::vfs::FilesystemInterface::new);
+    let root = vfs.register_fs("memory", filesystem).unwrap();
+
+    let file = vfs.create_file(root, String::from("hello"), 0o644).unwrap();
+    vfs.write_inode(file, b"Hello, world!").unwrap();
+
+    assert_eq!(vfs.read_inode(file).unwrap(), b"Hello, world!");
+    assert!(vfs.get_dir_entries(root).unwrap().iter().any(|entry| entry.name == "hello" && entry.index == file));
+
+    vfs.unregister_fs(root.mount_id);
+}
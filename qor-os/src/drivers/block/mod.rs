@@ -0,0 +1,125 @@
+//! Block Devices
+//!
+//! Filesystems access their backing storage through the `BlockDevice` trait,
+//! so the same filesystem driver can run over a VirtIO disk, a RAM disk, a
+//! file through a loop device or a buffer in memory.
+
+use crate::*;
+
+pub mod ioctl;
+
+mod loop_device;
+pub use loop_device::*;
+
+mod memory;
+
+mod ramdisk;
+pub use ramdisk::*;
+
+/// Errors reported by a block device
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockDeviceError
+{
+    // The access runs past the end of the device
+    OutOfRange,
+    // The offset or length of the access is not a multiple of the block size
+    Unaligned,
+    // The device failed to complete the request
+    DeviceError
+}
+
+pub type BlockDeviceResult<T> = Result<T, BlockDeviceError>;
+
+impl BlockDeviceError
+{
+    /// Convert the error to an errno value
+    pub fn to_errno(&self) -> usize
+    {
+        match self
+        {
+            BlockDeviceError::OutOfRange => errno::ENOSPC,
+            BlockDeviceError::Unaligned => errno::EINVAL,
+            BlockDeviceError::DeviceError => errno::EIO
+        }
+    }
+}
+
+/// Block Device Trait
+/// Storage which is read and written in whole blocks, offsets and lengths
+/// passed to the device are in bytes and must be multiples of the block size
+pub trait BlockDevice
+{
+    /// Get the size of a block in bytes
+    fn block_size(&self) -> usize;
+
+    /// Get the size of the device in bytes
+    fn size(&self) -> usize;
+
+    /// Read from the device at the given offset into the buffer
+    fn read(&mut self, offset: usize, buffer: &mut [u8]) -> BlockDeviceResult<()>;
+
+    /// Write the buffer to the device at the given offset
+    fn write(&mut self, offset: usize, buffer: &[u8]) -> BlockDeviceResult<()>;
+
+    /// Wait for any outstanding writes to reach the device
+    fn flush(&mut self) -> BlockDeviceResult<()>;
+
+    /// Detach the device from the filesystem which was using it, once the
+    /// filesystem is unmounted
+    fn remove(&mut self)
+    {
+
+    }
+
+    /// Check an access lies within the device and is made up of whole blocks
+    fn check_access(&self, offset: usize, length: usize) -> BlockDeviceResult<()>
+    {
+        if offset % self.block_size() != 0 || length % self.block_size() != 0
+        {
+            Err(BlockDeviceError::Unaligned)
+        }
+        else if offset.checked_add(length).map(|end| end > self.size()).unwrap_or(true)
+        {
+            Err(BlockDeviceError::OutOfRange)
+        }
+        else
+        {
+            Ok(())
+        }
+    }
+}
+
+/// Devices borrowed for the lifetime of the kernel, such as the discovered
+/// VirtIO disks, can be handed to a filesystem directly
+impl<T: BlockDevice + ?Sized> BlockDevice for &'static mut T
+{
+    fn block_size(&self) -> usize
+    {
+        (**self).block_size()
+    }
+
+    fn size(&self) -> usize
+    {
+        (**self).size()
+    }
+
+    fn read(&mut self, offset: usize, buffer: &mut [u8]) -> BlockDeviceResult<()>
+    {
+        (**self).read(offset, buffer)
+    }
+
+    fn write(&mut self, offset: usize, buffer: &[u8]) -> BlockDeviceResult<()>
+    {
+        (**self).write(offset, buffer)
+    }
+
+    fn flush(&mut self) -> BlockDeviceResult<()>
+    {
+        (**self).flush()
+    }
+
+    fn remove(&mut self)
+    {
+        (**self).remove()
+    }
+}
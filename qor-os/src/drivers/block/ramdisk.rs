@@ -0,0 +1,220 @@
+//! RAM Disks
+//!
+//! Block devices held in kernel pages, available as `/dev/ram0` onwards.
+//! They serve as a scratch device for filesystems, and as the root device
+//! when no disk is attached. The size of each disk may be given in KiB by the
+//! `ramdisk_size` boot parameter.
+
+use crate::*;
+
+use crate::drivers::generic::BufferInterface;
+use crate::fs::ioctl::IOControlCommand;
+
+use libutils::minix3;
+
+use super::*;
+
+// Names of the RAM disks created at boot
+pub const RAM_DISK_NAMES: [&str; 1] = ["ram0"];
+
+// Size of each RAM disk in bytes when no size is given at boot
+pub const DEFAULT_RAM_DISK_SIZE: usize = 4 * 1024 * 1024;
+
+// Size of the blocks the RAM disks are accessed in
+pub const RAM_DISK_BLOCK_SIZE: usize = 512;
+
+// RAM disks created at boot
+static mut RAM_DISKS: Vec<RamDisk> = Vec::new();
+
+/// Block device backed by consecutive kernel pages
+pub struct RamDisk
+{
+    data: *mut u8,
+    size: usize
+}
+
+impl RamDisk
+{
+    /// Allocate a zeroed RAM disk of the given size in bytes
+    pub fn new(size: usize) -> Option<Self>
+    {
+        let pages = (size + mem::PAGE_SIZE - 1) / mem::PAGE_SIZE;
+
+        let data = mem::kpzalloc(pages, "RAM Disk").ok()? as *mut u8;
+
+        Some(Self
+        {
+            data,
+            size: pages * mem::PAGE_SIZE
+        })
+    }
+
+    /// Get the contents of the disk
+    fn contents(&mut self) -> &mut [u8]
+    {
+        // Safety: The pages were allocated for the disk when it was created
+        unsafe { core::slice::from_raw_parts_mut(self.data, self.size) }
+    }
+}
+
+impl BlockDevice for RamDisk
+{
+    fn block_size(&self) -> usize
+    {
+        RAM_DISK_BLOCK_SIZE
+    }
+
+    fn size(&self) -> usize
+    {
+        self.size
+    }
+
+    fn read(&mut self, offset: usize, buffer: &mut [u8]) -> BlockDeviceResult<()>
+    {
+        Self::check_access(self, offset, buffer.len())?;
+
+        buffer.copy_from_slice(&self.contents()[offset..offset + buffer.len()]);
+
+        Ok(())
+    }
+
+    fn write(&mut self, offset: usize, buffer: &[u8]) -> BlockDeviceResult<()>
+    {
+        Self::check_access(self, offset, buffer.len())?;
+
+        self.contents()[offset..offset + buffer.len()].copy_from_slice(buffer);
+
+        Ok(())
+    }
+
+    fn flush(&mut self) -> BlockDeviceResult<()>
+    {
+        Ok(())
+    }
+}
+
+impl RamDisk
+{
+    /// Write a fresh Minix3 filesystem with an empty root directory to the
+    /// disk, the existing contents are lost
+    pub fn format_minix3(&mut self, inodes: usize) -> Result<(), usize>
+    {
+        let superblock = minix3::format_superblock(self.size / minix3::BLOCK_SIZE, inodes).map_err(|_| errno::EINVAL)?;
+
+        let time = drivers::rtc::RealTimeClockDriver::get_driver().get_unix_timestamp_nano() / 1_000_000_000;
+
+        minix3::format(&superblock, time as u32,
+            |index, block| self.write(index * minix3::BLOCK_SIZE, block).map_err(|e| e.to_errno()))
+    }
+}
+
+/// The device file of a RAM disk gives byte access to its contents
+impl BufferInterface for RamDisk
+{
+    fn read_byte(&mut self, offset: usize) -> Option<u8>
+    {
+        self.contents().get(offset).copied()
+    }
+
+    fn write_byte(&mut self, offset: usize, data: u8)
+    {
+        if let Some(byte) = self.contents().get_mut(offset)
+        {
+            *byte = data;
+        }
+    }
+
+    fn get_size(&self) -> usize
+    {
+        self.size
+    }
+
+    fn flush(&mut self) {}
+
+    fn get_buffer(&self) -> Option<*mut u8>
+    {
+        Some(self.data)
+    }
+}
+
+/// Get the size in bytes of the RAM disks to create, as given in KiB by the
+/// `ramdisk_size` boot parameter
+fn ram_disk_size() -> usize
+{
+    match bootargs::value("ramdisk_size").map(|size| size.parse::<usize>())
+    {
+        Some(Ok(size)) if size > 0 => size.saturating_mul(1024),
+        Some(_) =>
+        {
+            kwarnln!("Invalid ramdisk_size, using {} KiB", DEFAULT_RAM_DISK_SIZE / 1024);
+            DEFAULT_RAM_DISK_SIZE
+        },
+        None => DEFAULT_RAM_DISK_SIZE
+    }
+}
+
+/// Create the RAM disks
+pub fn init_ram_disks()
+{
+    let size = ram_disk_size();
+
+    for name in RAM_DISK_NAMES.iter()
+    {
+        if let Some(disk) = RamDisk::new(size)
+        {
+            unsafe { RAM_DISKS.push(disk) };
+        }
+        else
+        {
+            kwarnln!("Unable to allocate RAM disk {}", name);
+            break;
+        }
+    }
+}
+
+/// Get the RAM disk with the given index
+pub fn get_ram_disk(index: usize) -> Option<&'static mut RamDisk>
+{
+    unsafe { RAM_DISKS.get_mut(index) }
+}
+
+/// Get the number of RAM disks
+pub fn ram_disk_count() -> usize
+{
+    unsafe { RAM_DISKS.len() }
+}
+
+/// Run an ioctl on the RAM disk with the given index
+pub fn ram_disk_ioctl(index: usize, cmd: IOControlCommand) -> usize
+{
+    let disk = match get_ram_disk(index)
+    {
+        Some(disk) => disk,
+        None => return errno::ENXIO
+    };
+
+    match cmd
+    {
+        IOControlCommand::BlockFormatMinix3 { inodes } =>
+        {
+            // A mounted filesystem keeps its own cache of the disk, which
+            // would be written back over the new filesystem
+            let source = format!("/dev/{}", RAM_DISK_NAMES[index]);
+
+            if let Some(vfs) = fs::vfs::get_vfs_reference()
+            {
+                if vfs.source_in_use(&source)
+                {
+                    return errno::EBUSY;
+                }
+            }
+
+            match disk.format_minix3(inodes)
+            {
+                Ok(()) => 0,
+                Err(e) => e
+            }
+        },
+        _ => usize::MAX
+    }
+}
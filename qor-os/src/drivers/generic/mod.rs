@@ -1,5 +1,7 @@
 //! Generic traits for drivers to implement to allow connections between drivers
 
+use crate::*;
+
 /// Byte Interface Trait
 /// Allows for the reading and writing of bytes to and from the given interface
 pub trait ByteInterface
@@ -32,4 +34,28 @@ pub trait BufferInterface
 
     /// Attempt to access an underlying buffer for mmap
     fn get_buffer(&self) -> Option<*mut u8>;
-}
\ No newline at end of file
+}
+
+/// Power Management Trait
+/// Callbacks made to a driver as the system suspends and resumes, and when
+/// the user of the device is done with it
+pub trait PowerManagement
+{
+    /// Bring the device to rest before the system suspends
+    fn suspend(&mut self) -> Result<(), String>
+    {
+        Ok(())
+    }
+
+    /// Configure the device again once the system resumes
+    fn resume(&mut self) -> Result<(), String>
+    {
+        Ok(())
+    }
+
+    /// Detach the driver from the device
+    fn remove(&mut self)
+    {
+
+    }
+}
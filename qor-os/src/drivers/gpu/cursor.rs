@@ -0,0 +1,193 @@
+//! Console Mouse Cursor
+//!
+//! The cursor is drawn into the framebuffer over whatever is on screen, and
+//! the pixels it covers are saved so they can be put back when it moves. A
+//! pixel which was drawn over after the cursor was placed is left alone when
+//! the cursor is removed.
+
+use crate::resources::colors::Pixel;
+use crate::drivers::virtio::drivers::gpu::Framebuffer;
+
+// Size of the cursor sprite
+pub const CURSOR_WIDTH: usize = 12;
+pub const CURSOR_HEIGHT: usize = 19;
+
+// Cursor sprite, `X` is the outline, `o` the fill, and `.` is transparent
+const CURSOR_SPRITE: [&[u8; CURSOR_WIDTH]; CURSOR_HEIGHT] =
+[
+    b"X...........",
+    b"XX..........",
+    b"XoX.........",
+    b"XooX........",
+    b"XoooX.......",
+    b"XooooX......",
+    b"XoooooX.....",
+    b"XooooooX....",
+    b"XoooooooX...",
+    b"XooooooooX..",
+    b"XoooooooooX.",
+    b"XooooooooooX",
+    b"XooooooXXXXX",
+    b"XoooXooX....",
+    b"XooX.XooX...",
+    b"XoX..XooX...",
+    b"XX....XooX..",
+    b"X.....XooX..",
+    b"......XXX...",
+];
+
+const OUTLINE: Pixel = Pixel::new(0, 0, 0);
+const FILL: Pixel = Pixel::new(255, 255, 255);
+
+/// Get the pixel of the sprite at the given location, if it is not
+/// transparent
+fn sprite_pixel(x: usize, y: usize) -> Option<Pixel>
+{
+    match CURSOR_SPRITE[y][x]
+    {
+        b'X' => Some(OUTLINE),
+        b'o' => Some(FILL),
+        _ => None
+    }
+}
+
+/// State of the pointer reported to userspace
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct ConsoleCursorState
+{
+    pub x: u32,
+    pub y: u32,
+    pub buttons: u32,
+    pub visible: u32
+}
+
+/// Mouse cursor on the console
+pub struct Cursor
+{
+    pub position: (usize, usize),
+    pub buttons: u32,
+    pub visible: bool,
+    active: bool,
+    moved: bool,
+    drawn_at: Option<(usize, usize)>,
+    saved: [Pixel; CURSOR_WIDTH * CURSOR_HEIGHT]
+}
+
+impl Cursor
+{
+    /// Create a new cursor, which is not drawn until a pointer device moves
+    /// it
+    pub fn new() -> Self
+    {
+        Self
+        {
+            position: (0, 0),
+            buttons: 0,
+            visible: true,
+            active: false,
+            moved: false,
+            drawn_at: None,
+            saved: [Pixel::new(0, 0, 0); CURSOR_WIDTH * CURSOR_HEIGHT]
+        }
+    }
+
+    /// Move the cursor to a position on the display
+    pub fn move_to(&mut self, x: usize, y: usize)
+    {
+        self.position = (x, y);
+        self.active = true;
+        self.moved = true;
+    }
+
+    /// Show or hide the cursor
+    pub fn set_visible(&mut self, visible: bool)
+    {
+        self.visible = visible;
+        self.moved = true;
+    }
+
+    /// Check if the cursor must be drawn again, clearing the flag
+    pub fn take_moved(&mut self) -> bool
+    {
+        core::mem::replace(&mut self.moved, false)
+    }
+
+    /// Get the state reported to userspace
+    pub fn state(&self) -> ConsoleCursorState
+    {
+        ConsoleCursorState
+        {
+            x: self.position.0 as u32,
+            y: self.position.1 as u32,
+            buttons: self.buttons,
+            visible: self.visible as u32
+        }
+    }
+
+    /// Remove the cursor from the framebuffer, returning the rectangle which
+    /// was changed
+    pub fn hide(&mut self, frame_buffer: &mut Framebuffer) -> Option<(usize, usize, usize, usize)>
+    {
+        let (x, y) = self.drawn_at.take()?;
+        let (w, h) = Self::clip(frame_buffer, x, y);
+
+        for row in 0..h
+        {
+            for col in 0..w
+            {
+                if let Some(sprite) = sprite_pixel(col, row)
+                {
+                    let pixel = frame_buffer.pixel_mut(x + col, y + row);
+
+                    if *pixel == sprite
+                    {
+                        *pixel = self.saved[col + row * CURSOR_WIDTH];
+                    }
+                }
+            }
+        }
+
+        Some((x, y, w, h))
+    }
+
+    /// Draw the cursor into the framebuffer at its position on the display,
+    /// given the offset of the display, returning the rectangle which was
+    /// changed
+    pub fn show(&mut self, frame_buffer: &mut Framebuffer, offset: (usize, usize)) -> Option<(usize, usize, usize, usize)>
+    {
+        if !self.visible || !self.active
+        {
+            return None;
+        }
+
+        let (x, y) = (self.position.0 + offset.0, self.position.1 + offset.1);
+        let (w, h) = Self::clip(frame_buffer, x, y);
+
+        for row in 0..h
+        {
+            for col in 0..w
+            {
+                if let Some(sprite) = sprite_pixel(col, row)
+                {
+                    let pixel = frame_buffer.pixel_mut(x + col, y + row);
+
+                    self.saved[col + row * CURSOR_WIDTH] = *pixel;
+                    *pixel = sprite;
+                }
+            }
+        }
+
+        self.drawn_at = Some((x, y));
+
+        Some((x, y, w, h))
+    }
+
+    /// Get the part of the sprite which fits within the framebuffer
+    fn clip(frame_buffer: &Framebuffer, x: usize, y: usize) -> (usize, usize)
+    {
+        let (width, height) = frame_buffer.get_size();
+
+        (CURSOR_WIDTH.min(width.saturating_sub(x)), CURSOR_HEIGHT.min(height.saturating_sub(y)))
+    }
+}
@@ -1,12 +1,70 @@
 use crate::*;
 
-const TEXT_MODE_WIDTH: usize = 70;
-const TEXT_MODE_HEIGHT: usize = 30;
-
 use crate::drivers::generic::*;
 use crate::fs::ioctl::IOControlCommand;
 
 use super::structs::*;
+use super::cursor::Cursor;
+
+use crate::resources::fonts::psf::Font;
+
+use crate::process::PID;
+
+// Must be kept in sync with linux/fb.h
+const FB_TYPE_PACKED_PIXELS: u32 = 0;
+const FB_VISUAL_TRUECOLOR: u32 = 2;
+const FB_VISUAL_PSEUDOCOLOR: u32 = 3;
+const FB_ACTIVATE_TEST: u32 = 16;
+
+// Must be kept in sync with linux/kd.h
+pub const KD_TEXT: usize = 0;
+pub const KD_GRAPHICS: usize = 1;
+
+const BYTES_PER_PIXEL: usize = core::mem::size_of::<Pixel>();
+
+// Number of entries in the colormap used by the indexed pixel format
+const COLORMAP_SIZE: usize = 256;
+
+use crate::resources::colors::Pixel;
+
+/// Build the colormap used until a program sets its own, the first 16 entries
+/// are the EGA colors, followed by a 6x6x6 color cube and a grayscale ramp
+fn default_colormap() -> FramebufferColormap
+{
+    let mut colormap = FramebufferColormap::new(0, COLORMAP_SIZE, true);
+
+    // Scale an 8 bit channel up to the 16 bits used by the colormap
+    let scale = |v: usize| (v * 0x101) as u16;
+
+    for i in 0..COLORMAP_SIZE
+    {
+        let (r, g, b) = if i < 16
+        {
+            let (r, g, b) = crate::resources::colors::ega::EGA_COLORS[i].channels();
+
+            (scale(r as usize), scale(g as usize), scale(b as usize))
+        }
+        else if i < 232
+        {
+            let level = |v: usize| if v == 0 { 0 } else { scale(55 + v * 40) };
+            let v = i - 16;
+
+            (level(v / 36), level((v / 6) % 6), level(v % 6))
+        }
+        else
+        {
+            let v = scale(8 + (i - 232) * 10);
+
+            (v, v, v)
+        };
+
+        colormap.red[i] = r;
+        colormap.green[i] = g;
+        colormap.blue[i] = b;
+    }
+
+    colormap
+}
 
 #[derive(Debug, Clone)]
 pub enum ParserState
@@ -126,7 +184,7 @@ pub fn ansi_to_ega(c: u8) -> u8
 }
 
 /// Text Mode Cell
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct TextModeCell
 {
     c: u8,
@@ -134,20 +192,56 @@ pub struct TextModeCell
     bg: u8
 }
 
+/// Rectangle of cells on the text console, the end is exclusive
+#[derive(Debug, Clone, Copy)]
+pub struct CellRect
+{
+    x0: usize,
+    y0: usize,
+    x1: usize,
+    y1: usize
+}
+
+impl CellRect
+{
+    /// Grow the rectangle to cover another
+    fn union(self, other: CellRect) -> CellRect
+    {
+        CellRect
+        {
+            x0: self.x0.min(other.x0),
+            y0: self.y0.min(other.y0),
+            x1: self.x1.max(other.x1),
+            y1: self.y1.max(other.y1)
+        }
+    }
+}
+
 /// Text Mode Data
+///
+/// The text is written to `buffer`, and `front` holds the cells last drawn to
+/// the framebuffer. Cells which have changed since the last flush are within
+/// the `dirty` rectangle, and `scrolled` counts the lines the front buffer
+/// must be moved up by before they are compared.
 #[derive(Debug, Clone)]
 pub struct TextModeData
 {
     parser: ANSIParser,
-    buffer: [TextModeCell; TEXT_MODE_WIDTH * TEXT_MODE_HEIGHT],
+    width: usize,
+    height: usize,
+    buffer: Vec<TextModeCell>,
+    front: Vec<Option<TextModeCell>>,
+    dirty: Option<CellRect>,
+    scrolled: usize,
     cursor_pos: (usize, usize),
     bg: u8,
     fg: u8
 }
 
-impl core::default::Default for TextModeData
+impl TextModeData
 {
-    fn default() -> Self
+    /// Create a new text mode with the given number of columns and rows
+    pub fn new(width: usize, height: usize) -> Self
     {
         let fg = 15;
         let bg = 0;
@@ -155,24 +249,95 @@ impl core::default::Default for TextModeData
         Self
         {
             parser: ANSIParser::new(),
-            buffer: [TextModeCell { c: ' ' as u8, fg, bg}; TEXT_MODE_WIDTH * TEXT_MODE_HEIGHT],
+            width,
+            height,
+            buffer: vec![TextModeCell { c: ' ' as u8, fg, bg}; width * height],
+            front: vec![None; width * height],
+            dirty: Some(CellRect { x0: 0, y0: 0, x1: width, y1: height }),
+            scrolled: 0,
             cursor_pos: (0, 0),
             bg, fg
         }
     }
-}
 
-impl TextModeData
-{
+    /// Change the number of columns and rows, keeping the text which still
+    /// fits, the bottom of the text is kept if the height shrinks
+    pub fn resize(&mut self, width: usize, height: usize)
+    {
+        let blank = TextModeCell { c: ' ' as u8, fg: self.fg, bg: self.bg };
+        let mut buffer = vec![blank; width * height];
+
+        let skip = (self.cursor_pos.1 + 1).saturating_sub(height);
+
+        for y in 0..height.min(self.height - skip)
+        {
+            for x in 0..width.min(self.width)
+            {
+                buffer[x + y * width] = self.buffer[x + (y + skip) * self.width];
+            }
+        }
+
+        self.buffer = buffer;
+        self.width = width;
+        self.height = height;
+        self.cursor_pos = (self.cursor_pos.0.min(width - 1), self.cursor_pos.1 - skip);
+
+        self.invalidate_all();
+    }
+
+    /// Mark a rectangle of cells as changed
+    fn mark_dirty(&mut self, x: usize, y: usize, width: usize, height: usize)
+    {
+        let rect = CellRect { x0: x, y0: y, x1: x + width, y1: y + height };
+
+        self.dirty = Some(match self.dirty
+        {
+            Some(dirty) => dirty.union(rect),
+            None => rect
+        });
+    }
+
+    /// Forget what has been drawn, so every cell is drawn on the next flush
+    pub fn invalidate_all(&mut self)
+    {
+        self.front = vec![None; self.width * self.height];
+        self.scrolled = 0;
+
+        self.mark_dirty(0, 0, self.width, self.height);
+    }
+
+    /// Set the cell at the given position
+    fn set_cell(&mut self, x: usize, y: usize, cell: TextModeCell)
+    {
+        self.buffer[x + y * self.width] = cell;
+        self.mark_dirty(x, y, 1, 1);
+    }
+
+    /// Move the front buffer up by the lines scrolled since the last flush,
+    /// returning the number of lines moved
+    fn take_scroll(&mut self) -> usize
+    {
+        let amt = self.scrolled;
+        self.scrolled = 0;
+
+        if amt > 0
+        {
+            self.front.drain(0..amt * self.width);
+            self.front.resize(self.width * self.height, None);
+        }
+
+        amt
+    }
+
     /// Add a newline
     pub fn newline(&mut self)
     {
         self.cursor_pos.0 = 0;
         self.cursor_pos.1 += 1;
 
-        if self.cursor_pos.1 >= TEXT_MODE_HEIGHT
+        if self.cursor_pos.1 >= self.height
         {
-            let amt = 1 + self.cursor_pos.1 - TEXT_MODE_HEIGHT;
+            let amt = 1 + self.cursor_pos.1 - self.height;
             self.scroll(amt);
         }
     }
@@ -180,13 +345,13 @@ impl TextModeData
     /// Scroll by some amount
     pub fn scroll(&mut self, mut amt: usize)
     {
-        if amt <= TEXT_MODE_HEIGHT
+        if amt <= self.height
         {
-            for y in 0..TEXT_MODE_HEIGHT - amt
+            for y in 0..self.height - amt
             {
-                for x in 0..TEXT_MODE_WIDTH
+                for x in 0..self.width
                 {
-                    self.buffer[x + y * TEXT_MODE_WIDTH] = self.buffer[x + (y + amt) * TEXT_MODE_WIDTH];
+                    self.buffer[x + y * self.width] = self.buffer[x + (y + amt) * self.width];
                 }
             }
 
@@ -200,17 +365,21 @@ impl TextModeData
             }
         }
 
-        if amt > TEXT_MODE_HEIGHT
+        if amt > self.height
         {
-            amt = TEXT_MODE_HEIGHT;
+            amt = self.height;
         }
 
+        // The lines on screen are moved rather than redrawn when flushed
+        self.scrolled = (self.scrolled + amt).min(self.height);
+        self.mark_dirty(0, 0, self.width, self.height);
+
         
-        for y in (TEXT_MODE_HEIGHT - amt)..=TEXT_MODE_HEIGHT - 1
+        for y in (self.height - amt)..=self.height - 1
         {
-            for x in 0..TEXT_MODE_WIDTH
+            for x in 0..self.width
             {
-                self.buffer[x + y * TEXT_MODE_WIDTH] = TextModeCell { c: 0x20, fg: self.fg, bg: self.bg };
+                self.buffer[x + y * self.width] = TextModeCell { c: 0x20, fg: self.fg, bg: self.bg };
             }
         }
     }
@@ -228,7 +397,14 @@ pub enum GraphicsMode
 pub struct GenericGraphics
 {
     driver: &'static mut crate::drivers::virtio::drivers::gpu::GPUDriver,
-    mode: GraphicsMode
+    mode: GraphicsMode,
+    font: Font,
+    cursor: Cursor,
+    colormap: FramebufferColormap,
+    palette: [Pixel; COLORMAP_SIZE],
+    indexed_buffer: Option<*mut u8>,
+    indexed: bool,
+    display_owner: Option<PID>
 }
 
 impl GenericGraphics
@@ -236,11 +412,314 @@ impl GenericGraphics
     /// Create a new generic graphics driver
     pub fn new(driver: &'static mut crate::drivers::virtio::drivers::gpu::GPUDriver) -> Self
     {
-        Self
+        let font = Font::vga();
+        let (width, height) = driver.get_size();
+
+        let mut result = Self
         {
+            mode: GraphicsMode::PseudoTextMode(TextModeData::new(width / font.width, height / font.height)),
             driver,
-            mode: GraphicsMode::PseudoTextMode(TextModeData::default())
+            font,
+            cursor: Cursor::new(),
+            colormap: default_colormap(),
+            palette: [Pixel::new(0, 0, 0); COLORMAP_SIZE],
+            indexed_buffer: None,
+            indexed: false,
+            display_owner: None
+        };
+
+        result.update_palette();
+
+        result
+    }
+
+    /// Rebuild the palette used to convert indexed pixels from the colormap
+    fn update_palette(&mut self)
+    {
+        for i in 0..COLORMAP_SIZE
+        {
+            self.palette[i] = Pixel::new((self.colormap.red[i] >> 8) as u8, (self.colormap.green[i] >> 8) as u8, (self.colormap.blue[i] >> 8) as u8);
+        }
+    }
+
+    /// Switch between the native and 8 bit indexed pixel formats, the buffer
+    /// for indexed pixels is allocated the first time it is needed
+    fn set_indexed(&mut self, indexed: bool) -> bool
+    {
+        if indexed && self.indexed_buffer.is_none()
+        {
+            let (width, height) = self.driver.get_virtual_size();
+            let page_count = (width * height + crate::mem::PAGE_SIZE - 1) / crate::mem::PAGE_SIZE;
+
+            match crate::mem::kpzalloc(page_count, "Indexed Framebuffer")
+            {
+                Ok(ptr) => self.indexed_buffer = Some(ptr as *mut u8),
+                Err(_) => { return false; }
+            }
         }
+
+        self.indexed = indexed;
+
+        true
+    }
+
+    /// Get the number of bytes used to store a pixel in the current format
+    fn bytes_per_pixel(&self) -> usize
+    {
+        if self.indexed { 1 } else { BYTES_PER_PIXEL }
+    }
+
+    /// Get a pointer to the buffer programs draw to in the current format
+    fn buffer_pointer(&self) -> *mut u8
+    {
+        match (self.indexed, self.indexed_buffer)
+        {
+            (true, Some(ptr)) => ptr,
+            _ => self.driver.frame_buffer.get_pointer() as *mut u8
+        }
+    }
+
+    /// Convert a region of indexed pixels to the native format of the GPU
+    fn convert_indexed(&mut self, x: usize, y: usize, w: usize, h: usize)
+    {
+        if let (true, Some(ptr)) = (self.indexed, self.indexed_buffer)
+        {
+            let (width, _) = self.driver.get_virtual_size();
+
+            for py in y..y + h
+            {
+                for px in x..x + w
+                {
+                    let index = unsafe { ptr.add(px + py * width).read() };
+                    *self.driver.frame_buffer.pixel_mut(px, py) = self.palette[index as usize];
+                }
+            }
+        }
+    }
+
+    /// Push the visible part of the framebuffer to the display
+    fn flush_display(&mut self)
+    {
+        let (x, y) = self.driver.get_pan_offset();
+        let (w, h) = self.driver.get_size();
+
+        // The cursor is taken off while the pixels are converted, so it is
+        // not lost under them
+        self.cursor.hide(&mut self.driver.frame_buffer);
+        self.convert_indexed(x, y, w, h);
+
+        if self.display_owner.is_none()
+        {
+            self.cursor.show(&mut self.driver.frame_buffer, (x, y));
+        }
+
+        self.driver.invalidate(x, y, w, h);
+    }
+
+    /// Pan the display, converting the newly visible region if needed
+    fn pan_display(&mut self, x: usize, y: usize) -> bool
+    {
+        let (width, height) = self.driver.get_virtual_size();
+        let (w, h) = self.driver.get_size();
+
+        if x + w > width || y + h > height
+        {
+            return false;
+        }
+
+        self.cursor.hide(&mut self.driver.frame_buffer);
+        self.convert_indexed(x, y, w, h);
+
+        let result = self.driver.pan(x, y).is_ok();
+
+        if self.display_owner.is_some()
+        {
+            return result;
+        }
+
+        // The cursor stays at the same place on the display
+        let offset = self.driver.get_pan_offset();
+
+        if let Some((x, y, w, h)) = self.cursor.show(&mut self.driver.frame_buffer, offset)
+        {
+            self.driver.invalidate(x, y, w, h);
+        }
+
+        result
+    }
+
+    /// Draw the cursor again at its current position, transferring the
+    /// regions it left and entered
+    pub fn redraw_cursor(&mut self)
+    {
+        if self.display_owner.is_some()
+        {
+            return;
+        }
+
+        let old = self.cursor.hide(&mut self.driver.frame_buffer);
+        let offset = self.driver.get_pan_offset();
+        let new = self.cursor.show(&mut self.driver.frame_buffer, offset);
+
+        for (x, y, w, h) in old.into_iter().chain(new)
+        {
+            self.driver.invalidate(x, y, w, h);
+        }
+    }
+
+    /// Move the cursor to a position on the display, clamped to its edges
+    pub fn move_cursor(&mut self, x: isize, y: isize)
+    {
+        let (width, height) = self.driver.get_size();
+
+        self.cursor.move_to(x.clamp(0, width as isize - 1) as usize, y.clamp(0, height as isize - 1) as usize);
+    }
+
+    /// Get the size of the display
+    pub fn display_size(&self) -> (usize, usize)
+    {
+        self.driver.get_size()
+    }
+
+    /// Get the mouse cursor
+    pub fn cursor(&mut self) -> &mut Cursor
+    {
+        &mut self.cursor
+    }
+
+    /// Get the process which has taken over the display from the console
+    pub fn display_owner(&self) -> Option<PID>
+    {
+        self.display_owner
+    }
+
+    /// Check that the given process has taken over the display, giving `EBUSY`
+    /// if another process has it and `EPERM` if the console still does
+    fn check_display_owner(&self, pid: PID) -> Result<(), usize>
+    {
+        match self.display_owner
+        {
+            Some(owner) if owner == pid => Ok(()),
+            Some(_) => Err(errno::EBUSY),
+            None => Err(errno::EPERM)
+        }
+    }
+
+    /// Clear the visible part of the framebuffer to black
+    fn clear_display(&mut self)
+    {
+        let (width, height) = self.driver.get_size();
+        let (x_offset, y_offset) = self.driver.get_pan_offset();
+
+        for y in y_offset..y_offset + height
+        {
+            for x in x_offset..x_offset + width
+            {
+                *self.driver.frame_buffer.pixel_mut(x, y) = Pixel::new(0, 0, 0);
+            }
+        }
+
+        self.driver.invalidate(x_offset, y_offset, width, height);
+    }
+
+    /// Hand the display over to a process, the console stops drawing until
+    /// the display is released, though text written to it is still kept
+    pub fn acquire_display(&mut self, pid: PID) -> Result<(), usize>
+    {
+        match self.display_owner
+        {
+            Some(owner) if owner != pid => Err(errno::EBUSY),
+            Some(_) => Ok(()),
+            None =>
+            {
+                if let Some((x, y, w, h)) = self.cursor.hide(&mut self.driver.frame_buffer)
+                {
+                    self.driver.invalidate(x, y, w, h);
+                }
+
+                self.display_owner = Some(pid);
+
+                Ok(())
+            }
+        }
+    }
+
+    /// Give the display back to the console, returning to the native pixel
+    /// format and the first screen before the console is drawn again
+    pub fn release_display(&mut self)
+    {
+        if self.display_owner.is_none()
+        {
+            return;
+        }
+
+        // The cursor is only drawn again once the display is cleared
+        self.set_indexed(false);
+        self.pan_display(0, 0);
+        self.clear_display();
+
+        self.display_owner = None;
+
+        crate::drivers::keyboard::get_keyboard().reset_mode();
+
+        self.force_update();
+    }
+
+    /// Build the display mode for the current state of the framebuffer
+    fn display_mode(&self) -> FramebufferMode
+    {
+        let (width, height) = self.driver.get_size();
+        let (virtual_width, virtual_height) = self.driver.get_virtual_size();
+        let (_, y_offset) = self.driver.get_pan_offset();
+
+        FramebufferMode
+        {
+            width: width as u32,
+            height: height as u32,
+            pitch: (virtual_width * self.bytes_per_pixel()) as u32,
+            bits_per_pixel: (self.bytes_per_pixel() * 8) as u32,
+            buffer_count: (virtual_height / height) as u32,
+            front_buffer: (y_offset / height) as u32
+        }
+    }
+
+    /// Bring the display up to date, called regularly in place of a
+    /// vertical sync
+    pub fn refresh(&mut self)
+    {
+        if self.display_owner.is_some()
+        {
+            return;
+        }
+
+        self.flush_console();
+
+        if self.cursor.take_moved()
+        {
+            self.redraw_cursor();
+        }
+    }
+
+    /// Change the font used by the text console, the console is resized to
+    /// fit the display with the new font and redrawn
+    pub fn set_font(&mut self, font: Font)
+    {
+        let (width, height) = self.driver.get_size();
+
+        if let GraphicsMode::PseudoTextMode(data) = &mut self.mode
+        {
+            data.resize(width / font.width, height / font.height);
+        }
+
+        self.font = font;
+
+        // Clear anything left over from the old font around the edges
+        if self.display_owner.is_none()
+        {
+            self.clear_display();
+        }
+
+        self.force_update();
     }
 
     /// Initialize the driver
@@ -253,9 +732,10 @@ impl GenericGraphics
     /// Force a screen update
     pub fn force_update(&mut self)
     {
-        if let GraphicsMode::PseudoTextMode(_) = self.mode
+        if let GraphicsMode::PseudoTextMode(data) = &mut self.mode
         {
-            self.invalidate_screen();
+            data.invalidate_all();
+            self.flush_console();
         }
         else
         {
@@ -264,58 +744,88 @@ impl GenericGraphics
         }
     }
     
-    /// Update a character location
-    fn update_character(&mut self, x: usize, y: usize)
+    /// Draw the changed cells of the text console to the framebuffer and
+    /// transfer only the region which changed to the display
+    pub fn flush_console(&mut self)
     {
-        if let GraphicsMode::PseudoTextMode(data) = &mut self.mode
+        // Text is kept but not drawn while a program owns the display
+        if self.display_owner.is_some()
         {
-            let cell = data.buffer[x + y * TEXT_MODE_WIDTH];
+            return;
+        }
 
-            let c = cell.c;
+        let Self { driver, mode, font, cursor, .. } = self;
 
-            let fg = crate::resources::colors::ega::EGA_COLORS[cell.fg as usize];
-            let bg = crate::resources::colors::ega::EGA_COLORS[cell.bg as usize];
+        let data = if let GraphicsMode::PseudoTextMode(data) = mode
+        {
+            data
+        }
+        else
+        {
+            return;
+        };
 
-            self.driver.write_glpyh(&crate::resources::fonts::vga::GLYPHS[c as usize], x, y, fg, bg);
+        if data.dirty.is_none() && data.scrolled == 0
+        {
+            return;
         }
-    }
 
-    /// Invalidate a character location
-    fn invalidate_character(&mut self, x: usize, y: usize)
-    {
-        if let GraphicsMode::PseudoTextMode(_) = self.mode
+        // Take the cursor off the screen so it is neither scrolled nor left
+        // under the new text
+        let hidden = cursor.hide(&mut driver.frame_buffer);
+
+        let scrolled = data.take_scroll();
+
+        // Move the lines already drawn instead of drawing them again
+        if scrolled > 0
         {
-            self.update_character(x, y);
-            self.driver.invalidate(x * 9, y * 16, 9, 16);
+            driver.frame_buffer.scroll_rows(0, data.height * font.height, scrolled * font.height);
         }
-    }
 
-    /// Invalidate a line
-    fn invalidate_line(&mut self, y: usize)
-    {
-        if let GraphicsMode::PseudoTextMode(_) = self.mode
+        let dirty = data.dirty.take().unwrap_or(CellRect { x0: 0, y0: 0, x1: 0, y1: 0 });
+
+        let mut changed: Option<CellRect> = None;
+
+        for y in dirty.y0..dirty.y1.min(data.height)
         {
-            for x in 0..TEXT_MODE_WIDTH
+            for x in dirty.x0..dirty.x1.min(data.width)
             {
-                self.update_character(x, y);
+                let cell = data.buffer[x + y * data.width];
+
+                if data.front[x + y * data.width] == Some(cell)
+                {
+                    continue;
+                }
+
+                let fg = crate::resources::colors::ega::EGA_COLORS[cell.fg as usize];
+                let bg = crate::resources::colors::ega::EGA_COLORS[cell.bg as usize];
+
+                driver.write_glpyh(font, cell.c, x, y, fg, bg);
+                data.front[x + y * data.width] = Some(cell);
+
+                let rect = CellRect { x0: x, y0: y, x1: x + 1, y1: y + 1 };
+                changed = Some(changed.map(|changed| changed.union(rect)).unwrap_or(rect));
             }
+        }
 
-            self.driver.invalidate(0, y * 16, TEXT_MODE_WIDTH * 9, 16);
+        // After a scroll every line on screen has moved
+        if scrolled > 0
+        {
+            changed = Some(CellRect { x0: 0, y0: 0, x1: data.width, y1: data.height });
         }
-    }
 
-    /// Invalidate the entire screen
-    fn invalidate_screen(&mut self)
-    {
-        for y in 0..TEXT_MODE_HEIGHT
+        if let Some(rect) = changed
         {
-            for x in 0..TEXT_MODE_WIDTH
-            {
-                self.update_character(x, y);
-            }
+            driver.invalidate(rect.x0 * font.width, rect.y0 * font.height, (rect.x1 - rect.x0) * font.width, (rect.y1 - rect.y0) * font.height);
         }
 
-        self.driver.invalidate(0, 0, TEXT_MODE_WIDTH * 9, TEXT_MODE_HEIGHT * 16);
+        let offset = driver.get_pan_offset();
+        let shown = cursor.show(&mut driver.frame_buffer, offset);
+
+        for (x, y, w, h) in hidden.into_iter().chain(shown)
+        {
+            driver.invalidate(x, y, w, h);
+        }
     }
 
     /// Write a character to a position on screen
@@ -371,17 +881,17 @@ impl GenericGraphics
 
                 let (x, y) = data.cursor_pos;
 
-                data.buffer[x + y * TEXT_MODE_WIDTH] = TextModeCell { c: ' ' as u8, fg: data.fg, bg: data.bg };
+                data.set_cell(x, y, TextModeCell { c: ' ' as u8, fg: data.fg, bg: data.bg });
             }
             else
             {
                 let (x, y) = data.cursor_pos;
 
-                data.buffer[x + y * TEXT_MODE_WIDTH] = TextModeCell { c: c as u8, fg: data.fg, bg: data.bg };
+                data.set_cell(x, y, TextModeCell { c: c as u8, fg: data.fg, bg: data.bg });
 
                 data.cursor_pos.0 += 1;
 
-                if data.cursor_pos.0 >= TEXT_MODE_WIDTH
+                if data.cursor_pos.0 >= data.width
                 {
                     data.newline();
                 }
@@ -398,6 +908,53 @@ impl GenericGraphics
         }
     }
 
+    /// Build the variable screen info for the current state of the display
+    fn variable_info(&self) -> FramebufferVariableInfo
+    {
+        let (width, height) = self.driver.get_size();
+        let (virtual_width, virtual_height) = self.driver.get_virtual_size();
+        let (x_offset, y_offset) = self.driver.get_pan_offset();
+
+        FramebufferVariableInfo
+        {
+            x_res: width as u32,
+            y_res: height as u32,
+
+            x_res_virtual: virtual_width as u32,
+            y_res_virtual: virtual_height as u32,
+            x_offset: x_offset as u32,
+            y_offset: y_offset as u32,
+
+            bits_per_pixel: (self.bytes_per_pixel() * 8) as u32,
+            grayscale: 0,
+
+            // Pixels are stored as R8G8B8A8 in memory, in the indexed format
+            // every channel comes from the colormap entry of the pixel
+            red: FramebufferBitfield { offset: 0, length: 8, msb_right: 0 },
+            green: FramebufferBitfield { offset: if self.indexed { 0 } else { 8 }, length: 8, msb_right: 0 },
+            blue: FramebufferBitfield { offset: if self.indexed { 0 } else { 16 }, length: 8, msb_right: 0 },
+            transp: if self.indexed
+                {
+                    FramebufferBitfield { offset: 0, length: 0, msb_right: 0 }
+                }
+                else
+                {
+                    FramebufferBitfield { offset: 24, length: 8, msb_right: 0 }
+                },
+
+            non_std: 0,
+
+            activate: 0,
+
+            height: height as u32 / 80,
+            width: width as u32 / 80,
+
+            obsolete_flags: 0,
+
+            unused_timing: [0; 15]
+        }
+    }
+
     /// Execute an ioctl command for this driver
     pub fn exec_ioctl(&mut self, ioctl: IOControlCommand) -> usize
     {
@@ -405,24 +962,24 @@ impl GenericGraphics
         {
             IOControlCommand::FrameBufferGetFixedInfo { response } => 
             {
-                let (width, height) = self.driver.get_size();
+                let (width, height) = self.driver.get_virtual_size();
 
                 *response = FramebufferFixedInfo
                     {
                         name: ['V' as u8, 'i' as u8, 'r' as u8, 't' as u8,
                                'I' as u8, 'O' as u8, ' ' as u8, 'G' as u8,
                                'P' as u8, 'U' as u8, 0, 0, 0, 0, 0, 0],
-                        buffer_start: self.driver.frame_buffer.data as u64,
+                        buffer_start: self.buffer_pointer() as u64,
                     
-                        buffer_len: (width * height * 4) as u32,
-                        fb_type: 0x1000,
+                        buffer_len: (width * height * self.bytes_per_pixel()) as u32,
+                        fb_type: FB_TYPE_PACKED_PIXELS,
                         aux_type: 0,
-                        visual: 2,
-                        x_pan_step: 0,
-                        y_pan_step: 0,
+                        visual: if self.indexed { FB_VISUAL_PSEUDOCOLOR } else { FB_VISUAL_TRUECOLOR },
+                        x_pan_step: 1,
+                        y_pan_step: 1,
                         y_wrap_step: 0,
                     
-                        line_length: (width * 4) as u32,
+                        line_length: (width * self.bytes_per_pixel()) as u32,
                     
                         mmio_len: 0,
                         accel: 0,
@@ -433,53 +990,254 @@ impl GenericGraphics
 
                 0
             },
-            IOControlCommand::FrameBufferPutVariableInfo { .. } => 
+            IOControlCommand::FrameBufferPutVariableInfo { response } => 
             {
-                // TODO: Add variability here
+                let (width, height) = self.driver.get_size();
+                let (virtual_width, virtual_height) = self.driver.get_virtual_size();
+
+                // The resolution and pixel format are fixed, but a virtual
+                // resolution up to the size of the framebuffer may be asked
+                // for, along with an offset to pan the display to
+                if response.x_res as usize != width || response.y_res as usize != height
+                {
+                    return errno::EINVAL;
+                }
+
+                // Either the native format or 8 bit indexed pixels can be
+                // used, zero keeps the current format
+                let indexed = match response.bits_per_pixel
+                {
+                    0 => self.indexed,
+                    8 => true,
+                    bpp if bpp as usize == BYTES_PER_PIXEL * 8 => false,
+                    _ => { return errno::EINVAL; }
+                };
+
+                if response.x_res_virtual as usize > virtual_width || response.y_res_virtual as usize > virtual_height
+                {
+                    return errno::EINVAL;
+                }
+
+                if response.activate & FB_ACTIVATE_TEST == 0
+                {
+                    if !self.set_indexed(indexed)
+                    {
+                        return errno::ENOMEM;
+                    }
+
+                    if !self.pan_display(response.x_offset as usize, response.y_offset as usize)
+                    {
+                        return errno::EINVAL;
+                    }
+                }
+
+                // Report back the settings actually in effect
+                *response = self.variable_info();
 
-                // Nothing here can be edited so far, so we will have to leave
-                // this returning -1 until such features can be implemented
-                usize::MAX
+                0
             },
             IOControlCommand::FrameBufferGetVariableInfo { response } => 
             {
-                let (width, height) = self.driver.get_size();
+                *response = self.variable_info();
+
+                0
+            },
+            IOControlCommand::FrameBufferPanDisplay { response } =>
+            {
+                if !self.pan_display(response.x_offset as usize, response.y_offset as usize)
+                {
+                    return errno::EINVAL;
+                }
+
+                0
+            },
+            IOControlCommand::FrameBufferGetColormap { response } =>
+            {
+                if response.start + response.len() > COLORMAP_SIZE
+                {
+                    return errno::EINVAL;
+                }
+
+                for i in 0..response.len()
+                {
+                    let entry = response.start + i;
 
-                *response = FramebufferVariableInfo
+                    response.red[i] = self.colormap.red[entry];
+                    response.green[i] = self.colormap.green[entry];
+                    response.blue[i] = self.colormap.blue[entry];
+
+                    if let Some(transp) = &mut response.transp
                     {
-                        x_res: width as u32,
-                        y_res: height as u32,
+                        transp[i] = self.colormap.transp.as_ref().unwrap()[entry];
+                    }
+                }
+
+                0
+            },
+            IOControlCommand::FrameBufferPutColormap { response } =>
+            {
+                if response.start + response.len() > COLORMAP_SIZE
+                {
+                    return errno::EINVAL;
+                }
+
+                for i in 0..response.len()
+                {
+                    let entry = response.start + i;
+
+                    self.colormap.red[entry] = response.red[i];
+                    self.colormap.green[entry] = response.green[i];
+                    self.colormap.blue[entry] = response.blue[i];
 
-                        x_res_virtual: width as u32,
-                        y_res_virtual: height as u32,
-                        x_offset: 0,
-                        y_offset: 0,
+                    if let Some(transp) = &response.transp
+                    {
+                        self.colormap.transp.as_mut().unwrap()[entry] = transp[i];
+                    }
+                }
 
-                        bits_per_pixel: 32,
-                        grayscale: 0,
+                self.update_palette();
 
-                        red: FramebufferBitfield { offset: 0, length: 8, msb_right: 0 },
-                        green: FramebufferBitfield { offset: 8, length: 8, msb_right: 0 },
-                        blue: FramebufferBitfield { offset: 16, length: 8, msb_right: 0 },
-                        transp: FramebufferBitfield { offset: 24, length: 8, msb_right: 0 },
+                // Changing the colormap changes the colors already on screen
+                if self.indexed
+                {
+                    self.flush_display();
+                }
 
-                        non_std: 0,
+                0
+            },
+            IOControlCommand::ConsoleLoadFont { data } =>
+            {
+                // Loading an empty font restores the built in font
+                let font = if data.len() == 0
+                {
+                    Font::vga()
+                }
+                else if let Some(font) = Font::from_psf(&data)
+                {
+                    font
+                }
+                else
+                {
+                    return errno::EINVAL;
+                };
 
-                        activate: 0,
+                self.set_font(font);
 
-                        height: height as u32 / 80,
-                        width: width as u32 / 80,
+                0
+            },
+            IOControlCommand::ConsoleSetCursorVisible { visible } =>
+            {
+                self.cursor.set_visible(visible);
+                self.redraw_cursor();
 
-                        obsolete_flags: 0,
+                0
+            },
+            IOControlCommand::ConsoleGetCursor { response } =>
+            {
+                *response = self.cursor.state();
 
-                        unused_timing: [0; 15]
-                    };
                 0
             },
             IOControlCommand::FrameBufferFlush =>
             {
-                let (w, h) = self.driver.frame_buffer.get_size();
-                self.driver.invalidate(0, 0, w, h);
+                self.flush_display();
+
+                0
+            },
+            IOControlCommand::FrameBufferGetMode { response } =>
+            {
+                *response = self.display_mode();
+
+                0
+            },
+            IOControlCommand::FrameBufferSetMode { response, pid } =>
+            {
+                // Only the program which took over the display may change it
+                if let Err(e) = self.check_display_owner(pid)
+                {
+                    return e;
+                }
+
+                let (width, height) = self.driver.get_size();
+                let (_, virtual_height) = self.driver.get_virtual_size();
+
+                if response.width as usize != width || response.height as usize != height
+                {
+                    return errno::EINVAL;
+                }
+
+                if response.buffer_count as usize > virtual_height / height
+                {
+                    return errno::EINVAL;
+                }
+
+                let indexed = match response.bits_per_pixel
+                {
+                    0 => self.indexed,
+                    8 => true,
+                    bpp if bpp as usize == BYTES_PER_PIXEL * 8 => false,
+                    _ => { return errno::EINVAL; }
+                };
+
+                // Changing the pixel format moves the buffer, so mappings of
+                // the framebuffer must be made again afterwards
+                if !self.set_indexed(indexed)
+                {
+                    return errno::ENOMEM;
+                }
+
+                self.pan_display(0, 0);
+                self.flush_display();
+
+                *response = self.display_mode();
+
+                0
+            },
+            IOControlCommand::FrameBufferPageFlip { buffer, pid } =>
+            {
+                if let Err(e) = self.check_display_owner(pid)
+                {
+                    return e;
+                }
+
+                let (_, height) = self.driver.get_size();
+
+                if !self.pan_display(0, buffer * height)
+                {
+                    return errno::EINVAL;
+                }
+
+                0
+            },
+            IOControlCommand::ConsoleSetMode { mode, pid } =>
+            {
+                match mode
+                {
+                    KD_TEXT =>
+                    {
+                        // Only the owner may give the display back, though
+                        // switching to text mode when already there is fine
+                        match self.display_owner
+                        {
+                            Some(owner) if owner != pid => { return errno::EBUSY; },
+                            _ => self.release_display()
+                        }
+                    },
+                    KD_GRAPHICS =>
+                    {
+                        if let Err(e) = self.acquire_display(pid)
+                        {
+                            return e;
+                        }
+                    },
+                    _ => { return errno::EINVAL; }
+                }
+
+                0
+            },
+            IOControlCommand::ConsoleGetMode { response } =>
+            {
+                *response = if self.display_owner.is_some() { KD_GRAPHICS } else { KD_TEXT } as u32;
 
                 0
             }
@@ -503,7 +1261,7 @@ impl ByteInterface for GenericGraphics
 
     fn flush(&mut self)
     {
-        self.invalidate_screen();
+        self.flush_console();
     }
 }
 
@@ -513,7 +1271,7 @@ impl BufferInterface for GenericGraphics
     {
         if offset < self.get_size()
         {
-            Some(unsafe { (self.driver.frame_buffer.data as *mut u8).add(offset).read() })
+            Some(unsafe { self.buffer_pointer().add(offset).read() })
         }
         else
         {
@@ -525,25 +1283,24 @@ impl BufferInterface for GenericGraphics
     {
         if offset < self.get_size()
         {
-            unsafe { (self.driver.frame_buffer.data as *mut u8).add(offset).write(data) }
+            unsafe { self.buffer_pointer().add(offset).write(data) }
         }
     }
 
     fn get_size(&self) -> usize
     {
-        let (w, h) = self.driver.frame_buffer.get_size();
+        let (w, h) = self.driver.get_virtual_size();
 
-        4 * w * h
+        self.bytes_per_pixel() * w * h
     }
 
     fn flush(&mut self)
     {
-        let (w, h) = self.driver.frame_buffer.get_size();
-        self.driver.invalidate(0, 0, w, h)
+        self.flush_display();
     }
 
     fn get_buffer(&self) -> Option<*mut u8>
     {
-        Some(self.driver.frame_buffer.get_pointer() as *mut u8)
+        Some(self.buffer_pointer())
     }
 }
\ No newline at end of file
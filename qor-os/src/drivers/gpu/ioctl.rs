@@ -0,0 +1,236 @@
+//! Framebuffer and console ioctls
+
+use crate::*;
+
+use fs::ioctl::IOControlCommand;
+use process::process::Process;
+use syscalls::args::UserPtr;
+
+use super::structs::{ConsoleFontFile, FramebufferColormap, FramebufferUserColormap};
+
+// Types of the commands handled here
+pub const FRAMEBUFFER_IOCTL_TYPE: u8 = b'F';
+pub const CONSOLE_IOCTL_TYPE: u8 = b'K';
+
+// Must be kept in sync with syscalls.h
+const FBIOGET_VSCREENINFO: usize = 0x4600;
+const FBIOPUT_VSCREENINFO: usize = 0x4601;
+const FBIOGET_FSCREENINFO: usize = 0x4602;
+const FBIOGETCMAP: usize = 0x4604;
+const FBIOPUTCMAP: usize = 0x4605;
+const FBIOPAN_DISPLAY: usize = 0x4606;
+
+// Qor specific framebuffer commands to read and set the display mode, to
+// flip between the screens of the framebuffer and to flush it to the display
+const FB_GET_MODE: usize =   0x46F0;
+const FB_SET_MODE: usize =   0x46F1;
+const FB_PAGE_FLIP: usize =  0x46F2;
+const FB_FLUSH: usize =      0x46FF;
+
+// Qor specific commands to load a PSF font file into the console, and to
+// show or hide and read the state of the mouse cursor
+const CONSOLE_LOAD_FONT: usize = 0x4BFF;
+const CONSOLE_SET_CURSOR: usize = 0x4BFE;
+const CONSOLE_GET_CURSOR: usize = 0x4BFD;
+
+// Console display mode, switched to graphics by a program taking over the
+// display from the text console
+const KDSETMODE: usize =  0x4B3A;
+const KDGETMODE: usize =  0x4B3B;
+
+// Console keyboard commands
+const KDGETLED: usize =   0x4B31;
+const KDSETLED: usize =   0x4B32;
+const KDGKBMODE: usize =  0x4B44;
+const KDSKBMODE: usize =  0x4B45;
+const KDGKBENT: usize =   0x4B46;
+const KDSKBENT: usize =   0x4B47;
+
+// Largest colormap which can be passed to the framebuffer
+const MAX_COLORMAP_LENGTH: usize = 256;
+
+// Largest font file which can be loaded into the console
+const MAX_FONT_FILE_LENGTH: usize = 256 * 1024;
+
+/// View a colormap channel as bytes to copy it to or from userspace
+fn channel_bytes(channel: &mut Vec<u16>) -> &mut [u8]
+{
+    unsafe { core::slice::from_raw_parts_mut(channel.as_mut_ptr() as *mut u8, channel.len() * 2) }
+}
+
+/// Execute a colormap ioctl, the channels of the colormap are separate arrays
+/// in userspace which are copied into the kernel for the driver
+fn colormap_ioctl(proc: &mut Process, fd: usize, cmd: usize, args: usize) -> Result<usize, usize>
+{
+    let user: FramebufferUserColormap = UserPtr::new(args).read(proc)?;
+
+    if user.len as usize > MAX_COLORMAP_LENGTH
+    {
+        return Err(errno::EINVAL);
+    }
+
+    let mut colormap = FramebufferColormap::new(user.start as usize, user.len as usize, user.transp != 0);
+
+    if cmd == FBIOPUTCMAP
+    {
+        syscalls::utils::copy_from_user(proc, user.red as usize, channel_bytes(&mut colormap.red))?;
+        syscalls::utils::copy_from_user(proc, user.green as usize, channel_bytes(&mut colormap.green))?;
+        syscalls::utils::copy_from_user(proc, user.blue as usize, channel_bytes(&mut colormap.blue))?;
+
+        if let Some(transp) = &mut colormap.transp
+        {
+            syscalls::utils::copy_from_user(proc, user.transp as usize, channel_bytes(transp))?;
+        }
+    }
+
+    // Safety: The reference only needs to live until the ioctl returns, which
+    // happens before the colormap is dropped
+    let response = unsafe { (&mut colormap as *mut FramebufferColormap).as_mut().unwrap() };
+
+    let command = if cmd == FBIOPUTCMAP
+    {
+        IOControlCommand::FrameBufferPutColormap { response }
+    }
+    else
+    {
+        IOControlCommand::FrameBufferGetColormap { response }
+    };
+
+    let result = proc.exec_ioctl(fd, command);
+
+    if cmd == FBIOGETCMAP && result == 0
+    {
+        syscalls::utils::copy_to_user(proc, user.red as usize, channel_bytes(&mut colormap.red))?;
+        syscalls::utils::copy_to_user(proc, user.green as usize, channel_bytes(&mut colormap.green))?;
+        syscalls::utils::copy_to_user(proc, user.blue as usize, channel_bytes(&mut colormap.blue))?;
+
+        if let Some(transp) = &mut colormap.transp
+        {
+            syscalls::utils::copy_to_user(proc, user.transp as usize, channel_bytes(transp))?;
+        }
+    }
+
+    Ok(result)
+}
+
+/// Copy a font file from userspace to be loaded into the console
+fn read_font_file(proc: &mut Process, args: usize) -> Result<Vec<u8>, usize>
+{
+    let file: ConsoleFontFile = UserPtr::new(args).read(proc)?;
+
+    if file.length as usize > MAX_FONT_FILE_LENGTH
+    {
+        return Err(errno::EINVAL);
+    }
+
+    let mut data = vec![0; file.length as usize];
+    syscalls::utils::copy_from_user(proc, file.data as usize, &mut data)?;
+
+    Ok(data)
+}
+
+/// Handle the framebuffer ioctls
+pub fn framebuffer_ioctl(proc: &mut Process, fd: usize, cmd: usize, args: usize) -> Result<Option<usize>, usize>
+{
+    let command = match cmd
+    {
+        FBIOGET_VSCREENINFO =>
+        {
+            IOControlCommand::FrameBufferGetVariableInfo{ response: UserPtr::new(args).as_mut(proc)? }
+        },
+        FBIOPUT_VSCREENINFO =>
+        {
+            IOControlCommand::FrameBufferPutVariableInfo{ response: UserPtr::new(args).as_mut(proc)? }
+        },
+        FBIOGET_FSCREENINFO =>
+        {
+            IOControlCommand::FrameBufferGetFixedInfo{ response: UserPtr::new(args).as_mut(proc)? }
+        },
+        FBIOGETCMAP | FBIOPUTCMAP =>
+        {
+            return colormap_ioctl(proc, fd, cmd, args).map(Some);
+        },
+        FBIOPAN_DISPLAY =>
+        {
+            IOControlCommand::FrameBufferPanDisplay{ response: UserPtr::new(args).as_mut(proc)? }
+        },
+        FB_FLUSH =>
+        {
+            IOControlCommand::FrameBufferFlush
+        },
+        FB_GET_MODE =>
+        {
+            IOControlCommand::FrameBufferGetMode{ response: UserPtr::new(args).as_mut(proc)? }
+        },
+        FB_SET_MODE =>
+        {
+            IOControlCommand::FrameBufferSetMode{ response: UserPtr::new(args).as_mut(proc)?, pid: proc.pid }
+        },
+        FB_PAGE_FLIP =>
+        {
+            IOControlCommand::FrameBufferPageFlip{ buffer: args, pid: proc.pid }
+        },
+        _ => return Ok(None)
+    };
+
+    Ok(Some(proc.exec_ioctl(fd, command)))
+}
+
+/// Handle the console ioctls, covering both the display and the keyboard of
+/// the console
+pub fn console_ioctl(proc: &mut Process, fd: usize, cmd: usize, args: usize) -> Result<Option<usize>, usize>
+{
+    let command = match cmd
+    {
+        // Display
+        CONSOLE_LOAD_FONT =>
+        {
+            IOControlCommand::ConsoleLoadFont{ data: read_font_file(proc, args)? }
+        },
+        CONSOLE_SET_CURSOR =>
+        {
+            IOControlCommand::ConsoleSetCursorVisible{ visible: args != 0 }
+        },
+        CONSOLE_GET_CURSOR =>
+        {
+            IOControlCommand::ConsoleGetCursor{ response: UserPtr::new(args).as_mut(proc)? }
+        },
+        KDSETMODE =>
+        {
+            IOControlCommand::ConsoleSetMode{ mode: args, pid: proc.pid }
+        },
+        KDGETMODE =>
+        {
+            IOControlCommand::ConsoleGetMode{ response: UserPtr::new(args).as_mut(proc)? }
+        },
+
+        // Keyboard
+        KDGETLED =>
+        {
+            IOControlCommand::KeyboardGetLeds{ response: UserPtr::new(args).as_mut(proc)? }
+        },
+        KDSETLED =>
+        {
+            IOControlCommand::KeyboardSetLeds{ leds: args }
+        },
+        KDGKBMODE =>
+        {
+            IOControlCommand::KeyboardGetMode{ response: UserPtr::new(args).as_mut(proc)? }
+        },
+        KDSKBMODE =>
+        {
+            IOControlCommand::KeyboardSetMode{ mode: args }
+        },
+        KDGKBENT =>
+        {
+            IOControlCommand::KeyboardGetEntry{ response: UserPtr::new(args).as_mut(proc)? }
+        },
+        KDSKBENT =>
+        {
+            IOControlCommand::KeyboardSetEntry{ response: UserPtr::new(args).as_mut(proc)? }
+        },
+        _ => return Ok(None)
+    };
+
+    Ok(Some(proc.exec_ioctl(fd, command)))
+}
@@ -1,13 +1,25 @@
 use crate::*;
 
+pub mod cursor;
+
 pub mod driver;
 pub use driver::*;
 
+pub mod ioctl;
+
 pub mod structs;
 
+use crate::drivers::timer::KernelTime;
+
 // Global Graphics Driver
 static mut GLOBAL_GRAPHICS_DRIVER: Option<GenericGraphics> = None;
 
+// Milliseconds between refreshes of the text console
+const CONSOLE_REFRESH_INTERVAL: usize = 16;
+
+// Time the text console is next refreshed
+static mut NEXT_CONSOLE_REFRESH: KernelTime = KernelTime(0);
+
 /// Initialize the graphics driver
 pub fn init_graphics_driver() -> bool
 {
@@ -48,4 +60,78 @@ pub fn get_global_graphics_driver() -> &'static mut GenericGraphics
 pub fn is_graphics_driver_loaded() -> bool
 {
     unsafe { &GLOBAL_GRAPHICS_DRIVER }.is_some()
+}
+
+/// Flush changes to the text console once the refresh interval has passed,
+/// called from the timer interrupt in place of a vertical sync
+pub fn refresh_console()
+{
+    if let Some(driver) = unsafe { &mut GLOBAL_GRAPHICS_DRIVER }
+    {
+        let now = unsafe { &drivers::TIMER_DRIVER }.time();
+
+        if now >= unsafe { NEXT_CONSOLE_REFRESH }
+        {
+            unsafe { NEXT_CONSOLE_REFRESH = now + KernelTime::milliseconds(CONSOLE_REFRESH_INTERVAL) };
+
+            driver.refresh();
+        }
+    }
+}
+
+/// Hand the display back to the text console if it was taken over by the
+/// given process, called when a process exits
+pub fn release_display(pid: crate::process::PID)
+{
+    if let Some(driver) = unsafe { &mut GLOBAL_GRAPHICS_DRIVER }
+    {
+        if driver.display_owner() == Some(pid)
+        {
+            driver.release_display();
+        }
+    }
+}
+
+/// Move the console cursor by the motion reported by a mouse
+pub fn handle_pointer_motion(dx: isize, dy: isize)
+{
+    if let Some(driver) = unsafe { &mut GLOBAL_GRAPHICS_DRIVER }
+    {
+        let (x, y) = driver.cursor().position;
+        driver.move_cursor(x as isize + dx, y as isize + dy);
+    }
+}
+
+/// Move the console cursor to the position reported by a tablet, each axis
+/// is given from zero to `range`
+pub fn handle_pointer_position(x: Option<usize>, y: Option<usize>, range: usize)
+{
+    if let Some(driver) = unsafe { &mut GLOBAL_GRAPHICS_DRIVER }
+    {
+        let (width, height) = driver.display_size();
+        let (old_x, old_y) = driver.cursor().position;
+
+        let x = x.map(|x| x * width / (range + 1)).unwrap_or(old_x);
+        let y = y.map(|y| y * height / (range + 1)).unwrap_or(old_y);
+
+        driver.move_cursor(x as isize, y as isize);
+    }
+}
+
+/// Record a button of the pointer being pressed or released
+pub fn handle_pointer_button(button: usize, pressed: bool)
+{
+    if let Some(driver) = unsafe { &mut GLOBAL_GRAPHICS_DRIVER }
+    {
+        let cursor = driver.cursor();
+
+        if pressed
+        {
+            cursor.buttons |= 1 << button;
+        }
+        else
+        {
+            cursor.buttons &= !(1 << button);
+        }
+    }
 }
\ No newline at end of file
@@ -1,3 +1,5 @@
+use crate::*;
+
 /// Framebuffer Fixed Screen Info Structure
 #[repr(C)]
 #[derive(Debug, Clone, Copy)]
@@ -63,4 +65,75 @@ pub struct FramebufferVariableInfo
     pub obsolete_flags: u32,
 
     pub unused_timing: [u32; 15]
-}
\ No newline at end of file
+}
+
+/// Display mode used by the Qor specific mode setting and page flip ioctls,
+/// the framebuffer holds `buffer_count` screens stacked vertically of which
+/// `front_buffer` is shown
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FramebufferMode
+{
+    pub width: u32,
+    pub height: u32,
+    pub pitch: u32,
+    pub bits_per_pixel: u32,
+    pub buffer_count: u32,
+    pub front_buffer: u32
+}
+
+/// Framebuffer Colormap Structure as passed from userspace, the channels are
+/// pointers to arrays of `len` entries, transp may be null
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct FramebufferUserColormap
+{
+    pub start: u32,
+    pub len: u32,
+    pub red: u64,
+    pub green: u64,
+    pub blue: u64,
+    pub transp: u64
+}
+
+/// Framebuffer Colormap entries copied into the kernel
+#[derive(Debug, Clone)]
+pub struct FramebufferColormap
+{
+    pub start: usize,
+    pub red: Vec<u16>,
+    pub green: Vec<u16>,
+    pub blue: Vec<u16>,
+    pub transp: Option<Vec<u16>>
+}
+
+impl FramebufferColormap
+{
+    /// Create a new colormap of zeroed entries
+    pub fn new(start: usize, len: usize, transp: bool) -> Self
+    {
+        Self
+        {
+            start,
+            red: vec![0; len],
+            green: vec![0; len],
+            blue: vec![0; len],
+            transp: if transp { Some(vec![0; len]) } else { None }
+        }
+    }
+
+    /// Get the number of entries in the colormap
+    pub fn len(&self) -> usize
+    {
+        self.red.len()
+    }
+}
+
+/// Console font file passed from userspace
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct ConsoleFontFile
+{
+    pub data: u64,
+    pub length: u64
+}
@@ -0,0 +1,96 @@
+//! Default Keymap
+//!
+//! US layout used until userspace loads another. Keys are given by their
+//! input event codes, and each entry is a key type in the high byte and a
+//! value in the low byte, as in the Linux console.
+
+use super::*;
+
+// Characters produced by the keys up to the space bar, zero for keys which
+// do not produce a character
+const PLAIN_KEYS: &[u8; 58] = b"\0\x1b1234567890-=\x7f\tqwertyuiop[]\r\0asdfghjkl;'`\0\\zxcvbnm,./\0*\0 ";
+const SHIFT_KEYS: &[u8; 58] = b"\0\x1b!@#$%^&*()_+\x7f\tQWERTYUIOP{}\r\0ASDFGHJKL:\"~\0|ZXCVBNM<>?\0*\0 ";
+
+// Key codes of the keys which are not characters
+const KEY_LEFTCTRL: usize = 29;
+const KEY_LEFTSHIFT: usize = 42;
+const KEY_RIGHTSHIFT: usize = 54;
+const KEY_LEFTALT: usize = 56;
+const KEY_CAPSLOCK: usize = 58;
+const KEY_RIGHTCTRL: usize = 97;
+const KEY_RIGHTALT: usize = 100;
+const KEY_UP: usize = 103;
+const KEY_LEFT: usize = 105;
+const KEY_RIGHT: usize = 106;
+const KEY_DOWN: usize = 108;
+
+/// Build a keymap entry
+pub const fn key(kind: u16, value: u16) -> u16
+{
+    (kind << 8) | value
+}
+
+/// Build the default keymap
+pub fn default_keymap() -> Keymap
+{
+    let mut keymap = [[K_HOLE; NR_KEYS]; KEYMAP_COUNT];
+
+    for (table, entries) in keymap.iter_mut().enumerate()
+    {
+        let shift = table & (1 << KG_SHIFT) != 0;
+        let ctrl = table & (1 << KG_CTRL) != 0;
+        let alt = table & (1 << KG_ALT) != 0;
+
+        // AltGr produces nothing on a US keyboard
+        if table & (1 << KG_ALTGR) != 0
+        {
+            continue;
+        }
+
+        for code in 0..PLAIN_KEYS.len()
+        {
+            let c = if shift { SHIFT_KEYS[code] } else { PLAIN_KEYS[code] } as u16;
+
+            if c == 0
+            {
+                continue;
+            }
+
+            entries[code] = if ctrl && (c as u8).is_ascii_alphabetic()
+            {
+                key(KT_LATIN, c & 0x1F)
+            }
+            else if alt
+            {
+                key(KT_META, c)
+            }
+            else if (c as u8).is_ascii_alphabetic()
+            {
+                key(KT_LETTER, c)
+            }
+            else
+            {
+                key(KT_LATIN, c)
+            };
+        }
+    }
+
+    // Modifiers and locks are the same in every table
+    for entries in keymap.iter_mut()
+    {
+        entries[KEY_LEFTSHIFT] = key(KT_SHIFT, KG_SHIFT);
+        entries[KEY_RIGHTSHIFT] = key(KT_SHIFT, KG_SHIFT);
+        entries[KEY_LEFTCTRL] = key(KT_SHIFT, KG_CTRL);
+        entries[KEY_RIGHTCTRL] = key(KT_SHIFT, KG_CTRL);
+        entries[KEY_LEFTALT] = key(KT_SHIFT, KG_ALT);
+        entries[KEY_RIGHTALT] = key(KT_SHIFT, KG_ALTGR);
+        entries[KEY_CAPSLOCK] = K_CAPS;
+
+        entries[KEY_DOWN] = key(KT_CUR, 0);
+        entries[KEY_LEFT] = key(KT_CUR, 1);
+        entries[KEY_RIGHT] = key(KT_CUR, 2);
+        entries[KEY_UP] = key(KT_CUR, 3);
+    }
+
+    keymap
+}
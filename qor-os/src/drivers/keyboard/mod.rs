@@ -0,0 +1,269 @@
+//! Console Keyboard
+//!
+//! Key events from input devices are translated through the keymap into the
+//! bytes passed to the console tty. Userspace can switch the keyboard to raw
+//! mode, replace entries of the keymap to load another layout, and set the
+//! keyboard LEDs through ioctls on the console.
+
+use crate::*;
+
+use crate::fs::devfs::tty::TeletypeDevice;
+use crate::fs::ioctl::IOControlCommand;
+
+pub mod keymap;
+
+// Must be kept in sync with syscalls.h
+// Keyboard modes
+pub const K_RAW: usize =       0x00;
+pub const K_XLATE: usize =     0x01;
+pub const K_MEDIUMRAW: usize = 0x02;
+pub const K_UNICODE: usize =   0x03;
+pub const K_OFF: usize =       0x04;
+
+// LED flags
+pub const LED_SCR: u8 = 0x01;
+pub const LED_NUM: u8 = 0x02;
+pub const LED_CAP: u8 = 0x04;
+
+// Key types
+pub const KT_LATIN: u16 =  0;
+pub const KT_SPEC: u16 =   2;
+pub const KT_CUR: u16 =    6;
+pub const KT_SHIFT: u16 =  7;
+pub const KT_META: u16 =   8;
+pub const KT_LETTER: u16 = 11;
+
+// Modifiers, the table of the keymap used is chosen by the modifiers held
+pub const KG_SHIFT: u16 = 0;
+pub const KG_ALTGR: u16 = 1;
+pub const KG_CTRL: u16 =  2;
+pub const KG_ALT: u16 =   3;
+
+// Special keys
+pub const K_HOLE: u16 = keymap::key(KT_SPEC, 0);
+pub const K_ENTER: u16 = keymap::key(KT_SPEC, 1);
+pub const K_CAPS: u16 = keymap::key(KT_SPEC, 7);
+
+// Number of keys in each table of the keymap
+pub const NR_KEYS: usize = 128;
+
+// Number of tables in the keymap, one for each combination of modifiers
+pub const KEYMAP_COUNT: usize = 16;
+
+/// Keymap, indexed by the table and then the key code
+pub type Keymap = [[u16; NR_KEYS]; KEYMAP_COUNT];
+
+/// Keymap entry passed to the keymap ioctls
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct KeyboardEntry
+{
+    pub table: u8,
+    pub index: u8,
+    pub value: u16
+}
+
+/// Console keyboard state
+pub struct Keyboard
+{
+    mode: usize,
+    keymap: Keymap,
+    modifiers_down: [u8; 4],
+    caps_lock: bool,
+    leds: u8
+}
+
+impl Keyboard
+{
+    /// Create a new keyboard with the default keymap
+    pub fn new() -> Self
+    {
+        Self
+        {
+            mode: K_XLATE,
+            keymap: keymap::default_keymap(),
+            modifiers_down: [0; 4],
+            caps_lock: false,
+            leds: 0
+        }
+    }
+
+    /// Return the keyboard to translating keys for the console, used when a
+    /// program which took over the display exits
+    pub fn reset_mode(&mut self)
+    {
+        self.mode = K_XLATE;
+        self.modifiers_down = [0; 4];
+    }
+
+    /// Get the keymap table for the modifiers currently held
+    fn table(&self) -> usize
+    {
+        (0..4).filter(|m| self.modifiers_down[*m] > 0).fold(0, |acc, m| acc | (1 << m))
+    }
+
+    /// Pass bytes to the console tty
+    fn push_bytes(&mut self, bytes: &[u8])
+    {
+        for byte in bytes
+        {
+            drivers::get_uart_driver().tty_push_byte(*byte);
+        }
+    }
+
+    /// Update the LEDs on the input devices
+    fn update_leds(&mut self)
+    {
+        drivers::virtio::set_input_leds(self.leds);
+    }
+
+    /// Handle a key event from an input device, `value` is zero when the key
+    /// is released, one when it is pressed and two when it repeats
+    pub fn handle_key(&mut self, code: u16, value: u32)
+    {
+        let code = code as usize;
+
+        if code >= NR_KEYS
+        {
+            return;
+        }
+
+        match self.mode
+        {
+            K_RAW | K_MEDIUMRAW =>
+            {
+                let release = if value == 0 { 0x80 } else { 0 };
+                self.push_bytes(&[code as u8 | release]);
+            },
+            K_OFF => {},
+            _ => self.translate_key(code, value)
+        }
+    }
+
+    /// Translate a key through the keymap
+    fn translate_key(&mut self, code: usize, value: u32)
+    {
+        let table = self.table();
+        let entry = self.keymap[table][code];
+        let (kind, key) = (entry >> 8, entry & 0xFF);
+
+        if kind == KT_SHIFT
+        {
+            if key < 4 && value != 2
+            {
+                let count = &mut self.modifiers_down[key as usize];
+                *count = if value == 0 { count.saturating_sub(1) } else { *count + 1 };
+            }
+
+            return;
+        }
+
+        if value == 0
+        {
+            return;
+        }
+
+        match kind
+        {
+            KT_LATIN => self.push_bytes(&[key as u8]),
+            KT_LETTER =>
+            {
+                // Caps lock swaps to the table with shift toggled
+                let entry = if self.caps_lock { self.keymap[table ^ (1 << KG_SHIFT)][code] } else { entry };
+                self.push_bytes(&[entry as u8]);
+            },
+            KT_META => self.push_bytes(&[0x1B, key as u8]),
+            KT_CUR if key < 4 => self.push_bytes(&[0x1B, b'[', b"BDCA"[key as usize]]),
+            KT_SPEC if entry == K_ENTER => self.push_bytes(b"\r"),
+            KT_SPEC if entry == K_CAPS && value == 1 =>
+            {
+                self.caps_lock = !self.caps_lock;
+                self.leds ^= LED_CAP;
+                self.update_leds();
+            },
+            _ => {}
+        }
+    }
+
+    /// Execute an ioctl command for the keyboard
+    pub fn exec_ioctl(&mut self, ioctl: IOControlCommand) -> usize
+    {
+        match ioctl
+        {
+            IOControlCommand::KeyboardGetMode { response } =>
+            {
+                *response = self.mode as u32;
+
+                0
+            },
+            IOControlCommand::KeyboardSetMode { mode } =>
+            {
+                if mode > K_OFF
+                {
+                    return errno::EINVAL;
+                }
+
+                // Keys held while switching are not seen being released
+                self.modifiers_down = [0; 4];
+                self.mode = mode;
+
+                0
+            },
+            IOControlCommand::KeyboardGetEntry { response } =>
+            {
+                if response.table as usize >= KEYMAP_COUNT || response.index as usize >= NR_KEYS
+                {
+                    return errno::EINVAL;
+                }
+
+                response.value = self.keymap[response.table as usize][response.index as usize];
+
+                0
+            },
+            IOControlCommand::KeyboardSetEntry { response } =>
+            {
+                if response.table as usize >= KEYMAP_COUNT || response.index as usize >= NR_KEYS
+                {
+                    return errno::EINVAL;
+                }
+
+                // Keymaps written for the unicode console mark entries which
+                // are not unicode characters with 0xF000
+                let value = if response.value >> 12 == 0xF { response.value & 0xFFF } else { response.value };
+
+                self.keymap[response.table as usize][response.index as usize] = value;
+
+                0
+            },
+            IOControlCommand::KeyboardGetLeds { response } =>
+            {
+                *response = self.leds;
+
+                0
+            },
+            IOControlCommand::KeyboardSetLeds { leds } =>
+            {
+                if leds & !((LED_SCR | LED_NUM | LED_CAP) as usize) != 0
+                {
+                    return errno::EINVAL;
+                }
+
+                self.leds = leds as u8;
+                self.update_leds();
+
+                0
+            },
+            _ => usize::MAX
+        }
+    }
+}
+
+// Global Keyboard
+static mut GLOBAL_KEYBOARD: Option<Keyboard> = None;
+
+/// Get a reference to the global keyboard, creating it the first time it is
+/// used
+pub fn get_keyboard() -> &'static mut Keyboard
+{
+    unsafe { &mut GLOBAL_KEYBOARD }.get_or_insert_with(Keyboard::new)
+}
@@ -3,8 +3,10 @@
 use crate::*;
 
 // Modules for each driver
+pub mod block;
 pub mod generic;
 pub mod gpu;
+pub mod keyboard;
 pub mod mmio;
 pub mod plic;
 pub mod power;
@@ -47,6 +49,17 @@ pub fn init_plic_driver()
                                        drivers::plic::PLICPriority::Priority1) };
 }
 
+/// Register the ioctl commands of each device class, done once at boot as
+/// the handlers do not depend on which devices are present
+pub fn init_ioctl_classes()
+{
+    fs::ioctl::register_ioctl_class(gpu::ioctl::FRAMEBUFFER_IOCTL_TYPE, "fb", gpu::ioctl::framebuffer_ioctl);
+    fs::ioctl::register_ioctl_class(gpu::ioctl::CONSOLE_IOCTL_TYPE, "console", gpu::ioctl::console_ioctl);
+    fs::ioctl::register_ioctl_class(block::ioctl::BLOCK_IOCTL_TYPE, "block", block::ioctl::block_ioctl);
+    fs::ioctl::register_ioctl_class(rtc::ioctl::RTC_IOCTL_TYPE, "rtc", rtc::ioctl::rtc_ioctl);
+    fs::ioctl::register_ioctl_class(fs::devfs::tty_ioctl::TTY_IOCTL_TYPE, "tty", fs::devfs::tty_ioctl::tty_ioctl);
+}
+
 /// Get the UART driver
 pub fn get_uart_driver() -> &'static mut uart::UARTDriver
 {
@@ -1,4 +1,7 @@
-
+extern "C"
+{
+    fn asm_system_suspend(context: *mut crate::suspend::SuspendContext);
+}
 
 pub struct PowerDriver
 {
@@ -22,4 +25,21 @@ impl PowerDriver
             (self.base as *mut u32).write_volatile(0x5555);
         }
     }
-}
\ No newline at end of file
+
+    pub fn reboot(&self)
+    {
+        unsafe
+        {
+            (self.base as *mut u32).write_volatile(0x7777);
+        }
+    }
+
+    /// Suspend the hart until a device raises an interrupt, saving its state
+    /// to the context and restoring it from there on resume. The test device
+    /// QEMU provides has no suspend command, so memory stays powered while the
+    /// hart waits.
+    pub fn suspend(&self, context: &mut crate::suspend::SuspendContext)
+    {
+        unsafe { asm_system_suspend(context) };
+    }
+}
@@ -0,0 +1,35 @@
+//! Real time clock ioctls
+
+use crate::*;
+
+use fs::ioctl::IOControlCommand;
+use process::process::Process;
+use syscalls::args::UserPtr;
+
+// Type of the commands handled here
+pub const RTC_IOCTL_TYPE: u8 = b'p';
+
+// Must be kept in sync with syscalls.h
+const RTC_RD_TIME: usize = 0x7009;
+
+// Qor specific command to read the time as a timestamp
+const RTC_RD_TIMESTAMP: usize = 0x70FF;
+
+/// Handle the real time clock ioctls
+pub fn rtc_ioctl(proc: &mut Process, fd: usize, cmd: usize, args: usize) -> Result<Option<usize>, usize>
+{
+    let command = match cmd
+    {
+        RTC_RD_TIME =>
+        {
+            IOControlCommand::RealTimeClockGetTime{ response: UserPtr::new(args).as_mut(proc)? }
+        },
+        RTC_RD_TIMESTAMP =>
+        {
+            IOControlCommand::RealTimeClockGetTimestamp{ response: UserPtr::new(args).as_mut(proc)? }
+        },
+        _ => return Ok(None)
+    };
+
+    Ok(Some(proc.exec_ioctl(fd, command)))
+}
@@ -6,5 +6,7 @@ pub const LOCALIZATION_OFFSET: i64 = -5 * 3600;
 pub mod driver;
 pub use driver::*;
 
+pub mod ioctl;
+
 pub mod structs;
 pub use structs::*;
\ No newline at end of file
@@ -1,6 +1,9 @@
 // Frequency of the timer
 const FREQUENCY: usize = 10_000_000; 
 
+// Machine timer reading taken at boot
+static mut BOOT_TIME: KernelTime = KernelTime(0);
+
 /// Structure to store a time value
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub struct KernelTime(pub usize);
@@ -36,6 +39,12 @@ impl KernelTime
     {
         Self(nanoseconds * FREQUENCY / 1_000_000_000)
     }
+
+    /// Convert the Kernel Timing value to nanoseconds
+    pub fn as_nanoseconds(&self) -> usize
+    {
+        self.0 * (1_000_000_000 / FREQUENCY)
+    }
 }
 
 impl core::ops::Add for KernelTime
@@ -48,6 +57,17 @@ impl core::ops::Add for KernelTime
     }
 }
 
+impl core::ops::Sub for KernelTime
+{
+    type Output = KernelTime;
+
+    /// Times before the other time give zero
+    fn sub(self, rhs: Self) -> Self::Output
+    {
+        Self(self.0.saturating_sub(rhs.0))
+    }
+}
+
 /// Timer Driver
 pub struct TimerDriver
 {
@@ -113,9 +133,32 @@ impl TimerDriver
         self.interval = interval;
     }
 
+    /// Get the interval for the timer
+    pub fn get_interval(&self) -> KernelTime
+    {
+        self.interval
+    }
+
     /// Set the frequency of the timer
     pub fn set_frequency(&mut self, frequency: usize)
     {
         self.set_interval(KernelTime(FREQUENCY / frequency))
     }
-}
\ No newline at end of file
+}
+/// Record the time the system booted, this is called once early in kinit
+pub fn record_boot_time()
+{
+    unsafe { BOOT_TIME = crate::drivers::TIMER_DRIVER.time() };
+}
+
+/// Get the machine timer reading when the system booted
+pub fn boot_time() -> KernelTime
+{
+    unsafe { BOOT_TIME }
+}
+
+/// Get the time since the system booted
+pub fn uptime() -> KernelTime
+{
+    unsafe { &crate::drivers::TIMER_DRIVER }.time() - boot_time()
+}
@@ -55,6 +55,14 @@ unsafe fn read_byte(base: usize) -> Option<u8>
     }
 }
 
+/// Check if a break condition was recieved, this clears the condition
+/// Safety: if the base address is a vaild base address for an initialized UART
+/// driver, this will perform as expected.
+unsafe fn break_recieved(base: usize) -> bool
+{
+    mmio::read_offset::<u8>(base, 5) & (1 << 4) != 0
+}
+
 /// Write a byte to the UART port
 /// Safety: if the base address is a vaild base address for an initialized UART
 /// driver, this will perform as expected.
@@ -63,6 +71,10 @@ unsafe fn write_byte(base: usize, data: u8)
     mmio::write_offset::<u8>(base, 0, data);
 }
 
+// Escape sequence sent by a terminal for Ctrl+Alt+Delete, which requests an
+// orderly shutdown
+const SHUTDOWN_CHORD: &[u8] = b"\x1b[3;7~";
+
 /// MMIO UART Driver
 pub struct UARTDriver
 {
@@ -71,8 +83,11 @@ pub struct UARTDriver
     line_buffer: ByteRingBuffer,
     terminal_settings: crate::fs::devfs::tty::TeletypeSettings,
     fgpgid: PID,
+    session: PID,
     tty_paused: bool,
-    tty_preserve_next: bool
+    tty_preserve_next: bool,
+    chord_progress: usize,
+    sysrq_pending: bool
 }
 
 impl UARTDriver
@@ -89,8 +104,11 @@ impl UARTDriver
             line_buffer: ByteRingBuffer::new(),
             terminal_settings: crate::fs::devfs::tty::TeletypeSettings::new(),
             fgpgid: 0,
+            session: 0,
             tty_paused: false,
-            tty_preserve_next: false
+            tty_preserve_next: false,
+            chord_progress: 0,
+            sysrq_pending: false
         }
     }
 
@@ -110,9 +128,60 @@ impl UARTDriver
     {
         // Safety: Assuming the safety from the `new` implementation is
         // satisfied, this is safe
-        if let Some(byte) = unsafe { read_byte(self.base) }
+        // A break is followed by the SysRq key, neither is passed on to the
+        // terminal
+        if unsafe { break_recieved(self.base) }
+        {
+            unsafe { read_byte(self.base) };
+            self.sysrq_pending = true;
+        }
+        else if let Some(byte) = unsafe { read_byte(self.base) }
+        {
+            if self.sysrq_pending
+            {
+                self.sysrq_pending = false;
+                sysrq::handle_sysrq(byte, self.fgpgid);
+            }
+            else
+            {
+                self.check_shutdown_chord(byte);
+                self.tty_push_byte(byte);
+            }
+        }
+    }
+
+    /// Wait for a byte directly from the device, bypassing the terminal, for
+    /// use while interrupts are masked
+    pub fn poll_byte(&mut self) -> u8
+    {
+        loop
+        {
+            // Safety: Assuming the safety from the `new` implementation is
+            // satisfied, this is safe
+            if let Some(byte) = unsafe { read_byte(self.base) }
+            {
+                return byte;
+            }
+        }
+    }
+
+    /// Track the bytes of the shutdown key chord, requesting a shutdown once
+    /// the whole sequence has been recieved
+    fn check_shutdown_chord(&mut self, byte: u8)
+    {
+        if byte == SHUTDOWN_CHORD[self.chord_progress]
+        {
+            self.chord_progress += 1;
+        }
+        else
         {
-            self.tty_push_byte(byte);
+            self.chord_progress = if byte == SHUTDOWN_CHORD[0] { 1 } else { 0 };
+        }
+
+        if self.chord_progress == SHUTDOWN_CHORD.len()
+        {
+            self.chord_progress = 0;
+            halt::request_shutdown();
         }
     }
 }
@@ -277,6 +346,16 @@ impl crate::fs::devfs::tty::TeletypeDevice for UARTDriver
         self.fgpgid = pgid;
     }
 
+    fn get_session(&self) -> PID
+    {
+        self.session
+    }
+
+    fn set_session(&mut self, session: PID)
+    {
+        self.session = session;
+    }
+
     fn get_paused_state(&self) -> bool
     {
         self.tty_paused
@@ -203,6 +203,30 @@ pub fn initialize_virtio_devices()
                         }
                     }
                 },
+                VirtIODeviceType::InputDevice => 
+                {
+                    match driver.init_driver(!(1 << 5))
+                    {
+                        Err(e) =>
+                        {
+                            kprintln!("{}ERROR{}: `{}`", FMT_ERROR, FMT_CLEAR, e);
+                        },
+                        Ok(features) =>
+                        {
+                            let mut input_driver = super::drivers::input::InputDriver::new(driver);
+
+                            if let Err(e) = input_driver.device_specific(features)
+                            {
+                                kprintln!("{}ERROR{}: `{}`", FMT_ERROR, FMT_CLEAR, e);
+                            }
+                            else
+                            {
+                                kprintln!("{}OK{}", FMT_OK, FMT_CLEAR);
+                                devices.input_devices.push(input_driver);
+                            }
+                        }
+                    }
+                },
                 VirtIODeviceType::UnknownDevice => 
                 {
                     kprintln!("{}Unknown Device{}", FMT_WARN, FMT_CLEAR);
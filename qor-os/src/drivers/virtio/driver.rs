@@ -9,6 +9,9 @@ use crate::mem::PAGE_SIZE;
 
 use VirtIOMmioOffsets as Field;
 
+// Number of pages each queue takes up
+const QUEUE_PAGE_COUNT: usize = (core::mem::size_of::<VirtIOQueue>() + PAGE_SIZE - 1) / PAGE_SIZE;
+
 /// Generic VirtIO Device Driver
 pub struct VirtIODeviceDriver
 {
@@ -16,6 +19,7 @@ pub struct VirtIODeviceDriver
     device: VirtIOHelper,
     device_status: u32,
     driver_ready: bool,
+    accepted_features: u32,
     queues: Vec<*mut VirtIOQueue>,
     queue_aux_data: Vec<AuxQueueData>
 }
@@ -31,11 +35,19 @@ impl VirtIODeviceDriver
             device,
             device_status: 0,
             driver_ready: false,
+            accepted_features: 0,
             queues: Vec::new(),
             queue_aux_data: Vec::new()
         }
     }
 
+    /// Read a u32 from the device specific configuration space
+    pub fn read_config(&self, offset: usize) -> u32
+    {
+        // Safety: The helper holds a valid device base address
+        unsafe { crate::drivers::mmio::read_offset(self.device.base, Field::Config as usize + offset) }
+    }
+
     /// Finalize device initialization
     pub fn driver_ok(&mut self)
     {
@@ -51,6 +63,34 @@ impl VirtIODeviceDriver
         self.driver_ready = false;
     }
 
+    /// Check if the device has been initialized and is live
+    pub fn is_ready(&self) -> bool
+    {
+        self.driver_ready
+    }
+
+    /// Reset the device, it stops using its queues, which are then freed
+    pub fn reset(&mut self)
+    {
+        self.device_status = 0;
+        self.device.write_field(Field::Status, self.device_status);
+        self.driver_ready = false;
+
+        for queue in self.queues.drain(..)
+        {
+            crate::mem::kpfree(queue as usize, QUEUE_PAGE_COUNT).unwrap();
+        }
+
+        self.queue_aux_data.clear();
+    }
+
+    /// Negotiate the features of a reset device again, offering the features
+    /// accepted when the driver was first initialized
+    pub fn renegotiate(&mut self) -> Result<u32, String>
+    {
+        self.init_driver(self.accepted_features)
+    }
+
     /// If needed, verity the queue size with the device, for now, if the device
     /// does not accept our global queue size, we will simply fail, it may be
     /// possible in the future to have variable queue sizes, however, for now it
@@ -82,8 +122,7 @@ impl VirtIODeviceDriver
 
         for queue_index in 0..queue_count
         {
-            const PAGE_COUNT: usize = (core::mem::size_of::<VirtIOQueue>() + PAGE_SIZE - 1) / PAGE_SIZE;
-            let queue_location = crate::mem::kpalloc(PAGE_COUNT, "VirtIO Device Queue").unwrap() as *mut VirtIOQueue;
+            let queue_location = crate::mem::kpalloc(QUEUE_PAGE_COUNT, "VirtIO Device Queue").unwrap() as *mut VirtIOQueue;
 
             // Tell the device which queue we are telling it about
             self.device.write_field(Field::QueueSel, queue_index as u32);
@@ -128,14 +167,51 @@ impl VirtIODeviceDriver
     {
         let queue_ref = unsafe { self.queues[queue].as_mut().unwrap() };
 
-        // Insert the descriptor ptr into the queue
-        queue_ref.avail.ring[queue_ref.avail.idx as usize] = index as u16;
-        queue_ref.avail.idx = (queue_ref.avail.idx + 1) % VIRTIO_QUEUE_SIZE as u16;
+        // Insert the descriptor ptr into the queue, the index runs freely and
+        // wraps around the ring
+        queue_ref.avail.ring[queue_ref.avail.idx as usize % VIRTIO_QUEUE_SIZE as usize] = index as u16;
+        queue_ref.avail.idx = queue_ref.avail.idx.wrapping_add(1);
 
         // Notify the device
         self.device.write_field(Field::QueueNotify, queue as u32);
     }
 
+    /// Take the next entry the device has placed in the used ring of the
+    /// given queue, returning the index of the head descriptor and the number
+    /// of bytes the device wrote
+    pub fn pop_used(&mut self, queue: usize) -> Option<(usize, usize)>
+    {
+        let queue_ref = unsafe { self.queues[queue].as_mut().unwrap() };
+        let used_index = unsafe { core::ptr::read_volatile(&queue_ref.used.idx) };
+
+        let ack_index = self.queue_aux_data[queue].ack_index as u16;
+
+        if ack_index == used_index
+        {
+            return None;
+        }
+
+        let elem = &queue_ref.used.ring[ack_index as usize % VIRTIO_QUEUE_SIZE as usize];
+        let result = unsafe { (core::ptr::read_volatile(&elem.id) as usize, core::ptr::read_volatile(&elem.len) as usize) };
+
+        self.queue_aux_data[queue].ack_index = ack_index.wrapping_add(1) as usize;
+
+        Some(result)
+    }
+
+    /// Get a descriptor from one of the loaded queues
+    pub fn get_descriptor(&self, queue: usize, index: usize) -> VirtIODescriptor
+    {
+        unsafe { &*self.queues[queue] }.desc[index % VIRTIO_QUEUE_SIZE as usize]
+    }
+
+    /// Acknowledge the interrupts raised by the device
+    pub fn acknowledge_interrupt(&mut self)
+    {
+        let status = self.device.read_field(Field::InterruptStatus);
+        self.device.write_field(Field::InterruptAck, status);
+    }
+
     /// Add a VirtIODescriptor to one of the loaded queues
     pub fn add_descriptor_to_queue(&mut self, queue: usize, descriptor: VirtIODescriptor) -> usize
     {
@@ -233,6 +309,8 @@ impl VirtIODeviceDriver
     /// accepted
     pub fn init_driver(&mut self, accepted_features: u32) -> Result<u32, String>
     {
+        self.accepted_features = accepted_features;
+
         match self.wrapped_init(accepted_features)
         {
             Ok(v) => Ok(v),
@@ -1,5 +1,7 @@
 use crate::*;
 
+use crate::drivers::block::*;
+use crate::drivers::generic::PowerManagement;
 use crate::drivers::virtio::*;
 
 use super::structs::*;
@@ -108,14 +110,29 @@ impl BlockDriver
         self.block_operation(buffer, size, offset, true)
     }
 
-    // Generic function to sync with a request finishing
-    unsafe fn sync(request: *mut Request)
+    /// Get the capacity of the device in bytes
+    pub fn capacity(&self) -> usize
     {
-        while request.read_volatile().status.status == 111
+        // The capacity is given as a 64 bit count of sectors
+        let low = self.device.read_config(0) as usize;
+        let high = self.device.read_config(4) as usize;
+
+        ((high << 32) | low) * SECTOR_SIZE
+    }
+
+    // Generic function to sync with a request finishing, returning the status
+    // written by the device. Interrupts are taken while waiting, so a syscall
+    // waiting on the disk does not hold off the timer and other devices
+    unsafe fn sync(request: *mut Request) -> u8
+    {
+        trap::with_kernel_interrupts(||
         {
-        }
+            while request.read_volatile().status.status == 111
+            {
+            }
+        });
 
-        Box::from_raw(request);
+        Box::from_raw(request).status.status
     }
 
     pub fn sync_read(&mut self, buffer: *mut u8, size: u32, offset: u64)
@@ -127,4 +144,93 @@ impl BlockDriver
     {
         unsafe { Self::sync(self.write(buffer, size, offset).unwrap()) };
     }
+
+    /// Perform a block operation and wait for it to complete
+    fn checked_operation(&mut self, buffer: *mut u8, size: usize, offset: usize, write: bool) -> BlockDeviceResult<()>
+    {
+        Self::check_access(self, offset, size)?;
+
+        // A device detached when its filesystem was unmounted is attached
+        // again by the next filesystem to use it
+        if !self.device.is_ready()
+        {
+            self.resume().map_err(|e|
+            {
+                kerrorln!("Unable to attach block device: {}", e);
+                BlockDeviceError::DeviceError
+            })?;
+        }
+
+        let request = self.block_operation(buffer, size as u32, offset as u64, write).ok_or(BlockDeviceError::DeviceError)?;
+
+        if unsafe { Self::sync(request) } == VIRTIO_BLK_S_OK
+        {
+            Ok(())
+        }
+        else
+        {
+            Err(BlockDeviceError::DeviceError)
+        }
+    }
+}
+
+impl BlockDevice for BlockDriver
+{
+    fn block_size(&self) -> usize
+    {
+        SECTOR_SIZE
+    }
+
+    fn size(&self) -> usize
+    {
+        self.capacity()
+    }
+
+    fn read(&mut self, offset: usize, buffer: &mut [u8]) -> BlockDeviceResult<()>
+    {
+        self.checked_operation(buffer.as_mut_ptr(), buffer.len(), offset, false)
+    }
+
+    fn write(&mut self, offset: usize, buffer: &[u8]) -> BlockDeviceResult<()>
+    {
+        self.checked_operation(buffer.as_ptr() as *mut u8, buffer.len(), offset, true)
+    }
+
+    /// Requests are waited on as they are made, so there is never anything
+    /// left to flush
+    fn flush(&mut self) -> BlockDeviceResult<()>
+    {
+        Ok(())
+    }
+
+    fn remove(&mut self)
+    {
+        PowerManagement::remove(self)
+    }
+}
+
+/// Requests are waited on as they are made, so the queue is always empty by
+/// the time the device is reset
+impl PowerManagement for BlockDriver
+{
+    fn suspend(&mut self) -> Result<(), String>
+    {
+        self.device.reset();
+
+        Ok(())
+    }
+
+    fn resume(&mut self) -> Result<(), String>
+    {
+        let features = self.device.renegotiate()?;
+
+        self.device_specific(features)
+    }
+
+    fn remove(&mut self)
+    {
+        kdebugln!(BlockDevice, "Detaching block device");
+
+        self.device.reset();
+    }
 }
\ No newline at end of file
@@ -1,15 +1,25 @@
 use crate::*;
 
+use crate::drivers::generic::PowerManagement;
 use crate::drivers::virtio::*;
 
 use super::structs::*;
 use super::consts::*;
 
+// Size of the display
+const DISPLAY_WIDTH: usize = 640;
+const DISPLAY_HEIGHT: usize = 480;
+
+// The framebuffer is this many displays tall, so programs can draw off
+// screen and pan the display to flip between buffers
+const VIRTUAL_HEIGHT_FACTOR: usize = 2;
+
 /// VirtIO GPU Driver
 pub struct GPUDriver
 {
     pub device: VirtIODeviceDriver,
-    pub frame_buffer: Framebuffer
+    pub frame_buffer: Framebuffer,
+    pan_offset: (usize, usize)
 }
 
 impl GPUDriver
@@ -25,7 +35,8 @@ impl GPUDriver
         Self
         {
             device,
-            frame_buffer: Framebuffer::new(640, 480)
+            frame_buffer: Framebuffer::new(DISPLAY_WIDTH, DISPLAY_HEIGHT * VIRTUAL_HEIGHT_FACTOR),
+            pan_offset: (0, 0)
         }
     }
 
@@ -44,6 +55,12 @@ impl GPUDriver
     /// Send a request to the driver
     fn send_request<RqT, RpT: Default>(&mut self, rq: *mut Request<RqT, RpT>)
     {
+        // Nothing is sent to a device which has been reset for a suspend
+        if !self.device.is_ready()
+        {
+            return;
+        }
+
         let desc0 = VirtIODescriptor
         {
             addr: unsafe { &(*rq).request as *const RqT as u64 },
@@ -69,6 +86,12 @@ impl GPUDriver
     /// Send a request to the driver
     fn send_request3<RqT, RmT, RpT: Default>(&mut self, rq: *mut Request3<RqT, RmT, RpT>)
     {
+        // Nothing is sent to a device which has been reset for a suspend
+        if !self.device.is_ready()
+        {
+            return;
+        }
+
         let desc0 = VirtIODescriptor
         {
             addr: unsafe { &(*rq).request as *const RqT as u64 },
@@ -104,6 +127,7 @@ impl GPUDriver
     pub fn init(&mut self)
     {
         let (width, height) = self.frame_buffer.get_size();
+        let (display_width, display_height) = self.get_size();
 
         self.send_request(Request::<ResourceCreate2d, CtrlHeader>::new(ResourceCreate2d
             {
@@ -149,10 +173,49 @@ impl GPUDriver
                ctx_id: 0,
                padding: 0,
             },
-            r: Rect::new(0, 0, width as u32, height as u32),
+            r: Rect::new(0, 0, display_width as u32, display_height as u32),
+            resource_id: 1,
+            scanout_id: 0,
+        }));
+    }
+
+    /// Show the part of the framebuffer starting at the given offset on the
+    /// display, fails if the display would extend past the framebuffer
+    pub fn pan(&mut self, x: usize, y: usize) -> Result<(), ()>
+    {
+        let (width, height) = self.frame_buffer.get_size();
+        let (display_width, display_height) = self.get_size();
+
+        if x + display_width > width || y + display_height > height
+        {
+            return Err(());
+        }
+
+        self.send_request(Request::<SetScanout, CtrlHeader>::new(SetScanout {
+            hdr: CtrlHeader {
+               ctrl_type: CtrlType::CmdSetScanout,
+               flags: 0,
+               fence_id: 0,
+               ctx_id: 0,
+               padding: 0,
+            },
+            r: Rect::new(x as u32, y as u32, display_width as u32, display_height as u32),
             resource_id: 1,
             scanout_id: 0,
         }));
+
+        self.pan_offset = (x, y);
+
+        // Transfer the newly visible region
+        self.invalidate(x, y, display_width, display_height);
+
+        Ok(())
+    }
+
+    /// Get the offset of the displayed part of the framebuffer
+    pub fn get_pan_offset(&self) -> (usize, usize)
+    {
+        self.pan_offset
     }
 
     /// Invalidate and transfer part of the frame buffer
@@ -169,7 +232,7 @@ impl GPUDriver
                 padding: 0,
             },
             r: Rect::new(x as u32, y as u32, width as u32, height as u32),
-            offset: 0,
+            offset: ((y * self.frame_buffer.get_size().0 + x) * core::mem::size_of::<Pixel>()) as u64,
             resource_id: 1,
             padding: 0,
             }));
@@ -184,24 +247,24 @@ impl GPUDriver
                ctx_id: 0,
                padding: 0,
             },
-            r: Rect::new(0 as u32, 0 as u32, 640 as u32, 480 as u32),
+            r: Rect::new(self.pan_offset.0 as u32, self.pan_offset.1 as u32, DISPLAY_WIDTH as u32, DISPLAY_HEIGHT as u32),
             resource_id: 1,
             padding: 0,
          }));
     }
 
     /// Write a glpyh to part of the display
-    pub fn write_glpyh(&mut self, glyph: &[u16; 9], x: usize, y: usize, foreground: Pixel, background: Pixel)
+    pub fn write_glpyh(&mut self, font: &crate::resources::fonts::psf::Font, c: u8, x: usize, y: usize, foreground: Pixel, background: Pixel)
     {
-        let x_start = x * 9;
-        let y_start = y * 16;
+        let x_start = x * font.width;
+        let y_start = y * font.height;
 
-        for (i, col) in glyph.iter().enumerate()
+        for row in 0..font.height
         {
-            for row in 0..16
+            for col in 0..font.width
             {
-                *self.frame_buffer.pixel_mut(x_start + i, y_start + row) = 
-                    if col & (0b1000000000000000 >> row) > 0
+                *self.frame_buffer.pixel_mut(x_start + col, y_start + row) = 
+                    if font.pixel(c as usize, col, row)
                     {
                         foreground
                     }
@@ -215,7 +278,37 @@ impl GPUDriver
 
     /// Get the width and height of the display
     pub fn get_size(&self) -> (usize, usize)
+    {
+        (DISPLAY_WIDTH, DISPLAY_HEIGHT)
+    }
+
+    /// Get the width and height of the framebuffer, which is larger than the
+    /// display to allow panning
+    pub fn get_virtual_size(&self) -> (usize, usize)
     {
         self.frame_buffer.get_size()
     }
-}
\ No newline at end of file
+}
+
+/// The framebuffer is kept in memory across a suspend, and is given to the
+/// device again and redrawn on resume
+impl PowerManagement for GPUDriver
+{
+    fn suspend(&mut self) -> Result<(), String>
+    {
+        self.device.reset();
+
+        Ok(())
+    }
+
+    fn resume(&mut self) -> Result<(), String>
+    {
+        let features = self.device.renegotiate()?;
+        self.device_specific(features)?;
+
+        self.init();
+
+        let (x, y) = self.pan_offset;
+        self.pan(x, y).map_err(|_| String::from("Unable to restore the display offset"))
+    }
+}
@@ -93,6 +93,23 @@ impl Framebuffer
         (self.width, self.height)
     }
 
+    /// Move the rows of a band of the framebuffer up by `amount` rows, the
+    /// rows left at the bottom of the band keep their old contents
+    pub fn scroll_rows(&mut self, y: usize, height: usize, amount: usize)
+    {
+        assert!(y + height <= self.height);
+
+        if amount >= height
+        {
+            return;
+        }
+
+        unsafe
+        {
+            core::ptr::copy(self.data.add((y + amount) * self.width), self.data.add(y * self.width), (height - amount) * self.width);
+        }
+    }
+
     /// Get the pointer to the frame buffer
     pub fn get_pointer(&self) -> *mut Pixel
     {
@@ -0,0 +1,28 @@
+// Event types, from linux/input-event-codes.h
+pub const EV_SYN: u16 = 0x00;
+pub const EV_KEY: u16 = 0x01;
+pub const EV_REL: u16 = 0x02;
+pub const EV_ABS: u16 = 0x03;
+pub const EV_LED: u16 = 0x11;
+
+// Axis codes, for both relative and absolute events
+pub const AXIS_X: u16 = 0x00;
+pub const AXIS_Y: u16 = 0x01;
+
+// First and last mouse buttons, reported as key events
+pub const BTN_MOUSE: u16 = 0x110;
+pub const BTN_MOUSE_LAST: u16 = 0x117;
+
+// Largest value reported for an absolute axis by the QEMU tablet
+pub const ABS_RANGE: usize = 0x7FFF;
+
+// LED codes
+pub const LED_NUML: u16 = 0x00;
+pub const LED_CAPSL: u16 = 0x01;
+pub const LED_SCROLLL: u16 = 0x02;
+
+// Number of buffers kept available for the device to report events in
+pub const EVENT_BUFFER_COUNT: usize = 64;
+
+// Number of buffers used to send status events to the device
+pub const STATUS_BUFFER_COUNT: usize = 16;
@@ -0,0 +1,173 @@
+use crate::*;
+
+use crate::drivers::generic::PowerManagement;
+use crate::drivers::virtio::*;
+
+use super::structs::*;
+use super::consts::*;
+
+use crate::drivers::gpu;
+use crate::drivers::keyboard::{self, LED_CAP, LED_NUM, LED_SCR};
+
+/// VirtIO Input Driver
+pub struct InputDriver
+{
+    pub device: VirtIODeviceDriver,
+    events: *mut InputEvent,
+    status: *mut InputEvent,
+    status_index: usize
+}
+
+impl InputDriver
+{
+    /// Create a new input driver from a device driver
+    pub fn new(device: VirtIODeviceDriver) -> Self
+    {
+        if device.get_device_type() != VirtIODeviceType::InputDevice
+        {
+            panic!("Cannot create input device from {:?}", device.get_device_type());
+        }
+
+        Self
+        {
+            device,
+            events: 0 as *mut InputEvent,
+            status: 0 as *mut InputEvent,
+            status_index: 0
+        }
+    }
+
+    /// Perform the device specific initialization
+    pub fn device_specific(&mut self, _features: u32) -> Result<(), String>
+    {
+        self.device.verify_queue_size()?;
+
+        // The event queue followed by the status queue
+        self.device.init_queues(2)?;
+
+        // The buffers are kept when the device is initialized again on resume
+        if self.events.is_null()
+        {
+            self.events = crate::mem::kpzalloc(1, "VirtIO Input Events").map_err(|_| String::from("Unable to allocate event buffers"))? as *mut InputEvent;
+            self.status = crate::mem::kpzalloc(1, "VirtIO Input Status").map_err(|_| String::from("Unable to allocate status buffers"))? as *mut InputEvent;
+        }
+
+        self.status_index = 0;
+
+        for i in 0..EVENT_BUFFER_COUNT
+        {
+            self.queue_event_buffer(unsafe { self.events.add(i) });
+        }
+
+        self.device.driver_ok();
+
+        Ok(())
+    }
+
+    /// Give a buffer to the device to report an event in
+    fn queue_event_buffer(&mut self, buffer: *mut InputEvent)
+    {
+        let desc = VirtIODescriptor
+        {
+            addr: buffer as u64,
+            len: core::mem::size_of::<InputEvent>() as u32,
+            flags: VIRTIO_DESC_F_WRITE,
+            next: 0,
+        };
+
+        let head = self.device.add_descriptor_to_queue(0, desc);
+        self.device.send_on_queue(0, head);
+    }
+
+    /// Send a status event to the device
+    fn send_status(&mut self, event: InputEvent)
+    {
+        // Reclaim the buffers the device has finished reading
+        while self.device.pop_used(1).is_some() {}
+
+        let buffer = unsafe { self.status.add(self.status_index) };
+        self.status_index = (self.status_index + 1) % STATUS_BUFFER_COUNT;
+
+        unsafe { buffer.write(event) };
+
+        let desc = VirtIODescriptor
+        {
+            addr: buffer as u64,
+            len: core::mem::size_of::<InputEvent>() as u32,
+            flags: 0,
+            next: 0,
+        };
+
+        let head = self.device.add_descriptor_to_queue(1, desc);
+        self.device.send_on_queue(1, head);
+    }
+
+    /// Pass the events the device has reported on to the keyboard or the
+    /// console cursor, returning each buffer to the device once it has been
+    /// read
+    pub fn handle_events(&mut self)
+    {
+        if self.events.is_null() || !self.device.is_ready()
+        {
+            return;
+        }
+
+        self.device.acknowledge_interrupt();
+
+        while let Some((index, _)) = self.device.pop_used(0)
+        {
+            let buffer = self.device.get_descriptor(0, index).addr as *mut InputEvent;
+            let event = unsafe { buffer.read_volatile() };
+
+            match (event.event_type, event.code)
+            {
+                (EV_KEY, BTN_MOUSE..=BTN_MOUSE_LAST) => gpu::handle_pointer_button((event.code - BTN_MOUSE) as usize, event.value != 0),
+                (EV_KEY, _) => keyboard::get_keyboard().handle_key(event.code, event.value),
+                (EV_REL, AXIS_X) => gpu::handle_pointer_motion(event.value as i32 as isize, 0),
+                (EV_REL, AXIS_Y) => gpu::handle_pointer_motion(0, event.value as i32 as isize),
+                (EV_ABS, AXIS_X) => gpu::handle_pointer_position(Some(event.value as usize), None, ABS_RANGE),
+                (EV_ABS, AXIS_Y) => gpu::handle_pointer_position(None, Some(event.value as usize), ABS_RANGE),
+                _ => {}
+            }
+
+            self.queue_event_buffer(buffer);
+        }
+    }
+
+    /// Set the keyboard LEDs of the device, given as the console LED flags
+    pub fn set_leds(&mut self, leds: u8)
+    {
+        if self.status.is_null() || !self.device.is_ready()
+        {
+            return;
+        }
+
+        let lights = [(LED_SCROLLL, LED_SCR), (LED_NUML, LED_NUM), (LED_CAPSL, LED_CAP)];
+
+        for (code, flag) in lights
+        {
+            self.send_status(InputEvent { event_type: EV_LED, code, value: (leds & flag != 0) as u32 });
+        }
+
+        self.send_status(InputEvent { event_type: EV_SYN, code: 0, value: 0 });
+    }
+}
+
+/// The event buffers are handed to the device again as it is initialized on
+/// resume, so no events are lost beyond those sent while suspended
+impl PowerManagement for InputDriver
+{
+    fn suspend(&mut self) -> Result<(), String>
+    {
+        self.device.reset();
+
+        Ok(())
+    }
+
+    fn resume(&mut self) -> Result<(), String>
+    {
+        let features = self.device.renegotiate()?;
+
+        self.device_specific(features)
+    }
+}
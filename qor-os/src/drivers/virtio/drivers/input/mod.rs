@@ -0,0 +1,8 @@
+mod consts;
+pub use consts::*;
+
+mod driver;
+pub use driver::*;
+
+mod structs;
+pub use structs::*;
@@ -0,0 +1,9 @@
+/// Input event passed between the driver and the device
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct InputEvent
+{
+    pub event_type: u16,
+    pub code: u16,
+    pub value: u32
+}
@@ -1,2 +1,3 @@
 pub mod block;
-pub mod gpu;
\ No newline at end of file
+pub mod gpu;
+pub mod input;
\ No newline at end of file
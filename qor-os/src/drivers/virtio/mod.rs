@@ -1,3 +1,5 @@
+use crate::*;
+
 mod consts;
 pub use consts::*;
 
@@ -58,6 +60,57 @@ pub fn get_gpu_driver(index: usize) -> Option<&'static mut drivers::gpu::GPUDriv
 /// Interrupt handler for all VirtIO interrupts
 pub fn handle_interrupt(_interrupt: u32)
 {
+    // Input devices are checked for new events on any interrupt
+    if let Some(collection) = unsafe { &mut VIRTIO_DEVICE_COLLECTION }
+    {
+        for device in collection.input_devices.iter_mut()
+        {
+            device.handle_events();
+        }
+    }
+}
+
+/// Set the LEDs of every input device which has them
+pub fn set_input_leds(leds: u8)
+{
+    if let Some(collection) = unsafe { &mut VIRTIO_DEVICE_COLLECTION }
+    {
+        for device in collection.input_devices.iter_mut()
+        {
+            device.set_leds(leds);
+        }
+    }
+}
+
+/// Reset every VirtIO device before the system suspends
+pub fn suspend_devices()
+{
+    if let Some(collection) = unsafe { &mut VIRTIO_DEVICE_COLLECTION }
+    {
+        for device in collection.power_managed()
+        {
+            if let Err(e) = device.suspend()
+            {
+                kerrorln!("Unable to suspend VirtIO device: {}", e);
+            }
+        }
+    }
+}
+
+/// Initialize every VirtIO device again after the system resumes, in the
+/// reverse of the order they were suspended in
+pub fn resume_devices()
+{
+    if let Some(collection) = unsafe { &mut VIRTIO_DEVICE_COLLECTION }
+    {
+        for device in collection.power_managed().into_iter().rev()
+        {
+            if let Err(e) = device.resume()
+            {
+                kerrorln!("Unable to resume VirtIO device: {}", e);
+            }
+        }
+    }
 }
 
 /// Initialize the VirtIO interrupts
@@ -183,7 +183,8 @@ pub struct AuxQueueData
 pub struct DeviceCollection
 {
     pub block_devices: Vec<super::drivers::block::BlockDriver>,
-    pub gpu_devices: Vec<super::drivers::gpu::GPUDriver>
+    pub gpu_devices: Vec<super::drivers::gpu::GPUDriver>,
+    pub input_devices: Vec<super::drivers::input::InputDriver>
 }
 
 impl DeviceCollection
@@ -194,7 +195,22 @@ impl DeviceCollection
         Self
         {
             block_devices: Vec::new(),
-            gpu_devices: Vec::new()
+            gpu_devices: Vec::new(),
+            input_devices: Vec::new()
         }
     }
+
+    /// Get every device in the collection through its power management
+    /// callbacks, block devices come last so they are suspended once nothing
+    /// else is in use
+    pub fn power_managed(&mut self) -> Vec<&mut dyn crate::drivers::generic::PowerManagement>
+    {
+        let mut devices: Vec<&mut dyn crate::drivers::generic::PowerManagement> = Vec::new();
+
+        devices.extend(self.input_devices.iter_mut().map(|dev| dev as &mut dyn crate::drivers::generic::PowerManagement));
+        devices.extend(self.gpu_devices.iter_mut().map(|dev| dev as &mut dyn crate::drivers::generic::PowerManagement));
+        devices.extend(self.block_devices.iter_mut().map(|dev| dev as &mut dyn crate::drivers::generic::PowerManagement));
+
+        devices
+    }
 }
\ No newline at end of file
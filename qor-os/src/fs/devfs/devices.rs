@@ -17,6 +17,7 @@ pub enum DeviceDirectories
 }
 
 pub const PSUEDO_TERMINAL_FLAG: usize = 1 << (16 + 1);
+pub const PSEUDO_TERMINAL_MASTER_FLAG: usize = 1 << (16 + 2);
 
 impl core::fmt::Display for DeviceDirectories
 {
@@ -103,6 +104,27 @@ pub fn get_device_files() -> Vec<DeviceFile>
                     Box::new( |_| usize::MAX)
                 ));
 
+        // /dev/console : Text mode for the frame buffer, which also accepts
+        // ioctls to change the console font and the keyboard settings, and to
+        // hand the display over to a program
+        result.push(
+            DeviceFile::new(
+                "console",
+                Box::new(
+                    |inode| Box::new(
+                        ByteInterfaceDescriptor::new(drivers::gpu::get_global_graphics_driver(), inode)
+                    )),
+                    Box::new( |cmd|
+                        if cmd.is_keyboard_command()
+                        {
+                            drivers::keyboard::get_keyboard().exec_ioctl(cmd)
+                        }
+                        else
+                        {
+                            drivers::gpu::get_global_graphics_driver().exec_ioctl(cmd)
+                        })
+                ));
+
         // /dev/fb0 : Raw frame buffer access
         result.push(
             DeviceFile::new(
@@ -137,6 +159,16 @@ pub fn get_device_files() -> Vec<DeviceFile>
                 Box::new( |cmd| { drivers::get_uart_driver().exec_ioctl(cmd) } )
             ));
 
+    // /dev/ptmx : Creates a new pseudo terminal on every open
+    result.push(
+        DeviceFile::new(
+            "ptmx",
+            Box::new(
+                |inode| super::pty::open_pseudo_terminal_master(inode)
+                ),
+            Box::new( |_| usize::MAX)
+        ));
+
     // /dev/null : Null Descriptor
     result.push(
         DeviceFile::new(
@@ -148,6 +180,20 @@ pub fn get_device_files() -> Vec<DeviceFile>
             Box::new( |_| usize::MAX)
         ));
 
+    // /dev/ram0 onwards : RAM Disks
+    for (index, name) in drivers::block::RAM_DISK_NAMES.iter().enumerate().take(drivers::block::ram_disk_count())
+    {
+        result.push(
+            DeviceFile::new(
+                name,
+                Box::new(
+                    move |inode| Box::new(
+                        BufferDescriptor::new(drivers::block::get_ram_disk(index).unwrap(), inode)
+                    )),
+                Box::new( move |cmd| drivers::block::ram_disk_ioctl(index, cmd))
+            ));
+    }
+
     // TODO: This needs to respect the interrupt requirements of the RTC, however,
     // for right now we will just implement a null descriptor for it
     // /dev/rtc0 : Real Time Clock
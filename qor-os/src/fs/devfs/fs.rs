@@ -72,7 +72,7 @@ impl DevFilesystem
         // Specific cases for getting the entries in a directory
         if directory == DeviceDirectories::PseudoTerminalSecondaries
         {
-            for index in super::tty::get_open_pseudo_terminal_indexes()
+            for index in super::pty::get_open_pseudo_terminal_indexes()
             {
                 let dir_ent = DirectoryEntry
                 {
@@ -186,7 +186,7 @@ impl Filesystem for DevFilesystem
             {
                 Err(FilesystemError::INodeIsNotADirectory)
             }
-            else if inode.inode & PSUEDO_TERMINAL_FLAG > 0
+            else if inode.inode & (PSUEDO_TERMINAL_FLAG | PSEUDO_TERMINAL_MASTER_FLAG) > 0
             {
                 Err(FilesystemError::INodeIsNotADirectory)
             }
@@ -214,12 +214,12 @@ impl Filesystem for DevFilesystem
         todo!()
     }
 
-    fn create_file(&mut self, _inode: FilesystemIndex, _name: alloc::string::String) -> FilesystemResult<FilesystemIndex>
+    fn create_file(&mut self, _inode: FilesystemIndex, _name: alloc::string::String, _mode: u16) -> FilesystemResult<FilesystemIndex>
     {
         todo!()
     }
 
-    fn create_directory(&mut self, _inode: FilesystemIndex, _name: alloc::string::String) -> FilesystemResult<FilesystemIndex>
+    fn create_directory(&mut self, _inode: FilesystemIndex, _name: alloc::string::String, _mode: u16) -> FilesystemResult<FilesystemIndex>
     {
         todo!()
     }
@@ -248,12 +248,18 @@ impl Filesystem for DevFilesystem
         Err(FilesystemError::PermissionDenied)
     }
 
+    /// Add a directory entry for an existing inode to the directory at the given inode
+    fn add_dir_entry(&mut self, _directory_index: FilesystemIndex, _inode: FilesystemIndex, _name: String) -> FilesystemResult<()>
+    {
+        Err(FilesystemError::PermissionDenied)
+    }
+
     fn read_inode(&mut self, inode: FilesystemIndex) -> FilesystemResult<alloc::vec::Vec<u8>>
     {
         if Some(inode.mount_id) == self.mount_id
         {
             if inode.inode < 2 + self.directories.len() + self.devices.len() ||
-                inode.inode & PSUEDO_TERMINAL_FLAG > 0
+                inode.inode & (PSUEDO_TERMINAL_FLAG | PSEUDO_TERMINAL_MASTER_FLAG) > 0
             {
                 Ok(Vec::new())
             }
@@ -299,7 +305,13 @@ impl Filesystem for DevFilesystem
 
     fn mount_fs_at(&mut self, _inode: FilesystemIndex, _root: FilesystemIndex, _name: alloc::string::String) -> FilesystemResult<()>
     {
-        todo!()
+        Err(FilesystemError::PermissionDenied)
+    }
+
+    /// Remove a filesystem mounted at the given inode
+    fn unmount_fs_at(&mut self, _inode: FilesystemIndex, _root: FilesystemIndex) -> FilesystemResult<()>
+    {
+        Err(FilesystemError::NotAMountPoint)
     }
 
     /// Open a filedescriptor for the given inode
@@ -324,7 +336,7 @@ impl Filesystem for DevFilesystem
                         }
                         else if default & PSUEDO_TERMINAL_FLAG > 0
                         {
-                            super::tty::get_pseudo_terminal_secondary_file_descriptor(default & ((1 << 16) - 1), inode)
+                            super::pty::get_pseudo_terminal_secondary_file_descriptor(default & ((1 << 16) - 1), inode)
                         }
                         else
                         {
@@ -355,6 +367,10 @@ impl Filesystem for DevFilesystem
                 {
                     Ok(self.devices[inode.inode - 2 - self.directories.len()].exec_ioctl(cmd))
                 }
+                else if inode.inode & (PSUEDO_TERMINAL_FLAG | PSEUDO_TERMINAL_MASTER_FLAG) > 0
+                {
+                    super::pty::exec_pseudo_terminal_ioctl(inode.inode, cmd)
+                }
                 else
                 {
                     Err(FilesystemError::BadINode)
@@ -6,6 +6,8 @@ pub mod fs;
 pub use fs::*;
 
 mod devices;
+pub mod pty;
 pub mod tty;
+pub mod tty_ioctl;
 
 pub mod tty_consts;
\ No newline at end of file
@@ -0,0 +1,592 @@
+//! Pseudo Terminals
+//!
+//! Opening `/dev/ptmx` creates a new pseudo terminal and returns its master
+//! side, the secondary side then appears as `/dev/pts/N`. Bytes written to the
+//! master are passed through the line discipline as input to the secondary,
+//! and output written to the secondary is read from the master. In packet
+//! mode every read from the master starts with a status byte, so terminal
+//! emulators can track flushes and flow control on the secondary.
+
+use crate::*;
+use crate::fs::ioctl::IOControlCommand;
+use crate::utils::ByteRingBuffer;
+
+use alloc::collections::BTreeMap;
+
+use super::super::structures::*;
+use super::devices::{PSEUDO_TERMINAL_MASTER_FLAG, PSUEDO_TERMINAL_FLAG};
+use super::tty::{TeletypeDevice, TeletypeSettings};
+use super::tty_consts::*;
+
+use crate::process::{descriptor::*, PID};
+
+// Packet mode status bits
+// Must be kept in sync with syscalls.h
+pub const TIOCPKT_DATA: u8 = 0;
+pub const TIOCPKT_FLUSHREAD: u8 = 1;
+pub const TIOCPKT_FLUSHWRITE: u8 = 2;
+pub const TIOCPKT_STOP: u8 = 4;
+pub const TIOCPKT_START: u8 = 8;
+pub const TIOCPKT_NOSTOP: u8 = 16;
+pub const TIOCPKT_DOSTOP: u8 = 32;
+
+/// Pseudo Terminal
+pub struct PseudoTerminal
+{
+    index: usize,
+    input_buffer: ByteRingBuffer,
+    line_buffer: ByteRingBuffer,
+    output_buffer: ByteRingBuffer,
+    terminal_settings: TeletypeSettings,
+    fgpgid: PID,
+    session: PID,
+    tty_paused: bool,
+    tty_preserve_next: bool,
+    packet_mode: bool,
+    packet_status: u8,
+    master_open: bool,
+    secondaries_open: usize
+}
+
+impl PseudoTerminal
+{
+    /// Create a new pseudo terminal with the master side open
+    fn new(index: usize) -> Self
+    {
+        Self
+        {
+            index,
+            input_buffer: ByteRingBuffer::new(),
+            line_buffer: ByteRingBuffer::new(),
+            output_buffer: ByteRingBuffer::new(),
+            terminal_settings: TeletypeSettings::new(),
+            fgpgid: 0,
+            session: 0,
+            tty_paused: false,
+            tty_preserve_next: false,
+            packet_mode: false,
+            packet_status: 0,
+            master_open: true,
+            secondaries_open: 0
+        }
+    }
+
+    /// Record a change in the state of the secondary for the master to read
+    /// in packet mode
+    fn notify_packet(&mut self, status: u8)
+    {
+        if self.packet_mode
+        {
+            self.packet_status |= status;
+        }
+    }
+
+    /// Enable or disable packet mode, any pending status is discarded
+    pub fn set_packet_mode(&mut self, enabled: bool)
+    {
+        self.packet_mode = enabled;
+        self.packet_status = 0;
+    }
+
+    /// Read from the master side, in packet mode a pending status is returned
+    /// on its own, otherwise data is preceded by a `TIOCPKT_DATA` byte
+    fn master_read(&mut self, buffer: *mut u8, count: usize) -> usize
+    {
+        if count == 0
+        {
+            return 0;
+        }
+
+        let mut i = 0;
+
+        if self.packet_mode
+        {
+            if self.packet_status != 0
+            {
+                unsafe { buffer.write(self.packet_status) };
+                self.packet_status = 0;
+
+                return 1;
+            }
+
+            if self.output_buffer.is_empty()
+            {
+                return 0;
+            }
+
+            unsafe { buffer.write(TIOCPKT_DATA) };
+            i += 1;
+        }
+
+        while i < count
+        {
+            if let Some(byte) = self.output_buffer.dequeue_byte()
+            {
+                unsafe { buffer.add(i).write(byte) };
+                i += 1;
+            }
+            else
+            {
+                break;
+            }
+        }
+
+        i
+    }
+
+    /// Check if the master side has anything to read
+    fn master_available(&self) -> bool
+    {
+        !self.output_buffer.is_empty() || (self.packet_mode && self.packet_status != 0)
+    }
+
+    /// Execute an ioctl on the master side, the terminal settings can be
+    /// accessed from either side
+    fn master_ioctl(&mut self, cmd: IOControlCommand) -> usize
+    {
+        match cmd
+        {
+            IOControlCommand::TeletypeSetPacketMode { response } =>
+            {
+                self.set_packet_mode(*response != 0);
+                0
+            },
+            IOControlCommand::TeletypeGetPacketMode { response } =>
+            {
+                *response = self.packet_mode as i32;
+                0
+            },
+            IOControlCommand::TeletypeGetPseudoTerminalNumber { response } =>
+            {
+                *response = self.index as u32;
+                0
+            },
+            IOControlCommand::TeletypeGetProcessGroup { response, .. } =>
+            {
+                // The master side is not the controlling terminal of the
+                // session running on the terminal
+                *response = self.fgpgid;
+                0
+            },
+            default => self.exec_ioctl(default)
+        }
+    }
+}
+
+impl TeletypeDevice for PseudoTerminal
+{
+    fn tty_read_byte(&mut self) -> Option<u8>
+    {
+        if self.terminal_settings.local_flags & ICANON > 0
+        {
+            self.line_buffer.dequeue_byte()
+        }
+        else
+        {
+            self.input_buffer.dequeue_byte()
+        }
+    }
+
+    fn tty_write_byte(&mut self, byte: u8)
+    {
+        if self.terminal_settings.output_flags & OPOST > 0 && byte == 0xA
+        {
+            self.output_buffer.enqueue_byte(0x0D);
+        }
+
+        self.output_buffer.enqueue_byte(byte);
+    }
+
+    fn tty_close(&mut self)
+    {
+        self.secondaries_open -= 1;
+    }
+
+    fn tty_push_byte(&mut self, byte: u8)
+    {
+        let settings = self.get_tty_settings();
+
+        if self.handle_input(byte)
+        {
+            return;
+        }
+
+        if byte == 0xD && settings.input_flags & ICRNL > 0
+        {
+            self.input_buffer.enqueue_byte(0xA);
+        }
+        else
+        {
+            self.input_buffer.enqueue_byte(byte);
+        }
+
+        if settings.local_flags & ICANON > 0 && (byte == 0xD || byte == 0x4)
+        {
+            while let Some(b) = self.input_buffer.dequeue_byte()
+            {
+                self.line_buffer.enqueue_byte(b);
+            }
+        }
+    }
+
+    fn tty_pop_byte(&mut self) -> Option<u8>
+    {
+        self.output_buffer.dequeue_byte()
+    }
+
+    fn get_tty_settings(&self) -> TeletypeSettings
+    {
+        self.terminal_settings
+    }
+
+    fn set_tty_settings(&mut self, settings: TeletypeSettings)
+    {
+        // Let the master know if flow control was switched on or off
+        if settings.input_flags & IXON != self.terminal_settings.input_flags & IXON
+        {
+            self.notify_packet(if settings.input_flags & IXON > 0 { TIOCPKT_DOSTOP } else { TIOCPKT_NOSTOP });
+        }
+
+        self.terminal_settings = settings;
+    }
+
+    fn bytes_to_backaspace(&self) -> bool
+    {
+        !self.input_buffer.is_empty()
+    }
+
+    fn backspace(&mut self) -> bool
+    {
+        self.input_buffer.pop_byte().is_some()
+    }
+
+    fn bytes_available(&self) -> bool
+    {
+        if self.get_tty_settings().local_flags & ICANON > 0
+        {
+            !self.line_buffer.is_empty()
+        }
+        else
+        {
+            !self.input_buffer.is_empty()
+        }
+    }
+
+    fn flush_tty(&mut self)
+    {
+        while let Some(_) = self.input_buffer.pop_byte() {}
+        while let Some(_) = self.line_buffer.pop_byte() {}
+
+        self.notify_packet(TIOCPKT_FLUSHREAD);
+    }
+
+    fn flush_tty_output(&mut self)
+    {
+        while let Some(_) = self.output_buffer.dequeue_byte() {}
+
+        self.notify_packet(TIOCPKT_FLUSHWRITE);
+    }
+
+    fn get_foreground_process_group(&self) -> PID
+    {
+        self.fgpgid
+    }
+
+    fn set_foreground_process_group(&mut self, pgid: PID)
+    {
+        self.fgpgid = pgid;
+    }
+
+    fn get_session(&self) -> PID
+    {
+        self.session
+    }
+
+    fn set_session(&mut self, session: PID)
+    {
+        self.session = session;
+    }
+
+    fn get_paused_state(&self) -> bool
+    {
+        self.tty_paused
+    }
+
+    fn set_paused_state(&mut self, state: bool)
+    {
+        if state != self.tty_paused
+        {
+            self.notify_packet(if state { TIOCPKT_STOP } else { TIOCPKT_START });
+        }
+
+        self.tty_paused = state;
+    }
+
+    fn get_preserve_next_state(&self) -> bool
+    {
+        self.tty_preserve_next
+    }
+
+    fn set_preserve_next_state(&mut self, state: bool)
+    {
+        self.tty_preserve_next = state;
+    }
+}
+
+// Open pseudo terminals by index
+static mut PSEUDO_TERMINALS: BTreeMap<usize, PseudoTerminal> = BTreeMap::new();
+
+/// Get the pseudo terminal with the given index
+pub fn get_pseudo_terminal(index: usize) -> Option<&'static mut PseudoTerminal>
+{
+    unsafe { PSEUDO_TERMINALS.get_mut(&index) }
+}
+
+/// Remove a pseudo terminal once neither side is open
+fn release_pseudo_terminal(index: usize)
+{
+    if let Some(pty) = get_pseudo_terminal(index)
+    {
+        if !pty.master_open && pty.secondaries_open == 0
+        {
+            unsafe { PSEUDO_TERMINALS.remove(&index) };
+        }
+    }
+}
+
+/// Get the open pseudo terminal indexes
+pub fn get_open_pseudo_terminal_indexes() -> Vec<usize>
+{
+    unsafe { PSEUDO_TERMINALS.keys().cloned().collect() }
+}
+
+/// Create a new pseudo terminal, returning the descriptor for its master side,
+/// the inode is that of `/dev/ptmx`
+pub fn open_pseudo_terminal_master(inode: FilesystemIndex) -> Box<dyn FileDescriptor>
+{
+    let index = (0..).find(|i| get_pseudo_terminal(*i).is_none()).unwrap();
+
+    unsafe { PSEUDO_TERMINALS.insert(index, PseudoTerminal::new(index)) };
+
+    Box::new(PseudoTerminalMasterDescriptor
+    {
+        index,
+        inode: FilesystemIndex { mount_id: inode.mount_id, inode: PSEUDO_TERMINAL_MASTER_FLAG | index },
+        status_flags: 0
+    })
+}
+
+/// Get the file descriptor for the pseudo terminal secondary with the given
+/// index
+pub fn get_pseudo_terminal_secondary_file_descriptor(index: usize, inode: FilesystemIndex) -> FilesystemResult<Box<dyn FileDescriptor>>
+{
+    let pty = get_pseudo_terminal(index).ok_or(FilesystemError::BadINode)?;
+
+    pty.secondaries_open += 1;
+
+    Ok(Box::new(PseudoTerminalSecondaryDescriptor { index, inode, status_flags: 0 }))
+}
+
+/// Execute an ioctl on either side of a pseudo terminal, given the devfs
+/// inode number
+pub fn exec_pseudo_terminal_ioctl(inode: usize, cmd: IOControlCommand) -> FilesystemResult<usize>
+{
+    let pty = get_pseudo_terminal(inode & ((1 << 16) - 1)).ok_or(FilesystemError::BadINode)?;
+
+    if inode & PSEUDO_TERMINAL_MASTER_FLAG > 0
+    {
+        Ok(pty.master_ioctl(cmd))
+    }
+    else if inode & PSUEDO_TERMINAL_FLAG > 0
+    {
+        Ok(pty.exec_ioctl(cmd))
+    }
+    else
+    {
+        Err(FilesystemError::BadINode)
+    }
+}
+
+/// Master side of a pseudo terminal
+pub struct PseudoTerminalMasterDescriptor
+{
+    index: usize,
+    inode: FilesystemIndex,
+    status_flags: usize
+}
+
+impl FileDescriptor for PseudoTerminalMasterDescriptor
+{
+    fn close(&mut self, _: &mut fs::vfs::FilesystemInterface)
+    {
+        // Descriptors are shared between forked processes, so the terminal
+        // is only released once the last reference is dropped
+    }
+
+    fn write(&mut self, _: &mut fs::vfs::FilesystemInterface, buffer: *mut u8, count: usize) -> usize
+    {
+        if let Some(pty) = get_pseudo_terminal(self.index)
+        {
+            for i in 0..count
+            {
+                pty.tty_push_byte(unsafe { buffer.add(i).read() });
+            }
+
+            count
+        }
+        else
+        {
+            errno::EIO
+        }
+    }
+
+    fn read(&mut self, _: &mut fs::vfs::FilesystemInterface, buffer: *mut u8, count: usize) -> usize
+    {
+        if let Some(pty) = get_pseudo_terminal(self.index)
+        {
+            pty.master_read(buffer, count)
+        }
+        else
+        {
+            errno::EIO
+        }
+    }
+
+    fn get_inode(&mut self) -> Option<FilesystemIndex>
+    {
+        Some(self.inode)
+    }
+
+    fn check_available(&self) -> bool
+    {
+        get_pseudo_terminal(self.index).map(|pty| pty.master_available()).unwrap_or(true)
+    }
+
+    fn get_status_flags(&self) -> usize
+    {
+        self.status_flags
+    }
+
+    fn set_status_flags(&mut self, flags: usize)
+    {
+        self.status_flags = flags;
+    }
+}
+
+impl core::ops::Drop for PseudoTerminalMasterDescriptor
+{
+    fn drop(&mut self)
+    {
+        if let Some(pty) = get_pseudo_terminal(self.index)
+        {
+            pty.master_open = false;
+        }
+
+        release_pseudo_terminal(self.index);
+    }
+}
+
+/// Secondary side of a pseudo terminal
+pub struct PseudoTerminalSecondaryDescriptor
+{
+    index: usize,
+    inode: FilesystemIndex,
+    status_flags: usize
+}
+
+impl FileDescriptor for PseudoTerminalSecondaryDescriptor
+{
+    fn close(&mut self, _: &mut fs::vfs::FilesystemInterface)
+    {
+        // Descriptors are shared between forked processes, so the terminal
+        // is only released once the last reference is dropped
+    }
+
+    fn write(&mut self, _: &mut fs::vfs::FilesystemInterface, buffer: *mut u8, count: usize) -> usize
+    {
+        if let Some(pty) = get_pseudo_terminal(self.index)
+        {
+            for i in 0..count
+            {
+                pty.tty_write_byte(unsafe { buffer.add(i).read() });
+            }
+
+            count
+        }
+        else
+        {
+            errno::EIO
+        }
+    }
+
+    fn read(&mut self, _: &mut fs::vfs::FilesystemInterface, buffer: *mut u8, count: usize) -> usize
+    {
+        let pty = if let Some(pty) = get_pseudo_terminal(self.index) { pty } else { return errno::EIO };
+
+        let mut i = 0;
+
+        while i < count
+        {
+            if let Some(byte) = pty.tty_read_byte()
+            {
+                unsafe { buffer.add(i).write(byte) };
+                i += 1;
+            }
+            else
+            {
+                break;
+            }
+        }
+
+        i
+    }
+
+    fn get_inode(&mut self) -> Option<FilesystemIndex>
+    {
+        Some(self.inode)
+    }
+
+    fn check_available(&self) -> bool
+    {
+        // Once the master is closed reads return immediately
+        get_pseudo_terminal(self.index).map(|pty| pty.bytes_available() || !pty.master_open).unwrap_or(true)
+    }
+
+    fn check_writable(&self) -> bool
+    {
+        get_pseudo_terminal(self.index).map(|pty| !pty.tty_paused).unwrap_or(true)
+    }
+
+    fn is_terminal(&self) -> bool
+    {
+        true
+    }
+
+    fn get_terminal_foreground(&self) -> Option<PID>
+    {
+        get_pseudo_terminal(self.index).map(|pty| pty.get_foreground_process_group())
+    }
+
+    fn get_status_flags(&self) -> usize
+    {
+        self.status_flags
+    }
+
+    fn set_status_flags(&mut self, flags: usize)
+    {
+        self.status_flags = flags;
+    }
+}
+
+impl core::ops::Drop for PseudoTerminalSecondaryDescriptor
+{
+    fn drop(&mut self)
+    {
+        if let Some(pty) = get_pseudo_terminal(self.index)
+        {
+            pty.tty_close();
+        }
+
+        release_pseudo_terminal(self.index);
+    }
+}
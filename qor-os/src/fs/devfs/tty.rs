@@ -8,19 +8,6 @@ use crate::process::{descriptor::*, PID};
 
 use super::tty_consts::*;
 
-/// Get the file descriptor for the pseudo terminal secondary with the given
-/// index
-pub fn get_pseudo_terminal_secondary_file_descriptor(_index: usize, _inode: FilesystemIndex) -> FilesystemResult<Box<dyn crate::process::descriptor::FileDescriptor>>
-{
-    todo!()
-}
-
-/// Get the open pseudo terminal indexes
-pub fn get_open_pseudo_terminal_indexes() -> Vec<usize>
-{
-    vec![]
-}
-
 #[repr(C)]
 #[derive(Debug, Clone, Copy)]
 pub struct TeletypeSettings
@@ -71,6 +58,8 @@ pub trait TeletypeDevice
 
     fn handle_input(&mut self, byte: u8) -> bool
     {
+        crate::process::runqueue::note_terminal_input();
+
         let settings = self.get_tty_settings();
 
         if settings.local_flags & IEXTEN > 0 && self.get_preserve_next_state() && (settings.input_flags & IXON == 0 || !self.get_paused_state())
@@ -126,9 +115,9 @@ pub trait TeletypeDevice
                 if crate::process::scheduler::get_process_manager().as_mut().unwrap().send_signal_group(
                     self.get_foreground_process_group(),
                     0,
-                    POSIXSignal::new(0, 0, SignalType::SIGSTOP)).is_err()
+                    POSIXSignal::new(0, 0, SignalType::SIGTSTP)).is_err()
                 {
-                    kwarnln!("TTY Couldn't send SIGSTOP to PGID {}", self.get_foreground_process_group());
+                    kwarnln!("TTY Couldn't send SIGTSTP to PGID {}", self.get_foreground_process_group());
                 }
                 return true;
             }
@@ -174,9 +163,25 @@ pub trait TeletypeDevice
 
     fn flush_tty(&mut self);
 
+    /// Discard output which has been written but not yet sent
+    fn flush_tty_output(&mut self) {}
+
     fn get_foreground_process_group(&self) -> PID;
     fn set_foreground_process_group(&mut self, pgid: PID);
 
+    fn get_session(&self) -> PID;
+    fn set_session(&mut self, session: PID);
+
+    /// Check if the terminal can be controlled by a session, a terminal which
+    /// has not been taken as the controlling terminal of a session yet can be
+    /// used by any session
+    fn is_controlled_by(&self, session: PID) -> bool
+    {
+        let owner = self.get_session();
+
+        owner == 0 || owner == session
+    }
+
     fn get_paused_state(&self) -> bool;
     fn set_paused_state(&mut self, state: bool);
 
@@ -212,16 +217,104 @@ pub trait TeletypeDevice
                 self.set_tty_settings(*response);
                 0
             }
-            IOControlCommand::TeletypeGetProcessGroup {response } =>
+            IOControlCommand::TeletypeGetProcessGroup { response, session } =>
             {
+                if !self.is_controlled_by(session)
+                {
+                    return crate::errno::ENOTTY;
+                }
+
                 *response = self.get_foreground_process_group();
                 0
             }
-            IOControlCommand::TeletypeSetProcessGroup { response } => 
+            IOControlCommand::TeletypeSetProcessGroup { response, session } => 
             {
+                if !self.is_controlled_by(session)
+                {
+                    return crate::errno::ENOTTY;
+                }
+
+                // The first session to take over job control of a terminal
+                // makes it its controlling terminal
+                self.set_session(session);
                 self.set_foreground_process_group(*response);
                 0
             }
+            IOControlCommand::TeletypeGetSession { response, session } =>
+            {
+                if self.get_session() != session
+                {
+                    return crate::errno::ENOTTY;
+                }
+
+                *response = session;
+                0
+            }
+            IOControlCommand::TeletypeSetControllingTerminal { session, pgid, leader, steal } =>
+            {
+                if !leader
+                {
+                    return crate::errno::EPERM;
+                }
+
+                if self.get_session() == session
+                {
+                    return 0;
+                }
+
+                // A terminal controlled by another session can only be taken
+                // from it by a privileged process asking to steal it
+                if self.get_session() != 0 && !steal
+                {
+                    return crate::errno::EPERM;
+                }
+
+                self.set_session(session);
+                self.set_foreground_process_group(pgid);
+                0
+            }
+            IOControlCommand::TeletypeFlush { queue } =>
+            {
+                match queue
+                {
+                    TCIFLUSH => self.flush_tty(),
+                    TCOFLUSH => self.flush_tty_output(),
+                    TCIOFLUSH =>
+                    {
+                        self.flush_tty();
+                        self.flush_tty_output();
+                    },
+                    _ => return crate::errno::EINVAL
+                }
+
+                0
+            }
+            IOControlCommand::TeletypeFlowControl { action } =>
+            {
+                // Input is stopped and started by sending the STOP and START
+                // characters to the other end
+                match action
+                {
+                    TCOOFF => self.set_paused_state(true),
+                    TCOON => self.set_paused_state(false),
+                    TCIOFF => self.tty_write_byte(19),
+                    TCION => self.tty_write_byte(17),
+                    _ => return crate::errno::EINVAL
+                }
+
+                0
+            }
+            IOControlCommand::TeletypeInjectInput { byte, pgid, privileged } =>
+            {
+                // Only the foreground process group may fake input
+                if !privileged && pgid != self.get_foreground_process_group()
+                {
+                    return crate::errno::EPERM;
+                }
+
+                self.tty_push_byte(byte);
+                0
+            }
             _ => crate::errno::ENOIOCTLCMD
         }
     }
@@ -244,7 +337,8 @@ impl UARTTeletype
 pub struct TeletypeSecondaryDescriptor
 {
     teletype: &'static mut dyn TeletypeDevice,
-    inode: FilesystemIndex
+    inode: FilesystemIndex,
+    status_flags: usize
 }
 
 impl TeletypeSecondaryDescriptor
@@ -255,7 +349,8 @@ impl TeletypeSecondaryDescriptor
         Self
         {
             teletype,
-            inode
+            inode,
+            status_flags: 0
         }
     }
 }
@@ -306,6 +401,31 @@ impl FileDescriptor for TeletypeSecondaryDescriptor
     {
         self.teletype.bytes_available()
     }
+
+    fn check_writable(&self) -> bool
+    {
+        !self.teletype.get_paused_state()
+    }
+
+    fn is_terminal(&self) -> bool
+    {
+        true
+    }
+
+    fn get_terminal_foreground(&self) -> Option<PID>
+    {
+        Some(self.teletype.get_foreground_process_group())
+    }
+
+    fn get_status_flags(&self) -> usize
+    {
+        self.status_flags
+    }
+
+    fn set_status_flags(&mut self, flags: usize)
+    {
+        self.status_flags = flags;
+    }
 }
 
 impl core::ops::Drop for TeletypeSecondaryDescriptor
@@ -314,4 +434,4 @@ impl core::ops::Drop for TeletypeSecondaryDescriptor
     {
         unsafe { Box::from_raw(self.teletype as *mut dyn TeletypeDevice); }
     }
-}
\ No newline at end of file
+}
@@ -6,4 +6,13 @@ pub const IEXTEN: u32 = 0x00008000;
 pub const ICRNL: u32 = 0x00000100;
 pub const IXON: u32 = 0x00000400;
 
-pub const OPOST: u32 = 0x00000001;
\ No newline at end of file
+pub const OPOST: u32 = 0x00000001;
+
+pub const TCIFLUSH: usize = 0;
+pub const TCOFLUSH: usize = 1;
+pub const TCIOFLUSH: usize = 2;
+
+pub const TCOOFF: usize = 0;
+pub const TCOON: usize = 1;
+pub const TCIOFF: usize = 2;
+pub const TCION: usize = 3;
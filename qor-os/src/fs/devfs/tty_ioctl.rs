@@ -0,0 +1,121 @@
+//! Teletype ioctls
+
+use crate::*;
+
+use fs::ioctl::IOControlCommand;
+use process::process::{Process, ProcessState};
+use process::PID;
+use syscalls::args::UserPtr;
+
+// Type of the commands handled here
+pub const TTY_IOCTL_TYPE: u8 = b'T';
+
+// Must be kept in sync with syscalls.h
+const TCGETS: usize = 0x5401;
+const TCSETS: usize = 0x5402;
+const TCSETSW: usize = 0x5403;
+const TCSETSF: usize = 0x5404;
+const TIOCSCTTY: usize = 0x540E;
+const TIOCGPGRP: usize = 0x540F;
+const TCXONC: usize = 0x540A;
+const TCFLSH: usize = 0x540B;
+const TIOCSPGRP: usize = 0x5410;
+const TIOCSTI: usize = 0x5412;
+const TIOCPKT: usize = 0x5420;
+const TIOCGSID: usize = 0x5429;
+const TIOCGPTN: usize = 0x80045430;
+const TIOCGPKT: usize = 0x80045438;
+
+/// Check that a process group can be made the foreground process group of a
+/// terminal by a process, it must be a group in the session of the process
+fn check_process_group(proc: &Process, pgid: PID) -> Result<(), usize>
+{
+    if pgid == 0
+    {
+        return Err(errno::EINVAL);
+    }
+
+    let in_session = pgid == proc.data.process_group_id ||
+        process::scheduler::get_process_manager().unwrap().processes.values()
+            .any(|other| other.data.process_group_id == pgid && other.data.session_id == proc.data.session_id &&
+                !matches!(other.get_state(), ProcessState::Zombie | ProcessState::Dead));
+
+    if !in_session
+    {
+        return Err(errno::EPERM);
+    }
+
+    Ok(())
+}
+
+/// Handle the teletype ioctls
+pub fn tty_ioctl(proc: &mut Process, fd: usize, cmd: usize, args: usize) -> Result<Option<usize>, usize>
+{
+    let command = match cmd
+    {
+        TCGETS =>
+        {
+            IOControlCommand::TeletypeGetSettings{ response: UserPtr::new(args).as_mut(proc)? }
+        },
+        TCSETS =>
+        {
+            IOControlCommand::TeletypeSetSettingsNoWait{ response: UserPtr::new(args).as_mut(proc)? }
+        },
+        TCSETSW =>
+        {
+            IOControlCommand::TeletypeSetSettingsDrain{ response: UserPtr::new(args).as_mut(proc)? }
+        },
+        TCSETSF =>
+        {
+            IOControlCommand::TeletypeSetSettingsFlush{ response: UserPtr::new(args).as_mut(proc)? }
+        },
+        TIOCSCTTY =>
+        {
+            IOControlCommand::TeletypeSetControllingTerminal{ session: proc.data.session_id, pgid: proc.data.process_group_id,
+                leader: proc.data.session_id == proc.pid, steal: args == 1 && proc.is_privileged() }
+        },
+        TIOCGPGRP =>
+        {
+            IOControlCommand::TeletypeGetProcessGroup{ response: UserPtr::new(args).as_mut(proc)?, session: proc.data.session_id }
+        },
+        TIOCSPGRP =>
+        {
+            let response: &'static mut PID = UserPtr::new(args).as_mut(proc)?;
+            check_process_group(proc, *response)?;
+
+            IOControlCommand::TeletypeSetProcessGroup{ response, session: proc.data.session_id }
+        },
+        TIOCGSID =>
+        {
+            IOControlCommand::TeletypeGetSession{ response: UserPtr::new(args).as_mut(proc)?, session: proc.data.session_id }
+        },
+        TCXONC =>
+        {
+            IOControlCommand::TeletypeFlowControl{ action: args }
+        },
+        TCFLSH =>
+        {
+            IOControlCommand::TeletypeFlush{ queue: args }
+        },
+        TIOCSTI =>
+        {
+            let byte: u8 = UserPtr::new(args).read(proc)?;
+            IOControlCommand::TeletypeInjectInput{ byte, pgid: proc.data.process_group_id, privileged: proc.is_privileged() }
+        },
+        TIOCPKT =>
+        {
+            IOControlCommand::TeletypeSetPacketMode{ response: UserPtr::new(args).as_mut(proc)? }
+        },
+        TIOCGPTN =>
+        {
+            IOControlCommand::TeletypeGetPseudoTerminalNumber{ response: UserPtr::new(args).as_mut(proc)? }
+        },
+        TIOCGPKT =>
+        {
+            IOControlCommand::TeletypeGetPacketMode{ response: UserPtr::new(args).as_mut(proc)? }
+        },
+        _ => return Ok(None)
+    };
+
+    Ok(Some(proc.exec_ioctl(fd, command)))
+}
@@ -17,6 +17,20 @@ pub trait Filesystem
     /// Sync the filesystem with the current disk
     fn sync(&mut self) -> FilesystemResult<()>;
 
+    /// Write any changes to the given inode back to the disk, filesystems
+    /// which do not track changes per inode sync everything
+    fn fsync_inode(&mut self, _inode: FilesystemIndex) -> FilesystemResult<()>
+    {
+        self.sync()
+    }
+
+    /// Release the device behind the filesystem once it has been unmounted
+    /// and synced, filesystems without a device have nothing to release
+    fn detach(&mut self)
+    {
+
+    }
+
     /// Set the mount_id of the filesystem
     fn set_mount_id(&mut self, mount_id: usize, vfs: &'static mut crate::fs::vfs::FilesystemInterface);
 
@@ -35,11 +49,13 @@ pub trait Filesystem
     /// Get the directory entry for the given inode
     fn get_stat(&mut self, inode: FilesystemIndex) -> FilesystemResult<FileStat>;
 
-    /// Create a file in the directory at the given inode
-    fn create_file(&mut self, inode: FilesystemIndex, name: String) -> FilesystemResult<FilesystemIndex>;
+    /// Create a file with the given permission bits in the directory at the
+    /// given inode
+    fn create_file(&mut self, inode: FilesystemIndex, name: String, mode: u16) -> FilesystemResult<FilesystemIndex>;
 
-    /// Create a directory in the directory at the given inode
-    fn create_directory(&mut self, inode: FilesystemIndex, name: String) -> FilesystemResult<FilesystemIndex>;
+    /// Create a directory with the given permission bits in the directory at
+    /// the given inode
+    fn create_directory(&mut self, inode: FilesystemIndex, name: String, mode: u16) -> FilesystemResult<FilesystemIndex>;
 
     /// Remove an inode at the given index from the given directory
     fn remove_inode(&mut self, inode: FilesystemIndex) -> FilesystemResult<()>;
@@ -47,6 +63,9 @@ pub trait Filesystem
     /// Remove a directory entry from the directory at the given inode
     fn remove_dir_entry(&mut self, directory_index: FilesystemIndex, name: String) -> FilesystemResult<()>;
 
+    /// Add a directory entry for an existing inode to the directory at the given inode
+    fn add_dir_entry(&mut self, directory_index: FilesystemIndex, inode: FilesystemIndex, name: String) -> FilesystemResult<()>;
+
     /// Increment the number of links to an inode
     fn increment_links(&mut self, inode: FilesystemIndex) -> FilesystemResult<usize>;
 
@@ -62,12 +81,61 @@ pub trait Filesystem
     /// Mount a filesystem at the given inode
     fn mount_fs_at(&mut self, inode: FilesystemIndex, root: FilesystemIndex, name: String) -> FilesystemResult<()>;
 
+    /// Remove a filesystem mounted at the given inode
+    fn unmount_fs_at(&mut self, inode: FilesystemIndex, root: FilesystemIndex) -> FilesystemResult<()>;
+
     /// Open a filedescriptor for the given inode
     fn open_fd(&mut self, inode: FilesystemIndex, mode: usize) -> FilesystemResult<Box<dyn crate::process::descriptor::FileDescriptor>>;
 
     /// Execute an ioctl command on an inode
     fn exec_ioctl(&mut self, inode: FilesystemIndex, cmd: IOControlCommand) -> FilesystemResult<usize>;
 
+    /// Change the length of the file at the given inode, cutting off its end or
+    /// extending it with zeros, by default the whole file is rewritten
+    fn truncate_inode(&mut self, inode: FilesystemIndex, length: usize) -> FilesystemResult<()>
+    {
+        self.assert_not_directory(inode)?;
+
+        let mut data = self.read_inode(inode)?;
+        data.resize(length, 0);
+
+        self.write_inode(inode, &data)
+    }
+
+    /// Check if the entries of the directories of the filesystem only change
+    /// through the vfs, so a failed lookup can be remembered until an entry is
+    /// created, filesystems whose entries come and go on their own, such as
+    /// /proc, do not
+    fn caches_missing_entries(&self) -> bool
+    {
+        false
+    }
+
+    /// Update the permission bits, owner and group of the inode from the given
+    /// stat, filesystems which do not store them refuse the change
+    fn set_stat(&mut self, _inode: FilesystemIndex, _stat: FileStat) -> FilesystemResult<()>
+    {
+        Err(FilesystemError::PermissionDenied)
+    }
+
+    /// Create a symbolic link to the given target in the directory at the
+    /// given inode, filesystems without symbolic links refuse to create them
+    fn create_symlink(&mut self, _inode: FilesystemIndex, _name: String, _target: String) -> FilesystemResult<FilesystemIndex>
+    {
+        Err(FilesystemError::PermissionDenied)
+    }
+
+    /// Read the target of the symbolic link at the given inode
+    fn read_symlink(&mut self, inode: FilesystemIndex) -> FilesystemResult<String>
+    {
+        if self.get_stat(inode)?.mode & S_IFMT != S_IFLNK
+        {
+            return Err(FilesystemError::NotASymbolicLink);
+        }
+
+        String::from_utf8(self.read_inode(inode)?).map_err(|_| FilesystemError::BadFilesystemFormat)
+    }
+
     /// Assert is not a directory
     fn assert_not_directory(&mut self, inode: FilesystemIndex) -> FilesystemResult<()>
     {
@@ -100,15 +168,132 @@ pub trait Filesystem
         self.assert_not_directory(inode)?;
         self.assert_directory(directory)?;
 
+        // The entry is always removed, but the inode is only freed once the
+        // last link to it is gone
+        self.remove_dir_entry(directory, name)?;
+
         if self.decrement_links(inode)? == 0
         {
-            self.remove_dir_entry(directory, name)?;
             self.remove_inode(inode)?;
         }
 
         Ok(())
     }
 
+    /// Create a hard link to an inode
+    fn link_inode(&mut self, inode: FilesystemIndex, directory: FilesystemIndex, name: String) -> FilesystemResult<()>
+    {
+        self.assert_not_directory(inode)?;
+        self.assert_directory(directory)?;
+
+        // Links can only refer to inodes on the same filesystem
+        if inode.mount_id != directory.mount_id
+        {
+            return Err(FilesystemError::CrossDeviceLink);
+        }
+
+        self.add_dir_entry(directory, inode, name)?;
+        self.increment_links(inode)?;
+
+        Ok(())
+    }
+
+    /// Move the entry `old_name` in `old_directory` to `new_name` in
+    /// `new_directory`, replacing anything which already has the new name
+    fn rename_inode(&mut self, inode: FilesystemIndex, old_directory: FilesystemIndex, old_name: String, new_directory: FilesystemIndex, new_name: String) -> FilesystemResult<()>
+    {
+        self.assert_directory(old_directory)?;
+        self.assert_directory(new_directory)?;
+
+        // Entries can only be moved within the same filesystem
+        if inode.mount_id != new_directory.mount_id || old_directory.mount_id != new_directory.mount_id
+        {
+            return Err(FilesystemError::CrossDeviceLink);
+        }
+
+        let is_directory = self.assert_directory(inode).is_ok();
+
+        // If the new name is already taken, the existing entry is replaced,
+        // everything which could stop that is checked before anything changes
+        let existing = self.get_dir_entries(new_directory)?.iter().find(|entry| entry.name == new_name).map(|entry| entry.index);
+
+        if let Some(existing) = existing
+        {
+            // Renaming a file over another link to itself does nothing
+            if existing == inode
+            {
+                return Ok(());
+            }
+
+            if self.assert_directory(existing).is_ok()
+            {
+                if !is_directory
+                {
+                    return Err(FilesystemError::INodeIsDirectory);
+                }
+
+                if self.get_dir_entries(existing)?.iter().any(|entry| entry.name != "." && entry.name != "..")
+                {
+                    return Err(FilesystemError::DirectoryNotEmpty);
+                }
+            }
+            else if is_directory
+            {
+                return Err(FilesystemError::INodeIsNotADirectory);
+            }
+
+            // The entry being replaced frees the space the new entry needs
+            self.remove_dir_entry(new_directory, new_name.clone())?;
+        }
+
+        if let Err(error) = self.add_dir_entry(new_directory, inode, new_name.clone())
+        {
+            if let Some(existing) = existing
+            {
+                self.add_dir_entry(new_directory, existing, new_name)?;
+            }
+
+            return Err(error);
+        }
+
+        if let Err(error) = self.remove_dir_entry(old_directory, old_name)
+        {
+            self.remove_dir_entry(new_directory, new_name.clone())?;
+
+            if let Some(existing) = existing
+            {
+                self.add_dir_entry(new_directory, existing, new_name)?;
+            }
+
+            return Err(error);
+        }
+
+        // Only once the rename has happened is the replaced inode released
+        if let Some(existing) = existing
+        {
+            if self.assert_directory(existing).is_ok()
+            {
+                self.remove_inode(existing)?;
+            }
+            else if self.decrement_links(existing)? == 0
+            {
+                self.remove_inode(existing)?;
+            }
+        }
+
+        // A directory which changes parent must have its `..` entry updated
+        if is_directory && old_directory != new_directory
+        {
+            self.remove_dir_entry(inode, String::from(".."))?;
+            self.add_dir_entry(inode, new_directory, String::from(".."))?;
+
+            self.decrement_links(old_directory)?;
+            self.increment_links(new_directory)?;
+        }
+
+        Ok(())
+    }
+
     /// Unlink an inode
     fn remove_directory(&mut self, inode: FilesystemIndex, parent: FilesystemIndex, name: String) -> FilesystemResult<()>
     {
@@ -1,7 +1,75 @@
 use crate::*;
 
+use alloc::collections::BTreeMap;
+
+use crate::process::process::Process;
 use crate::process::PID;
 
+/// Handler for the ioctls of a device class, which decodes the command and
+/// its argument and runs it on the descriptor, giving `None` for a command
+/// the class does not know
+pub type IOControlHandler = fn(&mut Process, usize, usize, usize) -> Result<Option<usize>, usize>;
+
+/// Device class handling the ioctl commands of one type
+#[derive(Clone, Copy)]
+pub struct IOControlClass
+{
+    pub name: &'static str,
+    pub handler: IOControlHandler
+}
+
+// Device classes by the type of ioctl command they handle
+static mut IOCTL_CLASSES: Option<BTreeMap<u8, IOControlClass>> = None;
+
+/// Get the table of device classes
+fn get_ioctl_classes() -> &'static mut BTreeMap<u8, IOControlClass>
+{
+    let classes = unsafe { &mut IOCTL_CLASSES };
+
+    if classes.is_none()
+    {
+        *classes = Some(BTreeMap::new());
+    }
+
+    classes.as_mut().unwrap()
+}
+
+/// Get the type of an ioctl command, the second byte of the command, which
+/// selects the device class it belongs to
+pub fn ioctl_type(cmd: usize) -> u8
+{
+    (cmd >> 8) as u8
+}
+
+/// Register the handler for the ioctl commands of the given type
+pub fn register_ioctl_class(kind: u8, name: &'static str, handler: IOControlHandler)
+{
+    kdebugln!(Filesystem, "Registering ioctl class `{}` for type 0x{:x}", name, kind);
+
+    if let Some(existing) = get_ioctl_classes().insert(kind, IOControlClass { name, handler })
+    {
+        kwarnln!("ioctl class `{}` replaced `{}` for type 0x{:x}", name, existing.name, kind);
+    }
+}
+
+/// Run an ioctl on a descriptor through the handler for its device class,
+/// giving `None` if no class knows the command
+pub fn dispatch_ioctl(proc: &mut Process, fd: usize, cmd: usize, args: usize) -> Result<Option<usize>, usize>
+{
+    let result = match get_ioctl_classes().get(&ioctl_type(cmd))
+    {
+        Some(class) => (class.handler)(proc, fd, cmd, args),
+        None => Ok(None)
+    };
+
+    // Arguments which cross a page boundary were worked on in the kernel
+    let copied = crate::syscalls::args::finish_user_copies(proc, result.is_ok());
+    let result = result?;
+    copied?;
+
+    Ok(result)
+}
+
 #[derive(Debug)]
 /// ioctl Commands
 pub enum IOControlCommand
@@ -10,7 +78,31 @@ pub enum IOControlCommand
     FrameBufferGetFixedInfo{response: &'static mut drivers::gpu::structs::FramebufferFixedInfo},
     FrameBufferPutVariableInfo{response: &'static mut drivers::gpu::structs::FramebufferVariableInfo},
     FrameBufferGetVariableInfo{response: &'static mut drivers::gpu::structs::FramebufferVariableInfo},
+    FrameBufferPanDisplay{response: &'static mut drivers::gpu::structs::FramebufferVariableInfo},
+    FrameBufferGetColormap{response: &'static mut drivers::gpu::structs::FramebufferColormap},
+    FrameBufferPutColormap{response: &'static mut drivers::gpu::structs::FramebufferColormap},
     FrameBufferFlush,
+    FrameBufferGetMode{response: &'static mut drivers::gpu::structs::FramebufferMode},
+    FrameBufferSetMode{response: &'static mut drivers::gpu::structs::FramebufferMode, pid: PID},
+    FrameBufferPageFlip{buffer: usize, pid: PID},
+
+    // Console
+    ConsoleLoadFont{data: Vec<u8>},
+    ConsoleSetCursorVisible{visible: bool},
+    ConsoleGetCursor{response: &'static mut drivers::gpu::cursor::ConsoleCursorState},
+    ConsoleSetMode{mode: usize, pid: PID},
+    ConsoleGetMode{response: &'static mut u32},
+
+    // Keyboard
+    KeyboardGetMode{response: &'static mut u32},
+    KeyboardSetMode{mode: usize},
+    KeyboardGetEntry{response: &'static mut drivers::keyboard::KeyboardEntry},
+    KeyboardSetEntry{response: &'static mut drivers::keyboard::KeyboardEntry},
+    KeyboardGetLeds{response: &'static mut u8},
+    KeyboardSetLeds{leds: usize},
+
+    // Block Devices
+    BlockFormatMinix3{inodes: usize},
 
     // Real Time Clock
     RealTimeClockGetTime{response: &'static mut drivers::rtc::RTCTime},
@@ -21,6 +113,26 @@ pub enum IOControlCommand
     TeletypeSetSettingsNoWait{response: &'static mut fs::devfs::tty::TeletypeSettings},
     TeletypeSetSettingsDrain{response: &'static mut fs::devfs::tty::TeletypeSettings},
     TeletypeSetSettingsFlush{response: &'static mut fs::devfs::tty::TeletypeSettings},
-    TeletypeGetProcessGroup{response: &'static mut PID},
-    TeletypeSetProcessGroup{response: &'static mut PID},
-}
\ No newline at end of file
+    TeletypeGetProcessGroup{response: &'static mut PID, session: PID},
+    TeletypeSetProcessGroup{response: &'static mut PID, session: PID},
+    TeletypeGetSession{response: &'static mut PID, session: PID},
+    TeletypeSetControllingTerminal{session: PID, pgid: PID, leader: bool, steal: bool},
+    TeletypeSetPacketMode{response: &'static mut i32},
+    TeletypeGetPacketMode{response: &'static mut i32},
+    TeletypeGetPseudoTerminalNumber{response: &'static mut u32},
+    TeletypeFlush{queue: usize},
+    TeletypeFlowControl{action: usize},
+    TeletypeInjectInput{byte: u8, pgid: PID, privileged: bool},
+}
+
+impl IOControlCommand
+{
+    /// Check if the command is handled by the console keyboard
+    pub fn is_keyboard_command(&self) -> bool
+    {
+        matches!(self,
+            IOControlCommand::KeyboardGetMode { .. } | IOControlCommand::KeyboardSetMode { .. } |
+            IOControlCommand::KeyboardGetEntry { .. } | IOControlCommand::KeyboardSetEntry { .. } |
+            IOControlCommand::KeyboardGetLeds { .. } | IOControlCommand::KeyboardSetLeds { .. })
+    }
+}
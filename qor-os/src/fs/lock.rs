@@ -0,0 +1,110 @@
+//! Advisory File Locks
+//!
+//! Locks taken with flock belong to an open file, so they are shared by
+//! descriptors made with dup or inherited over fork. Each holder is kept as a
+//! weak reference to the open file, a lock is released when the last
+//! descriptor referring to the file is closed without any further
+//! bookkeeping, as the reference can no longer be upgraded.
+
+use crate::*;
+
+use alloc::collections::BTreeMap;
+use alloc::sync::Weak;
+use core::cell::RefCell;
+
+use process::descriptor::FileDescriptor;
+use process::mmap::SharedDescriptor;
+
+use super::structures::FilesystemIndex;
+
+/// Kind of lock held on a file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LockKind
+{
+    Shared,
+    Exclusive
+}
+
+/// Open file holding a lock
+struct LockHolder
+{
+    owner: Weak<RefCell<Box<dyn FileDescriptor>>>,
+    kind: LockKind
+}
+
+impl LockHolder
+{
+    /// Check if the lock is held through the given open file
+    fn is_held_by(&self, owner: &SharedDescriptor) -> bool
+    {
+        self.owner.as_ptr() as *const u8 == alloc::sync::Arc::as_ptr(owner) as *const u8
+    }
+
+    /// Check if the open file holding the lock is still open
+    fn is_open(&self) -> bool
+    {
+        self.owner.strong_count() > 0
+    }
+}
+
+/// Locks held on every inode
+pub struct FileLocks
+{
+    locks: BTreeMap<FilesystemIndex, Vec<LockHolder>>
+}
+
+impl FileLocks
+{
+    /// Create an empty lock table
+    pub fn new() -> Self
+    {
+        Self
+        {
+            locks: BTreeMap::new()
+        }
+    }
+
+    /// Get the holders of locks on an inode, dropping any held by files which
+    /// have since been closed
+    fn holders(&mut self, inode: FilesystemIndex) -> &mut Vec<LockHolder>
+    {
+        let holders = self.locks.entry(inode).or_default();
+        holders.retain(|holder| holder.is_open());
+
+        holders
+    }
+
+    /// Take a lock on an inode through an open file, replacing any lock the
+    /// file already holds. A lock which conflicts with one held through
+    /// another file gives `EAGAIN`, in which case the file is left without a
+    /// lock, as a conversion is not atomic.
+    pub fn lock(&mut self, inode: FilesystemIndex, owner: &SharedDescriptor, kind: LockKind) -> Result<(), usize>
+    {
+        let holders = self.holders(inode);
+        holders.retain(|holder| !holder.is_held_by(owner));
+
+        let conflict = kind == LockKind::Exclusive && !holders.is_empty() ||
+            holders.iter().any(|holder| holder.kind == LockKind::Exclusive);
+
+        if conflict
+        {
+            return Err(errno::EAGAIN);
+        }
+
+        holders.push(LockHolder { owner: alloc::sync::Arc::downgrade(owner), kind });
+
+        Ok(())
+    }
+
+    /// Release the lock held on an inode through an open file, if any
+    pub fn unlock(&mut self, inode: FilesystemIndex, owner: &SharedDescriptor)
+    {
+        let holders = self.holders(inode);
+        holders.retain(|holder| !holder.is_held_by(owner));
+
+        if holders.is_empty()
+        {
+            self.locks.remove(&inode);
+        }
+    }
+}
@@ -1,18 +1,24 @@
 use crate::*;
 
+use crate::drivers::block::BlockDevice;
 use crate::fs::fstrait::*;
 use crate::fs::structures::*;
 
-use super::structures::*;
+use libutils::minix3::*;
 
 use alloc::vec;
 
 use crate::process::descriptor::*;
 
+use libutils::bitmap;
+use libutils::codec::{FromBytes, ToBytes};
 use libutils::paths::PathBuffer;
 
 use super::super::ioctl::*;
 
+// Number of bits in a block of an allocation map
+const BITS_PER_BLOCK: usize = BLOCK_SIZE * 8;
+
 enum UpdateTimes
 {
     Access,
@@ -35,10 +41,11 @@ fn update_time(inode: &mut Minix3Inode, time: UpdateTimes)
 /// Minix3 Filesystem Driver
 pub struct Minix3Filesystem
 {
-    block_driver: &'static mut crate::drivers::virtio::drivers::block::BlockDriver,
+    device: Box<dyn BlockDevice>,
     mount_id: Option<usize>,
     vfs: Option<&'static mut crate::fs::vfs::FilesystemInterface>,
     superblock: Option<Minix3SuperBlock>,
+    version: MinixVersion,
     cache: Vec<(usize, [u8; 1024])>,
     rewritten: Vec<(usize, [u8; 1024])>,
     mount_inodes: Vec<(FilesystemIndex, FilesystemIndex, String)>
@@ -46,15 +53,16 @@ pub struct Minix3Filesystem
 
 impl Minix3Filesystem
 {
-    /// Initialize a new Minix3 Filesystem Interface
-    pub fn new(driver_id: usize) -> Self
+    /// Initialize a new Minix3 Filesystem Interface over a block device
+    pub fn new(device: Box<dyn BlockDevice>) -> Self
     {
         Self
         {
-            block_driver: crate::drivers::virtio::get_block_driver(driver_id).unwrap(),
+            device,
             mount_id: None,
             vfs: None,
             superblock: None,
+            version: MinixVersion::V3,
             cache: Vec::new(),
             rewritten: Vec::new(),
             mount_inodes: Vec::new(),
@@ -62,13 +70,13 @@ impl Minix3Filesystem
     }
 
     /// Read a block as a buffer
-    fn read_block_to_buffer(&mut self, index: usize) -> [u8; 1024]
+    fn read_block_to_buffer(&mut self, index: usize) -> FilesystemResult<[u8; 1024]>
     {
         for (idx, data) in &self.rewritten
         {
             if index == *idx
             {
-                return *data;
+                return Ok(*data);
             }
         }
         
@@ -76,19 +84,17 @@ impl Minix3Filesystem
         {
             if index == *idx
             {
-                return *data;
+                return Ok(*data);
             }
         }
 
         let mut buffer = Box::new([0; 1024]);
 
-        let ptr = &mut *buffer as *mut [u8; 1024] as *mut u8;
-
-        self.block_driver.sync_read(ptr, 1024, index as u64 * 1024);
+        self.device.read(index * BLOCK_SIZE, buffer.as_mut())?;
 
         self.cache.push((index, *buffer));
 
-        *buffer
+        Ok(*buffer)
     }
 
     /// Edit the contents of a block
@@ -133,7 +139,7 @@ impl Minix3Filesystem
             rewritten_index += 1;
         }
 
-        let mut prev_data = self.read_block_to_buffer(index);
+        let mut prev_data = self.read_block_to_buffer(index)?;
 
         for v in new_data
         {
@@ -148,6 +154,38 @@ impl Minix3Filesystem
         Ok(self.rewritten.len() - 1)
     }
 
+    /// Write the rewritten blocks accepted by the filter back to the disk,
+    /// the cache is updated so later reads do not see the old contents
+    fn write_back(&mut self, selected: impl Fn(usize) -> bool) -> FilesystemResult<()>
+    {
+        let mut i = 0;
+
+        while i < self.rewritten.len()
+        {
+            let (block, data) = self.rewritten[i];
+
+            if !selected(block)
+            {
+                i += 1;
+                continue;
+            }
+
+            kdebugln!(Filesystem, "Writing to Block {}", block);
+
+            self.device.write(block * BLOCK_SIZE, &data)?;
+            self.rewritten.remove(i);
+
+            if let Some((_, cached)) = self.cache.iter_mut().find(|(idx, _)| *idx == block)
+            {
+                *cached = data;
+            }
+        }
+
+        self.device.flush()?;
+
+        Ok(())
+    }
+
     /// Read an inode
     fn get_inode(&mut self, inode_number: usize) -> FilesystemResult<Minix3Inode>
     {
@@ -155,17 +193,13 @@ impl Minix3Filesystem
 
         if let Some(superblock) = self.superblock
         {
-            // Conver the inode number to a block index
-            let block_index = (inode_number - 1) / 16 + 2 + superblock.imap_blocks as usize + superblock.zmap_blocks as usize;
+            let (block_index, offset) = inode_position(&superblock, inode_number);
 
             // Read the block into a buffer
-            let mut buffer = self.read_block_to_buffer(block_index);
-
-            // Read the inode out of the buffer
-            let inode = unsafe { (&mut buffer as *mut [u8; 1024] as *mut Minix3Inode).add((inode_number - 1) % 16).read() };
+            let buffer = self.read_block_to_buffer(block_index)?;
 
-            // The buffer is freed implicitly after the return
-            Ok(inode)
+            // Parse the inode out of the buffer
+            Ok(Minix3Inode::from_bytes(&buffer[offset..])?)
         }
         else
         {
@@ -190,28 +224,27 @@ impl Minix3Filesystem
 
         if rewritten_index == self.rewritten.len()
         {
-            let buffer =  self.read_block_to_buffer(block);
+            let buffer = self.read_block_to_buffer(block)?;
             self.rewritten.push((block, buffer));
         }
 
         Ok(&mut self.rewritten[rewritten_index].1)
     }
 
-    /// Edit an inode
-    fn get_mut_inode(&mut self, inode_number: usize) -> FilesystemResult<& mut Minix3Inode>
+    /// Write back an inode
+    fn put_inode(&mut self, inode_number: usize, inode: &Minix3Inode) -> FilesystemResult<()>
     {
         if let Some(superblock) = self.superblock
         {
-            // Conver the inode number to a block index
-            let block_index = (inode_number - 1) / 16 + 2 + superblock.imap_blocks as usize + superblock.zmap_blocks as usize;
+            let (block_index, offset) = inode_position(&superblock, inode_number);
 
             // Get a reference to that memory
             let buffer_ref = self.get_mut_buffer(block_index)?;
 
-            // Get the reference to the specific inode
-            let inode = unsafe { (buffer_ref as *mut [u8; 1024] as *mut Minix3Inode).add((inode_number - 1) % 16).as_mut().unwrap() };
+            // Write the inode into its place in the block
+            inode.to_bytes(&mut buffer_ref[offset..])?;
 
-            Ok(inode)
+            Ok(())
         }
         else
         {
@@ -219,131 +252,50 @@ impl Minix3Filesystem
         }
     }
 
-    /// Read from a possibly nested zone
-    fn read_zone(&mut self, zone: usize, level: usize, buffer: *mut u8, index: &mut usize, remaining: &mut usize, offset: &mut usize)
-    {
-        // If no bytes are left to be read, terminate
-        if *remaining == 0
-        {
-            return;
-        }
-
-        if level == 0
-        {
-            // Read the block to a buffer
-            let data = self.read_block_to_buffer(zone);
-
-            // Read byte by byte
-            for v in data.iter()
-            {
-                if *offset > 0
-                {
-                    *offset -= 1;
-                    continue;
-                }
-
-                unsafe { buffer.add(*index).write(*v) };
-
-                *index += 1;
-                *remaining -= 1;
-
-                if *remaining == 0
-                {
-                    break;
-                }
-            }
-        }
-        else
-        {
-            // Read the block to a buffer
-            let data = unsafe { core::mem::transmute::<[u8; 1024], [u32; 256]>(self.read_block_to_buffer(zone)) };
-
-            // Read byte by byte
-            for v in data.iter()
-            {
-                // Skip entries which contain zero
-                if *v == 0
-                {
-                    continue;
-                }
-
-                // Otherwise, use it as the zone to go to the next level down
-                self.read_zone(*v as usize, level - 1, buffer, index, remaining, offset);
-
-                // If we are done reading the file, break
-                if *remaining == 0
-                {
-                    break;
-                }
-            }
-        }
-    }
-
     /// Read the data from an inode
-    fn read_from_inode(&mut self, inode: Minix3Inode) -> Vec<u8>
+    fn read_from_inode(&mut self, inode: Minix3Inode) -> FilesystemResult<Vec<u8>>
     {
-        let mut remaining = inode.size as usize;
-        let mut buffer = vec![0u8; remaining];
-        let mut index = 0;
-        let mut offset = 0;
-
-        for (i, zone) in inode.zones.iter().enumerate()
-        {
-            if *zone == 0 {continue;}
-            
-            self.read_zone(*zone as usize, 
-                i.max(6) - 6, 
-                buffer.as_mut_ptr(), &mut index, &mut remaining, &mut offset);
-        }
-
-        buffer
+        read_inode_data(&inode, |zone| self.read_block_to_buffer(zone))
     }
 
     /// Add a directory entry at the given inode
     fn add_directory_entry_raw(&mut self, inode: usize, entry: Minix3DirEntry) -> FilesystemResult<()>
     {
-        // Get a mutable reference to the inode
-        let inode_ref = self.get_mut_inode(inode)?;
+        let mut inode_data = self.get_inode(inode)?;
+
+        update_time(&mut inode_data, UpdateTimes::Modify);
 
-        update_time(inode_ref, UpdateTimes::Modify);
+        let entry_size = self.version.dir_entry_size();
+        let entries_per_zone = BLOCK_SIZE / entry_size;
 
         // Get the original size
-        let orig_entry_count = inode_ref.size / 64;
+        let orig_entry_count = inode_data.size as usize / entry_size;
 
         // Increment the size
-        inode_ref.size += 64;
+        inode_data.size += entry_size as u32;
 
-        let zone_index = orig_entry_count / 16;
+        let zone_index = orig_entry_count / entries_per_zone;
 
         if zone_index < 7
         {
-            let next = if inode_ref.zones[zone_index as usize] == 0
+            if inode_data.zones[zone_index] == 0
             {
                 let next = self.next_free_zone()?;
                 self.claim_zone(next)?;
 
-                Some(next as u32)
+                inode_data.zones[zone_index] = next as u32;
             }
-            else
-            {
-                None
-            };
-
 
-            // Get a mutable reference to the inode
-            let inode_ref = self.get_mut_inode(inode)?;
-
-            if let Some(next) = next
-            {
-                inode_ref.zones[zone_index as usize] = next;
-            }
+            self.put_inode(inode, &inode_data)?;
 
             // Get the zone
-            let zone = inode_ref.zones[zone_index as usize];
+            let zone = inode_data.zones[zone_index];
 
-            let buffer = unsafe { core::mem::transmute::<&mut[u8; 1024], &mut[Minix3DirEntry; 16]>(self.get_mut_buffer(zone as usize)?) };
+            let offset = (orig_entry_count % entries_per_zone) * entry_size;
+            let version = self.version;
+            let buffer = self.get_mut_buffer(zone as usize)?;
 
-            buffer[orig_entry_count as usize % 16] = entry;
+            entry.write(&mut buffer[offset..], version)?;
         }
         else
         {
@@ -356,42 +308,37 @@ impl Minix3Filesystem
     /// Add a directory entry at the given inode
     fn remove_directory_entry(&mut self, inode: usize, name: String) -> FilesystemResult<()>
     {
-        // Get a mutable reference to the inode
-        let inode_ref = self.get_mut_inode(inode)?;
+        let mut inode_data = self.get_inode(inode)?;
 
-        update_time(inode_ref, UpdateTimes::Modify);
+        update_time(&mut inode_data, UpdateTimes::Modify);
 
-        let inode_ref = *inode_ref;
+        self.put_inode(inode, &inode_data)?;
         
         // Get the original contents as a buffer
-        let mut buffer = self.read_from_inode(inode_ref);
+        let mut buffer = self.read_from_inode(inode_data)?;
+
+        let entry_size = self.version.dir_entry_size();
 
         // Get the original number of entries
-        let original_count = inode_ref.size / 64;
+        let original_count = inode_data.size as usize / entry_size;
 
-        // Cast the buffer as an array of directory entries
-        let array = unsafe { core::mem::transmute::<&mut [u8], &mut [Minix3DirEntry]>(buffer.as_mut()) };
+        // Find the entry, and move every entry after it back by one
+        let mut position = None;
 
-        // Iterate over the array
-        let mut move_back = false;
-        for i in 0..original_count as usize
+        for (i, chunk) in buffer.chunks_exact(entry_size).enumerate()
         {
-            if !move_back
+            if Minix3DirEntry::read(chunk, self.version)?.to_string() == name
             {
-                let s = array[i].to_string();
-
-                if s == name
-                {
-                    move_back = true;
-                }
-            }
-            else
-            {
-                array[i - 1] = array[i];
+                position = Some(i);
+                break;
             }
         }
 
-        self.write_to_file(inode, unsafe { core::slice::from_raw_parts(buffer.as_ptr(), (original_count as usize - 1) * 64) })?;
+        let position = position.ok_or_else(|| FilesystemError::FileNotFound(name.clone()))?;
+
+        buffer.copy_within((position + 1) * entry_size..original_count * entry_size, position * entry_size);
+
+        self.write_to_file(inode, &buffer[..(original_count - 1) * entry_size])?;
 
         Ok(())
     }
@@ -399,6 +346,11 @@ impl Minix3Filesystem
     /// Add a directory entry from the inode and name to the given inode
     fn add_directory_entry(&mut self, dest: usize, inode: usize, name: &str) -> FilesystemResult<()>
     {
+        if name.len() > self.version.name_length()
+        {
+            return Err(FilesystemError::NameTooLong);
+        }
+
         let mut ent = Minix3DirEntry
         {
             inode: inode as u32,
@@ -418,34 +370,13 @@ impl Minix3Filesystem
     {
         if let Some(superblock) = self.superblock
         {
-            let mut i = 0;
-
-            let num_blocks = superblock.imap_blocks;
-
-            for b in 0..num_blocks
+            for b in 0..superblock.imap_blocks as usize
             {
-                let buffer = self.read_block_to_buffer(2 + b as usize);
+                let buffer = self.read_block_to_buffer(2 + b)?;
 
-                for v in &buffer
+                if let Some(bit) = bitmap::first_clear_bit(&buffer, 0)
                 {
-                    if *v == 0xFF
-                    {
-                        i += 8;
-                        continue;
-                    }
-
-                    let mut walker = 0x01;
-
-                    while walker > 0
-                    {
-                        if *v & walker == 0
-                        {
-                            return Ok(i);
-                        }
-
-                        i += 1;
-                        walker <<= 1;
-                    }
+                    return Ok(b * BITS_PER_BLOCK + bit);
                 }
             }
 
@@ -458,70 +389,40 @@ impl Minix3Filesystem
     }
 
     /// Claim an inode
-    fn claim_inode(&mut self, mut inode: usize) -> FilesystemResult<()>
+    fn claim_inode(&mut self, inode: usize) -> FilesystemResult<()>
     {
-        inode -= 0;
+        let buffer = self.get_mut_buffer(2 + inode / BITS_PER_BLOCK)?;
 
-        let block = 2 + inode / (8 * 1024);
-        let byte = (inode / 8) % 1024;
-        let bit = inode % 8;
-
-        let buffer = self.get_mut_buffer(block)?;
-
-        buffer[byte] |= 0x01 << bit;
+        bitmap::set_bit(buffer, inode % BITS_PER_BLOCK);
 
         Ok(())
     }
 
     /// Free an inode
-    fn free_inode(&mut self, mut inode: usize) -> FilesystemResult<()>
+    fn free_inode(&mut self, inode: usize) -> FilesystemResult<()>
     {
-        inode -= 0;
-
-        let block = 2 + inode / (8 * 1024);
-        let byte = (inode / 8) % 1024;
-        let bit = inode % 8;
-
-        let buffer = self.get_mut_buffer(block)?;
+        let buffer = self.get_mut_buffer(2 + inode / BITS_PER_BLOCK)?;
 
-        buffer[byte] &= !(0x01 << bit);
+        bitmap::clear_bit(buffer, inode % BITS_PER_BLOCK);
 
         Ok(())
     }
 
-    /// Get the next available free inode
+    /// Get the next available free zone
     fn next_free_zone(&mut self) -> FilesystemResult<usize>
     {
         if let Some(superblock) = self.superblock
         {
-            let mut i = 0;
-
-            let num_blocks = superblock.zmap_blocks;
+            // Zones are only handed out some way past the first data zone
+            let first = superblock.first_data_zone as usize + 500;
 
-            for b in 0..num_blocks
+            for b in 0..superblock.zmap_blocks as usize
             {
-                let buffer = self.read_block_to_buffer(2 + b as usize + superblock.imap_blocks as usize);
+                let buffer = self.read_block_to_buffer(2 + b + superblock.imap_blocks as usize)?;
 
-                for v in &buffer
+                if let Some(bit) = bitmap::first_clear_bit(&buffer, first.saturating_sub(b * BITS_PER_BLOCK))
                 {
-                    if *v == 0xFF
-                    {
-                        i += 8;
-                        continue;
-                    }
-
-                    let mut walker = 0x01;
-
-                    while walker > 0
-                    {
-                        if *v & walker == 0 && i as u16 >= superblock.first_data_zone + 500
-                        {
-                            return Ok(i);
-                        }
-
-                        i += 1;
-                        walker <<= 1;
-                    }
+                    return Ok(b * BITS_PER_BLOCK + bit);
                 }
             }
 
@@ -538,13 +439,9 @@ impl Minix3Filesystem
     {
         if let Some(superblock) = self.superblock
         {
-            let block = 2 + superblock.imap_blocks as usize + zone / (8 * 1024);
-            let byte = (zone / 8) % 1024;
-            let bit = zone % 8;
-
-            let buffer = self.get_mut_buffer(block)?;
+            let buffer = self.get_mut_buffer(2 + superblock.imap_blocks as usize + zone / BITS_PER_BLOCK)?;
 
-            buffer[byte] |= 0x01 << bit;
+            bitmap::set_bit(buffer, zone % BITS_PER_BLOCK);
 
             Ok(())
         }
@@ -559,13 +456,9 @@ impl Minix3Filesystem
     {
         if let Some(superblock) = self.superblock
         {
-            let block = 2 + superblock.imap_blocks as usize + zone / (8 * 1024);
-            let byte = (zone / 8) % 1024;
-            let bit = zone % 8;
+            let buffer = self.get_mut_buffer(2 + superblock.imap_blocks as usize + zone / BITS_PER_BLOCK)?;
 
-            let buffer = self.get_mut_buffer(block)?;
-
-            buffer[byte] &= !(0x01 << bit);
+            bitmap::clear_bit(buffer, zone % BITS_PER_BLOCK);
 
             Ok(())
         }
@@ -588,13 +481,14 @@ impl Minix3Filesystem
 
         if level > 0
         {
-            let buffer = self.get_mut_buffer(zone)?;
-            let zones = unsafe { core::mem::transmute::<&mut [u8; 1024], &mut [u32; 256]>(buffer) };
+            let mut zones = [0; ZONES_PER_BLOCK];
 
-            for z in zones
+            for z in zones.iter_mut()
             {
                 *z = self.recursive_zone_alloc(level - 1, remaining)? as u32;
             }
+
+            write_zone_table(self.get_mut_buffer(zone)?, &zones);
         }
         else
         {
@@ -614,7 +508,7 @@ impl Minix3Filesystem
 
         for (i, slot) in inode.zones.iter_mut().enumerate()
         {
-            *slot = self.recursive_zone_alloc(i.max(6) - 6, &mut count)? as u32;
+            *slot = self.recursive_zone_alloc(zone_level(i), &mut count)? as u32;
 
             if count == 0
             {
@@ -649,7 +543,7 @@ impl Minix3Filesystem
         }
         else
         {
-            for slot in unsafe { core::mem::transmute::<[u8; 1024], [u32; 256]>(self.read_block_to_buffer(zone)) }.iter()
+            for slot in read_zone_table(&self.read_block_to_buffer(zone)?).iter()
             {
                 self.recursive_copy_to_zones(*slot as usize, level - 1, data, index)?;
 
@@ -675,7 +569,7 @@ impl Minix3Filesystem
 
         for (i, zone) in inode.zones.iter().enumerate()
         {
-            self.recursive_copy_to_zones(*zone as usize, i.max(6) - 6, data, &mut index)?;
+            self.recursive_copy_to_zones(*zone as usize, zone_level(i), data, &mut index)?;
 
             if index >= data.len()
             {
@@ -686,26 +580,91 @@ impl Minix3Filesystem
         Ok(())
     }
 
-    /// Recursive Free Zones
-    fn recursive_free_zones(&mut self, zone: usize, level: usize) -> FilesystemResult<()>
+    /// Recursively collect the zones referred to by a zone, including the
+    /// zones holding the tables themselves
+    fn recursive_collect_zones(&mut self, zone: usize, level: usize, blocks: &mut Vec<usize>) -> FilesystemResult<()>
+    {
+        blocks.push(zone);
+
+        if level > 0
+        {
+            // Sparse files leave holes, so a zero zone is skipped rather than
+            // ending the table
+            for zone in &read_zone_table(&self.read_block_to_buffer(zone)?)
+            {
+                if *zone == 0
+                {
+                    continue;
+                }
+
+                self.recursive_collect_zones(*zone as usize, level - 1, blocks)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Recursively free the data zones below a zone after the first `keep`,
+    /// along with any tables left empty, returning true if the zone is still
+    /// in use
+    fn recursive_truncate_zones(&mut self, zone: usize, level: usize, keep: &mut usize) -> FilesystemResult<bool>
     {
         if level == 0
         {
+            if *keep > 0
+            {
+                *keep -= 1;
+                return Ok(true);
+            }
+
             self.free_zone(zone)?;
+            return Ok(false);
         }
-        else
+
+        let mut zone_numbers = read_zone_table(&self.read_block_to_buffer(zone)?);
+        let mut used = false;
+        let mut changed = false;
+
+        for slot in zone_numbers.iter_mut()
         {
-            let current = self.read_block_to_buffer(zone);
-            let zone_numbers = unsafe { core::mem::transmute::<[u8; 1024], [u32; 256]>(current) };
+            if *slot == 0
+            {
+                continue;
+            }
 
-            for zone in &zone_numbers
+            if self.recursive_truncate_zones(*slot as usize, level - 1, keep)?
             {
-                if *zone == 0
-                {
-                    break;
-                }
+                used = true;
+            }
+            else
+            {
+                *slot = 0;
+                changed = true;
+            }
+        }
+
+        if !used
+        {
+            self.free_zone(zone)?;
+            return Ok(false);
+        }
+
+        if changed
+        {
+            write_zone_table(self.get_mut_buffer(zone)?, &zone_numbers);
+        }
 
-                self.recursive_free_zones(*zone as usize, level - 1)?;
+        Ok(true)
+    }
+
+    /// Free the data zones of an inode after the first `keep`
+    fn truncate_zones(&mut self, inode: &mut Minix3Inode, mut keep: usize) -> FilesystemResult<()>
+    {
+        for (i, zone) in inode.zones.iter_mut().enumerate()
+        {
+            if *zone != 0 && !self.recursive_truncate_zones(*zone as usize, zone_level(i), &mut keep)?
+            {
+                *zone = 0;
             }
         }
 
@@ -715,18 +674,78 @@ impl Minix3Filesystem
     /// Free zones
     fn free_zones(&mut self, inode: &mut Minix3Inode) -> FilesystemResult<()>
     {
-        for (i, zone) in inode.zones.iter_mut().enumerate()
+        self.truncate_zones(inode, 0)
+    }
+
+    /// Get the zone holding the given block of a file, if it has been
+    /// allocated
+    fn data_zone(&mut self, inode: &Minix3Inode, block: usize) -> FilesystemResult<Option<usize>>
+    {
+        let mut index = block;
+
+        for (i, zone) in inode.zones.iter().enumerate()
         {
-            if *zone == 0
+            let level = zone_level(i);
+            let span = ZONES_PER_BLOCK.pow(level as u32);
+
+            if index >= span
             {
-                break;
+                index -= span;
+                continue;
             }
 
-            self.recursive_free_zones(*zone as usize, i.max(6) - 6)?;
-            *zone = 0;
+            let mut zone = *zone as usize;
+
+            for level in (0..level).rev()
+            {
+                if zone == 0
+                {
+                    break;
+                }
+
+                let span = ZONES_PER_BLOCK.pow(level as u32);
+
+                zone = read_zone_table(&self.read_block_to_buffer(zone)?)[index / span] as usize;
+                index %= span;
+            }
+
+            return Ok(if zone == 0 { None } else { Some(zone) });
         }
 
-        Ok(())
+        Ok(None)
+    }
+
+    /// Change the length of a file, freeing the zones past the new end or
+    /// zeroing the rest of the last block so an extension reads as zeros
+    fn truncate_file(&mut self, inode_number: usize, length: usize) -> FilesystemResult<()>
+    {
+        if length > u32::MAX as usize
+        {
+            return Err(FilesystemError::OutOfSpace);
+        }
+
+        let mut inode = self.get_inode(inode_number)?;
+        let old_size = inode.size as usize;
+
+        if length < old_size
+        {
+            self.truncate_zones(&mut inode, (length + BLOCK_SIZE - 1) / BLOCK_SIZE)?;
+        }
+        else if length > old_size && old_size % BLOCK_SIZE != 0
+        {
+            // The blocks past the end are left unallocated, and read back as
+            // zeros
+            if let Some(zone) = self.data_zone(&inode, old_size / BLOCK_SIZE)?
+            {
+                self.get_mut_buffer(zone)?[old_size % BLOCK_SIZE..].fill(0);
+            }
+        }
+
+        inode.size = length as u32;
+
+        update_time(&mut inode, UpdateTimes::Modify);
+
+        self.put_inode(inode_number, &inode)
     }
 
     /// Write data to a file
@@ -744,7 +763,7 @@ impl Minix3Filesystem
 
         update_time(&mut inode, UpdateTimes::Modify);
 
-        *(self.get_mut_inode(inode_number)?) = inode;
+        self.put_inode(inode_number, &inode)?;
 
         Ok(())
     }
@@ -766,9 +785,7 @@ impl Minix3Filesystem
         let next_inode = self.next_free_inode()?;
         self.claim_inode(next_inode)?;
 
-        let inode = self.get_mut_inode(next_inode)?;
-
-        *inode = Minix3Inode
+        let mut inode = Minix3Inode
         {
             mode,
             nlinks: 1,
@@ -781,17 +798,19 @@ impl Minix3Filesystem
             zones: [0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
         };
 
-        update_time(inode, UpdateTimes::Create);
+        update_time(&mut inode, UpdateTimes::Create);
+
+        self.put_inode(next_inode, &inode)?;
 
         self.write_to_file(next_inode, data.as_bytes())?;
 
         Ok(next_inode)
     }
 
-    /// Allocate a new directory
-    fn new_directory(&mut self, dest: usize, name: String) -> FilesystemResult<usize>
+    /// Allocate a new directory with the given permission bits
+    fn new_directory(&mut self, dest: usize, name: String, mode: u16) -> FilesystemResult<usize>
     {
-        let inode = self.allocate_file(String::new(), 0x4000 | 0o777)?;
+        let inode = self.allocate_file(String::new(), S_IFDIR | mode)?;
 
         self.add_directory_entry(inode, inode, ".")?;
         self.add_directory_entry(inode, dest, "..")?;
@@ -810,19 +829,15 @@ impl Filesystem for Minix3Filesystem
         kdebugln!(Filesystem, "Initializing Minix3 Filesystem");
 
         // Read the super block
-        let mut ptr = Box::new([0u8; 512]);
+        let superblock = Minix3SuperBlock::from_bytes(&self.read_block_to_buffer(SUPERBLOCK_OFFSET / BLOCK_SIZE)?)?;
 
-        self.block_driver.sync_read(ptr.as_mut() as *mut [u8; 512] as *mut u8, 512, 1024);
+        // Verify the filesystem is a Minix V2 or V3 filesystem
+        let version = MinixVersion::from_magic(superblock.magic).ok_or(FilesystemError::BadFilesystemFormat)?;
 
-        let superblock = unsafe { *(ptr.as_mut() as *mut [u8; 512] as *mut Minix3SuperBlock) };
-
-        // Verify the filesystem is a minix3 filesystem
-        if superblock.magic != 0x4d5a
-        {
-            return Err(FilesystemError::BadFilesystemFormat)
-        }
+        kdebugln!(Filesystem, "Found {:?} Minix Filesystem", version);
 
         self.superblock = Some(superblock);
+        self.version = version;
 
         Ok(())
     }
@@ -832,19 +847,41 @@ impl Filesystem for Minix3Filesystem
     {   
         kdebugln!(Filesystem, "{} Zones Rewritten", self.rewritten.len());
 
-        for (block, data) in &self.rewritten
-        {
-            kdebugln!(Filesystem, "Writing to Block {}", block);
+        self.write_back(|_| true)
+    }
+
+    /// Detach the block device, so it can be mounted again later
+    fn detach(&mut self)
+    {
+        self.device.remove();
+    }
+
+    /// Write any changes to the given inode back to the disk
+    fn fsync_inode(&mut self, inode: FilesystemIndex) -> FilesystemResult<()>
+    {
+        let superblock = self.superblock.ok_or(FilesystemError::FilesystemUninitialized)?;
+        let inode_data = self.get_inode(inode.inode)?;
 
-            let ptr = data.as_ptr() as *mut u8;
+        // The block holding the inode and every zone it refers to
+        let mut blocks = vec![inode_position(&superblock, inode.inode).0];
 
-            self.block_driver.sync_write(ptr, 1024, 1024 * *block as u64);
+        for (i, zone) in inode_data.zones.iter().enumerate()
+        {
+            if *zone == 0
+            {
+                continue;
+            }
+
+            self.recursive_collect_zones(*zone as usize, zone_level(i), &mut blocks)?;
         }
 
-        // Clear the rewritten buffer
-        self.rewritten = Vec::new();
+        // The bitmaps are written too, as the inode may use newly claimed
+        // zones
+        let bitmaps = 2..2 + superblock.imap_blocks as usize + superblock.zmap_blocks as usize;
 
-        Ok(())
+        kdebugln!(Filesystem, "Syncing inode {} ({} Blocks)", inode.inode, blocks.len());
+
+        self.write_back(|block| bitmaps.contains(&block) || blocks.contains(&block))
     }
 
     /// Set the mount_id of the filesystem
@@ -911,35 +948,22 @@ impl Filesystem for Minix3Filesystem
                 return Err(FilesystemError::INodeIsNotADirectory);
             }
 
-            let data = self.read_from_inode(inode_data);
-
-            let dir_entries = unsafe { core::mem::transmute::<&[u8], &[Minix3DirEntry]>(data.as_slice()) };
+            let data = self.read_from_inode(inode_data)?;
 
             let mut result = Vec::new();
 
-            for i in 0..inode_data.size as usize / 64
+            for entry in parse_directory(&data, self.version)?
             {
-                let entry = &dir_entries[i];
-                let mut name = String::new();
-
-                for c in &entry.name
-                {
-                    if *c == 0
-                    {
-                        break;
-                    }
-
-                    name.push(*c as char);
-                }
-
-                result.push(DirectoryEntry{ index: FilesystemIndex{ mount_id: inode.mount_id, inode: entry.inode as usize }, name: name, entry_type: DirectoryEntryType::Unknown });
+                result.push(DirectoryEntry{ index: FilesystemIndex{ mount_id: inode.mount_id, inode: entry.inode as usize }, name: entry.to_string(), entry_type: DirectoryEntryType::Unknown });
             }
 
-            // Add any mounted filesystems
+            // Add any mounted filesystems, which hide any entry of the same
+            // name in the directory
             for (place, root, name) in &self.mount_inodes
             {
                 if *place == inode
                 {
+                    result.retain(|entry| entry.name != *name);
                     result.push(DirectoryEntry{ index: *root, name: name.clone(), entry_type: DirectoryEntryType::Directory });
                 }
             }
@@ -996,12 +1020,41 @@ impl Filesystem for Minix3Filesystem
         }
     }
 
+    /// Update the permission bits, owner and group of the inode, the type of
+    /// the inode is kept
+    fn set_stat(&mut self, inode: FilesystemIndex, stat: FileStat) -> FilesystemResult<()>
+    {
+        if Some(inode.mount_id) == self.mount_id
+        {
+            let mut inode_data = self.get_inode(inode.inode)?;
+
+            inode_data.mode = (inode_data.mode & S_IFMT) | (stat.mode & !S_IFMT);
+            inode_data.uid = stat.uid;
+            inode_data.gid = stat.gid;
+
+            update_time(&mut inode_data, UpdateTimes::Create);
+
+            self.put_inode(inode.inode, &inode_data)
+        }
+        else
+        {
+            if let Some(vfs) = &mut self.vfs
+            {
+                vfs.set_stat(inode, stat)
+            }
+            else
+            {
+                Err(FilesystemError::FilesystemNotMounted)
+            }
+        }
+    }
+
     /// Create a file in the directory at the given inode
-    fn create_file(&mut self, inode: FilesystemIndex, name: alloc::string::String) -> FilesystemResult<FilesystemIndex>
+    fn create_file(&mut self, inode: FilesystemIndex, name: alloc::string::String, mode: u16) -> FilesystemResult<FilesystemIndex>
     {
         if Some(inode.mount_id) == self.mount_id
         {
-            let file_inode = self.allocate_file(String::new(), 0o100777)?;
+            let file_inode = self.allocate_file(String::new(), S_IFREG | mode)?;
 
             self.add_directory_entry(inode.inode, file_inode, &name)?;
 
@@ -1011,7 +1064,32 @@ impl Filesystem for Minix3Filesystem
         {
             if let Some(vfs) = &mut self.vfs
             {
-                vfs.create_file(inode, name)
+                vfs.create_file(inode, name, mode)
+            }
+            else
+            {
+                Err(FilesystemError::FilesystemNotMounted)
+            }
+        }
+    }
+
+    /// Create a symbolic link in the directory at the given inode, the target
+    /// is stored as the contents of the link's inode
+    fn create_symlink(&mut self, inode: FilesystemIndex, name: alloc::string::String, target: alloc::string::String) -> FilesystemResult<FilesystemIndex>
+    {
+        if Some(inode.mount_id) == self.mount_id
+        {
+            let link_inode = self.allocate_file(target, S_IFLNK | 0o777)?;
+
+            self.add_directory_entry(inode.inode, link_inode, &name)?;
+
+            Ok(FilesystemIndex { mount_id: inode.mount_id, inode: link_inode } )
+        }
+        else
+        {
+            if let Some(vfs) = &mut self.vfs
+            {
+                vfs.create_symlink(inode, name, target)
             }
             else
             {
@@ -1021,11 +1099,11 @@ impl Filesystem for Minix3Filesystem
     }
 
     /// Create a directory in the directory at the given inode
-    fn create_directory(&mut self, inode: FilesystemIndex, name: alloc::string::String) -> FilesystemResult<FilesystemIndex>
+    fn create_directory(&mut self, inode: FilesystemIndex, name: alloc::string::String, mode: u16) -> FilesystemResult<FilesystemIndex>
     {
         if Some(inode.mount_id) == self.mount_id
         {
-            let dir_inode = self.new_directory(inode.inode, name)?;
+            let dir_inode = self.new_directory(inode.inode, name, mode)?;
 
             Ok(FilesystemIndex { mount_id: inode.mount_id, inode: dir_inode } )
         }
@@ -1033,7 +1111,7 @@ impl Filesystem for Minix3Filesystem
         {
             if let Some(vfs) = &mut self.vfs
             {
-                vfs.create_directory(inode, name)
+                vfs.create_directory(inode, name, mode)
             }
             else
             {
@@ -1086,15 +1164,37 @@ impl Filesystem for Minix3Filesystem
         }
     }
 
+    /// Add a directory entry for an existing inode to the directory at the given inode
+    fn add_dir_entry(&mut self, directory_index: FilesystemIndex, inode: FilesystemIndex, name: String) -> FilesystemResult<()>
+    {
+        if Some(directory_index.mount_id) == self.mount_id
+        {
+            self.add_directory_entry(directory_index.inode, inode.inode, &name)
+        }
+        else
+        {
+            if let Some(vfs) = &mut self.vfs
+            {
+                vfs.add_dir_entry(directory_index, inode, name)
+            }
+            else
+            {
+                Err(FilesystemError::FilesystemNotMounted)
+            }
+        }
+    }
+
     /// Increment the number of links to an inode
     fn increment_links(&mut self, inode: FilesystemIndex) -> FilesystemResult<usize>
     {
         if Some(inode.mount_id) == self.mount_id
         {
-            let r = self.get_mut_inode(inode.inode)?;
+            let mut r = self.get_inode(inode.inode)?;
 
             r.nlinks += 1;
 
+            self.put_inode(inode.inode, &r)?;
+
             Ok(r.nlinks.into())
         }
         else
@@ -1115,11 +1215,13 @@ impl Filesystem for Minix3Filesystem
     {
         if Some(inode.mount_id) == self.mount_id
         {
-            let r = self.get_mut_inode(inode.inode)?;
+            let mut r = self.get_inode(inode.inode)?;
 
             if r.nlinks > 0
             {
                 r.nlinks -= 1;
+                self.put_inode(inode.inode, &r)?;
+
                 Ok(r.nlinks.into())
             }
             else
@@ -1146,7 +1248,7 @@ impl Filesystem for Minix3Filesystem
         if Some(inode.mount_id) == self.mount_id
         {
             let inode = self.get_inode(inode.inode)?;
-            Ok(self.read_from_inode(inode))
+            self.read_from_inode(inode)
         }
         else
         {
@@ -1181,6 +1283,27 @@ impl Filesystem for Minix3Filesystem
         }
     }
 
+    /// Change the length of the file at an inode
+    fn truncate_inode(&mut self, inode: FilesystemIndex, length: usize) -> FilesystemResult<()>
+    {
+        if Some(inode.mount_id) == self.mount_id
+        {
+            self.assert_not_directory(inode)?;
+            self.truncate_file(inode.inode, length)
+        }
+        else
+        {
+            if let Some(vfs) = &mut self.vfs
+            {
+                vfs.truncate_inode(inode, length)
+            }
+            else
+            {
+                Err(FilesystemError::FilesystemNotMounted)
+            }
+        }
+    }
+
     /// Mount a filesystem at the given inode
     fn mount_fs_at(&mut self, inode: FilesystemIndex, root: FilesystemIndex, name: String) -> FilesystemResult<()>
     {
@@ -1189,6 +1312,23 @@ impl Filesystem for Minix3Filesystem
         Ok(())
     }
 
+    /// Remove a filesystem mounted at the given inode
+    fn unmount_fs_at(&mut self, inode: FilesystemIndex, root: FilesystemIndex) -> FilesystemResult<()>
+    {
+        let count = self.mount_inodes.len();
+
+        self.mount_inodes.retain(|(place, mounted, _)| *place != inode || *mounted != root);
+
+        if self.mount_inodes.len() == count
+        {
+            Err(FilesystemError::NotAMountPoint)
+        }
+        else
+        {
+            Ok(())
+        }
+    }
+
     /// Open a filedescriptor for the given inode
     fn open_fd(&mut self, inode: FilesystemIndex, mode: usize) -> FilesystemResult<Box<dyn crate::process::descriptor::FileDescriptor>>
     {
@@ -1229,5 +1369,12 @@ impl Filesystem for Minix3Filesystem
             Err(FilesystemError::FilesystemNotMounted)
         }
     }
+
+    /// Entries are only added through the vfs, so lookups of missing paths
+    /// can be remembered
+    fn caches_missing_entries(&self) -> bool
+    {
+        true
+    }
 }
 
@@ -1,4 +1,2 @@
 pub mod driver;
-pub use driver::*;
-
-pub mod structures;
\ No newline at end of file
+pub use driver::*;
\ No newline at end of file
@@ -4,8 +4,11 @@
 pub mod devfs;
 pub mod fstrait;
 pub mod ioctl;
+pub mod lock;
 pub mod minix3;
+pub mod namespace;
 pub mod procfs;
 pub mod ramdisk;
 pub mod structures;
+pub mod sysfs;
 pub mod vfs;
\ No newline at end of file
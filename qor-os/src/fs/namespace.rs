@@ -0,0 +1,312 @@
+//! Mount Namespaces
+
+use crate::*;
+
+use super::fstrait::Filesystem;
+use super::structures::*;
+use super::vfs::FilesystemInterface;
+
+use alloc::collections::BTreeMap;
+use alloc::sync::Arc;
+use core::cell::RefCell;
+
+use libutils::paths::{OwnedPath, PathBuffer};
+
+/// Shared reference to a mount namespace, processes created by fork share the
+/// namespace of their parent until one of them calls unshare
+pub type SharedMountNamespace = Arc<RefCell<MountNamespace>>;
+
+/// Mount Namespace
+///
+/// A private view of the mount table for a group of processes, mounts made in
+/// a namespace are overlaid on top of the global VFS and are not visible to
+/// processes outside of the namespace
+#[derive(Debug, Clone)]
+pub struct MountNamespace
+{
+    mounts: BTreeMap<OwnedPath, FilesystemIndex>
+}
+
+impl MountNamespace
+{
+    /// Create a new, empty mount namespace
+    pub fn new() -> Self
+    {
+        Self
+        {
+            mounts: BTreeMap::new()
+        }
+    }
+
+    /// Create a new shared namespace which is a copy of the given namespace,
+    /// or an empty namespace if `None` (the global namespace) is given
+    pub fn unshare(current: &Option<SharedMountNamespace>) -> SharedMountNamespace
+    {
+        let namespace = if let Some(current) = current
+        {
+            current.borrow().clone()
+        }
+        else
+        {
+            Self::new()
+        };
+
+        Arc::new(RefCell::new(namespace))
+    }
+
+    /// Add a mount of the filesystem with the given root at the given path,
+    /// failing with `FilesystemBusy` if something is already mounted there
+    pub fn mount(&mut self, path: PathBuffer, root: FilesystemIndex) -> FilesystemResult<()>
+    {
+        let path = Self::normalize(path);
+
+        if self.mounts.contains_key(&path)
+        {
+            return Err(FilesystemError::FilesystemBusy);
+        }
+
+        kdebugln!(Filesystem, "Mounting {:?} at {} in a private namespace", root, path);
+
+        self.mounts.insert(path, root);
+
+        Ok(())
+    }
+
+    /// Get the root of the filesystem mounted at the given path
+    pub fn mounted_at(&self, path: PathBuffer) -> Option<FilesystemIndex>
+    {
+        self.mounts.get(&Self::normalize(path)).copied()
+    }
+
+    /// Remove the mount at the given path, returning the root of the mounted
+    /// filesystem if there was one
+    pub fn unmount(&mut self, path: PathBuffer) -> Option<FilesystemIndex>
+    {
+        self.mounts.remove(&Self::normalize(path))
+    }
+
+    /// Get an iterator over the mounts in the namespace
+    pub fn mounts(&self) -> impl Iterator<Item = (&OwnedPath, &FilesystemIndex)>
+    {
+        self.mounts.iter()
+    }
+
+    /// Normalize a path such that it can be used as a key in the mount table
+    fn normalize(path: PathBuffer) -> OwnedPath
+    {
+        let mut result = String::new();
+
+        for name in path.iter()
+        {
+            result.push('/');
+            result += name;
+        }
+
+        if result.len() == 0
+        {
+            result.push('/');
+        }
+
+        OwnedPath::new(result)
+    }
+
+    /// Find the mount which the given path falls under along with the number
+    /// of path components consumed by the mount point
+    fn find_mount(&self, path: PathBuffer) -> Option<(FilesystemIndex, usize)>
+    {
+        let mut best: Option<(FilesystemIndex, usize)> = None;
+
+        for (mount_point, root) in &self.mounts
+        {
+            let mut mount_names = mount_point.iter();
+            let mut path_names = path.iter();
+            let mut depth = 0;
+
+            let matches = loop
+            {
+                match (mount_names.next(), path_names.next())
+                {
+                    (None, _) => break true,
+                    (Some(a), Some(b)) if a == b => depth += 1,
+                    _ => break false
+                }
+            };
+
+            if matches && best.map(|(_, d)| depth >= d).unwrap_or(true)
+            {
+                best = Some((*root, depth));
+            }
+        }
+
+        best
+    }
+
+    /// Convert a path to an inode, taking the mounts in this namespace into
+    /// account before falling back to the global VFS
+    pub fn path_to_inode(&self, vfs: &mut FilesystemInterface, path: PathBuffer) -> FilesystemResult<FilesystemIndex>
+    {
+        if let Some((mut index, depth)) = self.find_mount(path)
+        {
+            for name in path.iter().skip(depth)
+            {
+                let mut found = false;
+                for entry in vfs.get_dir_entries(index)?
+                {
+                    if entry.name == name
+                    {
+                        found = true;
+                        index = entry.index;
+                        break;
+                    }
+                }
+
+                if !found
+                {
+                    return Err(FilesystemError::FileNotFound(path.to_string()));
+                }
+            }
+
+            Ok(index)
+        }
+        else
+        {
+            vfs.path_to_inode(path)
+        }
+    }
+
+    /// Convert an inode to a path, an inode on a filesystem mounted in this
+    /// namespace is found by searching below where the filesystem is mounted,
+    /// others take their path from the global VFS unless a mount in this
+    /// namespace hides it
+    pub fn inode_to_path(&self, vfs: &mut FilesystemInterface, inode: FilesystemIndex) -> FilesystemResult<OwnedPath>
+    {
+        for (mount_point, root) in &self.mounts
+        {
+            if root.mount_id != inode.mount_id
+            {
+                continue;
+            }
+
+            if let Some(names) = find_below(vfs, *root, inode)
+            {
+                let mut result = String::from(mount_point.as_str().trim_end_matches('/'));
+
+                for name in names
+                {
+                    result.push('/');
+                    result += &name;
+                }
+
+                return Ok(OwnedPath::new(if result.len() == 0 { String::from("/") } else { result }));
+            }
+        }
+
+        let path = vfs.inode_to_path(inode)?.clone();
+
+        if self.find_mount(&path).is_some()
+        {
+            return Err(FilesystemError::FileNotFound(path.to_string()));
+        }
+
+        Ok(path)
+    }
+}
+
+/// Find the path to an inode below a directory by searching the directories
+/// under it, giving the names leading from the directory to the inode
+fn find_below(vfs: &mut FilesystemInterface, directory: FilesystemIndex, inode: FilesystemIndex) -> Option<Vec<String>>
+{
+    let mut pending = vec![(directory, Vec::new())];
+
+    while let Some((index, names)) = pending.pop()
+    {
+        if index == inode
+        {
+            return Some(names);
+        }
+
+        // Anything which cannot be listed is not a directory
+        if let Ok(entries) = vfs.get_dir_entries(index)
+        {
+            for entry in entries
+            {
+                if entry.name != "." && entry.name != ".."
+                {
+                    let mut path = names.clone();
+                    path.push(entry.name);
+
+                    pending.push((entry.index, path));
+                }
+            }
+        }
+    }
+
+    None
+}
+
+/// Walk a path from the root directory of a process which has changed its
+/// root, a `..` at the root stays at the root so the walk cannot leave it
+fn path_from_root(vfs: &mut FilesystemInterface, root: FilesystemIndex, path: PathBuffer) -> FilesystemResult<FilesystemIndex>
+{
+    let mut index = root;
+
+    for name in path.iter()
+    {
+        if name == ".." && index == root
+        {
+            continue;
+        }
+
+        index = vfs.get_dir_entries(index)?.into_iter()
+            .find(|entry| entry.name == name)
+            .map(|entry| entry.index)
+            .ok_or_else(|| FilesystemError::FileNotFound(path.to_string()))?;
+    }
+
+    Ok(index)
+}
+
+/// Convert a path to an inode within the given namespace, a namespace of
+/// `None` refers to the global mount table. For a process which has changed
+/// its root, the path is instead walked from that root, and the mounts of the
+/// namespace are not consulted as they are named by paths outside of it.
+pub fn path_to_inode(namespace: &Option<SharedMountNamespace>, root: Option<FilesystemIndex>, vfs: &mut FilesystemInterface, path: PathBuffer) -> FilesystemResult<FilesystemIndex>
+{
+    if let Some(root) = root
+    {
+        path_from_root(vfs, root, path)
+    }
+    else if let Some(namespace) = namespace
+    {
+        namespace.borrow().path_to_inode(vfs, path)
+    }
+    else
+    {
+        vfs.path_to_inode(path)
+    }
+}
+
+/// Convert an inode to a path within the given namespace as seen from the
+/// given root directory, failing with `FileNotFound` if the inode lies outside
+/// the root. As with `path_to_inode`, the mounts of the namespace are not
+/// consulted for a process which has changed its root.
+pub fn inode_to_path(namespace: &Option<SharedMountNamespace>, root: Option<FilesystemIndex>, vfs: &mut FilesystemInterface, inode: FilesystemIndex) -> FilesystemResult<OwnedPath>
+{
+    if let (None, Some(namespace)) = (root, namespace)
+    {
+        return namespace.borrow().inode_to_path(vfs, inode);
+    }
+
+    let path = vfs.inode_to_path(inode)?.clone();
+
+    if let Some(root) = root
+    {
+        let root = vfs.inode_to_path(root)?.clone();
+
+        super::vfs::strip_path_prefix(&path, &root).ok_or_else(|| FilesystemError::FileNotFound(path.to_string()))
+    }
+    else
+    {
+        Ok(path)
+    }
+}
@@ -7,12 +7,61 @@ use libutils::paths::PathBuffer;
 
 use crate::process::descriptor::*;
 
+const PROC_INODE_PERF: usize = 2;
+const PROC_INODE_UPTIME: usize = 3;
+
 const PROC_INODE_FLAG_PID: usize = 0x10000;
 const PROC_INODE_FLAG_PID_CMDLINE: usize = 0x20000;
 const PROC_INODE_FLAG_PID_STATM: usize = 0x40000;
+const PROC_INODE_FLAG_PID_STAT: usize = 0x80000;
+const PROC_INODE_FLAG_PID_COMM: usize = 0x100000;
 
 use super::super::ioctl::*;
 
+/// Render the uptime and idle time in seconds as will appear in /proc/uptime,
+/// idle time is not tracked so it is always zero
+fn uptime_string() -> String
+{
+    let uptime = drivers::timer::uptime().as_nanoseconds() / 10_000_000;
+
+    format!("{}.{:02} 0.00\n", uptime / 100, uptime % 100)
+}
+
+/// Render the status of a process as will appear in /proc/[pid]/stat, fields
+/// which are not tracked are reported as zero
+fn process_stat_string(proc: &process::process::Process) -> String
+{
+    use process::process::ProcessState;
+
+    let state = match proc.get_state()
+    {
+        ProcessState::Running => 'R',
+        ProcessState::Sleeping { .. } | ProcessState::Waiting(_) => 'S',
+        ProcessState::Stopped => 'T',
+        ProcessState::Zombie => 'Z',
+        ProcessState::Dead => 'X'
+    };
+
+    let start_ticks = process::stats::to_clock_ticks(proc.data.start_time - drivers::timer::boot_time());
+    let times = &proc.data.cpu_times;
+
+    format!("{} ({}) {} {} {} {} 0 0 0 0 0 0 0 {} {} {} {} {} 0 1 0 {} {} {}\n",
+        proc.pid,
+        proc.data.name,
+        state,
+        proc.data.parent_pid,
+        proc.data.process_group_id,
+        proc.data.session_id,
+        process::stats::to_clock_ticks(times.user),
+        process::stats::to_clock_ticks(times.system),
+        process::stats::to_clock_ticks(times.children_user),
+        process::stats::to_clock_ticks(times.children_system),
+        proc.data.priority,
+        start_ticks,
+        proc.get_process_memory() * mem::PAGE_SIZE,
+        proc.resident_pages())
+}
+
 /// /proc Filesystem Handler
 pub struct ProcFilesystem
 {
@@ -121,6 +170,18 @@ impl Filesystem for ProcFilesystem
 
                 if inode.inode == 1
                 {
+                    result.push(DirectoryEntry{
+                        index: FilesystemIndex { mount_id: inode.mount_id, inode: PROC_INODE_PERF},
+                        name: String::from("perf"),
+                        entry_type: DirectoryEntryType::RegularFile,
+                    });
+
+                    result.push(DirectoryEntry{
+                        index: FilesystemIndex { mount_id: inode.mount_id, inode: PROC_INODE_UPTIME},
+                        name: String::from("uptime"),
+                        entry_type: DirectoryEntryType::RegularFile,
+                    });
+
                     if let Some(proc_manager) = process::scheduler::get_process_manager()
                     {
                         for key in proc_manager.processes.keys()
@@ -160,13 +221,31 @@ impl Filesystem for ProcFilesystem
                                 };
 
                             result.push(entry);
+
+                            let entry = DirectoryEntry
+                                {
+                                    index: FilesystemIndex { mount_id: inode.mount_id, inode: PROC_INODE_FLAG_PID_STAT | (pid as usize)},
+                                    name: String::from("stat"),
+                                    entry_type: DirectoryEntryType::RegularFile,
+                                };
+
+                            result.push(entry);
+
+                            let entry = DirectoryEntry
+                                {
+                                    index: FilesystemIndex { mount_id: inode.mount_id, inode: PROC_INODE_FLAG_PID_COMM | (pid as usize)},
+                                    name: String::from("comm"),
+                                    entry_type: DirectoryEntryType::RegularFile,
+                                };
+
+                            result.push(entry);
                         }
                     }
                 }
 
                 Ok(result)
             }
-            else if inode.inode & !0xFFFF > 0
+            else if inode.inode & !0xFFFF > 0 || inode.inode == PROC_INODE_PERF || inode.inode == PROC_INODE_UPTIME
             {
                 Err(FilesystemError::INodeIsNotADirectory)
             }
@@ -194,12 +273,12 @@ impl Filesystem for ProcFilesystem
         todo!()
     }
 
-    fn create_file(&mut self, _inode: FilesystemIndex, _name: alloc::string::String) -> FilesystemResult<FilesystemIndex>
+    fn create_file(&mut self, _inode: FilesystemIndex, _name: alloc::string::String, _mode: u16) -> FilesystemResult<FilesystemIndex>
     {
         Err(FilesystemError::PermissionDenied)
     }
 
-    fn create_directory(&mut self, _inode: FilesystemIndex, _name: alloc::string::String) -> FilesystemResult<FilesystemIndex>
+    fn create_directory(&mut self, _inode: FilesystemIndex, _name: alloc::string::String, _mode: u16) -> FilesystemResult<FilesystemIndex>
     {
         Err(FilesystemError::PermissionDenied)
     }
@@ -216,6 +295,12 @@ impl Filesystem for ProcFilesystem
         Err(FilesystemError::PermissionDenied)
     }
 
+    /// Add a directory entry for an existing inode to the directory at the given inode
+    fn add_dir_entry(&mut self, _directory_index: FilesystemIndex, _inode: FilesystemIndex, _name: String) -> FilesystemResult<()>
+    {
+        Err(FilesystemError::PermissionDenied)
+    }
+
     /// Increment the number of links to an inode
     fn increment_links(&mut self, _inode: FilesystemIndex) -> FilesystemResult<usize>
     {
@@ -234,7 +319,15 @@ impl Filesystem for ProcFilesystem
         {
             let pid = inode.inode & 0xFFFF;
 
-            if inode.inode & PROC_INODE_FLAG_PID_CMDLINE > 0
+            if inode.inode == PROC_INODE_PERF
+            {
+                Ok(Vec::from(perf::dump().as_bytes()))
+            }
+            else if inode.inode == PROC_INODE_UPTIME
+            {
+                Ok(Vec::from(uptime_string().as_bytes()))
+            }
+            else if inode.inode & PROC_INODE_FLAG_PID_CMDLINE > 0
             {
                 if let Some(proc_manager) = process::scheduler::get_process_manager()
                 {
@@ -252,6 +345,42 @@ impl Filesystem for ProcFilesystem
                     Ok(Vec::new())
                 }
             }
+            else if inode.inode & PROC_INODE_FLAG_PID_COMM > 0
+            {
+                if let Some(proc_manager) = process::scheduler::get_process_manager()
+                {
+                    if let Some(proc) = proc_manager.get_process_by_pid(pid as u16)
+                    {
+                        Ok(Vec::from(format!("{}\n", proc.data.name).as_bytes()))
+                    }
+                    else
+                    {
+                        Err(FilesystemError::BadINode)
+                    }
+                }
+                else
+                {
+                    Ok(Vec::new())
+                }
+            }
+            else if inode.inode & PROC_INODE_FLAG_PID_STAT > 0
+            {
+                if let Some(proc_manager) = process::scheduler::get_process_manager()
+                {
+                    if let Some(proc) = proc_manager.get_process_by_pid(pid as u16)
+                    {
+                        Ok(Vec::from(process_stat_string(proc).as_bytes()))
+                    }
+                    else
+                    {
+                        Err(FilesystemError::BadINode)
+                    }
+                }
+                else
+                {
+                    Ok(Vec::new())
+                }
+            }
             else if inode.inode & PROC_INODE_FLAG_PID_STATM > 0
             {
                 if let Some(proc_manager) = process::scheduler::get_process_manager()
@@ -292,6 +421,12 @@ impl Filesystem for ProcFilesystem
     {
         if Some(inode.mount_id) == self.mount_id
         {
+            // Writing to the perf counters resets them
+            if inode.inode == PROC_INODE_PERF
+            {
+                perf::reset();
+            }
+
             // If an inode is written to, just dump the data, it doesn't need to
             // be stored
 
@@ -312,7 +447,13 @@ impl Filesystem for ProcFilesystem
 
     fn mount_fs_at(&mut self, _inode: FilesystemIndex, _root: FilesystemIndex, _name: alloc::string::String) -> FilesystemResult<()>
     {
-        todo!()
+        Err(FilesystemError::PermissionDenied)
+    }
+
+    /// Remove a filesystem mounted at the given inode
+    fn unmount_fs_at(&mut self, _inode: FilesystemIndex, _root: FilesystemIndex) -> FilesystemResult<()>
+    {
+        Err(FilesystemError::NotAMountPoint)
     }
 
     /// Open a filedescriptor for the given inode
@@ -326,7 +467,8 @@ impl Filesystem for ProcFilesystem
                 {
                     Ok(Box::new(InodeFileDescriptor::new(vfs, inode, mode).unwrap()))
                 }
-                else if inode.inode & (PROC_INODE_FLAG_PID_CMDLINE | PROC_INODE_FLAG_PID_STATM) > 0
+                else if inode.inode & (PROC_INODE_FLAG_PID_CMDLINE | PROC_INODE_FLAG_PID_STATM | PROC_INODE_FLAG_PID_STAT | PROC_INODE_FLAG_PID_COMM) > 0 ||
+                        inode.inode == PROC_INODE_PERF || inode.inode == PROC_INODE_UPTIME
                 {
                     Ok(Box::new(InodeFileDescriptor::new(vfs, inode, mode).unwrap()))
                 }
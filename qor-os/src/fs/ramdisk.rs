@@ -197,7 +197,7 @@ impl Filesystem for RamDiskFilesystem
     }
 
     /// Create a file in the directory at the given inode
-    fn create_file(&mut self, inode: FilesystemIndex, name: String) -> FilesystemResult<FilesystemIndex>
+    fn create_file(&mut self, inode: FilesystemIndex, name: String, mode: u16) -> FilesystemResult<FilesystemIndex>
     {
         if Some(inode.mount_id) == self.mount_id
         {
@@ -231,7 +231,7 @@ impl Filesystem for RamDiskFilesystem
         {
             if let Some(vfs) = &mut self.vfs
             {
-                (*vfs).create_file(inode, name)
+                (*vfs).create_file(inode, name, mode)
             }
             else
             {
@@ -241,7 +241,7 @@ impl Filesystem for RamDiskFilesystem
     }
 
     /// Create a directory in the directory at the given inode
-    fn create_directory(&mut self, inode: FilesystemIndex, name: String) -> FilesystemResult<FilesystemIndex>
+    fn create_directory(&mut self, inode: FilesystemIndex, name: String, mode: u16) -> FilesystemResult<FilesystemIndex>
     {
         if Some(inode.mount_id) == self.mount_id
         {
@@ -278,7 +278,7 @@ impl Filesystem for RamDiskFilesystem
         {
             if let Some(vfs) = &mut self.vfs
             {
-                (*vfs).create_file(inode, name)
+                (*vfs).create_file(inode, name, mode)
             }
             else
             {
@@ -299,6 +299,12 @@ impl Filesystem for RamDiskFilesystem
         todo!()
     }
 
+    /// Add a directory entry for an existing inode to the directory at the given inode
+    fn add_dir_entry(&mut self, _directory_index: FilesystemIndex, _inode: FilesystemIndex, _name: String) -> FilesystemResult<()>
+    {
+        Err(FilesystemError::PermissionDenied)
+    }
+
     /// Increment the number of links to an inode
     fn increment_links(&mut self, _inode: FilesystemIndex) -> FilesystemResult<usize>
     {
@@ -347,7 +353,13 @@ impl Filesystem for RamDiskFilesystem
     /// Mount a filesystem at the given inode
     fn mount_fs_at(&mut self, _inode: FilesystemIndex, _root: FilesystemIndex, _name: String) -> FilesystemResult<()>
     {
-        todo!()
+        Err(FilesystemError::PermissionDenied)
+    }
+
+    /// Remove a filesystem mounted at the given inode
+    fn unmount_fs_at(&mut self, _inode: FilesystemIndex, _root: FilesystemIndex) -> FilesystemResult<()>
+    {
+        Err(FilesystemError::NotAMountPoint)
     }
 
     /// Open a filedescriptor for the given inode
@@ -17,7 +17,31 @@ pub enum FilesystemError
     OutOfSpace,
     PermissionDenied,
     DirectoryNotEmpty,
-    INodeIsDirectory
+    INodeIsDirectory,
+    CrossDeviceLink,
+    FilesystemBusy,
+    NotAMountPoint,
+    NotASymbolicLink,
+    NameTooLong,
+    DeviceError(crate::drivers::block::BlockDeviceError)
+}
+
+impl From<crate::drivers::block::BlockDeviceError> for FilesystemError
+{
+    fn from(error: crate::drivers::block::BlockDeviceError) -> Self
+    {
+        FilesystemError::DeviceError(error)
+    }
+}
+
+impl From<libutils::codec::CodecError> for FilesystemError
+{
+    /// An on disk structure which does not fit in its block means the
+    /// filesystem is corrupt
+    fn from(_: libutils::codec::CodecError) -> Self
+    {
+        FilesystemError::BadFilesystemFormat
+    }
 }
 
 impl FilesystemError
@@ -38,6 +62,12 @@ impl FilesystemError
             FilesystemError::PermissionDenied => errno::EPERM,
             FilesystemError::DirectoryNotEmpty => errno::ENOTEMPTY,
             FilesystemError::INodeIsDirectory => errno::EISDIR,
+            FilesystemError::CrossDeviceLink => errno::EXDEV,
+            FilesystemError::FilesystemBusy => errno::EBUSY,
+            FilesystemError::NotAMountPoint => errno::EINVAL,
+            FilesystemError::NotASymbolicLink => errno::EINVAL,
+            FilesystemError::NameTooLong => errno::ENAMETOOLONG,
+            FilesystemError::DeviceError(error) => error.to_errno(),
         }
     }
 }
@@ -77,6 +107,12 @@ pub struct DirectoryEntry
     pub entry_type: DirectoryEntryType
 }
 
+// File type bits of the mode of an inode
+pub const S_IFMT: u16 =   0o170000;
+pub const S_IFLNK: u16 =  0o120000;
+pub const S_IFREG: u16 =  0o100000;
+pub const S_IFDIR: u16 =  0o040000;
+
 /// Stat structure
 #[derive(Debug, Clone, Copy)]
 pub struct FileStat
@@ -0,0 +1,612 @@
+use crate::*;
+
+use super::super::fstrait::*;
+use super::super::structures::*;
+
+use libutils::paths::PathBuffer;
+
+use crate::process::descriptor::*;
+use crate::process::rescg;
+
+use super::super::ioctl::*;
+
+const SYS_INODE_ROOT: usize = 1;
+const SYS_INODE_RESCG: usize = 2;
+const SYS_INODE_PIPE_MAX_SIZE: usize = 3;
+const SYS_INODE_TTY_WAKE_BOOST: usize = 4;
+const SYS_INODE_MAX_PROCESSES: usize = 5;
+
+// The files of resource group directories carry the id of the group in the
+// low bits of the inode, below the flags saying which file it is
+const SYS_INODE_ID_MASK: usize = (1 << 48) - 1;
+
+const SYS_INODE_FLAG_GROUP: usize = 1 << 48;
+const SYS_INODE_FLAG_GROUP_MEMORY_MAX: usize = 1 << 49;
+const SYS_INODE_FLAG_GROUP_MEMORY_CURRENT: usize = 1 << 50;
+const SYS_INODE_FLAG_GROUP_CPU_WEIGHT: usize = 1 << 51;
+const SYS_INODE_FLAG_GROUP_PROCS: usize = 1 << 52;
+
+const SYS_INODE_GROUP_FILES: [(usize, &str); 4] = [
+    (SYS_INODE_FLAG_GROUP_MEMORY_MAX, "memory.max"),
+    (SYS_INODE_FLAG_GROUP_MEMORY_CURRENT, "memory.current"),
+    (SYS_INODE_FLAG_GROUP_CPU_WEIGHT, "cpu.weight"),
+    (SYS_INODE_FLAG_GROUP_PROCS, "procs"),
+];
+
+/// /sys Filesystem Handler
+pub struct SysFilesystem
+{
+    mount_id: Option<usize>,
+    vfs: Option<&'static mut crate::fs::vfs::FilesystemInterface>,
+}
+
+impl SysFilesystem
+{
+    /// Create a new sys filesystem
+    pub fn new() -> Self
+    {
+        Self
+        {
+            mount_id: None,
+            vfs: None,
+        }
+    }
+
+    /// Check if an inode is a directory
+    fn is_directory(inode: usize) -> bool
+    {
+        inode == SYS_INODE_ROOT || inode == SYS_INODE_RESCG || inode & SYS_INODE_FLAG_GROUP > 0
+    }
+
+    /// Parse a decimal value written to a file
+    fn parse_value(data: &[u8]) -> Option<usize>
+    {
+        core::str::from_utf8(data).ok()?.trim().parse::<usize>().ok()
+    }
+
+    /// Apply a write to one of the files in a resource group directory
+    fn write_group_file(inode: usize, data: &[u8])
+    {
+        let id = inode & SYS_INODE_ID_MASK;
+
+        if inode & SYS_INODE_FLAG_GROUP_MEMORY_MAX > 0
+        {
+            if let Some(group) = rescg::get_resource_group(id)
+            {
+                if core::str::from_utf8(data).map(|s| s.trim() == "max").unwrap_or(false)
+                {
+                    group.memory_limit = None;
+                }
+                else if let Some(bytes) = Self::parse_value(data)
+                {
+                    group.memory_limit = Some((bytes + mem::PAGE_SIZE - 1) / mem::PAGE_SIZE);
+                }
+                else
+                {
+                    kwarnln!("Invalid memory limit written to resource group {}", id);
+                }
+            }
+        }
+        else if inode & SYS_INODE_FLAG_GROUP_CPU_WEIGHT > 0
+        {
+            if let Some(group) = rescg::get_resource_group(id)
+            {
+                match Self::parse_value(data)
+                {
+                    Some(weight) if weight >= rescg::MIN_CPU_WEIGHT && weight <= rescg::MAX_CPU_WEIGHT =>
+                    {
+                        group.cpu_weight = weight;
+                    },
+                    _ =>
+                    {
+                        kwarnln!("Invalid cpu weight written to resource group {}", id);
+                    }
+                }
+            }
+        }
+        else if inode & SYS_INODE_FLAG_GROUP_PROCS > 0
+        {
+            if let Some(pid) = Self::parse_value(data)
+            {
+                if !rescg::assign_process(pid as process::PID, id)
+                {
+                    kwarnln!("Unable to move PID {} to resource group {}", pid, id);
+                }
+            }
+        }
+    }
+}
+
+impl Filesystem for SysFilesystem
+{
+    fn init(&mut self) -> FilesystemResult<()>
+    {
+        // Nothing needs to be done here
+        Ok(())
+    }
+
+    fn sync(&mut self) -> FilesystemResult<()>
+    {
+        // Nothing to sync
+        Ok(())
+    }
+
+    fn set_mount_id(&mut self, mount_id: usize, vfs: &'static mut crate::fs::vfs::FilesystemInterface)
+    {
+        self.mount_id = Some(mount_id);
+        self.vfs = Some(vfs);
+    }
+
+    fn get_root_index(&mut self) -> FilesystemResult<FilesystemIndex>
+    {
+        if let Some(id) = self.mount_id
+        {
+            Ok(FilesystemIndex
+            {
+                mount_id: id,
+                inode: SYS_INODE_ROOT
+            })
+        }
+        else
+        {
+            Err(FilesystemError::FilesystemUninitialized)
+        }
+    }
+
+    /// Convert a path to an inode
+    fn path_to_inode(&mut self, path: PathBuffer) -> FilesystemResult<FilesystemIndex>
+    {
+        if let Some(vfs) = &mut self.vfs
+        {
+            vfs.path_to_inode(path)
+        }
+        else
+        {
+            Err(FilesystemError::FilesystemNotMounted)
+        }
+    }
+
+    /// Convert an inode to a path
+    fn inode_to_path(&mut self, inode: FilesystemIndex) -> FilesystemResult<PathBuffer>
+    {
+        if let Some(vfs) = &mut self.vfs
+        {
+            vfs.inode_to_path(inode)
+        }
+        else
+        {
+            Err(FilesystemError::FilesystemNotMounted)
+        }
+    }
+
+    fn get_dir_entries(&mut self, inode: FilesystemIndex) -> FilesystemResult<alloc::vec::Vec<DirectoryEntry>>
+    {
+        if Some(inode.mount_id) == self.mount_id
+        {
+            if Self::is_directory(inode.inode)
+            {
+                let mut result = Vec::new();
+
+                let parent_inode = match inode.inode
+                {
+                    SYS_INODE_ROOT | SYS_INODE_RESCG => SYS_INODE_ROOT,
+                    _ => SYS_INODE_RESCG
+                };
+
+                // Construct the loop back and parent entries
+                let loopback = DirectoryEntry{
+                    index: inode,
+                    name: String::from("."),
+                    entry_type: DirectoryEntryType::Directory,
+                };
+
+                let parent = DirectoryEntry{
+                    index: FilesystemIndex { mount_id: inode.mount_id, inode: parent_inode},
+                    name: String::from(".."),
+                    entry_type: DirectoryEntryType::Directory,
+                };
+
+                result.push(loopback);
+                result.push(parent);
+
+                if inode.inode == SYS_INODE_ROOT
+                {
+                    result.push(DirectoryEntry{
+                        index: FilesystemIndex { mount_id: inode.mount_id, inode: SYS_INODE_RESCG},
+                        name: String::from("rescg"),
+                        entry_type: DirectoryEntryType::Directory,
+                    });
+
+                    result.push(DirectoryEntry{
+                        index: FilesystemIndex { mount_id: inode.mount_id, inode: SYS_INODE_PIPE_MAX_SIZE},
+                        name: String::from("pipe-max-size"),
+                        entry_type: DirectoryEntryType::RegularFile,
+                    });
+
+                    result.push(DirectoryEntry{
+                        index: FilesystemIndex { mount_id: inode.mount_id, inode: SYS_INODE_TTY_WAKE_BOOST},
+                        name: String::from("tty-wake-boost"),
+                        entry_type: DirectoryEntryType::RegularFile,
+                    });
+
+                    result.push(DirectoryEntry{
+                        index: FilesystemIndex { mount_id: inode.mount_id, inode: SYS_INODE_MAX_PROCESSES},
+                        name: String::from("max-processes"),
+                        entry_type: DirectoryEntryType::RegularFile,
+                    });
+                }
+                else if inode.inode == SYS_INODE_RESCG
+                {
+                    for (id, group) in rescg::get_resource_groups().iter()
+                    {
+                        result.push(DirectoryEntry{
+                            index: FilesystemIndex { mount_id: inode.mount_id, inode: SYS_INODE_FLAG_GROUP | id},
+                            name: group.name.clone(),
+                            entry_type: DirectoryEntryType::Directory,
+                        });
+                    }
+                }
+                else
+                {
+                    let id = inode.inode & SYS_INODE_ID_MASK;
+
+                    if rescg::get_resource_group(id).is_none()
+                    {
+                        return Err(FilesystemError::BadINode);
+                    }
+
+                    for (flag, name) in &SYS_INODE_GROUP_FILES
+                    {
+                        result.push(DirectoryEntry{
+                            index: FilesystemIndex { mount_id: inode.mount_id, inode: flag | id},
+                            name: String::from(*name),
+                            entry_type: DirectoryEntryType::RegularFile,
+                        });
+                    }
+                }
+
+                Ok(result)
+            }
+            else if inode.inode & !SYS_INODE_ID_MASK > 0 || inode.inode == SYS_INODE_PIPE_MAX_SIZE || inode.inode == SYS_INODE_TTY_WAKE_BOOST || inode.inode == SYS_INODE_MAX_PROCESSES
+            {
+                Err(FilesystemError::INodeIsNotADirectory)
+            }
+            else
+            {
+                Err(FilesystemError::BadINode)
+            }
+        }
+        else
+        {
+            if let Some(vfs) = &mut self.vfs
+            {
+                (*vfs).get_dir_entries(inode)
+            }
+            else
+            {
+                Err(FilesystemError::FilesystemNotMounted)
+            }
+        }
+    }
+
+    /// Get the directory entry for the given inode
+    fn get_stat(&mut self, inode: FilesystemIndex) -> FilesystemResult<FileStat>
+    {
+        if Some(inode.mount_id) == self.mount_id
+        {
+            let mode = if Self::is_directory(inode.inode) { 0x41ED } else { 0x81A4 };
+
+            Ok(FileStat
+            {
+                dev_id: inode.mount_id,
+                inode: inode.inode,
+                mode,
+                links: 1,
+                uid: 0,
+                gid: 0,
+                special_dev_id: 0,
+                size: 0,
+                blk_size: 512,
+                blocks_alloced: 0,
+                atime: 0,
+                mtime: 0,
+                ctime: 0,
+            })
+        }
+        else
+        {
+            if let Some(vfs) = &mut self.vfs
+            {
+                vfs.get_stat(inode)
+            }
+            else
+            {
+                Err(FilesystemError::FilesystemNotMounted)
+            }
+        }
+    }
+
+    fn create_file(&mut self, _inode: FilesystemIndex, _name: alloc::string::String, _mode: u16) -> FilesystemResult<FilesystemIndex>
+    {
+        Err(FilesystemError::PermissionDenied)
+    }
+
+    /// Creating a directory in /sys/rescg creates a new resource group
+    fn create_directory(&mut self, inode: FilesystemIndex, name: alloc::string::String, _mode: u16) -> FilesystemResult<FilesystemIndex>
+    {
+        if Some(inode.mount_id) == self.mount_id && inode.inode == SYS_INODE_RESCG
+        {
+            if let Some(id) = rescg::create_resource_group(name)
+            {
+                Ok(FilesystemIndex { mount_id: inode.mount_id, inode: SYS_INODE_FLAG_GROUP | id })
+            }
+            else
+            {
+                Err(FilesystemError::PermissionDenied)
+            }
+        }
+        else
+        {
+            Err(FilesystemError::PermissionDenied)
+        }
+    }
+
+    /// Remove an inode at the given index from the given directory
+    fn remove_inode(&mut self, inode: FilesystemIndex) -> FilesystemResult<()>
+    {
+        // Groups are removed along with their directory entry
+        if inode.inode & SYS_INODE_FLAG_GROUP > 0
+        {
+            Ok(())
+        }
+        else
+        {
+            Err(FilesystemError::PermissionDenied)
+        }
+    }
+
+    /// Remove a directory entry from the directory at the given inode
+    fn remove_dir_entry(&mut self, directory_index: FilesystemIndex, name: String) -> FilesystemResult<()>
+    {
+        if directory_index.inode == SYS_INODE_RESCG
+        {
+            if let Some(id) = rescg::find_resource_group(&name)
+            {
+                if rescg::remove_resource_group(id)
+                {
+                    return Ok(());
+                }
+            }
+        }
+
+        Err(FilesystemError::PermissionDenied)
+    }
+
+    /// Add a directory entry for an existing inode to the directory at the given inode
+    fn add_dir_entry(&mut self, _directory_index: FilesystemIndex, _inode: FilesystemIndex, _name: String) -> FilesystemResult<()>
+    {
+        Err(FilesystemError::PermissionDenied)
+    }
+
+    /// Remove a directory, resource group directories are never empty, so
+    /// instead the group must have no member processes
+    fn remove_directory(&mut self, inode: FilesystemIndex, parent: FilesystemIndex, name: String) -> FilesystemResult<()>
+    {
+        if inode.inode & SYS_INODE_FLAG_GROUP > 0
+        {
+            self.remove_dir_entry(parent, name)?;
+            self.remove_inode(inode)
+        }
+        else
+        {
+            Err(FilesystemError::PermissionDenied)
+        }
+    }
+
+    /// Increment the number of links to an inode
+    fn increment_links(&mut self, _inode: FilesystemIndex) -> FilesystemResult<usize>
+    {
+        Err(FilesystemError::PermissionDenied)
+    }
+
+    /// Decrement the number of links to an inode
+    fn decrement_links(&mut self, _inode: FilesystemIndex) -> FilesystemResult<usize>
+    {
+        Err(FilesystemError::PermissionDenied)
+    }
+
+    fn read_inode(&mut self, inode: FilesystemIndex) -> FilesystemResult<alloc::vec::Vec<u8>>
+    {
+        if Some(inode.mount_id) == self.mount_id
+        {
+            if Self::is_directory(inode.inode)
+            {
+                return Ok(Vec::new());
+            }
+
+            if inode.inode == SYS_INODE_PIPE_MAX_SIZE
+            {
+                return Ok(Vec::from(format!("{}\n", process::pipe::pipe_max_size()).as_bytes()));
+            }
+
+            if inode.inode == SYS_INODE_TTY_WAKE_BOOST
+            {
+                return Ok(Vec::from(format!("{}\n", process::runqueue::tty_wake_boost()).as_bytes()));
+            }
+
+            if inode.inode == SYS_INODE_MAX_PROCESSES
+            {
+                return Ok(Vec::from(format!("{}\n", process::scheduler::max_processes()).as_bytes()));
+            }
+
+            let id = inode.inode & SYS_INODE_ID_MASK;
+            let group = rescg::get_resource_group(id).ok_or(FilesystemError::BadINode)?;
+
+            let text = if inode.inode & SYS_INODE_FLAG_GROUP_MEMORY_MAX > 0
+            {
+                if let Some(limit) = group.memory_limit
+                {
+                    format!("{}\n", limit * mem::PAGE_SIZE)
+                }
+                else
+                {
+                    String::from("max\n")
+                }
+            }
+            else if inode.inode & SYS_INODE_FLAG_GROUP_MEMORY_CURRENT > 0
+            {
+                format!("{}\n", rescg::group_memory_usage(id) * mem::PAGE_SIZE)
+            }
+            else if inode.inode & SYS_INODE_FLAG_GROUP_CPU_WEIGHT > 0
+            {
+                format!("{}\n", group.cpu_weight)
+            }
+            else if inode.inode & SYS_INODE_FLAG_GROUP_PROCS > 0
+            {
+                let mut s = String::new();
+
+                for pid in rescg::group_members(id)
+                {
+                    s += &format!("{}\n", pid);
+                }
+
+                s
+            }
+            else
+            {
+                return Err(FilesystemError::BadINode);
+            };
+
+            Ok(Vec::from(text.as_bytes()))
+        }
+        else
+        {
+            if let Some(vfs) = &mut self.vfs
+            {
+                vfs.read_inode(inode)
+            }
+            else
+            {
+                Err(FilesystemError::FilesystemNotMounted)
+            }
+        }
+    }
+
+    fn write_inode(&mut self, inode: FilesystemIndex, data: &[u8]) -> FilesystemResult<()>
+    {
+        if Some(inode.mount_id) == self.mount_id
+        {
+            // Invalid values are dropped with a warning, as the write back
+            // occurs when the descriptor is closed
+            if data.len() > 0 && inode.inode == SYS_INODE_PIPE_MAX_SIZE
+            {
+                if let Some(size) = Self::parse_value(data)
+                {
+                    process::pipe::set_pipe_max_size(size);
+                }
+                else
+                {
+                    kwarnln!("Invalid pipe size limit written to /sys/pipe-max-size");
+                }
+            }
+            else if data.len() > 0 && inode.inode == SYS_INODE_TTY_WAKE_BOOST
+            {
+                if let Some(boost) = Self::parse_value(data)
+                {
+                    process::runqueue::set_tty_wake_boost(boost);
+                }
+                else
+                {
+                    kwarnln!("Invalid boost written to /sys/tty-wake-boost");
+                }
+            }
+            else if data.len() > 0 && inode.inode == SYS_INODE_MAX_PROCESSES
+            {
+                if let Some(count) = Self::parse_value(data)
+                {
+                    process::scheduler::set_max_processes(count);
+                }
+                else
+                {
+                    kwarnln!("Invalid process limit written to /sys/max-processes");
+                }
+            }
+            else if data.len() > 0
+            {
+                Self::write_group_file(inode.inode, data);
+            }
+
+            Ok(())
+        }
+        else
+        {
+            if let Some(vfs) = &mut self.vfs
+            {
+                vfs.write_inode(inode, data)
+            }
+            else
+            {
+                Err(FilesystemError::FilesystemNotMounted)
+            }
+        }
+    }
+
+    fn mount_fs_at(&mut self, _inode: FilesystemIndex, _root: FilesystemIndex, _name: alloc::string::String) -> FilesystemResult<()>
+    {
+        Err(FilesystemError::PermissionDenied)
+    }
+
+    /// Remove a filesystem mounted at the given inode
+    fn unmount_fs_at(&mut self, _inode: FilesystemIndex, _root: FilesystemIndex) -> FilesystemResult<()>
+    {
+        Err(FilesystemError::NotAMountPoint)
+    }
+
+    /// Open a filedescriptor for the given inode
+    fn open_fd(&mut self, inode: FilesystemIndex, mode: usize) -> FilesystemResult<Box<dyn crate::process::descriptor::FileDescriptor>>
+    {
+        if let Some(vfs) = &mut self.vfs
+        {
+            if Some(inode.mount_id) == self.mount_id
+            {
+                if let Ok(desc) = InodeFileDescriptor::new(vfs, inode, mode)
+                {
+                    Ok(Box::new(desc))
+                }
+                else
+                {
+                    Err(FilesystemError::BadINode)
+                }
+            }
+            else
+            {
+                vfs.open_fd(inode, mode)
+            }
+        }
+        else
+        {
+            Err(FilesystemError::FilesystemNotMounted)
+        }
+    }
+
+    /// Execute an ioctl command on an inode
+    fn exec_ioctl(&mut self, inode: FilesystemIndex, cmd: IOControlCommand) -> FilesystemResult<usize>
+    {
+        if let Some(vfs) = &mut self.vfs
+        {
+            if Some(inode.mount_id) == self.mount_id
+            {
+                // Nothing to do here (yet)
+                Ok(usize::MAX)
+            }
+            else
+            {
+                vfs.exec_ioctl(inode, cmd)
+            }
+        }
+        else
+        {
+            Err(FilesystemError::FilesystemNotMounted)
+        }
+    }
+}
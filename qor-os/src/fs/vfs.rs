@@ -3,13 +3,17 @@ use crate::*;
 use super::fstrait::Filesystem;
 use super::structures::*;
 
-use alloc::{collections::BTreeMap, borrow::ToOwned};
+use alloc::{collections::{BTreeMap, BTreeSet}, borrow::ToOwned};
 use alloc::format;
 
 use libutils::paths::{OwnedPath, PathBuffer};
 
 use super::ioctl::IOControlCommand;
 
+// Most paths remembered as missing at once, the whole set is forgotten when
+// it fills up
+const MISSING_PATHS_MAX: usize = 256;
+
 static VFS_INTERFACE: core::sync::atomic::AtomicPtr<FilesystemInterface> = core::sync::atomic::AtomicPtr::new(0 as *mut FilesystemInterface);
 
 /// Get a reference to the vfs interface
@@ -20,13 +24,51 @@ pub fn get_vfs_reference() -> Option<&'static mut FilesystemInterface>
     unsafe { ptr.as_mut() }
 }
  
+/// Get a path relative to the given prefix, in the same form with a leading
+/// `/`, or `None` if the path does not lie below the prefix
+pub fn strip_path_prefix(path: PathBuffer, prefix: PathBuffer) -> Option<OwnedPath>
+{
+    let mut names = path.iter();
+
+    for prefix_name in prefix.iter()
+    {
+        if names.next() != Some(prefix_name)
+        {
+            return None;
+        }
+    }
+
+    let mut result = String::new();
+
+    for name in names
+    {
+        result.push('/');
+        result += name;
+    }
+
+    Some(OwnedPath::new(result))
+}
+
+/// Record of where a filesystem is mounted
+#[derive(Debug, Clone)]
+pub struct MountPoint
+{
+    pub source: String,
+    pub path: OwnedPath,
+    pub directory: Option<FilesystemIndex>
+}
+
 /// Virtual Filesystem Interface
 pub struct FilesystemInterface
 {
     mounts: Vec<Option<Box<dyn Filesystem>>>,
+    mount_points: BTreeMap<usize, MountPoint>,
+    sources: BTreeMap<usize, String>,
     root: Option<usize>,
     pub index: BTreeMap<OwnedPath, FilesystemIndex>,
-    indexed: BTreeMap<FilesystemIndex, OwnedPath>
+    indexed: BTreeMap<FilesystemIndex, OwnedPath>,
+    missing: BTreeSet<OwnedPath>,
+    pub locks: super::lock::FileLocks
 }
 
 impl FilesystemInterface
@@ -42,9 +84,13 @@ impl FilesystemInterface
         let singleton = Box::new(Self
         {
             mounts: Vec::new(),
+            mount_points: BTreeMap::new(),
+            sources: BTreeMap::new(),
             root: None,
             index: BTreeMap::new(),
-            indexed: BTreeMap::new()
+            indexed: BTreeMap::new(),
+            missing: BTreeSet::new(),
+            locks: super::lock::FileLocks::new()
         });
 
         let reference = Box::leak(singleton);
@@ -54,11 +100,12 @@ impl FilesystemInterface
         unsafe { (reference as *mut FilesystemInterface).as_mut().unwrap() } 
     }
 
-    /// Mount a filesystem to the vfs
-    pub fn mount_fs(&mut self, path: PathBuffer, mut fs: Box<dyn Filesystem>) -> Result<(), FilesystemError>
+    /// Register a filesystem with the vfs without attaching it to the global
+    /// tree, returning the index of the root of the filesystem, this allows
+    /// the filesystem to be mounted within a private mount namespace. The
+    /// source is the device or name the filesystem was created from.
+    pub fn register_fs(&mut self, source: &str, mut fs: Box<dyn Filesystem>) -> Result<FilesystemIndex, FilesystemError>
     {
-        kdebugln!(Filesystem, "Mounting filesystem to index {} at {}", self.mounts.len(), path);
-
         // Set the mount id
         let id = self.mounts.len();
         fs.set_mount_id(id, unsafe { (self as *mut FilesystemInterface).as_mut().unwrap() });
@@ -67,11 +114,50 @@ impl FilesystemInterface
 
         // Add the mount
         self.mounts.push(Some(fs));
+        self.sources.insert(id, source.to_string());
+
+        Ok(root)
+    }
+
+    /// Sync and release a filesystem which is no longer mounted anywhere,
+    /// such as one only mounted in a mount namespace
+    pub fn unregister_fs(&mut self, id: usize)
+    {
+        if let Some(mut fs) = self.mounts.get_mut(id).and_then(|fs| fs.take())
+        {
+            if let Err(e) = fs.sync()
+            {
+                kerrorln!("Unable to sync filesystem at index {}: {:?}", id, e);
+            }
+
+            fs.detach();
+        }
+
+        self.sources.remove(&id);
+        crate::process::textcache::get_text_cache().invalidate_mount(id);
+    }
+
+    /// Check if a filesystem created from the given source is registered,
+    /// whether mounted in the global tree or only in a mount namespace
+    pub fn source_in_use(&self, source: &str) -> bool
+    {
+        self.sources.values().any(|name| name.as_str() == source)
+    }
+
+    /// Mount a filesystem to the vfs, the source is the device or name the
+    /// filesystem was created from
+    pub fn mount_fs(&mut self, source: &str, path: PathBuffer, fs: Box<dyn Filesystem>) -> Result<(), FilesystemError>
+    {
+        kdebugln!(Filesystem, "Mounting {} to index {} at {}", source, self.mounts.len(), path);
 
         // Add the mapping to the mount paths
         if path.as_str() == "/"
         {
+            let id = self.mounts.len();
+            self.register_fs(source, fs)?;
+
             self.root = Some(id);
+            self.mount_points.insert(id, MountPoint { source: source.to_string(), path: path.clone(), directory: None });
 
             Ok(())
         }
@@ -86,11 +172,143 @@ impl FilesystemInterface
                 let (path_start, name) = path.split_last();
 
                 let inode = self.path_to_inode(&path_start)?;
-                self.mount_fs_at(inode, root, name.to_string())
+
+                let id = self.mounts.len();
+                let root = self.register_fs(source, fs)?;
+
+                if let Err(e) = self.mount_fs_at(inode, root, name.to_string())
+                {
+                    self.mounts[id] = None;
+                    self.sources.remove(&id);
+                    return Err(e);
+                }
+
+                self.mount_points.insert(id, MountPoint { source: source.to_string(), path: path.clone(), directory: Some(inode) });
+
+                // The mount hides whatever was previously at the path
+                self.invalidate_index(path)?;
+                self.forget_missing(path);
+                self.index_from(path, root)
             }
         }
     }
 
+    /// Unmount the filesystem whose root is at the given index, fails if the
+    /// filesystem is the root or has other filesystems mounted within it
+    pub fn unmount_fs(&mut self, root: FilesystemIndex) -> Result<(), FilesystemError>
+    {
+        let id = root.mount_id;
+
+        if self.root == Some(id)
+        {
+            return Err(FilesystemError::FilesystemBusy);
+        }
+
+        if self.get_fs_mount_error(id)?.get_root_index()? != root
+        {
+            return Err(FilesystemError::NotAMountPoint);
+        }
+
+        let directory = self.mount_points.get(&id).and_then(|mount| mount.directory).ok_or(FilesystemError::NotAMountPoint)?;
+
+        if self.mount_points.values().any(|mount| mount.directory.map(|dir| dir.mount_id == id).unwrap_or(false))
+        {
+            return Err(FilesystemError::FilesystemBusy);
+        }
+
+        kdebugln!(Filesystem, "Unmounting filesystem at index {}", id);
+
+        self.unmount_fs_at(directory, root)?;
+        self.unregister_fs(id);
+
+        self.mount_points.remove(&id);
+
+        // Rebuild the index so the paths under the mount point refer to what
+        // was there before the filesystem was mounted
+        self.index()
+    }
+
+    /// Make the filesystem mounted at `new_root` the root of the tree, and
+    /// mount the old root at `put_old`, which must be a directory below the
+    /// new root, failing with `NotAMountPoint` otherwise. Filesystems mounted
+    /// within either root keep their place within it.
+    pub fn pivot_root(&mut self, new_root: PathBuffer, put_old: PathBuffer) -> Result<(), FilesystemError>
+    {
+        let old_id = self.root.ok_or(FilesystemError::MissingRootMount)?;
+        let old_root = self.get_root_index()?;
+
+        let new_index = self.path_to_inode(new_root)?;
+        let new_id = new_index.mount_id;
+
+        if new_id == old_id || self.get_fs_mount_error(new_id)?.get_root_index()? != new_index
+        {
+            return Err(FilesystemError::NotAMountPoint);
+        }
+
+        let mount = self.mount_points.get(&new_id).ok_or(FilesystemError::NotAMountPoint)?;
+        let directory = mount.directory.ok_or(FilesystemError::NotAMountPoint)?;
+        let mount_name = mount.path.split_last().1.to_string();
+
+        // Where the old root will be mounted, as seen from the new root
+        let relative = strip_path_prefix(put_old, new_root)
+            .filter(|path| path.as_str().len() > 0)
+            .ok_or(FilesystemError::NotAMountPoint)?;
+
+        let put_old_index = self.path_to_inode(put_old)?;
+        self.assert_directory(put_old_index)?;
+
+        let (parent_path, name) = put_old.split_last();
+        let parent = self.path_to_inode(&parent_path)?;
+
+        kdebugln!(Filesystem, "Pivoting the root to {}, moving the old root to {}", new_root, relative);
+
+        self.unmount_fs_at(directory, new_index)?;
+
+        // Put the new root back where it was if the old root cannot be moved,
+        // so a failed pivot leaves the tree as it found it
+        if let Err(error) = self.mount_fs_at(parent, old_root, name.to_string())
+        {
+            if self.mount_fs_at(directory, new_index, mount_name).is_err()
+            {
+                kerrorln!("Unable to remount {} after a failed pivot_root", new_root);
+            }
+
+            return Err(error);
+        }
+
+        self.root = Some(new_id);
+
+        for (id, mount) in self.mount_points.iter_mut()
+        {
+            if *id == new_id
+            {
+                mount.path = OwnedPath::new("/");
+                mount.directory = None;
+            }
+            else if *id == old_id
+            {
+                mount.path = relative.clone();
+                mount.directory = Some(parent);
+            }
+            else if let Some(path) = strip_path_prefix(&mount.path, new_root)
+            {
+                mount.path = path;
+            }
+            else
+            {
+                mount.path = OwnedPath::new(format!("{}{}", relative, mount.path));
+            }
+        }
+
+        self.index()
+    }
+
+    /// Get an iterator over the mounted filesystems and where they are mounted
+    pub fn mount_points(&self) -> impl Iterator<Item = (&usize, &MountPoint)>
+    {
+        self.mount_points.iter()
+    }
+
     /// Get the fs mounted at the given index
     pub fn get_fs_mount(&mut self, id: usize) -> Option<&mut Box<dyn Filesystem>>
     {
@@ -124,6 +342,37 @@ impl FilesystemInterface
         }
     }
 
+    /// Sync and drop every mounted filesystem, used during shutdown, every
+    /// mount is removed even if syncing one of them fails
+    pub fn unmount_all(&mut self) -> FilesystemResult<()>
+    {
+        let mut result = Ok(());
+
+        for (id, mount) in self.mounts.iter_mut().enumerate()
+        {
+            if let Some(mut fs) = mount.take()
+            {
+                kdebugln!(Filesystem, "Unmounting filesystem at index {}", id);
+
+                if let Err(e) = fs.sync()
+                {
+                    kerrorln!("Unable to sync filesystem at index {}: {:?}", id, e);
+                    result = result.and(Err(e));
+                }
+
+                fs.detach();
+            }
+        }
+
+        self.root = None;
+        self.mount_points.clear();
+        self.index.clear();
+        self.indexed.clear();
+        self.missing.clear();
+
+        result
+    }
+
     /// Get the root filesystem
     pub fn get_root_fs(&mut self) -> Option<&mut Box<dyn Filesystem>>
     {
@@ -190,6 +439,7 @@ impl FilesystemInterface
         // Clear the previous index
         self.index = BTreeMap::new();
         self.indexed = BTreeMap::new();
+        self.missing.clear();
 
         let root = self.get_root_index()?;
         self.index_from(&OwnedPath::new(""), root)
@@ -215,6 +465,67 @@ impl FilesystemInterface
 
         Ok(())
     }
+
+    /// Check if a path, or a directory above it, is known not to exist
+    fn is_missing(&self, path: PathBuffer) -> bool
+    {
+        if self.missing.is_empty()
+        {
+            return false;
+        }
+
+        let mut prefix = String::new();
+
+        for name in path.iter()
+        {
+            prefix.push('/');
+            prefix += name;
+
+            if self.missing.contains(&OwnedPath::new(prefix.clone()))
+            {
+                return true;
+            }
+        }
+
+        false
+    }
+
+    /// Remember that there is no entry with the given path, when the lookup
+    /// failed in a directory whose entries only change through the vfs
+    fn remember_missing(&mut self, directory: FilesystemIndex, path: String)
+    {
+        let cacheable = self.get_fs_mount(directory.mount_id).map(|fs| fs.caches_missing_entries()).unwrap_or(false);
+
+        if cacheable
+        {
+            if self.missing.len() >= MISSING_PATHS_MAX
+            {
+                self.missing.clear();
+            }
+
+            self.missing.insert(OwnedPath::new(path));
+        }
+    }
+
+    /// Forget that a path and everything below it were missing, once an entry
+    /// is created there
+    fn forget_missing(&mut self, path: PathBuffer)
+    {
+        let below = format!("{}/", path);
+
+        self.missing.retain(|missing| missing.as_str() != path.as_str() && !missing.as_str().starts_with(below.as_str()));
+    }
+
+    /// Forget that an entry in a directory was missing, if the path of the
+    /// directory is not known every missing path is forgotten
+    fn forget_missing_entry(&mut self, directory: FilesystemIndex, name: &str)
+    {
+        match self.indexed.get(&directory).cloned()
+        {
+            Some(parent) => self.forget_missing(&OwnedPath::new(format!("{}/{}", parent, name))),
+            None => self.missing.clear()
+        }
+    }
 }
 
 impl Filesystem for FilesystemInterface
@@ -245,6 +556,19 @@ impl Filesystem for FilesystemInterface
         Ok(())
     }
 
+    /// Write any changes to the given inode back to the disk
+    fn fsync_inode(&mut self, inode: FilesystemIndex) -> FilesystemResult<()>
+    {
+        if let Some(fs) = self.get_fs_mount(inode.mount_id)
+        {
+            fs.fsync_inode(inode)
+        }
+        else
+        {
+            Err(FilesystemError::UnableToFindDiskMount(inode.mount_id))
+        }
+    }
+
     /// Set the mount_id of the filesystem
     fn set_mount_id(&mut self, _mount_id: usize, _vfs: &'static mut FilesystemInterface)
     {
@@ -267,13 +591,25 @@ impl Filesystem for FilesystemInterface
             Ok(*index)
         }
 
+        // Paths which were looked up before and did not exist fail without
+        // walking the disk again
+        else if self.is_missing(path)
+        {
+            kdebugln!(Filesystem, "Map path `{}` to inode -> File Not Found (Cached)", path);
+            Err(FilesystemError::FileNotFound(path.to_string()))
+        }
+
         // Otherwise, we will walk the filesystem
         else
         {
             let mut index = self.get_root_index()?;
+            let mut walked = String::new();
 
             for name in path.iter()
             {
+                walked.push('/');
+                walked += name;
+
                 let mut found = false;
                 for entry in self.get_dir_entries(index)?
                 {
@@ -287,6 +623,8 @@ impl Filesystem for FilesystemInterface
 
                 if !found
                 {
+                    self.remember_missing(index, walked);
+
                     kdebugln!(Filesystem, "Map path `{}` to inode -> File Not Found", path);
                     return Err(FilesystemError::FileNotFound(path.to_string()));
                 }
@@ -312,9 +650,12 @@ impl Filesystem for FilesystemInterface
         }
         else
         {
-            todo!()
+            // Inodes which are not reachable from the root, such as those on
+            // a private mount, have no path
+            kdebugln!(Filesystem, "Map inode {:?} to path -> Not Indexed", inode);
+            Err(FilesystemError::BadINode)
         }
-        
+
     }
 
     /// Get the directory entries in the directory at the given inode
@@ -346,13 +687,60 @@ impl Filesystem for FilesystemInterface
     }
 
     /// Create a file in the directory at the given inode
-    fn create_file(&mut self, inode: FilesystemIndex, name: String) -> FilesystemResult<FilesystemIndex>
+    fn create_file(&mut self, inode: FilesystemIndex, name: String, mode: u16) -> FilesystemResult<FilesystemIndex>
     {
         kdebugln!(Filesystem, "Create file `{}` at {:?}", name, inode);
 
         if let Some(fs) = self.get_fs_mount(inode.mount_id)
         {
-            fs.create_file(inode, name)
+            let index = fs.create_file(inode, name.clone(), mode)?;
+            self.forget_missing_entry(inode, &name);
+
+            Ok(index)
+        }
+        else
+        {
+            Err(FilesystemError::UnableToFindDiskMount(inode.mount_id))
+        }
+    }
+
+    /// Update the metadata of the inode
+    fn set_stat(&mut self, inode: FilesystemIndex, stat: FileStat) -> FilesystemResult<()>
+    {
+        if let Some(fs) = self.get_fs_mount(inode.mount_id)
+        {
+            fs.set_stat(inode, stat)
+        }
+        else
+        {
+            Err(FilesystemError::UnableToFindDiskMount(inode.mount_id))
+        }
+    }
+
+    /// Create a symbolic link in the directory at the given inode
+    fn create_symlink(&mut self, inode: FilesystemIndex, name: String, target: String) -> FilesystemResult<FilesystemIndex>
+    {
+        kdebugln!(Filesystem, "Create symbolic link `{}` to `{}` at {:?}", name, target, inode);
+
+        if let Some(fs) = self.get_fs_mount(inode.mount_id)
+        {
+            let index = fs.create_symlink(inode, name.clone(), target)?;
+            self.forget_missing_entry(inode, &name);
+
+            Ok(index)
+        }
+        else
+        {
+            Err(FilesystemError::UnableToFindDiskMount(inode.mount_id))
+        }
+    }
+
+    /// Read the target of the symbolic link at the given inode
+    fn read_symlink(&mut self, inode: FilesystemIndex) -> FilesystemResult<String>
+    {
+        if let Some(fs) = self.get_fs_mount(inode.mount_id)
+        {
+            fs.read_symlink(inode)
         }
         else
         {
@@ -361,13 +749,22 @@ impl Filesystem for FilesystemInterface
     }
 
     /// Create a directory in the directory at the given inode
-    fn create_directory(&mut self, inode: FilesystemIndex, name: String) -> FilesystemResult<FilesystemIndex>
+    fn create_directory(&mut self, inode: FilesystemIndex, name: String, mode: u16) -> FilesystemResult<FilesystemIndex>
     {
         kdebugln!(Filesystem, "Create directory `{}` at {:?}", name, inode);
 
         if let Some(fs) = self.get_fs_mount(inode.mount_id)
         {
-            fs.create_directory(inode, name)
+            let index = fs.create_directory(inode, name.clone(), mode)?;
+            self.forget_missing_entry(inode, &name);
+
+            // Drop any stale entries for the new path from the index
+            if let Some(parent) = self.indexed.get(&inode).cloned()
+            {
+                self.invalidate_index(&OwnedPath::new(format!("{}/{}", parent, name)))?;
+            }
+
+            Ok(index)
         }
         else
         {
@@ -380,6 +777,8 @@ impl Filesystem for FilesystemInterface
     {
         kdebugln!(Filesystem, "Remove inode {:?}", inode);
 
+        crate::process::textcache::get_text_cache().invalidate(inode);
+
         if let Some(fs) = self.get_fs_mount(inode.mount_id)
         {
             fs.remove_inode(inode)
@@ -411,6 +810,87 @@ impl Filesystem for FilesystemInterface
         }
     }
 
+    /// Add a directory entry for an existing inode to the directory at the given inode
+    fn add_dir_entry(&mut self, directory_index: FilesystemIndex, inode: FilesystemIndex, name: String) -> FilesystemResult<()>
+    {
+        kdebugln!(Filesystem, "Add directory entry {} for {:?} in {:?}", name, inode, directory_index);
+
+        if let Some(fs) = self.get_fs_mount(directory_index.mount_id)
+        {
+            fs.add_dir_entry(directory_index, inode, name.clone())?;
+            self.forget_missing_entry(directory_index, &name);
+
+            // Drop any stale entries for the new path from the index
+            if let Some(parent) = self.indexed.get(&directory_index).cloned()
+            {
+                self.invalidate_index(&OwnedPath::new(format!("{}/{}", parent, name)))?;
+            }
+
+            Ok(())
+        }
+        else
+        {
+            Err(FilesystemError::UnableToFindDiskMount(directory_index.mount_id))
+        }
+    }
+
+    /// Move a directory entry, this is passed on to the filesystem holding the
+    /// entry as a single operation, and the index is updated once it is done
+    fn rename_inode(&mut self, inode: FilesystemIndex, old_directory: FilesystemIndex, old_name: String, new_directory: FilesystemIndex, new_name: String) -> FilesystemResult<()>
+    {
+        kdebugln!(Filesystem, "Rename {} in {:?} to {} in {:?}", old_name, old_directory, new_name, new_directory);
+
+        if old_directory.mount_id != new_directory.mount_id
+        {
+            return Err(FilesystemError::CrossDeviceLink);
+        }
+
+        let old_path = self.indexed.get(&old_directory).cloned().map(|parent| OwnedPath::new(format!("{}/{}", parent, old_name)));
+        let new_path = self.indexed.get(&new_directory).cloned().map(|parent| OwnedPath::new(format!("{}/{}", parent, new_name)));
+
+        let result = self.get_fs_mount_error(old_directory.mount_id)?.rename_inode(inode, old_directory, old_name, new_directory, new_name.clone());
+        self.forget_missing_entry(new_directory, &new_name);
+
+        // Drop every path under either name from both directions of the index
+        // together, so no lookup can see the old and new locations at once
+        if let (Some(old_path), Some(new_path)) = (old_path, new_path)
+        {
+            self.invalidate_index(&old_path)?;
+            self.invalidate_index(&new_path)?;
+
+            self.indexed.retain(|_, path| !path.as_str().starts_with(old_path.as_str()) && !path.as_str().starts_with(new_path.as_str()));
+        }
+        else
+        {
+            self.index = BTreeMap::new();
+            self.indexed = BTreeMap::new();
+        }
+
+        result
+    }
+
+    /// Remove a directory, this is passed on to the filesystem holding the
+    /// directory so that it can apply its own rules for removal
+    fn remove_directory(&mut self, inode: FilesystemIndex, parent: FilesystemIndex, name: String) -> FilesystemResult<()>
+    {
+        kdebugln!(Filesystem, "Remove directory {} in {:?}", name, parent);
+
+        if let Some(fs) = self.get_fs_mount(inode.mount_id)
+        {
+            fs.remove_directory(inode, parent, name)?;
+
+            let path = self.inode_to_path(parent)?.to_owned();
+
+            self.invalidate_index(&path)?;
+
+            Ok(())
+        }
+        else
+        {
+            Err(FilesystemError::UnableToFindDiskMount(inode.mount_id))
+        }
+    }
+
     /// Increment the number of links to an inode
     fn increment_links(&mut self, inode: FilesystemIndex) -> FilesystemResult<usize>
     {
@@ -461,6 +941,8 @@ impl Filesystem for FilesystemInterface
     {
         kdebugln!(Filesystem, "Write data to inode {:?}", inode);
 
+        crate::process::textcache::get_text_cache().invalidate(inode);
+
         if let Some(fs) = self.get_fs_mount(inode.mount_id)
         {
             fs.write_inode(inode, data)
@@ -471,6 +953,23 @@ impl Filesystem for FilesystemInterface
         }
     }
 
+    /// Change the length of the file at an inode
+    fn truncate_inode(&mut self, inode: FilesystemIndex, length: usize) -> FilesystemResult<()>
+    {
+        kdebugln!(Filesystem, "Truncate inode {:?} to {} bytes", inode, length);
+
+        crate::process::textcache::get_text_cache().invalidate(inode);
+
+        if let Some(fs) = self.get_fs_mount(inode.mount_id)
+        {
+            fs.truncate_inode(inode, length)
+        }
+        else
+        {
+            Err(FilesystemError::UnableToFindDiskMount(inode.mount_id))
+        }
+    }
+
     /// Mount a filesystem at the given inode
     fn mount_fs_at(&mut self, inode: FilesystemIndex, root: FilesystemIndex, name: String) -> FilesystemResult<()>
     {
@@ -486,6 +985,21 @@ impl Filesystem for FilesystemInterface
         }
     }
 
+    /// Remove a filesystem mounted at the given inode
+    fn unmount_fs_at(&mut self, inode: FilesystemIndex, root: FilesystemIndex) -> FilesystemResult<()>
+    {
+        kdebugln!(Filesystem, "Unmount fs starting at {:?} from inode {:?}", root, inode);
+
+        if let Some(fs) = self.get_fs_mount(inode.mount_id)
+        {
+            fs.unmount_fs_at(inode, root)
+        }
+        else
+        {
+            Err(FilesystemError::UnableToFindDiskMount(inode.mount_id))
+        }
+    }
+
     /// Open a filedescriptor for the given inode
     fn open_fd(&mut self, inode: FilesystemIndex, mode: usize) -> FilesystemResult<Box<dyn crate::process::descriptor::FileDescriptor>>
     {
@@ -1,8 +1,222 @@
+//! System Halt and Shutdown
+//!
+//! An orderly shutdown first sends SIGTERM to every process, then SIGKILL to
+//! any which are still alive once the grace period runs out. After all of the
+//! processes are gone the filesystems are synced and unmounted and the power
+//! driver is used to turn the system off or restart it.
+
 use crate::*;
 
+use drivers::timer::KernelTime;
+
+use process::process::ProcessState;
+use process::signals::{POSIXSignal, SignalType};
+
+// Time processes are given to exit after SIGTERM
+const TERMINATE_GRACE_MS: usize = 2000;
+
+// Time processes are given to exit after SIGKILL before they are removed
+const KILL_GRACE_MS: usize = 500;
+
+/// Stage of the shutdown sequence
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShutdownState
+{
+    Running,
+    Terminating { deadline: KernelTime },
+    Killing { deadline: KernelTime }
+}
+
+/// What is done once the shutdown sequence completes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShutdownAction
+{
+    PowerOff,
+    Restart
+}
+
+// Current stage of the shutdown sequence
+static mut SHUTDOWN_STATE: ShutdownState = ShutdownState::Running;
+
+// Action taken at the end of the shutdown sequence
+static mut SHUTDOWN_ACTION: ShutdownAction = ShutdownAction::PowerOff;
+
+/// Get the current stage of the shutdown sequence
+pub fn shutdown_state() -> ShutdownState
+{
+    unsafe { SHUTDOWN_STATE }
+}
+
+/// Check if a shutdown has been requested
+pub fn shutdown_in_progress() -> bool
+{
+    shutdown_state() != ShutdownState::Running
+}
+
+/// Get the time the given number of milliseconds from now
+fn deadline_in(milliseconds: usize) -> KernelTime
+{
+    unsafe { &drivers::TIMER_DRIVER }.time() + KernelTime::milliseconds(milliseconds)
+}
+
+/// Send a signal to every process which has not exited
+fn signal_all(sig_type: SignalType)
+{
+    if let Some(manager) = process::scheduler::get_process_manager()
+    {
+        let pids: Vec<_> = manager.processes.values()
+            .filter(|proc| !matches!(proc.get_state(), ProcessState::Zombie | ProcessState::Dead))
+            .map(|proc| proc.pid)
+            .collect();
+
+        for pid in pids
+        {
+            if manager.send_signal(pid, POSIXSignal::new(pid, 0, sig_type)).is_err()
+            {
+                kwarnln!("Unable to send {:?} to PID {} during shutdown", sig_type, pid);
+            }
+        }
+    }
+}
+
+/// Count the processes which have not exited
+fn live_processes() -> usize
+{
+    process::scheduler::get_process_manager()
+        .map(|manager| manager.processes.values().filter(|proc| !matches!(proc.get_state(), ProcessState::Zombie | ProcessState::Dead)).count())
+        .unwrap_or(0)
+}
+
+/// Begin an orderly shutdown which powers the system off, this returns
+/// immediately and the shutdown is carried out over the following scheduler
+/// ticks
+pub fn request_shutdown()
+{
+    begin_shutdown(ShutdownAction::PowerOff);
+}
+
+/// Begin an orderly shutdown which restarts the system
+pub fn request_restart()
+{
+    begin_shutdown(ShutdownAction::Restart);
+}
+
+/// Start the shutdown sequence, a shutdown which is already in progress keeps
+/// the action it was started with
+fn begin_shutdown(action: ShutdownAction)
+{
+    if shutdown_in_progress()
+    {
+        return;
+    }
+
+    kprintln!("The system is going down for {}", match action { ShutdownAction::PowerOff => "power off", ShutdownAction::Restart => "restart" });
+
+    unsafe { SHUTDOWN_ACTION = action };
+
+    signal_all(SignalType::SIGTERM);
+
+    unsafe { SHUTDOWN_STATE = ShutdownState::Terminating { deadline: deadline_in(TERMINATE_GRACE_MS) } };
+}
+
+/// Advance the shutdown sequence, this is called on every scheduler tick
+pub fn shutdown_tick()
+{
+    let now = unsafe { &drivers::TIMER_DRIVER }.time();
+
+    match shutdown_state()
+    {
+        ShutdownState::Running => {},
+        ShutdownState::Terminating { deadline } =>
+        {
+            if live_processes() == 0
+            {
+                finish_shutdown();
+            }
+            else if now > deadline
+            {
+                kwarnln!("{} processes did not exit after SIGTERM", live_processes());
+
+                signal_all(SignalType::SIGKILL);
+
+                unsafe { SHUTDOWN_STATE = ShutdownState::Killing { deadline: deadline_in(KILL_GRACE_MS) } };
+            }
+        },
+        ShutdownState::Killing { deadline } =>
+        {
+            if live_processes() == 0 || now > deadline
+            {
+                finish_shutdown();
+            }
+        }
+    }
+}
+
+/// Remove every process, sync and unmount the filesystems and power off or
+/// restart
+fn finish_shutdown() -> !
+{
+    kprintln!("Unmounting filesystems");
+
+    if let Some(manager) = process::scheduler::get_process_manager()
+    {
+        // Dropping the processes closes their descriptors and writes back any
+        // shared file mappings before the filesystems go away
+        for (pid, mut proc) in core::mem::take(&mut manager.processes)
+        {
+            if !matches!(proc.get_state(), ProcessState::Zombie | ProcessState::Dead)
+            {
+                kwarnln!("Killing PID {}", pid);
+                proc.kill_by_signal(SignalType::SIGKILL);
+            }
+        }
+    }
+
+    if let Some(vfs) = fs::vfs::get_vfs_reference()
+    {
+        if let Err(e) = vfs.unmount_all()
+        {
+            kerrorln!("Unable to unmount filesystems: {:?}", e);
+        }
+    }
+
+    match unsafe { SHUTDOWN_ACTION }
+    {
+        ShutdownAction::PowerOff => power_off(),
+        ShutdownAction::Restart => restart()
+    }
+}
+
+/// Turn the system off
+pub fn power_off() -> !
+{
+    kprintln!("System Halt");
+
+    unsafe { crate::drivers::POWER_DRIVER.shutdown() };
+
+    loop
+    {
+        unsafe { riscv::asm::wfi() };
+    }
+}
+
+/// Restart the system
+pub fn restart() -> !
+{
+    kprintln!("System Restart");
+
+    unsafe { crate::drivers::POWER_DRIVER.reboot() };
+
+    loop
+    {
+        unsafe { riscv::asm::wfi() };
+    }
+}
+
+/// Halt the system immediately
 pub fn kernel_halt()
 {
     kprintln!("System Halt");
 
     unsafe { crate::drivers::POWER_DRIVER.shutdown() };
-}
\ No newline at end of file
+}
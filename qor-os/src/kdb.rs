@@ -0,0 +1,205 @@
+//! Kernel Monitor
+//!
+//! A small line based monitor entered with SysRq `x`, for looking around the
+//! kernel once userspace has stopped responding. It runs inside the interrupt
+//! handler and polls the UART, so nothing else runs until it is left with
+//! `go`.
+
+use crate::*;
+
+use process::PID;
+
+// Longest command line accepted
+const LINE_MAX: usize = 128;
+
+// Bytes shown by `md` when no length is given
+const DEFAULT_DUMP_LENGTH: usize = 64;
+
+// Largest dump printed by a single `md`
+const MAX_DUMP_LENGTH: usize = 4096;
+
+/// Enter the monitor, returning once the user leaves it
+pub fn enter()
+{
+    kprintln!("kdb: Entered kernel monitor, type help for commands");
+
+    loop
+    {
+        kprint!("kdb> ");
+
+        let line = read_line();
+        let mut words = line.split_whitespace();
+
+        let command = match words.next()
+        {
+            Some(command) => command,
+            None => continue
+        };
+
+        let args: Vec<&str> = words.collect();
+
+        match command
+        {
+            "help" => show_help(),
+            "ps" => sysrq::dump_processes(),
+            "mem" => sysrq::dump_memory(),
+            "md" => dump_memory_range(&args),
+            "pt" => dump_page_table(&args),
+            "sync" => sysrq::sync_filesystems(),
+            "reboot" =>
+            {
+                kprintln!("kdb: Rebooting");
+                unsafe { drivers::POWER_DRIVER.reboot() };
+            },
+            "go" | "exit" => break,
+            _ => kprintln!("kdb: Unknown command `{}`", command)
+        }
+    }
+
+    kprintln!("kdb: Leaving kernel monitor");
+}
+
+/// List the available commands
+fn show_help()
+{
+    kprintln!("  help              show this message");
+    kprintln!("  ps                list processes");
+    kprintln!("  mem               show memory usage");
+    kprintln!("  md ADDR [LEN]     dump kernel memory");
+    kprintln!("  pt PID            dump the page table of a process");
+    kprintln!("  sync              sync every filesystem");
+    kprintln!("  reboot            reboot the system");
+    kprintln!("  go                leave the monitor");
+}
+
+/// Read a line from the console, echoing it back and handling backspace
+fn read_line() -> String
+{
+    let uart = drivers::get_uart_driver();
+    let mut line = String::new();
+
+    loop
+    {
+        match uart.poll_byte()
+        {
+            b'\r' | b'\n' =>
+            {
+                kprintln!();
+                return line;
+            },
+            0x08 | 0x7f =>
+            {
+                if line.pop().is_some()
+                {
+                    kprint!("\x08 \x08");
+                }
+            },
+            byte if (0x20..0x7f).contains(&byte) && line.len() < LINE_MAX =>
+            {
+                line.push(byte as char);
+                kprint!("{}", byte as char);
+            },
+            _ => {}
+        }
+    }
+}
+
+/// Parse a number, in hex if prefixed with `0x`
+fn parse_number(text: &str) -> Option<usize>
+{
+    if let Some(hex) = text.strip_prefix("0x")
+    {
+        usize::from_str_radix(hex, 16).ok()
+    }
+    else
+    {
+        text.parse().ok()
+    }
+}
+
+/// Dump a range of kernel memory as hex and ascii, only addresses between the
+/// start of the kernel image and the end of the heap can be read, as anything
+/// else may fault
+fn dump_memory_range(args: &[&str])
+{
+    let start = match args.first().and_then(|arg| parse_number(arg))
+    {
+        Some(start) => start,
+        None =>
+        {
+            kprintln!("kdb: usage: md ADDR [LEN]");
+            return;
+        }
+    };
+
+    let length = match args.get(1)
+    {
+        Some(arg) => match parse_number(arg)
+        {
+            Some(length) => length.min(MAX_DUMP_LENGTH),
+            None =>
+            {
+                kprintln!("kdb: usage: md ADDR [LEN]");
+                return;
+            }
+        },
+        None => DEFAULT_DUMP_LENGTH
+    };
+
+    let end = match start.checked_add(length)
+    {
+        Some(end) if start >= mem::lds::text_start() && end <= mem::lds::heap_end() => end,
+        _ =>
+        {
+            kprintln!("kdb: 0x{:x} is outside of kernel memory (0x{:x} - 0x{:x})", start, mem::lds::text_start(), mem::lds::heap_end());
+            return;
+        }
+    };
+
+    for line in (start..end).step_by(16)
+    {
+        let bytes: Vec<u8> = (line..end.min(line + 16)).map(|addr| unsafe { (addr as *const u8).read_volatile() }).collect();
+
+        kprint!("0x{:016x}:", line);
+
+        for i in 0..16
+        {
+            match bytes.get(i)
+            {
+                Some(byte) => kprint!(" {:02x}", byte),
+                None => kprint!("   ")
+            }
+        }
+
+        kprint!("  ");
+
+        for byte in &bytes
+        {
+            kprint!("{}", if (0x20..0x7f).contains(byte) { *byte as char } else { '.' });
+        }
+
+        kprintln!();
+    }
+}
+
+/// Dump the mappings in the page table of a process
+fn dump_page_table(args: &[&str])
+{
+    let pid = match args.first().and_then(|arg| arg.parse::<PID>().ok())
+    {
+        Some(pid) => pid,
+        None =>
+        {
+            kprintln!("kdb: usage: pt PID");
+            return;
+        }
+    };
+
+    let proc = process::scheduler::get_process_manager().and_then(|manager| manager.processes.get(&pid));
+
+    match proc.and_then(|proc| unsafe { proc.root.as_ref() })
+    {
+        Some(table) => table.display_mapping(),
+        None => kprintln!("kdb: No process with pid {}", pid)
+    }
+}
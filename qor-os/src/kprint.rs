@@ -1,15 +1,122 @@
+//! Kernel Printing
+//!
+//! Debug, warning and error messages are logged with a prefix giving the time
+//! since boot, the level and the subsystem, as in
+//! `[   12.3456] debug [fs] message`. The prefix is only written at the start
+//! of a line, so a message can be built up over several prints.
+
+use crate::*;
+
+use debug::DebugCategories;
+
 // Flag to set if the output should be colored
 pub const COLORED: bool = true;
 
+// Set while the last byte written to the console ended a line
+static mut AT_LINE_START: bool = true;
+
+/// Level of a logged message
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogLevel
+{
+    Debug,
+    Warning,
+    Error
+}
+
+impl LogLevel
+{
+    /// Get the name of the level as printed in the prefix
+    pub const fn name(&self) -> &'static str
+    {
+        match self
+        {
+            LogLevel::Debug => "debug",
+            LogLevel::Warning => "warn",
+            LogLevel::Error => "error"
+        }
+    }
+
+    /// Get the escape sequence for the color of messages at this level
+    pub const fn color(&self) -> &'static str
+    {
+        match self
+        {
+            LogLevel::Debug => "\x1B[34m",
+            LogLevel::Warning => "\x1B[33m",
+            LogLevel::Error => "\x1B[31m"
+        }
+    }
+}
+
+/// Writer for the console which keeps track of whether the output is at the
+/// start of a line
+struct ConsoleWriter;
+
+impl core::fmt::Write for ConsoleWriter
+{
+    fn write_str(&mut self, s: &str) -> core::fmt::Result
+    {
+        if let Some(last) = s.bytes().last()
+        {
+            unsafe { AT_LINE_START = last == b'\n' };
+        }
+
+        // Safety: This is safe because overlapping writes is acceptable if annoying
+        unsafe { &mut drivers::UART_DRIVER }.write_str(s)
+    }
+}
+
+/// Write to the console without a prefix
+pub fn print(args: core::fmt::Arguments)
+{
+    use core::fmt::Write;
+
+    let _ = ConsoleWriter.write_fmt(args);
+}
+
+/// End the line being written, if any
+pub fn finish_line()
+{
+    if !unsafe { AT_LINE_START }
+    {
+        print(format_args!("\r\n"));
+    }
+}
+
+/// Log a message, prefixing it with the time, level and subsystem if it
+/// starts a new line
+pub fn log(level: LogLevel, category: DebugCategories, args: core::fmt::Arguments)
+{
+    use core::fmt::Write;
+
+    if unsafe { AT_LINE_START }
+    {
+        let uptime = drivers::timer::uptime().as_nanoseconds() / 100_000;
+
+        print(format_args!("[{:>5}.{:04}] {:<5} [{}] ", uptime / 10_000, uptime % 10_000, level.name(), category.tag()));
+    }
+
+    // The color codes are left out of the line tracking
+    if COLORED
+    {
+        let _ = unsafe { &mut drivers::UART_DRIVER }.write_str(level.color());
+    }
+
+    print(args);
+
+    if COLORED
+    {
+        let _ = unsafe { &mut drivers::UART_DRIVER }.write_str("\x1B[m");
+    }
+}
+
 /// Kernel print function
 #[macro_export]
 macro_rules! kprint
 {
     ($($args:tt)+) => ({
-        use core::fmt::Write;
-
-        // Safety: This is safe because overlapping writes is acceptable if annoying
-		let _ = write!(unsafe { &mut crate::drivers::UART_DRIVER }, $($args)+);    
+        crate::kprint::print(format_args!($($args)+))
     });
 }
 
@@ -33,30 +140,16 @@ macro_rules! kprintln
 macro_rules! kdebug
 {
     ($mode:ident, $fmt:expr, $($args:tt)+) => ({
-        if crate::debug::check_debug(crate::debug::DebugCategories::$mode)
+        if crate::debug::should_log(crate::debug::DebugCategories::$mode)
         {
-            if crate::kprint::COLORED
-            {
-                crate::kprint!(concat!("\x1B[34m", $fmt, "\x1B[m"), $($args)+)
-            }
-            else
-            {
-                crate::kprint!($fmt, $($args)+);
-            }
+            crate::kprint::log(crate::kprint::LogLevel::Debug, crate::debug::DebugCategories::$mode, format_args!($fmt, $($args)+))
         }
     });
     
     ($mode:ident, $fmt:expr) => ({
-        if crate::debug::check_debug(crate::debug::DebugCategories::$mode)
+        if crate::debug::should_log(crate::debug::DebugCategories::$mode)
         {
-            if crate::kprint::COLORED
-            {
-                crate::kprint!(concat!("\x1B[34m", $fmt, "\x1B[m"))
-            }
-            else
-            {
-                crate::kprint!($fmt);
-            }
+            crate::kprint::log(crate::kprint::LogLevel::Debug, crate::debug::DebugCategories::$mode, format_args!($fmt))
         }
     });
 
@@ -98,26 +191,20 @@ macro_rules! kdebugln
 #[macro_export]
 macro_rules! kwarn
 {
+    ($mode:ident, $fmt:expr, $($args:tt)+) => ({
+        crate::kprint::log(crate::kprint::LogLevel::Warning, crate::debug::DebugCategories::$mode, format_args!($fmt, $($args)+))
+    });
+
+    ($mode:ident, $fmt:expr) => ({
+        crate::kprint::log(crate::kprint::LogLevel::Warning, crate::debug::DebugCategories::$mode, format_args!($fmt))
+    });
+
     ($fmt:expr, $($args:tt)+) => ({
-        if crate::kprint::COLORED
-        {
-            crate::kprint!(concat!("\x1B[33m", $fmt, "\x1B[m"), $($args)+)
-        }
-        else
-        {
-            crate::kprint!($fmt, $($args)+);
-        }
+        crate::kwarn!(Other, $fmt, $($args)+)
     });
     
     ($fmt:expr) => ({
-        if crate::kprint::COLORED
-        {
-            crate::kprint!(concat!("\x1B[33m", $fmt, "\x1B[m"))
-        }
-        else
-        {
-            crate::kprint!($fmt);
-        }
+        crate::kwarn!(Other, $fmt)
     });
 }
 
@@ -127,6 +214,14 @@ macro_rules! kwarnln
 {
     () => ({crate::kwarn!("\r\n")});
 
+    ($mode:ident, $fmt:expr) => ({
+        crate::kwarn!($mode, concat!($fmt, "\r\n"))
+    });
+
+    ($mode:ident, $fmt:expr, $($args:tt)+) => ({
+        crate::kwarn!($mode, concat!($fmt, "\r\n"), $($args)+)
+    });
+
     ($fmt:expr) => ({
         crate::kwarn!(concat!($fmt, "\r\n"))
     });
@@ -140,26 +235,20 @@ macro_rules! kwarnln
 #[macro_export]
 macro_rules! kerror
 {
+    ($mode:ident, $fmt:expr, $($args:tt)+) => ({
+        crate::kprint::log(crate::kprint::LogLevel::Error, crate::debug::DebugCategories::$mode, format_args!($fmt, $($args)+))
+    });
+
+    ($mode:ident, $fmt:expr) => ({
+        crate::kprint::log(crate::kprint::LogLevel::Error, crate::debug::DebugCategories::$mode, format_args!($fmt))
+    });
+
     ($fmt:expr, $($args:tt)+) => ({
-        if crate::kprint::COLORED
-        {
-            crate::kprint!(concat!("\x1B[31m", $fmt, "\x1B[m"), $($args)+)
-        }
-        else
-        {
-            crate::kprint!($fmt, $($args)+);
-        }
+        crate::kerror!(Other, $fmt, $($args)+)
     });
     
     ($fmt:expr) => ({
-        if crate::kprint::COLORED
-        {
-            crate::kprint!(concat!("\x1B[31m", $fmt, "\x1B[m"))
-        }
-        else
-        {
-            crate::kprint!($fmt);
-        }
+        crate::kerror!(Other, $fmt)
     });
 }
 
@@ -169,6 +258,14 @@ macro_rules! kerrorln
 {
     () => ({crate::kerror!("\r\n")});
 
+    ($mode:ident, $fmt:expr) => ({
+        crate::kerror!($mode, concat!($fmt, "\r\n"))
+    });
+
+    ($mode:ident, $fmt:expr, $($args:tt)+) => ({
+        crate::kerror!($mode, concat!($fmt, "\r\n"), $($args)+)
+    });
+
     ($fmt:expr) => ({
         crate::kerror!(concat!($fmt, "\r\n"))
     });
@@ -27,17 +27,23 @@ use alloc::{vec::*, boxed::*, string::*};
 
 // Includes
 mod asm;
+mod bootargs;
 mod debug;
 mod drivers;
 mod errno;
 mod fs;
 mod halt;
+mod kdb;
 mod mem;
 mod kprint;
 mod panic;
+mod percpu;
+mod perf;
 mod process;
 mod resources;
+mod suspend;
 mod syscalls;
+mod sysrq;
 mod test;
 mod trap;
 mod utils;
@@ -45,11 +51,22 @@ mod utils;
 /// Kernel Initialize Function (Called immediately after boot)
 #[no_mangle]
 pub extern "C"
-fn kinit()
+fn kinit(device_tree: usize)
 {
+    // Initialize the per-CPU data areas
+    percpu::init_per_cpu();
+
+    // Read the boot parameters before the device tree can be overwritten
+    bootargs::init(device_tree);
+    debug::init_verbosity();
+
     // Initialize the UART driver
     drivers::init_uart_driver();
     kdebugln!(Initialization, "UART Driver Initialized");
+    kdebugln!(Initialization, "Command line: {}", bootargs::command_line());
+
+    // Note the boot time for uptime reporting
+    drivers::timer::record_boot_time();
 
     // Initialize the global kernel page allocator
     mem::init_kernel_page_allocator();
@@ -100,11 +117,9 @@ fn kmain()
     drivers::virtio::init_virtio_interrupts();
     kdebugln!(Initialization, "VirtIO Interrupts Initialized");
 
-    // Check for a block device
-    if drivers::virtio::get_block_driver(0).is_none()
-    {
-        panic!("Cannot boot without block device");
-    }
+    // Create the RAM disks
+    drivers::block::init_ram_disks();
+    kdebugln!(Initialization, "RAM Disks Initialized");
 
     // Initialize the graphics driver
     if drivers::gpu::init_graphics_driver()
@@ -112,33 +127,84 @@ fn kmain()
         kdebugln!(Initialization, "Graphics Driver Initialized");
     }
 
+    // Register the ioctls accepted by each class of device
+    drivers::init_ioctl_classes();
+    kdebugln!(Initialization, "ioctl Classes Registered");
+
     let mut vfs = fs::vfs::FilesystemInterface::new();
-    let mut disk0 = fs::minix3::Minix3Filesystem::new(0);
+
+    // Boot from the first disk, falling back to a fresh filesystem on the
+    // first RAM disk when no disk is attached
+    let (root_source, root_device): (&str, Box<dyn drivers::block::BlockDevice>) =
+        if let Some(driver) = drivers::virtio::get_block_driver(0)
+        {
+            ("/dev/vda", Box::new(driver))
+        }
+        else if let Some(disk) = drivers::block::get_ram_disk(0)
+        {
+            kwarnln!("No block device attached, using /dev/ram0 as the root device");
+
+            if let Err(e) = disk.format_minix3(0)
+            {
+                panic!("Unable to format /dev/ram0 as the root device: {}", e as isize);
+            }
+
+            ("/dev/ram0", Box::new(disk))
+        }
+        else
+        {
+            panic!("Cannot boot without block device");
+        };
+
+    let mut disk0 = fs::minix3::Minix3Filesystem::new(root_device);
     let mut dev = fs::devfs::DevFilesystem::new();
     let mut proc = fs::procfs::ProcFilesystem::new();
+    let mut sys = fs::sysfs::SysFilesystem::new();
 
     use fs::fstrait::Filesystem;
     use libutils::paths::OwnedPath;
 
     vfs.init().unwrap();
-    disk0.init().unwrap();
+
+    if let Err(e) = disk0.init()
+    {
+        panic!("Unable to read the root filesystem on {}: {:?}", root_source, e);
+    }
+
     dev.init().unwrap();
     proc.init().unwrap();
+    sys.init().unwrap();
 
-    vfs.mount_fs(&OwnedPath::new("/"), Box::new(disk0)).unwrap();
-    vfs.mount_fs(&OwnedPath::new("/dev"), Box::new(dev)).unwrap();
-    vfs.mount_fs(&OwnedPath::new("/proc"), Box::new(proc)).unwrap();
+    vfs.mount_fs(root_source, &OwnedPath::new("/"), Box::new(disk0)).unwrap();
+    vfs.mount_fs("devfs", &OwnedPath::new("/dev"), Box::new(dev)).unwrap();
+    vfs.mount_fs("proc", &OwnedPath::new("/proc"), Box::new(proc)).unwrap();
+    vfs.mount_fs("sysfs", &OwnedPath::new("/sys"), Box::new(sys)).unwrap();
 
     vfs.index().unwrap();
 
     let elf_proc = process::loading::load_process(
         &mut vfs, 
+        &None,
+        None,
         &OwnedPath::new("/bin/init"), 
         &mut Vec::new(),
-        &mut vec![String::from("PATH=/bin\0")]).unwrap();
-    process::scheduler::get_init_process_mut().unwrap().register_child(elf_proc.pid);
+        &mut vec![String::from("PATH=/bin")]);
 
-    process::scheduler::add_process(elf_proc);
+    match elf_proc
+    {
+        Ok(elf_proc) =>
+        {
+            process::scheduler::get_init_process_mut().unwrap().register_child(elf_proc.pid);
+            process::scheduler::add_process(elf_proc);
+        },
+        // A freshly formatted RAM disk has no programs on it, which leaves
+        // only the built in init process running rather than a panic
+        Err(e) if root_source == "/dev/ram0" =>
+        {
+            kwarnln!("No /bin/init on {}, nothing will be started: {:?}", root_source, e);
+        },
+        Err(e) => panic!("Unable to start /bin/init from {}: {:?}", root_source, e)
+    }
     
     // Start the timer
     drivers::init_timer_driver(1000);
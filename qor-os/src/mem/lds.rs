@@ -15,6 +15,8 @@ extern "C"
     static KERNEL_STACK_END: usize;
     static HEAP_START: usize;
     static HEAP_END: usize;
+    static TRAMPOLINE_START: usize;
+    static TRAMPOLINE_END: usize;
 }
 
 /// Get the text start address as a usize
@@ -112,3 +114,19 @@ pub fn heap_end() -> usize
 {
 	unsafe { HEAP_END }
 }
+
+/// Get the trampoline start address as a usize
+/// Safety: Because this value should have been read properly from the linker
+/// script, this is safe
+pub fn trampoline_start() -> usize
+{
+	unsafe { TRAMPOLINE_START }
+}
+
+/// Get the trampoline end address as a usize
+/// Safety: Because this value should have been read properly from the linker
+/// script, this is safe
+pub fn trampoline_end() -> usize
+{
+	unsafe { TRAMPOLINE_END }
+}
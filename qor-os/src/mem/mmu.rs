@@ -109,6 +109,12 @@ impl PageTableEntryFlags
     {
         Self(128)
     }
+
+    /// Get these flags with the given flags cleared
+    pub fn without(self, other: Self) -> Self
+    {
+        Self(self.0 & !other.0)
+    }
 }
 
 impl core::ops::BitOr<PageTableEntryFlags> for PageTableEntryFlags
@@ -156,6 +162,17 @@ impl PageTableEntry
     }
 }
 
+/// Flush any cached translation of the given virtual address, processes do
+/// not use address space identifiers so the entry is flushed for all of them
+pub fn flush_tlb_page(vaddr: usize)
+{
+    unsafe { core::arch::asm!("sfence.vma {0}, zero", in(reg) vaddr) };
+}
+
+// Index of the first root entry in the kernel's half of the address space,
+// the entries from here on are shared between every page table
+const KERNEL_SPACE_ENTRY: usize = (mem::KERNEL_SPACE_START >> 30) & 0x1ff;
+
 /// Sv39 Page Table
 #[derive(Debug)]
 pub struct PageTable
@@ -295,8 +312,8 @@ impl PageTable
             // Loop ever every entry
             for entry in &self.entries
             {
-                // If the entry is valid
-                if entry.flag() & PageTableEntryFlags::valid()
+                // If the entry is valid and links to another table
+                if entry.flag() & PageTableEntryFlags::valid() && entry.flag().0 & 0xE == 0
                 {
                     // Then drop the page it links to
                     let page = (entry.0 & !0x3ff) << 2;
@@ -319,6 +336,12 @@ impl PageTable
     {
         kdebugln!(MemoryMapping, "Dropping the page table at 0x{:x}", self as *mut PageTable as usize);
 
+        // The kernel's half is shared, so must not be freed with this table
+        for entry in &mut self.entries[KERNEL_SPACE_ENTRY..]
+        {
+            *entry = PageTableEntry(0);
+        }
+
         self.drop_level(2);
     }
 
@@ -358,7 +381,7 @@ impl PageTable
                     // This level comes from the virtual address
                     if j < i
                     {
-                        result += vpn[j] << (12 + 9 * i);
+                        result += vpn[j] << (12 + 9 * j);
                     }
                     // This level comes from the physical address
                     else
@@ -382,6 +405,62 @@ impl PageTable
         Err(TranslationError::NoLeaf)
     }
 
+    /// Get the lowest level entry for a virtual address, if the tables leading
+    /// to it exist
+    fn level_zero_entry(&mut self, vaddr: usize) -> Option<&mut PageTableEntry>
+    {
+        let vpn = [
+				(vaddr >> 12) & 0x1ff,
+				(vaddr >> 21) & 0x1ff,
+				(vaddr >> 30) & 0x1ff,
+	        ];
+
+        let mut table = self;
+
+        for i in (1..=2).rev()
+        {
+            let entry = table.entries[vpn[i]];
+
+            // Stop at invalid entries and larger pages
+            if !(entry.flag() & PageTableEntryFlags::valid()) || entry.flag().0 & 0xE != 0
+            {
+                return None;
+            }
+
+            table = unsafe { (((entry.0 & !0x3ff) << 2) as *mut PageTable).as_mut().unwrap() };
+        }
+
+        Some(&mut table.entries[vpn[0]])
+    }
+
+    /// Get the flags of the 4 KiB page mapped at the given address, if there
+    /// is one
+    pub fn page_flags(&mut self, vaddr: usize) -> Option<PageTableEntryFlags>
+    {
+        self.level_zero_entry(vaddr)
+            .map(|entry| entry.flag())
+            .filter(|flags| *flags & PageTableEntryFlags::valid())
+    }
+
+    /// Replace the flags of the 4 KiB page mapped at the given address,
+    /// returning false if no page is mapped there
+    pub fn protect(&mut self, vaddr: usize, flags: PageTableEntryFlags) -> bool
+    {
+        kdebugln!(MemoryMapping, "Protecting Virt 0x{:x}", vaddr);
+
+        if let Some(entry) = self.level_zero_entry(vaddr)
+        {
+            if entry.flag() & PageTableEntryFlags::valid()
+            {
+                *entry = PageTableEntry::new((entry.0 >> 10) as usize, flags | PageTableEntryFlags::valid());
+
+                return true;
+            }
+        }
+
+        false
+    }
+
     /// Duplicate this memory map
     pub fn duplicate_map(&self) -> *mut PageTable
     {
@@ -392,6 +471,7 @@ impl PageTable
         let table = unsafe {(new_table as *mut PageTable).as_mut().unwrap()};
 
         self.duplicate_level(2, 0, table);
+        table.share_kernel_space();
 
         table as *mut PageTable
     }
@@ -401,8 +481,8 @@ impl PageTable
     {
         for (i, entry) in self.entries.iter().enumerate()
         {
-            // If this entry is invalid, skip
-            if !(entry.flag() & PageTableEntryFlags::valid())
+            // If this entry is invalid, or in the shared kernel half, skip
+            if !(entry.flag() & PageTableEntryFlags::valid()) || (level == 2 && i >= KERNEL_SPACE_ENTRY)
             {
                 continue;
             }
@@ -601,6 +681,47 @@ impl PageTable
         }
     }
 
+    /// Map a region of physical memory starting at the given virtual address,
+    /// using 2 MiB pages wherever both addresses are aligned to them
+    pub fn map_range(&mut self, vaddr: usize, paddr: usize, length: usize, flags: PageTableEntryFlags)
+    {
+        kdebugln!(MemoryMapping, "Mapping Virt 0x{:x} to Phys 0x{:x} - 0x{:x}", vaddr, paddr, paddr + length);
+
+        let offset = paddr & (4096 - 1);
+
+        let mut current_vaddr = vaddr - offset;
+        let mut current_paddr = paddr - offset;
+        let end = (paddr + length + 4096 - 1) & !(4096 - 1);
+
+        while current_paddr < end
+        {
+            if end - current_paddr >= 0x20_0000 && (current_paddr | current_vaddr) & (0x20_0000 - 1) == 0
+            {
+                self.inner_map(current_vaddr, current_paddr, flags, 1);
+
+                current_vaddr += 0x20_0000;
+                current_paddr += 0x20_0000;
+            }
+            else
+            {
+                self.inner_map(current_vaddr, current_paddr, flags, 0);
+
+                current_vaddr += 0x1000;
+                current_paddr += 0x1000;
+            }
+        }
+    }
+
+    /// Link the kernel's half of the address space into this table, the
+    /// lower levels are shared with the kernel's page table
+    pub fn share_kernel_space(&mut self)
+    {
+        if let Some(kernel) = mem::kernel_page_table()
+        {
+            self.entries[KERNEL_SPACE_ENTRY..].copy_from_slice(&kernel.entries[KERNEL_SPACE_ENTRY..]);
+        }
+    }
+
     /// Identity map a region of memory
     pub fn identity_map(&mut self, start_addr: usize, end_addr: usize, flags: PageTableEntryFlags)
     {
@@ -673,4 +794,17 @@ pub fn test_mapping_virtual_address_translation()
     assert_eq!(next_ptr, 0x1_0000_0123);
 
     table.drop_table();
-}
\ No newline at end of file
+}
+/// Test MMU - Translation through a 2 MiB page
+#[test_case]
+pub fn test_megapage_virtual_address_translation()
+{
+    let table = mem::mmu::PageTable::allocate();
+
+    table.map_range(0x3F_0000_0000, 0x1_0000_0000, 0x20_0000, PageTableEntryFlags::readable() | PageTableEntryFlags::writable());
+
+    assert_eq!(table.virt_to_phys(0x3F_0012_3456).unwrap(), 0x1_0012_3456);
+    assert_eq!(table.virt_to_phys(0x3F_001F_FFFF).unwrap(), 0x1_001F_FFFF);
+
+    table.drop_table();
+}
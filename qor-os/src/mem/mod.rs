@@ -6,6 +6,7 @@ pub mod alloc;
 pub mod lds;
 pub mod mmu;
 pub mod page;
+pub mod pagelist;
 
 // Tests
 #[cfg(test)]
@@ -14,6 +15,16 @@ pub mod test;
 // Page size for global use
 pub use page::PAGE_SIZE;
 
+// The Sv39 address space is split in two, the lower half belongs to the
+// process and the upper half to the kernel. The upper half maps kernel memory
+// without the user bit, and is shared by every page table.
+pub const USER_SPACE_END: usize = 0x40_0000_0000;
+pub const KERNEL_SPACE_START: usize = 0xFFFF_FFC0_0000_0000;
+
+// Address of the page holding the trap entry and exit code in every address
+// space
+pub const TRAMPOLINE: usize = 0xFFFF_FFFF_FFFF_F000;
+
 // Global Kernel Page Allocator
 static mut GLOBAL_KERNEL_PAGE_ALLOCATOR: *mut page::PageMap = 0 as *mut page::PageMap;
 
@@ -93,6 +104,26 @@ pub fn kpfree(addr: usize, count: usize) -> Result<(), page::KernelPageAllocatio
     }
 }
 
+/// Zero consecutive pages and then free them, this must be used for any
+/// memory which was accessible from userspace so that stale data is not handed
+/// to the next owner of the pages
+pub fn kpzfree(addr: usize, count: usize) -> Result<(), page::KernelPageAllocationError>
+{
+    let ptr = addr as *mut [u8; PAGE_SIZE];
+
+    // Write zeros to each page
+    for i in 0..count
+    {
+        // Safety: The pages are owned by the caller until they are freed below
+        unsafe
+        {
+            ptr.add(i).write_volatile([0; PAGE_SIZE]);
+        }
+    }
+
+    kpfree(addr, count)
+}
+
 /// Get the number of allocated pages on the kernel heap
 pub fn allocated_kernel_pages() -> usize
 {
@@ -131,6 +162,36 @@ pub fn total_kernel_pages() -> usize
     }
 }
 
+// Page table used by the kernel while running in supervisor mode
+static mut KERNEL_PAGE_TABLE: *mut mmu::PageTable = 0 as *mut mmu::PageTable;
+
+/// Get the address in the kernel's half of the address space which maps the
+/// given physical address
+pub fn kernel_address(paddr: usize) -> usize
+{
+    KERNEL_SPACE_START + paddr
+}
+
+/// Get the kernel's page table, if it has been created
+pub fn kernel_page_table() -> Option<&'static mmu::PageTable>
+{
+    unsafe { KERNEL_PAGE_TABLE.as_ref() }
+}
+
+/// Get the satp value which selects the kernel's page table, translation is
+/// left off until the table has been created
+pub fn kernel_satp() -> usize
+{
+    if unsafe { KERNEL_PAGE_TABLE.is_null() }
+    {
+        0
+    }
+    else
+    {
+        8 << 60 | unsafe { KERNEL_PAGE_TABLE } as usize >> 12
+    }
+}
+
 /// Identity map the kernel
 pub fn identity_map_kernel()
 {
@@ -166,9 +227,16 @@ pub fn identity_map_kernel()
     // Map the VirtIO MMIO
     page_table.identity_map(0x1000_1000, 0x1000_8000, PageTableEntryFlags::readable() | PageTableEntryFlags::writable());
 
+    // Map the kernel into the upper half, these mappings are shared with
+    // every process but are never accessible from user mode
+    page_table.map_range(kernel_address(lds::text_start()), lds::text_start(), lds::rodata_end() - lds::text_start(), PageTableEntryFlags::readable() | PageTableEntryFlags::executable() | PageTableEntryFlags::global());
+    page_table.map_range(kernel_address(lds::data_start()), lds::data_start(), lds::heap_end() - lds::data_start(), PageTableEntryFlags::readable() | PageTableEntryFlags::writable() | PageTableEntryFlags::global());
+
+    // Map the trampoline
+    page_table.map_range(TRAMPOLINE, lds::trampoline_start(), lds::trampoline_end() - lds::trampoline_start(), PageTableEntryFlags::readable() | PageTableEntryFlags::executable() | PageTableEntryFlags::global());
+
+    unsafe { KERNEL_PAGE_TABLE = page_table as *mut mmu::PageTable };
+
     // Write the current page to SATP
-    let root_ppn = page_table as *mut mmu::PageTable as usize >> 12;
-    let satp_val = 8 << 60 | root_ppn;
-    
-    riscv::register::satp::write(satp_val);
+    riscv::register::satp::write(kernel_satp());
 }
\ No newline at end of file
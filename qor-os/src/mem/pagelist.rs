@@ -0,0 +1,117 @@
+//! Page List Buffers
+//!
+//! Large kernel buffers are built from single pages which need not be
+//! physically contiguous, so they can be allocated however fragmented the page
+//! allocator is. The kernel runs with translation off, so the pages are not
+//! mapped side by side, instead the buffer is used through its segments, each
+//! of which lies within a single page.
+
+use crate::*;
+
+use super::page::KernelPageAllocationError;
+
+/// Kernel buffer made up of separately allocated pages
+pub struct PageListBuffer
+{
+    pages: Vec<usize>,
+    length: usize
+}
+
+impl PageListBuffer
+{
+    /// Allocate a zeroed buffer of the given length a page at a time, any
+    /// pages already allocated are freed again if the allocator runs out. An
+    /// empty buffer still holds a page, so it has a segment to pass on
+    pub fn new(length: usize) -> Result<Self, KernelPageAllocationError>
+    {
+        let count = ((length + super::PAGE_SIZE - 1) / super::PAGE_SIZE).max(1);
+
+        let mut buffer = Self
+        {
+            pages: Vec::with_capacity(count),
+            length
+        };
+
+        for _ in 0..count
+        {
+            buffer.pages.push(super::kpzalloc(1, "PageListBuffer")?);
+        }
+
+        Ok(buffer)
+    }
+
+    /// Get the length of the buffer in bytes
+    pub fn len(&self) -> usize
+    {
+        self.length
+    }
+
+    /// Check if the buffer is empty
+    pub fn is_empty(&self) -> bool
+    {
+        self.length == 0
+    }
+
+    /// Get the address and length of each piece of the first `length` bytes
+    /// of the buffer, in order, there is always at least one piece
+    pub fn segments(&self, length: usize) -> Vec<(*mut u8, usize)>
+    {
+        let length = length.min(self.length);
+        let count = ((length + super::PAGE_SIZE - 1) / super::PAGE_SIZE).max(1);
+
+        self.pages.iter().take(count).enumerate()
+            .map(|(i, page)| (*page as *mut u8, (length - i * super::PAGE_SIZE).min(super::PAGE_SIZE)))
+            .collect()
+    }
+
+    /// Copy data out of the buffer starting at the given offset, returning the
+    /// number of bytes copied
+    pub fn read(&self, offset: usize, data: &mut [u8]) -> usize
+    {
+        let count = data.len().min(self.length.saturating_sub(offset));
+        let mut copied = 0;
+
+        while copied < count
+        {
+            let position = offset + copied;
+            let page = self.pages[position / super::PAGE_SIZE] + position % super::PAGE_SIZE;
+            let size = (super::PAGE_SIZE - position % super::PAGE_SIZE).min(count - copied);
+
+            data[copied..copied + size].copy_from_slice(unsafe { core::slice::from_raw_parts(page as *const u8, size) });
+            copied += size;
+        }
+
+        count
+    }
+
+    /// Copy data into the buffer starting at the given offset, returning the
+    /// number of bytes copied
+    pub fn write(&mut self, offset: usize, data: &[u8]) -> usize
+    {
+        let count = data.len().min(self.length.saturating_sub(offset));
+        let mut copied = 0;
+
+        while copied < count
+        {
+            let position = offset + copied;
+            let page = self.pages[position / super::PAGE_SIZE] + position % super::PAGE_SIZE;
+            let size = (super::PAGE_SIZE - position % super::PAGE_SIZE).min(count - copied);
+
+            unsafe { core::slice::from_raw_parts_mut(page as *mut u8, size) }.copy_from_slice(&data[copied..copied + size]);
+            copied += size;
+        }
+
+        count
+    }
+}
+
+impl core::ops::Drop for PageListBuffer
+{
+    fn drop(&mut self)
+    {
+        for page in &self.pages
+        {
+            super::kpzfree(*page, 1).unwrap();
+        }
+    }
+}
@@ -165,4 +165,32 @@ pub fn test_kernel_byte_allocator_multiple()
     {
         unsafe { Box::from_raw(ptr) };
     }
-}
\ No newline at end of file
+}
+/// Test Page List Buffer - Data written across page boundaries is read back
+/// and every page is freed with the buffer
+#[test_case]
+pub fn test_page_list_buffer_across_pages()
+{
+    super::alloc::init_kernel_global_allocator(2);
+
+    let initial_pages = super::allocated_kernel_pages();
+
+    let mut buffer = super::pagelist::PageListBuffer::new(3 * super::PAGE_SIZE - 16).unwrap();
+    let data: Vec<u8> = (0..super::PAGE_SIZE + 64).map(|i| i as u8).collect();
+
+    assert_eq!(buffer.write(super::PAGE_SIZE - 32, &data), data.len());
+
+    let mut read = vec![0; data.len()];
+    assert_eq!(buffer.read(super::PAGE_SIZE - 32, &mut read), data.len());
+    assert_eq!(read, data);
+
+    // Accesses past the end are cut short
+    assert_eq!(buffer.write(3 * super::PAGE_SIZE - 24, &data), 8);
+
+    let lengths: Vec<usize> = buffer.segments(super::PAGE_SIZE + 1).iter().map(|(_, length)| *length).collect();
+    assert_eq!(lengths, [super::PAGE_SIZE, 1]);
+
+    drop(buffer);
+
+    assert_eq!(super::allocated_kernel_pages(), initial_pages);
+}
@@ -6,7 +6,8 @@ use crate::*;
 #[panic_handler]
 fn panic(info: &core::panic::PanicInfo) -> !
 {
-    kerror!("\nAborting: ");
+    kprint::finish_line();
+    kerror!("Aborting: ");
 
     if let Some(p) = info.location()
     {
@@ -0,0 +1,113 @@
+//! Per-CPU Data
+//!
+//! Each hart has its own data area holding the state which would otherwise be
+//! shared between harts, such as the running process and the trap depth. While
+//! in the kernel the `tp` register points to the area of the current hart, it
+//! is set at boot and again on every trap entry, the value userspace placed in
+//! `tp` is kept in the trap frame and restored when the trap returns.
+
+use crate::*;
+
+use core::arch::asm;
+
+use perf::PerfSample;
+use process::PID;
+use trap::TrapFrame;
+
+// Maximum number of harts supported
+// Must be kept in sync with trap.s
+pub const MAX_CPUS: usize = 8;
+
+// Size of the data area of each hart
+// Must be kept in sync with trap.s
+pub const PER_CPU_STRIDE: usize = 256;
+
+/// Event counters for a single hart
+#[derive(Debug, Clone, Copy)]
+pub struct PerCpuCounters
+{
+    pub traps: usize,
+    pub syscalls: usize,
+    pub interrupts: usize,
+    pub context_switches: usize
+}
+
+/// Data area for a single hart
+#[repr(C, align(256))]
+#[derive(Debug)]
+pub struct PerCpu
+{
+    pub hartid: usize,
+    pub current_pid: Option<PID>,
+    pub trap_depth: usize,
+    pub kernel_trap_frame: *mut TrapFrame,
+    pub trap_entry: Option<PerfSample>,
+    pub syscall_exit: Option<PerfSample>,
+    pub counters: PerCpuCounters
+}
+
+impl PerCpu
+{
+    /// Create an empty data area
+    pub const fn new() -> Self
+    {
+        Self
+        {
+            hartid: 0,
+            current_pid: None,
+            trap_depth: 0,
+            kernel_trap_frame: 0 as *mut TrapFrame,
+            trap_entry: None,
+            syscall_exit: None,
+            counters: PerCpuCounters
+            {
+                traps: 0,
+                syscalls: 0,
+                interrupts: 0,
+                context_switches: 0
+            }
+        }
+    }
+}
+
+// The assembly indexes the areas by shifting the hart id
+const _: () = assert!(core::mem::size_of::<PerCpu>() == PER_CPU_STRIDE);
+
+const EMPTY_PER_CPU: PerCpu = PerCpu::new();
+
+// Data areas for every hart, referenced by name from boot.s and trap.s
+#[no_mangle]
+static mut PER_CPU_AREAS: [PerCpu; MAX_CPUS] = [EMPTY_PER_CPU; MAX_CPUS];
+
+/// Initialize the data areas
+pub fn init_per_cpu()
+{
+    for (i, area) in unsafe { PER_CPU_AREAS.iter_mut() }.enumerate()
+    {
+        area.hartid = i;
+    }
+}
+
+/// Get the data area of the hart running this code
+pub fn this_cpu() -> &'static mut PerCpu
+{
+    let ptr: usize;
+    unsafe { asm!("mv {}, tp", out(reg) ptr) };
+
+    unsafe { (ptr as *mut PerCpu).as_mut() }.expect("Per-CPU data accessed before tp was set")
+}
+
+/// Get the data area of the given hart
+pub fn get_cpu(hartid: usize) -> Option<&'static mut PerCpu>
+{
+    unsafe { PER_CPU_AREAS.get_mut(hartid) }
+}
+
+/// Access a field of the data area of the current hart, `percpu!(field)` reads
+/// the field and `percpu!(field = value)` writes it
+#[macro_export]
+macro_rules! percpu
+{
+    ($($field:ident).+ = $value:expr) => { $crate::percpu::this_cpu().$($field).+ = $value };
+    ($($field:ident).+) => { $crate::percpu::this_cpu().$($field).+ };
+}
@@ -0,0 +1,216 @@
+//! Performance Counters
+//!
+//! Latency measurements taken with the RISC-V cycle and instret counters,
+//! each measurement keeps a histogram of the cycles taken with power of two
+//! buckets, and the results can be read from `/proc/perf`
+
+use crate::*;
+
+/// Number of histogram buckets, bucket `n` holds samples in `[2^n, 2^(n+1))`
+/// and the last bucket holds everything larger
+pub const HISTOGRAM_BUCKETS: usize = 24;
+
+/// Measurement being recorded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Measurement
+{
+    ContextSwitch,
+    SyscallEntry,
+    SyscallExit,
+    InterruptLatency,
+}
+
+impl Measurement
+{
+    /// Every measurement, in the order they are stored
+    pub const ALL: [Measurement; 4] = [Measurement::ContextSwitch, Measurement::SyscallEntry, Measurement::SyscallExit, Measurement::InterruptLatency];
+
+    /// Get the name of the measurement as displayed in procfs
+    pub fn name(&self) -> &'static str
+    {
+        match self
+        {
+            Measurement::ContextSwitch => "context_switch",
+            Measurement::SyscallEntry => "syscall_entry",
+            Measurement::SyscallExit => "syscall_exit",
+            Measurement::InterruptLatency => "interrupt_latency",
+        }
+    }
+}
+
+/// Reading of the cycle and instret counters
+#[derive(Debug, Clone, Copy)]
+pub struct PerfSample
+{
+    pub cycles: usize,
+    pub instructions: usize
+}
+
+impl PerfSample
+{
+    /// Read the counters
+    pub fn now() -> Self
+    {
+        Self
+        {
+            cycles: riscv::register::cycle::read(),
+            instructions: riscv::register::instret::read()
+        }
+    }
+}
+
+/// Histogram of the samples of a single measurement
+#[derive(Debug, Clone, Copy)]
+pub struct PerfCounter
+{
+    pub count: usize,
+    pub total_cycles: usize,
+    pub total_instructions: usize,
+    pub min_cycles: usize,
+    pub max_cycles: usize,
+    pub buckets: [usize; HISTOGRAM_BUCKETS]
+}
+
+impl PerfCounter
+{
+    /// Create a new counter with no samples
+    pub const fn new() -> Self
+    {
+        Self
+        {
+            count: 0,
+            total_cycles: 0,
+            total_instructions: 0,
+            min_cycles: usize::MAX,
+            max_cycles: 0,
+            buckets: [0; HISTOGRAM_BUCKETS]
+        }
+    }
+
+    /// Add a sample to the counter
+    pub fn record(&mut self, cycles: usize, instructions: usize)
+    {
+        let bucket = if cycles == 0 { 0 } else { (usize::BITS - 1 - cycles.leading_zeros()) as usize };
+
+        self.count += 1;
+        self.total_cycles = self.total_cycles.wrapping_add(cycles);
+        self.total_instructions = self.total_instructions.wrapping_add(instructions);
+        self.min_cycles = self.min_cycles.min(cycles);
+        self.max_cycles = self.max_cycles.max(cycles);
+        self.buckets[bucket.min(HISTOGRAM_BUCKETS - 1)] += 1;
+    }
+}
+
+// Counters for each measurement
+static mut PERF_COUNTERS: [PerfCounter; 4] = [PerfCounter::new(); 4];
+
+/// Get the counter for a measurement
+pub fn get_counter(measurement: Measurement) -> &'static mut PerfCounter
+{
+    unsafe { &mut PERF_COUNTERS[measurement as usize] }
+}
+
+/// Record a measurement which started at the given sample
+pub fn record(measurement: Measurement, start: PerfSample)
+{
+    let end = PerfSample::now();
+
+    get_counter(measurement).record(end.cycles.wrapping_sub(start.cycles), end.instructions.wrapping_sub(start.instructions));
+}
+
+/// Mark the entry into a trap, only the outermost trap is tracked
+pub fn mark_trap_entry()
+{
+    if trap::trap_depth() == 0
+    {
+        percpu!(trap_entry = Some(PerfSample::now()));
+    }
+}
+
+/// Clear the trap entry mark, used when the trap will not be returned from
+pub fn clear_trap_entry()
+{
+    percpu!(trap_entry = None);
+    percpu!(syscall_exit = None);
+}
+
+/// Record a measurement which started when the current trap was entered
+pub fn record_since_trap_entry(measurement: Measurement)
+{
+    if let Some(start) = percpu!(trap_entry)
+    {
+        record(measurement, start);
+    }
+}
+
+/// Mark the return from a syscall handler
+pub fn mark_syscall_exit()
+{
+    percpu!(syscall_exit = Some(PerfSample::now()));
+}
+
+/// Record the time taken to leave the trap after a syscall handler returned
+pub fn finish_syscall_exit()
+{
+    if let Some(start) = percpu::this_cpu().syscall_exit.take()
+    {
+        record(Measurement::SyscallExit, start);
+    }
+}
+
+/// Clear all of the counters
+pub fn reset()
+{
+    unsafe { PERF_COUNTERS = [PerfCounter::new(); 4] };
+}
+
+/// Dump the counters as text
+pub fn dump() -> String
+{
+    let mut result = String::new();
+
+    for measurement in Measurement::ALL
+    {
+        let counter = get_counter(measurement);
+
+        if counter.count == 0
+        {
+            result += &format!("{}: count 0\n", measurement.name());
+            continue;
+        }
+
+        result += &format!("{}: count {} min {} max {} mean {} mean_instret {}\n",
+                            measurement.name(),
+                            counter.count,
+                            counter.min_cycles,
+                            counter.max_cycles,
+                            counter.total_cycles / counter.count,
+                            counter.total_instructions / counter.count);
+
+        for (i, samples) in counter.buckets.iter().enumerate()
+        {
+            if *samples > 0
+            {
+                result += &format!("  [{}, {}): {}\n", if i == 0 { 0 } else { 1usize << i }, if i == HISTOGRAM_BUCKETS - 1 { String::from("inf") } else { format!("{}", 1usize << (i + 1)) }, samples);
+            }
+        }
+    }
+
+    // Event counts for each hart which has taken a trap
+    for hartid in 0..percpu::MAX_CPUS
+    {
+        let counters = percpu::get_cpu(hartid).unwrap().counters;
+
+        if counters.traps > 0
+        {
+            result += &format!("cpu{}: traps {} syscalls {} interrupts {} context_switches {}\n",
+                                hartid,
+                                counters.traps,
+                                counters.syscalls,
+                                counters.interrupts,
+                                counters.context_switches);
+        }
+    }
+
+    result
+}
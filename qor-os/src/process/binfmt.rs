@@ -0,0 +1,121 @@
+//! Binary Format Handler Registry
+//!
+//! Each executable format the kernel can load registers a handler here, when
+//! a process is loaded the handlers are probed in order with the start of the
+//! file and the first handler to accept the file loads it
+
+use crate::*;
+
+use alloc::vec::Vec;
+use libutils::paths::{PathBuffer, OwnedPath};
+
+use fs::namespace::SharedMountNamespace;
+use fs::structures::FilesystemIndex;
+
+use super::loading::{self, ProcessLoadError};
+use super::process::Process;
+
+/// Binary Format Handler
+pub trait BinaryFormat
+{
+    /// Name of the format for debug output
+    fn name(&self) -> &'static str;
+
+    /// Check if the file data is in this format
+    fn probe(&self, file_data: &[u8]) -> bool;
+
+    /// Load a process from the file data
+    fn load(&self, interface: &mut fs::vfs::FilesystemInterface, namespace: &Option<SharedMountNamespace>, root: Option<FilesystemIndex>, file_data: &[u8], path: PathBuffer, args: &mut Vec<String>, envp: &mut Vec<String>) -> Result<Process, ProcessLoadError>;
+}
+
+/// ELF Executables
+pub struct ElfFormat;
+
+impl BinaryFormat for ElfFormat
+{
+    fn name(&self) -> &'static str
+    {
+        "elf"
+    }
+
+    fn probe(&self, file_data: &[u8]) -> bool
+    {
+        file_data.starts_with(&[0x7F, 'E' as u8, 'L' as u8, 'F' as u8])
+    }
+
+    fn load(&self, _interface: &mut fs::vfs::FilesystemInterface, _namespace: &Option<SharedMountNamespace>, _root: Option<FilesystemIndex>, file_data: &[u8], path: PathBuffer, args: &mut Vec<String>, envp: &mut Vec<String>) -> Result<Process, ProcessLoadError>
+    {
+        super::elf::load_elf(file_data, path, args, envp)
+    }
+}
+
+/// Scripts starting with `#!` followed by the path to an interpreter
+pub struct ShebangFormat;
+
+impl BinaryFormat for ShebangFormat
+{
+    fn name(&self) -> &'static str
+    {
+        "shebang"
+    }
+
+    fn probe(&self, file_data: &[u8]) -> bool
+    {
+        file_data.starts_with(&['#' as u8, '!' as u8])
+    }
+
+    fn load(&self, interface: &mut fs::vfs::FilesystemInterface, namespace: &Option<SharedMountNamespace>, root: Option<FilesystemIndex>, file_data: &[u8], path: PathBuffer, args: &mut Vec<String>, envp: &mut Vec<String>) -> Result<Process, ProcessLoadError>
+    {
+        let mut f = String::new();
+
+        for c in file_data.iter().skip(2)
+        {
+            if *c == '\n' as u8 { break }
+
+            f.push(*c as char);
+        }
+
+        args.insert(0, path.to_string());
+
+        loading::load_process(interface, namespace, root, &OwnedPath::new(f.trim()), args, envp)
+    }
+}
+
+/// Flat binaries, used for testing the loader without a toolchain
+pub struct FlatFormat;
+
+impl BinaryFormat for FlatFormat
+{
+    fn name(&self) -> &'static str
+    {
+        "flat"
+    }
+
+    fn probe(&self, file_data: &[u8]) -> bool
+    {
+        file_data.starts_with(&super::flat::FLAT_MAGIC)
+    }
+
+    fn load(&self, _interface: &mut fs::vfs::FilesystemInterface, _namespace: &Option<SharedMountNamespace>, _root: Option<FilesystemIndex>, file_data: &[u8], path: PathBuffer, args: &mut Vec<String>, envp: &mut Vec<String>) -> Result<Process, ProcessLoadError>
+    {
+        super::flat::load_flat(file_data, path, args, envp)
+    }
+}
+
+// Registered binary formats, in the order they are probed
+static BINARY_FORMATS: [&(dyn BinaryFormat + Sync); 3] = [&ElfFormat, &ShebangFormat, &FlatFormat];
+
+/// Find the handler for the given file data
+pub fn find_binary_format(file_data: &[u8]) -> Option<&'static (dyn BinaryFormat + Sync)>
+{
+    for format in BINARY_FORMATS.iter()
+    {
+        if format.probe(file_data)
+        {
+            kdebugln!(Processes, "Loading executable as `{}`", format.name());
+            return Some(*format);
+        }
+    }
+
+    None
+}
@@ -0,0 +1,224 @@
+//! Process Credentials
+//!
+//! The user and group ids a process runs as. Each is made of a real id naming
+//! who owns the process, an effective id used for permission checks, and a
+//! saved id which lets a process that dropped its privileges take them back.
+//! Credentials are inherited across fork and execve.
+
+use crate::*;
+
+pub type UID = u32;
+pub type GID = u32;
+
+// Ids of the superuser and its group, which the first process runs as
+pub const ROOT_UID: UID = 0;
+pub const ROOT_GID: GID = 0;
+
+// Passed in place of an id which should be left unchanged
+pub const ID_UNCHANGED: u32 = u32::MAX;
+
+// Mode bits which make a program run as the owner or group of its file
+pub const S_ISUID: u16 = 0o4000;
+pub const S_ISGID: u16 = 0o2000;
+
+/// Real, effective and saved values of a user or group id
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IdSet
+{
+    pub real: u32,
+    pub effective: u32,
+    pub saved: u32
+}
+
+impl IdSet
+{
+    /// Create a set where every id has the same value
+    pub const fn new(id: u32) -> Self
+    {
+        Self { real: id, effective: id, saved: id }
+    }
+
+    /// Check if an unprivileged process may switch to an id, which must be
+    /// one of the ids it already holds
+    fn holds(&self, id: u32) -> bool
+    {
+        id == self.real || id == self.effective || id == self.saved
+    }
+
+    /// Change the ids as setuid does, a privileged process sets all three,
+    /// otherwise only the effective id can be changed, to the real or saved id
+    pub fn set(&mut self, id: u32, privileged: bool) -> Result<(), usize>
+    {
+        if privileged
+        {
+            *self = Self::new(id);
+        }
+        else if id == self.real || id == self.saved
+        {
+            self.effective = id;
+        }
+        else
+        {
+            return Err(errno::EPERM);
+        }
+
+        Ok(())
+    }
+
+    /// Change the real and effective ids as setreuid does, the saved id
+    /// follows the new effective id if the real id is set or the effective id
+    /// moves away from the old real id
+    pub fn set_real_effective(&mut self, real: u32, effective: u32, privileged: bool) -> Result<(), usize>
+    {
+        if !privileged
+        {
+            if real != ID_UNCHANGED && real != self.real && real != self.effective
+            {
+                return Err(errno::EPERM);
+            }
+
+            if effective != ID_UNCHANGED && !self.holds(effective)
+            {
+                return Err(errno::EPERM);
+            }
+        }
+
+        let old_real = self.real;
+
+        if real != ID_UNCHANGED
+        {
+            self.real = real;
+        }
+
+        if effective != ID_UNCHANGED
+        {
+            self.effective = effective;
+        }
+
+        if real != ID_UNCHANGED || (effective != ID_UNCHANGED && effective != old_real)
+        {
+            self.saved = self.effective;
+        }
+
+        Ok(())
+    }
+
+    /// Change any of the ids as setresuid does, an unprivileged process can
+    /// only set each to one of the ids it already holds
+    pub fn set_all(&mut self, real: u32, effective: u32, saved: u32, privileged: bool) -> Result<(), usize>
+    {
+        if !privileged && [real, effective, saved].iter().any(|id| *id != ID_UNCHANGED && !self.holds(*id))
+        {
+            return Err(errno::EPERM);
+        }
+
+        if real != ID_UNCHANGED
+        {
+            self.real = real;
+        }
+
+        if effective != ID_UNCHANGED
+        {
+            self.effective = effective;
+        }
+
+        if saved != ID_UNCHANGED
+        {
+            self.saved = saved;
+        }
+
+        Ok(())
+    }
+}
+
+/// User and group ids of a process
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Credentials
+{
+    pub user: IdSet,
+    pub group: IdSet
+}
+
+impl Credentials
+{
+    /// Create the credentials given to the first process
+    pub const fn root() -> Self
+    {
+        Self
+        {
+            user: IdSet::new(ROOT_UID),
+            group: IdSet::new(ROOT_GID)
+        }
+    }
+
+    /// Check if the credentials carry the privileges of the superuser
+    pub fn is_privileged(&self) -> bool
+    {
+        self.user.effective == ROOT_UID
+    }
+
+    /// Check if the credentials allow changing the mode of a file with the
+    /// given owner, which only its owner and the superuser may do
+    pub fn may_change_mode(&self, owner: u32) -> bool
+    {
+        self.is_privileged() || self.user.effective == owner
+    }
+
+    /// Check if the credentials allow changing the owner and group of a file
+    /// from its current owner and group. Only the superuser can give a file
+    /// away, the owner may only move it to its own effective group.
+    pub fn may_change_owner(&self, file_owner: u32, file_group: u32, owner: u32, group: u32) -> bool
+    {
+        if self.is_privileged()
+        {
+            return true;
+        }
+
+        let owner_allowed = owner == ID_UNCHANGED || owner == file_owner;
+        let group_allowed = group == ID_UNCHANGED || group == file_group || group == self.group.effective;
+
+        self.user.effective == file_owner && owner_allowed && group_allowed
+    }
+
+    /// Check if the credentials allow sending a signal to a process with the
+    /// given credentials, the real or effective user id of the sender must
+    /// match the real or saved user id of the target
+    pub fn may_signal(&self, target: &Credentials) -> bool
+    {
+        self.is_privileged() ||
+            [self.user.real, self.user.effective].iter().any(|id| *id == target.user.real || *id == target.user.saved)
+    }
+
+    /// Check if the credentials allow reading and writing the memory of a
+    /// process with the given credentials, every user and group id of the
+    /// target must be the real id of the caller
+    pub fn may_access_memory(&self, target: &Credentials) -> bool
+    {
+        let matches = |own: &IdSet, other: &IdSet| other.real == own.real && other.effective == own.real && other.saved == own.real;
+
+        self.is_privileged() || (matches(&self.user, &target.user) && matches(&self.group, &target.group))
+    }
+
+    /// Get the credentials a program runs with, a file with the set user or
+    /// set group bit runs as its owner or group unless the process has given
+    /// up gaining privileges. The saved ids always take the effective ids.
+    pub fn after_exec(&self, mode: u16, owner: u32, group: u32, no_new_privs: bool) -> Self
+    {
+        let mut result = *self;
+
+        if !no_new_privs && mode & S_ISUID != 0
+        {
+            result.user.effective = owner;
+        }
+
+        if !no_new_privs && mode & S_ISGID != 0
+        {
+            result.group.effective = group;
+        }
+
+        result.user.saved = result.user.effective;
+        result.group.saved = result.group.effective;
+
+        result
+    }
+}
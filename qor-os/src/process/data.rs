@@ -3,12 +3,30 @@ use libutils::paths::OwnedPath;
 
 use crate::*;
 
+use super::credentials::Credentials;
 use super::descriptor::*;
+use super::itimer::IntervalTimer;
+use super::mmap::{MemoryMapping, MMAP_START};
 use super::signals::SignalType;
 use super::signals::SignalDisposition;
 use super::stats::*;
 
 use super::PID;
+use super::rescg::{ResourceGroupId, ROOT_RESOURCE_GROUP};
+use super::rlimit::ResourceLimits;
+use super::runqueue::DEFAULT_PRIORITY;
+
+use crate::drivers::timer::KernelTime;
+use crate::fs::namespace::SharedMountNamespace;
+use crate::fs::structures::FilesystemIndex;
+use crate::syscalls::args::UserCopy;
+
+// Permission bits masked out of files a process creates unless it sets its
+// own mask
+pub const DEFAULT_UMASK: u16 = 0o022;
+
+// Longest process name, not counting the null terminator
+pub const PROCESS_NAME_LENGTH: usize = 15;
 
 /// Process Data
 pub struct ProcessData
@@ -16,16 +34,39 @@ pub struct ProcessData
     pub stack_size: usize, // Stack size in pages
     pub mem: Vec<(*mut u8, usize)>,
     pub next_heap: usize,
+    pub heap_start: usize,
+    pub program_break: usize,
     pub descriptors: DescriptorTable,
     pub children: Vec<PID>,
     pub parent_pid: PID,
     pub process_group_id: PID,
+    pub session_id: PID,
+    pub has_executed: bool,
     pub cwd: OwnedPath,
+    pub root: Option<FilesystemIndex>,
+    pub umask: u16,
     pub cmdline_args: Vec<String>,
+    pub name: String,
+    pub dumpable: bool,
+    pub no_new_privs: bool,
     pub mem_stats: MemoryStats,
     pub signal_map: BTreeMap<SignalType, SignalDisposition>,
-    pub mmapped_files: BTreeMap<*mut u8, usize>,
-    pub return_code_listener: Option<&'static mut u32>
+    pub signal_mask: u64,
+    pub saved_signal_mask: Option<u64>, // Mask to restore once the handler which ends a sigsuspend returns
+    pub sleep_remaining: Option<usize>,
+    pub mappings: BTreeMap<usize, MemoryMapping>,
+    pub memory_owner: Option<PID>, // Process whose address space this one runs in, set by CLONE_VM
+    pub mount_namespace: Option<SharedMountNamespace>,
+    pub resource_group: ResourceGroupId,
+    pub limits: ResourceLimits,
+    pub credentials: Credentials,
+    pub clear_child_tid: Option<usize>,
+    pub priority: usize,
+    pub start_time: KernelTime,
+    pub cpu_times: CpuTimes,
+    pub real_timer: IntervalTimer,
+    pub aux_vector: Vec<(usize, usize)>,
+    pub user_copies: Vec<UserCopy> // Copies of ioctl arguments which cross a page boundary
 }
 
 impl ProcessData
@@ -34,41 +75,67 @@ impl ProcessData
     /// Safety: The mem_ptr must be valid or zero
     pub unsafe fn new(stack_size: usize, mem_stats: MemoryStats, pgid: PID) -> Self
     {
-        let descriptors: DescriptorTable = BTreeMap::new();
-        
-        let mut signal_map = BTreeMap::new();
-
-        signal_map.insert(SignalType::SIGTRAP, SignalDisposition::Core);
-        signal_map.insert(SignalType::SIGTERM, SignalDisposition::Terminate);
-        signal_map.insert(SignalType::SIGSTOP, SignalDisposition::Stop);
-        signal_map.insert(SignalType::SIGCONT, SignalDisposition::Continue);
-        signal_map.insert(SignalType::SIGKILL, SignalDisposition::Terminate);
-        signal_map.insert(SignalType::SIGINT, SignalDisposition::Terminate);
+        let signal_map = SignalType::ALL.iter().map(|sig| (*sig, sig.default_disposition())).collect();
 
         Self
         {
             stack_size,
             mem: Vec::new(),
-            next_heap: 0x4_0000_0000,
-            descriptors: descriptors,
+            next_heap: MMAP_START,
+            heap_start: 0,
+            program_break: 0,
+            descriptors: DescriptorTable::new(),
             children: Vec::new(),
             parent_pid: 0,
             process_group_id: pgid,
+            session_id: pgid,
+            has_executed: false,
             cwd: OwnedPath::new("/home/root/"),
+            root: None,
+            umask: DEFAULT_UMASK,
             cmdline_args: Vec::new(),
+            name: String::new(),
+            dumpable: true,
+            no_new_privs: false,
             mem_stats,
             signal_map,
-            mmapped_files: BTreeMap::new(),
-            return_code_listener: None
+            signal_mask: 0,
+            saved_signal_mask: None,
+            sleep_remaining: None,
+            mappings: BTreeMap::new(),
+            memory_owner: None,
+            mount_namespace: None,
+            resource_group: ROOT_RESOURCE_GROUP,
+            limits: ResourceLimits::new(),
+            credentials: Credentials::root(),
+            clear_child_tid: None,
+            priority: DEFAULT_PRIORITY,
+            start_time: unsafe { &drivers::TIMER_DRIVER }.time(),
+            cpu_times: CpuTimes::new(),
+            real_timer: IntervalTimer::new(),
+            aux_vector: Vec::new(),
+            user_copies: Vec::new()
         }
     }
 
-    /// Fill in the command line arguments
+    /// Fill in the command line arguments, the process is named after the
+    /// program it runs
     pub fn fill_command_line_args(&mut self, args: Vec<String>)
     {
+        if let Some(program) = args.first()
+        {
+            self.set_name(program.rsplit('/').next().unwrap_or(program));
+        }
+
         self.cmdline_args = args;
     }
 
+    /// Set the name of the process, truncating it to the longest name allowed
+    pub fn set_name(&mut self, name: &str)
+    {
+        self.name = name.chars().take(PROCESS_NAME_LENGTH).collect();
+    }
+
     /// Convert the command line arguments to a single string to display
     pub fn command_line_args_to_string(&self) -> String
     {
@@ -4,9 +4,155 @@ use fs::fstrait::Filesystem;
 
 use fs::structures::FilesystemIndex;
 use alloc::collections::BTreeMap;
+use alloc::sync::Arc;
+use core::cell::RefCell;
 
-/// Descriptor table type
-pub type DescriptorTable = BTreeMap<usize, alloc::sync::Arc<core::cell::RefCell<Box<dyn super::descriptor::FileDescriptor>>>>;
+use super::mmap::SharedDescriptor;
+
+// Must be kept in sync with syscalls.h
+pub const O_NONBLOCK: usize = 64;
+pub const O_CLOEXEC: usize = 128;
+
+/// Flags of an open file which can be changed after it is opened
+pub const O_CHANGEABLE_FLAGS: usize = O_APPEND | O_NONBLOCK;
+
+/// Flags of an open file which are kept for as long as it is open
+pub const O_STATUS_FLAGS: usize = O_RDONLY | O_WRONLY | O_CHANGEABLE_FLAGS;
+
+// Must be kept in sync with syscalls.h
+pub const FD_CLOEXEC: usize = 1;
+
+/// Open descriptors by number along with the descriptor flags set on each
+#[derive(Default)]
+struct DescriptorEntries
+{
+    descriptors: BTreeMap<usize, SharedDescriptor>,
+    flags: BTreeMap<usize, usize>
+}
+
+/// Descriptor table of a process, processes cloned with `CLONE_FILES` hold
+/// the same table, so a descriptor opened or closed by one is seen by all
+pub struct DescriptorTable
+{
+    entries: Arc<RefCell<DescriptorEntries>>
+}
+
+impl DescriptorTable
+{
+    /// Create an empty descriptor table
+    pub fn new() -> Self
+    {
+        Self { entries: Arc::new(RefCell::new(DescriptorEntries::default())) }
+    }
+
+    /// Get another handle to the same table
+    pub fn share(&self) -> Self
+    {
+        Self { entries: self.entries.clone() }
+    }
+
+    /// Get a table of its own holding the same descriptors and flags
+    pub fn duplicate(&self) -> Self
+    {
+        let entries = self.entries.borrow();
+
+        Self
+        {
+            entries: Arc::new(RefCell::new(DescriptorEntries
+            {
+                descriptors: entries.descriptors.clone(),
+                flags: entries.flags.clone()
+            }))
+        }
+    }
+
+    /// Get a table of its own holding the descriptors not marked close on
+    /// exec, for the process image replacing this one
+    pub fn without_close_on_exec(&self) -> Self
+    {
+        let entries = self.entries.borrow();
+
+        let descriptors = entries.descriptors.iter()
+            .filter(|(fd, _)| entries.flags.get(fd).map(|flags| flags & FD_CLOEXEC == 0).unwrap_or(true))
+            .map(|(fd, desc)| (*fd, desc.clone()))
+            .collect();
+
+        Self { entries: Arc::new(RefCell::new(DescriptorEntries { descriptors, flags: BTreeMap::new() })) }
+    }
+
+    /// Check if the table is held by more than one process
+    pub fn is_shared(&self) -> bool
+    {
+        Arc::strong_count(&self.entries) > 1
+    }
+
+    /// Get the descriptor with the given number
+    pub fn get(&self, fd: &usize) -> Option<SharedDescriptor>
+    {
+        self.entries.borrow().descriptors.get(fd).cloned()
+    }
+
+    /// Check if a descriptor number is in use
+    pub fn contains_key(&self, fd: &usize) -> bool
+    {
+        self.entries.borrow().descriptors.contains_key(fd)
+    }
+
+    /// Get every open descriptor
+    pub fn values(&self) -> Vec<SharedDescriptor>
+    {
+        self.entries.borrow().descriptors.values().cloned().collect()
+    }
+
+    /// Place a descriptor at the given number, the new descriptor starts
+    /// without any flags set
+    pub fn insert(&mut self, fd: usize, desc: SharedDescriptor)
+    {
+        let mut entries = self.entries.borrow_mut();
+
+        entries.descriptors.insert(fd, desc);
+        entries.flags.remove(&fd);
+    }
+
+    /// Remove a descriptor along with its flags
+    pub fn remove(&mut self, fd: &usize) -> Option<SharedDescriptor>
+    {
+        let mut entries = self.entries.borrow_mut();
+
+        entries.flags.remove(fd);
+        entries.descriptors.remove(fd)
+    }
+
+    /// Remove every descriptor, returning them so they can be closed
+    pub fn take_all(&mut self) -> Vec<SharedDescriptor>
+    {
+        let mut entries = self.entries.borrow_mut();
+
+        entries.flags.clear();
+        core::mem::take(&mut entries.descriptors).into_values().collect()
+    }
+
+    /// Get the flags set on a descriptor
+    pub fn flags(&self, fd: &usize) -> usize
+    {
+        self.entries.borrow().flags.get(fd).copied().unwrap_or(0)
+    }
+
+    /// Set the flags of a descriptor
+    pub fn set_flags(&mut self, fd: usize, flags: usize)
+    {
+        let mut entries = self.entries.borrow_mut();
+
+        if flags == 0
+        {
+            entries.flags.remove(&fd);
+        }
+        else
+        {
+            entries.flags.insert(fd, flags);
+        }
+    }
+}
 
 /// Seek Modes
 #[derive(Debug, Clone, Copy)]
@@ -32,10 +178,34 @@ pub trait FileDescriptor
     /// Get the inode of the entry
     fn get_inode(&mut self) -> Option<FilesystemIndex>;
 
-    /// Seek to the given location in the descriptor
-    fn seek(&mut self, offset: usize, _mode: SeekMode) -> usize
+    /// Seek to the given location in the descriptor, the offset is treated as
+    /// signed for `SeekCurrent` and `SeekEnd`, descriptors which do not
+    /// support seeking return `ESPIPE`
+    fn seek(&mut self, _offset: usize, _mode: SeekMode) -> usize
     {
-        offset
+        errno::ESPIPE
+    }
+
+    /// Read from the given offset without moving the position of the
+    /// descriptor, descriptors which do not support seeking return `ESPIPE`
+    fn read_at(&mut self, _fs: &mut fs::vfs::FilesystemInterface, _buffer: *mut u8, _count: usize, _offset: usize) -> usize
+    {
+        errno::ESPIPE
+    }
+
+    /// Write at the given offset without moving the position of the
+    /// descriptor, descriptors which do not support seeking return `ESPIPE`
+    fn write_at(&mut self, _fs: &mut fs::vfs::FilesystemInterface, _buffer: *mut u8, _count: usize, _offset: usize) -> usize
+    {
+        errno::ESPIPE
+    }
+
+    /// Change the length of the file behind the descriptor, cutting off its
+    /// end or extending it with zeros, descriptors which are not open for
+    /// writing to a file give `EINVAL`
+    fn truncate(&mut self, _fs: &mut fs::vfs::FilesystemInterface, _length: usize) -> Result<(), usize>
+    {
+        Err(errno::EINVAL)
     }
 
     /// Attempt to use an underlying buffer for an mmap
@@ -44,16 +214,101 @@ pub trait FileDescriptor
         None
     }
 
+    /// Get the shared memory object the descriptor refers to, so mappings of
+    /// the descriptor can share its pages
+    fn get_shared_memory(&self) -> Option<super::shm::SharedMemory>
+    {
+        None
+    }
+
     /// Check for available data
     fn check_available(&self) -> bool
     {
         true
     }
 
+    /// Check if a write to the descriptor can make progress without blocking
+    fn check_writable(&self) -> bool
+    {
+        true
+    }
+
+    /// Check if the other side of the descriptor has been closed
+    fn check_hangup(&self) -> bool
+    {
+        false
+    }
+
     fn set_end(&mut self, _: &alloc::sync::Arc<core::cell::RefCell<Box<dyn FileDescriptor>>>)
     {
 
     }
+
+    /// Get the capacity of the pipe the descriptor refers to
+    fn get_pipe_size(&self) -> Result<usize, usize>
+    {
+        Err(errno::EBADF)
+    }
+
+    /// Change the capacity of the pipe the descriptor refers to, returning
+    /// the new capacity
+    fn set_pipe_size(&mut self, _size: usize) -> Result<usize, usize>
+    {
+        Err(errno::EBADF)
+    }
+
+    /// Get the end of the pipe the descriptor refers to
+    fn get_pipe_end(&self) -> Option<super::pipe::PipeEnd>
+    {
+        None
+    }
+
+    /// Check if the descriptor refers to a terminal
+    fn is_terminal(&self) -> bool
+    {
+        false
+    }
+
+    /// Get the foreground process group of the terminal the descriptor
+    /// refers to, descriptors which are not terminals return `None`
+    fn get_terminal_foreground(&self) -> Option<super::PID>
+    {
+        None
+    }
+
+    /// Get the status flags the file was opened with, such as O_APPEND and
+    /// O_NONBLOCK, descriptors which do not track them report none
+    fn get_status_flags(&self) -> usize
+    {
+        0
+    }
+
+    /// Change the status flags of the file
+    fn set_status_flags(&mut self, _flags: usize)
+    {
+
+    }
+
+    /// Get the socket the descriptor refers to, descriptors which are not
+    /// sockets return `None`
+    fn as_socket(&mut self) -> Option<&mut SocketDescriptor>
+    {
+        None
+    }
+
+    /// Get the set of signals read through the descriptor, descriptors which
+    /// are not signalfds return `None`
+    fn get_signal_mask(&self) -> Option<u64>
+    {
+        None
+    }
+
+    /// Change the set of signals read through the descriptor, only signalfds
+    /// accept a new set
+    fn set_signal_mask(&mut self, _mask: u64) -> Result<(), usize>
+    {
+        Err(errno::EINVAL)
+    }
 }
 
 /// Null File Descriptor
@@ -86,6 +341,11 @@ impl FileDescriptor for NullDescriptor
     {
         Some(self.inode)
     }
+
+    fn seek(&mut self, _: usize, _: SeekMode) -> usize
+    {
+        0
+    }
 }
 
 /// Filesystem Inode File Descriptor
@@ -96,17 +356,41 @@ pub struct InodeFileDescriptor
     index: usize,
     data: Vec<u8>,
     is_write: bool,
-    is_read: bool
+    is_read: bool,
+    status_flags: usize
 }
 
 // Must be kept in sync with syscalls.h
-const O_RDONLY: usize = 1;
-const O_WRONLY: usize = 2;
-const O_APPEND: usize = 4;
+pub const O_RDONLY: usize = 1;
+pub const O_WRONLY: usize = 2;
+pub const O_APPEND: usize = 4;
 const O_TRUNC: usize =  8;
 const O_CREAT: usize =  16;
 const O_EXCL: usize =   32;
 
+/// Compute the new position of a seek within a file of the given length,
+/// returning None if the result would be negative
+pub fn seek_position(current: usize, length: usize, offset: usize, mode: SeekMode) -> Option<usize>
+{
+    let base = match mode
+    {
+        SeekMode::SeekSet => { return Some(offset); },
+        SeekMode::SeekCurrent => current,
+        SeekMode::SeekEnd => length
+    };
+
+    let result = base as isize + offset as isize;
+
+    if result < 0
+    {
+        None
+    }
+    else
+    {
+        Some(result as usize)
+    }
+}
+
 impl InodeFileDescriptor
 {
     pub fn new(fs: &mut fs::vfs::FilesystemInterface, inode: FilesystemIndex, mode: usize) -> Result<Self, ()>
@@ -117,7 +401,8 @@ impl InodeFileDescriptor
             index: 0,
             data: Vec::new(),
             is_write: mode & (O_WRONLY | O_APPEND) > 0,
-            is_read: mode & O_RDONLY > 0
+            is_read: mode & O_RDONLY > 0,
+            status_flags: mode & O_STATUS_FLAGS
         };
 
         if temp.is_read || (((mode & O_APPEND) > 0) && (mode & O_TRUNC) == 0)
@@ -138,6 +423,37 @@ impl InodeFileDescriptor
 
         Ok(temp)
     }
+
+    /// Write the buffer into the file at the given position, a gap left by
+    /// writing past the end of the file is filled with zeros
+    fn write_data(&mut self, buffer: *mut u8, count: usize, position: usize)
+    {
+        let end = position + count;
+
+        if end > self.data.len()
+        {
+            self.data.resize(end, 0);
+        }
+
+        for i in 0..count
+        {
+            self.data[position + i] = unsafe { buffer.add(i).read() };
+        }
+    }
+
+    /// Read from the file at the given position into the buffer, returning
+    /// the number of bytes read
+    fn read_data(&self, buffer: *mut u8, count: usize, position: usize) -> usize
+    {
+        let available = self.data.len().saturating_sub(position).min(count);
+
+        for i in 0..available
+        {
+            unsafe { buffer.add(i).write(self.data[position + i]) };
+        }
+
+        available
+    }
 }
 
 impl FileDescriptor for InodeFileDescriptor
@@ -157,27 +473,17 @@ impl FileDescriptor for InodeFileDescriptor
             return usize::MAX;
         }
 
-
-        for i in 0..count
+        if self.status_flags & O_APPEND > 0
         {
-            let value = unsafe { buffer.add(i).read() };
-
-            if self.index < self.data.len()
-            {
-                self.data[self.index] = value;
-            }
-            else
-            {
-                self.data.push(value);
-            }
-
-            self.index += 1;
+            self.index = self.data.len();
         }
 
+        self.write_data(buffer, count, self.index);
+        self.index += count;
+
         count
     }
 
-    // TODO: This read implementation is beyond inefficent
     fn read(&mut self, _fs: &mut fs::vfs::FilesystemInterface, buffer: *mut u8, count: usize) -> usize
     {
         if !self.is_read
@@ -185,23 +491,50 @@ impl FileDescriptor for InodeFileDescriptor
             return usize::MAX;
         }
 
-        let mut written = 0;
+        let read = self.read_data(buffer, count, self.index);
+        self.index += read;
 
-        while self.index < self.data.len()
+        read
+    }
+
+    fn read_at(&mut self, _fs: &mut fs::vfs::FilesystemInterface, buffer: *mut u8, count: usize, offset: usize) -> usize
+    {
+        if !self.is_read
         {
-            let data = self.data[self.index];
-            unsafe { buffer.add(written).write(data) };
+            return errno::EBADF;
+        }
 
-            written += 1;
-            self.index += 1;
+        self.read_data(buffer, count, offset)
+    }
 
-            if written == count
-            {
-                break;
-            }
+    fn write_at(&mut self, _fs: &mut fs::vfs::FilesystemInterface, buffer: *mut u8, count: usize, offset: usize) -> usize
+    {
+        if !self.is_write
+        {
+            return errno::EBADF;
+        }
+
+        // As on Linux, files opened for appending are always written at the
+        // end whatever the offset
+        let position = if self.status_flags & O_APPEND > 0 { self.data.len() } else { offset };
+
+        self.write_data(buffer, count, position);
+
+        count
+    }
+
+    fn truncate(&mut self, fs: &mut fs::vfs::FilesystemInterface, length: usize) -> Result<(), usize>
+    {
+        if !self.is_write
+        {
+            return Err(errno::EINVAL);
         }
 
-        written
+        // The buffered contents are written back when the descriptor is
+        // closed, so they are cut to the new length as well
+        self.data.resize(length, 0);
+
+        fs.truncate_inode(self.inode, length).map_err(|e| e.to_errno())
     }
 
     /// Get the inode of the entry
@@ -213,26 +546,25 @@ impl FileDescriptor for InodeFileDescriptor
     /// Seek to the given location in the descriptor
     fn seek(&mut self, offset: usize, mode: SeekMode) -> usize
     {
-        match mode
+        if let Some(index) = seek_position(self.index, self.data.len(), offset, mode)
         {
-            SeekMode::SeekSet => 
-            {
-                self.index = offset;
-                self.index
-            },
-            SeekMode::SeekCurrent => 
-            {
-                self.index += offset;
-                self.index
-            },
-            SeekMode::SeekEnd => 
-            {
-                self.index = self.data.len() - 1 + offset;
-                self.data.extend_from_slice(&vec![0; offset]);
-
-                self.index
-            },
+            self.index = index;
+            self.index
         }
+        else
+        {
+            errno::EINVAL
+        }
+    }
+
+    fn get_status_flags(&self) -> usize
+    {
+        self.status_flags
+    }
+
+    fn set_status_flags(&mut self, flags: usize)
+    {
+        self.status_flags = flags;
     }
 }
 
@@ -337,38 +669,15 @@ impl FileDescriptor for BufferDescriptor
 {
     fn seek(&mut self, offset: usize, mode: SeekMode) -> usize
     {
-        match mode
+        // Buffers cannot grow, so the position must stay within the buffer
+        match seek_position(self.index, self.buffer.get_size(), offset, mode)
         {
-            SeekMode::SeekSet => 
-            {
-                if offset >= self.buffer.get_size()
-                {
-                    return offset - 1;
-                }
-
-                self.index = offset;
-                self.index
-            },
-            SeekMode::SeekCurrent => 
-            {
-                if self.index + offset >= self.buffer.get_size()
-                {
-                    return offset - 1;
-                }
-
-                self.index += offset;
-                self.index
-            },
-            SeekMode::SeekEnd => 
+            Some(index) if index <= self.buffer.get_size() =>
             {
-                if offset != 0
-                {
-                    return offset - 1;
-                }
-
-                self.index = self.buffer.get_size() - 1;
+                self.index = index;
                 self.index
             },
+            _ => errno::EINVAL
         }
     }
 
@@ -416,6 +725,24 @@ impl FileDescriptor for BufferDescriptor
         count
     }
 
+    fn read_at(&mut self, fs: &mut fs::vfs::FilesystemInterface, buffer: *mut u8, count: usize, offset: usize) -> usize
+    {
+        let saved = core::mem::replace(&mut self.index, offset);
+        let result = self.read(fs, buffer, count);
+        self.index = saved;
+
+        result
+    }
+
+    fn write_at(&mut self, fs: &mut fs::vfs::FilesystemInterface, buffer: *mut u8, count: usize, offset: usize) -> usize
+    {
+        let saved = core::mem::replace(&mut self.index, offset);
+        let result = self.write(fs, buffer, count);
+        self.index = saved;
+
+        result
+    }
+
     fn get_inode(&mut self) -> Option<FilesystemIndex>
     {
         Some(self.inode)
@@ -425,4 +752,91 @@ impl FileDescriptor for BufferDescriptor
     {
         self.buffer.get_buffer()
     }
-}
\ No newline at end of file
+}
+
+/// Socket descriptor, wrapping a socket of any address family
+pub struct SocketDescriptor
+{
+    socket: Box<dyn super::socket::Socket>,
+    status_flags: usize
+}
+
+impl SocketDescriptor
+{
+    /// Create a new socket descriptor
+    pub fn new(socket: Box<dyn super::socket::Socket>, status_flags: usize) -> Self
+    {
+        Self
+        {
+            socket,
+            status_flags: O_RDONLY | O_WRONLY | status_flags
+        }
+    }
+
+    /// Get the socket behind the descriptor
+    pub fn socket(&mut self) -> &mut dyn super::socket::Socket
+    {
+        &mut *self.socket
+    }
+}
+
+impl FileDescriptor for SocketDescriptor
+{
+    fn close(&mut self, _fs: &mut fs::vfs::FilesystemInterface)
+    {
+        // The connection is closed once the last reference to the socket is dropped
+    }
+
+    fn write(&mut self, _fs: &mut fs::vfs::FilesystemInterface, buffer: *mut u8, count: usize) -> usize
+    {
+        match self.socket.send(unsafe { core::slice::from_raw_parts(buffer, count) })
+        {
+            Ok(count) => count,
+            Err(e) => e
+        }
+    }
+
+    fn read(&mut self, _fs: &mut fs::vfs::FilesystemInterface, buffer: *mut u8, count: usize) -> usize
+    {
+        match self.socket.recv(unsafe { core::slice::from_raw_parts_mut(buffer, count) })
+        {
+            Ok(count) => count,
+            Err(e) => e
+        }
+    }
+
+    fn get_inode(&mut self) -> Option<FilesystemIndex>
+    {
+        None
+    }
+
+    fn check_available(&self) -> bool
+    {
+        self.socket.check_available()
+    }
+
+    fn check_writable(&self) -> bool
+    {
+        self.socket.check_writable()
+    }
+
+    fn check_hangup(&self) -> bool
+    {
+        self.socket.check_hangup()
+    }
+
+    fn get_status_flags(&self) -> usize
+    {
+        self.status_flags
+    }
+
+    fn set_status_flags(&mut self, flags: usize)
+    {
+        self.status_flags = flags;
+    }
+
+    fn as_socket(&mut self) -> Option<&mut SocketDescriptor>
+    {
+        Some(self)
+    }
+}
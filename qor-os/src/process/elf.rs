@@ -53,6 +53,11 @@ pub struct ProgramHeader
     pub align: usize,
 }
 
+// Program header types
+const PT_LOAD: u32 = 1;
+const PT_PHDR: u32 = 6;
+const PT_TLS: u32 = 7;
+
 /// Segment to load into program memory
 #[derive(Debug)]
 pub struct Segment
@@ -67,7 +72,7 @@ pub struct Segment
 
 
 /// Load a file from a file interface and convert it to a process
-pub fn load_elf(file_data: Vec<u8>, path: PathBuffer, args: &Vec<String>, envp: &Vec<String>) -> Result<Process, loading::ProcessLoadError>
+pub fn load_elf(file_data: &[u8], path: PathBuffer, args: &Vec<String>, envp: &Vec<String>) -> Result<Process, loading::ProcessLoadError>
 {
     kdebugln!(Elf, "Loading ELF File `{}`", path);
 
@@ -101,13 +106,24 @@ pub fn load_elf(file_data: Vec<u8>, path: PathBuffer, args: &Vec<String>, envp:
     // Segments to write to memory
     let mut segments = Vec::new();
 
+    // Thread local storage template
+    let mut tls_header = None;
+
     // Iterate over the program headers
     for i in 0..elf_header.e_phnum
     {
         let header = &prog_headers[i as usize];
 
+        // Keep track of the TLS template so the initial block can be built
+        if header.seg_type == PT_TLS
+        {
+            kdebugln!(Elf, "   TLS: {} bytes ({} initialized)", header.memsz, header.filesz);
+            tls_header = Some(*header);
+            continue;
+        }
+
         // Skip any headers which are not LOAD
-        if header.seg_type != 1
+        if header.seg_type != PT_LOAD
         {
             continue;
         }
@@ -160,6 +176,16 @@ pub fn load_elf(file_data: Vec<u8>, path: PathBuffer, args: &Vec<String>, envp:
     // Allocate a new page table
     let table = unsafe { (mem::kpzalloc(1, "ELF Page Table").unwrap() as *mut mem::mmu::PageTable).as_mut().unwrap() };
 
+    // Link in the kernel's half of the address space
+    table.share_kernel_space();
+
+    // Physical allocations backing the segments, these are handed to the
+    // process so they are released (and zeroed) when it is dropped
+    let mut segment_allocations = Vec::new();
+
+    // The heap starts after the highest segment
+    let image_end = segments.iter().map(|segment| segment.vaddr + segment.msize).max().unwrap_or(0);
+
     // Map the segments
     for segment in segments
     {
@@ -167,6 +193,7 @@ pub fn load_elf(file_data: Vec<u8>, path: PathBuffer, args: &Vec<String>, envp:
 
         let num_pages = (segment.msize + poff + mem::PAGE_SIZE - 1) / mem::PAGE_SIZE;
         let phys_ptr = mem::kpzalloc(num_pages, "ELF Segment").unwrap() as *mut u8;
+        segment_allocations.push((phys_ptr, num_pages));
 
         for i in 0..segment.fsize
         {
@@ -201,12 +228,80 @@ pub fn load_elf(file_data: Vec<u8>, path: PathBuffer, args: &Vec<String>, envp:
         stack_size, super::process::STACK_END - mem::PAGE_SIZE * stack_size,
         mem_stats);
 
+    proc.data.mem.extend(segment_allocations);
+    proc.init_heap(image_end);
+
+    // Build the initial thread local storage block
+    if let Some(header) = tls_header
+    {
+        allocate_tls(&mut proc, file_data, &header);
+    }
+
+    proc.data.aux_vector = aux_vector(&elf_header, &prog_headers[..elf_header.e_phnum as usize]);
+
     let mut full_arguments = vec![path.as_str().to_string()];
     full_arguments.extend_from_slice(&args);
 
     proc.set_arguments(&full_arguments, envp);
 
-    proc.data.fill_command_line_args(full_arguments);
-
     Ok(proc)
+}
+
+/// Build the auxiliary vector passed to the program, giving the C library
+/// the program headers in memory, the page size and the entry point
+fn aux_vector(elf_header: &ElfHeader, prog_headers: &[ProgramHeader]) -> Vec<(usize, usize)>
+{
+    use super::process::{AT_PHDR, AT_PHENT, AT_PHNUM, AT_PAGESZ, AT_ENTRY};
+
+    let phoff = elf_header.e_phoff as usize;
+
+    // The headers are found through PT_PHDR if there is one, otherwise
+    // through the loaded segment which contains them
+    let phdr = prog_headers.iter().find(|header| header.seg_type == PT_PHDR).map(|header| header.vaddr)
+        .or_else(|| prog_headers.iter()
+            .find(|header| header.seg_type == PT_LOAD && header.off <= phoff && phoff < header.off + header.filesz)
+            .map(|header| header.vaddr + phoff - header.off));
+
+    let mut result = Vec::new();
+
+    if let Some(phdr) = phdr
+    {
+        result.push((AT_PHDR, phdr));
+        result.push((AT_PHENT, elf_header.e_phentsize as usize));
+        result.push((AT_PHNUM, elf_header.e_phnum as usize));
+    }
+
+    result.push((AT_PAGESZ, mem::PAGE_SIZE));
+    result.push((AT_ENTRY, elf_header.e_entry as usize));
+
+    result
+}
+
+/// Allocate the initial thread local storage block for a process from the TLS
+/// template segment and point the thread pointer at it
+fn allocate_tls(proc: &mut Process, file_data: &[u8], header: &ProgramHeader)
+{
+    // RISC-V uses TLS variant I with no TCB, so the thread pointer is the
+    // start of the block itself
+    let num_pages = (header.memsz.max(1) + mem::PAGE_SIZE - 1) / mem::PAGE_SIZE;
+    let phys_ptr = mem::kpzalloc(num_pages, "ELF TLS Block").unwrap() as *mut u8;
+
+    // Copy the initialized data, the rest was zeroed by the allocation
+    for i in 0..header.filesz
+    {
+        unsafe { phys_ptr.add(i).write( file_data[header.off + i] ) }
+    }
+
+    for i in 0..num_pages
+    {
+        unsafe { proc.root.as_mut().unwrap() }.map(super::process::TLS_START + i * mem::PAGE_SIZE,
+            phys_ptr as usize + i * mem::PAGE_SIZE,
+            mem::mmu::PageTableEntryFlags::user() | mem::mmu::PageTableEntryFlags::readable() | mem::mmu::PageTableEntryFlags::writable() | mem::mmu::PageTableEntryFlags::dirty() | mem::mmu::PageTableEntryFlags::accessed(),
+            0);
+    }
+
+    proc.data.mem.push((phys_ptr, num_pages));
+    proc.data.mem_stats.data += num_pages;
+
+    unsafe { proc.frame.as_mut().unwrap() }.regs[4] = super::process::TLS_START;
 }
\ No newline at end of file
@@ -0,0 +1,121 @@
+//! Event Counters
+//!
+//! Descriptors made by eventfd hold a 64 bit counter, writes add to it and
+//! reads return it and reset it, or take one from it in semaphore mode. A
+//! read of a zero counter blocks until something is written, so the counter
+//! works as a wakeup for event loops through poll. The counter lives in the
+//! open file, so it is shared by descriptors made with dup or inherited over
+//! fork.
+
+use crate::*;
+
+use fs::structures::FilesystemIndex;
+
+use super::descriptor::{FileDescriptor, O_RDONLY, O_WRONLY};
+
+// Largest value the counter can hold
+const EVENT_COUNTER_MAX: u64 = u64::MAX - 1;
+
+/// Descriptor of an event counter
+pub struct EventDescriptor
+{
+    counter: u64,
+    semaphore: bool,
+    status_flags: usize
+}
+
+impl EventDescriptor
+{
+    /// Create an event counter starting at the given value
+    pub fn new(initial: u64, semaphore: bool) -> Self
+    {
+        Self
+        {
+            counter: initial,
+            semaphore,
+            status_flags: O_RDONLY | O_WRONLY
+        }
+    }
+}
+
+impl FileDescriptor for EventDescriptor
+{
+    fn close(&mut self, _fs: &mut fs::vfs::FilesystemInterface)
+    {
+        // Nothing is held outside of the descriptor
+    }
+
+    /// Add the eight byte value in the buffer to the counter, a write which
+    /// would overflow the counter fails with `EAGAIN` rather than blocking
+    fn write(&mut self, _fs: &mut fs::vfs::FilesystemInterface, buffer: *mut u8, count: usize) -> usize
+    {
+        if count < 8
+        {
+            return errno::EINVAL;
+        }
+
+        let value = unsafe { (buffer as *const u64).read_unaligned() };
+
+        if value == u64::MAX
+        {
+            return errno::EINVAL;
+        }
+
+        match self.counter.checked_add(value).filter(|sum| *sum <= EVENT_COUNTER_MAX)
+        {
+            Some(sum) =>
+            {
+                self.counter = sum;
+                8
+            },
+            None => errno::EAGAIN
+        }
+    }
+
+    /// Read the counter as an eight byte value, reads are only made once the
+    /// counter is non zero
+    fn read(&mut self, _fs: &mut fs::vfs::FilesystemInterface, buffer: *mut u8, count: usize) -> usize
+    {
+        if count < 8
+        {
+            return errno::EINVAL;
+        }
+
+        if self.counter == 0
+        {
+            return errno::EAGAIN;
+        }
+
+        let value = if self.semaphore { 1 } else { self.counter };
+        self.counter -= value;
+
+        unsafe { (buffer as *mut u64).write_unaligned(value) };
+
+        8
+    }
+
+    fn get_inode(&mut self) -> Option<FilesystemIndex>
+    {
+        None
+    }
+
+    fn check_available(&self) -> bool
+    {
+        self.counter > 0
+    }
+
+    fn check_writable(&self) -> bool
+    {
+        self.counter < EVENT_COUNTER_MAX
+    }
+
+    fn get_status_flags(&self) -> usize
+    {
+        self.status_flags
+    }
+
+    fn set_status_flags(&mut self, flags: usize)
+    {
+        self.status_flags = flags;
+    }
+}
@@ -0,0 +1,97 @@
+//! Flat Binary Loader
+//!
+//! A flat binary is an eight byte header (the magic followed by the offset of
+//! the entry point) and a raw image which is loaded read, write, execute at
+//! `FLAT_LOAD_ADDRESS`
+
+use crate::*;
+
+use super::loading;
+
+use alloc::vec::Vec;
+use libutils::paths::PathBuffer;
+
+use super::process::Process;
+
+use super::stats::MemoryStats;
+
+/// Magic at the start of every flat binary
+pub const FLAT_MAGIC: [u8; 4] = [0x7F, 'Q' as u8, 'F' as u8, 'B' as u8];
+
+/// Address the image is loaded to
+pub const FLAT_LOAD_ADDRESS: usize = 0x1_0000_0000;
+
+// Size of the header before the image
+const FLAT_HEADER_SIZE: usize = 8;
+
+/// Load a flat binary and convert it to a process
+pub fn load_flat(file_data: &[u8], path: PathBuffer, args: &Vec<String>, envp: &Vec<String>) -> Result<Process, loading::ProcessLoadError>
+{
+    kdebugln!(Processes, "Loading Flat Binary `{}`", path);
+
+    if file_data.len() < FLAT_HEADER_SIZE || file_data[0..4] != FLAT_MAGIC
+    {
+        return Err(loading::ProcessLoadError::NotAnExecutable);
+    }
+
+    let entry_offset = u32::from_le_bytes([file_data[4], file_data[5], file_data[6], file_data[7]]) as usize;
+    let image = &file_data[FLAT_HEADER_SIZE..];
+
+    if entry_offset >= image.len()
+    {
+        return Err(loading::ProcessLoadError::BadFormat(String::from("Flat binary entry point is outside of the image")));
+    }
+
+    // Allocate a new page table
+    let table = unsafe { (mem::kpzalloc(1, "Flat Page Table").unwrap() as *mut mem::mmu::PageTable).as_mut().unwrap() };
+
+    // Link in the kernel's half of the address space
+    table.share_kernel_space();
+
+    // Map the image
+    let num_pages = (image.len() + mem::PAGE_SIZE - 1) / mem::PAGE_SIZE;
+    let phys_ptr = mem::kpzalloc(num_pages, "Flat Image").unwrap() as *mut u8;
+
+    for (i, byte) in image.iter().enumerate()
+    {
+        unsafe { phys_ptr.add(i).write(*byte) }
+    }
+
+    let flags = mem::mmu::PageTableEntryFlags::user() | mem::mmu::PageTableEntryFlags::readable() | mem::mmu::PageTableEntryFlags::writable() | mem::mmu::PageTableEntryFlags::executable() | mem::mmu::PageTableEntryFlags::dirty() | mem::mmu::PageTableEntryFlags::accessed();
+
+    for i in 0..num_pages
+    {
+        table.map(FLAT_LOAD_ADDRESS + i * mem::PAGE_SIZE, phys_ptr as usize + i * mem::PAGE_SIZE, flags, 0);
+    }
+
+    let stack_size = 1;
+
+    // Allocate the stack space
+    let stack_space = mem::kpzalloc(stack_size, "Flat Stack Space").unwrap();
+
+    for i in 0..stack_size
+    {
+        table.map(super::process::STACK_END - mem::PAGE_SIZE - mem::PAGE_SIZE * i, stack_space + mem::PAGE_SIZE * i, flags, 0);
+    }
+
+    let mem_stats = MemoryStats::new(0, 0, num_pages, stack_size);
+
+    let mut proc = Process::from_components(
+        FLAT_LOAD_ADDRESS + entry_offset, 
+        table as *mut mem::mmu::PageTable, 
+        stack_size, super::process::STACK_END - mem::PAGE_SIZE * stack_size,
+        mem_stats);
+
+    proc.data.mem.push((phys_ptr, num_pages));
+    proc.init_heap(FLAT_LOAD_ADDRESS + num_pages * mem::PAGE_SIZE);
+
+    // Flat binaries have no program headers to pass
+    proc.data.aux_vector = vec![(super::process::AT_PAGESZ, mem::PAGE_SIZE), (super::process::AT_ENTRY, FLAT_LOAD_ADDRESS + entry_offset)];
+
+    let mut full_arguments = vec![path.as_str().to_string()];
+    full_arguments.extend_from_slice(&args);
+
+    proc.set_arguments(&full_arguments, envp);
+
+    Ok(proc)
+}
@@ -1,14 +1,21 @@
-/// init process
-pub fn init_proc()
+//! Code for the init process
+
+// Bounds of the init process code from init.s
+extern "C"
 {
-    loop
+    static INIT_CODE_START: u8;
+    static INIT_CODE_END: u8;
+}
+
+/// Get the machine code run by the init process
+pub fn init_code() -> &'static [u8]
+{
+    // Safety: The symbols bound the code placed in rodata by init.s
+    unsafe
     {
-        /*
-        unsafe 
-        {
-            asm!("li a7, 61");
-            asm!("li a0, 0");
-            asm!("ecall");
-        }*/
+        let start = &INIT_CODE_START as *const u8;
+        let end = &INIT_CODE_END as *const u8;
+
+        core::slice::from_raw_parts(start, end as usize - start as usize)
     }
-}
\ No newline at end of file
+}
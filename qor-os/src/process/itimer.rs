@@ -0,0 +1,58 @@
+use crate::*;
+
+use drivers::timer::KernelTime;
+
+// Must be kept in sync with syscalls.h
+pub const ITIMER_REAL: usize = 0;
+pub const ITIMER_VIRTUAL: usize = 1;
+pub const ITIMER_PROF: usize = 2;
+
+/// Interval timer counting down in real time, a process is sent `SIGALRM`
+/// when it expires and the timer is reloaded from its interval if one is set
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IntervalTimer
+{
+    pub expires: Option<KernelTime>,
+    pub interval: KernelTime
+}
+
+impl IntervalTimer
+{
+    /// Create a new disarmed timer
+    pub fn new() -> Self
+    {
+        Self
+        {
+            expires: None,
+            interval: KernelTime(0)
+        }
+    }
+
+    /// Get the time left before the timer expires, zero if it is disarmed
+    pub fn remaining(&self, now: KernelTime) -> KernelTime
+    {
+        self.expires.map(|expires| expires - now).unwrap_or(KernelTime(0))
+    }
+
+    /// Arm the timer to expire after the given time, a value of zero
+    /// disarms it
+    pub fn set(&mut self, now: KernelTime, value: KernelTime, interval: KernelTime)
+    {
+        self.expires = if value.0 > 0 { Some(now + value) } else { None };
+        self.interval = interval;
+    }
+
+    /// Reload the timer once it has expired, disarming it if there is no
+    /// interval. Expiries missed while the kernel was busy are not made up,
+    /// the timer is next due one interval from now.
+    pub fn reload(&mut self, now: KernelTime)
+    {
+        self.expires = match self.expires
+        {
+            Some(_) if self.interval.0 == 0 => None,
+            Some(expires) if expires + self.interval > now => Some(expires + self.interval),
+            Some(_) => Some(now + self.interval),
+            None => None
+        };
+    }
+}
@@ -4,9 +4,12 @@ use crate::*;
 use super::loading;
 
 use fs::fstrait::Filesystem;
+use fs::namespace::SharedMountNamespace;
+use fs::structures::FilesystemIndex;
 
+use alloc::sync::Arc;
 use alloc::vec::Vec;
-use libutils::paths::{PathBuffer, OwnedPath};
+use libutils::paths::PathBuffer;
 
 use super::process::Process;
 
@@ -21,37 +24,34 @@ pub enum ProcessLoadError
 }
 
 
-pub fn load_process(interface: &mut fs::vfs::FilesystemInterface, path: PathBuffer, args: &mut Vec<String>, envp: &mut Vec<String>) -> Result<Process, ProcessLoadError>
+/// Load a process from the executable at the given path
+pub fn load_process(interface: &mut fs::vfs::FilesystemInterface, namespace: &Option<SharedMountNamespace>, root: Option<FilesystemIndex>, path: PathBuffer, args: &mut Vec<String>, envp: &mut Vec<String>) -> Result<Process, ProcessLoadError>
 {
     // Open the file
-    let index = interface.path_to_inode(path).map_err(|e| loading::ProcessLoadError::ReadError(e))?;
-    let file_data = interface.read_inode(index).map_err(|e| loading::ProcessLoadError::ReadError(e))?;
+    let index = fs::namespace::path_to_inode(namespace, root, interface, path).map_err(|e| loading::ProcessLoadError::ReadError(e))?;
 
-    // If the file is an ELF file, load that file
-    if file_data[0..4] == [0x7F, 'E' as u8, 'L' as u8, 'F' as u8]
+    let cache = super::textcache::get_text_cache();
+
+    let file_data = if let Some(image) = cache.lookup(index)
     {
-        super::elf::load_elf(file_data, path, args, envp)
+        image
     }
-    else if file_data[0..2] == ['#' as u8, '!' as u8]
+    else
     {
-        let mut f = String::new();
-
-        for (i, c) in file_data.iter().enumerate()
-        {
-            if i < 2 { continue }
-
-            if *c == '\n' as u8 { break }
+        Arc::new(interface.read_inode(index).map_err(|e| loading::ProcessLoadError::ReadError(e))?)
+    };
 
-            f.push(*c as char);
-        }
-
-        args.insert(0, path.to_string());
+    // Hand the file off to the first format which recognizes it, keeping the
+    // image if it loaded so the next exec of it skips the disk
+    if let Some(format) = super::binfmt::find_binary_format(&file_data)
+    {
+        let proc = format.load(interface, namespace, root, &file_data, path, args, envp)?;
+        cache.insert(index, file_data);
 
-        load_process(interface, &OwnedPath::new(f), args, envp)
+        Ok(proc)
     }
     else
     {
         Err(loading::ProcessLoadError::NotAnExecutable)
     }
-
 }
\ No newline at end of file
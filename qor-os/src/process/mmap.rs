@@ -0,0 +1,97 @@
+//! Memory Mappings
+//!
+//! Regions of a process created by mmap, each mapping records what backs its
+//! pages so they can be written back or freed when the region is unmapped
+
+use crate::*;
+
+use alloc::sync::Arc;
+use core::cell::RefCell;
+
+use mem::mmu::PageTableEntryFlags;
+
+use super::descriptor::FileDescriptor;
+use super::shm::SharedMemory;
+
+/// Lowest address used for mappings
+pub const MMAP_START: usize = 0x4_0000_0000;
+
+/// Shared reference to an open file descriptor
+pub type SharedDescriptor = Arc<RefCell<Box<dyn FileDescriptor>>>;
+
+/// Memory backing a mapping
+#[derive(Clone)]
+pub enum MappingBacking
+{
+    // Zeroed pages owned by the process
+    Anonymous,
+    // Pages owned by the process filled from a file, shared mappings are
+    // written back to the file when they are synced or unmapped
+    File { descriptor: SharedDescriptor, offset: usize, shared: bool },
+    // Memory owned by a device, such as a framebuffer
+    Device { physical: usize },
+    // Pages of a shared memory object, which every shared mapping of the
+    // object refers to
+    SharedMemory { object: SharedMemory, offset: usize }
+}
+
+/// Memory Mapping
+#[derive(Clone)]
+pub struct MemoryMapping
+{
+    pub pages: usize,
+    pub perm: Option<PageTableEntryFlags>,
+    pub backing: MappingBacking
+}
+
+impl MemoryMapping
+{
+    /// Create a new mapping, a permission of `None` reserves the region
+    /// without mapping any pages
+    pub fn new(pages: usize, perm: Option<PageTableEntryFlags>, backing: MappingBacking) -> Self
+    {
+        Self
+        {
+            pages,
+            perm,
+            backing
+        }
+    }
+
+    /// Check if the pages of the mapping are owned by the process
+    pub fn owns_pages(&self) -> bool
+    {
+        !matches!(self.backing, MappingBacking::Device { .. } | MappingBacking::SharedMemory { .. })
+    }
+
+    /// Get the physical address of a page of a mapping whose memory is not
+    /// owned by the process, which must be the same in every process mapping
+    /// it
+    pub fn shared_page(&self, page: usize) -> Option<usize>
+    {
+        match &self.backing
+        {
+            MappingBacking::Device { physical } => Some(physical + page * mem::PAGE_SIZE),
+            MappingBacking::SharedMemory { object, offset } => object.borrow_mut().page(offset / mem::PAGE_SIZE + page).ok(),
+            _ => None
+        }
+    }
+
+    /// Get the part of the mapping starting at the given page
+    pub fn slice(&self, first_page: usize, pages: usize) -> Self
+    {
+        let byte_offset = first_page * mem::PAGE_SIZE;
+
+        let backing = match &self.backing
+        {
+            MappingBacking::Anonymous => MappingBacking::Anonymous,
+            MappingBacking::File { descriptor, offset, shared } =>
+                MappingBacking::File { descriptor: descriptor.clone(), offset: offset + byte_offset, shared: *shared },
+            MappingBacking::Device { physical } => MappingBacking::Device { physical: physical + byte_offset },
+            MappingBacking::SharedMemory { object, offset } =>
+                MappingBacking::SharedMemory { object: object.clone(), offset: offset + byte_offset }
+        };
+
+        Self::new(pages, self.perm, backing)
+    }
+}
@@ -1,14 +1,29 @@
 // Modules
+pub mod binfmt;
+pub mod credentials;
 pub mod data;
 pub mod descriptor;
 pub mod elf;
+pub mod eventfd;
+pub mod flat;
 pub mod init;
+pub mod itimer;
 pub mod loading;
+pub mod mmap;
+pub mod poll;
 pub mod process;
+pub mod rescg;
+pub mod rlimit;
+pub mod runqueue;
 pub mod scheduler;
+pub mod shm;
+pub mod signalfd;
 pub mod stats;
 pub mod signals;
+pub mod textcache;
+pub mod wait;
 
-mod pipe;
+pub mod pipe;
+pub mod socket;
 
 pub type PID = u16;
\ No newline at end of file
@@ -1,13 +1,303 @@
 use crate::*;
 use super::descriptor::*;
 
+use alloc::collections::VecDeque;
+use alloc::sync::Arc;
+
 use crate::fs::structures::FilesystemIndex;
 
+/// Capacity given to new pipes
+pub const PIPE_DEFAULT_SIZE: usize = 64 * 1024;
+
+/// Largest write to a pipe which is never interleaved with other writes
+pub const PIPE_BUF: usize = 4096;
+
+// Largest capacity a pipe can be given, configured through /sys/pipe-max-size
+static mut PIPE_MAX_SIZE: usize = 1024 * 1024;
+
+/// Get the largest capacity a pipe can be given
+pub fn pipe_max_size() -> usize
+{
+    unsafe { PIPE_MAX_SIZE }
+}
+
+/// Set the largest capacity a pipe can be given, this is rounded up to a
+/// whole number of pages
+pub fn set_pipe_max_size(size: usize)
+{
+    unsafe { PIPE_MAX_SIZE = round_to_pages(size.max(PIPE_DEFAULT_SIZE)) };
+}
+
+/// Round a pipe capacity up to a whole number of pages
+fn round_to_pages(size: usize) -> usize
+{
+    (size.max(1) + mem::PAGE_SIZE - 1) / mem::PAGE_SIZE * mem::PAGE_SIZE
+}
+
+/// Reference to part of a page of pipe data, pages are shared between pipes
+/// by tee and moved between them by splice rather than being copied
+#[derive(Clone)]
+struct PipeChunk
+{
+    page: Arc<Vec<u8>>,
+    start: usize,
+    end: usize
+}
+
+impl PipeChunk
+{
+    /// Get the data of the chunk
+    fn bytes(&self) -> &[u8]
+    {
+        &self.page[self.start..self.end]
+    }
+
+    /// Get the first `count` bytes of the chunk
+    fn prefix(&self, count: usize) -> Self
+    {
+        Self
+        {
+            page: self.page.clone(),
+            start: self.start,
+            end: self.start + count.min(self.end - self.start)
+        }
+    }
+}
+
+/// Data held in a pipe as a queue of page references, the storage grows as
+/// data is written up to the capacity of the pipe
+pub struct PipeBuffer
+{
+    chunks: VecDeque<PipeChunk>,
+    length: usize,
+    capacity: usize
+}
+
+impl PipeBuffer
+{
+    /// Create a new empty pipe buffer
+    pub fn new(capacity: usize) -> Self
+    {
+        Self
+        {
+            chunks: VecDeque::new(),
+            length: 0,
+            capacity
+        }
+    }
+
+    /// Get the capacity of the pipe
+    pub fn capacity(&self) -> usize
+    {
+        self.capacity
+    }
+
+    /// Get the number of bytes in the pipe
+    pub fn len(&self) -> usize
+    {
+        self.length
+    }
+
+    /// Get the number of bytes which can be added to the pipe
+    pub fn space(&self) -> usize
+    {
+        self.capacity - self.length.min(self.capacity)
+    }
+
+    /// Check if the pipe is empty
+    pub fn is_empty(&self) -> bool
+    {
+        self.length == 0
+    }
+
+    /// Get a page with room for more data at the end of the pipe, pages
+    /// which are shared with another pipe are never written to
+    fn tail_page(&mut self) -> &mut Vec<u8>
+    {
+        let reusable = self.chunks.back_mut()
+            .map(|chunk| chunk.end == chunk.page.len() && chunk.page.len() < mem::PAGE_SIZE && Arc::get_mut(&mut chunk.page).is_some())
+            .unwrap_or(false);
+
+        if !reusable
+        {
+            let page = Arc::new(Vec::with_capacity(mem::PAGE_SIZE));
+            self.chunks.push_back(PipeChunk { page, start: 0, end: 0 });
+        }
+
+        Arc::get_mut(&mut self.chunks.back_mut().unwrap().page).unwrap()
+    }
+
+    /// Fill the pipe from a source which writes into the buffer it is given
+    /// and returns the number of bytes written, this stops at the first short
+    /// fill and returns the total number of bytes added
+    pub fn fill<F: FnMut(&mut [u8]) -> usize>(&mut self, max: usize, mut source: F) -> usize
+    {
+        let mut total = 0;
+
+        while total < max && self.space() > 0
+        {
+            let wanted = (max - total).min(self.space());
+
+            let page = self.tail_page();
+            let old_length = page.len();
+            let count = wanted.min(mem::PAGE_SIZE - old_length);
+
+            page.resize(old_length + count, 0);
+            let added = source(&mut page[old_length..]).min(count);
+            page.truncate(old_length + added);
+
+            let chunk = self.chunks.back_mut().unwrap();
+            chunk.end += added;
+
+            // Drop the page again if nothing was written into it
+            if chunk.start == chunk.end
+            {
+                self.chunks.pop_back();
+            }
+
+            self.length += added;
+            total += added;
+
+            if added < count
+            {
+                break;
+            }
+        }
+
+        total
+    }
+
+    /// Write as much of the buffer into the pipe as will fit, returning the
+    /// number of bytes written
+    pub fn write(&mut self, buffer: &[u8]) -> usize
+    {
+        let mut offset = 0;
+
+        self.fill(buffer.len(), |dest|
+        {
+            let count = dest.len().min(buffer.len() - offset);
+            dest[..count].copy_from_slice(&buffer[offset..offset + count]);
+            offset += count;
+
+            count
+        })
+    }
+
+    /// Drain the pipe into a sink which consumes a prefix of the data it is
+    /// given and returns the number of bytes consumed, this stops at the
+    /// first short drain and returns the total number of bytes removed
+    pub fn drain<F: FnMut(&[u8]) -> usize>(&mut self, max: usize, mut sink: F) -> usize
+    {
+        let mut total = 0;
+
+        while total < max
+        {
+            let chunk = if let Some(chunk) = self.chunks.front_mut() { chunk } else { break; };
+
+            let available = chunk.bytes().len().min(max - total);
+            let consumed = sink(&chunk.bytes()[..available]).min(available);
+
+            chunk.start += consumed;
+            self.length -= consumed;
+            total += consumed;
+
+            if chunk.start == chunk.end
+            {
+                self.chunks.pop_front();
+            }
+
+            if consumed < available
+            {
+                break;
+            }
+        }
+
+        total
+    }
+
+    /// Read as much of the pipe into the buffer as is available, returning
+    /// the number of bytes read
+    pub fn read(&mut self, buffer: &mut [u8]) -> usize
+    {
+        let mut offset = 0;
+
+        self.drain(buffer.len(), |data|
+        {
+            buffer[offset..offset + data.len()].copy_from_slice(data);
+            offset += data.len();
+
+            data.len()
+        })
+    }
+
+    /// Add references to the data at the front of this pipe to the end of
+    /// another, the data is left in this pipe when `consume` is false
+    pub fn transfer(&mut self, dest: &mut PipeBuffer, max: usize, consume: bool) -> usize
+    {
+        let mut total = 0;
+
+        for chunk in &self.chunks
+        {
+            let count = chunk.bytes().len().min(max - total).min(dest.space());
+
+            if count == 0
+            {
+                break;
+            }
+
+            dest.chunks.push_back(chunk.prefix(count));
+            dest.length += count;
+            total += count;
+        }
+
+        if consume
+        {
+            self.drain(total, |data| data.len());
+        }
+
+        total
+    }
+
+    /// Change the capacity of the pipe, returning the new capacity, the pipe
+    /// cannot be made smaller than the data it currently holds
+    pub fn resize(&mut self, size: usize) -> Result<usize, usize>
+    {
+        let size = round_to_pages(size);
+
+        if size > pipe_max_size()
+        {
+            return Err(errno::EPERM);
+        }
+
+        if size < self.length
+        {
+            return Err(errno::EBUSY);
+        }
+
+        self.capacity = size;
+
+        Ok(size)
+    }
+}
+
+/// Pipe buffer shared between both ends of a pipe
+pub type SharedPipeBuffer = alloc::sync::Arc<core::cell::RefCell<PipeBuffer>>;
+
+/// End of a pipe which a descriptor refers to, used by splice and tee to move
+/// data in and out of the pipe without copying it through userspace
+pub struct PipeEnd
+{
+    pub buffer: SharedPipeBuffer,
+    pub writable: bool,
+    pub peer_closed: bool
+}
+
 /// Write side of a pipe
 pub struct WritePipeDescriptor
 {
-    buffer: alloc::sync::Arc<core::cell::RefCell<utils::ByteRingBuffer>>,
-    read_end: Option<alloc::sync::Weak<core::cell::RefCell<Box<dyn FileDescriptor>>>>
+    buffer: SharedPipeBuffer,
+    read_end: Option<alloc::sync::Weak<core::cell::RefCell<Box<dyn FileDescriptor>>>>,
+    status_flags: usize
 }
 
 impl WritePipeDescriptor
@@ -39,12 +329,17 @@ impl FileDescriptor for WritePipeDescriptor
             return errno::EPIPE;
         }
 
-        for i in 0..count
+        let mut pipe_buffer = self.buffer.borrow_mut();
+
+        // Writes of up to PIPE_BUF bytes go in whole or not at all
+        if count <= PIPE_BUF && pipe_buffer.space() < count
         {
-            self.buffer.borrow_mut().enqueue_byte(unsafe { buffer.add(i).read() });
+            return 0;
         }
 
-        count
+        // If the pipe is full, report a short write rather than dropping the
+        // remaining data
+        pipe_buffer.write(unsafe { core::slice::from_raw_parts(buffer, count) })
     }
 
     fn read(&mut self, _fs: &mut fs::vfs::FilesystemInterface, _buffer: *mut u8, _count: usize) -> usize
@@ -58,17 +353,53 @@ impl FileDescriptor for WritePipeDescriptor
         None
     }
 
+    fn check_writable(&self) -> bool
+    {
+        self.buffer.borrow().space() > 0 || self.is_end_closed()
+    }
+
+    fn check_hangup(&self) -> bool
+    {
+        self.is_end_closed()
+    }
+
+    fn get_pipe_size(&self) -> Result<usize, usize>
+    {
+        Ok(self.buffer.borrow().capacity())
+    }
+
+    fn set_pipe_size(&mut self, size: usize) -> Result<usize, usize>
+    {
+        self.buffer.borrow_mut().resize(size)
+    }
+
+    fn get_pipe_end(&self) -> Option<PipeEnd>
+    {
+        Some(PipeEnd { buffer: self.buffer.clone(), writable: true, peer_closed: self.is_end_closed() })
+    }
+
     fn set_end(&mut self, end: &alloc::sync::Arc<core::cell::RefCell<Box<dyn FileDescriptor>>>)
     {
         self.read_end = Some(alloc::sync::Arc::<core::cell::RefCell::<Box<dyn FileDescriptor>>>::downgrade(end));
     }
+
+    fn get_status_flags(&self) -> usize
+    {
+        self.status_flags
+    }
+
+    fn set_status_flags(&mut self, flags: usize)
+    {
+        self.status_flags = flags;
+    }
 }
 
 /// Read side of a pipe
 pub struct ReadPipeDescriptor
 {
-    buffer: alloc::sync::Arc<core::cell::RefCell<utils::ByteRingBuffer>>,
-    write_end: Option<alloc::sync::Weak<core::cell::RefCell<Box<dyn FileDescriptor>>>>
+    buffer: SharedPipeBuffer,
+    write_end: Option<alloc::sync::Weak<core::cell::RefCell<Box<dyn FileDescriptor>>>>,
+    status_flags: usize
 }
 
 impl ReadPipeDescriptor
@@ -101,19 +432,7 @@ impl FileDescriptor for ReadPipeDescriptor
 
     fn read(&mut self, _fs: &mut fs::vfs::FilesystemInterface, buffer: *mut u8, count: usize) -> usize
     {
-        for i in 0..count
-        {
-            if let Some(data) = self.buffer.borrow_mut().dequeue_byte()
-            {
-                unsafe { buffer.add(i).write(data) }
-            }
-            else
-            {
-                return i;
-            }
-        }
-
-        count
+        self.buffer.borrow_mut().read(unsafe { core::slice::from_raw_parts_mut(buffer, count) })
     }
 
     fn get_inode(&mut self) -> Option<FilesystemIndex>
@@ -126,23 +445,58 @@ impl FileDescriptor for ReadPipeDescriptor
         !self.buffer.borrow_mut().is_empty() || self.is_end_closed()
     }
 
+    fn check_writable(&self) -> bool
+    {
+        false
+    }
+
+    fn check_hangup(&self) -> bool
+    {
+        self.is_end_closed()
+    }
+
+    fn get_pipe_size(&self) -> Result<usize, usize>
+    {
+        Ok(self.buffer.borrow().capacity())
+    }
+
+    fn set_pipe_size(&mut self, size: usize) -> Result<usize, usize>
+    {
+        self.buffer.borrow_mut().resize(size)
+    }
+
+    fn get_pipe_end(&self) -> Option<PipeEnd>
+    {
+        Some(PipeEnd { buffer: self.buffer.clone(), writable: false, peer_closed: self.is_end_closed() })
+    }
+
     fn set_end(&mut self, end: &alloc::sync::Arc<core::cell::RefCell<Box<dyn FileDescriptor>>>)
     {
         self.write_end = Some(alloc::sync::Arc::<core::cell::RefCell::<Box<dyn FileDescriptor>>>::downgrade(end));
     }
+
+    fn get_status_flags(&self) -> usize
+    {
+        self.status_flags
+    }
+
+    fn set_status_flags(&mut self, flags: usize)
+    {
+        self.status_flags = flags;
+    }
 }
 
 /// Create a new pipe pair
 pub fn new_pipe() -> (alloc::sync::Arc<core::cell::RefCell<Box<dyn FileDescriptor>>>, alloc::sync::Arc<core::cell::RefCell<Box<dyn FileDescriptor>>>)
 {
-    let buffer = utils::ByteRingBuffer::new();
+    let buffer = PipeBuffer::new(PIPE_DEFAULT_SIZE);
     let wrapped_buffer = 
         alloc::sync::Arc::new(
             core::cell::RefCell::new(
                 buffer));
     
-    let read = alloc::sync::Arc::new(core::cell::RefCell::new(Box::new(ReadPipeDescriptor { buffer: wrapped_buffer.clone(), write_end: None }) as Box<dyn FileDescriptor>));
-    let write = alloc::sync::Arc::new(core::cell::RefCell::new(Box::new(WritePipeDescriptor { buffer: wrapped_buffer.clone(), read_end: None }) as Box<dyn FileDescriptor>));
+    let read = alloc::sync::Arc::new(core::cell::RefCell::new(Box::new(ReadPipeDescriptor { buffer: wrapped_buffer.clone(), write_end: None, status_flags: O_RDONLY }) as Box<dyn FileDescriptor>));
+    let write = alloc::sync::Arc::new(core::cell::RefCell::new(Box::new(WritePipeDescriptor { buffer: wrapped_buffer.clone(), read_end: None, status_flags: O_WRONLY }) as Box<dyn FileDescriptor>));
 
     read.borrow_mut().set_end(&write);
     write.borrow_mut().set_end(&read);
@@ -0,0 +1,220 @@
+//! Polling File Descriptors
+//!
+//! Readiness of a set of descriptors which a process waits on through poll
+//! or select. A blocked call is checked again each time the scheduler looks
+//! over the waiting processes, so data arriving on a tty or pipe wakes it.
+
+use crate::*;
+
+use drivers::timer::KernelTime;
+
+use super::process::Process;
+
+// Must be kept in sync with syscalls.h
+pub const POLLIN: i16 =   0x0001;
+pub const POLLPRI: i16 =  0x0002;
+pub const POLLOUT: i16 =  0x0004;
+pub const POLLERR: i16 =  0x0008;
+pub const POLLHUP: i16 =  0x0010;
+pub const POLLNVAL: i16 = 0x0020;
+
+// Largest number of entries a single poll may pass
+pub const POLL_MAX_DESCRIPTORS: usize = 1024;
+
+// Number of descriptors an fd_set can hold
+pub const FD_SETSIZE: usize = 1024;
+
+/// Entry of the array passed to poll
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PollFd
+{
+    pub fd: i32,
+    pub events: i16,
+    pub revents: i16
+}
+
+/// Descriptors a call is waiting on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PollSet
+{
+    // User address of an array of `count` PollFd entries
+    Array{ fds: usize, count: usize },
+    // User addresses of the read, write and exception fd_sets covering the
+    // first `count` descriptors, null sets are skipped
+    Select{ count: usize, sets: [usize; 3] }
+}
+
+/// Outstanding poll or select call, a call without a deadline waits until a
+/// descriptor is ready
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PollRequest
+{
+    pub set: PollSet,
+    pub deadline: Option<KernelTime>
+}
+
+/// Get the events which are ready on a descriptor, errors and hangups are
+/// reported whether or not they were requested
+fn descriptor_events(proc: &Process, fd: i32, requested: i16) -> i16
+{
+    if fd < 0
+    {
+        return 0;
+    }
+
+    let descriptor = if let Some(descriptor) = proc.data.descriptors.get(&(fd as usize))
+    {
+        descriptor
+    }
+    else
+    {
+        return POLLNVAL;
+    };
+
+    let descriptor = descriptor.borrow();
+
+    let mut events = 0;
+
+    // Signals are pending on the process, rather than held by the descriptor
+    let available = match descriptor.get_signal_mask()
+    {
+        Some(set) => proc.has_pending_signal_in(set),
+        None => descriptor.check_available()
+    };
+
+    if requested & POLLIN != 0 && available
+    {
+        events |= POLLIN;
+    }
+
+    if requested & POLLOUT != 0 && descriptor.check_writable()
+    {
+        events |= POLLOUT;
+    }
+
+    if descriptor.check_hangup()
+    {
+        events |= POLLHUP;
+    }
+
+    events
+}
+
+/// Evaluate the readiness of every entry in a poll array, writing the
+/// results back to userspace and returning the number of ready entries
+fn poll_array(proc: &mut Process, fds: usize, count: usize) -> Result<usize, usize>
+{
+    let mut entries = vec![PollFd::default(); count];
+    let size = count * core::mem::size_of::<PollFd>();
+
+    let bytes = unsafe { core::slice::from_raw_parts_mut(entries.as_mut_ptr() as *mut u8, size) };
+    syscalls::utils::copy_from_user(proc, fds, bytes)?;
+
+    let mut ready = 0;
+
+    for entry in entries.iter_mut()
+    {
+        entry.revents = descriptor_events(proc, entry.fd, entry.events);
+
+        if entry.revents != 0
+        {
+            ready += 1;
+        }
+    }
+
+    let bytes = unsafe { core::slice::from_raw_parts(entries.as_ptr() as *const u8, size) };
+    syscalls::utils::copy_to_user(proc, fds, bytes)?;
+
+    Ok(ready)
+}
+
+/// Evaluate the readiness of the descriptors in a set of fd_sets, returning
+/// the number of bits set in the results. The sets are only overwritten when
+/// the call finishes, as a blocked select must check the same sets again.
+fn select_sets(proc: &mut Process, count: usize, sets: [usize; 3], finish: bool) -> Result<usize, usize>
+{
+    // Sets are copied as whole words, as userspace allocates them
+    let size = (count + 63) / 64 * 8;
+
+    let mut requested = [[0u8; FD_SETSIZE / 8]; 3];
+    let mut results = [[0u8; FD_SETSIZE / 8]; 3];
+
+    for (set, addr) in sets.iter().enumerate()
+    {
+        if *addr != 0
+        {
+            syscalls::utils::copy_from_user(proc, *addr, &mut requested[set][..size])?;
+        }
+    }
+
+    // Events checked for the descriptors in each set, hangups count as
+    // readable as a read will not block
+    let events = [POLLIN | POLLHUP, POLLOUT | POLLERR, POLLPRI];
+    let mut ready = 0;
+
+    for fd in 0..count
+    {
+        let (byte, bit) = (fd / 8, 1 << (fd % 8));
+
+        let wanted = (0..3).filter(|set| requested[*set][byte] & bit != 0).fold(0, |acc, set| acc | events[set]);
+
+        if wanted == 0
+        {
+            continue;
+        }
+
+        let found = descriptor_events(proc, fd as i32, wanted);
+
+        if found & POLLNVAL != 0
+        {
+            return Err(errno::EBADF);
+        }
+
+        for set in 0..3
+        {
+            if requested[set][byte] & bit != 0 && found & events[set] != 0
+            {
+                results[set][byte] |= bit;
+                ready += 1;
+            }
+        }
+    }
+
+    if ready > 0 || finish
+    {
+        for (set, addr) in sets.iter().enumerate()
+        {
+            if *addr != 0
+            {
+                syscalls::utils::copy_to_user(proc, *addr, &results[set][..size])?;
+            }
+        }
+    }
+
+    Ok(ready)
+}
+
+/// Evaluate the readiness of the descriptors in the request, returning the
+/// number which are ready, `finish` is set if the call will not wait again
+pub fn poll_descriptors(proc: &mut Process, request: &PollRequest, finish: bool) -> Result<usize, usize>
+{
+    match request.set
+    {
+        PollSet::Array { fds, count } => poll_array(proc, fds, count),
+        PollSet::Select { count, sets } => select_sets(proc, count, sets, finish)
+    }
+}
+
+/// Check a blocked poll or select, returning the value to give back to the
+/// process if it can stop waiting
+pub fn check_poll(proc: &mut Process, request: &PollRequest, now: KernelTime) -> Option<Result<usize, usize>>
+{
+    let timed_out = request.deadline.map(|deadline| now >= deadline).unwrap_or(false);
+
+    match poll_descriptors(proc, request, timed_out)
+    {
+        Ok(0) if !timed_out => None,
+        result => Some(result)
+    }
+}
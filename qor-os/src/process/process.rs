@@ -3,13 +3,18 @@ use crate::drivers::timer::KernelTime;
 use crate::fs::fstrait::Filesystem;
 use crate::mem::mmu::PageTableEntryFlags;
 
+use alloc::collections::BTreeMap;
+
 use fs::structures::DirectoryEntry;
 use libutils::paths::OwnedPath;
 use libutils::paths::PathBuffer;
 
 use super::data::ProcessData;
-use super::descriptor::FileDescriptor;
+use super::descriptor::{FileDescriptor, SeekMode, FD_CLOEXEC, O_CLOEXEC, O_STATUS_FLAGS};
+use super::mmap::{MappingBacking, MemoryMapping, SharedDescriptor, MMAP_START};
+use super::runqueue::RunQueueLink;
 use super::stats::MemoryStats;
+use super::wait::JobEvent;
 
 use mem::mmu::PageTable;
 use mem::mmu::TranslationError;
@@ -23,13 +28,25 @@ use super::PID;
 // Global PID counter
 static mut NEXT_PID: PID = 0;
 
-/// Get the next PID
+/// Get the next PID, once the counter wraps around any PID still held by a
+/// process is skipped
 fn next_pid() -> PID
 {
-    unsafe
+    loop
     {
-        NEXT_PID += 1;
-        NEXT_PID - 1
+        let pid = unsafe { NEXT_PID };
+
+        // PID 0 always belongs to init
+        unsafe { NEXT_PID = NEXT_PID.checked_add(1).unwrap_or(1) };
+
+        let taken = super::scheduler::get_process_manager()
+                        .map(|manager| manager.processes.contains_key(&pid))
+                        .unwrap_or(false);
+
+        if !taken
+        {
+            return pid;
+        }
     }
 }
 
@@ -45,20 +62,43 @@ const SEEK_SET: usize = 1;
 const SEEK_CUR: usize = 2;
 const SEEK_END: usize = 4;
 
-const MAP_ANON: usize = 1;
+const MAP_ANON: usize =    1;
+const MAP_PRIVATE: usize = 2;
+const MAP_FIXED: usize =   4;
 
 // Stack locations
 pub const STACK_START: usize = 0x2_0000_0000;
 pub const STACK_END: usize = 0x3_0000_0000;
 
+// Auxiliary vector entries, must be kept in sync with elf.h
+pub const AT_NULL: usize =   0;
+pub const AT_PHDR: usize =   3;
+pub const AT_PHENT: usize =  4;
+pub const AT_PHNUM: usize =  5;
+pub const AT_PAGESZ: usize = 6;
+pub const AT_ENTRY: usize =  9;
+
+// Return address given to signal handlers installed without a restorer, it
+// is never mapped so returning to it faults into sigreturn
+pub const SIGNAL_RETURN_ADDRESS: usize = 0x3_FFFF_F000;
+
+// Location of the initial thread local storage block
+pub const TLS_START: usize = 0x1_8000_0000;
+
+// Address code passed to `Process::from_code` is loaded at
+pub const CODE_START: usize = 0x1_0000;
+
+// Highest address the program break can reach
+pub const HEAP_END: usize = TLS_START;
+
 /// Reasons for a process to be waiting
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum WaitMode
 {
-    // Pointer to return code
-    ForChild,
+    ForChildEvent(super::wait::WaitRequest),
     ForSignal,
-    ForIO((usize, usize, *mut u8))
+    ForIO((usize, usize, usize)),
+    ForPoll(super::poll::PollRequest)
 }
 
 /// Process State Enumeration
@@ -77,57 +117,71 @@ pub enum ProcessState
 pub struct Process
 {
     pub frame: *mut TrapFrame,
-    pub backup_frame: *mut TrapFrame,
     pub stack: *mut u8,
     pub program_counter: usize,
-    pub backup_program_counter: usize,
     pub pid: PID,
     pub root: *mut PageTable,
     pub state: ProcessState,
     pub data: ProcessData,
     pub fs_interface: Option<&'static mut fs::vfs::FilesystemInterface>,
-    pub signals: [Option<POSIXSignal>; 4],
+    pub pending_signals: BTreeMap<SignalType, POSIXSignal>,
     pub exit_code: u32,
+    pub exit_signal: Option<SignalType>,
+    pub job_event: Option<JobEvent>,
+    pub run_link: RunQueueLink,
 } 
 
 impl Process
 {
-    /// Create a new process from a function pointer
-    pub fn from_fn_ptr(f: fn()) -> Self
+    /// Create a new process which runs the given position independent code,
+    /// the code is copied into pages of its own so none of the kernel is
+    /// mapped into the process
+    pub fn from_code(code: &[u8]) -> Self
     {
         let stack_size = 2;
-        let entry_point = f as usize;
+        let code_pages = (code.len() + mem::PAGE_SIZE - 1) / mem::PAGE_SIZE;
+
+        let page_table_ptr = mem::kpzalloc(1, "Code Page Table").unwrap() as *mut PageTable;
+        let page_table = unsafe {page_table_ptr.as_mut()}.unwrap();
 
-        let page_table_ptr = mem::kpzalloc(1, "Fn Ptr Page Table").unwrap() as *mut PageTable;
+        // Link in the kernel's half of the address space
+        page_table.share_kernel_space();
 
-        // Initialize the stack
-        let stack = mem::kpzalloc(stack_size, "Fn Ptr Stack").unwrap();
+        // Copy the code
+        let code_ptr = mem::kpzalloc(code_pages, "Code Pages").unwrap() as *mut u8;
 
-        let page_table = unsafe {page_table_ptr.as_mut()}.unwrap();
+        for (i, byte) in code.iter().enumerate()
+        {
+            unsafe { code_ptr.add(i).write(*byte) }
+        }
 
-        // Map the Kernel
-        page_table.identity_map(mem::lds::text_start(), mem::lds::text_end(), PageTableEntryFlags::readable() | PageTableEntryFlags::executable() | PageTableEntryFlags::user());
-        page_table.identity_map(mem::lds::rodata_start(), mem::lds::rodata_end(), PageTableEntryFlags::readable() | PageTableEntryFlags::executable() | PageTableEntryFlags::user());
+        for i in 0..code_pages
+        {
+            page_table.map(CODE_START + i * mem::PAGE_SIZE, code_ptr as usize + i * mem::PAGE_SIZE, PageTableEntryFlags::readable() | PageTableEntryFlags::executable() | PageTableEntryFlags::user(), 0);
+        }
 
         // Map the stack
-        page_table.identity_map(stack, stack + (stack_size - 1) * mem::PAGE_SIZE, PageTableEntryFlags::readable() | PageTableEntryFlags::writable() | PageTableEntryFlags::user());
+        let stack = mem::kpzalloc(stack_size, "Code Stack").unwrap();
+
+        for i in 0..stack_size
+        {
+            page_table.map(STACK_END - (stack_size - i) * mem::PAGE_SIZE, stack + i * mem::PAGE_SIZE, PageTableEntryFlags::readable() | PageTableEntryFlags::writable() | PageTableEntryFlags::user(), 0);
+        }
 
-        let text = mem::lds::text_end() - mem::lds::text_start();
-        let data = mem::lds::rodata_end() - mem::lds::rodata_start();
+        let mem_stats = MemoryStats::new(0, 0, code_pages, stack_size);
 
-        let mem_stats = MemoryStats::new(0, 0, text / mem::PAGE_SIZE, data / mem::PAGE_SIZE + stack_size);
+        let mut proc = Self::from_components(CODE_START, page_table_ptr, stack_size, STACK_END - stack_size * mem::PAGE_SIZE, mem_stats);
+        proc.data.mem.push((code_ptr, code_pages));
 
-        Self::from_components(entry_point, page_table_ptr, stack_size, stack, mem_stats)
+        proc
     }
 
     /// Create a new process from components
     pub fn from_components(entry_point: usize, page_table: *mut PageTable, stack_size: usize, stack_ptr: usize, mem_stats: MemoryStats) -> Self
     {
         let frame = mem::kpalloc(1, "Trap Frame").unwrap() as *mut TrapFrame;
-        let backup_frame = mem::kpalloc(1, "Backup Trap Frame").unwrap() as *mut TrapFrame;
 
         unsafe { frame.write(TrapFrame::new(4)) }
-        unsafe { backup_frame.write(TrapFrame::new(4)) }
 
         let pid = next_pid();
 
@@ -136,17 +190,18 @@ impl Process
             Process
             {
                 frame,
-                backup_frame,
                 stack: stack_ptr as *mut u8,
                 program_counter: entry_point,
-                backup_program_counter: 0,
                 pid: pid,
                 root: page_table,
                 state: ProcessState::Running,
                 data: unsafe { ProcessData::new(stack_size, mem_stats, pid) },
                 fs_interface: None,
-                signals: [None, None, None, None],
-                exit_code: 0
+                pending_signals: BTreeMap::new(),
+                exit_code: 0,
+                exit_signal: None,
+                job_event: None,
+                run_link: RunQueueLink::new()
             };
 
         // Update the stack pointer
@@ -155,8 +210,9 @@ impl Process
         temp_result
     }
     
-    /// Expand the stack downwards
-    pub fn expand_stack(&mut self, address: usize)
+    /// Expand the stack downwards, returns false if the stack could not be
+    /// expanded because the process is out of memory
+    pub fn expand_stack(&mut self, address: usize) -> bool
     {
         if address < STACK_START || address >= STACK_END
         {
@@ -167,6 +223,11 @@ impl Process
         
         while address < self.stack as usize
         {
+            if !super::rescg::can_charge_memory(self, 1)
+            {
+                return false;
+            }
+
             let new_page = mem::kpzalloc(1, "Expanded Stack").unwrap();
 
             unsafe
@@ -176,9 +237,12 @@ impl Process
                 table.map(self.stack as usize - mem::PAGE_SIZE, new_page, PageTableEntryFlags::readable() | PageTableEntryFlags::writable() | PageTableEntryFlags::user(), 0);
                 self.stack = (self.stack as usize - mem::PAGE_SIZE) as *mut u8;
 
+                self.data.stack_size += 1;
                 self.data.mem_stats.data += 1;
             }
         }
+
+        true
     }
 
     /// Set the environment arguments
@@ -203,53 +267,80 @@ impl Process
         unsafe { self.frame.as_mut().unwrap() }.regs[12] = ptr;
     }
 
-    /// Set the command line and environment arguments
+    /// Set the command line and environment arguments, laid out on the stack
+    /// as the RISC-V ABI expects: argc, the argv and envp arrays each ended
+    /// by a null pointer, then the auxiliary vector ended by AT_NULL. For
+    /// programs which read them from registers, a0, a1 and a2 also hold argc,
+    /// argv and envp.
     pub fn set_arguments(&mut self, args: &[String], envp: &[String])
     {
-        // Set argc
-        unsafe { self.frame.as_mut().unwrap() }.regs[10] = args.len();
+        // Start again from the top of the stack, so the arguments set by
+        // execve replace those set by the loader
+        unsafe { self.frame.as_mut().unwrap() }.regs[2] = STACK_END;
 
-        let mut args_to_store = Vec::new();
-        
         let mut arg_addrs = Vec::with_capacity(args.len());
         let mut envp_addrs = Vec::with_capacity(envp.len());
 
-        // Write the arguments
+        // Write the strings, the stack grows down, so the terminator is
+        // pushed before the string
         for s in args
         {
-            args_to_store.push(s.clone());
+            self.push_buffer(&[0]);
             arg_addrs.push(self.push_buffer(s.as_bytes()));
         }
 
-        // Write the argument array
-        let mut ptr = self.push(0usize);
-        for v in arg_addrs.iter().rev()
+        for s in envp
         {
-            ptr = self.push(*v);
+            self.push_buffer(&[0]);
+            envp_addrs.push(self.push_buffer(s.as_bytes()));
         }
 
-        // Set argv
-        unsafe { self.frame.as_mut().unwrap() }.regs[11] = ptr;
+        // The stack pointer must be 16 byte aligned on entry, so pad the
+        // stack if the number of words pushed below would leave it misaligned
+        unsafe { self.frame.as_mut().unwrap() }.regs[2] &= !0xF;
 
-        // Write the environment variables
-        for s in envp
+        let aux_vector = self.data.aux_vector.clone();
+        let words = 1 + (args.len() + 1) + (envp.len() + 1) + 2 * (aux_vector.len() + 1);
+
+        if words % 2 != 0
         {
-            envp_addrs.push(self.push_buffer(s.as_bytes()));
-            self.push_buffer(&[0]);
+            self.push(0usize);
         }
 
-        // Write envp
-        let mut ptr = self.push(0usize);
+        // Write the auxiliary vector
+        self.push(0usize);
+        self.push(AT_NULL);
+
+        for (key, value) in aux_vector.iter().rev()
+        {
+            self.push(*value);
+            self.push(*key);
+        }
+
+        // Write the environment array
+        let mut envp_ptr = self.push(0usize);
         for v in envp_addrs.iter().rev()
         {
-            ptr = self.push(*v);
+            envp_ptr = self.push(*v);
         }
 
-        // Set envp
-        unsafe { self.frame.as_mut().unwrap() }.regs[12] = ptr;
+        // Write the argument array
+        let mut argv_ptr = self.push(0usize);
+        for v in arg_addrs.iter().rev()
+        {
+            argv_ptr = self.push(*v);
+        }
+
+        self.push(args.len());
+
+        let frame = unsafe { self.frame.as_mut().unwrap() };
+
+        frame.regs[10] = args.len();
+        frame.regs[11] = argv_ptr;
+        frame.regs[12] = envp_ptr;
 
         // Store the arguments in the process data
-        self.data.fill_command_line_args(args_to_store);
+        self.data.fill_command_line_args(args.to_vec());
     }
 
     /// Push a buffer
@@ -290,9 +381,15 @@ impl Process
         unsafe { self.frame.as_mut().unwrap() }.regs[2]
     }
 
-    /// Map memory based on its page table
+    /// Map memory based on its page table, only addresses in the process's
+    /// half of the address space are translated
     pub fn map_mem(&self, addr: usize) -> Result<usize, TranslationError>
     {
+        if addr >= mem::USER_SPACE_END
+        {
+            return Err(TranslationError::InvalidPage(2));
+        }
+
         unsafe { (*self.root).virt_to_phys(addr) }
     }
 
@@ -309,6 +406,40 @@ impl Process
 
         self.state = ProcessState::Zombie;
         self.exit_code = value as u32;
+
+        // Clear the thread id registered by set_tid_address so the thread
+        // library can tell that the thread has exited
+        if let Some(addr) = self.data.clear_child_tid.take()
+        {
+            if let Ok(ptr) = self.map_mem(addr)
+            {
+                unsafe { (ptr as *mut u32).write_volatile(0) };
+            }
+        }
+
+        // A program which took over the display gives it back to the console
+        // when it exits, however it exits
+        drivers::gpu::release_display(self.pid);
+
+        // The memory of the process is freed once it is reaped, so any process
+        // running in its address space must exit along with it
+        if self.data.memory_owner.is_none()
+        {
+            super::scheduler::kill_memory_sharers(self.pid, value);
+        }
+
+        // Release the descriptors now rather than when the process is reaped,
+        // otherwise the read end of a pipe would not see EOF until the parent
+        // waits on the writer
+        self.context_cleanup();
+    }
+
+    /// Kill a process because of a signal, the exit code is 128 plus the
+    /// signal number as reported by shells
+    pub fn kill_by_signal(&mut self, signal: SignalType)
+    {
+        self.kill(128 + signal as usize);
+        self.exit_signal = Some(signal);
     }
 
     /// Initialize the file system
@@ -326,44 +457,73 @@ impl Process
         }
     }
 
-    /// Write descriptor into the next open file descriptor
-    pub fn add_descriptor(&mut self, fd: Box<dyn FileDescriptor>) -> usize
+    /// Get the number descriptors must stay below, set by the `RLIMIT_NOFILE`
+    /// limit of the process
+    pub fn descriptor_limit(&self) -> usize
     {
-        let mut i = 0;
-
-        while self.data.descriptors.contains_key(&i)
-        {
-            i += 1;
-        }
+        self.data.limits.soft(super::rlimit::RLIMIT_NOFILE).min(usize::MAX as u64) as usize
+    }
 
-        self.data.descriptors.insert(i, alloc::sync::Arc::new(core::cell::RefCell::new(fd)));
+    /// Get the lowest unused file descriptor number which is at least `min`,
+    /// returning `EMFILE` if every descriptor below the limit is in use
+    fn next_free_descriptor(&self, min: usize) -> Result<usize, usize>
+    {
+        (min..self.descriptor_limit()).find(|i| !self.data.descriptors.contains_key(i)).ok_or(errno::EMFILE)
+    }
 
-        i
+    /// Write descriptor into the next open file descriptor
+    pub fn add_descriptor(&mut self, fd: Box<dyn FileDescriptor>) -> Result<usize, usize>
+    {
+        self.add_wrapped_descriptor(alloc::sync::Arc::new(core::cell::RefCell::new(fd)))
     }
 
     // Add an already wrapped descriptor
-    pub fn add_wrapped_descriptor(&mut self, fd: alloc::sync::Arc<core::cell::RefCell<Box<dyn FileDescriptor>>>) -> usize
+    pub fn add_wrapped_descriptor(&mut self, fd: alloc::sync::Arc<core::cell::RefCell<Box<dyn FileDescriptor>>>) -> Result<usize, usize>
     {
-        let mut i = 0;
-
-        while self.data.descriptors.contains_key(&i)
-        {
-            i += 1;
-        }
+        let i = self.next_free_descriptor(0)?;
 
         self.data.descriptors.insert(i, fd);
 
-        i
+        Ok(i)
+    }
+
+    /// Set or clear the close on exec flag of a file descriptor
+    pub fn set_close_on_exec(&mut self, fd: usize, close_on_exec: bool)
+    {
+        self.data.descriptors.set_flags(fd, if close_on_exec { FD_CLOEXEC } else { 0 });
+    }
+
+    /// Get a copy of the descriptor table without the descriptors marked
+    /// close on exec, for the process image replacing this one
+    pub fn exec_descriptors(&self) -> super::descriptor::DescriptorTable
+    {
+        self.data.descriptors.without_close_on_exec()
+    }
+
+    /// Get the permission bits a file created with the given mode will have
+    /// once the umask of the process has been applied
+    pub fn creation_mode(&self, mode: usize) -> u16
+    {
+        mode as u16 & 0o7777 & !self.data.umask
     }
 
-    /// Open a file by path
-    pub fn open(&mut self, path: PathBuffer, mode: usize) -> Result<usize, fs::structures::FilesystemError>
+    /// Open a file by path, a file created by the call is given the
+    /// permission bits in `create_mode`
+    pub fn open(&mut self, path: PathBuffer, mode: usize, create_mode: usize) -> Result<usize, fs::structures::FilesystemError>
     {
         self.ensure_fs();
 
+        // Check for a free descriptor before a file is created
+        if let Err(e) = self.next_free_descriptor(0)
+        {
+            return Ok(e);
+        }
+
+        let create_mode = self.creation_mode(create_mode);
+
         let vfs = self.fs_interface.as_mut().unwrap();
         let inode = 
-            if let Ok(inode_result) = vfs.path_to_inode(&path)
+            if let Ok(inode_result) = fs::namespace::path_to_inode(&self.data.mount_namespace, self.data.root, vfs, &path)
             {
                 if (mode & O_EXCL) > 0
                 {
@@ -381,13 +541,23 @@ impl Process
 
                 let (path, name) = path.split_last();
 
-                let dest_inode = vfs.path_to_inode(&path)?;
+                let dest_inode = fs::namespace::path_to_inode(&self.data.mount_namespace, self.data.root, vfs, &path)?;
 
-                vfs.create_file(dest_inode, name.to_string())?
+                vfs.create_file(dest_inode, name.to_string(), create_mode)?
             };
 
-        let fd = vfs.open_fd(inode, mode)?;
-        Ok(self.add_descriptor(fd))
+        let mut fd = vfs.open_fd(inode, mode)?;
+        fd.set_status_flags(mode & O_STATUS_FLAGS);
+
+        let fd = match self.add_descriptor(fd)
+        {
+            Ok(fd) => fd,
+            Err(e) => return Ok(e)
+        };
+
+        self.set_close_on_exec(fd, mode & O_CLOEXEC > 0);
+
+        Ok(fd)
     }
 
     /// Read from a file descriptor
@@ -395,7 +565,7 @@ impl Process
     {
         self.ensure_fs();
 
-        if let Some(fd) = self.data.descriptors.get_mut(&fd)
+        if let Some(fd) = self.data.descriptors.get(&fd)
         {
             fd.borrow_mut().read(self.fs_interface.as_mut().unwrap(), buffer, count)
         }
@@ -405,12 +575,33 @@ impl Process
         }
     }
 
+    /// Read from a file descriptor into a userspace buffer, which is
+    /// translated a page at a time
+    pub fn read_user(&mut self, fd: usize, buffer: usize, count: usize) -> usize
+    {
+        if let Some(set) = self.signal_descriptor_mask(fd)
+        {
+            return self.read_signals(set, buffer, count);
+        }
+
+        match syscalls::utils::user_buffer_segments(self, buffer, count, true)
+        {
+            Ok(segments) => syscalls::utils::transfer_segments(&segments, |ptr, length| self.read(fd, ptr, length)),
+            Err(e) => e
+        }
+    }
+
     /// Check for data available on a file descriptor
     pub fn check_available(&mut self, fd: usize) -> bool
     {
         self.ensure_fs();
 
-        if let Some(fd) = self.data.descriptors.get_mut(&fd)
+        if let Some(set) = self.signal_descriptor_mask(fd)
+        {
+            return self.has_pending_signal_in(set);
+        }
+
+        if let Some(fd) = self.data.descriptors.get(&fd)
         {
             fd.borrow_mut().check_available()
         }
@@ -425,7 +616,7 @@ impl Process
     {
         self.ensure_fs();
 
-        if let Some(fd) = self.data.descriptors.get_mut(&fd)
+        if let Some(fd) = self.data.descriptors.get(&fd)
         {
             fd.borrow_mut().write(self.fs_interface.as_mut().unwrap(), buffer, count)
         }
@@ -435,12 +626,108 @@ impl Process
         }
     }
 
+    /// Write to a file descriptor from a userspace buffer, which is translated
+    /// a page at a time. Buffers of up to `PIPE_BUF` bytes which cross a page
+    /// boundary are gathered first, so they reach the descriptor in one write
+    pub fn write_user(&mut self, fd: usize, buffer: usize, count: usize) -> usize
+    {
+        let segments = match syscalls::utils::user_buffer_segments(self, buffer, count, false)
+        {
+            Ok(segments) => segments,
+            Err(e) => return e
+        };
+
+        if segments.len() > 1 && count <= super::pipe::PIPE_BUF
+        {
+            let mut data = vec![0; count];
+
+            if let Err(e) = syscalls::utils::copy_from_user(self, buffer, &mut data)
+            {
+                return e;
+            }
+
+            return self.write(fd, data.as_mut_ptr(), count);
+        }
+
+        syscalls::utils::transfer_segments(&segments, |ptr, length| self.write(fd, ptr, length))
+    }
+
+    /// Read from a file descriptor at an offset, leaving its position alone
+    pub fn read_at(&mut self, fd: usize, buffer: *mut u8, count: usize, offset: usize) -> usize
+    {
+        self.ensure_fs();
+
+        if let Some(fd) = self.data.descriptors.get(&fd)
+        {
+            fd.borrow_mut().read_at(self.fs_interface.as_mut().unwrap(), buffer, count, offset)
+        }
+        else
+        {
+            errno::EBADF
+        }
+    }
+
+    /// Write to a file descriptor at an offset, leaving its position alone
+    pub fn write_at(&mut self, fd: usize, buffer: *mut u8, count: usize, offset: usize) -> usize
+    {
+        self.ensure_fs();
+
+        if let Some(fd) = self.data.descriptors.get(&fd)
+        {
+            fd.borrow_mut().write_at(self.fs_interface.as_mut().unwrap(), buffer, count, offset)
+        }
+        else
+        {
+            errno::EBADF
+        }
+    }
+
+    /// Change the length of the file behind a file descriptor
+    pub fn truncate(&mut self, fd: usize, length: usize) -> Result<(), usize>
+    {
+        self.ensure_fs();
+
+        if let Some(fd) = self.data.descriptors.get(&fd)
+        {
+            fd.borrow_mut().truncate(self.fs_interface.as_mut().unwrap(), length)
+        }
+        else
+        {
+            Err(errno::EBADF)
+        }
+    }
+
+    /// Check if the process may perform privileged operations, such as
+    /// rebooting the system, which requires an effective user id of root
+    pub fn is_privileged(&self) -> bool
+    {
+        self.data.credentials.is_privileged()
+    }
+
+    /// Check if a file descriptor refers to a terminal whose foreground
+    /// process group the process belongs to
+    pub fn is_foreground_terminal(&self, fd: usize) -> bool
+    {
+        self.data.descriptors.get(&fd).and_then(|desc| desc.borrow().get_terminal_foreground()) == Some(self.data.process_group_id)
+    }
+
+    /// Check if any terminal the process is in the foreground of has input
+    /// waiting
+    pub fn has_terminal_input(&self) -> bool
+    {
+        self.data.descriptors.values().iter().any(|desc|
+        {
+            let desc = desc.borrow();
+            desc.get_terminal_foreground() == Some(self.data.process_group_id) && desc.check_available()
+        })
+    }
+
     /// Close a file descriptor
     pub fn close(&mut self, fd_number: usize) -> usize
     {
         self.ensure_fs();
 
-        let v = if let Some(fd) = self.data.descriptors.get_mut(&fd_number)
+        let v = if let Some(fd) = self.data.descriptors.get(&fd_number)
         {
             fd.borrow_mut().close(self.fs_interface.as_mut().unwrap());
             0
@@ -459,77 +746,91 @@ impl Process
     }
 
     /// Create a new pipe
-    pub fn pipe(&mut self) -> (usize, usize)
+    pub fn pipe(&mut self) -> Result<(usize, usize), usize>
     {
+        // Both ends must fit below the limit before the pipe is created
+        let first = self.next_free_descriptor(0)?;
+        self.next_free_descriptor(first + 1)?;
+
         let (read, write) = super::pipe::new_pipe();
 
-        let read = self.add_wrapped_descriptor(read);
-        let write = self.add_wrapped_descriptor(write);
+        let read = self.add_wrapped_descriptor(read)?;
+        let write = self.add_wrapped_descriptor(write)?;
 
-        (read, write)
+        Ok((read, write))
     }
 
-    /// Duplicate a file descriptor
-    pub fn dup(&mut self, old: usize, new: Option<usize>) -> usize
+    /// Duplicate a file descriptor, both descriptors will refer to the same
+    /// open file, and so will share an offset. A target descriptor at or above
+    /// the limit is refused with `EBADF`.
+    pub fn dup(&mut self, old: usize, new: Option<usize>) -> Result<usize, usize>
     {
         self.ensure_fs();
         
-        let fd = if let Some(fd) = self.data.descriptors.get(&old)
+        let fd = self.data.descriptors.get(&old).ok_or(errno::EBADF)?;
+
+        // Duplicating a descriptor onto itself does nothing
+        if new == Some(old)
         {
-            fd.clone()
+            return Ok(old);
         }
-        else
-        {
-            return usize::MAX;
-        };
 
         let out = if let Some(new) = new
         {
+            if new >= self.descriptor_limit()
+            {
+                return Err(errno::EBADF);
+            }
+
             new
         }
         else
         {
-            let mut i = 0;
-
-            while self.data.descriptors.contains_key(&i)
-            {
-                i += 1;
-            }
-
-            i
+            self.next_free_descriptor(0)?
         };
 
-        if let Some(v) = self.data.descriptors.get_mut(&out)
+        if let Some(v) = self.data.descriptors.get(&out)
         {
             v.borrow_mut().close(self.fs_interface.as_mut().unwrap());
         }
 
+        // The new descriptor never inherits the close on exec flag
         self.data.descriptors.insert(out, fd);
 
-        if new.is_some()
+        Ok(out)
+    }
+
+    /// Duplicate a file descriptor onto the lowest unused descriptor which is
+    /// at least `min`, which must be below the limit
+    pub fn dup_above(&mut self, old: usize, min: usize) -> Result<usize, usize>
+    {
+        if !self.data.descriptors.contains_key(&old)
         {
-            0
+            return Err(errno::EBADF);
         }
-        else
+
+        if min >= self.descriptor_limit()
         {
-            out
+            return Err(errno::EINVAL);
         }
+
+        let out = self.next_free_descriptor(min)?;
+
+        self.dup(old, Some(out))
     }
 
     /// Seek to a location in the file descriptor
     pub fn seek(&mut self, fd: usize, offset: usize, mode: usize) -> usize
     {
-        use super::descriptor::SeekMode;
-
         let enum_mode = match mode
         {
             SEEK_CUR => SeekMode::SeekCurrent,
             SEEK_END => SeekMode::SeekEnd,
             SEEK_SET => SeekMode::SeekSet,
-            _ => { return offset.wrapping_sub(1); }
+            _ => { return errno::EINVAL; }
         };
 
-        if let Some(fd) = self.data.descriptors.get_mut(&fd)
+        if let Some(fd) = self.data.descriptors.get(&fd)
         {
             fd.borrow_mut().seek(offset, enum_mode)
         }
@@ -544,7 +845,7 @@ impl Process
     {
         self.ensure_fs();
 
-        if let Some(fd) = self.data.descriptors.get_mut(&fd)
+        if let Some(fd) = self.data.descriptors.get(&fd)
         {
             if let Some(inode) = fd.borrow_mut().get_inode()
             {
@@ -576,31 +877,117 @@ impl Process
         pt.display_mapping();
     }
 
-    /// Get a forked version of the current process
+    /// Get a forked version of the current process, the child gets a copy of
+    /// the address space this process runs in
     pub fn forked(&mut self) -> Self
     {
-        let stack_size = self.data.stack_size;
+        // A process sharing another's address space copies the owner's memory
+        let space = self.address_space();
+
+        let mut temp = Self::from_components(0, unsafe { space.root.as_mut().unwrap().duplicate_map() }, space.data.stack_size, space.stack as usize, space.data.mem_stats);
+
+        // The pages of device and shared memory mappings were copied along
+        // with the rest of the memory, so point the child back at the pages
+        // it shares
+        temp.data.next_heap = space.data.next_heap;
+        temp.data.heap_start = space.data.heap_start;
+        temp.data.program_break = space.data.program_break;
+        temp.data.mappings = space.data.mappings.clone();
+
+        for (addr, mapping) in &temp.data.mappings
+        {
+            if mapping.owns_pages() || mapping.perm.is_none()
+            {
+                continue;
+            }
+
+            let table = unsafe { temp.root.as_mut().unwrap() };
+
+            for i in 0..mapping.pages
+            {
+                let vaddr = addr + i * mem::PAGE_SIZE;
+
+                if let Ok(copy) = table.virt_to_phys(vaddr)
+                {
+                    let flags = table.page_flags(vaddr).unwrap();
+                    mem::kpzfree(copy, 1).unwrap();
+
+                    table.map(vaddr, mapping.shared_page(i).unwrap(), flags, 0);
+                }
+            }
+        }
+
+        self.inherit(&mut temp);
+
+        temp
+    }
+
+    /// Get a copy of the current process which runs in the same address
+    /// space, as clone does with `CLONE_VM`. The memory stays with the owner
+    /// of the address space, so the child needs a stack of its own.
+    pub fn forked_sharing_memory(&mut self) -> Self
+    {
+        let owner = self.data.memory_owner.unwrap_or(self.pid);
 
-        let mut temp = Self::from_components(self.program_counter + 4, unsafe { self.root.as_mut().unwrap().duplicate_map() }, stack_size, self.stack as usize, self.data.mem_stats);
+        let mut temp = Self::from_components(0, self.root, 0, self.stack as usize, MemoryStats::new(0, 0, 0, 0));
+        temp.data.memory_owner = Some(owner);
 
-        let new_frame = mem::kpalloc(1, "Trap Frame").unwrap() as *mut TrapFrame;
+        self.inherit(&mut temp);
+
+        temp
+    }
 
-        unsafe { new_frame.write(self.frame.read()) }
+    /// Give a newly created child the registers, descriptors and the rest of
+    /// the context it inherits from this process
+    fn inherit(&mut self, temp: &mut Process)
+    {
+        temp.program_counter = self.program_counter + 4;
 
-        temp.frame = new_frame;
+        unsafe { temp.frame.write(self.frame.read()) }
         unsafe { temp.frame.as_mut().unwrap() }.regs[10] = 0;
 
-        temp.data.descriptors = self.data.descriptors.clone();
-        
+        temp.data.descriptors = self.data.descriptors.duplicate();
+
         temp.data.cwd = self.data.cwd.clone();
+        temp.data.root = self.data.root;
+        temp.data.umask = self.data.umask;
 
         temp.data.cmdline_args = self.data.cmdline_args.clone();
+        temp.data.name = self.data.name.clone();
+        temp.data.dumpable = self.data.dumpable;
+        temp.data.no_new_privs = self.data.no_new_privs;
 
         temp.data.process_group_id = self.data.process_group_id;
+        temp.data.session_id = self.data.session_id;
+
+        temp.data.mount_namespace = self.data.mount_namespace.clone();
+
+        temp.data.resource_group = self.data.resource_group;
+        temp.data.limits = self.data.limits;
+        temp.data.credentials = self.data.credentials;
+
+        temp.data.priority = self.data.priority;
+
+        temp.data.signal_map = self.data.signal_map.clone();
+        temp.data.signal_mask = self.data.signal_mask;
 
         self.register_child(temp.pid);
+        temp.data.set_parent(self.pid);
+    }
 
-        temp
+    /// Get the process which owns the address space this process runs in,
+    /// memory is mapped, unmapped and faulted in through the owner
+    pub fn address_space(&mut self) -> &mut Process
+    {
+        if let Some(owner) = self.data.memory_owner
+        {
+            if let Some(owner) = super::scheduler::get_process_manager().and_then(|manager| manager.get_process_by_pid_mut(owner))
+            {
+                return owner;
+            }
+        }
+
+        self
     }
 
     /// Check if the state has changed for the wait syscall
@@ -642,171 +1029,909 @@ impl Process
         &self.data.children
     }
 
-    /// Map a region of memory with the given permissions
-    pub fn map(&mut self, length: usize, perm: mem::mmu::PageTableEntryFlags, flags: usize, fd: usize, offset: usize) -> usize
+    /// Map memory into the process, returning the address of the mapping. A
+    /// permission of `None` reserves the region without mapping any pages
+    pub fn map(&mut self, addr: usize, length: usize, perm: Option<PageTableEntryFlags>, flags: usize, fd: usize, offset: usize) -> Result<usize, usize>
     {
-        if offset != 0
+        if length == 0 || offset % mem::PAGE_SIZE != 0
         {
-            todo!()
+            return Err(errno::EINVAL);
         }
 
-        // Allocate the memory
-        let mut ptr_op = None;
+        let pages = (length + mem::PAGE_SIZE - 1) / mem::PAGE_SIZE;
 
-        if flags & MAP_ANON == 0 && (flags as i64) >= 0
+        // Find what will back the mapping
+        let backing = if flags & MAP_ANON > 0
+        {
+            MappingBacking::Anonymous
+        }
+        else
         {
-            if let Some(fd_obj) = self.data.descriptors.get_mut(&fd)
+            let descriptor = self.data.descriptors.get(&fd).ok_or(errno::EBADF)?;
+            let buffer = descriptor.borrow().get_buffer();
+            let shared_memory = descriptor.borrow().get_shared_memory();
+
+            if let Some(buffer) = buffer
             {
-                if let Some(b) = fd_obj.borrow().get_buffer()
-                {
-                    ptr_op = Some(b);
-                }
+                MappingBacking::Device { physical: buffer as usize + offset }
             }
-        }
+            else if let Some(object) = shared_memory.clone().filter(|_| flags & MAP_PRIVATE == 0)
+            {
+                MappingBacking::SharedMemory { object, offset }
+            }
+            // Private mappings of a shared memory object are filled from it
+            // as they would be from a file
+            else if shared_memory.is_some() || descriptor.borrow_mut().get_inode().is_some()
+            {
+                MappingBacking::File { descriptor, offset, shared: flags & MAP_PRIVATE == 0 }
+            }
+            else
+            {
+                return Err(errno::ENODEV);
+            }
+        };
 
-        let ptr;
+        // Pick the address, a fixed mapping replaces anything already there,
+        // otherwise the address is only a hint
+        let vaddr = if flags & MAP_FIXED > 0
+        {
+            if addr % mem::PAGE_SIZE != 0 || addr < MMAP_START
+            {
+                return Err(errno::EINVAL);
+            }
 
-        if let Some(p) = ptr_op
+            addr
+        }
+        else if addr % mem::PAGE_SIZE == 0 && addr >= MMAP_START && self.mapping_range_free(addr, pages)
         {
-            ptr = p as usize;
+            addr
         }
         else
         {
-            ptr = mem::kpzalloc(length, "mmap").unwrap();
-        }
-
-        self.data.mem_stats.resident += length;
+            self.data.next_heap
+        };
 
-        let user_addr = self.data.next_heap;
+        let mapping = MemoryMapping::new(pages, perm, backing);
 
-        self.data.mem.push((ptr as *mut u8, length));
+        // Everything which can fail is done before a fixed mapping replaces
+        // what was there, so a failed call leaves the old mapping in place
+        let memory = if perm.is_some() { self.allocate_mapping(&mapping)? } else { None };
 
-        // Map the memory
-        for i in 0..length
+        if flags & MAP_FIXED > 0
         {
-            unsafe { self.root.as_mut().unwrap() }.map(self.data.next_heap, ptr + i * mem::PAGE_SIZE, perm, 0);
-            self.data.next_heap += mem::PAGE_SIZE;
+            self.unmap(vaddr, pages * mem::PAGE_SIZE)?;
         }
 
-        // If need be, fill the memory
-        if flags & MAP_ANON == 0 && (flags as i64) >= 0
+        if let Some(perm) = perm
         {
-            if let Some(fd_obj) = self.data.descriptors.get_mut(&fd)
-            {
-                if ptr_op.is_none()
-                {
-                    fd_obj.borrow_mut().seek(offset, process::descriptor::SeekMode::SeekSet);
-                    fd_obj.borrow_mut().read(self.fs_interface.as_mut().unwrap(), ptr as *mut u8, 4096 * length);
-                }
-                
-                self.data.mmapped_files.insert(ptr as *mut u8, fd);
-            }
-            else
+            self.install_mapping(vaddr, &mapping, memory, perm);
+        }
+
+        self.data.mappings.insert(vaddr, mapping);
+        self.data.next_heap = self.data.next_heap.max(vaddr + pages * mem::PAGE_SIZE);
+
+        Ok(vaddr)
+    }
+
+    /// Allocate and map the pages of a mapping at the given address with the
+    /// given permissions, filling them from the file behind it if there is one
+    fn populate_mapping(&mut self, vaddr: usize, mapping: &MemoryMapping, perm: PageTableEntryFlags) -> Result<(), usize>
+    {
+        let memory = self.allocate_mapping(mapping)?;
+        self.install_mapping(vaddr, mapping, memory, perm);
+
+        Ok(())
+    }
+
+    /// Allocate the pages of a mapping, filling them from the file behind it
+    /// if there is one, returning the contiguous memory backing the mapping.
+    /// The pages of a shared memory object are not contiguous, so `None` is
+    /// given for them and they are found again when mapped.
+    fn allocate_mapping(&mut self, mapping: &MemoryMapping) -> Result<Option<usize>, usize>
+    {
+        let pages = mapping.pages;
+
+        if let MappingBacking::SharedMemory { .. } = &mapping.backing
+        {
+            // Every page must exist before any is mapped
+            if (0..pages).any(|i| mapping.shared_page(i).is_none())
+            {
+                return Err(errno::ENOMEM);
+            }
+
+            return Ok(None);
+        }
+
+        // Allocate the memory
+        let ptr = match &mapping.backing
+        {
+            MappingBacking::Device { physical } => *physical,
+            _ =>
+            {
+                if !super::rescg::can_charge_memory(self, pages)
+                {
+                    return Err(errno::ENOMEM);
+                }
+
+                mem::kpzalloc(pages, "mmap").map_err(|_| errno::ENOMEM)?
+            }
+        };
+
+        // If need be, fill the memory from the file, leaving the position
+        // of the descriptor where it was
+        if let MappingBacking::File { descriptor, offset, .. } = &mapping.backing
+        {
+            self.ensure_fs();
+
+            let mut desc = descriptor.borrow_mut();
+
+            let position = desc.seek(0, SeekMode::SeekCurrent);
+            desc.seek(*offset, SeekMode::SeekSet);
+            let read = desc.read(self.fs_interface.as_mut().unwrap(), ptr as *mut u8, pages * mem::PAGE_SIZE);
+            desc.seek(position, SeekMode::SeekSet);
+
+            if read > pages * mem::PAGE_SIZE
+            {
+                drop(desc);
+                mem::kpzfree(ptr, pages).unwrap();
+
+                return Err(errno::EACCES);
+            }
+        }
+
+        Ok(Some(ptr))
+    }
+
+    /// Map the pages allocated for a mapping by `allocate_mapping` at the
+    /// given address with the given permissions
+    fn install_mapping(&mut self, vaddr: usize, mapping: &MemoryMapping, memory: Option<usize>, perm: PageTableEntryFlags)
+    {
+        let pages = mapping.pages;
+
+        if let Some(ptr) = memory
+        {
+            if mapping.owns_pages()
+            {
+                self.data.mem_stats.resident += pages;
+            }
+
+            for i in 0..pages
+            {
+                unsafe { self.root.as_mut().unwrap() }.map(vaddr + i * mem::PAGE_SIZE, ptr + i * mem::PAGE_SIZE, perm, 0);
+            }
+        }
+        else
+        {
+            for i in 0..pages
+            {
+                let page = mapping.shared_page(i).unwrap();
+                unsafe { self.root.as_mut().unwrap() }.map(vaddr + i * mem::PAGE_SIZE, page, perm, 0);
+            }
+        }
+    }
+
+    /// Place the start of the heap after the end of the loaded image
+    pub fn init_heap(&mut self, image_end: usize)
+    {
+        let heap_start = (image_end + mem::PAGE_SIZE - 1) / mem::PAGE_SIZE * mem::PAGE_SIZE;
+
+        self.data.heap_start = heap_start;
+        self.data.program_break = heap_start;
+    }
+
+    /// Move the program break, growing or shrinking the heap, returning the
+    /// new break. The heap is made of whole pages, so pages are only
+    /// allocated or freed when the break crosses a page boundary
+    pub fn set_program_break(&mut self, new_break: usize) -> Result<usize, usize>
+    {
+        if self.data.heap_start == 0 || new_break < self.data.heap_start || new_break > HEAP_END
+        {
+            return Err(errno::ENOMEM);
+        }
+
+        let old_end = (self.data.program_break + mem::PAGE_SIZE - 1) / mem::PAGE_SIZE * mem::PAGE_SIZE;
+        let new_end = (new_break + mem::PAGE_SIZE - 1) / mem::PAGE_SIZE * mem::PAGE_SIZE;
+
+        if new_end > old_end
+        {
+            let pages = (new_end - old_end) / mem::PAGE_SIZE;
+
+            if !super::rescg::can_charge_memory(self, pages)
+            {
+                return Err(errno::ENOMEM);
+            }
+
+            for i in 0..pages
+            {
+                let page = if let Ok(page) = mem::kpzalloc(1, "Heap")
+                {
+                    page
+                }
+                else
+                {
+                    // Give back the pages allocated so far
+                    self.free_heap_pages(old_end, old_end + i * mem::PAGE_SIZE);
+
+                    return Err(errno::ENOMEM);
+                };
+
+                unsafe { self.root.as_mut().unwrap() }.map(old_end + i * mem::PAGE_SIZE, page, PageTableEntryFlags::readable() | PageTableEntryFlags::writable() | PageTableEntryFlags::user() | PageTableEntryFlags::accessed() | PageTableEntryFlags::dirty(), 0);
+
+                self.data.mem_stats.data += 1;
+            }
+        }
+        else if new_end < old_end
+        {
+            self.free_heap_pages(new_end, old_end);
+        }
+
+        self.data.program_break = new_break;
+
+        Ok(new_break)
+    }
+
+    /// Get the number of pages in the heap
+    pub fn heap_pages(&self) -> usize
+    {
+        ((self.data.program_break + mem::PAGE_SIZE - 1) / mem::PAGE_SIZE * mem::PAGE_SIZE - self.data.heap_start) / mem::PAGE_SIZE
+    }
+
+    /// Unmap and free the heap pages in the given range
+    fn free_heap_pages(&mut self, start: usize, end: usize)
+    {
+        for vaddr in (start..end).step_by(mem::PAGE_SIZE)
+        {
+            if let Ok(phys_addr) = unsafe { self.root.as_ref().unwrap() }.virt_to_phys(vaddr)
+            {
+                unsafe { self.root.as_mut().unwrap() }.unmap(vaddr, 0);
+                mem::kpzfree(phys_addr, 1).unwrap();
+
+                self.data.mem_stats.data -= 1;
+            }
+        }
+    }
+
+    /// Check that no mapping overlaps the given range
+    fn mapping_range_free(&self, addr: usize, pages: usize) -> bool
+    {
+        let end = addr + pages * mem::PAGE_SIZE;
+
+        !self.data.mappings.range(..end).any(|(start, mapping)| start + mapping.pages * mem::PAGE_SIZE > addr)
+    }
+
+    /// Unmap a region of memory, any mappings which only partially overlap
+    /// the region are split, and the region does not need to be mapped
+    pub fn unmap(&mut self, addr: usize, length: usize) -> Result<(), usize>
+    {
+        if addr % mem::PAGE_SIZE != 0 || length == 0
+        {
+            return Err(errno::EINVAL);
+        }
+
+        let end = addr + (length + mem::PAGE_SIZE - 1) / mem::PAGE_SIZE * mem::PAGE_SIZE;
+
+        let overlapping: Vec<usize> = self.data.mappings.range(..end)
+            .filter(|(start, mapping)| *start + mapping.pages * mem::PAGE_SIZE > addr)
+            .map(|(start, _)| *start)
+            .collect();
+
+        for start in overlapping
+        {
+            let mapping = self.data.mappings.remove(&start).unwrap();
+            let mapping_end = start + mapping.pages * mem::PAGE_SIZE;
+
+            let cut_start = start.max(addr);
+            let cut_end = mapping_end.min(end);
+
+            let first_page = (cut_start - start) / mem::PAGE_SIZE;
+            let cut_pages = (cut_end - cut_start) / mem::PAGE_SIZE;
+
+            self.release_mapping(cut_start, &mapping.slice(first_page, cut_pages));
+
+            // Keep the parts of the mapping outside of the region
+            if start < cut_start
+            {
+                self.data.mappings.insert(start, mapping.slice(0, first_page));
+            }
+
+            if cut_end < mapping_end
+            {
+                self.data.mappings.insert(cut_end, mapping.slice(first_page + cut_pages, (mapping_end - cut_end) / mem::PAGE_SIZE));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Get the start of every mapping overlapping a region
+    fn overlapping_mappings(&self, addr: usize, end: usize) -> Vec<usize>
+    {
+        self.data.mappings.range(..end)
+            .filter(|(start, mapping)| *start + mapping.pages * mem::PAGE_SIZE > addr)
+            .map(|(start, _)| *start)
+            .collect()
+    }
+
+    /// Get the pages of a region which are not covered by mappings, these
+    /// belong to the program image, the heap, the stack or the thread local
+    /// storage block, returning `ENOMEM` if any of them is not mapped
+    fn unmanaged_pages(&mut self, addr: usize, end: usize) -> Result<Vec<usize>, usize>
+    {
+        let mut pages = Vec::new();
+        let mut vaddr = addr;
+
+        while vaddr < end
+        {
+            let mapping_end = self.data.mappings.range(..=vaddr).next_back()
+                .map(|(start, mapping)| start + mapping.pages * mem::PAGE_SIZE)
+                .filter(|mapping_end| *mapping_end > vaddr);
+
+            if let Some(mapping_end) = mapping_end
+            {
+                vaddr = mapping_end;
+                continue;
+            }
+
+            if unsafe { self.root.as_mut().unwrap() }.page_flags(vaddr).is_none()
+            {
+                return Err(errno::ENOMEM);
+            }
+
+            pages.push(vaddr);
+            vaddr += mem::PAGE_SIZE;
+        }
+
+        Ok(pages)
+    }
+
+    /// Get the start of every mapping overlapping a region, returning `ENOMEM`
+    /// if any part of the region is not mapped
+    fn covering_mappings(&self, addr: usize, end: usize) -> Result<Vec<usize>, usize>
+    {
+        let overlapping = self.overlapping_mappings(addr, end);
+
+        // Any gap in the region means it is not all mapped
+        let mut covered = addr;
+
+        for start in &overlapping
+        {
+            if *start > covered
+            {
+                return Err(errno::ENOMEM);
+            }
+
+            covered = covered.max(start + self.data.mappings[start].pages * mem::PAGE_SIZE);
+        }
+
+        if covered < end
+        {
+            return Err(errno::ENOMEM);
+        }
+
+        Ok(overlapping)
+    }
+
+    /// Change the permissions of a region of memory, the region must be
+    /// entirely mapped, either by mappings, which are split where the region
+    /// only partially overlaps them, or as part of the program image, heap or
+    /// stack. Reserved regions are allocated when they are first given
+    /// permissions, and pages made inaccessible are kept so their contents
+    /// survive until access is given back.
+    pub fn protect(&mut self, addr: usize, length: usize, perm: PageTableEntryFlags) -> Result<(), usize>
+    {
+        if addr % mem::PAGE_SIZE != 0
+        {
+            return Err(errno::EINVAL);
+        }
+
+        if length == 0
+        {
+            return Ok(());
+        }
+
+        if addr >= mem::USER_SPACE_END || length > mem::USER_SPACE_END - addr
+        {
+            return Err(errno::ENOMEM);
+        }
+
+        let end = addr + (length + mem::PAGE_SIZE - 1) / mem::PAGE_SIZE * mem::PAGE_SIZE;
+
+        // Check the whole region before changing any of it
+        let unmanaged = self.unmanaged_pages(addr, end)?;
+
+        for vaddr in unmanaged
+        {
+            self.protect_page(vaddr, perm);
+        }
+
+        for start in self.overlapping_mappings(addr, end)
+        {
+            let mapping = self.data.mappings.remove(&start).unwrap();
+            let mapping_end = start + mapping.pages * mem::PAGE_SIZE;
+
+            let cut_start = start.max(addr);
+            let cut_end = mapping_end.min(end);
+
+            let first_page = (cut_start - start) / mem::PAGE_SIZE;
+            let cut_pages = (cut_end - cut_start) / mem::PAGE_SIZE;
+
+            // Keep the parts of the mapping outside of the region
+            if start < cut_start
+            {
+                self.data.mappings.insert(start, mapping.slice(0, first_page));
+            }
+
+            if cut_end < mapping_end
+            {
+                self.data.mappings.insert(cut_end, mapping.slice(first_page + cut_pages, (mapping_end - cut_end) / mem::PAGE_SIZE));
+            }
+
+            let mut changed = mapping.slice(first_page, cut_pages);
+            let result = self.change_protection(cut_start, &mut changed, perm);
+
+            self.data.mappings.insert(cut_start, changed);
+
+            result?;
+        }
+
+        Ok(())
+    }
+
+    /// Apply new permissions to the pages of a mapping at the given address
+    fn change_protection(&mut self, addr: usize, mapping: &mut MemoryMapping, perm: PageTableEntryFlags) -> Result<(), usize>
+    {
+        let accessible = perm & (PageTableEntryFlags::readable() | PageTableEntryFlags::writable() | PageTableEntryFlags::executable());
+
+        if mapping.perm.is_none()
+        {
+            // A reserved region stays unallocated until it can be accessed
+            if accessible
+            {
+                self.populate_mapping(addr, mapping, perm)?;
+                mapping.perm = Some(perm);
+            }
+
+            return Ok(());
+        }
+
+        let shared_file = matches!(mapping.backing, MappingBacking::File { shared: true, .. });
+
+        // Dirty pages are written back before they stop being writable, as
+        // they could no longer be told apart from clean pages
+        if shared_file && !(perm & PageTableEntryFlags::writable())
+        {
+            self.write_back_dirty(addr, mapping, addr, addr + mapping.pages * mem::PAGE_SIZE)?;
+        }
+
+        for i in 0..mapping.pages
+        {
+            let vaddr = addr + i * mem::PAGE_SIZE;
+
+            // Clean pages of shared file mappings stay write protected, so the
+            // first store to them marks them dirty
+            let clean = shared_file && !unsafe { self.root.as_mut().unwrap() }.page_flags(vaddr)
+                .map(|flags| flags & PageTableEntryFlags::writable()).unwrap_or(false);
+
+            self.protect_page(vaddr, if clean { perm.without(PageTableEntryFlags::writable() | PageTableEntryFlags::dirty()) } else { perm });
+        }
+
+        mapping.perm = Some(perm);
+
+        Ok(())
+    }
+
+    /// Apply new permissions to a single page if it is mapped, flushing it
+    /// from the TLB
+    fn protect_page(&mut self, vaddr: usize, perm: PageTableEntryFlags)
+    {
+        let accessible = perm & (PageTableEntryFlags::readable() | PageTableEntryFlags::writable() | PageTableEntryFlags::executable());
+
+        // A page table entry must have a permission bit set, so inaccessible
+        // pages are left readable by the kernel only
+        let entry_flags = if accessible { perm } else { PageTableEntryFlags::readable() };
+
+        if unsafe { self.root.as_mut().unwrap() }.protect(vaddr, entry_flags)
+        {
+            mem::mmu::flush_tlb_page(vaddr);
+        }
+    }
+
+    /// Write a page of a shared file mapping back to the file, without
+    /// extending the file or moving the position of the descriptor
+    fn write_back_page(&mut self, descriptor: &SharedDescriptor, file_offset: usize, phys_addr: usize)
+    {
+        let mut desc = descriptor.borrow_mut();
+
+        let position = desc.seek(0, SeekMode::SeekCurrent);
+        let file_length = desc.seek(0, SeekMode::SeekEnd);
+
+        if file_offset < file_length
+        {
+            desc.seek(file_offset, SeekMode::SeekSet);
+            desc.write(self.fs_interface.as_mut().unwrap(), phys_addr as *mut u8, mem::PAGE_SIZE.min(file_length - file_offset));
+        }
+
+        desc.seek(position, SeekMode::SeekSet);
+    }
+
+    /// Write the dirty pages of a shared file mapping which fall between two
+    /// addresses back to its file. Pages are write protected once they are
+    /// written back, so the next store to them faults and marks them dirty
+    /// again.
+    fn write_back_dirty(&mut self, start: usize, mapping: &MemoryMapping, first: usize, last: usize) -> Result<(), usize>
+    {
+        let (descriptor, offset, perm) = match (&mapping.backing, mapping.perm)
+        {
+            (MappingBacking::File { descriptor, offset, shared: true }, Some(perm)) => (descriptor, *offset, perm),
+            _ => return Ok(())
+        };
+
+        self.ensure_fs();
+
+        for vaddr in (first..last).step_by(mem::PAGE_SIZE)
+        {
+            let root = unsafe { self.root.as_mut().unwrap() };
+
+            // Only pages which have been written to are writable
+            if !root.page_flags(vaddr).map(|flags| flags & PageTableEntryFlags::writable()).unwrap_or(false)
+            {
+                continue;
+            }
+
+            let phys_addr = root.virt_to_phys(vaddr).map_err(|_| errno::EFAULT)?;
+
+            self.write_back_page(descriptor, offset + (vaddr - start), phys_addr);
+
+            let clean = perm.without(PageTableEntryFlags::writable() | PageTableEntryFlags::dirty());
+
+            if unsafe { self.root.as_mut().unwrap() }.protect(vaddr, clean)
+            {
+                mem::mmu::flush_tlb_page(vaddr);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Write the dirty pages of the shared file mappings in a region back to
+    /// their files, the region must be entirely covered by mappings. If `wait`
+    /// is set the files are synced to the disk before returning.
+    pub fn sync_mappings(&mut self, addr: usize, length: usize, wait: bool) -> Result<(), usize>
+    {
+        if addr % mem::PAGE_SIZE != 0
+        {
+            return Err(errno::EINVAL);
+        }
+
+        if addr >= mem::USER_SPACE_END || length > mem::USER_SPACE_END - addr
+        {
+            return Err(errno::ENOMEM);
+        }
+
+        let end = addr + (length + mem::PAGE_SIZE - 1) / mem::PAGE_SIZE * mem::PAGE_SIZE;
+
+        self.ensure_fs();
+
+        let mut inodes = Vec::new();
+
+        for start in self.covering_mappings(addr, end)?
+        {
+            let mapping = self.data.mappings[&start].clone();
+
+            let first = start.max(addr);
+            let last = (start + mapping.pages * mem::PAGE_SIZE).min(end);
+
+            self.write_back_dirty(start, &mapping, first, last)?;
+
+            if let MappingBacking::File { descriptor, shared: true, .. } = &mapping.backing
+            {
+                let inode = descriptor.borrow_mut().get_inode();
+
+                if let Some(inode) = inode
+                {
+                    if !inodes.contains(&inode)
+                    {
+                        inodes.push(inode);
+                    }
+                }
+            }
+        }
+
+        if wait
+        {
+            for inode in inodes
+            {
+                self.fs_interface.as_mut().unwrap().fsync_inode(inode).map_err(|e| e.to_errno())?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Handle a store to a page of a shared file mapping which was write
+    /// protected when it was written back, returning true if the page was
+    /// made writable again
+    pub fn handle_write_fault(&mut self, addr: usize) -> bool
+    {
+        let vaddr = addr & !(mem::PAGE_SIZE - 1);
+
+        let perm = match self.data.mappings.range(..=vaddr).next_back()
+        {
+            Some((start, mapping)) if vaddr < start + mapping.pages * mem::PAGE_SIZE =>
+            {
+                match (&mapping.backing, mapping.perm)
+                {
+                    (MappingBacking::File { shared: true, .. }, Some(perm)) if perm & PageTableEntryFlags::writable() => perm,
+                    _ => return false
+                }
+            },
+            _ => return false
+        };
+
+        if unsafe { self.root.as_mut().unwrap() }.protect(vaddr, perm)
+        {
+            mem::mmu::flush_tlb_page(vaddr);
+
+            return true;
+        }
+
+        false
+    }
+
+    /// Make sure the page holding a user address can be accessed by the
+    /// kernel on behalf of the process, expanding the stack into it if needed.
+    /// A page the kernel will write to must be writable by the process, which
+    /// marks a written back shared file page as dirty again.
+    pub fn prepare_user_page(&mut self, addr: usize, write: bool) -> bool
+    {
+        let space = self.address_space();
+
+        if space.map_mem(addr).is_err()
+        {
+            if addr < STACK_START || addr >= space.stack as usize || !space.expand_stack(addr)
+            {
+                return false;
+            }
+        }
+
+        if write
+        {
+            let flags = unsafe { space.root.as_mut().unwrap() }.page_flags(addr);
+
+            match flags
+            {
+                Some(flags) if flags & PageTableEntryFlags::writable() => {},
+                Some(_) => return space.handle_write_fault(addr),
+                None => return false
+            }
+        }
+
+        true
+    }
+
+    /// Write back and free the pages of a mapping at the given address
+    fn release_mapping(&mut self, addr: usize, mapping: &MemoryMapping)
+    {
+        if mapping.perm.is_none()
+        {
+            return;
+        }
+
+        self.ensure_fs();
+
+        for i in 0..mapping.pages
+        {
+            let vaddr = addr + i * mem::PAGE_SIZE;
+
+            let phys_addr = if let Ok(phys_addr) = unsafe { self.root.as_ref().unwrap() }.virt_to_phys(vaddr)
+            {
+                phys_addr
+            }
+            else
+            {
+                continue;
+            };
+
+            // Pages of shared file mappings which have been written to are
+            // written back, clean pages are left alone so changes made to the
+            // file since they were last synced are kept
+            if let MappingBacking::File { descriptor, offset, shared: true } = &mapping.backing
+            {
+                let dirty = unsafe { self.root.as_mut().unwrap() }.page_flags(vaddr)
+                    .map(|flags| flags & PageTableEntryFlags::writable()).unwrap_or(false);
+
+                if dirty
+                {
+                    self.write_back_page(descriptor, offset + i * mem::PAGE_SIZE, phys_addr);
+                }
+            }
+
+            unsafe { self.root.as_mut().unwrap() }.unmap(vaddr, 0);
+
+            if mapping.owns_pages()
+            {
+                mem::kpzfree(phys_addr, 1).unwrap();
+                self.data.mem_stats.resident -= 1;
+            }
+        }
+    }
+
+    /// Get directory entries for the given file descriptor
+    pub fn get_dir_entries(&mut self, fd: usize) -> Result<Vec<DirectoryEntry>, usize>
+    {
+        let inode = if let Some(desc) = self.data.descriptors.get(&fd)
+        {
+            if let Some(inode) = desc.borrow_mut().get_inode()
+            {
+                inode
+            }
+            else
+            {
+                return Err(errno::ENOENT); // File not found
+            }
+        }
+        else
+        {
+            return Err(errno::EBADF); // Bad file descriptor
+        };
+
+        self.ensure_fs();
+
+        self.fs_interface.as_mut().unwrap().get_dir_entries(inode).map_err(|e| e.to_errno())
+    }
+
+    /// Unlink a path
+    pub fn unlink(&mut self, path: OwnedPath) -> Result<(), usize>
+    {
+        self.ensure_fs();
+
+        let vfs = self.fs_interface.as_mut().unwrap();
+
+        // Get the inode of the path involved
+        let inode = 
+            if let Ok(inode_result) = fs::namespace::path_to_inode(&self.data.mount_namespace, self.data.root, vfs, &path)
+            {
+                inode_result
+            }
+            else
+            {
+                return Err(errno::ENOENT);
+            };
+
+        let (parent_path, name) = path.split_last();
+
+        // Get the inode of the parent directory
+        let parent = 
+            if let Ok(inode_result) = fs::namespace::path_to_inode(&self.data.mount_namespace, self.data.root, vfs, &parent_path)
             {
-                kwarnln!("Bad fd {}", fd);
-                return errno::EBADF; // Bad file descriptor
+                inode_result
             }
+            else
+            {
+                return Err(errno::ENOENT);
+            };
+
+        // Unlink the inode
+        if let Err(e) = vfs.unlink_inode(inode, parent, name.to_string())
+        {
+            return Err(e.to_errno());
         }
 
-        user_addr
+        Ok(())
     }
 
-    /// Unmap a region of memory
-    pub fn unmap(&mut self, addr: usize, length: usize) -> usize
+    /// Create a hard link to a file
+    pub fn link(&mut self, old_path: OwnedPath, new_path: OwnedPath) -> Result<(), usize>
     {
-        // Convert the user address to a physical address
-        let phys_addr = self.map_mem(addr).unwrap();
-
-        let mut should_free = true;
+        self.ensure_fs();
 
-        // Check if the mapped region is a file
-        if let Some(fd) = self.data.mmapped_files.get(&(phys_addr as *mut u8))
-        {
-            // TODO: Support offsets
+        let vfs = self.fs_interface.as_mut().unwrap();
 
-            // If the file still exists, update the cache
-            if let Some(fd_obj) = self.data.descriptors.get_mut(&fd)
+        // Get the inode being linked to
+        let inode = 
+            if let Ok(inode_result) = fs::namespace::path_to_inode(&self.data.mount_namespace, self.data.root, vfs, &old_path)
             {
-                if fd_obj.borrow().get_buffer().is_some()
-                {
-                    should_free = false;
-                }
-                else
-                {
-                    fd_obj.borrow_mut().seek(0, process::descriptor::SeekMode::SeekSet);
-                    fd_obj.borrow_mut().write(self.fs_interface.as_mut().unwrap(), phys_addr as *mut u8, 4096 * length);
-                }
+                inode_result
             }
+            else
+            {
+                return Err(errno::ENOENT);
+            };
 
-            // And remove the mapping from the process
-            self.data.mmapped_files.remove(&(phys_addr as *mut u8));
-        }
+        let (parent_path, name) = new_path.split_last();
+
+        // Get the inode of the directory the link will be placed in
+        let parent = 
+            if let Ok(inode_result) = fs::namespace::path_to_inode(&self.data.mount_namespace, self.data.root, vfs, &parent_path)
+            {
+                inode_result
+            }
+            else
+            {
+                return Err(errno::ENOENT);
+            };
 
-        // Unmap the memory
-        for i in 0..(length / mem::PAGE_SIZE)
+        // The new name must not already exist
+        if vfs.get_dir_entries(parent).map_err(|e| e.to_errno())?.iter().any(|entry| entry.name == name)
         {
-            unsafe { self.root.as_mut().unwrap() }.unmap(addr + i * mem::PAGE_SIZE, 0);
+            return Err(errno::EEXIST);
         }
 
-        // Free the memory
-        if should_free
+        // Create the link
+        if let Err(e) = vfs.link_inode(inode, parent, name.to_string())
         {
-            mem::kpfree(phys_addr, length / mem::PAGE_SIZE).unwrap();
+            return Err(e.to_errno());
         }
 
-        // Remove the mapping entry
-        let mut index = None;
-        for (i, mapping) in self.data.mem.iter().enumerate()
+        Ok(())
+    }
+
+    /// Create a symbolic link at `link_path` holding `target`, the target is
+    /// stored as given and need not exist
+    pub fn symlink(&mut self, target: String, link_path: OwnedPath) -> Result<(), usize>
+    {
+        self.ensure_fs();
+
+        let vfs = self.fs_interface.as_mut().unwrap();
+
+        let (parent_path, name) = link_path.split_last();
+
+        if name.len() == 0
         {
-            if mapping.0 as usize == phys_addr
-            {
-                index = Some(i);
-                break;
-            }
+            return Err(errno::EEXIST);
         }
 
-        if let Some(index) = index
+        // Get the inode of the directory the link will be placed in
+        let parent = fs::namespace::path_to_inode(&self.data.mount_namespace, self.data.root, vfs, &parent_path).map_err(|_| errno::ENOENT)?;
+
+        // The link must not replace an existing entry
+        if vfs.get_dir_entries(parent).map_err(|e| e.to_errno())?.iter().any(|entry| entry.name == name)
         {
-            self.data.mem.remove(index);
+            return Err(errno::EEXIST);
         }
 
-        0
+        vfs.create_symlink(parent, name.to_string(), target).map_err(|e| e.to_errno())?;
+
+        Ok(())
     }
 
-    /// Get directory entries for the given file descriptor
-    pub fn get_dir_entries(&mut self, fd: usize) -> Result<Vec<DirectoryEntry>, usize>
+    /// Read the target of the symbolic link at a path
+    pub fn readlink(&mut self, path: OwnedPath) -> Result<String, usize>
     {
-        let inode = if let Some(desc) = self.data.descriptors.get_mut(&fd)
-        {
-            if let Some(inode) = desc.borrow_mut().get_inode()
-            {
-                inode
-            }
-            else
-            {
-                return Err(errno::ENOENT); // File not found
-            }
-        }
-        else
-        {
-            return Err(errno::EBADF); // Bad file descriptor
-        };
-
         self.ensure_fs();
 
-        Ok(self.fs_interface.as_mut().unwrap().get_dir_entries(inode).unwrap())
+        let vfs = self.fs_interface.as_mut().unwrap();
+
+        let inode = fs::namespace::path_to_inode(&self.data.mount_namespace, self.data.root, vfs, &path).map_err(|_| errno::ENOENT)?;
+
+        vfs.read_symlink(inode).map_err(|e| e.to_errno())
     }
 
-    /// Unlink a path
-    pub fn unlink(&mut self, path: OwnedPath) -> Result<(), usize>
+    /// Rename a file or directory
+    pub fn rename(&mut self, old_path: OwnedPath, new_path: OwnedPath) -> Result<(), usize>
     {
         self.ensure_fs();
 
         let vfs = self.fs_interface.as_mut().unwrap();
 
-        // Get the inode of the path involved
+        let (old_parent_path, old_name) = old_path.split_last();
+        let (new_parent_path, new_name) = new_path.split_last();
+
+        // The root directory cannot be moved or replaced
+        if old_name.len() == 0 || new_name.len() == 0
+        {
+            return Err(errno::EBUSY);
+        }
+
+        // A directory cannot be moved inside of itself
+        if new_path.as_str().starts_with(&format!("{}/", old_path))
+        {
+            return Err(errno::EINVAL);
+        }
+
+        // Get the inode being renamed
         let inode = 
-            if let Ok(inode_result) = vfs.path_to_inode(&path)
+            if let Ok(inode_result) = fs::namespace::path_to_inode(&self.data.mount_namespace, self.data.root, vfs, &old_path)
             {
                 inode_result
             }
@@ -815,11 +1940,19 @@ impl Process
                 return Err(errno::ENOENT);
             };
 
-        let (parent_path, name) = path.split_last();
+        // Get the inodes of the old and new parent directories
+        let old_parent = 
+            if let Ok(inode_result) = fs::namespace::path_to_inode(&self.data.mount_namespace, self.data.root, vfs, &old_parent_path)
+            {
+                inode_result
+            }
+            else
+            {
+                return Err(errno::ENOENT);
+            };
 
-        // Get the inode of the parent directory
-        let parent = 
-            if let Ok(inode_result) = vfs.path_to_inode(&parent_path)
+        let new_parent = 
+            if let Ok(inode_result) = fs::namespace::path_to_inode(&self.data.mount_namespace, self.data.root, vfs, &new_parent_path)
             {
                 inode_result
             }
@@ -828,8 +1961,8 @@ impl Process
                 return Err(errno::ENOENT);
             };
 
-        // Unlink the inode
-        if let Err(e) = vfs.unlink_inode(inode, parent, name.to_string())
+        // Move the entry
+        if let Err(e) = vfs.rename_inode(inode, old_parent, old_name.to_string(), new_parent, new_name.to_string())
         {
             return Err(e.to_errno());
         }
@@ -846,7 +1979,7 @@ impl Process
 
         // Get the inode of the path involved
         let inode = 
-            if let Ok(inode_result) = vfs.path_to_inode(&path)
+            if let Ok(inode_result) = fs::namespace::path_to_inode(&self.data.mount_namespace, self.data.root, vfs, &path)
             {
                 inode_result
             }
@@ -859,7 +1992,7 @@ impl Process
 
         // Get the inode of the parent directory
         let parent = 
-            if let Ok(inode_result) = vfs.path_to_inode(&parent_path)
+            if let Ok(inode_result) = fs::namespace::path_to_inode(&self.data.mount_namespace, self.data.root, vfs, &parent_path)
             {
                 inode_result
             }
@@ -877,6 +2010,52 @@ impl Process
         Ok(())
     }
 
+    /// Create a directory with the given permission bits
+    pub fn mkdir(&mut self, path: OwnedPath, mode: usize) -> Result<(), usize>
+    {
+        self.ensure_fs();
+
+        let mode = self.creation_mode(mode);
+
+        let vfs = self.fs_interface.as_mut().unwrap();
+
+        let (parent_path, name) = path.split_last();
+
+        // The root directory always exists
+        if name.len() == 0
+        {
+            return Err(errno::EEXIST);
+        }
+
+        // Get the inode of the parent directory
+        let parent = 
+            if let Ok(inode_result) = fs::namespace::path_to_inode(&self.data.mount_namespace, self.data.root, vfs, &parent_path)
+            {
+                inode_result
+            }
+            else
+            {
+                return Err(errno::ENOENT);
+            };
+
+        // Make sure the parent is a directory which does not already contain
+        // an entry with the same name
+        let entries = vfs.get_dir_entries(parent).map_err(|e| e.to_errno())?;
+
+        if entries.iter().any(|entry| entry.name == name)
+        {
+            return Err(errno::EEXIST);
+        }
+
+        // Create the directory
+        if let Err(e) = vfs.create_directory(parent, name.to_string(), mode)
+        {
+            return Err(e.to_errno());
+        }
+
+        Ok(())
+    }
+
     /// Stat a file
     pub fn stat(&mut self, path: OwnedPath) -> Result<fs::structures::FileStat, usize>
     {
@@ -884,7 +2063,7 @@ impl Process
         let vfs = self.fs_interface.as_mut().unwrap();
 
         // Convert the path to an inode
-        if let Ok(inode_result) = vfs.path_to_inode(&path)
+        if let Ok(inode_result) = fs::namespace::path_to_inode(&self.data.mount_namespace, self.data.root, vfs, &path)
         {
             vfs.get_stat(inode_result).map_err(|e| e.to_errno())
         }
@@ -894,6 +2073,40 @@ impl Process
         }
     }
 
+    /// Get the inode open on a file descriptor
+    pub fn descriptor_inode(&mut self, fd: usize) -> Result<fs::structures::FilesystemIndex, usize>
+    {
+        let descriptor = self.data.descriptors.get(&fd).ok_or(errno::EBADF)?;
+
+        // Descriptors such as pipes have no inode to change
+        let inode = descriptor.borrow_mut().get_inode().ok_or(errno::EINVAL)?;
+
+        Ok(inode)
+    }
+
+    /// Get the inode at a path
+    pub fn path_inode(&mut self, path: &OwnedPath) -> Result<fs::structures::FilesystemIndex, usize>
+    {
+        self.ensure_fs();
+        let vfs = self.fs_interface.as_mut().unwrap();
+
+        fs::namespace::path_to_inode(&self.data.mount_namespace, self.data.root, vfs, path).map_err(|_| errno::ENOENT)
+    }
+
+    /// Change the metadata of an inode, the update is applied to the current
+    /// stat of the inode and the result written back, unless the update
+    /// refuses the change
+    pub fn update_stat(&mut self, inode: fs::structures::FilesystemIndex, update: impl FnOnce(&mut fs::structures::FileStat) -> Result<(), usize>) -> Result<(), usize>
+    {
+        self.ensure_fs();
+        let vfs = self.fs_interface.as_mut().unwrap();
+
+        let mut stat = vfs.get_stat(inode).map_err(|e| e.to_errno())?;
+        update(&mut stat)?;
+
+        vfs.set_stat(inode, stat).map_err(|e| e.to_errno())
+    }
+
     /// Get the total memory held by the process in pages
     pub fn get_process_memory(&self) -> usize
     {
@@ -904,7 +2117,22 @@ impl Process
             total += size;
         }
 
-        total
+        for mapping in self.data.mappings.values()
+        {
+            if mapping.perm.is_some() && mapping.owns_pages()
+            {
+                total += mapping.pages;
+            }
+        }
+
+        total + self.heap_pages()
+    }
+
+    /// Get the total memory resident for the process in pages, including the
+    /// stack
+    pub fn resident_pages(&self) -> usize
+    {
+        self.get_process_memory() + self.data.stack_size
     }
 
     /// Get the disposition for a given signal
@@ -919,110 +2147,275 @@ impl Process
     {
         kdebug!(Signals, "PID {} got Signal {:?}, ", self.pid, signal.sig_type);
 
+        // SIGCONT always continues a stopped process, even if it is caught
+        if signal.sig_type == SignalType::SIGCONT && self.state == ProcessState::Stopped
+        {
+            self.state = ProcessState::Running;
+            self.job_event = Some(JobEvent::Continued);
+        }
+
         match self.get_disposition_for_signal(signal.sig_type)
         {
             SignalDisposition::Terminate =>
             {
                 kdebugln!(Signals, "Terminating");
-                self.kill(128 + signal.sig_type as usize)
+                self.kill_by_signal(signal.sig_type)
             },
             SignalDisposition::Ignore =>
             { 
                 kdebugln!(Signals, "Ignoring");
             },
-            SignalDisposition::Handler(addr) => 
+            SignalDisposition::Handler(action) =>
+            {
+                kdebugln!(Signals, "Running Handler at {:x}", action.handler_address());
+
+                // Running a handler interrupts a blocking syscall, which
+                // then fails with EINTR once the handler returns
+                if let ProcessState::Sleeping { wake_time } = self.state
+                {
+                    self.interrupt_sleep(wake_time);
+                }
+
+                if matches!(self.state, ProcessState::Waiting(_) | ProcessState::Sleeping { .. })
+                {
+                    unsafe { self.frame.as_mut().unwrap() }.regs[10] = errno::EINTR;
+                    self.state = ProcessState::Running;
+                }
+
+                if self.switch_to_signal_handler(action, signal)
+                {
+                    // A stopped process runs the handler once it is continued
+                    return self.state == ProcessState::Running;
+                }
+
+                kdebugln!(Signals, "PID {} has no room for a signal frame", self.pid);
+                self.kill_by_signal(SignalType::SIGSEGV)
+            },
+            // Core dumps are not written, so this is the same as terminating
+            SignalDisposition::Core =>
             {
-                self.switch_to_signal_handler(addr, signal);
-                return true;
+                kdebugln!(Signals, "Terminating (Core)");
+                self.kill_by_signal(signal.sig_type)
             },
-            SignalDisposition::Core => todo!(),
             SignalDisposition::Stop =>
             {
                 kdebugln!(Signals, "Stopping");
                 self.state = ProcessState::Stopped;
+                self.job_event = Some(JobEvent::Stopped(signal.sig_type));
             },
-            SignalDisposition::Continue => 
+            SignalDisposition::Continue =>
             {
                 kdebugln!(Signals, "Continuing");
-
-                if self.state == ProcessState::Stopped
-                {
-                    self.state = ProcessState::Running;
-                }
             },
         }
 
         false
     }
 
-    /// Push a signal to the signal stack
-    pub fn push_signal(&mut self, signal: POSIXSignal) -> Result<(), ()>
+    /// Report the time left in an interrupted nanosleep
+    fn interrupt_sleep(&mut self, wake_time: KernelTime)
     {
-        for val in self.signals.iter_mut()
+        if let Some(addr) = self.data.sleep_remaining.take()
         {
-            if val.is_none()
+            let remaining = (wake_time - unsafe { &drivers::TIMER_DRIVER }.time()).as_nanoseconds();
+            let time = [remaining / 1_000_000_000, remaining % 1_000_000_000];
+
+            let bytes = unsafe { core::slice::from_raw_parts(time.as_ptr() as *const u8, core::mem::size_of_val(&time)) };
+
+            if syscalls::utils::copy_to_user(self, addr, bytes).is_err()
             {
-                *val = Some(signal);
-                return Ok(())
+                kdebugln!(Signals, "Unable to write the remaining sleep time for PID {}", self.pid);
             }
         }
+    }
+
+    /// Add a signal to the pending signals, a signal which is already
+    /// pending is only delivered once
+    pub fn push_signal(&mut self, signal: POSIXSignal)
+    {
+        // Stop and continue signals cancel each other out
+        match signal.sig_type
+        {
+            SignalType::SIGCONT =>
+            {
+                for sig in [SignalType::SIGSTOP, SignalType::SIGTSTP, SignalType::SIGTTIN, SignalType::SIGTTOU]
+                {
+                    self.pending_signals.remove(&sig);
+                }
+            },
+            SignalType::SIGSTOP | SignalType::SIGTSTP | SignalType::SIGTTIN | SignalType::SIGTTOU =>
+            {
+                self.pending_signals.remove(&SignalType::SIGCONT);
+            },
+            _ => {}
+        }
 
-        Err(())
+        self.pending_signals.entry(signal.sig_type).or_insert(signal);
     }
 
-    /// Pop a signal from the signal stack
+    /// Take the lowest numbered pending signal which is not blocked
     pub fn pop_signal(&mut self) -> Option<POSIXSignal>
     {
-        let result = self.signals[0];
+        let mask = self.data.signal_mask;
+        let sig_type = *self.pending_signals.keys().find(|sig| mask & sig.mask_bit() == 0)?;
 
-        for i in 1..self.signals.len()
+        self.pending_signals.remove(&sig_type)
+    }
+
+    /// Get the set of signals read through a descriptor, if it is a signalfd
+    pub fn signal_descriptor_mask(&self, fd: usize) -> Option<u64>
+    {
+        self.data.descriptors.get(&fd).and_then(|desc| desc.borrow().get_signal_mask())
+    }
+
+    /// Check if a signal in the given set is pending, blocked or not
+    pub fn has_pending_signal_in(&self, set: u64) -> bool
+    {
+        self.pending_signals.keys().any(|sig| set & sig.mask_bit() != 0)
+    }
+
+    /// Take the pending signals in the given set, lowest numbered first, and
+    /// write a record for each to a userspace buffer, as many as fit. The
+    /// signals are only taken once the records have been written.
+    pub fn read_signals(&mut self, set: u64, buffer: usize, count: usize) -> usize
+    {
+        let size = core::mem::size_of::<SignalFdInfo>();
+
+        if count < size
+        {
+            return errno::EINVAL;
+        }
+
+        let signals: Vec<POSIXSignal> = self.pending_signals.values()
+            .filter(|signal| set & signal.sig_type.mask_bit() != 0)
+            .take(count / size)
+            .copied()
+            .collect();
+
+        if signals.is_empty()
+        {
+            return errno::EAGAIN;
+        }
+
+        let records: Vec<SignalFdInfo> = signals.iter().map(|signal| signal.to_signalfd_info()).collect();
+        let bytes = unsafe { core::slice::from_raw_parts(records.as_ptr() as *const u8, records.len() * size) };
+
+        if let Err(e) = syscalls::utils::copy_to_user(self, buffer, bytes)
+        {
+            return e;
+        }
+
+        for signal in &signals
         {
-            self.signals[i - 1] = self.signals[i];
+            self.pending_signals.remove(&signal.sig_type);
         }
 
-        result
+        bytes.len()
     }
 
-    /// Swap out the trap frames
-    pub fn swap_frames(&mut self)
+    /// Check if any pending signal needs to be acted on, ignored signals are
+    /// left to be discarded the next time signals are delivered
+    pub fn has_pending_signals(&self) -> bool
     {
-        core::mem::swap(
-            unsafe { self.frame.as_mut().unwrap() }, 
-            unsafe { self.backup_frame.as_mut().unwrap() });
-
-        core::mem::swap(
-            &mut self.program_counter,
-            &mut self.backup_program_counter);
+        self.pending_signals.keys().any(|sig|
+            self.data.signal_mask & sig.mask_bit() == 0 &&
+            self.data.signal_map.get(sig) != Some(&SignalDisposition::Ignore))
     }
 
-    /// Return from a signal handler
+    /// Return from a signal handler, restoring the context saved in the
+    /// signal frame at the stack pointer
     pub fn return_from_signal(&mut self)
     {
         kdebugln!(Signals, "Returning from signal on PID {}", self.pid);
-        
-        self.swap_frames();
+
+        let frame = unsafe { self.frame.as_mut() }.unwrap();
+
+        let mut signal_frame: SignalFrame = unsafe { core::mem::zeroed() };
+        let bytes = unsafe { core::slice::from_raw_parts_mut(&mut signal_frame as *mut SignalFrame as *mut u8, core::mem::size_of::<SignalFrame>()) };
+
+        if syscalls::utils::copy_from_user(self, frame.regs[2], bytes).is_err()
+        {
+            kdebugln!(Signals, "PID {} returned from a signal with an invalid frame", self.pid);
+            self.kill_by_signal(SignalType::SIGSEGV);
+        }
+        else
+        {
+            frame.regs = signal_frame.regs;
+            frame.regs[0] = 0;
+            frame.fregs = signal_frame.fregs;
+            frame.fcsr = signal_frame.fcsr;
+
+            self.program_counter = signal_frame.program_counter;
+            self.data.signal_mask = SignalType::sanitize_mask(signal_frame.mask);
+        }
 
         trap::handler::switch_process();
     }
 
-    /// Swap to a signal handler
-    pub fn switch_to_signal_handler(&mut self, addr: usize, signal: POSIXSignal)
+    /// Set up the process to run a signal handler, the interrupted context is
+    /// saved in a signal frame pushed onto the user stack. Returns false if
+    /// the frame could not be written.
+    pub fn switch_to_signal_handler(&mut self, action: SignalAction, signal: POSIXSignal) -> bool
     {
-        self.swap_frames();
+        let frame = unsafe { self.frame.as_mut() }.unwrap();
 
-        self.program_counter = addr;
-        
-        if let Some(frame) = unsafe { self.frame.as_mut() }
+        let signal_frame = SignalFrame
         {
-            frame.regs[10] = signal.sig_type as u32 as usize;
-            frame.regs[2] = unsafe { self.backup_frame.as_mut() }.unwrap().regs[2];
+            info: signal.to_sig_info(),
+            regs: frame.regs,
+            fregs: frame.fregs,
+            fcsr: frame.fcsr,
+            program_counter: self.program_counter,
+            mask: self.data.saved_signal_mask.unwrap_or(self.data.signal_mask)
+        };
+
+        let size = core::mem::size_of::<SignalFrame>();
+        let addr = frame.regs[2].wrapping_sub(size) & !15;
+
+        let space = self.address_space();
 
-            // TODO: Add the signal info structure
+        if addr >= STACK_START && addr < space.stack as usize && !space.expand_stack(addr)
+        {
+            return false;
         }
-        else
+
+        let bytes = unsafe { core::slice::from_raw_parts(&signal_frame as *const SignalFrame as *const u8, size) };
+
+        if syscalls::utils::copy_to_user(self, addr, bytes).is_err()
+        {
+            return false;
+        }
+
+        // The mask replaced by sigsuspend is restored when the handler returns
+        self.data.saved_signal_mask = None;
+
+        // The handler is called as handler(signal, &info, &frame), the info
+        // is the first field of the frame
+        frame.regs[1] = if action.flags & SA_RESTORER > 0 { action.restoring_addr } else { SIGNAL_RETURN_ADDRESS };
+        frame.regs[2] = addr;
+        frame.regs[10] = signal.sig_type as usize;
+        frame.regs[11] = addr;
+        frame.regs[12] = addr;
+
+        self.program_counter = action.handler_address();
+
+        // Block the signal along with the mask of the action while the
+        // handler runs
+        let mut mask = self.data.signal_mask | action.mask;
+
+        if action.flags & SA_NODEFER == 0
+        {
+            mask |= signal.sig_type.mask_bit();
+        }
+
+        self.data.signal_mask = SignalType::sanitize_mask(mask);
+
+        if action.flags & SA_RESETHAND > 0
         {
-            panic!("Process PID {} has an invalid trap frame loaded!", self.pid);
+            self.data.signal_map.insert(signal.sig_type, signal.sig_type.default_disposition());
         }
+
+        true
     }
 
     /// Perform explicit cleanup which requires context such as closing file descriptors
@@ -1030,12 +2423,17 @@ impl Process
     {
         self.ensure_fs();
 
-        for (_, desc) in &mut self.data.descriptors
+        // A table shared with other processes stays open for them
+        if self.data.descriptors.is_shared()
         {
-            desc.borrow_mut().close(self.fs_interface.as_mut().unwrap());
+            self.data.descriptors = super::descriptor::DescriptorTable::new();
+            return;
         }
 
-        self.data.descriptors.clear();
+        for desc in self.data.descriptors.take_all()
+        {
+            desc.borrow_mut().close(self.fs_interface.as_mut().unwrap());
+        }
     }
 }
 
@@ -1045,12 +2443,29 @@ impl core::ops::Drop for Process
     {
         self.context_cleanup();
 
+        // The owner of the address space frees the memory
+        if self.data.memory_owner.is_some()
+        {
+            mem::kpzfree(self.frame as usize, 1).unwrap();
+            return;
+        }
+
+        // Release every mapping, writing back any shared file mappings
+        for (addr, mapping) in core::mem::take(&mut self.data.mappings)
+        {
+            self.release_mapping(addr, &mapping);
+        }
+
+        // Free the heap
+        let heap_end = self.data.heap_start + self.heap_pages() * mem::PAGE_SIZE;
+        self.free_heap_pages(self.data.heap_start, heap_end);
+
         for i in 0..self.data.stack_size
         {
             let true_stack = unsafe { (*self.root).virt_to_phys(self.stack as usize + mem::PAGE_SIZE * i) }.unwrap();
 
             // Drop the stack
-            mem::kpfree(true_stack, 1).unwrap();
+            mem::kpzfree(true_stack, 1).unwrap();
         }
 
         // Drop the page table
@@ -1061,14 +2476,11 @@ impl core::ops::Drop for Process
         {
             if !ptr.is_null()
             {
-                mem::kpfree(*ptr as usize, *length).unwrap();
+                mem::kpzfree(*ptr as usize, *length).unwrap();
             }
         }
         
         // Drop the trap frame
-        mem::kpfree(self.frame as usize, 1).unwrap();
-
-        // Drop the backup trap frame
-        mem::kpfree(self.backup_frame as usize, 1).unwrap();
+        mem::kpzfree(self.frame as usize, 1).unwrap();
     }
 }
@@ -0,0 +1,205 @@
+//! Resource Control Groups
+//!
+//! Processes are assigned to a resource group which caps the resident memory
+//! of all of its members and weights the length of their time slices
+
+use crate::*;
+
+use alloc::collections::BTreeMap;
+
+use drivers::timer::KernelTime;
+
+use super::process::Process;
+use super::PID;
+
+/// Resource Group Identifier
+pub type ResourceGroupId = usize;
+
+/// The group every process starts in, it can never be removed
+pub const ROOT_RESOURCE_GROUP: ResourceGroupId = 0;
+
+/// CPU weight given to groups by default, a group with twice this weight will
+/// get time slices which are twice as long
+pub const DEFAULT_CPU_WEIGHT: usize = 100;
+
+/// Bounds on the CPU weight of a group
+pub const MIN_CPU_WEIGHT: usize = 1;
+pub const MAX_CPU_WEIGHT: usize = 10000;
+
+/// Resource Group
+#[derive(Debug, Clone)]
+pub struct ResourceGroup
+{
+    pub name: String,
+    pub memory_limit: Option<usize>, // Limit in pages
+    pub cpu_weight: usize
+}
+
+impl ResourceGroup
+{
+    /// Create a new resource group with no memory limit and the default weight
+    pub fn new(name: String) -> Self
+    {
+        Self
+        {
+            name,
+            memory_limit: None,
+            cpu_weight: DEFAULT_CPU_WEIGHT
+        }
+    }
+}
+
+// Global Resource Groups
+static mut GLOBAL_RESOURCE_GROUPS: Option<BTreeMap<ResourceGroupId, ResourceGroup>> = None;
+
+/// Get a reference to the resource groups, initializing the root group if needed
+pub fn get_resource_groups() -> &'static mut BTreeMap<ResourceGroupId, ResourceGroup>
+{
+    let groups = unsafe { &mut GLOBAL_RESOURCE_GROUPS };
+
+    if groups.is_none()
+    {
+        let mut map = BTreeMap::new();
+        map.insert(ROOT_RESOURCE_GROUP, ResourceGroup::new(String::from("root")));
+
+        *groups = Some(map);
+    }
+
+    groups.as_mut().unwrap()
+}
+
+/// Get a resource group by id
+pub fn get_resource_group(id: ResourceGroupId) -> Option<&'static mut ResourceGroup>
+{
+    get_resource_groups().get_mut(&id)
+}
+
+/// Find a resource group by name
+pub fn find_resource_group(name: &str) -> Option<ResourceGroupId>
+{
+    get_resource_groups().iter().find(|(_, group)| group.name == name).map(|(id, _)| *id)
+}
+
+/// Create a new resource group, returning None if the name is already taken
+pub fn create_resource_group(name: String) -> Option<ResourceGroupId>
+{
+    if find_resource_group(&name).is_some()
+    {
+        return None;
+    }
+
+    let groups = get_resource_groups();
+    let id = groups.keys().last().map(|id| id + 1).unwrap_or(ROOT_RESOURCE_GROUP);
+
+    kdebugln!(Processes, "Creating resource group {} `{}`", id, name);
+
+    groups.insert(id, ResourceGroup::new(name));
+
+    Some(id)
+}
+
+/// Remove a resource group, this will fail if the group still has members or
+/// is the root group
+pub fn remove_resource_group(id: ResourceGroupId) -> bool
+{
+    if id == ROOT_RESOURCE_GROUP || group_members(id).len() > 0
+    {
+        return false;
+    }
+
+    get_resource_groups().remove(&id).is_some()
+}
+
+/// Get the pids of the processes in a group
+pub fn group_members(id: ResourceGroupId) -> Vec<PID>
+{
+    let mut result = Vec::new();
+
+    if let Some(manager) = process::scheduler::get_process_manager()
+    {
+        for (pid, proc) in &manager.processes
+        {
+            if proc.data.resource_group == id
+            {
+                result.push(*pid);
+            }
+        }
+    }
+
+    result
+}
+
+/// Get the resident memory of every process in the group in pages
+pub fn group_memory_usage(id: ResourceGroupId) -> usize
+{
+    let mut total = 0;
+
+    if let Some(manager) = process::scheduler::get_process_manager()
+    {
+        for proc in manager.processes.values()
+        {
+            if proc.data.resource_group == id
+            {
+                total += proc.resident_pages();
+            }
+        }
+    }
+
+    total
+}
+
+/// Move a process into a resource group
+pub fn assign_process(pid: PID, id: ResourceGroupId) -> bool
+{
+    if get_resource_group(id).is_none()
+    {
+        return false;
+    }
+
+    if let Some(proc) = process::scheduler::get_process_manager().map(|manager| manager.get_process_by_pid_mut(pid)).flatten()
+    {
+        kdebugln!(Processes, "Moving PID {} to resource group {}", pid, id);
+
+        proc.data.resource_group = id;
+
+        true
+    }
+    else
+    {
+        false
+    }
+}
+
+/// Check if a process can allocate the given number of pages without
+/// exceeding the memory limit of its group
+pub fn can_charge_memory(proc: &Process, pages: usize) -> bool
+{
+    if let Some(limit) = get_resource_group(proc.data.resource_group).map(|group| group.memory_limit).flatten()
+    {
+        let usage = group_memory_usage(proc.data.resource_group);
+
+        if usage + pages > limit
+        {
+            kwarnln!("PID {} exceeded the memory limit of resource group {} ({} + {} > {} pages)", proc.pid, proc.data.resource_group, usage, pages, limit);
+
+            return false;
+        }
+    }
+
+    true
+}
+
+/// Get the length of the time slice to give the process with the given pid
+/// given the base scheduling interval
+pub fn time_slice(pid: PID, interval: KernelTime) -> KernelTime
+{
+    let weight = process::scheduler::get_process_manager()
+        .map(|manager| manager.get_process_by_pid(pid))
+        .flatten()
+        .map(|proc| get_resource_group(proc.data.resource_group))
+        .flatten()
+        .map(|group| group.cpu_weight)
+        .unwrap_or(DEFAULT_CPU_WEIGHT);
+
+    KernelTime(interval.0 * weight / DEFAULT_CPU_WEIGHT)
+}
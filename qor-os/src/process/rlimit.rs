@@ -0,0 +1,104 @@
+//! Resource Limits
+//!
+//! Limits on the resources a process may use, each made of a soft limit which
+//! is enforced and a hard limit which the soft limit cannot be raised above.
+//! Limits are inherited across fork and execve.
+
+use crate::*;
+
+// Resources, must be kept in sync with syscalls.h
+pub const RLIMIT_CPU: usize =    0;
+pub const RLIMIT_STACK: usize =  3;
+pub const RLIMIT_NPROC: usize =  6;
+pub const RLIMIT_NOFILE: usize = 7;
+pub const RLIMIT_AS: usize =     9;
+
+// Number of resources which have limits
+pub const RLIMIT_COUNT: usize = 16;
+
+// Limit which is never reached
+pub const RLIM_INFINITY: u64 = u64::MAX;
+
+// Default limits on open descriptors, the stack size in bytes and the number
+// of processes
+pub const DEFAULT_NOFILE_SOFT: u64 = 1024;
+pub const DEFAULT_NOFILE_HARD: u64 = 4096;
+pub const DEFAULT_STACK_SOFT: u64 = 8 * 1024 * 1024;
+pub const DEFAULT_NPROC_SOFT: u64 = 512;
+
+/// Limit on a single resource, laid out as struct rlimit
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResourceLimit
+{
+    pub soft: u64,
+    pub hard: u64
+}
+
+impl ResourceLimit
+{
+    /// Create a new limit
+    pub const fn new(soft: u64, hard: u64) -> Self
+    {
+        Self { soft, hard }
+    }
+
+    /// A limit which is never reached
+    pub const fn unlimited() -> Self
+    {
+        Self::new(RLIM_INFINITY, RLIM_INFINITY)
+    }
+}
+
+/// Limits on every resource of a process
+#[derive(Debug, Clone, Copy)]
+pub struct ResourceLimits
+{
+    limits: [ResourceLimit; RLIMIT_COUNT]
+}
+
+impl ResourceLimits
+{
+    /// Create the limits given to the first process
+    pub fn new() -> Self
+    {
+        let mut limits = [ResourceLimit::unlimited(); RLIMIT_COUNT];
+
+        limits[RLIMIT_NOFILE] = ResourceLimit::new(DEFAULT_NOFILE_SOFT, DEFAULT_NOFILE_HARD);
+        limits[RLIMIT_STACK] = ResourceLimit::new(DEFAULT_STACK_SOFT, RLIM_INFINITY);
+        limits[RLIMIT_NPROC] = ResourceLimit::new(DEFAULT_NPROC_SOFT, RLIM_INFINITY);
+
+        Self { limits }
+    }
+
+    /// Get the limit on a resource, returning `EINVAL` for unknown resources
+    pub fn get(&self, resource: usize) -> Result<ResourceLimit, usize>
+    {
+        self.limits.get(resource).copied().ok_or(errno::EINVAL)
+    }
+
+    /// Get the soft limit on a resource, which is the one enforced
+    pub fn soft(&self, resource: usize) -> u64
+    {
+        self.limits[resource].soft
+    }
+
+    /// Change the limit on a resource, the soft limit may not be above the
+    /// hard limit, and only a privileged process may raise the hard limit
+    pub fn set(&mut self, resource: usize, limit: ResourceLimit, privileged: bool) -> Result<(), usize>
+    {
+        if resource >= RLIMIT_COUNT || limit.soft > limit.hard
+        {
+            return Err(errno::EINVAL);
+        }
+
+        if limit.hard > self.limits[resource].hard && !privileged
+        {
+            return Err(errno::EPERM);
+        }
+
+        self.limits[resource] = limit;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,228 @@
+//! Scheduler Run Queue
+//!
+//! Runnable processes are kept in one queue per priority, with a bitmap of the
+//! non-empty queues so the next process to run is found in constant time. The
+//! queues are intrusive lists threaded through the processes themselves, so
+//! queueing a process never allocates.
+
+use crate::*;
+
+use alloc::collections::BTreeMap;
+
+use super::process::Process;
+use super::PID;
+
+/// Number of priority levels, lower numbers are scheduled first
+pub const NUM_PRIORITIES: usize = 40;
+
+/// Priority given to processes by default
+pub const DEFAULT_PRIORITY: usize = 20;
+
+// Priority levels a process woken by terminal input is raised by until it is
+// next scheduled, configured through /sys/tty-wake-boost
+static mut TTY_WAKE_BOOST: usize = 10;
+
+// Set when a terminal receives input, so the running process can be cut short
+static mut TERMINAL_INPUT: bool = false;
+
+/// Get the number of priority levels processes woken by terminal input are
+/// raised by, zero disables the boost
+pub fn tty_wake_boost() -> usize
+{
+    unsafe { TTY_WAKE_BOOST }
+}
+
+/// Set the number of priority levels processes woken by terminal input are
+/// raised by
+pub fn set_tty_wake_boost(boost: usize)
+{
+    unsafe { TTY_WAKE_BOOST = boost.min(NUM_PRIORITIES - 1) };
+}
+
+/// Record that a terminal received input
+pub fn note_terminal_input()
+{
+    unsafe { TERMINAL_INPUT = true };
+}
+
+/// Check if a terminal received input since the last check
+pub fn take_terminal_input() -> bool
+{
+    let input = unsafe { TERMINAL_INPUT };
+    unsafe { TERMINAL_INPUT = false };
+
+    input
+}
+
+/// Process table the run queue links through
+pub type ProcessTable = BTreeMap<PID, Box<Process>>;
+
+/// Links placing a process in a run queue
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RunQueueLink
+{
+    pub next: Option<PID>,
+    pub prev: Option<PID>,
+    pub queued: bool,
+    // Levels the process is raised by while queued, cleared once it leaves
+    // the queue
+    pub boost: usize
+}
+
+impl RunQueueLink
+{
+    /// Create a new link for a process which is not queued
+    pub fn new() -> Self
+    {
+        Self
+        {
+            next: None,
+            prev: None,
+            queued: false,
+            boost: 0
+        }
+    }
+}
+
+/// Get the queue a process is placed in, its priority raised by any boost
+fn queue_priority(proc: &Process) -> usize
+{
+    proc.data.priority.saturating_sub(proc.run_link.boost).min(NUM_PRIORITIES - 1)
+}
+
+/// Run Queue
+pub struct RunQueue
+{
+    heads: [Option<PID>; NUM_PRIORITIES],
+    tails: [Option<PID>; NUM_PRIORITIES],
+    bitmap: u64
+}
+
+impl RunQueue
+{
+    /// Create a new, empty run queue
+    pub fn new() -> Self
+    {
+        Self
+        {
+            heads: [None; NUM_PRIORITIES],
+            tails: [None; NUM_PRIORITIES],
+            bitmap: 0
+        }
+    }
+
+    /// Check if there are no queued processes
+    pub fn is_empty(&self) -> bool
+    {
+        self.bitmap == 0
+    }
+
+    /// Get the highest priority with a queued process
+    pub fn highest_priority(&self) -> Option<usize>
+    {
+        if self.bitmap == 0
+        {
+            None
+        }
+        else
+        {
+            Some(self.bitmap.trailing_zeros() as usize)
+        }
+    }
+
+    /// Add a process to the back of the queue for its priority, processes
+    /// which are already queued are left where they are
+    pub fn push_back(&mut self, processes: &mut ProcessTable, pid: PID)
+    {
+        let priority = if let Some(proc) = processes.get_mut(&pid)
+        {
+            if proc.run_link.queued
+            {
+                return;
+            }
+
+            let priority = queue_priority(proc);
+
+            proc.run_link = RunQueueLink
+            {
+                next: None,
+                prev: self.tails[priority],
+                queued: true,
+                boost: proc.run_link.boost
+            };
+
+            priority
+        }
+        else
+        {
+            return;
+        };
+
+        if let Some(tail) = self.tails[priority]
+        {
+            processes.get_mut(&tail).unwrap().run_link.next = Some(pid);
+        }
+        else
+        {
+            self.heads[priority] = Some(pid);
+        }
+
+        self.tails[priority] = Some(pid);
+        self.bitmap |= 1 << priority;
+    }
+
+    /// Remove the process at the front of the highest priority queue
+    pub fn pop_front(&mut self, processes: &mut ProcessTable) -> Option<PID>
+    {
+        let priority = self.highest_priority()?;
+        let pid = self.heads[priority].unwrap();
+
+        self.unlink(processes, pid, priority);
+
+        Some(pid)
+    }
+
+    /// Remove a process from the run queue if it is queued
+    pub fn remove(&mut self, processes: &mut ProcessTable, pid: PID)
+    {
+        if let Some(proc) = processes.get(&pid)
+        {
+            if proc.run_link.queued
+            {
+                let priority = queue_priority(proc);
+                self.unlink(processes, pid, priority);
+            }
+        }
+    }
+
+    /// Unlink a queued process from the queue for the given priority
+    fn unlink(&mut self, processes: &mut ProcessTable, pid: PID, priority: usize)
+    {
+        let link = processes.get(&pid).unwrap().run_link;
+
+        if let Some(prev) = link.prev
+        {
+            processes.get_mut(&prev).unwrap().run_link.next = link.next;
+        }
+        else
+        {
+            self.heads[priority] = link.next;
+        }
+
+        if let Some(next) = link.next
+        {
+            processes.get_mut(&next).unwrap().run_link.prev = link.prev;
+        }
+        else
+        {
+            self.tails[priority] = link.prev;
+        }
+
+        if self.heads[priority].is_none()
+        {
+            self.bitmap &= !(1 << priority);
+        }
+
+        processes.get_mut(&pid).unwrap().run_link = RunQueueLink::new();
+    }
+}
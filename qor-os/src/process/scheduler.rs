@@ -1,20 +1,56 @@
 use crate::*;
 
+use super::itimer::IntervalTimer;
 use super::process::Process;
 use super::process::ProcessState;
-use super::signals::POSIXSignal;
+use super::signals::{POSIXSignal, SignalDisposition, SignalType};
 
-use alloc::collections::BTreeMap;
+use super::runqueue;
+use super::runqueue::RunQueue;
+
+use alloc::collections::{BTreeMap, BTreeSet};
+
+use drivers::timer::KernelTime;
 
 static mut GLOBAL_PROC_MANAGER: Option<ProcessManager> = None;
 
 use super::PID;
 
+/// Default limit on the number of processes in the system
+pub const DEFAULT_MAX_PROCESSES: usize = 1024;
+
+/// Smallest value the limit on the number of processes can be lowered to
+pub const MIN_MAX_PROCESSES: usize = 16;
+
+// Limit on the number of processes in the system, configured through
+// /sys/max-processes
+static mut MAX_PROCESSES: usize = DEFAULT_MAX_PROCESSES;
+
+/// Get the limit on the number of processes in the system
+pub fn max_processes() -> usize
+{
+    unsafe { MAX_PROCESSES }
+}
+
+/// Set the limit on the number of processes in the system, this can never
+/// be raised above the number of PIDs
+pub fn set_max_processes(count: usize)
+{
+    unsafe { MAX_PROCESSES = count.max(MIN_MAX_PROCESSES).min(PID::MAX as usize) };
+}
+
 /// Process Manager
 pub struct ProcessManager
 {
-    current_pid: Option<PID>,
-    max_pid: Option<PID>,
+    run_queue: RunQueue,
+    sleepers: BTreeSet<(KernelTime, PID)>,
+    timers: BTreeSet<(KernelTime, PID)>,
+    // Processes blocked in a syscall, which are checked on each timer tick
+    waiting: BTreeSet<PID>,
+    // Processes which an event was posted to, checked on the next pick
+    notified: BTreeSet<PID>,
+    // Set by the timer tick so the next pick checks the waiting processes
+    tick: bool,
     pub processes: BTreeMap<PID, Box<Process>>
 }
 
@@ -25,8 +61,12 @@ impl ProcessManager
     {
         Self
         {
-            current_pid: None,
-            max_pid: None,
+            run_queue: RunQueue::new(),
+            sleepers: BTreeSet::new(),
+            timers: BTreeSet::new(),
+            waiting: BTreeSet::new(),
+            notified: BTreeSet::new(),
+            tick: false,
             processes: BTreeMap::new()
         }
     }
@@ -36,16 +76,49 @@ impl ProcessManager
     {
         kdebugln!(Processes, "Adding process with PID {}", proc.pid);
 
-        if let Some(old_max) = self.max_pid
+        let pid = proc.pid;
+
+        self.processes.insert(pid, Box::new(proc));
+        self.run_queue.push_back(&mut self.processes, pid);
+    }
+
+    /// Have the next pick check a process, used by the sources of events
+    /// which change the state of a process, such as a signal being sent to it
+    pub fn notify(&mut self, pid: PID)
+    {
+        self.notified.insert(pid);
+    }
+
+    /// Have the next pick check every waiting process, called on each timer
+    /// tick
+    pub fn tick(&mut self)
+    {
+        self.tick = true;
+    }
+
+    /// Check that the given process may create another, returning `EAGAIN` if
+    /// this would exceed the system wide limit, or if its real user already
+    /// owns as many processes as its `RLIMIT_NPROC` limit. Zombies count
+    /// towards both, as they hold on to their PID until they are reaped.
+    pub fn check_process_limits(&self, proc: &Process) -> Result<(), usize>
+    {
+        let count = self.processes.len();
+
+        if count >= max_processes()
         {
-            self.max_pid = Some(old_max.max(proc.pid));
+            kwarnln!("Process limit of {} reached, refusing fork from PID {}", max_processes(), proc.pid);
+            return Err(errno::EAGAIN);
         }
-        else
+
+        let user = proc.data.credentials.user.real;
+        let owned = self.processes.values().filter(|other| other.data.credentials.user.real == user).count();
+
+        if owned as u64 >= proc.data.limits.soft(super::rlimit::RLIMIT_NPROC)
         {
-            self.max_pid = Some(proc.pid);
+            return Err(errno::EAGAIN);
         }
 
-        self.processes.insert(proc.pid, Box::new(proc));
+        Ok(())
     }
 
     /// Replace a process
@@ -56,6 +129,32 @@ impl ProcessManager
 
         proc.pid = pid;
 
+        // Keep the place of the process in the run queue, along with any
+        // signals which are still pending
+        let old = self.processes.get_mut(&pid).unwrap();
+        proc.run_link = old.run_link;
+        proc.pending_signals = core::mem::take(&mut old.pending_signals);
+
+        // Handlers do not survive the exec, but ignored signals and the
+        // signal mask do
+        for (sig, disposition) in &old.data.signal_map
+        {
+            if *disposition == SignalDisposition::Ignore
+            {
+                proc.data.signal_map.insert(*sig, SignalDisposition::Ignore);
+            }
+        }
+
+        proc.data.signal_mask = old.data.signal_mask;
+
+        // The process keeps its start time, the time it has used and its
+        // place in the process tree across the exec
+        proc.data.start_time = old.data.start_time;
+        proc.data.cpu_times = old.data.cpu_times;
+        proc.data.real_timer = old.data.real_timer;
+        proc.data.parent_pid = old.data.parent_pid;
+        proc.data.children = core::mem::take(&mut old.data.children);
+
         self.processes.insert(pid, Box::new(proc));
     }
 
@@ -74,7 +173,7 @@ impl ProcessManager
     /// Get a reference to the currently running process
     pub fn currently_running(&self) -> Option<&Box<Process>>
     {
-        if let Some(pid) = self.current_pid
+        if let Some(pid) = percpu!(current_pid)
         {
             self.get_process_by_pid(pid)
         }
@@ -87,7 +186,7 @@ impl ProcessManager
     /// Get a mutable reference to the currently running process
     pub fn currently_running_mut(&mut self) -> Option<&mut Box<Process>>
     {
-        if let Some(pid) = self.current_pid
+        if let Some(pid) = percpu!(current_pid)
         {
             self.get_process_by_pid_mut(pid)
         }
@@ -108,7 +207,8 @@ impl ProcessManager
     /// Schedule the given pid
     pub fn schedule_pid(&mut self, pid: PID) -> (usize, usize, usize)
     {
-        self.current_pid = Some(pid);
+        percpu!(current_pid = Some(pid));
+        super::stats::restart_sampling();
 
         kdebugln!(Scheduling, "Scheduling PID {}", pid);
 
@@ -118,155 +218,371 @@ impl ProcessManager
     /// Schedule the next process by returning a pid
     pub fn pid_of_next(&mut self) -> PID
     {
-        if let Some(pid) = self.current_pid
+        if let Some(current) = percpu!(current_pid)
         {
-            let highest = self.max_pid.unwrap();
+            loop
+            {
+                // A process which blocked is checked on each tick until it
+                // can run again
+                if self.get_process_by_pid(current).map(|proc| proc.get_state() != ProcessState::Running).unwrap_or(false)
+                {
+                    self.waiting.insert(current);
+                }
 
-            let mut step_pid = pid;
+                // Check the process which was running, as it may have changed
+                // its signal mask, the processes which events were posted to,
+                // and on a tick every waiting process, a process which is
+                // switching to a signal handler is run immediately
+                let mut pids = core::mem::take(&mut self.notified);
+                pids.insert(current);
 
-            let mut stopped_pid = None;
+                if core::mem::take(&mut self.tick)
+                {
+                    pids.extend(self.waiting.iter());
+                }
 
-            loop
-            {
-                // Increment and wrap back to zero
-                step_pid = (step_pid + 1) % (highest + 1);
+                if let Some(pid) = self.wake_processes(current, pids)
+                {
+                    if pid != current && self.get_process_by_pid(current).map(|proc| proc.get_state() == ProcessState::Running).unwrap_or(false)
+                    {
+                        self.run_queue.push_back(&mut self.processes, current);
+                    }
 
-                let mut children = None;
-                let mut adoption_data: Option<(PID, Vec<PID>)> = None;
+                    return pid;
+                }
 
-                // Check the current step_pid
-                if let Some(proc) = self.get_process_by_pid_mut(step_pid)
+                // The process which was running goes to the back of its queue
+                if self.get_process_by_pid(current).map(|proc| proc.get_state() == ProcessState::Running).unwrap_or(false)
                 {
-                    if proc.get_state() != ProcessState::Dead && proc.get_state() != ProcessState::Zombie
+                    self.run_queue.push_back(&mut self.processes, current);
+                }
+
+                // Take the first process which is still runnable, anything which
+                // stopped running while queued will be requeued once it wakes
+                while let Some(pid) = self.run_queue.pop_front(&mut self.processes)
+                {
+                    if self.get_process_by_pid(pid).map(|proc| proc.get_state() == ProcessState::Running).unwrap_or(false)
                     {
-                        if let Some(sig) = proc.pop_signal()
-                        {
-                            if proc.get_state() == ProcessState::Waiting(process::process::WaitMode::ForSignal)
-                            {
-                                proc.state = ProcessState::Running;
-                            }
+                        return pid;
+                    }
+                }
 
-                            if proc.trigger_signal(sig)
-                            {
-                                return proc.pid;
-                            }
-                        }
+                // Nothing is runnable, keep the shutdown moving while waiting
+                // for the remaining processes to exit, the timer interrupt
+                // cannot fire here so sleepers are woken, timers expired and
+                // the waiting processes checked directly
+                halt::shutdown_tick();
+
+                let now = unsafe { &drivers::TIMER_DRIVER }.time();
+                self.wake_sleepers(now);
+                self.expire_timers(now);
+                self.tick = true;
+            }
+        }
+        // If this is the first scheduling, schedule the init process
+        else
+        {
+            // Ensure the 0 process exists, otherwise panic
+            if !self.processes.contains_key(&0)
+            {
+                panic!("No Processes Initialized");
+            }
+            0
+        }
+    }
+
+    /// Deliver pending signals to the given processes, check those which are
+    /// waiting and queue any which can run again, returning the pid of a
+    /// process which must be run immediately to handle a signal. Processes
+    /// which are still blocked stay on the waiting list.
+    fn wake_processes(&mut self, current: PID, pids: BTreeSet<PID>) -> Option<PID>
+    {
+        let mut remaining = pids.into_iter();
+
+        while let Some(step_pid) = remaining.next()
+        {
+            let mut child_event = None;
+            let mut adoption_data: Option<(PID, Vec<PID>)> = None;
+            let mut run_now = false;
+            let mut terminal_wake = false;
+            let mut more_signals = false;
+
+            // Check the current step_pid
+            if let Some(proc) = self.get_process_by_pid_mut(step_pid)
+            {
+                if proc.get_state() != ProcessState::Dead && proc.get_state() != ProcessState::Zombie
+                {
+                    // A process waiting for a signal is only woken by a
+                    // handler, which interrupts the wait
+                    if let Some(sig) = proc.pop_signal()
+                    {
+                        run_now = proc.trigger_signal(sig);
+                        more_signals = proc.has_pending_signals();
                     }
+                }
 
-                    match proc.get_state()
+                match proc.get_state()
+                {
+                    // Running processes are already queued
+                    ProcessState::Running => {},
+                    // If the process is waiting, perform the proper wait checks
+                    ProcessState::Waiting(mode) =>
                     {
-                        // If the process is running, switch to it
-                        ProcessState::Running => 
+                        match mode
                         {
-                            break;
-                        },
-                        // If the process is waiting, perform the proper wait checks
-                        ProcessState::Waiting(mode) =>
-                        {
-                            match mode
+                            process::process::WaitMode::ForIO((fd, count, buffer)) =>
                             {
-                                process::process::WaitMode::ForChild => 
-                                {
-                                    children = Some(proc.get_children().clone());
-                                },
-                                process::process::WaitMode::ForIO((fd, count, buffer)) =>
+                                if proc.check_available(fd)
                                 {
-                                    if proc.check_available(fd)
-                                    {
-                                        let length = proc.read(fd, buffer, count);
-                                        unsafe { proc.frame.as_mut().unwrap().regs[10] = length; }
+                                    let length = proc.read_user(fd, buffer, count);
+                                    unsafe { proc.frame.as_mut().unwrap().regs[10] = length; }
 
-                                        break;
-                                    }
+                                    proc.state = ProcessState::Running;
+                                    terminal_wake = proc.is_foreground_terminal(fd);
                                 }
-                                process::process::WaitMode::ForSignal => {},
                             }
-                            
-                        },
-                        // If it is asleep, check if it hsould be woken up
-                        ProcessState::Sleeping {wake_time } =>
-                        {
-                            // Check if the wake time has passed by checking the timer driver
-                            let current = unsafe { &drivers::TIMER_DRIVER }.time();
-
-                            // If so, switch the process to the Running state and switch to it
-                            if current > wake_time
+                            process::process::WaitMode::ForChildEvent(request) =>
                             {
-                                break;
-                            }
-                        },
-                        // If the process is a zombie or stopped, ignore it
-                        ProcessState::Zombie | ProcessState::Stopped => {},
-                        // If it is dead, remove it from the process tree
-                        ProcessState::Dead => 
-                        {
-                            kdebugln!(Processes, "Cleaning Up PID {}", step_pid);
-                            adoption_data = Some((proc.data.parent_pid, proc.data.children.clone()));
-                            self.processes.remove(&step_pid);
+                                child_event = Some(request);
+                            },
+                            process::process::WaitMode::ForPoll(request) =>
+                            {
+                                let now = unsafe { &drivers::TIMER_DRIVER }.time();
+
+                                if let Some(result) = super::poll::check_poll(proc, &request, now)
+                                {
+                                    unsafe { proc.frame.as_mut().unwrap() }.regs[10] = syscalls::flatten_syscall_result(result);
+                                    proc.state = ProcessState::Running;
+                                    terminal_wake = proc.has_terminal_input();
+                                }
+                            },
+                            process::process::WaitMode::ForSignal => {},
                         }
+                        
+                    },
+                    // Sleeping processes are woken by the timer interrupt
+                    ProcessState::Sleeping { .. } => {},
+                    // If the process is a zombie or stopped, ignore it
+                    ProcessState::Zombie | ProcessState::Stopped => {},
+                    // If it is dead, remove it from the process tree
+                    ProcessState::Dead => 
+                    {
+                        kdebugln!(Processes, "Cleaning Up PID {}", step_pid);
+                        adoption_data = Some((proc.data.parent_pid, proc.data.children.clone()));
                     }
                 }
+            }
 
-                // If this process is waiting
-                if let Some(children) = children
-                {
-                    for child in children
-                    {
-                        if let Some(child_proc) = self.get_process_by_pid_mut(child)
-                        {
-                            if child_proc.wait_check()
-                            {
-                                stopped_pid = Some(child);
-                                break;
-                            }
-                        }
-                    }
+            // Processes in the foreground of a terminal woken by its input are
+            // raised above the processes using up their time slices, so
+            // interactive programs respond promptly
+            if terminal_wake && runqueue::tty_wake_boost() > 0
+            {
+                self.run_queue.remove(&mut self.processes, step_pid);
+                self.get_process_by_pid_mut(step_pid).unwrap().run_link.boost = runqueue::tty_wake_boost();
+            }
 
-                    if let Some(stopped) = stopped_pid
-                    {
-                        let exit_code = self.get_process_by_pid(stopped).unwrap().exit_code;
-                        if let Some(return_code_listener) = self.get_process_by_pid_mut(step_pid).unwrap().data.return_code_listener.as_mut()
-                        {
-                            **return_code_listener = exit_code;
-                        }
+            // One signal is delivered each time a process is checked
+            if more_signals
+            {
+                self.notify(step_pid);
+            }
 
-                        self.get_process_by_pid_mut(step_pid).unwrap().remove_child(stopped);
-                        unsafe { self.get_process_by_pid_mut(step_pid).unwrap().frame.as_mut().unwrap() }.regs[10] = stopped as usize;
-                        self.get_process_by_pid_mut(step_pid).unwrap().state = ProcessState::Running;
-                        break;
-                    }
+            if run_now
+            {
+                self.run_queue.remove(&mut self.processes, step_pid);
+                self.waiting.remove(&step_pid);
+
+                // The processes which were not reached are checked on the
+                // next pick
+                self.notified.extend(remaining);
+
+                return Some(step_pid);
+            }
+
+            // If this process is waiting on a state change of a child
+            if let Some(request) = child_event
+            {
+                let result = super::wait::poll_child_event(self, step_pid, &request);
+                let proc = self.get_process_by_pid_mut(step_pid).unwrap();
+
+                let value = match result
+                {
+                    Ok(Some(info)) => Some(super::wait::report_child_event(proc, &request, &info)),
+                    Ok(None) => None,
+                    Err(e) => Some(Err(e))
+                };
+
+                if let Some(value) = value
+                {
+                    unsafe { proc.frame.as_mut().unwrap() }.regs[10] = syscalls::flatten_syscall_result(value);
+                    proc.state = ProcessState::Running;
                 }
+            }
+
+            // If data needs to be adopted
+            if let Some((pid, data)) = adoption_data
+            {
+                self.run_queue.remove(&mut self.processes, step_pid);
+                self.processes.remove(&step_pid);
 
-                // If data needs to be adopted
-                if let Some((pid, data)) = adoption_data
+                if let Some(r) = self.get_process_by_pid_mut(pid)
                 {
-                    if let Some(r) = self.get_process_by_pid_mut(pid)
+                    for cpid in data
                     {
-                        for cpid in data
+                        if !r.data.children.contains(&cpid)
                         {
-                            if !r.data.children.contains(&cpid)
-                            {
-                                r.register_child(cpid);
-                            }
+                            r.register_child(cpid);
                         }
                     }
                 }
             }
 
-            // If the process was woken up or sleeping, make sure it is running now
-            self.get_process_by_pid_mut(step_pid).unwrap().state = ProcessState::Running;
+            // Queue the process if it can run, the current process is queued
+            // after everything else so it does not jump ahead of the others
+            if step_pid != current && self.get_process_by_pid(step_pid).map(|proc| proc.get_state() == ProcessState::Running).unwrap_or(false)
+            {
+                self.run_queue.push_back(&mut self.processes, step_pid);
+            }
 
-            step_pid
+            if !self.get_process_by_pid(step_pid).map(|proc| matches!(proc.get_state(), ProcessState::Waiting(_))).unwrap_or(false)
+            {
+                self.waiting.remove(&step_pid);
+            }
         }
-        // If this is the first scheduling, schedule the init process
-        else
+
+        None
+    }
+
+    /// Put a process to sleep until the given time
+    pub fn sleep_until(&mut self, pid: PID, wake_time: KernelTime)
+    {
+        if let Some(proc) = self.get_process_by_pid_mut(pid)
         {
-            // Ensure the 0 process exists, otherwise panic
-            if !self.processes.contains_key(&0)
+            proc.state = ProcessState::Sleeping { wake_time };
+            self.sleepers.insert((wake_time, pid));
+        }
+    }
+
+    /// Wake every process whose wake time has passed, entries for processes
+    /// which were already woken by a signal or have exited are dropped
+    pub fn wake_sleepers(&mut self, now: KernelTime)
+    {
+        while let Some((wake_time, pid)) = self.sleepers.first().cloned()
+        {
+            if wake_time > now
             {
-                panic!("No Processes Initialized");
+                break;
+            }
+
+            self.sleepers.pop_first();
+
+            if let Some(proc) = self.get_process_by_pid_mut(pid)
+            {
+                if proc.get_state() == (ProcessState::Sleeping { wake_time })
+                {
+                    kdebugln!(Scheduling, "Waking PID {}", pid);
+
+                    proc.state = ProcessState::Running;
+                    self.run_queue.push_back(&mut self.processes, pid);
+                }
             }
-            0
+        }
+    }
+
+    /// Arm the real time interval timer of a process, or disarm it with a
+    /// value of zero, returning the timer as it was before
+    pub fn set_real_timer(&mut self, pid: PID, value: KernelTime, interval: KernelTime) -> Option<IntervalTimer>
+    {
+        let now = unsafe { &drivers::TIMER_DRIVER }.time();
+        let proc = self.get_process_by_pid_mut(pid)?;
+
+        let old = proc.data.real_timer;
+        proc.data.real_timer.set(now, value, interval);
+
+        if let Some(expires) = proc.data.real_timer.expires
+        {
+            self.timers.insert((expires, pid));
+        }
+
+        Some(old)
+    }
+
+    /// Send `SIGALRM` to every process whose real time interval timer has
+    /// expired, entries for timers which were since changed or for processes
+    /// which have exited are dropped
+    pub fn expire_timers(&mut self, now: KernelTime)
+    {
+        while let Some((expires, pid)) = self.timers.first().cloned()
+        {
+            if expires > now
+            {
+                break;
+            }
+
+            self.timers.pop_first();
+
+            let reload = match self.get_process_by_pid_mut(pid)
+            {
+                Some(proc) if proc.data.real_timer.expires == Some(expires) =>
+                {
+                    proc.data.real_timer.reload(now);
+                    proc.data.real_timer.expires
+                },
+                _ => continue
+            };
+
+            kdebugln!(Signals, "Interval timer of PID {} expired", pid);
+
+            // A process which can no longer take the signal has its timer
+            // disarmed, so it is not reported as still running
+            if self.send_signal(pid, POSIXSignal::new(pid, 0, SignalType::SIGALRM)).is_err()
+            {
+                if let Some(proc) = self.get_process_by_pid_mut(pid)
+                {
+                    proc.data.real_timer = IntervalTimer::new();
+                }
+
+                continue;
+            }
+
+            if let Some(next) = reload
+            {
+                self.timers.insert((next, pid));
+            }
+        }
+    }
+
+    /// Get the earliest time a sleeping process needs to be woken or an
+    /// interval timer expires
+    pub fn next_wakeup(&self) -> Option<KernelTime>
+    {
+        let sleeper = self.sleepers.first().map(|(wake_time, _)| *wake_time);
+        let timer = self.timers.first().map(|(expires, _)| *expires);
+
+        match (sleeper, timer)
+        {
+            (Some(sleeper), Some(timer)) => Some(sleeper.min(timer)),
+            (sleeper, timer) => sleeper.or(timer)
+        }
+    }
+
+    /// Change the priority of a process, moving it to the queue for its new
+    /// priority if it is queued
+    pub fn set_priority(&mut self, pid: PID, priority: usize)
+    {
+        let queued = self.get_process_by_pid(pid).map(|proc| proc.run_link.queued).unwrap_or(false);
+
+        self.run_queue.remove(&mut self.processes, pid);
+
+        if let Some(proc) = self.get_process_by_pid_mut(pid)
+        {
+            proc.data.priority = priority.min(runqueue::NUM_PRIORITIES - 1);
+        }
+
+        if queued
+        {
+            self.run_queue.push_back(&mut self.processes, pid);
         }
     }
 
@@ -292,34 +608,44 @@ impl ProcessManager
 
         if let Some(proc) = self.get_process_by_pid_mut(dest_pid)
         {
-            proc.push_signal(signal)?;
-        }
+            if proc.get_state() == ProcessState::Zombie || proc.get_state() == ProcessState::Dead
+            {
+                return Err(());
+            }
 
-        Ok(())
+            proc.push_signal(signal);
+            self.notify(dest_pid);
+
+            Ok(())
+        }
+        else
+        {
+            Err(())
+        }
     }
 
     /// Send a signal to the parent process' group
     pub fn send_signal_group(&mut self, group: PID, source_pid: PID, signal: POSIXSignal) -> Result<(), ()>
     {
         kdebugln!(Signals, "Sending Signal {:?} to Group {}", signal.sig_type, group);
-        let mut pids = Vec::new();
 
-        for proc in self.processes.values_mut()
-        {
-            if proc.pid != source_pid && proc.data.process_group_id == group
-            {
-                pids.push(proc.pid);
-            }
-        }
+        let pids: Vec<PID> = self.processes.values()
+            .filter(|proc| proc.pid != source_pid && proc.data.process_group_id == group)
+            .map(|proc| proc.pid)
+            .collect();
+
+        // Processes in the group which have already exited are skipped, the
+        // send only fails if no process recieved the signal
+        let mut sent = false;
 
         for pid in pids
         {
             let mut s = signal.clone();
             s.dest_pid = pid;
-            self.send_signal(pid, s)?;
+            sent |= self.send_signal(pid, s).is_ok();
         }
 
-        Ok(())
+        if sent { Ok(()) } else { Err(()) }
     }
 }
 
@@ -332,7 +658,7 @@ pub fn init_process_manager()
     }
 
     // Add the init process
-    let process = super::process::Process::from_fn_ptr(super::init::init_proc);
+    let process = super::process::Process::from_code(super::init::init_code());
     add_process(process);
 }
 
@@ -372,6 +698,30 @@ pub fn replace_process(pid: PID, proc: Process)
     }
 }
 
+/// Kill every process running in the address space owned by the given
+/// process, with the same exit value
+pub fn kill_memory_sharers(owner: PID, value: usize)
+{
+    if let Some(manager) = get_process_manager()
+    {
+        for proc in manager.processes.values_mut()
+        {
+            if proc.data.memory_owner == Some(owner) && !matches!(proc.get_state(), ProcessState::Zombie | ProcessState::Dead)
+            {
+                proc.kill(value);
+            }
+        }
+    }
+}
+
+/// Check that the given process may create another
+pub fn check_process_limits(proc: &Process) -> Result<(), usize>
+{
+    unsafe 
+    {
+        GLOBAL_PROC_MANAGER.as_ref().unwrap().check_process_limits(proc)
+    }
+}
 
 /// Get the current process
 pub fn current_process() -> Option<&'static mut Box<Process>>
@@ -391,6 +741,21 @@ pub fn schedule_next() -> (usize, usize, usize)
     }
 }
 
+/// Wake any sleeping processes whose wake time has passed, signal any
+/// processes whose interval timer has expired, and have the next pick check
+/// the processes which are waiting
+pub fn wake_sleepers()
+{
+    if let Some(manager) = get_process_manager()
+    {
+        let now = unsafe { &drivers::TIMER_DRIVER }.time();
+
+        manager.wake_sleepers(now);
+        manager.expire_timers(now);
+        manager.tick();
+    }
+}
+
 /// Get a reference to the process manager
 pub fn get_process_manager() -> Option<&'static mut ProcessManager>
 {
@@ -412,16 +777,27 @@ extern "C"
 /// Jump into the process
 pub fn schedule_jump(data: (usize, usize, usize)) -> !
 {
+    perf::record_since_trap_entry(perf::Measurement::ContextSwitch);
+    perf::clear_trap_entry();
+
+    percpu::this_cpu().counters.context_switches += 1;
+
+    // The trap being handled will never be returned from
+    trap::reset_trap_depth();
+
     unsafe { switch_to_user(data.0, data.1, data.2) }
 }
 
 extern "C"
 {
-    pub fn asm_wait_for_int() -> !;
+    pub fn asm_wait_for_int(satp: usize) -> !;
 }
 
 /// Wait until an interrupt is recieved
 pub fn wait_for_int() -> !
 {
-    unsafe { asm_wait_for_int() }
+    perf::clear_trap_entry();
+    trap::reset_trap_depth();
+
+    unsafe { asm_wait_for_int(mem::kernel_satp()) }
 }
\ No newline at end of file
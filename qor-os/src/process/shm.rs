@@ -0,0 +1,248 @@
+//! Shared Memory Objects
+//!
+//! Files which only exist in memory, created by memfd_create. The pages of an
+//! object are allocated when they are first written or mapped and stay at the
+//! same physical address for as long as the object exists, so shared mappings
+//! of the object in different processes all see the same memory. The pages
+//! are freed once the last descriptor and mapping referring to the object are
+//! gone.
+
+use crate::*;
+
+use alloc::collections::BTreeMap;
+use alloc::sync::Arc;
+use core::cell::RefCell;
+
+use fs::structures::FilesystemIndex;
+
+use super::descriptor::{seek_position, FileDescriptor, SeekMode, O_APPEND, O_RDONLY, O_WRONLY};
+
+/// Shared reference to a shared memory object
+pub type SharedMemory = Arc<RefCell<SharedMemoryObject>>;
+
+/// Shared Memory Object
+pub struct SharedMemoryObject
+{
+    name: String,
+    size: usize,
+    pages: BTreeMap<usize, usize>
+}
+
+impl SharedMemoryObject
+{
+    /// Create an empty shared memory object
+    pub fn new(name: String) -> SharedMemory
+    {
+        Arc::new(RefCell::new(Self
+        {
+            name,
+            size: 0,
+            pages: BTreeMap::new()
+        }))
+    }
+
+    /// Get the name the object was created with, which is only used to tell
+    /// objects apart when debugging
+    pub fn name(&self) -> &str
+    {
+        &self.name
+    }
+
+    /// Get the length of the object in bytes
+    pub fn size(&self) -> usize
+    {
+        self.size
+    }
+
+    /// Get the physical address of a page of the object, allocating it if it
+    /// has not been used yet
+    pub fn page(&mut self, index: usize) -> Result<usize, usize>
+    {
+        if let Some(page) = self.pages.get(&index)
+        {
+            return Ok(*page);
+        }
+
+        let page = mem::kpzalloc(1, "Shared Memory").map_err(|_| errno::ENOMEM)?;
+        self.pages.insert(index, page);
+
+        Ok(page)
+    }
+
+    /// Read from the object at an offset, pages which have not been used yet
+    /// read as zeros
+    pub fn read(&self, offset: usize, buffer: *mut u8, count: usize) -> usize
+    {
+        let count = count.min(self.size.saturating_sub(offset));
+
+        for i in 0..count
+        {
+            let position = offset + i;
+
+            let byte = self.pages.get(&(position / mem::PAGE_SIZE))
+                .map(|page| unsafe { ((page + position % mem::PAGE_SIZE) as *const u8).read() })
+                .unwrap_or(0);
+
+            unsafe { buffer.add(i).write(byte) };
+        }
+
+        count
+    }
+
+    /// Write to the object at an offset, extending it if the write goes past
+    /// the end
+    pub fn write(&mut self, offset: usize, buffer: *mut u8, count: usize) -> usize
+    {
+        for i in 0..count
+        {
+            let position = offset + i;
+
+            let page = match self.page(position / mem::PAGE_SIZE)
+            {
+                Ok(page) => page,
+                Err(e) if i == 0 => return e,
+                Err(_) => return i
+            };
+
+            unsafe { ((page + position % mem::PAGE_SIZE) as *mut u8).write(buffer.add(i).read()) };
+
+            self.size = self.size.max(position + 1);
+        }
+
+        count
+    }
+
+    /// Change the length of the object. Pages cut off stay allocated, as they
+    /// may still be mapped, but are cleared so the object reads as zeros if
+    /// it is extended again.
+    pub fn truncate(&mut self, length: usize)
+    {
+        if length < self.size
+        {
+            for (index, page) in self.pages.range((length / mem::PAGE_SIZE)..)
+            {
+                let start = length.max(index * mem::PAGE_SIZE) - index * mem::PAGE_SIZE;
+
+                unsafe { core::ptr::write_bytes((page + start) as *mut u8, 0, mem::PAGE_SIZE - start) };
+            }
+        }
+
+        self.size = length;
+    }
+}
+
+impl core::ops::Drop for SharedMemoryObject
+{
+    fn drop(&mut self)
+    {
+        for page in self.pages.values()
+        {
+            mem::kpzfree(*page, 1).unwrap();
+        }
+    }
+}
+
+/// Descriptor of an open shared memory object
+pub struct SharedMemoryDescriptor
+{
+    object: SharedMemory,
+    index: usize,
+    status_flags: usize
+}
+
+impl SharedMemoryDescriptor
+{
+    /// Open a shared memory object for reading and writing
+    pub fn new(object: SharedMemory) -> Self
+    {
+        Self
+        {
+            object,
+            index: 0,
+            status_flags: O_RDONLY | O_WRONLY
+        }
+    }
+}
+
+impl FileDescriptor for SharedMemoryDescriptor
+{
+    fn close(&mut self, _fs: &mut fs::vfs::FilesystemInterface)
+    {
+        // The object is freed once the last reference to it is dropped
+    }
+
+    fn write(&mut self, fs: &mut fs::vfs::FilesystemInterface, buffer: *mut u8, count: usize) -> usize
+    {
+        if self.status_flags & O_APPEND > 0
+        {
+            self.index = self.object.borrow().size();
+        }
+
+        let result = self.write_at(fs, buffer, count, self.index);
+
+        if (result as isize) > 0
+        {
+            self.index += result;
+        }
+
+        result
+    }
+
+    fn read(&mut self, fs: &mut fs::vfs::FilesystemInterface, buffer: *mut u8, count: usize) -> usize
+    {
+        let result = self.read_at(fs, buffer, count, self.index);
+        self.index += result;
+
+        result
+    }
+
+    fn read_at(&mut self, _fs: &mut fs::vfs::FilesystemInterface, buffer: *mut u8, count: usize, offset: usize) -> usize
+    {
+        self.object.borrow().read(offset, buffer, count)
+    }
+
+    fn write_at(&mut self, _fs: &mut fs::vfs::FilesystemInterface, buffer: *mut u8, count: usize, offset: usize) -> usize
+    {
+        self.object.borrow_mut().write(offset, buffer, count)
+    }
+
+    fn seek(&mut self, offset: usize, mode: SeekMode) -> usize
+    {
+        match seek_position(self.index, self.object.borrow().size(), offset, mode)
+        {
+            Some(index) =>
+            {
+                self.index = index;
+                self.index
+            },
+            None => errno::EINVAL
+        }
+    }
+
+    fn truncate(&mut self, _fs: &mut fs::vfs::FilesystemInterface, length: usize) -> Result<(), usize>
+    {
+        self.object.borrow_mut().truncate(length);
+
+        Ok(())
+    }
+
+    fn get_inode(&mut self) -> Option<FilesystemIndex>
+    {
+        None
+    }
+
+    fn get_shared_memory(&self) -> Option<SharedMemory>
+    {
+        Some(self.object.clone())
+    }
+
+    fn get_status_flags(&self) -> usize
+    {
+        self.status_flags
+    }
+
+    fn set_status_flags(&mut self, flags: usize)
+    {
+        self.status_flags = flags;
+    }
+}
@@ -0,0 +1,89 @@
+//! Signal Descriptors
+//!
+//! Descriptors made by signalfd read the signals pending on the process
+//! which reads them, rather than having them delivered, as records in the
+//! layout of `signalfd_siginfo`. The signals are normally blocked with
+//! sigprocmask first, otherwise they are delivered as usual before they can
+//! be read. The descriptor only holds the set of signals, taking and
+//! checking for signals is done by the process, as a descriptor shared over
+//! fork reads the signals of whichever process uses it.
+
+use crate::*;
+
+use fs::structures::FilesystemIndex;
+
+use super::descriptor::{FileDescriptor, O_RDONLY};
+use super::signals::SignalType;
+
+/// Descriptor reading a set of signals
+pub struct SignalDescriptor
+{
+    mask: u64,
+    status_flags: usize
+}
+
+impl SignalDescriptor
+{
+    /// Create a descriptor reading the given set of signals
+    pub fn new(mask: u64) -> Self
+    {
+        Self
+        {
+            mask: SignalType::sanitize_mask(mask),
+            status_flags: O_RDONLY
+        }
+    }
+}
+
+impl FileDescriptor for SignalDescriptor
+{
+    fn close(&mut self, _fs: &mut fs::vfs::FilesystemInterface)
+    {
+        // Nothing is held outside of the descriptor
+    }
+
+    fn write(&mut self, _fs: &mut fs::vfs::FilesystemInterface, _buffer: *mut u8, _count: usize) -> usize
+    {
+        errno::EINVAL
+    }
+
+    /// Signals are read by the process through `Process::read_signals`, as
+    /// the descriptor does not know which process is reading it
+    fn read(&mut self, _fs: &mut fs::vfs::FilesystemInterface, _buffer: *mut u8, _count: usize) -> usize
+    {
+        errno::EINVAL
+    }
+
+    fn get_inode(&mut self) -> Option<FilesystemIndex>
+    {
+        None
+    }
+
+    fn check_writable(&self) -> bool
+    {
+        false
+    }
+
+    fn get_status_flags(&self) -> usize
+    {
+        self.status_flags
+    }
+
+    fn set_status_flags(&mut self, flags: usize)
+    {
+        self.status_flags = flags;
+    }
+
+    fn get_signal_mask(&self) -> Option<u64>
+    {
+        Some(self.mask)
+    }
+
+    /// SIGKILL and SIGSTOP are dropped from the set, as with sigprocmask
+    fn set_signal_mask(&mut self, mask: u64) -> Result<(), usize>
+    {
+        self.mask = SignalType::sanitize_mask(mask);
+
+        Ok(())
+    }
+}
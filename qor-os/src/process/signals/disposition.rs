@@ -1,3 +1,5 @@
+use super::structs::SignalAction;
+
 /// Signal Dispositions (default behavior if no handler has been created)
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SignalDisposition
@@ -7,5 +9,5 @@ pub enum SignalDisposition
     Core,
     Stop,
     Continue,
-    Handler(usize)
+    Handler(SignalAction)
 }
\ No newline at end of file
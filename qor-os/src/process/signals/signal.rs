@@ -1,4 +1,5 @@
 use super::structs::*;
+use super::disposition::SignalDisposition;
 
 use super::super::PID;
 
@@ -7,30 +8,78 @@ use super::super::PID;
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum SignalType
 {
+    SIGHUP = 1,
+    SIGINT = 2,
+    SIGQUIT = 3,
+    SIGILL = 4,
     SIGTRAP = 5,
+    SIGABRT = 6,
+    SIGBUS = 7,
+    SIGFPE = 8,
+    SIGKILL = 9,
+    SIGUSR1 = 10,
+    SIGSEGV = 11,
+    SIGUSR2 = 12,
+    SIGPIPE = 13,
+    SIGALRM = 14,
     SIGTERM = 15,
-    SIGSTOP = 19,
+    SIGCHLD = 17,
     SIGCONT = 18,
-    SIGKILL = 9,
-    SIGINT = 2
+    SIGSTOP = 19,
+    SIGTSTP = 20,
+    SIGTTIN = 21,
+    SIGTTOU = 22,
+    SIGURG = 23,
+    SIGWINCH = 28
 }
 
 impl SignalType
 {
+    /// Every signal type, in order of signal number
+    pub const ALL: [SignalType; 23] = [
+        Self::SIGHUP, Self::SIGINT, Self::SIGQUIT, Self::SIGILL, Self::SIGTRAP, Self::SIGABRT,
+        Self::SIGBUS, Self::SIGFPE, Self::SIGKILL, Self::SIGUSR1, Self::SIGSEGV, Self::SIGUSR2,
+        Self::SIGPIPE, Self::SIGALRM, Self::SIGTERM, Self::SIGCHLD, Self::SIGCONT, Self::SIGSTOP,
+        Self::SIGTSTP, Self::SIGTTIN, Self::SIGTTOU, Self::SIGURG, Self::SIGWINCH];
+
     /// Convert a number to a signal type
-    pub fn number_to_signal(num: usize) -> Self
+    pub fn from_number(num: usize) -> Option<Self>
+    {
+        Self::ALL.iter().find(|sig| **sig as usize == num).cloned()
+    }
+
+    /// Get the action taken for the signal when no handler is installed
+    pub fn default_disposition(&self) -> SignalDisposition
     {
-        match num
+        match self
         {
-            2 => Self::SIGINT,
-            5 => Self::SIGTRAP,
-            9 => Self::SIGKILL,
-            15 => Self::SIGTERM,
-            18 => Self::SIGCONT,
-            19 => Self::SIGSTOP,
-            default => panic!("Bad signal number {}", default)
+            Self::SIGQUIT | Self::SIGILL | Self::SIGTRAP | Self::SIGABRT |
+            Self::SIGBUS | Self::SIGFPE | Self::SIGSEGV => SignalDisposition::Core,
+            Self::SIGCHLD | Self::SIGURG | Self::SIGWINCH => SignalDisposition::Ignore,
+            Self::SIGSTOP | Self::SIGTSTP | Self::SIGTTIN | Self::SIGTTOU => SignalDisposition::Stop,
+            Self::SIGCONT => SignalDisposition::Continue,
+            _ => SignalDisposition::Terminate
         }
     }
+
+    /// Check if the disposition of the signal can be changed, SIGKILL and
+    /// SIGSTOP always take their default action
+    pub fn can_be_caught(&self) -> bool
+    {
+        !matches!(self, Self::SIGKILL | Self::SIGSTOP)
+    }
+
+    /// Get the bit representing the signal in a signal mask
+    pub fn mask_bit(&self) -> u64
+    {
+        1 << (*self as u64 - 1)
+    }
+
+    /// Remove the signals which cannot be blocked from a signal mask
+    pub fn sanitize_mask(mask: u64) -> u64
+    {
+        mask & !(Self::SIGKILL.mask_bit() | Self::SIGSTOP.mask_bit())
+    }
 }
 
 /// POSIX Signal
@@ -72,4 +121,30 @@ impl POSIXSignal
             value: SignalValue { integer: 0 },
         }
     }
-}
\ No newline at end of file
+
+    /// Convert to the record read from a signalfd
+    pub fn to_signalfd_info(&self) -> SignalFdInfo
+    {
+        SignalFdInfo
+        {
+            signal_number: self.sig_type as u16 as u32,
+            error: 0,
+            code: 0,
+            pid: self.sending_pid as u32,
+            uid: 0,
+            fd: 0,
+            timer: 0,
+            band: 0,
+            overrun: 0,
+            trap: 0,
+            status: 0,
+            integer: 0,
+            ptr: 0,
+            utime: 0,
+            stime: 0,
+            addr: 0,
+            addr_lsb: 0,
+            padding: [0; 46]
+        }
+    }
+}
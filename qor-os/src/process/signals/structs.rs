@@ -1,5 +1,14 @@
 use super::super::PID;
 
+// Must be kept in sync with syscalls.h
+pub const SIG_DFL: usize = 0;
+pub const SIG_IGN: usize = 1;
+
+pub const SA_SIGINFO: u32 =   0x00000001;
+pub const SA_RESTORER: u32 =  0x04000000;
+pub const SA_NODEFER: u32 =   0x40000000;
+pub const SA_RESETHAND: u32 = 0x80000000;
+
 /// Generic data structure for holding information about a signal
 #[derive(Clone, Copy)]
 pub union SignalValue
@@ -52,8 +61,55 @@ struct sigaction
     sigset_t   sa_mask;
     int        sa_flags;
     void     (*sa_restorer)(void);
+};
+
+struct signalfd_siginfo
+{
+    uint32_t ssi_signo;    /* Signal number */
+    int32_t  ssi_errno;    /* Error number (unused) */
+    int32_t  ssi_code;     /* Signal code */
+    uint32_t ssi_pid;      /* PID of sender */
+    uint32_t ssi_uid;      /* Real UID of sender */
+    int32_t  ssi_fd;       /* File descriptor (SIGIO) */
+    uint32_t ssi_tid;      /* Kernel timer ID (POSIX timers) */
+    uint32_t ssi_band;     /* Band event (SIGIO) */
+    uint32_t ssi_overrun;  /* POSIX timer overrun count */
+    uint32_t ssi_trapno;   /* Trap number that caused signal */
+    int32_t  ssi_status;   /* Exit status or signal (SIGCHLD) */
+    int32_t  ssi_int;      /* Integer sent by sigqueue */
+    uint64_t ssi_ptr;      /* Pointer sent by sigqueue */
+    uint64_t ssi_utime;    /* User CPU time consumed (SIGCHLD) */
+    uint64_t ssi_stime;    /* System CPU time consumed (SIGCHLD) */
+    uint64_t ssi_addr;     /* Address that generated signal */
+    uint16_t ssi_addr_lsb; /* Least significant bit of address */
+    uint8_t  pad[46];      /* Pad size to 128 bytes */
 };*/
 
+/// Record read from a signalfd for each signal taken
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct SignalFdInfo
+{
+    pub signal_number: u32,
+    pub error: i32,
+    pub code: i32,
+    pub pid: u32,
+    pub uid: u32,
+    pub fd: i32,
+    pub timer: u32,
+    pub band: u32,
+    pub overrun: u32,
+    pub trap: u32,
+    pub status: i32,
+    pub integer: i32,
+    pub ptr: u64,
+    pub utime: u64,
+    pub stime: u64,
+    pub addr: u64,
+    pub addr_lsb: u16,
+    pub padding: [u8; 46]
+}
+
 /// Signal Action Structure
 #[repr(C)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -64,4 +120,34 @@ pub struct SignalAction
     pub mask: u64,
     pub flags: u32,
     pub restoring_addr: usize
-}
\ No newline at end of file
+}
+
+impl SignalAction
+{
+    /// Get the address of the function to call for the signal
+    pub fn handler_address(&self) -> usize
+    {
+        if self.flags & SA_SIGINFO > 0
+        {
+            self.action_fn_ptr
+        }
+        else
+        {
+            self.handler_value
+        }
+    }
+}
+
+/// Context saved on the user stack while a signal handler runs, sigreturn
+/// restores the interrupted state from it
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct SignalFrame
+{
+    pub info: SignalInfo,
+    pub regs: [usize; 32],
+    pub fregs: [usize; 32],
+    pub fcsr: usize,
+    pub program_counter: usize,
+    pub mask: u64
+}
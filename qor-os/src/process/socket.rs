@@ -0,0 +1,537 @@
+//! Sockets
+//!
+//! Sockets are created in an address family which provides their behaviour
+//! through the `Socket` trait, and are held open by a `SocketDescriptor`. Only
+//! the local AF_UNIX family exists so far, with stream sockets carrying data
+//! through a pair of pipe buffers once connected.
+//!
+//! Names bound to AF_UNIX sockets are kept by the kernel rather than appearing
+//! as files, so a name is released when the socket bound to it is closed.
+//! Names beginning with a null byte are abstract and are never canonicalized.
+//! Paths are kept as the directory they name an entry in, so processes with
+//! a different root or mount namespace reach a socket through their own view
+//! of the filesystem.
+
+use crate::*;
+
+use alloc::collections::{BTreeMap, VecDeque};
+use alloc::sync::{Arc, Weak};
+use core::cell::RefCell;
+
+use fs::structures::FilesystemIndex;
+
+use libutils::paths::OwnedPath;
+
+use super::descriptor::{O_CLOEXEC, O_NONBLOCK};
+use super::pipe::{PipeBuffer, PIPE_DEFAULT_SIZE};
+
+// Must be kept in sync with syscalls.h
+pub const AF_UNIX: u16 = 1;
+
+pub const SOCK_STREAM: usize = 1;
+pub const SOCK_NONBLOCK: usize = O_NONBLOCK;
+pub const SOCK_CLOEXEC: usize = O_CLOEXEC;
+
+pub const SHUT_RD: usize = 0;
+pub const SHUT_WR: usize = 1;
+pub const SHUT_RDWR: usize = 2;
+
+// Largest number of connections waiting to be accepted on a listening socket
+pub const SOMAXCONN: usize = 128;
+
+// Size of the path field of a sockaddr_un
+pub const UNIX_PATH_MAX: usize = 108;
+
+/// Address of a socket
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SocketAddress
+{
+    // Socket which has not been bound to a name
+    Unnamed,
+    // Canonical path, or a name beginning with a null byte when abstract
+    Unix(String)
+}
+
+impl SocketAddress
+{
+    /// Decode a sockaddr passed by userspace, paths are canonicalized against
+    /// the given working directory
+    pub fn parse(bytes: &[u8], cwd: &OwnedPath) -> Result<Self, usize>
+    {
+        if bytes.len() < 2
+        {
+            return Err(errno::EINVAL);
+        }
+
+        if u16::from_ne_bytes([bytes[0], bytes[1]]) != AF_UNIX
+        {
+            return Err(errno::EAFNOSUPPORT);
+        }
+
+        let name = &bytes[2..];
+
+        if name.len() > UNIX_PATH_MAX
+        {
+            return Err(errno::EINVAL);
+        }
+
+        if name.is_empty()
+        {
+            return Ok(Self::Unnamed);
+        }
+
+        if name[0] == 0
+        {
+            return Ok(Self::Unix(name.iter().map(|b| *b as char).collect()));
+        }
+
+        let length = name.iter().position(|b| *b == 0).unwrap_or(name.len());
+        let path = core::str::from_utf8(&name[..length]).map_err(|_| errno::EINVAL)?;
+
+        Ok(Self::Unix(OwnedPath::new(path).canonicalized(cwd).as_str().into()))
+    }
+
+    /// Encode the address as a sockaddr
+    pub fn to_bytes(&self) -> Vec<u8>
+    {
+        let mut bytes = AF_UNIX.to_ne_bytes().to_vec();
+
+        match self
+        {
+            Self::Unix(name) if name.starts_with('\0') => bytes.extend(name.chars().map(|c| c as u8)),
+            Self::Unix(name) =>
+            {
+                bytes.extend(name.bytes());
+                bytes.push(0);
+            },
+            Self::Unnamed => {}
+        }
+
+        bytes
+    }
+}
+
+/// Name an AF_UNIX socket is bound to, as it is looked up among the bound
+/// names
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum UnixName
+{
+    // Abstract name, beginning with a null byte
+    Abstract(String),
+    // Entry with the given name in the directory with the given index
+    Path(FilesystemIndex, String)
+}
+
+/// Behaviour of a socket provided by its address family, operations which
+/// would block return `EAGAIN` and are retried by the caller
+pub trait Socket
+{
+    /// Give the socket a name, the address is reported back to userspace and
+    /// the name is what other sockets connect to
+    fn bind(&mut self, address: &SocketAddress, name: UnixName) -> Result<(), usize>;
+
+    /// Start accepting connections, holding up to `backlog` of them until
+    /// they are accepted
+    fn listen(&mut self, backlog: usize) -> Result<(), usize>;
+
+    /// Take the next connection made to a listening socket
+    fn accept(&mut self) -> Result<Box<dyn Socket>, usize>;
+
+    /// Connect the socket to the socket bound to a name, which was given at
+    /// the address
+    fn connect(&mut self, address: &SocketAddress, name: &UnixName) -> Result<(), usize>;
+
+    /// Send data to the peer, returning the number of bytes sent
+    fn send(&mut self, data: &[u8]) -> Result<usize, usize>;
+
+    /// Receive data from the peer, returning the number of bytes received,
+    /// which is zero once the peer will send no more
+    fn recv(&mut self, data: &mut [u8]) -> Result<usize, usize>;
+
+    /// Shut down receiving, sending or both
+    fn shutdown(&mut self, how: usize) -> Result<(), usize>;
+
+    /// Get the address the socket is bound to
+    fn local_address(&self) -> SocketAddress;
+
+    /// Get the address of the peer, giving `ENOTCONN` if there is none
+    fn peer_address(&self) -> Result<SocketAddress, usize>;
+
+    /// Check if the socket is accepting connections
+    fn is_listening(&self) -> bool;
+
+    /// Check if a receive or accept can make progress without blocking
+    fn check_available(&self) -> bool;
+
+    /// Check if a send can make progress without blocking
+    fn check_writable(&self) -> bool;
+
+    /// Check if the connection has been closed in both directions
+    fn check_hangup(&self) -> bool;
+}
+
+/// Create a socket in the given address family
+pub fn new_socket(domain: usize, kind: usize, protocol: usize) -> Result<Box<dyn Socket>, usize>
+{
+    if domain != AF_UNIX as usize
+    {
+        return Err(errno::EAFNOSUPPORT);
+    }
+
+    if kind != SOCK_STREAM
+    {
+        return Err(errno::ESOCKTNOSUPPORT);
+    }
+
+    if protocol != 0
+    {
+        return Err(errno::EPROTONOSUPPORT);
+    }
+
+    Ok(Box::new(UnixStreamSocket::new()))
+}
+
+/// Create a pair of connected sockets in the given address family
+pub fn new_socket_pair(domain: usize, kind: usize, protocol: usize) -> Result<(Box<dyn Socket>, Box<dyn Socket>), usize>
+{
+    new_socket(domain, kind, protocol)?;
+
+    let (first, second) = UnixStreamSocket::pair();
+
+    Ok((Box::new(first), Box::new(second)))
+}
+
+/// Data flowing in one direction of a connection
+struct UnixStream
+{
+    buffer: PipeBuffer,
+    // No more data will be sent
+    write_closed: bool,
+    // No more data will be received
+    read_closed: bool
+}
+
+type SharedUnixStream = Arc<RefCell<UnixStream>>;
+
+/// Name bound to an AF_UNIX socket, and the connections waiting on it once
+/// it is listening
+struct UnixEndpoint
+{
+    listening: bool,
+    backlog: usize,
+    pending: VecDeque<UnixStreamSocket>
+}
+
+/// Names bound to AF_UNIX sockets, a name is free once its endpoint is dropped
+static mut UNIX_NAMESPACE: Option<BTreeMap<UnixName, Weak<RefCell<UnixEndpoint>>>> = None;
+
+/// Get the names bound to AF_UNIX sockets
+fn get_unix_namespace() -> &'static mut BTreeMap<UnixName, Weak<RefCell<UnixEndpoint>>>
+{
+    unsafe
+    {
+        if UNIX_NAMESPACE.is_none()
+        {
+            UNIX_NAMESPACE = Some(BTreeMap::new());
+        }
+
+        UNIX_NAMESPACE.as_mut().unwrap()
+    }
+}
+
+/// Connection state of an AF_UNIX stream socket
+enum UnixState
+{
+    Unconnected,
+    Listening,
+    Connected
+    {
+        incoming: SharedUnixStream,
+        outgoing: SharedUnixStream,
+        peer: SocketAddress
+    }
+}
+
+/// AF_UNIX stream socket
+pub struct UnixStreamSocket
+{
+    state: UnixState,
+    address: SocketAddress,
+    endpoint: Option<(UnixName, Arc<RefCell<UnixEndpoint>>)>
+}
+
+impl UnixStreamSocket
+{
+    /// Create a new unconnected socket
+    pub fn new() -> Self
+    {
+        Self
+        {
+            state: UnixState::Unconnected,
+            address: SocketAddress::Unnamed,
+            endpoint: None
+        }
+    }
+
+    /// Create the states of two sockets connected to each other, given the
+    /// address of each
+    fn connection(first: SocketAddress, second: SocketAddress) -> (UnixState, UnixState)
+    {
+        let new_stream = || Arc::new(RefCell::new(UnixStream { buffer: PipeBuffer::new(PIPE_DEFAULT_SIZE), write_closed: false, read_closed: false }));
+
+        let forward = new_stream();
+        let backward = new_stream();
+
+        (UnixState::Connected { incoming: backward.clone(), outgoing: forward.clone(), peer: second },
+         UnixState::Connected { incoming: forward, outgoing: backward, peer: first })
+    }
+
+    /// Create two unnamed sockets connected to each other
+    pub fn pair() -> (Self, Self)
+    {
+        let (first, second) = Self::connection(SocketAddress::Unnamed, SocketAddress::Unnamed);
+
+        (Self { state: first, address: SocketAddress::Unnamed, endpoint: None },
+         Self { state: second, address: SocketAddress::Unnamed, endpoint: None })
+    }
+}
+
+impl Socket for UnixStreamSocket
+{
+    fn bind(&mut self, address: &SocketAddress, name: UnixName) -> Result<(), usize>
+    {
+        if self.endpoint.is_some()
+        {
+            return Err(errno::EINVAL);
+        }
+
+        let namespace = get_unix_namespace();
+
+        if namespace.get(&name).and_then(|endpoint| endpoint.upgrade()).is_some()
+        {
+            return Err(errno::EADDRINUSE);
+        }
+
+        let endpoint = Arc::new(RefCell::new(UnixEndpoint { listening: false, backlog: 0, pending: VecDeque::new() }));
+        namespace.insert(name.clone(), Arc::downgrade(&endpoint));
+
+        self.endpoint = Some((name, endpoint));
+        self.address = address.clone();
+
+        Ok(())
+    }
+
+    fn listen(&mut self, backlog: usize) -> Result<(), usize>
+    {
+        let (_, endpoint) = self.endpoint.as_ref().ok_or(errno::EINVAL)?;
+
+        match self.state
+        {
+            UnixState::Unconnected | UnixState::Listening => {},
+            UnixState::Connected { .. } => return Err(errno::EINVAL)
+        }
+
+        let mut endpoint = endpoint.borrow_mut();
+        endpoint.listening = true;
+        endpoint.backlog = backlog.clamp(1, SOMAXCONN);
+
+        self.state = UnixState::Listening;
+
+        Ok(())
+    }
+
+    fn accept(&mut self) -> Result<Box<dyn Socket>, usize>
+    {
+        if !matches!(self.state, UnixState::Listening)
+        {
+            return Err(errno::EINVAL);
+        }
+
+        let connection = self.endpoint.as_ref().unwrap().1.borrow_mut().pending.pop_front().ok_or(errno::EAGAIN)?;
+
+        Ok(Box::new(connection))
+    }
+
+    fn connect(&mut self, address: &SocketAddress, name: &UnixName) -> Result<(), usize>
+    {
+        match self.state
+        {
+            UnixState::Unconnected => {},
+            UnixState::Listening => return Err(errno::EINVAL),
+            UnixState::Connected { .. } => return Err(errno::EISCONN)
+        }
+
+        let endpoint = get_unix_namespace().get(name).and_then(|endpoint| endpoint.upgrade()).ok_or(errno::ECONNREFUSED)?;
+        let mut endpoint = endpoint.borrow_mut();
+
+        if !endpoint.listening
+        {
+            return Err(errno::ECONNREFUSED);
+        }
+
+        if endpoint.pending.len() >= endpoint.backlog
+        {
+            return Err(errno::EAGAIN);
+        }
+
+        let (client, server) = Self::connection(self.address.clone(), address.clone());
+
+        self.state = client;
+        endpoint.pending.push_back(Self { state: server, address: address.clone(), endpoint: None });
+
+        Ok(())
+    }
+
+    fn send(&mut self, data: &[u8]) -> Result<usize, usize>
+    {
+        if let UnixState::Connected { outgoing, .. } = &self.state
+        {
+            let mut outgoing = outgoing.borrow_mut();
+
+            if outgoing.write_closed || outgoing.read_closed
+            {
+                return Err(errno::EPIPE);
+            }
+
+            // If the buffer is full, report a short send rather than dropping
+            // the remaining data
+            Ok(outgoing.buffer.write(data))
+        }
+        else
+        {
+            Err(errno::ENOTCONN)
+        }
+    }
+
+    fn recv(&mut self, data: &mut [u8]) -> Result<usize, usize>
+    {
+        if let UnixState::Connected { incoming, .. } = &self.state
+        {
+            let mut incoming = incoming.borrow_mut();
+
+            if incoming.read_closed
+            {
+                return Ok(0);
+            }
+
+            Ok(incoming.buffer.read(data))
+        }
+        else
+        {
+            Err(errno::ENOTCONN)
+        }
+    }
+
+    fn shutdown(&mut self, how: usize) -> Result<(), usize>
+    {
+        if how > SHUT_RDWR
+        {
+            return Err(errno::EINVAL);
+        }
+
+        if let UnixState::Connected { incoming, outgoing, .. } = &self.state
+        {
+            if how != SHUT_WR
+            {
+                incoming.borrow_mut().read_closed = true;
+            }
+
+            if how != SHUT_RD
+            {
+                outgoing.borrow_mut().write_closed = true;
+            }
+
+            Ok(())
+        }
+        else
+        {
+            Err(errno::ENOTCONN)
+        }
+    }
+
+    fn local_address(&self) -> SocketAddress
+    {
+        self.address.clone()
+    }
+
+    fn peer_address(&self) -> Result<SocketAddress, usize>
+    {
+        if let UnixState::Connected { peer, .. } = &self.state
+        {
+            Ok(peer.clone())
+        }
+        else
+        {
+            Err(errno::ENOTCONN)
+        }
+    }
+
+    fn is_listening(&self) -> bool
+    {
+        matches!(self.state, UnixState::Listening)
+    }
+
+    fn check_available(&self) -> bool
+    {
+        match &self.state
+        {
+            UnixState::Listening => !self.endpoint.as_ref().unwrap().1.borrow().pending.is_empty(),
+            UnixState::Connected { incoming, .. } =>
+            {
+                let incoming = incoming.borrow();
+                !incoming.buffer.is_empty() || incoming.write_closed || incoming.read_closed
+            },
+            // Receiving fails at once on an unconnected socket
+            UnixState::Unconnected => true
+        }
+    }
+
+    fn check_writable(&self) -> bool
+    {
+        if let UnixState::Connected { outgoing, .. } = &self.state
+        {
+            let outgoing = outgoing.borrow();
+            outgoing.buffer.space() > 0 || outgoing.write_closed || outgoing.read_closed
+        }
+        else
+        {
+            false
+        }
+    }
+
+    fn check_hangup(&self) -> bool
+    {
+        if let UnixState::Connected { incoming, outgoing, .. } = &self.state
+        {
+            incoming.borrow().write_closed && outgoing.borrow().read_closed
+        }
+        else
+        {
+            false
+        }
+    }
+}
+
+impl core::ops::Drop for UnixStreamSocket
+{
+    fn drop(&mut self)
+    {
+        // The peer sees the end of the data and any further sends fail
+        if let UnixState::Connected { incoming, outgoing, .. } = &self.state
+        {
+            incoming.borrow_mut().read_closed = true;
+            outgoing.borrow_mut().write_closed = true;
+        }
+
+        // Release the name, unless it was already taken over by another socket
+        if let Some((name, endpoint)) = &self.endpoint
+        {
+            let namespace = get_unix_namespace();
+
+            if namespace.get(name).map(|bound| core::ptr::eq(bound.as_ptr(), Arc::as_ptr(endpoint))).unwrap_or(false)
+            {
+                namespace.remove(name);
+            }
+        }
+    }
+}
@@ -1,3 +1,17 @@
+use crate::*;
+
+use drivers::timer::KernelTime;
+
+// Clock ticks per second used for times reported to userspace
+// Must be kept in sync with syscalls.h
+pub const USER_HZ: usize = 100;
+
+/// Convert a time to clock ticks
+pub fn to_clock_ticks(time: KernelTime) -> usize
+{
+    time.as_nanoseconds() / (1_000_000_000 / USER_HZ)
+}
+
 /// Memory statistics for a process
 #[derive(Debug, Clone, Copy)]
 pub struct MemoryStats
@@ -38,4 +52,108 @@ impl core::fmt::Display for MemoryStats
             0)
     }
     
-}
\ No newline at end of file
+}
+
+/// Processor time used by a process, along with the time used by the
+/// children it has waited on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CpuTimes
+{
+    pub user: KernelTime,
+    pub system: KernelTime,
+    pub children_user: KernelTime,
+    pub children_system: KernelTime
+}
+
+impl CpuTimes
+{
+    /// Create a new CpuTimes object with no time used
+    pub const fn new() -> Self
+    {
+        Self
+        {
+            user: KernelTime(0),
+            system: KernelTime(0),
+            children_user: KernelTime(0),
+            children_system: KernelTime(0)
+        }
+    }
+
+    /// Add the time used by a child which has been waited on, along with the
+    /// time of its own children
+    pub fn add_child(&mut self, child: &CpuTimes)
+    {
+        self.children_user = self.children_user + child.user + child.children_user;
+        self.children_system = self.children_system + child.system + child.children_system;
+    }
+}
+
+/// Resource usage reported by getrusage and wait4, only the processor times
+/// are tracked, every other field is zero
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct ResourceUsage
+{
+    pub user_seconds: usize,
+    pub user_micro_seconds: usize,
+    pub system_seconds: usize,
+    pub system_micro_seconds: usize,
+    pub untracked: [usize; 14]
+}
+
+impl ResourceUsage
+{
+    /// Create the usage report for the given user and system times
+    pub fn new(user: KernelTime, system: KernelTime) -> Self
+    {
+        let user = user.as_nanoseconds() / 1_000;
+        let system = system.as_nanoseconds() / 1_000;
+
+        Self
+        {
+            user_seconds: user / 1_000_000,
+            user_micro_seconds: user % 1_000_000,
+            system_seconds: system / 1_000_000,
+            system_micro_seconds: system % 1_000_000,
+            untracked: [0; 14]
+        }
+    }
+}
+
+// Time the running process was last charged up to
+static mut LAST_SAMPLE: KernelTime = KernelTime(0);
+
+/// Start charging time to a process as it is switched to, time spent before
+/// this, such as while waiting for a process to become runnable, is not
+/// charged to anyone
+pub fn restart_sampling()
+{
+    unsafe { LAST_SAMPLE = drivers::TIMER_DRIVER.time() };
+}
+
+/// Charge the time since the last sample to the running process on a timer
+/// tick, as user or system time depending on the mode the tick interrupted.
+/// Time is sampled rather than measured at every trap, so a process which
+/// blocks between ticks is not charged for the time it ran before blocking.
+pub fn sample_tick(user_mode: bool)
+{
+    let now = unsafe { &drivers::TIMER_DRIVER }.time();
+    let elapsed = now - unsafe { LAST_SAMPLE };
+    unsafe { LAST_SAMPLE = now };
+
+    // The last process to run stays current while the hart idles, but is no
+    // longer running
+    if let Some(proc) = process::scheduler::current_process().filter(|proc| proc.get_state() == process::process::ProcessState::Running)
+    {
+        let times = &mut proc.data.cpu_times;
+
+        if user_mode
+        {
+            times.user = times.user + elapsed;
+        }
+        else
+        {
+            times.system = times.system + elapsed;
+        }
+    }
+}
@@ -0,0 +1,128 @@
+//! Executable Cache
+//!
+//! The images of recently executed binaries are kept in memory keyed by their
+//! inode, so repeatedly executing the same program (such as the shell or the
+//! coreutils) does not read it back from the disk each time. Cached images
+//! are never modified, and are dropped as soon as their inode is written to
+//! or removed, or the filesystem holding it is unmounted.
+
+use crate::*;
+
+use alloc::collections::BTreeMap;
+use alloc::sync::Arc;
+
+use fs::structures::FilesystemIndex;
+
+/// Total size of the cached images in bytes, the least recently executed
+/// images are evicted to stay under it
+pub const TEXT_CACHE_LIMIT: usize = 4 * 1024 * 1024;
+
+/// Largest image which will be cached
+pub const TEXT_CACHE_MAX_IMAGE: usize = 1024 * 1024;
+
+/// Image of an executable shared by every load of it
+pub type ExecutableImage = Arc<Vec<u8>>;
+
+/// Cached Executable
+struct CachedImage
+{
+    image: ExecutableImage,
+    last_used: usize
+}
+
+/// Executable Cache
+pub struct TextCache
+{
+    images: BTreeMap<FilesystemIndex, CachedImage>,
+    size: usize,
+    clock: usize
+}
+
+impl TextCache
+{
+    /// Create a new empty cache
+    pub fn new() -> Self
+    {
+        Self
+        {
+            images: BTreeMap::new(),
+            size: 0,
+            clock: 0
+        }
+    }
+
+    /// Get the cached image of an inode, marking it as recently used
+    pub fn lookup(&mut self, inode: FilesystemIndex) -> Option<ExecutableImage>
+    {
+        self.clock += 1;
+        let clock = self.clock;
+
+        self.images.get_mut(&inode).map(|entry|
+        {
+            entry.last_used = clock;
+            entry.image.clone()
+        })
+    }
+
+    /// Add the image of an inode which was just loaded to the cache
+    pub fn insert(&mut self, inode: FilesystemIndex, image: ExecutableImage)
+    {
+        if image.len() > TEXT_CACHE_MAX_IMAGE || self.lookup(inode).is_some()
+        {
+            return;
+        }
+
+        kdebugln!(Processes, "Caching executable image of {:?} ({} bytes)", inode, image.len());
+
+        self.size += image.len();
+        self.images.insert(inode, CachedImage { image, last_used: self.clock });
+
+        self.evict();
+    }
+
+    /// Evict the least recently used images until the cache is under its limit
+    fn evict(&mut self)
+    {
+        while self.size > TEXT_CACHE_LIMIT
+        {
+            let oldest = *self.images.iter().min_by_key(|(_, entry)| entry.last_used).unwrap().0;
+            self.invalidate(oldest);
+        }
+    }
+
+    /// Drop the cached image of an inode
+    pub fn invalidate(&mut self, inode: FilesystemIndex)
+    {
+        if let Some(entry) = self.images.remove(&inode)
+        {
+            self.size -= entry.image.len();
+        }
+    }
+
+    /// Drop every cached image from the filesystem with the given mount id
+    pub fn invalidate_mount(&mut self, mount_id: usize)
+    {
+        let inodes: Vec<FilesystemIndex> = self.images.keys().filter(|inode| inode.mount_id == mount_id).copied().collect();
+
+        for inode in inodes
+        {
+            self.invalidate(inode);
+        }
+    }
+}
+
+// Global Executable Cache
+static mut GLOBAL_TEXT_CACHE: Option<TextCache> = None;
+
+/// Get a reference to the executable cache, initializing it if needed
+pub fn get_text_cache() -> &'static mut TextCache
+{
+    let cache = unsafe { &mut GLOBAL_TEXT_CACHE };
+
+    if cache.is_none()
+    {
+        *cache = Some(TextCache::new());
+    }
+
+    cache.as_mut().unwrap()
+}
@@ -0,0 +1,230 @@
+//! Waiting on Children
+//!
+//! State changes of children which a parent collects through waitid or wait4.
+//! A child reports once when it exits, and with job control each time it is
+//! stopped or continued.
+
+use crate::*;
+
+use super::PID;
+use super::process::{Process, ProcessState};
+use super::scheduler::ProcessManager;
+use super::signals::{SignalInfo, SignalType, SignalValue};
+use super::stats::{to_clock_ticks, ResourceUsage, USER_HZ};
+
+use drivers::timer::KernelTime;
+
+// Must be kept in sync with syscalls.h
+pub const WNOHANG: usize =    0x00000001;
+pub const WSTOPPED: usize =   0x00000002;
+pub const WEXITED: usize =    0x00000004;
+pub const WCONTINUED: usize = 0x00000008;
+pub const WNOWAIT: usize =    0x01000000;
+
+pub const P_ALL: usize =  0;
+pub const P_PID: usize =  1;
+pub const P_PGID: usize = 2;
+
+pub const CLD_EXITED: u32 =    1;
+pub const CLD_KILLED: u32 =    2;
+pub const CLD_STOPPED: u32 =   5;
+pub const CLD_CONTINUED: u32 = 6;
+
+/// Job control event which has not yet been collected by the parent
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobEvent
+{
+    Stopped(SignalType),
+    Continued
+}
+
+/// Children a wait applies to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WaitTarget
+{
+    Any,
+    Pid(PID),
+    Group(PID)
+}
+
+/// Where the result of a wait is written, as a user address which may be
+/// null
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WaitReport
+{
+    // Signal info structure written by waitid
+    SignalInfo(usize),
+    // Status word and resource usage written by wait4
+    Status { status: usize, usage: usize }
+}
+
+/// Outstanding waitid or wait4 call
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WaitRequest
+{
+    pub target: WaitTarget,
+    pub options: usize,
+    pub report: WaitReport
+}
+
+/// Build the signal info reported for a child
+pub fn child_info(pid: PID, code: u32, status: u32) -> SignalInfo
+{
+    SignalInfo
+    {
+        signal_number: if pid == 0 { 0 } else { SignalType::SIGCHLD as u32 },
+        error: 0,
+        code,
+        trap: 0,
+        pid,
+        uid: 0,
+        status,
+        utime: 0,
+        stime: 0,
+        value: SignalValue { integer: 0 }
+    }
+}
+
+/// Take the state change of a child which the request is waiting for, the
+/// change is left in place if WNOWAIT is given
+fn take_event(child: &mut Process, options: usize) -> Option<(u32, u32)>
+{
+    let consume = options & WNOWAIT == 0;
+
+    if options & WEXITED != 0 && child.get_state() == ProcessState::Zombie
+    {
+        let event = match child.exit_signal
+        {
+            Some(sig) => (CLD_KILLED, sig as u32),
+            None => (CLD_EXITED, child.exit_code)
+        };
+
+        if consume
+        {
+            child.wait_check();
+        }
+
+        return Some(event);
+    }
+
+    let event = match child.job_event?
+    {
+        JobEvent::Stopped(sig) if options & WSTOPPED != 0 => (CLD_STOPPED, sig as u32),
+        JobEvent::Continued if options & WCONTINUED != 0 => (CLD_CONTINUED, SignalType::SIGCONT as u32),
+        _ => { return None; }
+    };
+
+    if consume
+    {
+        child.job_event = None;
+    }
+
+    Some(event)
+}
+
+/// Find a child of the given process with a state change the request is
+/// waiting for, returns ECHILD if the process has no children the request
+/// could apply to
+pub fn poll_child_event(manager: &mut ProcessManager, parent: PID, request: &WaitRequest) -> Result<Option<SignalInfo>, usize>
+{
+    let children = manager.get_process_by_pid(parent).map(|proc| proc.data.children.clone()).unwrap_or_default();
+    let mut matched = false;
+
+    for pid in children
+    {
+        let child = if let Some(child) = manager.get_process_by_pid_mut(pid) { child } else { continue; };
+
+        let selected = match request.target
+        {
+            WaitTarget::Any => true,
+            WaitTarget::Pid(target) => pid == target,
+            WaitTarget::Group(group) => child.data.process_group_id == group
+        };
+
+        if !selected
+        {
+            continue;
+        }
+
+        matched = true;
+
+        if let Some((code, status)) = take_event(child, request.options)
+        {
+            let times = child.data.cpu_times;
+
+            let mut info = child_info(pid, code, status);
+            info.utime = to_clock_ticks(times.user + times.children_user) as u64;
+            info.stime = to_clock_ticks(times.system + times.children_system) as u64;
+
+            // A child which has been reaped is no longer a child of the
+            // parent, and the time it used is added to that of the parent
+            if child.get_state() == ProcessState::Dead
+            {
+                let parent = manager.get_process_by_pid_mut(parent).unwrap();
+
+                parent.remove_child(pid);
+                parent.data.cpu_times.add_child(&times);
+
+                // The scheduler removes the child once it is reaped
+                manager.notify(pid);
+            }
+
+            return Ok(Some(info));
+        }
+    }
+
+    if matched { Ok(None) } else { Err(errno::ECHILD) }
+}
+
+/// Encode a state change as the status word reported by wait4
+pub fn status_word(info: &SignalInfo) -> u32
+{
+    match info.code
+    {
+        CLD_EXITED => (info.status & 0xFF) << 8,
+        CLD_KILLED => info.status & 0x7F,
+        CLD_STOPPED => ((info.status & 0xFF) << 8) | 0x7F,
+        CLD_CONTINUED => 0xFFFF,
+        _ => 0
+    }
+}
+
+/// Write the result of a wait to the process, waitid returns zero and wait4
+/// returns the pid of the child, or zero if no child changed state
+pub fn report_child_event(proc: &mut Process, request: &WaitRequest, info: &SignalInfo) -> Result<usize, usize>
+{
+    match request.report
+    {
+        WaitReport::SignalInfo(addr) =>
+        {
+            if addr != 0
+            {
+                let bytes = unsafe { core::slice::from_raw_parts(info as *const SignalInfo as *const u8, core::mem::size_of::<SignalInfo>()) };
+
+                syscalls::utils::copy_to_user(proc, addr, bytes)?;
+            }
+
+            Ok(0)
+        },
+        WaitReport::Status { status, usage } =>
+        {
+            if status != 0 && info.pid != 0
+            {
+                syscalls::utils::copy_to_user(proc, status, &status_word(info).to_ne_bytes())?;
+            }
+
+            // The usage is reported at the resolution of the clock ticks in
+            // the signal info
+            if usage != 0 && info.pid != 0
+            {
+                let tick = KernelTime::nanoseconds(1_000_000_000 / USER_HZ);
+                let report = ResourceUsage::new(KernelTime(tick.0 * info.utime as usize), KernelTime(tick.0 * info.stime as usize));
+                let bytes = unsafe { core::slice::from_raw_parts(&report as *const ResourceUsage as *const u8, core::mem::size_of::<ResourceUsage>()) };
+
+                syscalls::utils::copy_to_user(proc, usage, bytes)?;
+            }
+
+            Ok(info.pid as usize)
+        }
+    }
+}
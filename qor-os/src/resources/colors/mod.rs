@@ -1,6 +1,6 @@
 /// Pixel Object
 #[repr(C)]
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Pixel
 {
     red: u8,
@@ -22,6 +22,12 @@ impl Pixel
             alpha: 255
         }
     }
+
+    /// Get the red, green and blue channels of the pixel
+    pub const fn channels(&self) -> (u8, u8, u8)
+    {
+        (self.red, self.green, self.blue)
+    }
 }
 
 pub mod ega;
\ No newline at end of file
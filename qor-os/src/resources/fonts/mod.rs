@@ -1 +1,2 @@
+pub mod psf;
 pub mod vga;
\ No newline at end of file
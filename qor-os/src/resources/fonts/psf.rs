@@ -0,0 +1,143 @@
+//! PC Screen Font Loading
+
+use crate::*;
+
+// PSF1 Header Constants
+const PSF1_MAGIC: [u8; 2] = [0x36, 0x04];
+const PSF1_MODE512: u8 = 0x01;
+const PSF1_HEADER_SIZE: usize = 4;
+
+// PSF2 Header Constants
+const PSF2_MAGIC: [u8; 4] = [0x72, 0xB5, 0x4A, 0x86];
+const PSF2_HEADER_SIZE: usize = 32;
+
+/// Largest glyph which can be loaded
+pub const MAX_GLYPH_WIDTH: usize = 32;
+pub const MAX_GLYPH_HEIGHT: usize = 64;
+
+/// Bitmap Font
+///
+/// Glyphs are stored row by row, with each row padded to a whole number of
+/// bytes and the leftmost pixel in the most significant bit
+#[derive(Debug, Clone)]
+pub struct Font
+{
+    pub width: usize,
+    pub height: usize,
+    bytes_per_row: usize,
+    glyph_count: usize,
+    data: Vec<u8>
+}
+
+/// Read a little endian u32 from the given offset
+fn read_u32(data: &[u8], offset: usize) -> Option<usize>
+{
+    let bytes = data.get(offset..offset + 4)?;
+
+    Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize)
+}
+
+impl Font
+{
+    /// Build the built in VGA font
+    pub fn vga() -> Self
+    {
+        let glyphs = &super::vga::GLYPHS;
+        let width = glyphs[0].len();
+        let height = 16;
+        let bytes_per_row = (width + 7) / 8;
+
+        let mut data = vec![0; glyphs.len() * height * bytes_per_row];
+
+        // The built in glyphs are stored column by column
+        for (c, glyph) in glyphs.iter().enumerate()
+        {
+            for (x, col) in glyph.iter().enumerate()
+            {
+                for y in 0..height
+                {
+                    if col & (0b1000000000000000 >> y) > 0
+                    {
+                        data[(c * height + y) * bytes_per_row + x / 8] |= 0x80 >> (x % 8);
+                    }
+                }
+            }
+        }
+
+        Self
+        {
+            width,
+            height,
+            bytes_per_row,
+            glyph_count: glyphs.len(),
+            data
+        }
+    }
+
+    /// Parse a PSF1 or PSF2 font, returning None if the font is malformed or
+    /// its glyphs are too large, any unicode table is ignored
+    pub fn from_psf(file: &[u8]) -> Option<Self>
+    {
+        let (width, height, glyph_count, header_size, glyph_size) = if file.starts_with(&PSF1_MAGIC)
+        {
+            let mode = *file.get(2)?;
+            let height = *file.get(3)? as usize;
+
+            (8, height, if mode & PSF1_MODE512 > 0 { 512 } else { 256 }, PSF1_HEADER_SIZE, height)
+        }
+        else if file.starts_with(&PSF2_MAGIC)
+        {
+            let header_size = read_u32(file, 8)?;
+            let glyph_count = read_u32(file, 16)?;
+            let glyph_size = read_u32(file, 20)?;
+            let height = read_u32(file, 24)?;
+            let width = read_u32(file, 28)?;
+
+            if header_size < PSF2_HEADER_SIZE
+            {
+                return None;
+            }
+
+            (width, height, glyph_count, header_size, glyph_size)
+        }
+        else
+        {
+            return None;
+        };
+
+        let bytes_per_row = (width + 7) / 8;
+
+        if width == 0 || height == 0 || width > MAX_GLYPH_WIDTH || height > MAX_GLYPH_HEIGHT || glyph_count == 0
+        {
+            return None;
+        }
+
+        if glyph_size != bytes_per_row * height
+        {
+            return None;
+        }
+
+        let data = file.get(header_size..header_size.checked_add(glyph_count.checked_mul(glyph_size)?)?)?;
+
+        Some(Self
+        {
+            width,
+            height,
+            bytes_per_row,
+            glyph_count,
+            data: data.to_vec()
+        })
+    }
+
+    /// Check if the pixel at the given position in the glyph for the given
+    /// character is set, characters outside of the font are drawn blank
+    pub fn pixel(&self, c: usize, x: usize, y: usize) -> bool
+    {
+        if c >= self.glyph_count || x >= self.width || y >= self.height
+        {
+            return false;
+        }
+
+        self.data[(c * self.height + y) * self.bytes_per_row + x / 8] & (0x80 >> (x % 8)) > 0
+    }
+}
@@ -0,0 +1,84 @@
+//! System Suspend to RAM
+//!
+//! Suspending to RAM writes back the filesystems and quiesces the devices
+//! before the power driver suspends the boot hart. The suspend is
+//! non-retentive: the registers and machine mode CSRs the kernel relies on
+//! are saved to memory, which keeps its contents, and the hart resumes at a
+//! resume address which rebuilds them from there. The kernel runs in machine
+//! mode without firmware below it, so it makes the system suspend call of the
+//! SBI itself. Each VirtIO driver resets its device through its power
+//! management callbacks, giving up its queues. Every hart other than the boot
+//! hart waits in a wfi loop from boot, so only the boot hart has state to
+//! save. On resume the drivers are initialized again, as the devices cannot
+//! be relied on to keep their configuration, with each VirtIO device
+//! renegotiating its features and queues.
+
+use crate::*;
+
+use fs::fstrait::Filesystem;
+
+/// State of the boot hart saved across a suspend, the layout must be kept in
+/// sync with suspend.s
+#[repr(C)]
+#[derive(Default)]
+pub struct SuspendContext
+{
+    regs: [usize; 16],
+    float_regs: [u64; 12],
+    fcsr: usize,
+    mstatus: usize,
+    mie: usize,
+    mtvec: usize,
+    mscratch: usize,
+    satp: usize,
+    mepc: usize,
+    pmpcfg0: usize,
+    pmpaddr0: usize
+}
+
+/// Bring the devices to rest before the suspend, the filesystems are written
+/// back so nothing is lost should the power go while suspended
+fn quiesce_devices()
+{
+    if let Some(vfs) = fs::vfs::get_vfs_reference()
+    {
+        if let Err(e) = vfs.sync()
+        {
+            kerrorln!("Unable to sync filesystems before suspend: {:?}", e);
+        }
+    }
+
+    drivers::virtio::suspend_devices();
+}
+
+/// Initialize the drivers again after a resume and restart the timer
+fn resume_devices()
+{
+    drivers::init_uart_driver();
+    drivers::init_plic_driver();
+    drivers::virtio::init_virtio_interrupts();
+    drivers::virtio::resume_devices();
+
+    unsafe { drivers::TIMER_DRIVER.trigger() };
+}
+
+/// Suspend the system to RAM until a device interrupt wakes it, the
+/// interrupt which woke the system is handled once the caller returns from
+/// the trap
+pub fn suspend_to_ram()
+{
+    kprintln!("Suspending the system");
+
+    quiesce_devices();
+
+    let mut context = SuspendContext::default();
+    let start = unsafe { &drivers::TIMER_DRIVER }.time();
+
+    unsafe { drivers::POWER_DRIVER.suspend(&mut context) };
+
+    let suspended = unsafe { &drivers::TIMER_DRIVER }.time() - start;
+
+    resume_devices();
+
+    kprintln!("Resumed after {}ms suspended", suspended.as_nanoseconds() / 1_000_000);
+}
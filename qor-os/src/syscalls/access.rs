@@ -0,0 +1,49 @@
+use crate::*;
+
+use libutils::paths::OwnedPath;
+
+use super::args::Flags;
+use super::utils::userspace_path_at;
+
+// Must be kept in sync with syscalls.h
+pub const F_OK: usize = 0;
+pub const X_OK: usize = 1;
+pub const W_OK: usize = 2;
+pub const R_OK: usize = 4;
+
+pub const AT_SYMLINK_NOFOLLOW: usize = 0x100;
+pub const AT_EACCESS: usize = 0x200;
+
+/// Check a process may access the file at a path in the given way
+fn check_access(proc: &mut super::Process, path: OwnedPath, mode: usize) -> Result<usize, usize>
+{
+    let stat = proc.stat(path)?;
+
+    // Permission bits are not yet checked against the credentials of the
+    // process, so each has the access of the superuser: reads and writes are
+    // always allowed, and anything other than a directory needs an execute
+    // bit set to be executed
+    if mode & X_OK != 0 && stat.mode & 0x4000 == 0 && stat.mode & 0o111 == 0
+    {
+        return Err(errno::EACCES);
+    }
+
+    Ok(0)
+}
+
+/// access Syscall
+pub fn syscall_access(proc: &mut super::Process, path: OwnedPath, mode: Flags<{F_OK | X_OK | W_OK | R_OK}>) -> Result<usize, usize>
+{
+    check_access(proc, path, mode.0)
+}
+
+/// faccessat Syscall
+///
+/// As there are no symbolic links, and access is not yet decided by the ids
+/// of a process, both flags are accepted and have no effect
+pub fn syscall_faccessat(proc: &mut super::Process, dirfd: isize, path: usize, mode: Flags<{F_OK | X_OK | W_OK | R_OK}>, _flags: Flags<{AT_SYMLINK_NOFOLLOW | AT_EACCESS}>) -> Result<usize, usize>
+{
+    let path = userspace_path_at(proc, dirfd, path)?;
+
+    check_access(proc, path, mode.0)
+}
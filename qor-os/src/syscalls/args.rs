@@ -0,0 +1,339 @@
+//! Syscall Argument Decoding
+//!
+//! Syscall handlers declare the types of their arguments, and the raw register
+//! values are converted and validated before the handler is called. A failed
+//! conversion returns its errno to userspace without ever reaching the handler.
+
+use crate::*;
+
+use core::alloc::Layout;
+use core::marker::PhantomData;
+use core::mem::MaybeUninit;
+
+use libutils::paths::OwnedPath;
+
+use super::Process;
+
+/// Number of raw arguments passed to a syscall
+pub const SYSCALL_ARG_COUNT: usize = 7;
+
+/// Raw syscall arguments as read from the trap frame
+pub type RawSyscallArgs = [usize; SYSCALL_ARG_COUNT];
+
+/// A value which can be decoded from a raw syscall argument
+pub trait SyscallArg: Sized
+{
+    /// Decode the argument, returning an errno if it is invalid
+    fn decode(proc: &mut Process, raw: usize) -> Result<Self, usize>;
+}
+
+impl SyscallArg for usize
+{
+    fn decode(_: &mut Process, raw: usize) -> Result<Self, usize>
+    {
+        Ok(raw)
+    }
+}
+
+impl SyscallArg for isize
+{
+    fn decode(_: &mut Process, raw: usize) -> Result<Self, usize>
+    {
+        Ok(raw as isize)
+    }
+}
+
+/// Paths are read from userspace and canonicalized against the working
+/// directory of the process
+impl SyscallArg for OwnedPath
+{
+    fn decode(proc: &mut Process, raw: usize) -> Result<Self, usize>
+    {
+        super::utils::userspace_string_to_path(proc, raw)
+    }
+}
+
+/// Pointer into the address space of a process
+#[derive(Debug)]
+pub struct UserPtr<T>
+{
+    addr: usize,
+    _marker: PhantomData<*mut T>
+}
+
+impl<T> Clone for UserPtr<T>
+{
+    fn clone(&self) -> Self
+    {
+        Self::new(self.addr)
+    }
+}
+
+impl<T> Copy for UserPtr<T> {}
+
+impl<T> UserPtr<T>
+{
+    /// Create a new user pointer from a virtual address
+    pub fn new(addr: usize) -> Self
+    {
+        Self
+        {
+            addr,
+            _marker: PhantomData
+        }
+    }
+
+    /// Get the virtual address of the pointer
+    pub fn addr(&self) -> usize
+    {
+        self.addr
+    }
+
+    /// Check if the pointer is null
+    pub fn is_null(&self) -> bool
+    {
+        self.addr == 0
+    }
+
+    /// Check that the whole object the pointer refers to is mapped in the
+    /// process, and writable by it if the kernel is going to write it
+    pub fn check(&self, proc: &mut Process, write: bool) -> Result<(), usize>
+    {
+        if self.is_null()
+        {
+            return Err(errno::EFAULT);
+        }
+
+        super::utils::user_buffer_segments(proc, self.addr, core::mem::size_of::<T>(), write).map(|_| ())
+    }
+
+    /// Get a mutable reference to the object the pointer refers to. An object
+    /// within a single page is used in place, one which crosses a page
+    /// boundary is copied into the kernel and copied back by
+    /// `finish_user_copies` once the ioctl using it has run, as consecutive
+    /// pages are not guaranteed to be physically contiguous
+    pub fn as_mut(&self, proc: &mut Process) -> Result<&'static mut T, usize>
+    {
+        self.check(proc, true)?;
+
+        if (self.addr % mem::PAGE_SIZE) + core::mem::size_of::<T>() <= mem::PAGE_SIZE
+        {
+            // Safety: The pointer was checked to be mapped in the process,
+            // and the kernel runs with paging disabled so the physical
+            // address is valid
+            let ptr = proc.map_mem(self.addr).map_err(|_| errno::EFAULT)? as *mut T;
+
+            return unsafe { ptr.as_mut() }.ok_or(errno::EFAULT);
+        }
+
+        let kernel = Box::into_raw(Box::new(self.read(proc)?));
+
+        proc.data.user_copies.push(UserCopy { addr: self.addr, kernel: kernel as *mut u8, layout: Layout::new::<T>() });
+
+        // Safety: The copy stays allocated until the ioctl has run
+        Ok(unsafe { &mut *kernel })
+    }
+
+    /// Read the object the pointer refers to
+    pub fn read(&self, proc: &Process) -> Result<T, usize>
+    {
+        if self.is_null()
+        {
+            return Err(errno::EFAULT);
+        }
+
+        let mut value = MaybeUninit::<T>::uninit();
+
+        // Safety: The buffer covers exactly the object being read
+        let bytes = unsafe { core::slice::from_raw_parts_mut(value.as_mut_ptr() as *mut u8, core::mem::size_of::<T>()) };
+        super::utils::copy_from_user(proc, self.addr, bytes)?;
+
+        // Safety: Every byte of the object was filled in from userspace
+        Ok(unsafe { value.assume_init() })
+    }
+
+    /// Write the object the pointer refers to, every page it covers is checked
+    /// before any of it is written
+    pub fn write(&self, proc: &mut Process, value: T) -> Result<(), usize>
+    {
+        self.check(proc, true)?;
+
+        // Safety: The slice covers exactly the object being written
+        let bytes = unsafe { core::slice::from_raw_parts(&value as *const T as *const u8, core::mem::size_of::<T>()) };
+
+        super::utils::copy_to_user(proc, self.addr, bytes)
+    }
+}
+
+/// Kernel copy of an object which crosses a page boundary in userspace, made
+/// by `UserPtr::as_mut`
+pub struct UserCopy
+{
+    addr: usize,
+    kernel: *mut u8,
+    layout: Layout
+}
+
+/// Release the kernel copies made by `UserPtr::as_mut` for the process, first
+/// writing them back to userspace if the command using them ran
+pub fn finish_user_copies(proc: &mut Process, write_back: bool) -> Result<(), usize>
+{
+    let mut result = Ok(());
+
+    for copy in core::mem::take(&mut proc.data.user_copies)
+    {
+        if write_back && result.is_ok()
+        {
+            // Safety: The copy was allocated with the layout of the object
+            let bytes = unsafe { core::slice::from_raw_parts(copy.kernel, copy.layout.size()) };
+            result = super::utils::copy_to_user(proc, copy.addr, bytes);
+        }
+
+        unsafe { alloc::alloc::dealloc(copy.kernel, copy.layout) };
+    }
+
+    result
+}
+
+/// Non null user pointers must be mapped in the process
+impl<T> SyscallArg for UserPtr<T>
+{
+    fn decode(proc: &mut Process, raw: usize) -> Result<Self, usize>
+    {
+        let ptr = Self::new(raw);
+        ptr.check(proc, false)?;
+
+        Ok(ptr)
+    }
+}
+
+/// Optional user pointers decode a null pointer to `None`
+impl<T> SyscallArg for Option<UserPtr<T>>
+{
+    fn decode(proc: &mut Process, raw: usize) -> Result<Self, usize>
+    {
+        if raw == 0
+        {
+            Ok(None)
+        }
+        else
+        {
+            UserPtr::decode(proc, raw).map(Some)
+        }
+    }
+}
+
+/// File descriptor which is open in the process
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fd(pub usize);
+
+impl SyscallArg for Fd
+{
+    fn decode(proc: &mut Process, raw: usize) -> Result<Self, usize>
+    {
+        if proc.data.descriptors.contains_key(&raw)
+        {
+            Ok(Fd(raw))
+        }
+        else
+        {
+            Err(errno::EBADF)
+        }
+    }
+}
+
+/// Set of flags, where any bits outside of `VALID` are rejected with `EINVAL`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Flags<const VALID: usize>(pub usize);
+
+impl<const VALID: usize> Flags<VALID>
+{
+    /// Check if any of the given flags are set
+    pub fn contains(&self, flags: usize) -> bool
+    {
+        self.0 & flags != 0
+    }
+}
+
+impl<const VALID: usize> SyscallArg for Flags<VALID>
+{
+    fn decode(_: &mut Process, raw: usize) -> Result<Self, usize>
+    {
+        if raw & !VALID != 0
+        {
+            Err(errno::EINVAL)
+        }
+        else
+        {
+            Ok(Flags(raw))
+        }
+    }
+}
+
+/// Value returned from a syscall handler
+pub trait SyscallReturn
+{
+    /// Convert the value to the raw value passed back to userspace
+    fn into_raw(self) -> usize;
+}
+
+impl SyscallReturn for usize
+{
+    fn into_raw(self) -> usize
+    {
+        self
+    }
+}
+
+impl SyscallReturn for Result<usize, usize>
+{
+    fn into_raw(self) -> usize
+    {
+        super::flatten_syscall_result(self)
+    }
+}
+
+/// Syscall handler which takes decoded arguments
+pub trait SyscallHandler<Args>
+{
+    /// Decode the raw arguments and call the handler
+    fn call(self, proc: &mut Process, raw: &RawSyscallArgs) -> usize;
+}
+
+macro_rules! impl_syscall_handler
+{
+    ($($arg:ident $value:ident $index:tt),*) =>
+    {
+        impl<Func, Ret, $($arg: SyscallArg),*> SyscallHandler<($($arg,)*)> for Func
+            where Func: FnOnce(&mut Process, $($arg),*) -> Ret, Ret: SyscallReturn
+        {
+            #[allow(unused_variables)]
+            fn call(self, proc: &mut Process, raw: &RawSyscallArgs) -> usize
+            {
+                $(
+                    let $value = match <$arg as SyscallArg>::decode(proc, raw[$index])
+                    {
+                        Ok(value) => value,
+                        Err(e) => { return e; }
+                    };
+                )*
+
+                self(proc, $($value),*).into_raw()
+            }
+        }
+    };
+}
+
+impl_syscall_handler!();
+impl_syscall_handler!(A0 a0 0);
+impl_syscall_handler!(A0 a0 0, A1 a1 1);
+impl_syscall_handler!(A0 a0 0, A1 a1 1, A2 a2 2);
+impl_syscall_handler!(A0 a0 0, A1 a1 1, A2 a2 2, A3 a3 3);
+impl_syscall_handler!(A0 a0 0, A1 a1 1, A2 a2 2, A3 a3 3, A4 a4 4);
+impl_syscall_handler!(A0 a0 0, A1 a1 1, A2 a2 2, A3 a3 3, A4 a4 4, A5 a5 5);
+
+/// Decode the arguments for a syscall handler and call it
+pub fn dispatch<Args, H: SyscallHandler<Args>>(proc: &mut Process, handler: H, raw: &RawSyscallArgs) -> usize
+{
+    handler.call(proc, raw)
+}
@@ -0,0 +1,11 @@
+/// brk Syscall
+///
+/// Move the program break to the given address, returning the new break, or
+/// the current break if it could not be moved. Passing zero queries the
+/// current break, which is how sbrk is built in userspace
+pub fn syscall_brk(proc: &mut super::Process, addr: usize) -> usize
+{
+    let space = proc.address_space();
+
+    space.set_program_break(addr).unwrap_or(space.data.program_break)
+}
@@ -1,18 +1,17 @@
 use crate::*;
 
-use fs::fstrait::Filesystem;
 use libutils::paths::OwnedPath;
 
 /// chdir Syscall
-pub fn syscall_chdir(proc: &mut super::Process, path_ptr: usize) -> Result<usize, usize>
+pub fn syscall_chdir(proc: &mut super::Process, path: OwnedPath) -> Result<usize, usize>
 {
-    let path = super::utils::userspace_string_to_path(proc, path_ptr)?;
-    
     proc.ensure_fs();
 
-    if let Ok(inode) = proc.fs_interface.as_mut().unwrap().path_to_inode(&path)
+    if let Ok(inode) = fs::namespace::path_to_inode(&proc.data.mount_namespace, proc.data.root, proc.fs_interface.as_mut().unwrap(), &path)
     {
-        if let Ok(path) = proc.fs_interface.as_mut().unwrap().inode_to_path(inode)
+        let resolved = fs::namespace::inode_to_path(&proc.data.mount_namespace, proc.data.root, proc.fs_interface.as_mut().unwrap(), inode);
+
+        if let Ok(path) = resolved
         {
             if path.as_str().len() == 0
             {
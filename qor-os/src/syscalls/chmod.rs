@@ -0,0 +1,52 @@
+use crate::*;
+
+use libutils::paths::OwnedPath;
+
+use super::args::Fd;
+use super::utils::userspace_path_at;
+
+/// Set the permission bits of an inode, which only its owner and the
+/// superuser may do
+fn set_mode(proc: &mut super::Process, inode: crate::fs::structures::FilesystemIndex, mode: usize) -> Result<usize, usize>
+{
+    let credentials = proc.data.credentials;
+
+    proc.update_stat(inode, |stat|
+    {
+        if !credentials.may_change_mode(stat.uid as u32)
+        {
+            return Err(errno::EPERM);
+        }
+
+        stat.mode = mode as u16 & 0o7777;
+
+        Ok(())
+    })?;
+
+    Ok(0)
+}
+
+/// chmod Syscall
+pub fn syscall_chmod(proc: &mut super::Process, path: OwnedPath, mode: usize) -> Result<usize, usize>
+{
+    let inode = proc.path_inode(&path)?;
+
+    set_mode(proc, inode, mode)
+}
+
+/// fchmod Syscall
+pub fn syscall_fchmod(proc: &mut super::Process, fd: Fd, mode: usize) -> Result<usize, usize>
+{
+    let inode = proc.descriptor_inode(fd.0)?;
+
+    set_mode(proc, inode, mode)
+}
+
+/// fchmodat Syscall
+pub fn syscall_fchmodat(proc: &mut super::Process, dirfd: isize, path: usize, mode: usize) -> Result<usize, usize>
+{
+    let path = userspace_path_at(proc, dirfd, path)?;
+    let inode = proc.path_inode(&path)?;
+
+    set_mode(proc, inode, mode)
+}
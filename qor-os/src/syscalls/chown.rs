@@ -0,0 +1,90 @@
+use crate::*;
+
+use libutils::paths::OwnedPath;
+
+use super::args::{Fd, Flags};
+use super::utils::userspace_path_at;
+
+use super::access::AT_SYMLINK_NOFOLLOW;
+
+use crate::process::credentials::{ID_UNCHANGED, S_ISGID, S_ISUID};
+
+/// Set the owner and group of an inode, only the superuser may give a file
+/// to another user. Changing either id clears the set user and set group
+/// bits, so a file cannot keep privileges granted to its old owner. Ids which
+/// do not fit in an inode are rejected with `EINVAL`.
+fn set_owner(proc: &mut super::Process, inode: crate::fs::structures::FilesystemIndex, owner: usize, group: usize) -> Result<usize, usize>
+{
+    let (owner, group) = (owner as u32, group as u32);
+
+    // Inodes only have room for 16 bit ids
+    if [owner, group].iter().any(|id| *id != ID_UNCHANGED && *id > u16::MAX as u32)
+    {
+        return Err(errno::EINVAL);
+    }
+
+    let credentials = proc.data.credentials;
+
+    proc.update_stat(inode, |stat|
+    {
+        if !credentials.may_change_owner(stat.uid as u32, stat.gid as u32, owner, group)
+        {
+            return Err(errno::EPERM);
+        }
+
+        let old = (stat.uid, stat.gid);
+
+        if owner != ID_UNCHANGED
+        {
+            stat.uid = owner as u16;
+        }
+
+        if group != ID_UNCHANGED
+        {
+            stat.gid = group as u16;
+        }
+
+        if (stat.uid, stat.gid) != old
+        {
+            stat.mode &= !(S_ISUID | S_ISGID);
+        }
+
+        Ok(())
+    })?;
+
+    Ok(0)
+}
+
+/// chown Syscall
+pub fn syscall_chown(proc: &mut super::Process, path: OwnedPath, owner: usize, group: usize) -> Result<usize, usize>
+{
+    let inode = proc.path_inode(&path)?;
+
+    set_owner(proc, inode, owner, group)
+}
+
+/// fchown Syscall
+pub fn syscall_fchown(proc: &mut super::Process, fd: Fd, owner: usize, group: usize) -> Result<usize, usize>
+{
+    let inode = proc.descriptor_inode(fd.0)?;
+
+    set_owner(proc, inode, owner, group)
+}
+
+/// lchown Syscall
+///
+/// Paths are never resolved through symbolic links, so this is the same as
+/// chown
+pub fn syscall_lchown(proc: &mut super::Process, path: OwnedPath, owner: usize, group: usize) -> Result<usize, usize>
+{
+    syscall_chown(proc, path, owner, group)
+}
+
+/// fchownat Syscall
+pub fn syscall_fchownat(proc: &mut super::Process, dirfd: isize, path: usize, owner: usize, group: usize, _flags: Flags<AT_SYMLINK_NOFOLLOW>) -> Result<usize, usize>
+{
+    let path = userspace_path_at(proc, dirfd, path)?;
+    let inode = proc.path_inode(&path)?;
+
+    set_owner(proc, inode, owner, group)
+}
@@ -0,0 +1,30 @@
+use crate::*;
+
+use fs::fstrait::Filesystem;
+use libutils::paths::OwnedPath;
+
+/// chroot Syscall
+///
+/// Change the directory absolute paths are resolved from, which requires
+/// privilege. As the working directory is kept as a path, it would be read
+/// relative to the new root, so it is moved to the new root.
+pub fn syscall_chroot(proc: &mut super::Process, path: OwnedPath) -> Result<usize, usize>
+{
+    if !proc.is_privileged()
+    {
+        return Err(errno::EPERM);
+    }
+
+    proc.ensure_fs();
+    let vfs = proc.fs_interface.as_mut().unwrap();
+
+    let inode = fs::namespace::path_to_inode(&proc.data.mount_namespace, proc.data.root, vfs, &path).map_err(|_| errno::ENOENT)?;
+    vfs.assert_directory(inode).map_err(|e| e.to_errno())?;
+
+    kdebugln!(Syscalls, "PID {} changing root to {} ({:?})", proc.pid, path, inode);
+
+    proc.data.root = Some(inode);
+    proc.data.cwd = OwnedPath::new("/");
+
+    Ok(0)
+}
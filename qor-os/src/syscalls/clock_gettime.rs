@@ -0,0 +1,53 @@
+use crate::*;
+
+use super::args::UserPtr;
+
+// Must be kept in sync with syscalls.h
+pub const CLOCK_REALTIME: usize =      0;
+pub const CLOCK_MONOTONIC: usize =     1;
+pub const CLOCK_MONOTONIC_RAW: usize = 4;
+pub const CLOCK_BOOTTIME: usize =      7;
+
+/// Time as seconds and nanoseconds
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct TimeSpec
+{
+    pub seconds: usize,
+    pub nano_seconds: usize
+}
+
+impl TimeSpec
+{
+    /// Split a number of nanoseconds into a timespec
+    pub fn from_nanoseconds(nanoseconds: usize) -> Self
+    {
+        Self
+        {
+            seconds: nanoseconds / 1_000_000_000,
+            nano_seconds: nanoseconds % 1_000_000_000
+        }
+    }
+}
+
+/// Read a clock in nanoseconds, the realtime clock comes from the RTC and
+/// the monotonic clocks count from boot using the machine timer
+pub fn read_clock(clock: usize) -> Result<usize, usize>
+{
+    match clock
+    {
+        CLOCK_REALTIME => Ok(drivers::rtc::RealTimeClockDriver::get_driver().get_unix_timestamp_nano() as usize),
+        CLOCK_MONOTONIC | CLOCK_MONOTONIC_RAW | CLOCK_BOOTTIME => Ok(unsafe { &drivers::TIMER_DRIVER }.time().as_nanoseconds()),
+        _ => Err(errno::EINVAL)
+    }
+}
+
+/// clock_gettime Syscall
+pub fn syscall_clock_gettime(proc: &mut super::Process, clock: usize, time: UserPtr<TimeSpec>) -> Result<usize, usize>
+{
+    let now = read_clock(clock)?;
+
+    time.write(proc, TimeSpec::from_nanoseconds(now))?;
+
+    Ok(0)
+}
@@ -0,0 +1,108 @@
+use crate::*;
+
+use super::args::{Flags, UserPtr};
+
+// Must be kept in sync with syscalls.h
+const CSIGNAL: usize =              0x000000ff;
+const CLONE_VM: usize =             0x00000100;
+const CLONE_FILES: usize =          0x00000400;
+const CLONE_SETTLS: usize =         0x00080000;
+const CLONE_PARENT_SETTID: usize =  0x00100000;
+const CLONE_CHILD_CLEARTID: usize = 0x00200000;
+const CLONE_CHILD_SETTID: usize =   0x01000000;
+
+const SUPPORTED_FLAGS: usize = CSIGNAL | CLONE_VM | CLONE_FILES | CLONE_SETTLS | CLONE_PARENT_SETTID | CLONE_CHILD_CLEARTID | CLONE_CHILD_SETTID;
+
+/// Write the tid of a newly cloned child wherever the flags ask for it, unless
+/// the address space is shared, the parent tid is written to both copies
+fn write_tids(proc: &mut super::Process, forked: &mut super::Process, flags: &Flags<SUPPORTED_FLAGS>, parent_tid: Option<UserPtr<u32>>, child_tid: Option<UserPtr<u32>>) -> Result<(), usize>
+{
+    let pid = forked.pid as u32;
+
+    if let Some(parent_tid) = parent_tid.filter(|_| flags.contains(CLONE_PARENT_SETTID))
+    {
+        parent_tid.write(proc, pid)?;
+
+        if !flags.contains(CLONE_VM)
+        {
+            parent_tid.write(forked, pid)?;
+        }
+    }
+
+    if let Some(child_tid) = child_tid.filter(|_| flags.contains(CLONE_CHILD_SETTID))
+    {
+        child_tid.write(forked, pid)?;
+    }
+
+    Ok(())
+}
+
+/// clone Syscall
+///
+/// The child gets a copy of the address space and descriptor table, unless
+/// `CLONE_VM` or `CLONE_FILES` is given to share them with the parent. A child
+/// sharing the address space runs on the stack it is given, and exits along
+/// with the owner of the address space.
+pub fn syscall_clone(proc: &mut super::Process, flags: Flags<SUPPORTED_FLAGS>, stack: usize, parent_tid: Option<UserPtr<u32>>, child_tid: Option<UserPtr<u32>>, tls: usize) -> Result<usize, usize>
+{
+    kdebugln!(Syscalls, "PID {} cloning with flags 0x{:x}", proc.pid, flags.0);
+
+    // Two processes cannot run on the same stack
+    if flags.contains(CLONE_VM) && stack == 0
+    {
+        return Err(errno::EINVAL);
+    }
+
+    // The pointers the tids are written through must be given
+    if (flags.contains(CLONE_PARENT_SETTID) && parent_tid.is_none()) || (flags.contains(CLONE_CHILD_SETTID) && child_tid.is_none())
+    {
+        return Err(errno::EFAULT);
+    }
+
+    process::scheduler::check_process_limits(proc)?;
+
+    let mut forked = if flags.contains(CLONE_VM)
+    {
+        proc.forked_sharing_memory()
+    }
+    else
+    {
+        proc.forked()
+    };
+
+    if flags.contains(CLONE_FILES)
+    {
+        forked.data.descriptors = proc.data.descriptors.share();
+    }
+
+    let pid = forked.pid;
+
+    let frame = unsafe { forked.frame.as_mut().unwrap() };
+
+    if stack != 0
+    {
+        frame.regs[2] = stack;
+    }
+
+    if flags.contains(CLONE_SETTLS)
+    {
+        frame.regs[4] = tls;
+    }
+
+    // The child is not runnable yet, so if either tid cannot be written it
+    // is dropped again, freeing its memory, and forgotten by the parent
+    if let Err(e) = write_tids(proc, &mut forked, &flags, parent_tid, child_tid)
+    {
+        proc.remove_child(pid);
+        return Err(e);
+    }
+
+    if flags.contains(CLONE_CHILD_CLEARTID)
+    {
+        forked.data.clear_child_tid = child_tid.map(|ptr| ptr.addr());
+    }
+
+    process::scheduler::add_process(forked);
+
+    Ok(pid as usize)
+}
@@ -0,0 +1,105 @@
+use crate::*;
+
+use process::credentials::IdSet;
+
+use super::args::UserPtr;
+
+/// Write the real, effective and saved ids of a set back to userspace
+fn write_ids(proc: &mut super::Process, ids: IdSet, real: UserPtr<u32>, effective: UserPtr<u32>, saved: UserPtr<u32>) -> Result<usize, usize>
+{
+    real.write(proc, ids.real)?;
+    effective.write(proc, ids.effective)?;
+    saved.write(proc, ids.saved)?;
+
+    Ok(0)
+}
+
+/// getuid Syscall
+pub fn syscall_getuid(proc: &mut super::Process) -> usize
+{
+    proc.data.credentials.user.real as usize
+}
+
+/// geteuid Syscall
+pub fn syscall_geteuid(proc: &mut super::Process) -> usize
+{
+    proc.data.credentials.user.effective as usize
+}
+
+/// getgid Syscall
+pub fn syscall_getgid(proc: &mut super::Process) -> usize
+{
+    proc.data.credentials.group.real as usize
+}
+
+/// getegid Syscall
+pub fn syscall_getegid(proc: &mut super::Process) -> usize
+{
+    proc.data.credentials.group.effective as usize
+}
+
+/// getresuid Syscall
+pub fn syscall_getresuid(proc: &mut super::Process, real: UserPtr<u32>, effective: UserPtr<u32>, saved: UserPtr<u32>) -> Result<usize, usize>
+{
+    write_ids(proc, proc.data.credentials.user, real, effective, saved)
+}
+
+/// getresgid Syscall
+pub fn syscall_getresgid(proc: &mut super::Process, real: UserPtr<u32>, effective: UserPtr<u32>, saved: UserPtr<u32>) -> Result<usize, usize>
+{
+    write_ids(proc, proc.data.credentials.group, real, effective, saved)
+}
+
+/// setuid Syscall
+pub fn syscall_setuid(proc: &mut super::Process, uid: usize) -> Result<usize, usize>
+{
+    let privileged = proc.is_privileged();
+    proc.data.credentials.user.set(uid as u32, privileged)?;
+
+    Ok(0)
+}
+
+/// setgid Syscall
+pub fn syscall_setgid(proc: &mut super::Process, gid: usize) -> Result<usize, usize>
+{
+    let privileged = proc.is_privileged();
+    proc.data.credentials.group.set(gid as u32, privileged)?;
+
+    Ok(0)
+}
+
+/// setreuid Syscall
+pub fn syscall_setreuid(proc: &mut super::Process, real: usize, effective: usize) -> Result<usize, usize>
+{
+    let privileged = proc.is_privileged();
+    proc.data.credentials.user.set_real_effective(real as u32, effective as u32, privileged)?;
+
+    Ok(0)
+}
+
+/// setregid Syscall
+pub fn syscall_setregid(proc: &mut super::Process, real: usize, effective: usize) -> Result<usize, usize>
+{
+    let privileged = proc.is_privileged();
+    proc.data.credentials.group.set_real_effective(real as u32, effective as u32, privileged)?;
+
+    Ok(0)
+}
+
+/// setresuid Syscall
+pub fn syscall_setresuid(proc: &mut super::Process, real: usize, effective: usize, saved: usize) -> Result<usize, usize>
+{
+    let privileged = proc.is_privileged();
+    proc.data.credentials.user.set_all(real as u32, effective as u32, saved as u32, privileged)?;
+
+    Ok(0)
+}
+
+/// setresgid Syscall
+pub fn syscall_setresgid(proc: &mut super::Process, real: usize, effective: usize, saved: usize) -> Result<usize, usize>
+{
+    let privileged = proc.is_privileged();
+    proc.data.credentials.group.set_all(real as u32, effective as u32, saved as u32, privileged)?;
+
+    Ok(0)
+}
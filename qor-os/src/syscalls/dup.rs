@@ -1,17 +1,39 @@
 use crate::*;
 
+use process::descriptor::O_CLOEXEC;
+
+use super::args::Flags;
+
 /// Dup Syscall
-pub fn syscall_dup(proc: &mut super::Process, old_fd: usize)-> usize
+pub fn syscall_dup(proc: &mut super::Process, old_fd: usize)-> Result<usize, usize>
 {
-    kdebugln!(Syscalls, "Duplicating FD {}on Process PID {}", old_fd, proc.pid);
+    kdebugln!(Syscalls, "Duplicating FD {} on Process PID {}", old_fd, proc.pid);
 
     proc.dup(old_fd, None)
 }
 
 /// Dup2 Syscall
-pub fn syscall_dup2(proc: &mut super::Process, old_fd: usize, new_fd: usize)-> usize
+pub fn syscall_dup2(proc: &mut super::Process, old_fd: usize, new_fd: usize)-> Result<usize, usize>
 {
     kdebugln!(Syscalls, "Duplicating FD {} to {} on Process PID {}", old_fd, new_fd, proc.pid);
 
     proc.dup(old_fd, Some(new_fd))
-}
\ No newline at end of file
+}
+
+/// Dup3 Syscall
+pub fn syscall_dup3(proc: &mut super::Process, old_fd: usize, new_fd: usize, flags: Flags<O_CLOEXEC>) -> Result<usize, usize>
+{
+    kdebugln!(Syscalls, "Duplicating FD {} to {} with flags {:x} on Process PID {}", old_fd, new_fd, flags.0, proc.pid);
+
+    // Unlike dup2, dup3 refuses to duplicate a descriptor onto itself
+    if old_fd == new_fd
+    {
+        return Err(errno::EINVAL);
+    }
+
+    let result = proc.dup(old_fd, Some(new_fd))?;
+
+    proc.set_close_on_exec(result, flags.contains(O_CLOEXEC));
+
+    Ok(result)
+}
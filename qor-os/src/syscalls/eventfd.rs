@@ -0,0 +1,38 @@
+use crate::*;
+
+use process::descriptor::{FileDescriptor, O_CLOEXEC, O_NONBLOCK, O_RDONLY, O_WRONLY};
+use process::eventfd::EventDescriptor;
+
+use super::args::Flags;
+
+// Mirror the definitions in syscalls.h
+/*
+    #define EFD_SEMAPHORE 1
+    #define EFD_NONBLOCK O_NONBLOCK
+    #define EFD_CLOEXEC O_CLOEXEC
+*/
+
+const EFD_SEMAPHORE: usize = 1;
+const EFD_NONBLOCK: usize = O_NONBLOCK;
+const EFD_CLOEXEC: usize = O_CLOEXEC;
+
+/// eventfd Syscall
+pub fn syscall_eventfd(proc: &mut super::Process, initial: usize) -> Result<usize, usize>
+{
+    syscall_eventfd2(proc, initial, Flags(0))
+}
+
+/// eventfd2 Syscall
+///
+/// Create a descriptor holding a counter which starts at the given value,
+/// the value is an unsigned int so only its low 32 bits are used
+pub fn syscall_eventfd2(proc: &mut super::Process, initial: usize, flags: Flags<{ EFD_SEMAPHORE | EFD_NONBLOCK | EFD_CLOEXEC }>) -> Result<usize, usize>
+{
+    let mut descriptor = EventDescriptor::new(initial as u32 as u64, flags.contains(EFD_SEMAPHORE));
+    descriptor.set_status_flags(O_RDONLY | O_WRONLY | (flags.0 & EFD_NONBLOCK));
+
+    let fd = proc.add_descriptor(Box::new(descriptor))?;
+    proc.set_close_on_exec(fd, flags.contains(EFD_CLOEXEC));
+
+    Ok(fd)
+}
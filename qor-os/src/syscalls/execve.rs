@@ -1,113 +1,96 @@
 use crate::*;
 
-use alloc::format;
 use libutils::paths::OwnedPath;
 
-/// Execve Syscall
-pub fn syscall_execve(proc: &mut super::Process, path_ptr: usize, argv_ptr: usize, envp_ptr: usize) -> usize
-{
-    let path_ptr = proc.map_mem(path_ptr).unwrap() as *mut u8;
-    let argv_ptr = proc.map_mem(argv_ptr).unwrap() as *mut usize;
-    let envp_ptr = proc.map_mem(envp_ptr).unwrap() as *mut usize;
+use super::args::UserPtr;
 
-    // Convert argv_ptr to a vector of &[u8]
-    let mut argv_vecs = Vec::new();
+/// Read a null terminated array of userspace strings
+fn read_string_array(proc: &super::Process, array: Option<UserPtr<usize>>) -> Result<Vec<String>, usize>
+{
+    let mut result = Vec::new();
 
-    for i in 0..
+    if let Some(array) = array
     {
-        // Get the pointer at the given value
-        let ptr = unsafe { argv_ptr.add(i).read() };   
-        if ptr == 0 { break };
-
-        // Convert the pointer to a physical address
-        let ptr = proc.map_mem(ptr).unwrap() as *mut u8;
-
-        let mut current_vec = Vec::new();
-
-        for j in 0..
+        loop
         {
-            let v = unsafe { ptr.add(j).read() };
-            current_vec.push(v);
-            if v == 0 { break };
-        }
+            let ptr = UserPtr::<usize>::new(array.addr() + result.len() * core::mem::size_of::<usize>()).read(proc)?;
+            if ptr == 0 { break; }
 
-        argv_vecs.push(current_vec);
-    }
+            if result.len() == super::utils::MAX_ARG_COUNT
+            {
+                return Err(errno::E2BIG);
+            }
 
-    let mut argv_vals = Vec::with_capacity(argv_vecs.len());
+            let value = super::utils::string_from_user(proc, ptr, super::utils::MAX_ARG_LENGTH)
+                .map_err(|e| if e == errno::ENAMETOOLONG { errno::E2BIG } else { e })?;
 
-    for v in argv_vecs
-    {
-        argv_vals.push(unsafe { String::from_utf8_unchecked(v) });
+            result.push(value);
+        }
     }
 
-    
-    // Convert envp_ptr to a vector of &[u8]
-    let mut envp_vecs = Vec::new();
+    Ok(result)
+}
 
-    for i in 0..
-    {
-        // Get the pointer at the given value
-        let ptr = unsafe { envp_ptr.add(i).read() };   
-        if ptr == 0 { break };
-
-        // Convert the pointer to a physical address
-        let ptr = proc.map_mem(ptr).unwrap() as *mut u8;
-
-        let mut current_vec = Vec::new();
-
-        for j in 0..
-        {
-            let v = unsafe { ptr.add(j).read() };
-            current_vec.push(v);
-            if v == 0 { break };
-        }
+/// Execve Syscall
+///
+/// Replace the process with the program at `path`, passing it copies of the
+/// null terminated argv and envp arrays along with an auxiliary vector
+pub fn syscall_execve(proc: &mut super::Process, path: OwnedPath, argv: Option<UserPtr<usize>>, envp: Option<UserPtr<usize>>) -> Result<usize, usize>
+{
+    let mut argv_vals = read_string_array(proc, argv)?;
+    let mut envp_vals = read_string_array(proc, envp)?;
 
-        envp_vecs.push(current_vec);
-    }
+    // Ensure the filesystem has been initialized
+    proc.ensure_fs();
 
-    let mut envp_vals = Vec::with_capacity(envp_vecs.len());
+    // The owner and mode of the file decide the credentials the program runs with
+    let stat = proc.stat(path.clone())?;
 
-    for v in envp_vecs
+    // Create a process from an elf file
+    if let Ok(mut new_proc) = process::loading::load_process(proc.fs_interface.as_mut().unwrap(), &proc.data.mount_namespace, proc.data.root, &path, &mut argv_vals, &mut envp_vals)
+    // if true
     {
-        envp_vals.push(unsafe { String::from_utf8_unchecked(v) });
-    }
-    
-    // Ensure the filesystem has been initialized
-    proc.ensure_fs();
+        new_proc.data.descriptors = proc.exec_descriptors();
 
-    let mut path = String::new();
+        new_proc.data.cwd = proc.data.cwd.clone();
+        new_proc.data.root = proc.data.root;
+        new_proc.data.umask = proc.data.umask;
 
-    let mut i = 0; 
+        // Once set, no_new_privs holds for every program run afterwards
+        new_proc.data.no_new_privs = proc.data.no_new_privs;
+        new_proc.data.credentials = proc.data.credentials.after_exec(stat.mode, stat.uid as u32, stat.gid as u32, proc.data.no_new_privs);
 
-    loop
-    {
-        let v = unsafe { path_ptr.add(i).read() } as char;
+        new_proc.data.process_group_id = proc.data.process_group_id;
+        new_proc.data.session_id = proc.data.session_id;
 
-        if v == '\x00' { break; }
+        // The parent can no longer move the process to another group
+        new_proc.data.has_executed = true;
 
-        path.push(v);
+        new_proc.data.mount_namespace = proc.data.mount_namespace.clone();
 
-        i += 1;
-    }
+        new_proc.data.resource_group = proc.data.resource_group;
+        new_proc.data.limits = proc.data.limits;
 
-    if !path.starts_with("/")
-    {
-        path = format!("{}{}", proc.data.cwd, path);
-    }
-     
-    // Create a process from an elf file
-    if let Ok(mut new_proc) = process::loading::load_process(proc.fs_interface.as_mut().unwrap(), &OwnedPath::new(path), &mut argv_vals, &mut envp_vals)
-    // if true
-    {
-        new_proc.data.descriptors = proc.data.descriptors.clone();
+        // The loaded image takes the place of the old one, and must fit within
+        // the memory limit of the group, dropping it frees the memory again
+        let old_pages = if proc.data.memory_owner.is_none() { proc.resident_pages() } else { 0 };
 
-        new_proc.data.cwd = proc.data.cwd.clone();
+        if !process::rescg::can_charge_memory(proc, new_proc.resident_pages().saturating_sub(old_pages))
+        {
+            return Err(errno::ENOMEM);
+        }
 
-        new_proc.data.process_group_id = proc.data.process_group_id;
+        new_proc.data.priority = proc.data.priority;
 
         new_proc.set_arguments(&argv_vals, &envp_vals);
 
+        // The old address space goes away with the old image, along with any
+        // process running in it
+        if proc.data.memory_owner.is_none()
+        {
+            process::scheduler::kill_memory_sharers(proc.pid, 128 + process::signals::SignalType::SIGKILL as usize);
+        }
+
         process::scheduler::replace_process(proc.pid, new_proc);
         
         let schedule = process::scheduler::schedule_next();
@@ -115,6 +98,6 @@ pub fn syscall_execve(proc: &mut super::Process, path_ptr: usize, argv_ptr: usiz
     }
     else
     {
-        errno::ENOENT // File not found
+        Err(errno::ENOENT) // File not found
     }
 }
\ No newline at end of file
@@ -0,0 +1,60 @@
+use crate::*;
+
+use process::descriptor::{FD_CLOEXEC, O_CHANGEABLE_FLAGS};
+
+use super::args::Fd;
+
+// Must be kept in sync with syscalls.h
+const F_DUPFD: usize = 0;
+const F_GETFD: usize = 1;
+const F_SETFD: usize = 2;
+const F_GETFL: usize = 3;
+const F_SETFL: usize = 4;
+const F_DUPFD_CLOEXEC: usize = 1030;
+const F_SETPIPE_SZ: usize = 1031;
+const F_GETPIPE_SZ: usize = 1032;
+
+/// Duplicate a descriptor onto the lowest unused descriptor at or above `min`
+fn duplicate(proc: &mut super::Process, fd: usize, min: usize, close_on_exec: bool) -> Result<usize, usize>
+{
+    let result = proc.dup_above(fd, min)?;
+
+    proc.set_close_on_exec(result, close_on_exec);
+
+    Ok(result)
+}
+
+/// fcntl Syscall
+pub fn syscall_fcntl(proc: &mut super::Process, fd: Fd, cmd: usize, arg: usize) -> Result<usize, usize>
+{
+    let descriptor = proc.data.descriptors.get(&fd.0).ok_or(errno::EBADF)?;
+
+    match cmd
+    {
+        F_DUPFD => duplicate(proc, fd.0, arg, false),
+        F_DUPFD_CLOEXEC => duplicate(proc, fd.0, arg, true),
+        F_GETFD => Ok(proc.data.descriptors.flags(&fd.0)),
+        F_SETFD =>
+        {
+            proc.set_close_on_exec(fd.0, arg & FD_CLOEXEC > 0);
+            Ok(0)
+        },
+        F_GETFL => Ok(descriptor.borrow().get_status_flags()),
+        F_SETFL =>
+        {
+            // Only the append and non blocking flags can be changed, the rest
+            // are silently ignored
+            let current = descriptor.borrow().get_status_flags();
+            descriptor.borrow_mut().set_status_flags((current & !O_CHANGEABLE_FLAGS) | (arg & O_CHANGEABLE_FLAGS));
+
+            Ok(0)
+        },
+        F_SETPIPE_SZ => descriptor.borrow_mut().set_pipe_size(arg),
+        F_GETPIPE_SZ => descriptor.borrow().get_pipe_size(),
+        _ =>
+        {
+            kwarnln!("Unknown fcntl command {} from PID {}", cmd, proc.pid);
+            Err(errno::EINVAL)
+        }
+    }
+}
@@ -0,0 +1,53 @@
+use crate::*;
+
+use fs::lock::LockKind;
+
+use super::args::Fd;
+
+// Must be kept in sync with syscalls.h
+pub const LOCK_SH: usize = 1;
+pub const LOCK_EX: usize = 2;
+pub const LOCK_NB: usize = 4;
+pub const LOCK_UN: usize = 8;
+
+/// flock Syscall
+///
+/// Take or release an advisory lock on the file open at a descriptor. A lock
+/// which conflicts with one held through another open file blocks until it
+/// is released, unless `LOCK_NB` is given. Locks are released when the last
+/// descriptor referring to the open file is closed.
+pub fn syscall_flock(proc: &mut super::Process, fd: Fd, operation: usize) -> Result<usize, usize>
+{
+    let desc = proc.data.descriptors.get(&fd.0).ok_or(errno::EBADF)?;
+    let inode = desc.borrow_mut().get_inode().ok_or(errno::EINVAL)?;
+
+    let kind = match operation & !LOCK_NB
+    {
+        LOCK_SH => Some(LockKind::Shared),
+        LOCK_EX => Some(LockKind::Exclusive),
+        LOCK_UN => None,
+        _ => return Err(errno::EINVAL)
+    };
+
+    proc.ensure_fs();
+    let locks = &mut proc.fs_interface.as_mut().unwrap().locks;
+
+    if let Some(kind) = kind
+    {
+        match locks.lock(inode, &desc, kind)
+        {
+            Err(errno::EAGAIN) if operation & LOCK_NB == 0 =>
+            {
+                drop(desc);
+                super::utils::yield_and_retry(proc)
+            },
+            result => result?
+        }
+    }
+    else
+    {
+        locks.unlock(inode, &desc);
+    }
+
+    Ok(0)
+}
@@ -1,8 +1,10 @@
 use crate::*;
 
 /// Fork Syscall
-pub fn syscall_fork(proc: &mut super::Process) -> usize
+pub fn syscall_fork(proc: &mut super::Process) -> Result<usize, usize>
 {
+    process::scheduler::check_process_limits(proc)?;
+
     // Get the forked process
     let forked = proc.forked();
 
@@ -10,5 +12,5 @@ pub fn syscall_fork(proc: &mut super::Process) -> usize
 
     process::scheduler::add_process(forked);
 
-    pid as usize
+    Ok(pid as usize)
 }
\ No newline at end of file
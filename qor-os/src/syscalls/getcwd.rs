@@ -1,25 +1,18 @@
+use crate::*;
+
 /// Getcwd Syscall
-pub fn syscall_getcwd(proc: &mut super::Process, buffer_ptr: usize, size: usize) -> usize
+pub fn syscall_getcwd(proc: &mut super::Process, buffer_ptr: usize, size: usize) -> Result<usize, usize>
 {
-    let buffer = proc.map_mem(buffer_ptr).unwrap() as *mut u8;
+    let mut cwd = Vec::from(proc.data.cwd.as_str().as_bytes());
+    cwd.push(0);
 
-    let mut i = 0;
-    for c in proc.data.cwd.as_str().as_bytes()
+    // The path and its terminator must fit in the buffer
+    if cwd.len() > size
     {
-        if i == size
-        {
-            break;
-        }
-        else
-        {
-            unsafe 
-            {
-                buffer.add(i).write(*c);
-            }
-        }
-
-        i += 1;
+        return Err(errno::ERANGE);
     }
 
-    i
-}
\ No newline at end of file
+    super::utils::copy_to_user(proc, buffer_ptr, &cwd)?;
+
+    Ok(cwd.len())
+}
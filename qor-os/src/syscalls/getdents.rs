@@ -1,58 +1,126 @@
-/// Write a directory entry at the given pointer, returning the number of bytes written
-fn write_dir_entry(mut ptr: usize, inode: usize, offset: usize, name: &str) -> usize
-{
-    let length = 8 + 8 + 2 + name.len() + 1;
+use crate::*;
 
-    // Write the inode number
-    unsafe { (ptr as *mut u64).write(inode as u64); }
-    ptr += 8;
+use fs::structures::{DirectoryEntry, DirectoryEntryType};
+use process::descriptor::SeekMode;
 
-    // Write the offset
-    unsafe { (ptr as *mut u64).write(offset as u64); }
-    ptr += 8;
+use super::args::Fd;
+use super::utils::copy_to_user;
 
-    // Write the length
-    unsafe { (ptr as *mut u16).write(length as u16); }
-    ptr += 2;
+// Directory entry types reported by getdents64
+// Must be kept in sync with syscalls.h
+const DT_UNKNOWN: u8 = 0;
+const DT_FIFO: u8 = 1;
+const DT_CHR: u8 = 2;
+const DT_DIR: u8 = 4;
+const DT_BLK: u8 = 6;
+const DT_REG: u8 = 8;
+const DT_LNK: u8 = 10;
+const DT_SOCK: u8 = 12;
 
-    // Write the string
-    for (i, c) in name.chars().enumerate()
+/// Convert a directory entry type to the value reported to userspace
+fn entry_type_to_dt(entry_type: DirectoryEntryType) -> u8
+{
+    match entry_type
     {
-        unsafe { ((ptr + i) as *mut u8).write(c as u8); }
+        DirectoryEntryType::Unknown => DT_UNKNOWN,
+        DirectoryEntryType::RegularFile => DT_REG,
+        DirectoryEntryType::Directory => DT_DIR,
+        DirectoryEntryType::CharDevice => DT_CHR,
+        DirectoryEntryType::BlockDevice => DT_BLK,
+        DirectoryEntryType::FirstInFirstOut => DT_FIFO,
+        DirectoryEntryType::Socket => DT_SOCK,
+        DirectoryEntryType::SymbolicLink => DT_LNK,
     }
+}
+
+/// Encode a directory entry in the getdents layout: inode, offset, record
+/// length and the NUL terminated name
+fn encode_dir_entry(entry: &DirectoryEntry, next_offset: usize) -> Vec<u8>
+{
+    let length = 8 + 8 + 2 + entry.name.len() + 1;
+
+    let mut result = Vec::with_capacity(length);
 
-    // Write the zero terminator
-    unsafe { ((ptr + name.len()) as *mut u8).write(0) }
+    result.extend_from_slice(&(entry.index.inode as u64).to_le_bytes());
+    result.extend_from_slice(&(next_offset as u64).to_le_bytes());
+    result.extend_from_slice(&(length as u16).to_le_bytes());
+    result.extend_from_slice(entry.name.as_bytes());
+    result.push(0);
 
-    length
+    result
 }
 
-/// Getdents Syscall
-pub fn syscall_getdents(proc: &mut super::Process, fd: usize, buffer_ptr: usize, size: usize) -> usize
+/// Encode a directory entry in the getdents64 layout: inode, offset, record
+/// length, type and the NUL terminated name, padded to an eight byte boundary
+fn encode_dir_entry64(entry: &DirectoryEntry, next_offset: usize) -> Vec<u8>
+{
+    let length = (8 + 8 + 2 + 1 + entry.name.len() + 1 + 7) & !7;
+
+    let mut result = Vec::with_capacity(length);
+
+    result.extend_from_slice(&(entry.index.inode as u64).to_le_bytes());
+    result.extend_from_slice(&(next_offset as u64).to_le_bytes());
+    result.extend_from_slice(&(length as u16).to_le_bytes());
+    result.push(entry_type_to_dt(entry.entry_type));
+    result.extend_from_slice(entry.name.as_bytes());
+    result.resize(length, 0);
+
+    result
+}
+
+/// Fill the buffer with as many directory entries as fit, starting from the
+/// position of the descriptor. The position counts entries, so a listing can
+/// be continued over multiple calls, and 0 is returned once it is exhausted
+fn fill_dir_entries(proc: &mut super::Process, fd: Fd, buffer: usize, size: usize, encode: fn(&DirectoryEntry, usize) -> Vec<u8>) -> Result<usize, usize>
 {
-    let buffer_ptr = proc.map_mem(buffer_ptr).unwrap();
+    let entries = proc.get_dir_entries(fd.0)?;
 
-    let mut amount_written = 0;
+    let descriptor = proc.data.descriptors.get(&fd.0).unwrap();
 
-    match proc.get_dir_entries(fd)
+    // Directories without a position cannot be listed incrementally
+    let start = descriptor.borrow_mut().seek(0, SeekMode::SeekCurrent);
+    if start == errno::ESPIPE
     {
-        Ok(entries) =>
-        {
-            for entry in entries
-            {
-                let length = 8 + 8 + 2 + entry.name.len() + 1;
-                if amount_written + length >= size
-                {
-                    break;
-                }
+        return Err(errno::ENOTDIR);
+    }
 
-                let size = write_dir_entry(buffer_ptr + amount_written, entry.index.inode as usize, amount_written, &entry.name);
+    let mut data = Vec::new();
+    let mut position = start;
 
-                amount_written += size;
-            }
+    for entry in entries.iter().skip(start)
+    {
+        let record = encode(entry, position + 1);
 
-            amount_written
+        if data.len() + record.len() > size
+        {
+            break;
         }
-        Err(e) => e
+
+        data.extend_from_slice(&record);
+        position += 1;
+    }
+
+    // The buffer must hold at least one record if any are left
+    if data.len() == 0 && position < entries.len()
+    {
+        return Err(errno::EINVAL);
     }
-}
\ No newline at end of file
+
+    copy_to_user(proc, buffer, &data)?;
+
+    descriptor.borrow_mut().seek(position, SeekMode::SeekSet);
+
+    Ok(data.len())
+}
+
+/// Getdents Syscall
+pub fn syscall_getdents(proc: &mut super::Process, fd: Fd, buffer_ptr: usize, size: usize) -> Result<usize, usize>
+{
+    fill_dir_entries(proc, fd, buffer_ptr, size, encode_dir_entry)
+}
+
+/// Getdents64 Syscall
+pub fn syscall_getdents64(proc: &mut super::Process, fd: Fd, buffer_ptr: usize, size: usize) -> Result<usize, usize>
+{
+    fill_dir_entries(proc, fd, buffer_ptr, size, encode_dir_entry64)
+}
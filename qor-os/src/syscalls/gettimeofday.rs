@@ -0,0 +1,38 @@
+use super::args::UserPtr;
+use super::clock_gettime::{read_clock, CLOCK_REALTIME};
+
+/// Time as seconds and microseconds
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct TimeVal
+{
+    pub seconds: usize,
+    pub micro_seconds: usize
+}
+
+/// Timezone, which is always reported as UTC
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct TimeZone
+{
+    pub minutes_west: i32,
+    pub dst_time: i32
+}
+
+/// gettimeofday Syscall
+pub fn syscall_gettimeofday(proc: &mut super::Process, time: Option<UserPtr<TimeVal>>, zone: Option<UserPtr<TimeZone>>) -> Result<usize, usize>
+{
+    if let Some(time) = time
+    {
+        let now = read_clock(CLOCK_REALTIME)?;
+
+        time.write(proc, TimeVal { seconds: now / 1_000_000_000, micro_seconds: now % 1_000_000_000 / 1_000 })?;
+    }
+
+    if let Some(zone) = zone
+    {
+        zone.write(proc, TimeZone { minutes_west: 0, dst_time: 0 })?;
+    }
+
+    Ok(0)
+}
@@ -0,0 +1,120 @@
+use crate::*;
+
+use drivers::timer::KernelTime;
+use process::itimer::{IntervalTimer, ITIMER_PROF, ITIMER_REAL, ITIMER_VIRTUAL};
+
+use super::args::UserPtr;
+use super::gettimeofday::TimeVal;
+
+/// Value and reload interval of an interval timer
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct IntervalTimerValue
+{
+    pub interval: TimeVal,
+    pub value: TimeVal
+}
+
+/// Convert a time from userspace, giving `EINVAL` if it is out of range
+fn from_timeval(time: TimeVal) -> Result<KernelTime, usize>
+{
+    if time.micro_seconds >= 1_000_000
+    {
+        return Err(errno::EINVAL);
+    }
+
+    Ok(KernelTime::seconds(time.seconds) + KernelTime::microseconds(time.micro_seconds))
+}
+
+/// Convert a time to be written to userspace
+fn to_timeval(time: KernelTime) -> TimeVal
+{
+    let micro_seconds = time.as_nanoseconds() / 1_000;
+
+    TimeVal { seconds: micro_seconds / 1_000_000, micro_seconds: micro_seconds % 1_000_000 }
+}
+
+/// Get the value of a timer as reported to userspace, a timer which is still
+/// armed never reports zero time left
+fn timer_value(timer: &IntervalTimer) -> IntervalTimerValue
+{
+    let mut value = to_timeval(timer.remaining(unsafe { &drivers::TIMER_DRIVER }.time()));
+
+    if timer.expires.is_some() && value.seconds == 0 && value.micro_seconds == 0
+    {
+        value.micro_seconds = 1;
+    }
+
+    IntervalTimerValue { interval: to_timeval(timer.interval), value }
+}
+
+/// Check that a timer is one which can be used, only real time timers are
+/// kept, as no signals exist for the others to raise
+fn check_timer(which: usize) -> Result<(), usize>
+{
+    match which
+    {
+        ITIMER_REAL => Ok(()),
+        ITIMER_VIRTUAL | ITIMER_PROF => Err(errno::ENOSYS),
+        _ => Err(errno::EINVAL)
+    }
+}
+
+/// getitimer Syscall
+pub fn syscall_getitimer(proc: &mut super::Process, which: usize, current: UserPtr<IntervalTimerValue>) -> Result<usize, usize>
+{
+    check_timer(which)?;
+
+    current.write(proc, timer_value(&proc.data.real_timer))?;
+
+    Ok(0)
+}
+
+/// setitimer Syscall
+///
+/// Arm the timer to send `SIGALRM` once the value given has passed, and again
+/// every interval after that if the interval is not zero. A value of zero, or
+/// no value at all, disarms the timer.
+pub fn syscall_setitimer(proc: &mut super::Process, which: usize, new: Option<UserPtr<IntervalTimerValue>>, old: Option<UserPtr<IntervalTimerValue>>) -> Result<usize, usize>
+{
+    check_timer(which)?;
+
+    let (value, interval) = match new
+    {
+        Some(new) =>
+        {
+            let new = new.read(proc)?;
+            (from_timeval(new.value)?, from_timeval(new.interval)?)
+        },
+        None => (KernelTime(0), KernelTime(0))
+    };
+
+    // Make sure the old value can be written before changing the timer
+    if let Some(old) = old
+    {
+        old.check(proc, true)?;
+    }
+
+    let previous = process::scheduler::get_process_manager().unwrap().set_real_timer(proc.pid, value, interval).ok_or(errno::ESRCH)?;
+
+    if let Some(old) = old
+    {
+        old.write(proc, timer_value(&previous))?;
+    }
+
+    Ok(0)
+}
+
+/// alarm Syscall
+///
+/// Arm the real time timer to send `SIGALRM` after the given number of
+/// seconds, or disarm it if zero, returning the seconds which were left on
+/// the timer rounded to the nearest second
+pub fn syscall_alarm(proc: &mut super::Process, seconds: usize) -> Result<usize, usize>
+{
+    let previous = process::scheduler::get_process_manager().unwrap().set_real_timer(proc.pid, KernelTime::seconds(seconds), KernelTime(0)).ok_or(errno::ESRCH)?;
+    let left = timer_value(&previous).value;
+
+    // An alarm which is still pending is never reported as having no time left
+    Ok((left.seconds + (left.micro_seconds >= 500_000) as usize).max(previous.expires.is_some() as usize))
+}
@@ -1,48 +1,82 @@
 use crate::*;
 
+use crate::process::PID;
+use crate::process::process::ProcessState;
 use crate::process::signals::POSIXSignal;
 use crate::process::signals::SignalType;
 
 /// Kill Syscall
-pub fn syscall_kill(proc: &mut super::Process, pid: usize, signal: usize) -> usize
+///
+/// A positive pid signals that process, zero signals the group of the caller,
+/// -1 signals every process other than init and the caller, and any other
+/// negative pid signals the group with that id. A signal of zero only checks
+/// that the processes exist. Unless the caller is privileged, its real or
+/// effective user id must match the real or saved user id of each target.
+/// The signal is acted on the next time the process is scheduled or returns
+/// from a syscall.
+pub fn syscall_kill(proc: &mut super::Process, pid: isize, signal: usize) -> Result<usize, usize>
 {
     // Convert the signal to the kernel's representation
-    let sig_type = match signal
+    let sig_type = if signal == 0
     {
-        2 => SignalType::SIGINT,
-        9 => SignalType::SIGKILL,
-        15 => SignalType::SIGTERM,
-        _ => { kwarnln!("Unknown signal {}", signal); return errno::EINVAL }
+        None
+    }
+    else
+    {
+        Some(SignalType::from_number(signal).ok_or(errno::EINVAL)?)
     };
 
     kdebugln!(Syscalls, "PID {} Sending Signal {:?} to PID {}", proc.pid, sig_type, pid);
 
-    if pid != 0
+    let manager = process::scheduler::get_process_manager().unwrap();
+
+    let group = match pid
     {
-        if process::scheduler::get_process_manager().as_mut().unwrap().send_signal(
-            pid as u16, 
-            POSIXSignal::new(pid as u16, proc.pid, sig_type)).is_err()
-        {
-            errno::ESRCH // Bad pid
-        }
-        else
-        {
-            0
-        }
+        0 => Some(proc.data.process_group_id),
+        -1 | 1.. => None,
+        _ => Some(pid.unsigned_abs() as PID)
+    };
+
+    let targets: Vec<PID> = manager.processes.values()
+        .filter(|target| !matches!(target.get_state(), ProcessState::Zombie | ProcessState::Dead))
+        .filter(|target| match (pid, group)
+            {
+                (_, Some(group)) => target.data.process_group_id == group,
+                (-1, None) => target.pid != 0 && target.pid != proc.pid,
+                (pid, None) => target.pid as isize == pid
+            })
+        .map(|target| target.pid)
+        .collect();
+
+    if targets.is_empty()
+    {
+        return Err(errno::ESRCH);
     }
-    else
+
+    // Only the processes the caller has permission to signal receive it, the
+    // call fails if there were targets but none of them may be signalled
+    let credentials = proc.data.credentials;
+    let targets: Vec<PID> = targets.into_iter()
+        .filter(|target| *target == proc.pid || manager.get_process_by_pid(*target)
+            .map(|target| credentials.may_signal(&target.data.credentials))
+            .unwrap_or(false))
+        .collect();
+
+    if targets.is_empty()
     {
-        if process::scheduler::get_process_manager().as_mut().unwrap().send_signal_group(
-            proc.data.process_group_id,
-            proc.pid,
-            POSIXSignal::new(pid as u16, proc.pid, sig_type)).is_err()
-        {
-            errno::ESRCH // Bad pid
-        }
-        else
+        return Err(errno::EPERM);
+    }
+
+    if let Some(sig_type) = sig_type
+    {
+        let sending_pid = proc.pid;
+
+        for target in targets
         {
-            0
+            // The targets were all alive, so this cannot fail
+            let _ = manager.send_signal(target, POSIXSignal::new(target, sending_pid, sig_type));
         }
     }
-    
-}
\ No newline at end of file
+
+    Ok(0)
+}
@@ -0,0 +1,9 @@
+use libutils::paths::OwnedPath;
+
+/// link Syscall
+pub fn syscall_link(proc: &mut super::Process, old_path: OwnedPath, new_path: OwnedPath) -> Result<usize, usize>
+{
+    proc.link(old_path, new_path)?;
+
+    Ok(0)
+}
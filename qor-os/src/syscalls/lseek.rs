@@ -1,5 +1,8 @@
 /// lseek Syscall
+///
+/// Returns the new offset, `EINVAL` for a bad whence or a negative result and
+/// `ESPIPE` for descriptors which cannot seek (pipes and character devices)
 pub fn syscall_lseek(proc: &mut super::Process, fd: usize, offset: usize, mode: usize) -> usize
 {
     proc.seek(fd, offset, mode)
-}
\ No newline at end of file
+}
@@ -0,0 +1,35 @@
+use crate::*;
+
+use process::shm::{SharedMemoryDescriptor, SharedMemoryObject};
+
+use super::args::Flags;
+
+// Mirror the definitions in syscalls.h
+/*
+    #define MFD_CLOEXEC 1
+    #define MFD_ALLOW_SEALING 2
+*/
+
+const MFD_CLOEXEC: usize = 1;
+const MFD_ALLOW_SEALING: usize = 2;
+
+// Longest name an object can be given, not counting the null terminator
+const MFD_NAME_MAX: usize = 249;
+
+/// memfd_create Syscall
+///
+/// Create an empty file which only exists in memory, and which is freed once
+/// every descriptor and mapping of it is gone. The file is sized with
+/// ftruncate or by writing to it, and shared mappings of it in different
+/// processes all refer to the same memory. Seals are not supported, so
+/// `MFD_ALLOW_SEALING` is accepted but has no effect.
+pub fn syscall_memfd_create(proc: &mut super::Process, name: usize, flags: Flags<{ MFD_CLOEXEC | MFD_ALLOW_SEALING }>) -> Result<usize, usize>
+{
+    let name = super::utils::string_from_user(proc, name, MFD_NAME_MAX)
+        .map_err(|e| if e == errno::ENAMETOOLONG { errno::EINVAL } else { e })?;
+
+    let fd = proc.add_descriptor(Box::new(SharedMemoryDescriptor::new(SharedMemoryObject::new(name))))?;
+    proc.set_close_on_exec(fd, flags.contains(MFD_CLOEXEC));
+
+    Ok(fd)
+}
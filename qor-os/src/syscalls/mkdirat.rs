@@ -0,0 +1,11 @@
+use super::utils::userspace_path_at;
+
+/// mkdirat Syscall
+pub fn syscall_mkdirat(proc: &mut super::Process, dirfd: isize, path: usize, mode: usize) -> Result<usize, usize>
+{
+    let path = userspace_path_at(proc, dirfd, path)?;
+
+    proc.mkdir(path, mode)?;
+
+    Ok(0)
+}
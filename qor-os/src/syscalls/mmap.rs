@@ -1,5 +1,7 @@
 use crate::*;
 
+use super::args::Flags;
+
 // Mirror the definitions in syscalls.h
 /*
     #define PROT_READ 1
@@ -11,25 +13,41 @@ const PROT_READ: usize = 1;
 const PROT_WRITE: usize = 2;
 const PROT_EXEC: usize = 4;
 
-/// mmap Syscall
-pub fn syscall_mmap(proc: &mut super::Process, _start_ptr: usize, length: usize, prot: usize, flags: usize, fd: usize, offset: usize) -> usize
+pub type ProtFlags = Flags<{ PROT_READ | PROT_WRITE | PROT_EXEC }>;
+
+/// Convert the protection bits given to mmap or mprotect to the flags of the
+/// page table entries, no protection bits gives the user bit alone
+pub fn prot_to_flags(prot: ProtFlags) -> mem::mmu::PageTableEntryFlags
 {
     let mut mem_flags = mem::mmu::PageTableEntryFlags::user();
 
-    if prot & PROT_EXEC > 0
+    if prot.contains(PROT_EXEC)
     {
         mem_flags = mem_flags | mem::mmu::PageTableEntryFlags::executable() | mem::mmu::PageTableEntryFlags::accessed();
     }
 
-    if prot & PROT_WRITE > 0
+    // Writable pages must also be readable, as write only pages are reserved
+    if prot.contains(PROT_WRITE)
     {
-        mem_flags = mem_flags | mem::mmu::PageTableEntryFlags::writable() | mem::mmu::PageTableEntryFlags::dirty();
+        mem_flags = mem_flags | mem::mmu::PageTableEntryFlags::writable() | mem::mmu::PageTableEntryFlags::dirty() | mem::mmu::PageTableEntryFlags::readable();
     }
 
-    if prot & PROT_READ > 0
+    if prot.contains(PROT_READ)
     {
         mem_flags = mem_flags | mem::mmu::PageTableEntryFlags::readable() | mem::mmu::PageTableEntryFlags::accessed();
     }
 
-    proc.map((length + mem::PAGE_SIZE - 1) / mem::PAGE_SIZE, mem_flags, flags, fd, offset)
-}
\ No newline at end of file
+    mem_flags
+}
+
+/// mmap Syscall
+///
+/// Maps anonymous memory, a device buffer (such as the framebuffer) or the
+/// contents of a file, returning the address of the mapping. A mapping with
+/// no permissions reserves the region without allocating it
+pub fn syscall_mmap(proc: &mut super::Process, start_ptr: usize, length: usize, prot: ProtFlags, flags: usize, fd: usize, offset: usize) -> Result<usize, usize>
+{
+    let perm = if prot.0 == 0 { None } else { Some(prot_to_flags(prot)) };
+
+    proc.address_space().map(start_ptr, length, perm, flags, fd, offset)
+}
@@ -5,37 +5,84 @@ use crate::*;
 use process::process::Process;
 
 // Modules
+mod access;
+mod brk;
 mod chdir;
+mod chmod;
+mod chown;
+mod chroot;
+mod clock_gettime;
+mod clone;
 mod close;
+mod credentials;
 mod dup;
+mod eventfd;
 mod execve;
 mod exit;
+mod fcntl;
+mod flock;
 mod fork;
 mod getcwd;
 mod getdents;
 mod getpid;
+mod gettimeofday;
 mod ioctl;
+mod itimer;
 mod kill;
+mod link;
 mod lseek;
+mod memfd_create;
 mod mkdir;
+mod mkdirat;
 mod mmap;
+mod mount;
+mod mprotect;
+mod msync;
 mod munmap;
 mod nanosleep;
 mod open;
+mod openat;
 mod pause;
 mod pipe;
+mod pivot_root;
+mod poll;
+mod pread;
+mod prctl;
+mod process_vm;
 mod read;
+mod readlink;
 mod reboot;
+mod rename;
+mod rlimit;
 mod rmdir;
+mod select;
+mod sendfile;
+mod set_tid_address;
 mod setpgid;
+mod setsid;
 mod sigaction;
+mod signalfd;
+mod sigprocmask;
 mod sigreturn;
+mod socket;
+mod splice;
 mod stat;
+mod symlink;
 mod sync;
+mod tee;
+mod times;
+mod truncate;
+mod umask;
+mod umount2;
+mod uname;
 mod unlink;
+mod unlinkat;
+mod unshare;
 mod wait;
+mod waitid;
 mod write;
 
+pub mod args;
 pub mod utils;
 
 pub fn flatten_syscall_result(result: Result<usize, usize>) -> usize
@@ -50,104 +97,229 @@ pub fn flatten_syscall_result(result: Result<usize, usize>) -> usize
 /// Syscall callback
 pub fn handle_syscall(proc: &mut Process, num: usize, arg0: usize, arg1: usize, arg2: usize, arg3: usize, arg4: usize, arg5: usize, arg6: usize) -> usize
 {
+    let raw = [arg0, arg1, arg2, arg3, arg4, arg5, arg6];
+
     match num
     {
         // Read Syscall
         0 =>
         {
-            read::syscall_read(proc, arg0, arg1, arg2)
+            args::dispatch(proc, read::syscall_read, &raw)
         },
         // Write Syscall
         1 =>
         {
-            write::syscall_write(proc, arg0, arg1, arg2)
+            args::dispatch(proc, write::syscall_write, &raw)
         },
         // Open Syscall
         2 =>
         {
-            flatten_syscall_result(open::syscall_open(proc, arg0, arg1, arg2))
+            args::dispatch(proc, open::syscall_open, &raw)
         },
         // Close Syscall
         3 =>
         {
-            close::syscall_close(proc, arg0)
+            args::dispatch(proc, close::syscall_close, &raw)
         },
         // Stat Syscall
         4 =>
         {
-            flatten_syscall_result(stat::syscall_stat(proc, arg0, arg1))
+            args::dispatch(proc, stat::syscall_stat, &raw)
+        },
+        // Poll Syscall
+        7 =>
+        {
+            args::dispatch(proc, poll::syscall_poll, &raw)
         },
         // lseek Syscall
         8 =>
         {
-            lseek::syscall_lseek(proc, arg0, arg1, arg2)
+            args::dispatch(proc, lseek::syscall_lseek, &raw)
         },
         // mmap Syscall
         9 =>
         {
-            mmap::syscall_mmap(proc, arg0, arg1, arg2, arg3, arg4, arg5)
+            args::dispatch(proc, mmap::syscall_mmap, &raw)
+        },
+        // mprotect Syscall
+        10 =>
+        {
+            args::dispatch(proc, mprotect::syscall_mprotect, &raw)
         },
         // munmap Syscall
         11 =>
         {
-            munmap::syscall_munmap(proc, arg0, arg1)
+            args::dispatch(proc, munmap::syscall_munmap, &raw)
+        },
+        // brk Syscall
+        12 =>
+        {
+            args::dispatch(proc, brk::syscall_brk, &raw)
         },
         // sigaction Syscall
         13 =>
         {
-            sigaction::syscall_sigaction(proc, arg0, arg1, arg2);
-            0
+            args::dispatch(proc, sigaction::syscall_sigaction, &raw)
+        },
+        // sigprocmask Syscall
+        14 =>
+        {
+            args::dispatch(proc, sigprocmask::syscall_sigprocmask, &raw)
         },
         // sigreturn Syscall
         15 =>
         {
-            sigreturn::syscall_sigreturn(proc);
-            0
+            sigreturn::syscall_sigreturn(proc)
         },
         // ioctl Syscall
         16 =>
         {
-            ioctl::syscall_ioctl(proc, arg0, arg1, arg2)
+            args::dispatch(proc, ioctl::syscall_ioctl, &raw)
+        },
+        // pread64 Syscall
+        17 =>
+        {
+            args::dispatch(proc, pread::syscall_pread, &raw)
+        },
+        // pwrite64 Syscall
+        18 =>
+        {
+            args::dispatch(proc, pread::syscall_pwrite, &raw)
+        },
+        // access Syscall
+        21 =>
+        {
+            args::dispatch(proc, access::syscall_access, &raw)
         },
         // pipe Syscall
         22 =>
         {
-            pipe::syscall_pipe(proc, arg0)
+            args::dispatch(proc, pipe::syscall_pipe, &raw)
+        },
+        // Select Syscall
+        23 =>
+        {
+            args::dispatch(proc, select::syscall_select, &raw)
+        },
+        // msync Syscall
+        26 =>
+        {
+            args::dispatch(proc, msync::syscall_msync, &raw)
         },
         // dup Syscall
         32 =>
         {
-            dup::syscall_dup(proc, arg0)
+            args::dispatch(proc, dup::syscall_dup, &raw)
         },
         // dup2 Syscall
         33 =>
         {
-            dup::syscall_dup2(proc, arg0, arg1)
+            args::dispatch(proc, dup::syscall_dup2, &raw)
         },
         // pause Syscall
         34 =>
         {
-            pause::syscall_pause(proc)
+            args::dispatch(proc, pause::syscall_pause, &raw)
         },
         // nanosleep Syscall
         35 =>
         {
-            nanosleep::syscall_nanosleep(proc, arg0, arg1)
+            args::dispatch(proc, nanosleep::syscall_nanosleep, &raw)
+        },
+        // getitimer Syscall
+        36 =>
+        {
+            args::dispatch(proc, itimer::syscall_getitimer, &raw)
+        },
+        // alarm Syscall
+        37 =>
+        {
+            args::dispatch(proc, itimer::syscall_alarm, &raw)
+        },
+        // setitimer Syscall
+        38 =>
+        {
+            args::dispatch(proc, itimer::syscall_setitimer, &raw)
         },
         // getpid Syscall
         39 =>
         {
-            getpid::syscall_getpid(proc)
+            args::dispatch(proc, getpid::syscall_getpid, &raw)
+        },
+        // sendfile Syscall
+        40 =>
+        {
+            args::dispatch(proc, sendfile::syscall_sendfile, &raw)
+        },
+        // socket Syscall
+        41 =>
+        {
+            args::dispatch(proc, socket::syscall_socket, &raw)
+        },
+        // connect Syscall
+        42 =>
+        {
+            args::dispatch(proc, socket::syscall_connect, &raw)
+        },
+        // accept Syscall
+        43 =>
+        {
+            args::dispatch(proc, socket::syscall_accept, &raw)
+        },
+        // sendto Syscall
+        44 =>
+        {
+            args::dispatch(proc, socket::syscall_sendto, &raw)
+        },
+        // recvfrom Syscall
+        45 =>
+        {
+            args::dispatch(proc, socket::syscall_recvfrom, &raw)
+        },
+        // shutdown Syscall
+        48 =>
+        {
+            args::dispatch(proc, socket::syscall_shutdown, &raw)
+        },
+        // bind Syscall
+        49 =>
+        {
+            args::dispatch(proc, socket::syscall_bind, &raw)
+        },
+        // listen Syscall
+        50 =>
+        {
+            args::dispatch(proc, socket::syscall_listen, &raw)
+        },
+        // getsockname Syscall
+        51 =>
+        {
+            args::dispatch(proc, socket::syscall_getsockname, &raw)
+        },
+        // getpeername Syscall
+        52 =>
+        {
+            args::dispatch(proc, socket::syscall_getpeername, &raw)
+        },
+        // socketpair Syscall
+        53 =>
+        {
+            args::dispatch(proc, socket::syscall_socketpair, &raw)
+        },
+        // Clone Syscall
+        56 =>
+        {
+            args::dispatch(proc, clone::syscall_clone, &raw)
         },
         // Fork Syscall
         57 =>
         {
-            fork::syscall_fork(proc)
+            args::dispatch(proc, fork::syscall_fork, &raw)
         }
         // Execve Syscall
         59 =>
         {
-            execve::syscall_execve(proc, arg0, arg1, arg2)
+            args::dispatch(proc, execve::syscall_execve, &raw)
         },
         // Exit Syscall
         60 =>
@@ -155,62 +327,420 @@ pub fn handle_syscall(proc: &mut Process, num: usize, arg0: usize, arg1: usize,
             exit::syscall_exit(proc, arg0);
             0
         },
-        // Wait Syscall
+        // wait4 Syscall
         61 =>
         {
-            wait::syscall_wait(proc, arg0);
-            0
+            args::dispatch(proc, wait::syscall_wait4, &raw)
         },
         // Kill Syscall
         62 =>
         {
-            kill::syscall_kill(proc, arg0, arg1);
-            0
+            args::dispatch(proc, kill::syscall_kill, &raw)
+        },
+        // uname Syscall
+        63 =>
+        {
+            args::dispatch(proc, uname::syscall_uname, &raw)
+        },
+        // fcntl Syscall
+        72 =>
+        {
+            args::dispatch(proc, fcntl::syscall_fcntl, &raw)
+        },
+        // flock Syscall
+        73 =>
+        {
+            args::dispatch(proc, flock::syscall_flock, &raw)
+        },
+        // fsync Syscall
+        74 =>
+        {
+            args::dispatch(proc, sync::syscall_fsync, &raw)
+        },
+        // fdatasync Syscall
+        75 =>
+        {
+            args::dispatch(proc, sync::syscall_fdatasync, &raw)
+        },
+        // truncate Syscall
+        76 =>
+        {
+            args::dispatch(proc, truncate::syscall_truncate, &raw)
+        },
+        // ftruncate Syscall
+        77 =>
+        {
+            args::dispatch(proc, truncate::syscall_ftruncate, &raw)
         },
         // Getdents Syscall
         78 =>
         {
-            getdents::syscall_getdents(proc, arg0, arg1, arg2)
+            args::dispatch(proc, getdents::syscall_getdents, &raw)
         },
         // Getcwd Syscall
         79 =>
         {
-            getcwd::syscall_getcwd(proc, arg0, arg1)
+            args::dispatch(proc, getcwd::syscall_getcwd, &raw)
         },
         // Chdir Syscall
         80 =>
         {
-            flatten_syscall_result(chdir::syscall_chdir(proc, arg0))
+            args::dispatch(proc, chdir::syscall_chdir, &raw)
+        },
+        // Rename Syscall
+        82 =>
+        {
+            args::dispatch(proc, rename::syscall_rename, &raw)
         },
         // Mkdir Syscall
         83 =>
         {
-            flatten_syscall_result(mkdir::syscall_mkdir(proc, arg0, arg1))
+            args::dispatch(proc, mkdir::syscall_mkdir, &raw)
         },
         // Rmdir Syscall
         84 =>
         {
-            flatten_syscall_result(rmdir::syscall_rmdir(proc, arg0))
+            args::dispatch(proc, rmdir::syscall_rmdir, &raw)
+        },
+        // Link Syscall
+        86 =>
+        {
+            args::dispatch(proc, link::syscall_link, &raw)
         },
         // Unlink Syscall
         87 =>
         {
-            flatten_syscall_result(unlink::syscall_unlink(proc, arg0))
+            args::dispatch(proc, unlink::syscall_unlink, &raw)
+        },
+        // symlink Syscall
+        88 =>
+        {
+            args::dispatch(proc, symlink::syscall_symlink, &raw)
+        },
+        // readlink Syscall
+        89 =>
+        {
+            args::dispatch(proc, readlink::syscall_readlink, &raw)
+        },
+        // chmod Syscall
+        90 =>
+        {
+            args::dispatch(proc, chmod::syscall_chmod, &raw)
+        },
+        // fchmod Syscall
+        91 =>
+        {
+            args::dispatch(proc, chmod::syscall_fchmod, &raw)
+        },
+        // chown Syscall
+        92 =>
+        {
+            args::dispatch(proc, chown::syscall_chown, &raw)
+        },
+        // fchown Syscall
+        93 =>
+        {
+            args::dispatch(proc, chown::syscall_fchown, &raw)
+        },
+        // lchown Syscall
+        94 =>
+        {
+            args::dispatch(proc, chown::syscall_lchown, &raw)
+        },
+        // umask Syscall
+        95 =>
+        {
+            args::dispatch(proc, umask::syscall_umask, &raw)
+        },
+        // gettimeofday Syscall
+        96 =>
+        {
+            args::dispatch(proc, gettimeofday::syscall_gettimeofday, &raw)
+        },
+        // getrlimit Syscall
+        97 =>
+        {
+            args::dispatch(proc, rlimit::syscall_getrlimit, &raw)
+        },
+        // getrusage Syscall
+        98 =>
+        {
+            args::dispatch(proc, times::syscall_getrusage, &raw)
+        },
+        // times Syscall
+        100 =>
+        {
+            args::dispatch(proc, times::syscall_times, &raw)
+        },
+        // getuid Syscall
+        102 =>
+        {
+            args::dispatch(proc, credentials::syscall_getuid, &raw)
+        },
+        // getgid Syscall
+        104 =>
+        {
+            args::dispatch(proc, credentials::syscall_getgid, &raw)
+        },
+        // setuid Syscall
+        105 =>
+        {
+            args::dispatch(proc, credentials::syscall_setuid, &raw)
+        },
+        // setgid Syscall
+        106 =>
+        {
+            args::dispatch(proc, credentials::syscall_setgid, &raw)
+        },
+        // geteuid Syscall
+        107 =>
+        {
+            args::dispatch(proc, credentials::syscall_geteuid, &raw)
+        },
+        // getegid Syscall
+        108 =>
+        {
+            args::dispatch(proc, credentials::syscall_getegid, &raw)
         },
         // setpgid Syscall
         109 =>
         {
-            setpgid::syscall_setpgid(proc, arg0, arg1)
+            args::dispatch(proc, setpgid::syscall_setpgid, &raw)
+        },
+        // getpgrp Syscall
+        111 =>
+        {
+            args::dispatch(proc, setpgid::syscall_getpgrp, &raw)
+        },
+        // setsid Syscall
+        112 =>
+        {
+            args::dispatch(proc, setsid::syscall_setsid, &raw)
+        },
+        // setreuid Syscall
+        113 =>
+        {
+            args::dispatch(proc, credentials::syscall_setreuid, &raw)
+        },
+        // setregid Syscall
+        114 =>
+        {
+            args::dispatch(proc, credentials::syscall_setregid, &raw)
+        },
+        // setresuid Syscall
+        117 =>
+        {
+            args::dispatch(proc, credentials::syscall_setresuid, &raw)
+        },
+        // getresuid Syscall
+        118 =>
+        {
+            args::dispatch(proc, credentials::syscall_getresuid, &raw)
+        },
+        // setresgid Syscall
+        119 =>
+        {
+            args::dispatch(proc, credentials::syscall_setresgid, &raw)
+        },
+        // getresgid Syscall
+        120 =>
+        {
+            args::dispatch(proc, credentials::syscall_getresgid, &raw)
+        },
+        // getpgid Syscall
+        121 =>
+        {
+            args::dispatch(proc, setpgid::syscall_getpgid, &raw)
+        },
+        // getsid Syscall
+        124 =>
+        {
+            args::dispatch(proc, setsid::syscall_getsid, &raw)
+        },
+        // sigsuspend Syscall
+        130 =>
+        {
+            args::dispatch(proc, pause::syscall_sigsuspend, &raw)
+        },
+        // pivot_root Syscall
+        155 =>
+        {
+            args::dispatch(proc, pivot_root::syscall_pivot_root, &raw)
+        },
+        // prctl Syscall
+        157 =>
+        {
+            args::dispatch(proc, prctl::syscall_prctl, &raw)
+        },
+        // setrlimit Syscall
+        160 =>
+        {
+            args::dispatch(proc, rlimit::syscall_setrlimit, &raw)
+        },
+        // chroot Syscall
+        161 =>
+        {
+            args::dispatch(proc, chroot::syscall_chroot, &raw)
         },
         // Sync Syscall
         162 =>
         {
-            sync::syscall_sync(proc)
+            args::dispatch(proc, sync::syscall_sync, &raw)
+        },
+        // mount Syscall
+        165 =>
+        {
+            args::dispatch(proc, mount::syscall_mount, &raw)
+        },
+        // umount2 Syscall
+        166 =>
+        {
+            args::dispatch(proc, umount2::syscall_umount2, &raw)
         },
         // Reboot Syscall
         169 =>
         {
-            reboot::syscall_reboot(proc, arg0, arg1, arg2, arg3)
+            args::dispatch(proc, reboot::syscall_reboot, &raw)
+        },
+        // Getdents64 Syscall
+        217 =>
+        {
+            args::dispatch(proc, getdents::syscall_getdents64, &raw)
+        },
+        // set_tid_address Syscall
+        218 =>
+        {
+            args::dispatch(proc, set_tid_address::syscall_set_tid_address, &raw)
+        },
+        // clock_gettime Syscall
+        228 =>
+        {
+            args::dispatch(proc, clock_gettime::syscall_clock_gettime, &raw)
+        },
+        // waitid Syscall
+        247 =>
+        {
+            args::dispatch(proc, waitid::syscall_waitid, &raw)
+        },
+        // accept4 Syscall
+        288 =>
+        {
+            args::dispatch(proc, socket::syscall_accept4, &raw)
+        },
+        // dup3 Syscall
+        292 =>
+        {
+            args::dispatch(proc, dup::syscall_dup3, &raw)
+        },
+        // pipe2 Syscall
+        293 =>
+        {
+            args::dispatch(proc, pipe::syscall_pipe2, &raw)
+        },
+        // prlimit64 Syscall
+        302 =>
+        {
+            args::dispatch(proc, rlimit::syscall_prlimit, &raw)
+        },
+        // Openat Syscall
+        257 =>
+        {
+            args::dispatch(proc, openat::syscall_openat, &raw)
+        },
+        // mkdirat Syscall
+        258 =>
+        {
+            args::dispatch(proc, mkdirat::syscall_mkdirat, &raw)
+        },
+        // fchownat Syscall
+        260 =>
+        {
+            args::dispatch(proc, chown::syscall_fchownat, &raw)
+        },
+        // unlinkat Syscall
+        263 =>
+        {
+            args::dispatch(proc, unlinkat::syscall_unlinkat, &raw)
+        },
+        // Renameat Syscall
+        264 =>
+        {
+            args::dispatch(proc, rename::syscall_renameat, &raw)
+        },
+        // symlinkat Syscall
+        266 =>
+        {
+            args::dispatch(proc, symlink::syscall_symlinkat, &raw)
+        },
+        // readlinkat Syscall
+        267 =>
+        {
+            args::dispatch(proc, readlink::syscall_readlinkat, &raw)
+        },
+        // fchmodat Syscall
+        268 =>
+        {
+            args::dispatch(proc, chmod::syscall_fchmodat, &raw)
+        },
+        // faccessat Syscall
+        269 =>
+        {
+            args::dispatch(proc, access::syscall_faccessat, &raw)
+        },
+        // Pselect6 Syscall
+        270 =>
+        {
+            args::dispatch(proc, select::syscall_pselect6, &raw)
+        },
+        // Unshare Syscall
+        272 =>
+        {
+            args::dispatch(proc, unshare::syscall_unshare, &raw)
+        },
+        // splice Syscall
+        275 =>
+        {
+            args::dispatch(proc, splice::syscall_splice, &raw)
+        },
+        // tee Syscall
+        276 =>
+        {
+            args::dispatch(proc, tee::syscall_tee, &raw)
+        },
+        // process_vm_readv Syscall
+        310 =>
+        {
+            args::dispatch(proc, process_vm::syscall_process_vm_readv, &raw)
+        },
+        // process_vm_writev Syscall
+        311 =>
+        {
+            args::dispatch(proc, process_vm::syscall_process_vm_writev, &raw)
+        },
+        // memfd_create Syscall
+        319 =>
+        {
+            args::dispatch(proc, memfd_create::syscall_memfd_create, &raw)
+        },
+        // eventfd Syscall
+        284 =>
+        {
+            args::dispatch(proc, eventfd::syscall_eventfd, &raw)
+        },
+        // eventfd2 Syscall
+        290 =>
+        {
+            args::dispatch(proc, eventfd::syscall_eventfd2, &raw)
+        },
+        // signalfd Syscall
+        282 =>
+        {
+            args::dispatch(proc, signalfd::syscall_signalfd, &raw)
+        },
+        // signalfd4 Syscall
+        289 =>
+        {
+            args::dispatch(proc, signalfd::syscall_signalfd4, &raw)
         },
         default =>
         {
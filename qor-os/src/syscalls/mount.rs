@@ -0,0 +1,161 @@
+use crate::*;
+
+use drivers::block::BlockDevice;
+use fs::fstrait::Filesystem;
+
+use libutils::paths::OwnedPath;
+
+// Must be kept in sync with syscalls.h
+const MS_SILENT: usize = 0x8000;
+
+// Older programs pass this magic number in the upper half of the flags
+const MS_MGC_VAL: usize = 0xC0ED0000;
+const MS_MGC_MSK: usize = 0xFFFF0000;
+
+const FSTYPE_MAX_LENGTH: usize = 64;
+
+/// Get the block device at the given path, block devices are named as on
+/// Linux, the first VirtIO disk being `/dev/vda` and the first RAM disk
+/// `/dev/ram0`. A regular file is attached to a loop device
+fn block_device(proc: &mut super::Process, vfs: &mut fs::vfs::FilesystemInterface, path: &OwnedPath) -> Result<Box<dyn BlockDevice>, usize>
+{
+    if let Some(name) = path.as_str().strip_prefix("/dev/vd")
+    {
+        let index = match name.as_bytes()
+        {
+            [c @ b'a'..=b'z'] => (c - b'a') as usize,
+            _ => { return Err(errno::ENOTBLK); }
+        };
+
+        let device = drivers::virtio::get_block_driver(index).ok_or(errno::ENXIO)?;
+
+        Ok(Box::new(device))
+    }
+    else if let Some(name) = path.as_str().strip_prefix("/dev/ram")
+    {
+        if name.is_empty() || !name.bytes().all(|c| c.is_ascii_digit())
+        {
+            return Err(errno::ENOTBLK);
+        }
+
+        let index = name.parse::<usize>().map_err(|_| errno::ENXIO)?;
+
+        let device = drivers::block::get_ram_disk(index).ok_or(errno::ENXIO)?;
+
+        Ok(Box::new(device))
+    }
+    else
+    {
+        let inode = fs::namespace::path_to_inode(&proc.data.mount_namespace, proc.data.root, vfs, path).map_err(|e| e.to_errno())?;
+
+        if vfs.get_stat(inode).map_err(|e| e.to_errno())?.mode & 0xF000 != 0x8000
+        {
+            return Err(errno::ENOTBLK);
+        }
+
+        let device = drivers::block::LoopDevice::new(vfs, inode).map_err(|e| e.to_errno())?;
+
+        Ok(Box::new(device))
+    }
+}
+
+/// Construct the filesystem of the given type, returning the filesystem along
+/// with the name of its source
+fn construct_filesystem(proc: &mut super::Process, vfs: &mut fs::vfs::FilesystemInterface, source: usize, fstype: &str) -> Result<(String, Box<dyn Filesystem>), usize>
+{
+    let (name, mut fs): (String, Box<dyn Filesystem>) = match fstype
+    {
+        "minix" | "minix3" =>
+        {
+            let path = super::utils::userspace_string_to_path(proc, source)?;
+            let device = block_device(proc, vfs, &path)?;
+
+            // A block device may only be in use by one filesystem at a time,
+            // as the filesystems each keep their own cache of the device
+            if vfs.source_in_use(path.as_str())
+            {
+                return Err(errno::EBUSY);
+            }
+
+            (path.as_str().to_string(), Box::new(fs::minix3::Minix3Filesystem::new(device)))
+        },
+        "devfs" | "devtmpfs" => (String::from("devfs"), Box::new(fs::devfs::DevFilesystem::new())),
+        "proc" => (String::from("proc"), Box::new(fs::procfs::ProcFilesystem::new())),
+        "sysfs" => (String::from("sysfs"), Box::new(fs::sysfs::SysFilesystem::new())),
+        _ => { return Err(errno::ENODEV); }
+    };
+
+    fs.init().map_err(|e| e.to_errno())?;
+
+    Ok((name, fs))
+}
+
+/// mount Syscall
+///
+/// Construct a filesystem of the given type and mount it over the target
+/// directory, processes in a mount namespace mount within the namespace.
+/// Flags which would change how the filesystem is accessed are not supported
+/// and are rejected.
+pub fn syscall_mount(proc: &mut super::Process, source: usize, target: OwnedPath, fstype: usize, mut flags: usize, _data: usize) -> Result<usize, usize>
+{
+    if flags & MS_MGC_MSK == MS_MGC_VAL
+    {
+        flags &= !MS_MGC_MSK;
+    }
+
+    if flags & !MS_SILENT != 0
+    {
+        return Err(errno::EINVAL);
+    }
+
+    let fstype = super::utils::string_from_user(proc, fstype, FSTYPE_MAX_LENGTH)?;
+
+    proc.ensure_fs();
+    let vfs = fs::vfs::get_vfs_reference().ok_or(errno::ENODEV)?;
+
+    // The target must be an existing directory other than the root
+    if target.as_str() == "/"
+    {
+        return Err(errno::EBUSY);
+    }
+
+    let inode = fs::namespace::path_to_inode(&proc.data.mount_namespace, proc.data.root, vfs, &target).map_err(|e| e.to_errno())?;
+    vfs.assert_directory(inode).map_err(|e| e.to_errno())?;
+
+    // Only one filesystem can be mounted at a path within a namespace
+    if proc.data.mount_namespace.as_ref().map(|namespace| namespace.borrow().mounted_at(&target).is_some()).unwrap_or(false)
+    {
+        return Err(errno::EBUSY);
+    }
+
+    let (source, filesystem) = construct_filesystem(proc, vfs, source, fstype.as_str())?;
+
+    kdebugln!(Syscalls, "PID {} mounting {} ({}) at {}", proc.pid, source, fstype, target);
+
+    if let Some(namespace) = &proc.data.mount_namespace
+    {
+        let root = vfs.register_fs(source.as_str(), filesystem).map_err(|e| e.to_errno())?;
+
+        if let Err(e) = namespace.borrow_mut().mount(&target, root)
+        {
+            vfs.unregister_fs(root.mount_id);
+            return Err(e.to_errno());
+        }
+    }
+    else
+    {
+        // The global mount table is named by paths outside of any changed root
+        let target = if proc.data.root.is_some()
+        {
+            fs::namespace::inode_to_path(&None, None, vfs, inode).map_err(|e| e.to_errno())?
+        }
+        else
+        {
+            target
+        };
+
+        vfs.mount_fs(source.as_str(), &target, filesystem).map_err(|e| e.to_errno())?;
+    }
+
+    Ok(0)
+}
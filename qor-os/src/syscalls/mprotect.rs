@@ -0,0 +1,13 @@
+use super::mmap::{prot_to_flags, ProtFlags};
+
+/// mprotect Syscall
+///
+/// Change the permissions of pages created by mmap or belonging to the program
+/// image, heap or stack, returning `EINVAL` for an unaligned address and
+/// `ENOMEM` if any part of the range is not mapped by the process
+pub fn syscall_mprotect(proc: &mut super::Process, start_ptr: usize, length: usize, prot: ProtFlags) -> Result<usize, usize>
+{
+    proc.address_space().protect(start_ptr, length, prot_to_flags(prot))?;
+
+    Ok(0)
+}
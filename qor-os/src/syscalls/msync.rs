@@ -0,0 +1,56 @@
+use crate::*;
+
+use super::args::Flags;
+
+// Mirror the definitions in syscalls.h
+/*
+    #define MS_ASYNC 1
+    #define MS_INVALIDATE 2
+    #define MS_SYNC 4
+*/
+
+const MS_ASYNC: usize = 1;
+const MS_INVALIDATE: usize = 2;
+const MS_SYNC: usize = 4;
+
+pub type MsyncFlags = Flags<{ MS_ASYNC | MS_INVALIDATE | MS_SYNC }>;
+
+/// msync Syscall
+///
+/// Writes the dirty pages of shared file mappings back to their files, with
+/// `MS_SYNC` waiting until the files are on the disk. Mapped pages are the
+/// only copy of the data, so `MS_INVALIDATE` has nothing to do.
+pub fn syscall_msync(proc: &mut super::Process, start_ptr: usize, length: usize, flags: MsyncFlags) -> Result<usize, usize>
+{
+    if flags.contains(MS_ASYNC) && flags.contains(MS_SYNC)
+    {
+        return Err(errno::EINVAL);
+    }
+
+    proc.address_space().sync_mappings(start_ptr, length, flags.contains(MS_SYNC))?;
+
+    Ok(0)
+}
+
+/*
+    ===================== Tests for msync =====================
+*/
+
+/// Test msync - Data the kernel reads into a synced page of a shared mapping
+/// is written back by the next sync
+#[test_case]
+pub fn test_msync_after_pread()
+{
+    use mem::mmu::PageTableEntryFlags;
+
+    let perm = PageTableEntryFlags::user() | PageTableEntryFlags::readable() | PageTableEntryFlags::writable() | PageTableEntryFlags::accessed() | PageTableEntryFlags::dirty();
+    let (mut proc, data, fd, addr) = super::pread::map_test_file(perm);
+
+    assert_eq!(syscall_msync(&mut proc, addr, mem::PAGE_SIZE, Flags(MS_ASYNC)), Ok(0));
+
+    assert_eq!(super::pread::syscall_pread(&mut proc, super::args::Fd(fd), addr, 16, mem::PAGE_SIZE as isize / 2), Ok(16));
+
+    assert_eq!(syscall_msync(&mut proc, addr, mem::PAGE_SIZE, Flags(MS_ASYNC)), Ok(0));
+
+    assert!(data.borrow()[..16].iter().all(|b| *b == 0x5A));
+}
@@ -1,5 +1,7 @@
 /// munmap Syscall
-pub fn syscall_munmap(proc: &mut super::Process, start_ptr: usize, length: usize) -> usize
+pub fn syscall_munmap(proc: &mut super::Process, start_ptr: usize, length: usize) -> Result<usize, usize>
 {
-    proc.unmap(start_ptr, length)
-}
\ No newline at end of file
+    proc.address_space().unmap(start_ptr, length)?;
+
+    Ok(0)
+}
@@ -1,35 +1,42 @@
 use crate::*;
 
-use process::process::ProcessState;
 use drivers::timer::KernelTime;
 
+use super::args::UserPtr;
+
 /// Incoming representation of time
 #[repr(C)]
 #[derive(Debug, Clone, Copy)]
 pub struct IncomingTime
 {
-    seconds: usize,
-    nano_seconds: usize
-}
-
-/// This is beyond unsafe, but this is what happens when we interact with C like
-/// this
-fn map_ptr<T>(proc: &mut super::Process, ptr: usize) -> &'static mut T
-{
-    unsafe { (proc.map_mem(ptr).unwrap() as *mut T).as_mut() }.unwrap()
+    pub seconds: usize,
+    pub nano_seconds: usize
 }
 
 /// Nanosleep Syscall
-pub fn syscall_nanosleep(proc: &mut super::Process, time: usize, _remaining: usize) -> usize
+///
+/// Sleep until the given time has passed, the process is woken by the timer
+/// interrupt. If a signal handler interrupts the sleep, the syscall fails with
+/// EINTR and the time which was left is written to `remaining`.
+pub fn syscall_nanosleep(proc: &mut super::Process, time: UserPtr<IncomingTime>, remaining: Option<UserPtr<IncomingTime>>) -> Result<usize, usize>
 {
-    let time: &'static mut IncomingTime = map_ptr(proc, time);
-    let kernel_duration = KernelTime::nanoseconds(time.seconds * 1_000_000_000 + time.nano_seconds);
+    let time = time.read(proc)?;
+
+    if time.nano_seconds >= 1_000_000_000
+    {
+        return Err(errno::EINVAL);
+    }
+
+    let kernel_duration = KernelTime::seconds(time.seconds) + KernelTime::nanoseconds(time.nano_seconds);
     let current = unsafe { &drivers::TIMER_DRIVER }.time();
 
-    proc.state = ProcessState::Sleeping { wake_time:  current + kernel_duration };
+    proc.data.sleep_remaining = remaining.map(|ptr| ptr.addr());
+    unsafe { proc.frame.as_mut().unwrap() }.regs[10] = 0;
+
+    process::scheduler::get_process_manager().unwrap().sleep_until(proc.pid, current + kernel_duration);
 
     proc.program_counter += 4;
 
     let schedule = process::scheduler::schedule_next();
     process::scheduler::schedule_jump(schedule);
-}
\ No newline at end of file
+}
@@ -1,9 +1,9 @@
 use crate::*;
 
+use libutils::paths::OwnedPath;
+
 /// Open Syscall
-pub fn syscall_open(proc: &mut super::Process, path_ptr: usize, flags: usize, _create_mode: usize) -> Result<usize, usize>
+pub fn syscall_open(proc: &mut super::Process, path: OwnedPath, flags: usize, create_mode: usize) -> Result<usize, usize>
 {
-    let expanded_path = super::utils::userspace_string_to_path(proc, path_ptr)?;
-
-    proc.open(&expanded_path, flags).map_err( |_| errno::ENOENT )
+    proc.open(&path, flags, create_mode).map_err( |_| errno::ENOENT )
 }
\ No newline at end of file
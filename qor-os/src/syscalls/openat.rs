@@ -0,0 +1,11 @@
+use crate::*;
+
+use super::utils::userspace_path_at;
+
+/// Openat Syscall
+pub fn syscall_openat(proc: &mut super::Process, dirfd: isize, path: usize, flags: usize, create_mode: usize) -> Result<usize, usize>
+{
+    let path = userspace_path_at(proc, dirfd, path)?;
+
+    proc.open(&path, flags, create_mode).map_err( |_| errno::ENOENT )
+}
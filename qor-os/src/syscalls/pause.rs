@@ -1,11 +1,41 @@
 use crate::*;
 
-/// Pause Syscall
-pub fn syscall_pause(proc: &mut super::Process) -> usize
+use process::process::{ProcessState, WaitMode};
+
+use super::args::UserPtr;
+
+/// Block until a signal handler runs, the handler sets the return value to
+/// `EINTR`, which is the only value these syscalls return
+fn wait_for_signal(proc: &mut super::Process) -> !
 {
-    proc.state = process::process::ProcessState::Waiting(process::process::WaitMode::ForSignal);
+    unsafe { proc.frame.as_mut().unwrap() }.regs[10] = errno::EINTR;
+
+    proc.state = ProcessState::Waiting(WaitMode::ForSignal);
     proc.program_counter += 4;
 
     let schedule = process::scheduler::schedule_next();
     process::scheduler::schedule_jump(schedule);
-}
\ No newline at end of file
+}
+
+/// Pause Syscall
+///
+/// Wait until a signal is caught, signals which are ignored or blocked do not
+/// end the wait
+pub fn syscall_pause(proc: &mut super::Process) -> usize
+{
+    wait_for_signal(proc)
+}
+
+/// sigsuspend Syscall
+///
+/// Replace the signal mask and wait until a signal is caught, the original
+/// mask is restored once the handler returns
+pub fn syscall_sigsuspend(proc: &mut super::Process, mask: UserPtr<u64>) -> Result<usize, usize>
+{
+    let mask = mask.read(proc)?;
+
+    proc.data.saved_signal_mask = Some(proc.data.signal_mask);
+    proc.data.signal_mask = process::signals::SignalType::sanitize_mask(mask);
+
+    wait_for_signal(proc)
+}
@@ -1,15 +1,29 @@
+use crate::process::descriptor::{O_CLOEXEC, O_NONBLOCK, O_RDONLY, O_WRONLY};
+
+use super::args::{Flags, UserPtr};
+
 /// Pipe Syscall
-pub fn syscall_pipe(proc: &mut super::Process, fds: usize) -> usize
+pub fn syscall_pipe(proc: &mut super::Process, fds: UserPtr<[u32; 2]>) -> Result<usize, usize>
 {
-    let buffer = proc.map_mem(fds).unwrap() as *mut u32;
-
-    let (read, write) = proc.pipe();
-    
-    unsafe
-    {
-        buffer.add(0).write(read as u32);
-        buffer.add(1).write(write as u32);
-    }
-
-    0
-}
\ No newline at end of file
+    syscall_pipe2(proc, fds, Flags(0))
+}
+
+/// Pipe2 Syscall
+pub fn syscall_pipe2(proc: &mut super::Process, fds: UserPtr<[u32; 2]>, flags: Flags<{ O_CLOEXEC | O_NONBLOCK }>) -> Result<usize, usize>
+{
+    // Make sure the buffer can be written before creating the pipe
+    fds.check(proc, true)?;
+
+    let (read, write) = proc.pipe()?;
+
+    let nonblock = flags.0 & O_NONBLOCK;
+    proc.data.descriptors.get(&read).unwrap().borrow_mut().set_status_flags(O_RDONLY | nonblock);
+    proc.data.descriptors.get(&write).unwrap().borrow_mut().set_status_flags(O_WRONLY | nonblock);
+
+    proc.set_close_on_exec(read, flags.contains(O_CLOEXEC));
+    proc.set_close_on_exec(write, flags.contains(O_CLOEXEC));
+
+    fds.write(proc, [read as u32, write as u32])?;
+
+    Ok(0)
+}
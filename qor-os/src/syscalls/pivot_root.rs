@@ -0,0 +1,32 @@
+use crate::*;
+
+use libutils::paths::OwnedPath;
+
+/// pivot_root Syscall
+///
+/// Make the filesystem mounted at `new_root` the root of the global tree and
+/// move the old root to `put_old`, used to switch from a RAM disk to the real
+/// root filesystem. Working directories are kept as paths, so they now refer
+/// to the new tree. Processes in a mount namespace, or which changed their
+/// root, see the global tree through paths it would move, so cannot pivot it.
+pub fn syscall_pivot_root(proc: &mut super::Process, new_root: OwnedPath, put_old: OwnedPath) -> Result<usize, usize>
+{
+    if !proc.is_privileged()
+    {
+        return Err(errno::EPERM);
+    }
+
+    if proc.data.mount_namespace.is_some() || proc.data.root.is_some()
+    {
+        return Err(errno::EINVAL);
+    }
+
+    proc.ensure_fs();
+    let vfs = fs::vfs::get_vfs_reference().ok_or(errno::ENODEV)?;
+
+    kdebugln!(Syscalls, "PID {} pivoting the root to {} with the old root at {}", proc.pid, new_root, put_old);
+
+    vfs.pivot_root(&new_root, &put_old).map_err(|e| e.to_errno())?;
+
+    Ok(0)
+}
@@ -0,0 +1,51 @@
+use crate::*;
+
+use drivers::timer::KernelTime;
+
+use process::poll::{PollRequest, PollSet, POLL_MAX_DESCRIPTORS};
+use process::process::{ProcessState, WaitMode};
+
+/// Check the descriptors in the set, blocking until one is ready or the
+/// timeout has passed, shared by poll and select. A timeout of `None` waits
+/// indefinitely and a timeout of zero returns immediately.
+pub fn wait_for_descriptors(proc: &mut super::Process, set: PollSet, timeout: Option<KernelTime>) -> Result<usize, usize>
+{
+    let mut request = PollRequest { set, deadline: None };
+
+    let immediate = timeout == Some(KernelTime(0));
+    let ready = process::poll::poll_descriptors(proc, &request, immediate)?;
+
+    if ready > 0 || immediate
+    {
+        return Ok(ready);
+    }
+
+    if let Some(timeout) = timeout
+    {
+        let current = unsafe { &drivers::TIMER_DRIVER }.time();
+        request.deadline = Some(current + timeout);
+    }
+
+    proc.state = ProcessState::Waiting(WaitMode::ForPoll(request));
+    proc.program_counter += 4;
+
+    let schedule = process::scheduler::schedule_next();
+    process::scheduler::schedule_jump(schedule);
+}
+
+/// Poll Syscall
+///
+/// Check the readiness of the descriptors in the array, blocking until one is
+/// ready or `timeout` milliseconds have passed. A negative timeout waits
+/// indefinitely and a timeout of zero returns immediately.
+pub fn syscall_poll(proc: &mut super::Process, fds: usize, count: usize, timeout: isize) -> Result<usize, usize>
+{
+    if count > POLL_MAX_DESCRIPTORS
+    {
+        return Err(errno::EINVAL);
+    }
+
+    let timeout = if timeout < 0 { None } else { Some(KernelTime::milliseconds(timeout as usize)) };
+
+    wait_for_descriptors(proc, PollSet::Array { fds, count }, timeout)
+}
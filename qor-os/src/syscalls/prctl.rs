@@ -0,0 +1,96 @@
+use crate::*;
+
+use process::data::PROCESS_NAME_LENGTH;
+
+use super::utils::{copy_from_user, copy_to_user};
+
+// Must be kept in sync with syscalls.h
+pub const PR_GET_DUMPABLE: usize = 3;
+pub const PR_SET_DUMPABLE: usize = 4;
+pub const PR_SET_NAME: usize = 15;
+pub const PR_GET_NAME: usize = 16;
+pub const PR_SET_NO_NEW_PRIVS: usize = 38;
+pub const PR_GET_NO_NEW_PRIVS: usize = 39;
+
+/// Read a process name from userspace, names longer than the limit are
+/// truncated rather than rejected
+fn name_from_user(proc: &super::Process, addr: usize) -> Result<String, usize>
+{
+    let mut name = String::new();
+
+    for i in 0..PROCESS_NAME_LENGTH
+    {
+        let mut byte = [0u8];
+        copy_from_user(proc, addr + i, &mut byte)?;
+
+        if byte[0] == 0
+        {
+            break;
+        }
+
+        name.push(byte[0] as char);
+    }
+
+    Ok(name)
+}
+
+/// prctl Syscall
+///
+/// Get or set the name of the process, whether it may be dumped, and whether
+/// programs it runs may gain privileges
+pub fn syscall_prctl(proc: &mut super::Process, option: usize, arg2: usize, arg3: usize, arg4: usize, arg5: usize) -> Result<usize, usize>
+{
+    match option
+    {
+        PR_SET_NAME =>
+        {
+            let name = name_from_user(proc, arg2)?;
+            proc.data.set_name(&name);
+
+            Ok(0)
+        },
+        PR_GET_NAME =>
+        {
+            let name = proc.data.name.as_bytes();
+            let length = name.len().min(PROCESS_NAME_LENGTH);
+
+            let mut buffer = [0u8; PROCESS_NAME_LENGTH + 1];
+            buffer[..length].copy_from_slice(&name[..length]);
+
+            copy_to_user(proc, arg2, &buffer)?;
+
+            Ok(0)
+        },
+        PR_SET_DUMPABLE =>
+        {
+            match arg2
+            {
+                0 | 1 => { proc.data.dumpable = arg2 == 1; Ok(0) },
+                _ => Err(errno::EINVAL)
+            }
+        },
+        PR_GET_DUMPABLE => Ok(proc.data.dumpable as usize),
+        PR_SET_NO_NEW_PRIVS =>
+        {
+            // The flag can only be set, never cleared
+            if arg2 != 1 || arg3 != 0 || arg4 != 0 || arg5 != 0
+            {
+                return Err(errno::EINVAL);
+            }
+
+            proc.data.no_new_privs = true;
+
+            Ok(0)
+        },
+        PR_GET_NO_NEW_PRIVS =>
+        {
+            if arg2 != 0 || arg3 != 0 || arg4 != 0 || arg5 != 0
+            {
+                return Err(errno::EINVAL);
+            }
+
+            Ok(proc.data.no_new_privs as usize)
+        },
+        _ => Err(errno::EINVAL)
+    }
+}
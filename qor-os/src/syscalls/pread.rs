@@ -0,0 +1,214 @@
+use crate::*;
+
+use super::args::Fd;
+use super::utils::{copy_from_user, copy_to_user, is_error, transfer_segments};
+
+use mem::pagelist::PageListBuffer;
+
+// Largest transfer a single call makes, longer requests are cut short
+pub const MAX_POSITIONAL_TRANSFER: usize = 0x10_0000;
+
+/// pread64 Syscall
+///
+/// Read from the given offset in the file without changing its position,
+/// descriptors which cannot seek give `ESPIPE`
+pub fn syscall_pread(proc: &mut super::Process, fd: Fd, buffer: usize, count: usize, offset: isize) -> Result<usize, usize>
+{
+    if offset < 0
+    {
+        return Err(errno::EINVAL);
+    }
+
+    let data = PageListBuffer::new(count.min(MAX_POSITIONAL_TRANSFER)).map_err(|_| errno::ENOMEM)?;
+    let mut position = offset as usize;
+
+    let read = transfer_segments(&data.segments(data.len()), |ptr, length|
+    {
+        let read = proc.read_at(fd.0, ptr, length, position);
+        position += if is_error(read) { 0 } else { read };
+
+        read
+    });
+
+    if is_error(read)
+    {
+        return Err(read);
+    }
+
+    let mut copied = 0;
+
+    for (ptr, length) in data.segments(read)
+    {
+        copy_to_user(proc, buffer + copied, unsafe { core::slice::from_raw_parts(ptr, length) })?;
+        copied += length;
+    }
+
+    Ok(read)
+}
+
+/// pwrite64 Syscall
+///
+/// Write at the given offset in the file without changing its position,
+/// descriptors which cannot seek give `ESPIPE`
+pub fn syscall_pwrite(proc: &mut super::Process, fd: Fd, buffer: usize, count: usize, offset: isize) -> Result<usize, usize>
+{
+    if offset < 0
+    {
+        return Err(errno::EINVAL);
+    }
+
+    let data = PageListBuffer::new(count.min(MAX_POSITIONAL_TRANSFER)).map_err(|_| errno::ENOMEM)?;
+    let segments = data.segments(data.len());
+    let mut copied = 0;
+
+    for (ptr, length) in &segments
+    {
+        copy_from_user(proc, buffer + copied, unsafe { core::slice::from_raw_parts_mut(*ptr, *length) })?;
+        copied += length;
+    }
+
+    let mut position = offset as usize;
+
+    let written = transfer_segments(&segments, |ptr, length|
+    {
+        let written = proc.write_at(fd.0, ptr, length, position);
+        position += if is_error(written) { 0 } else { written };
+
+        written
+    });
+
+    if is_error(written)
+    {
+        return Err(written);
+    }
+
+    Ok(written)
+}
+
+/*
+    ===================== Tests for Positional Reads =====================
+*/
+
+/// File held in memory, so the test can see what a mapping writes back
+#[cfg(test)]
+pub(super) struct MemoryFile
+{
+    data: alloc::rc::Rc<core::cell::RefCell<Vec<u8>>>,
+    position: usize
+}
+
+#[cfg(test)]
+impl process::descriptor::FileDescriptor for MemoryFile
+{
+    fn close(&mut self, _fs: &mut fs::vfs::FilesystemInterface) {}
+
+    fn write(&mut self, _fs: &mut fs::vfs::FilesystemInterface, buffer: *mut u8, count: usize) -> usize
+    {
+        let mut data = self.data.borrow_mut();
+        let count = count.min(data.len() - self.position);
+
+        data[self.position..self.position + count].copy_from_slice(unsafe { core::slice::from_raw_parts(buffer, count) });
+        self.position += count;
+
+        count
+    }
+
+    fn read(&mut self, fs: &mut fs::vfs::FilesystemInterface, buffer: *mut u8, count: usize) -> usize
+    {
+        let read = self.read_at(fs, buffer, count, self.position);
+        self.position += read;
+
+        read
+    }
+
+    fn read_at(&mut self, _fs: &mut fs::vfs::FilesystemInterface, buffer: *mut u8, count: usize, offset: usize) -> usize
+    {
+        let data = self.data.borrow();
+        let count = count.min(data.len().saturating_sub(offset));
+
+        unsafe { core::slice::from_raw_parts_mut(buffer, count) }.copy_from_slice(&data[offset..offset + count]);
+
+        count
+    }
+
+    fn get_inode(&mut self) -> Option<fs::structures::FilesystemIndex>
+    {
+        Some(fs::structures::FilesystemIndex { mount_id: 0, inode: 1 })
+    }
+
+    fn seek(&mut self, offset: usize, mode: process::descriptor::SeekMode) -> usize
+    {
+        self.position = match mode
+        {
+            process::descriptor::SeekMode::SeekSet => offset,
+            process::descriptor::SeekMode::SeekCurrent => self.position + offset,
+            process::descriptor::SeekMode::SeekEnd => self.data.borrow().len() + offset
+        };
+
+        self.position
+    }
+}
+
+/// Create a process with a page of a file mapped with the given permissions,
+/// the first half of the file is zero and the second half is `0x5A`
+#[cfg(test)]
+pub(super) fn map_test_file(perm: mem::mmu::PageTableEntryFlags) -> (super::Process, alloc::rc::Rc<core::cell::RefCell<Vec<u8>>>, usize, usize)
+{
+    mem::alloc::init_kernel_global_allocator(64);
+
+    if fs::vfs::get_vfs_reference().is_none()
+    {
+        fs::vfs::FilesystemInterface::new();
+    }
+
+    let mut contents = vec![0u8; mem::PAGE_SIZE];
+    contents[mem::PAGE_SIZE / 2..].fill(0x5A);
+
+    let data = alloc::rc::Rc::new(core::cell::RefCell::new(contents));
+
+    let mut proc = super::Process::from_code(&[0; 4]);
+    let fd = proc.add_descriptor(Box::new(MemoryFile { data: data.clone(), position: 0 })).unwrap();
+
+    // A shared mapping, as none of the mapping flags are given
+    let addr = proc.map(0, mem::PAGE_SIZE, Some(perm), 0, fd, 0).unwrap();
+
+    (proc, data, fd, addr)
+}
+
+/// Test Positional Reads - Data read into a clean page of a shared mapping is
+/// written back when the mapping is removed
+#[test_case]
+pub fn test_pread_into_shared_mapping()
+{
+    use mem::mmu::PageTableEntryFlags;
+
+    let perm = PageTableEntryFlags::user() | PageTableEntryFlags::readable() | PageTableEntryFlags::writable() | PageTableEntryFlags::accessed() | PageTableEntryFlags::dirty();
+    let (mut proc, data, fd, addr) = map_test_file(perm);
+
+    // Syncing the mapping leaves its page clean
+    proc.sync_mappings(addr, mem::PAGE_SIZE, false).unwrap();
+
+    assert_eq!(syscall_pread(&mut proc, Fd(fd), addr, 16, mem::PAGE_SIZE as isize / 2), Ok(16));
+
+    proc.unmap(addr, mem::PAGE_SIZE).unwrap();
+
+    assert!(data.borrow()[..16].iter().all(|b| *b == 0x5A));
+    assert!(data.borrow()[16..mem::PAGE_SIZE / 2].iter().all(|b| *b == 0));
+}
+
+/// Test Positional Reads - The kernel does not write into read only mappings
+#[test_case]
+pub fn test_pread_into_read_only_mapping()
+{
+    use mem::mmu::PageTableEntryFlags;
+
+    let perm = PageTableEntryFlags::user() | PageTableEntryFlags::readable() | PageTableEntryFlags::accessed();
+    let (mut proc, data, fd, addr) = map_test_file(perm);
+
+    assert_eq!(syscall_pread(&mut proc, Fd(fd), addr, 16, mem::PAGE_SIZE as isize / 2), Err(errno::EFAULT));
+    assert_eq!(unsafe { (proc.map_mem(addr).unwrap() as *const u8).read() }, 0);
+
+    proc.unmap(addr, mem::PAGE_SIZE).unwrap();
+
+    assert!(data.borrow()[..mem::PAGE_SIZE / 2].iter().all(|b| *b == 0));
+}
@@ -0,0 +1,199 @@
+use crate::*;
+
+use crate::process::PID;
+use crate::process::process::{Process, ProcessState};
+
+use super::args::Flags;
+use super::utils::{copy_from_user, copy_to_user};
+
+// Largest number of entries in either vector
+pub const IOV_MAX: usize = 1024;
+
+// Largest transfer a single call makes, longer requests are cut short
+pub const MAX_VM_TRANSFER: usize = 0x10_0000;
+
+/// Entry of a vector of buffers
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct IoVec
+{
+    pub base: usize,
+    pub len: usize
+}
+
+/// Read a vector of buffers from userspace
+fn read_iovecs(proc: &Process, addr: usize, count: usize) -> Result<Vec<IoVec>, usize>
+{
+    if count > IOV_MAX
+    {
+        return Err(errno::EINVAL);
+    }
+
+    let mut entries = vec![IoVec::default(); count];
+    let size = count * core::mem::size_of::<IoVec>();
+
+    let bytes = unsafe { core::slice::from_raw_parts_mut(entries.as_mut_ptr() as *mut u8, size) };
+    copy_from_user(proc, addr, bytes)?;
+
+    // The total length must be representable as a return value
+    entries.iter().try_fold(0usize, |total, entry| total.checked_add(entry.len).filter(|total| *total <= isize::MAX as usize)).ok_or(errno::EINVAL)?;
+
+    Ok(entries)
+}
+
+/// Copy the contents of the remote buffers out of the process, stopping at
+/// the first buffer which cannot be read in full
+fn gather_remote(target: &Process, remote: &[IoVec], limit: usize) -> Vec<u8>
+{
+    let mut data = Vec::new();
+
+    for entry in remote
+    {
+        let length = entry.len.min(limit - data.len());
+        let mut buffer = vec![0; length];
+
+        if copy_from_user(target, entry.base, &mut buffer).is_err()
+        {
+            break;
+        }
+
+        data.extend_from_slice(&buffer);
+
+        if data.len() == limit
+        {
+            break;
+        }
+    }
+
+    data
+}
+
+/// Copy data into the remote buffers of the process, stopping at the first
+/// buffer which cannot be written in full, returning the number of bytes
+/// written
+fn scatter_remote(target: &mut Process, remote: &[IoVec], data: &[u8]) -> usize
+{
+    let mut written = 0;
+
+    for entry in remote
+    {
+        let length = entry.len.min(data.len() - written);
+
+        if copy_to_user(target, entry.base, &data[written..written + length]).is_err()
+        {
+            break;
+        }
+
+        written += length;
+
+        if written == data.len()
+        {
+            break;
+        }
+    }
+
+    written
+}
+
+/// Look up the live process with the given pid, which may be the caller
+fn remote_process(proc: &mut Process, pid: PID) -> Result<&mut Process, usize>
+{
+    if pid == proc.pid
+    {
+        return Ok(proc);
+    }
+
+    process::scheduler::get_process_manager().unwrap().get_process_by_pid_mut(pid)
+        .filter(|target| !matches!(target.get_state(), ProcessState::Zombie | ProcessState::Dead))
+        .map(|target| &mut **target)
+        .ok_or(errno::ESRCH)
+}
+
+/// Transfer data between buffers in the calling process and buffers in the
+/// process with the given pid
+fn transfer(proc: &mut Process, pid: isize, local: usize, local_count: usize, remote: usize, remote_count: usize, write: bool) -> Result<usize, usize>
+{
+    let local = read_iovecs(proc, local, local_count)?;
+    let remote = read_iovecs(proc, remote, remote_count)?;
+
+    if pid <= 0 || pid > PID::MAX as isize
+    {
+        return Err(errno::ESRCH);
+    }
+
+    let pid = pid as PID;
+
+    // Another process may only be accessed if it runs entirely as the real
+    // user and group of the caller, or the caller is privileged
+    let credentials = proc.data.credentials;
+
+    if !credentials.may_access_memory(&remote_process(proc, pid)?.data.credentials)
+    {
+        return Err(errno::EPERM);
+    }
+
+    let local_total: usize = local.iter().map(|entry| entry.len).sum();
+    let remote_total: usize = remote.iter().map(|entry| entry.len).sum();
+    let limit = local_total.min(remote_total).min(MAX_VM_TRANSFER);
+
+    if limit == 0
+    {
+        return Ok(0);
+    }
+
+    let transferred = if write
+    {
+        let mut data = Vec::with_capacity(limit);
+
+        for entry in &local
+        {
+            let mut buffer = vec![0; entry.len.min(limit - data.len())];
+            copy_from_user(proc, entry.base, &mut buffer)?;
+
+            data.extend_from_slice(&buffer);
+        }
+
+        scatter_remote(remote_process(proc, pid)?, &remote, &data)
+    }
+    else
+    {
+        let data = gather_remote(remote_process(proc, pid)?, &remote, limit);
+
+        let mut copied = 0;
+
+        for entry in &local
+        {
+            let length = entry.len.min(data.len() - copied);
+            copy_to_user(proc, entry.base, &data[copied..copied + length])?;
+
+            copied += length;
+        }
+
+        data.len()
+    };
+
+    if transferred == 0
+    {
+        Err(errno::EFAULT)
+    }
+    else
+    {
+        Ok(transferred)
+    }
+}
+
+/// process_vm_readv Syscall
+///
+/// Read from buffers in another process into buffers in the caller
+pub fn syscall_process_vm_readv(proc: &mut Process, pid: isize, local: usize, local_count: usize, remote: usize, remote_count: usize, _flags: Flags<0>) -> Result<usize, usize>
+{
+    transfer(proc, pid, local, local_count, remote, remote_count, false)
+}
+
+/// process_vm_writev Syscall
+///
+/// Write from buffers in the caller into buffers in another process
+pub fn syscall_process_vm_writev(proc: &mut Process, pid: isize, local: usize, local_count: usize, remote: usize, remote_count: usize, _flags: Flags<0>) -> Result<usize, usize>
+{
+    transfer(proc, pid, local, local_count, remote, remote_count, true)
+}
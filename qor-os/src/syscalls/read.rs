@@ -1,11 +1,10 @@
 use crate::{errno, process::process::{ProcessState, WaitMode}};
 use crate::process;
+use crate::process::descriptor::O_NONBLOCK;
 
 /// Read Syscall
 pub fn syscall_read(proc: &mut super::Process, fd: usize, buffer: usize, count: usize) -> usize
 {
-    let ptr = proc.map_mem(buffer).unwrap() as *mut u8;
-
     if !proc.data.descriptors.contains_key(&fd)
     {
         return errno::EBADFD;
@@ -13,11 +12,21 @@ pub fn syscall_read(proc: &mut super::Process, fd: usize, buffer: usize, count:
 
     if proc.check_available(fd)
     {
-        proc.read(fd, ptr, count)
+        proc.read_user(fd, buffer, count)
+    }
+    else if proc.data.descriptors.get(&fd).unwrap().borrow().get_status_flags() & O_NONBLOCK > 0
+    {
+        errno::EAGAIN
     }
     else
     {
-        proc.state = ProcessState::Waiting(WaitMode::ForIO((fd, count, ptr)));
+        // Fault on a bad buffer now rather than once data arrives
+        if let Err(e) = crate::syscalls::utils::user_buffer_segments(proc, buffer, count, true)
+        {
+            return e;
+        }
+
+        proc.state = ProcessState::Waiting(WaitMode::ForIO((fd, count, buffer)));
         proc.program_counter += 4;
         
         let schedule = process::scheduler::schedule_next();
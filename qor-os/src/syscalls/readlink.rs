@@ -0,0 +1,36 @@
+use crate::*;
+
+use libutils::paths::OwnedPath;
+
+use super::utils::{copy_to_user, userspace_path_at};
+
+/// Copy the target of a symbolic link to a userspace buffer, the target is
+/// truncated to fit and is not null terminated
+fn copy_link_target(proc: &mut super::Process, path: OwnedPath, buffer: usize, size: isize) -> Result<usize, usize>
+{
+    if size <= 0
+    {
+        return Err(errno::EINVAL);
+    }
+
+    let target = proc.readlink(path)?;
+    let length = target.len().min(size as usize);
+
+    copy_to_user(proc, buffer, &target.as_bytes()[..length])?;
+
+    Ok(length)
+}
+
+/// readlink Syscall
+pub fn syscall_readlink(proc: &mut super::Process, path: OwnedPath, buffer: usize, size: isize) -> Result<usize, usize>
+{
+    copy_link_target(proc, path, buffer, size)
+}
+
+/// readlinkat Syscall
+pub fn syscall_readlinkat(proc: &mut super::Process, dirfd: isize, path: usize, buffer: usize, size: isize) -> Result<usize, usize>
+{
+    let path = userspace_path_at(proc, dirfd, path)?;
+
+    copy_link_target(proc, path, buffer, size)
+}
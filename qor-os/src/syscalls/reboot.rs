@@ -1,24 +1,77 @@
 use crate::*;
 
-const REBOOT_CMD_HALT: u32 = 0xcdef0123;
+// Mirror the definitions in syscalls.h
+/*
+    #define REBOOT_MAGIC1 0xfee1dead
+    #define REBOOT_MAGIC2 0x516f7200
+
+    #define REBOOT_CMD_CAD_OFF 0x00000000
+    #define REBOOT_CMD_RESTART 0x01234567
+    #define REBOOT_CMD_CAD_ON 0x89abcdef
+    #define REBOOT_CMD_HALT 0xcdef0123
+    #define REBOOT_CMD_POWER_OFF 0x4321fedc
+    #define REBOOT_CMD_SW_SUSPEND 0xd000fce2
+*/
+
+const REBOOT_MAGIC1: u32 = 0xfee1dead;
+
+// The second magic is the one Qor defines, or any of the dates Linux accepts
+const REBOOT_MAGIC2: [u32; 5] = [0x516f7200, 0x28121969, 0x05121996, 0x16041998, 0x20112000];
+
+const REBOOT_CMD_CAD_OFF: u32 =   0x00000000;
+const REBOOT_CMD_RESTART: u32 =   0x01234567;
+const REBOOT_CMD_CAD_ON: u32 =    0x89abcdef;
+const REBOOT_CMD_HALT: u32 =      0xcdef0123;
+const REBOOT_CMD_POWER_OFF: u32 = 0x4321fedc;
+const REBOOT_CMD_SW_SUSPEND: u32 = 0xd000fce2;
 
 /// Reboot Syscall
-pub fn syscall_reboot(_proc: &mut super::Process, magic1: usize, magic2: usize, cmd: usize, _args: usize) -> usize
+///
+/// Power off or restart the system, only privileged processes may do so. The
+/// processes are terminated and the filesystems synced and unmounted before
+/// the power driver is used, so this returns immediately and the shutdown
+/// continues over the following scheduler ticks. A suspend returns once the
+/// system has resumed.
+pub fn syscall_reboot(proc: &mut super::Process, magic1: usize, magic2: usize, cmd: usize, _args: usize) -> Result<usize, usize>
 {
-    // Verify the magic
-    if magic1 as u32 != 0xfee1dead || magic2 as u32 != 0x516f7200
+    if !proc.is_privileged()
     {
-        return errno::EINVAL;
+        return Err(errno::EPERM);
     }
 
-    // Check the command
-    if cmd as u32 == REBOOT_CMD_HALT
+    // Verify the magic
+    if magic1 as u32 != REBOOT_MAGIC1 || !REBOOT_MAGIC2.contains(&(magic2 as u32))
     {
-        halt::kernel_halt();
-        return 0;
+        return Err(errno::EINVAL);
     }
-    else
+
+    match cmd as u32
     {
-        return errno::EINVAL;
+        REBOOT_CMD_HALT | REBOOT_CMD_POWER_OFF =>
+        {
+            kdebugln!(Syscalls, "PID {} requests power off", proc.pid);
+            halt::request_shutdown();
+        },
+        REBOOT_CMD_RESTART =>
+        {
+            kdebugln!(Syscalls, "PID {} requests restart", proc.pid);
+            halt::request_restart();
+        },
+        REBOOT_CMD_SW_SUSPEND =>
+        {
+            if halt::shutdown_in_progress()
+            {
+                return Err(errno::EBUSY);
+            }
+
+            kdebugln!(Syscalls, "PID {} requests suspend", proc.pid);
+            suspend::suspend_to_ram();
+        },
+        // Ctrl-Alt-Delete is not handled, so changing its behavior is
+        // accepted and ignored
+        REBOOT_CMD_CAD_ON | REBOOT_CMD_CAD_OFF => {},
+        _ => return Err(errno::EINVAL)
     }
-}
\ No newline at end of file
+
+    Ok(0)
+}
@@ -0,0 +1,22 @@
+use libutils::paths::OwnedPath;
+
+use super::utils::userspace_path_at;
+
+/// rename Syscall
+pub fn syscall_rename(proc: &mut super::Process, old_path: OwnedPath, new_path: OwnedPath) -> Result<usize, usize>
+{
+    proc.rename(old_path, new_path)?;
+
+    Ok(0)
+}
+
+/// renameat Syscall
+pub fn syscall_renameat(proc: &mut super::Process, old_dirfd: isize, old_path: usize, new_dirfd: isize, new_path: usize) -> Result<usize, usize>
+{
+    let old_path = userspace_path_at(proc, old_dirfd, old_path)?;
+    let new_path = userspace_path_at(proc, new_dirfd, new_path)?;
+
+    proc.rename(old_path, new_path)?;
+
+    Ok(0)
+}
@@ -0,0 +1,99 @@
+use crate::*;
+
+use process::credentials::{Credentials, IdSet};
+use process::process::{Process, ProcessState};
+use process::rlimit::ResourceLimit;
+use process::PID;
+
+use super::args::UserPtr;
+
+/// getrlimit Syscall
+pub fn syscall_getrlimit(proc: &mut super::Process, resource: usize, limit: UserPtr<ResourceLimit>) -> Result<usize, usize>
+{
+    limit.write(proc, proc.data.limits.get(resource)?)?;
+
+    Ok(0)
+}
+
+/// setrlimit Syscall
+pub fn syscall_setrlimit(proc: &mut super::Process, resource: usize, limit: UserPtr<ResourceLimit>) -> Result<usize, usize>
+{
+    let value = limit.read(proc)?;
+    let privileged = proc.is_privileged();
+
+    proc.data.limits.set(resource, value, privileged)?;
+
+    Ok(0)
+}
+
+/// Replace the limit on a resource of a process if a new value is given,
+/// returning the previous limit
+fn update_limit(target: &mut Process, resource: usize, new_value: Option<ResourceLimit>, privileged: bool) -> Result<ResourceLimit, usize>
+{
+    let old_value = target.data.limits.get(resource)?;
+
+    if let Some(value) = new_value
+    {
+        target.data.limits.set(resource, value, privileged)?;
+    }
+
+    Ok(old_value)
+}
+
+/// Check if every user and group id of a process matches the real ids in the
+/// given credentials
+fn runs_as(target: &Process, credentials: &Credentials) -> bool
+{
+    let (user, group) = (credentials.user.real, credentials.group.real);
+
+    target.data.credentials.user == IdSet::new(user) && target.data.credentials.group == IdSet::new(group)
+}
+
+/// prlimit64 Syscall
+///
+/// Get and set the limit on a resource of any process, a pid of zero refers
+/// to the calling process. The old limit is read before the new one is set.
+pub fn syscall_prlimit(proc: &mut super::Process, pid: isize, resource: usize, new_limit: Option<UserPtr<ResourceLimit>>, old_limit: Option<UserPtr<ResourceLimit>>) -> Result<usize, usize>
+{
+    if pid < 0 || pid > PID::MAX as isize
+    {
+        return Err(errno::ESRCH);
+    }
+
+    let new_value = match new_limit
+    {
+        Some(ptr) => Some(ptr.read(proc)?),
+        None => None
+    };
+
+    let pid = if pid == 0 { proc.pid } else { pid as PID };
+
+    let privileged = proc.is_privileged();
+
+    let old_value = if pid == proc.pid
+    {
+        update_limit(proc, resource, new_value, privileged)?
+    }
+    else
+    {
+        let target = process::scheduler::get_process_manager().unwrap().get_process_by_pid_mut(pid)
+            .filter(|target| !matches!(target.get_state(), ProcessState::Zombie | ProcessState::Dead))
+            .ok_or(errno::ESRCH)?;
+
+        // An unprivileged process may only reach processes running entirely
+        // under its own real user and group
+        if !privileged && !runs_as(target, &proc.data.credentials)
+        {
+            return Err(errno::EPERM);
+        }
+
+        update_limit(target, resource, new_value, privileged)?
+    };
+
+    if let Some(ptr) = old_limit
+    {
+        ptr.write(proc, old_value)?;
+    }
+
+    Ok(0)
+}
@@ -0,0 +1,72 @@
+use crate::*;
+
+use drivers::timer::KernelTime;
+
+use process::poll::{PollSet, FD_SETSIZE};
+
+use super::args::UserPtr;
+use super::gettimeofday::TimeVal;
+use super::nanosleep::IncomingTime;
+
+/// Select Syscall
+///
+/// Wait until one of the descriptors below `count` in the read, write or
+/// exception sets is ready, or the timeout has passed. The sets are replaced
+/// with the descriptors which are ready, and a null timeout waits
+/// indefinitely.
+pub fn syscall_select(proc: &mut super::Process, count: usize, read: usize, write: usize, except: usize, timeout: Option<UserPtr<TimeVal>>) -> Result<usize, usize>
+{
+    let timeout = if let Some(timeout) = timeout
+    {
+        let time = timeout.read(proc)?;
+
+        if time.micro_seconds >= 1_000_000
+        {
+            return Err(errno::EINVAL);
+        }
+
+        Some(KernelTime::seconds(time.seconds) + KernelTime::microseconds(time.micro_seconds))
+    }
+    else
+    {
+        None
+    };
+
+    select(proc, count, [read, write, except], timeout)
+}
+
+/// Pselect6 Syscall
+///
+/// Select with the timeout given in nanoseconds. Signal masks are not yet
+/// supported, so the mask argument is ignored.
+pub fn syscall_pselect6(proc: &mut super::Process, count: usize, read: usize, write: usize, except: usize, timeout: Option<UserPtr<IncomingTime>>, _mask: usize) -> Result<usize, usize>
+{
+    let timeout = if let Some(timeout) = timeout
+    {
+        let time = timeout.read(proc)?;
+
+        if time.nano_seconds >= 1_000_000_000
+        {
+            return Err(errno::EINVAL);
+        }
+
+        Some(KernelTime::seconds(time.seconds) + KernelTime::nanoseconds(time.nano_seconds))
+    }
+    else
+    {
+        None
+    };
+
+    select(proc, count, [read, write, except], timeout)
+}
+
+/// Wait on the given fd_sets
+fn select(proc: &mut super::Process, count: usize, sets: [usize; 3], timeout: Option<KernelTime>) -> Result<usize, usize>
+{
+    if count > FD_SETSIZE
+    {
+        return Err(errno::EINVAL);
+    }
+
+    super::poll::wait_for_descriptors(proc, PollSet::Select { count, sets }, timeout)
+}
@@ -0,0 +1,93 @@
+use crate::*;
+
+use process::descriptor::SeekMode;
+
+use super::args::{Fd, UserPtr};
+use super::utils::is_error;
+
+// Size of the chunks data is moved in
+const SENDFILE_CHUNK_SIZE: usize = mem::PAGE_SIZE;
+
+/// sendfile Syscall
+///
+/// Copy data from one descriptor to another inside the kernel, so it never
+/// passes through userspace. Given an offset, the input is read from there and
+/// the offset is advanced past the data sent, leaving the position of the
+/// input unchanged, otherwise the input is read from its position.
+pub fn syscall_sendfile(proc: &mut super::Process, out_fd: Fd, in_fd: Fd, offset: Option<UserPtr<u64>>, count: usize) -> Result<usize, usize>
+{
+    let input = proc.data.descriptors.get(&in_fd.0).unwrap();
+    let output = proc.data.descriptors.get(&out_fd.0).unwrap();
+
+    let mut position = match offset
+    {
+        Some(offset) => Some(offset.read(proc)? as usize),
+        None => None
+    };
+
+    proc.ensure_fs();
+    let fs = proc.fs_interface.as_mut().unwrap();
+
+    let mut buffer = vec![0u8; SENDFILE_CHUNK_SIZE];
+    let mut sent = 0;
+
+    while sent < count
+    {
+        let length = (count - sent).min(SENDFILE_CHUNK_SIZE);
+
+        let read = match position
+        {
+            Some(position) => input.borrow_mut().read_at(fs, buffer.as_mut_ptr(), length, position),
+            None => input.borrow_mut().read(fs, buffer.as_mut_ptr(), length)
+        };
+
+        if is_error(read)
+        {
+            if sent == 0
+            {
+                return Err(read);
+            }
+
+            break;
+        }
+
+        if read == 0
+        {
+            break;
+        }
+
+        let written = output.borrow_mut().write(fs, buffer.as_mut_ptr(), read);
+
+        if is_error(written)
+        {
+            if sent == 0
+            {
+                return Err(written);
+            }
+
+            break;
+        }
+
+        sent += written;
+        position = position.map(|position| position + written);
+
+        // The output is full, so give back the data which was read but not
+        // written
+        if written < read
+        {
+            if position.is_none()
+            {
+                input.borrow_mut().seek((written as isize - read as isize) as usize, SeekMode::SeekCurrent);
+            }
+
+            break;
+        }
+    }
+
+    if let (Some(offset), Some(position)) = (offset, position)
+    {
+        offset.write(proc, position as u64)?;
+    }
+
+    Ok(sent)
+}
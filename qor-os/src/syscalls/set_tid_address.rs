@@ -0,0 +1,13 @@
+use crate::*;
+
+use super::args::UserPtr;
+
+/// set_tid_address Syscall
+pub fn syscall_set_tid_address(proc: &mut super::Process, tidptr: Option<UserPtr<u32>>) -> usize
+{
+    kdebugln!(Syscalls, "PID {} setting its clear child tid address to {:?}", proc.pid, tidptr);
+
+    proc.data.clear_child_tid = tidptr.map(|ptr| ptr.addr());
+
+    proc.pid as usize
+}
@@ -1,13 +1,102 @@
+use crate::*;
+
 use crate::process::PID;
+use crate::process::process::{Process, ProcessState};
+
+/// Check if a process has not yet exited
+fn is_alive(proc: &Process) -> bool
+{
+    !matches!(proc.get_state(), ProcessState::Zombie | ProcessState::Dead)
+}
 
-/// Pause setpgid
-pub fn syscall_setpgid(proc: &mut super::Process, pid: usize, pgid: usize) -> usize
+/// setpgid Syscall
+///
+/// Move a process into a process group, a pid of zero is the caller and a
+/// pgid of zero uses the pid of the process as the group. Only the caller or
+/// a child in the same session which has not yet called execve can be moved,
+/// a session leader cannot be moved, and the group must either be new or
+/// already exist in the same session.
+pub fn syscall_setpgid(proc: &mut super::Process, pid: isize, pgid: isize) -> Result<usize, usize>
 {
-    if proc.pid == pid as PID
+    if pid < 0 || pgid < 0
+    {
+        return Err(errno::EINVAL);
+    }
+
+    let pid = if pid == 0 { proc.pid } else { pid as PID };
+    let pgid = if pgid == 0 { pid } else { pgid as PID };
+
+    let manager = process::scheduler::get_process_manager().unwrap();
+
+    let session = if pid == proc.pid
     {
-        proc.data.process_group_id = pgid as PID;
-        return 0;
+        proc.data.session_id
     }
+    else
+    {
+        let target = manager.get_process_by_pid(pid)
+            .filter(|target| proc.data.children.contains(&pid) && is_alive(target))
+            .ok_or(errno::ESRCH)?;
+
+        if target.data.session_id != proc.data.session_id
+        {
+            return Err(errno::EPERM);
+        }
+
+        if target.data.has_executed
+        {
+            return Err(errno::EACCES);
+        }
+
+        target.data.session_id
+    };
 
-    usize::MAX
-}
\ No newline at end of file
+    // A session leader stays in the group it created
+    if session == pid
+    {
+        return Err(errno::EPERM);
+    }
+
+    // Joining a group other than its own requires the group to exist
+    if pgid != pid && !manager.processes.values().any(|other| other.data.process_group_id == pgid && other.data.session_id == session && is_alive(other))
+    {
+        return Err(errno::EPERM);
+    }
+
+    if pid == proc.pid
+    {
+        proc.data.process_group_id = pgid;
+    }
+    else
+    {
+        manager.get_process_by_pid_mut(pid).unwrap().data.process_group_id = pgid;
+    }
+
+    Ok(0)
+}
+
+/// getpgid Syscall
+///
+/// Get the process group of a process, a pid of zero is the caller
+pub fn syscall_getpgid(proc: &mut super::Process, pid: usize) -> Result<usize, usize>
+{
+    if pid == 0 || pid as PID == proc.pid
+    {
+        return Ok(proc.data.process_group_id as usize);
+    }
+
+    let manager = process::scheduler::get_process_manager().unwrap();
+
+    manager.get_process_by_pid(pid as PID)
+        .filter(|target| is_alive(target))
+        .map(|target| target.data.process_group_id as usize)
+        .ok_or(errno::ESRCH)
+}
+
+/// getpgrp Syscall
+///
+/// Get the process group of the caller
+pub fn syscall_getpgrp(proc: &mut super::Process) -> usize
+{
+    proc.data.process_group_id as usize
+}
@@ -0,0 +1,46 @@
+use crate::*;
+
+use crate::process::PID;
+use crate::process::process::ProcessState;
+
+/// setsid Syscall
+///
+/// Start a new session with the caller as its leader, the caller also becomes
+/// the leader of a new process group. A process which already leads a group
+/// cannot start a session, as other members of its group would be left in
+/// the old session. Returns the id of the new session.
+pub fn syscall_setsid(proc: &mut super::Process) -> Result<usize, usize>
+{
+    let manager = process::scheduler::get_process_manager().unwrap();
+
+    let group_in_use = manager.processes.values()
+        .any(|other| other.data.process_group_id == proc.pid && !matches!(other.get_state(), ProcessState::Zombie | ProcessState::Dead));
+
+    if proc.data.process_group_id == proc.pid || group_in_use
+    {
+        return Err(errno::EPERM);
+    }
+
+    proc.data.session_id = proc.pid;
+    proc.data.process_group_id = proc.pid;
+
+    Ok(proc.pid as usize)
+}
+
+/// getsid Syscall
+///
+/// Get the session of a process, a pid of zero is the caller
+pub fn syscall_getsid(proc: &mut super::Process, pid: usize) -> Result<usize, usize>
+{
+    if pid == 0 || pid as PID == proc.pid
+    {
+        return Ok(proc.data.session_id as usize);
+    }
+
+    let manager = process::scheduler::get_process_manager().unwrap();
+
+    manager.get_process_by_pid(pid as PID)
+        .filter(|target| !matches!(target.get_state(), ProcessState::Zombie | ProcessState::Dead))
+        .map(|target| target.data.session_id as usize)
+        .ok_or(errno::ESRCH)
+}
@@ -2,41 +2,59 @@ use crate::*;
 
 use process::signals::*;
 
+use super::args::UserPtr;
+
 /// sigaction Syscall
-pub fn syscall_sigaction(proc: &mut super::Process, signal: usize, new_ptr: usize, old_ptr: usize)
+///
+/// Install a new action for a signal and return the previous one, either
+/// pointer may be null. A handler address of SIG_DFL restores the default
+/// disposition and SIG_IGN ignores the signal.
+pub fn syscall_sigaction(proc: &mut super::Process, signal: usize, new_ptr: Option<UserPtr<SignalAction>>, old_ptr: Option<UserPtr<SignalAction>>) -> Result<usize, usize>
 {
-    let new_ref = unsafe { (proc.map_mem(new_ptr).unwrap() as *mut SignalAction).as_mut() };
-    let _old_ref = unsafe { (proc.map_mem(old_ptr).unwrap() as *mut SignalAction).as_mut() };
+    let sig = SignalType::from_number(signal).ok_or(errno::EINVAL)?;
 
-    if let Some(new) = new_ref
+    if let Some(old_ptr) = old_ptr
     {
-        let sig = SignalType::number_to_signal(signal);
+        let old = match proc.get_disposition_for_signal(sig)
+        {
+            SignalDisposition::Handler(action) => action,
+            disposition =>
+            {
+                let handler_value = if disposition == SignalDisposition::Ignore { SIG_IGN } else { SIG_DFL };
 
-        kdebugln!(Signals, "sigaction from PID {}: On Signal {:?}", proc.pid, sig);
+                SignalAction { handler_value, action_fn_ptr: 0, mask: 0, flags: 0, restoring_addr: 0 }
+            }
+        };
 
-        if new.flags & 1 > 0
+        old_ptr.write(proc, old)?;
+    }
+
+    if let Some(new_ptr) = new_ptr
+    {
+        if !sig.can_be_caught()
         {
-            // Set the handler as a function
-            proc.data.signal_map.insert(sig, SignalDisposition::Handler(new.action_fn_ptr));
+            return Err(errno::EINVAL);
         }
-        else
+
+        let new = new_ptr.read(proc)?;
+
+        kdebugln!(Signals, "sigaction from PID {}: On Signal {:?}", proc.pid, sig);
+
+        let disposition = match new.handler_address()
         {
-            // Set the handler as a dispoisiton
-            match new.handler_value
-            {
-                0 => {},
-                1 => { return; },
-                2 => { proc.data.signal_map.insert(sig, SignalDisposition::Ignore); return; }
-                _ => todo!()
-            }
+            SIG_DFL => sig.default_disposition(),
+            SIG_IGN => SignalDisposition::Ignore,
+            _ => SignalDisposition::Handler(new)
+        };
+
+        // Ignoring a signal discards it if it is already pending
+        if disposition == SignalDisposition::Ignore
+        {
+            proc.pending_signals.remove(&sig);
         }
 
-        // TODO: We still need to handle writing to the old_ref if it exists
-    }
-    else
-    {
-        // We still need to work on writing to the old_ref, and this branch is
-        // where that is the only option.
-        todo!()
+        proc.data.signal_map.insert(sig, disposition);
     }
-}
\ No newline at end of file
+
+    Ok(0)
+}
@@ -0,0 +1,52 @@
+use crate::*;
+
+use process::descriptor::{FileDescriptor, O_CLOEXEC, O_NONBLOCK, O_RDONLY};
+use process::signalfd::SignalDescriptor;
+
+use super::args::{Flags, UserPtr};
+
+// Mirror the definitions in syscalls.h
+/*
+    #define SFD_NONBLOCK O_NONBLOCK
+    #define SFD_CLOEXEC O_CLOEXEC
+*/
+
+const SFD_NONBLOCK: usize = O_NONBLOCK;
+const SFD_CLOEXEC: usize = O_CLOEXEC;
+
+/// signalfd Syscall
+pub fn syscall_signalfd(proc: &mut super::Process, fd: isize, mask: UserPtr<u64>, size: usize) -> Result<usize, usize>
+{
+    syscall_signalfd4(proc, fd, mask, size, Flags(0))
+}
+
+/// signalfd4 Syscall
+///
+/// Create a descriptor which reads the pending signals in the given set, or
+/// change the set of an existing signalfd if a descriptor other than -1 is
+/// given, in which case the flags are not used
+pub fn syscall_signalfd4(proc: &mut super::Process, fd: isize, mask: UserPtr<u64>, size: usize, flags: Flags<{ SFD_NONBLOCK | SFD_CLOEXEC }>) -> Result<usize, usize>
+{
+    if size != core::mem::size_of::<u64>()
+    {
+        return Err(errno::EINVAL);
+    }
+
+    let mask = mask.read(proc)?;
+
+    if fd != -1
+    {
+        let descriptor = proc.data.descriptors.get(&(fd as usize)).ok_or(errno::EBADF)?;
+        descriptor.borrow_mut().set_signal_mask(mask)?;
+
+        return Ok(fd as usize);
+    }
+
+    let mut descriptor = SignalDescriptor::new(mask);
+    descriptor.set_status_flags(O_RDONLY | (flags.0 & SFD_NONBLOCK));
+
+    let fd = proc.add_descriptor(Box::new(descriptor))?;
+    proc.set_close_on_exec(fd, flags.contains(SFD_CLOEXEC));
+
+    Ok(fd)
+}
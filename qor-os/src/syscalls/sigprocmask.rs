@@ -0,0 +1,42 @@
+use crate::*;
+
+use process::signals::SignalType;
+
+use super::args::UserPtr;
+
+// Must be kept in sync with syscalls.h
+pub const SIG_BLOCK: usize = 0;
+pub const SIG_UNBLOCK: usize = 1;
+pub const SIG_SETMASK: usize = 2;
+
+/// sigprocmask Syscall
+///
+/// Change the set of blocked signals and return the previous set, either
+/// pointer may be null. Blocked signals stay pending until they are
+/// unblocked, `SIGKILL` and `SIGSTOP` cannot be blocked.
+pub fn syscall_sigprocmask(proc: &mut super::Process, how: usize, set: Option<UserPtr<u64>>, old_set: Option<UserPtr<u64>>) -> Result<usize, usize>
+{
+    let old = proc.data.signal_mask;
+
+    if let Some(set) = set
+    {
+        let set = set.read(proc)?;
+
+        let mask = match how
+        {
+            SIG_BLOCK => old | set,
+            SIG_UNBLOCK => old & !set,
+            SIG_SETMASK => set,
+            _ => return Err(errno::EINVAL)
+        };
+
+        proc.data.signal_mask = SignalType::sanitize_mask(mask);
+    }
+
+    if let Some(old_set) = old_set
+    {
+        old_set.write(proc, old)?;
+    }
+
+    Ok(0)
+}
@@ -1,5 +1,10 @@
 /// sigreturn Syscall
-pub fn syscall_sigreturn(proc: &mut super::Process)
+///
+/// Restore the context saved when the signal handler was entered, the signal
+/// frame is found at the stack pointer the handler returned with
+pub fn syscall_sigreturn(proc: &mut super::Process) -> usize
 {
     proc.return_from_signal();
-}
\ No newline at end of file
+
+    unreachable!()
+}
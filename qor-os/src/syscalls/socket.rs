@@ -0,0 +1,295 @@
+use crate::*;
+
+use process::descriptor::{SocketDescriptor, O_NONBLOCK};
+use process::process::{ProcessState, WaitMode};
+use process::socket::{SocketAddress, UnixName, SOCK_CLOEXEC, SOCK_NONBLOCK, SOMAXCONN, UNIX_PATH_MAX};
+
+use libutils::paths::OwnedPath;
+
+use super::args::{Fd, Flags, UserPtr};
+use super::utils::is_error;
+
+// Must be kept in sync with syscalls.h
+pub const MSG_DONTWAIT: usize = 0x40;
+pub const MSG_NOSIGNAL: usize = 0x4000;
+
+// Size of the largest sockaddr which can be passed in
+const SOCKADDR_MAX: usize = 2 + UNIX_PATH_MAX;
+
+/// Run a function on the socket a descriptor refers to, giving `ENOTSOCK` if
+/// it is not a socket
+fn with_socket<R, F: FnOnce(&mut SocketDescriptor) -> Result<R, usize>>(proc: &super::Process, fd: Fd, f: F) -> Result<R, usize>
+{
+    let descriptor = proc.data.descriptors.get(&fd.0).ok_or(errno::EBADF)?;
+    let mut descriptor = descriptor.borrow_mut();
+
+    f(descriptor.as_socket().ok_or(errno::ENOTSOCK)?)
+}
+
+/// Check if operations on a descriptor should fail rather than block
+fn is_nonblocking(proc: &super::Process, fd: Fd) -> bool
+{
+    proc.data.descriptors.get(&fd.0).map(|desc| desc.borrow().get_status_flags() & O_NONBLOCK > 0).unwrap_or(false)
+}
+
+/// Read a socket address from userspace
+fn read_address(proc: &super::Process, addr: usize, length: usize) -> Result<SocketAddress, usize>
+{
+    if length > SOCKADDR_MAX
+    {
+        return Err(errno::EINVAL);
+    }
+
+    let mut bytes = vec![0; length];
+    super::utils::copy_from_user(proc, addr, &mut bytes)?;
+
+    SocketAddress::parse(&bytes, &proc.data.cwd)
+}
+
+/// Find the name a socket address refers to, a path is looked up from the
+/// root and in the mount namespace of the process, so the directory it names
+/// must exist
+fn resolve_name(proc: &mut super::Process, address: &SocketAddress) -> Result<UnixName, usize>
+{
+    match address
+    {
+        SocketAddress::Unnamed => Err(errno::EINVAL),
+        SocketAddress::Unix(name) if name.starts_with('\0') => Ok(UnixName::Abstract(name.clone())),
+        SocketAddress::Unix(path) =>
+        {
+            let path = OwnedPath::new(path.as_str());
+            let (directory, entry) = path.split_last();
+
+            Ok(UnixName::Path(proc.path_inode(&directory)?, entry.into()))
+        }
+    }
+}
+
+/// Write a socket address back to userspace, truncated to the size of the
+/// buffer given, the full size of the address is written to `length`
+fn write_address(proc: &mut super::Process, address: SocketAddress, addr: usize, length: UserPtr<u32>) -> Result<(), usize>
+{
+    let bytes = address.to_bytes();
+    let space = length.read(proc)? as usize;
+
+    super::utils::copy_to_user(proc, addr, &bytes[..bytes.len().min(space)])?;
+    length.write(proc, bytes.len() as u32)
+}
+
+/// Add a socket to the descriptor table of the process with the socket flags
+/// given
+fn add_socket(proc: &mut super::Process, socket: Box<dyn process::socket::Socket>, flags: usize) -> Result<usize, usize>
+{
+    let fd = proc.add_descriptor(Box::new(SocketDescriptor::new(socket, flags & SOCK_NONBLOCK)))?;
+    proc.set_close_on_exec(fd, flags & SOCK_CLOEXEC != 0);
+
+    Ok(fd)
+}
+
+/// socket Syscall
+pub fn syscall_socket(proc: &mut super::Process, domain: usize, kind: usize, protocol: usize) -> Result<usize, usize>
+{
+    let flags = kind & (SOCK_NONBLOCK | SOCK_CLOEXEC);
+    let socket = process::socket::new_socket(domain, kind & !flags, protocol)?;
+
+    add_socket(proc, socket, flags)
+}
+
+/// socketpair Syscall
+pub fn syscall_socketpair(proc: &mut super::Process, domain: usize, kind: usize, protocol: usize, fds: UserPtr<[u32; 2]>) -> Result<usize, usize>
+{
+    // Make sure the buffer can be written before creating the sockets
+    fds.check(proc, true)?;
+
+    let flags = kind & (SOCK_NONBLOCK | SOCK_CLOEXEC);
+    let (first, second) = process::socket::new_socket_pair(domain, kind & !flags, protocol)?;
+
+    let first = add_socket(proc, first, flags)?;
+
+    let second = match add_socket(proc, second, flags)
+    {
+        Ok(fd) => fd,
+        Err(e) =>
+        {
+            proc.close(first);
+            return Err(e);
+        }
+    };
+
+    fds.write(proc, [first as u32, second as u32])?;
+
+    Ok(0)
+}
+
+/// bind Syscall
+pub fn syscall_bind(proc: &mut super::Process, fd: Fd, addr: usize, length: usize) -> Result<usize, usize>
+{
+    let address = read_address(proc, addr, length)?;
+    let name = resolve_name(proc, &address)?;
+
+    with_socket(proc, fd, |desc| desc.socket().bind(&address, name))?;
+
+    Ok(0)
+}
+
+/// listen Syscall
+pub fn syscall_listen(proc: &mut super::Process, fd: Fd, backlog: isize) -> Result<usize, usize>
+{
+    let backlog = if backlog < 0 { SOMAXCONN } else { backlog as usize };
+    with_socket(proc, fd, |desc| desc.socket().listen(backlog))?;
+
+    Ok(0)
+}
+
+/// connect Syscall
+///
+/// The connection is queued on the listening socket and can be used at once,
+/// a call blocks only while the queue of the listening socket is full
+pub fn syscall_connect(proc: &mut super::Process, fd: Fd, addr: usize, length: usize) -> Result<usize, usize>
+{
+    let address = read_address(proc, addr, length)?;
+    let name = resolve_name(proc, &address)?;
+
+    match with_socket(proc, fd, |desc| desc.socket().connect(&address, &name))
+    {
+        Err(errno::EAGAIN) if !is_nonblocking(proc, fd) => super::utils::yield_and_retry(proc),
+        result => result.map(|_| 0)
+    }
+}
+
+/// accept Syscall
+pub fn syscall_accept(proc: &mut super::Process, fd: Fd, addr: usize, length: Option<UserPtr<u32>>) -> Result<usize, usize>
+{
+    syscall_accept4(proc, fd, addr, length, Flags(0))
+}
+
+/// accept4 Syscall
+///
+/// Take the next connection made to a listening socket, blocking until one
+/// arrives unless the socket is non blocking
+pub fn syscall_accept4(proc: &mut super::Process, fd: Fd, addr: usize, length: Option<UserPtr<u32>>, flags: Flags<{ SOCK_NONBLOCK | SOCK_CLOEXEC }>) -> Result<usize, usize>
+{
+    let socket = match with_socket(proc, fd, |desc| desc.socket().accept())
+    {
+        Err(errno::EAGAIN) if !is_nonblocking(proc, fd) => super::utils::yield_and_retry(proc),
+        result => result?
+    };
+
+    let peer = socket.peer_address()?;
+    let new_fd = add_socket(proc, socket, flags.0)?;
+
+    if let Some(length) = length
+    {
+        if let Err(e) = write_address(proc, peer, addr, length)
+        {
+            proc.close(new_fd);
+            return Err(e);
+        }
+    }
+
+    Ok(new_fd)
+}
+
+/// sendto Syscall
+///
+/// Connected sockets cannot be given an address, and a socket without a peer
+/// fails at once, with `ENOTCONN` if it is listening and `EDESTADDRREQ`
+/// otherwise. A send blocks until there is room for some of the data unless
+/// the socket is non blocking or `MSG_DONTWAIT` is given. `MSG_NOSIGNAL` is accepted, as sending to a
+/// closed socket gives `EPIPE` without raising `SIGPIPE`.
+pub fn syscall_sendto(proc: &mut super::Process, fd: Fd, buffer: usize, count: usize, flags: Flags<{ MSG_DONTWAIT | MSG_NOSIGNAL }>, addr: usize, _length: usize) -> Result<usize, usize>
+{
+    let (connected, listening) = with_socket(proc, fd, |desc| Ok((desc.socket().peer_address().is_ok(), desc.socket().is_listening())))?;
+
+    if addr != 0
+    {
+        return Err(if connected { errno::EISCONN } else { errno::EOPNOTSUPP });
+    }
+
+    // Nothing will ever make room on a socket without a peer
+    if listening
+    {
+        return Err(errno::ENOTCONN);
+    }
+
+    if !connected
+    {
+        return Err(errno::EDESTADDRREQ);
+    }
+
+    if !proc.data.descriptors.get(&fd.0).unwrap().borrow().check_writable()
+    {
+        if flags.contains(MSG_DONTWAIT) || is_nonblocking(proc, fd)
+        {
+            return Err(errno::EAGAIN);
+        }
+
+        super::utils::yield_and_retry(proc);
+    }
+
+    let sent = proc.write_user(fd.0, buffer, count);
+
+    if is_error(sent) { Err(sent) } else { Ok(sent) }
+}
+
+/// recvfrom Syscall
+///
+/// Receive data from a connected socket, blocking until some arrives unless
+/// the socket is non blocking or `MSG_DONTWAIT` is given. The peer of a
+/// stream socket is known, so no address is returned.
+pub fn syscall_recvfrom(proc: &mut super::Process, fd: Fd, buffer: usize, count: usize, flags: Flags<MSG_DONTWAIT>, _addr: usize, length: Option<UserPtr<u32>>) -> Result<usize, usize>
+{
+    with_socket(proc, fd, |_| Ok(()))?;
+
+    if let Some(length) = length
+    {
+        length.write(proc, 0)?;
+    }
+
+    if proc.check_available(fd.0)
+    {
+        let received = proc.read_user(fd.0, buffer, count);
+
+        return if is_error(received) { Err(received) } else { Ok(received) };
+    }
+
+    if flags.contains(MSG_DONTWAIT) || is_nonblocking(proc, fd)
+    {
+        return Err(errno::EAGAIN);
+    }
+
+    // Fault on a bad buffer now rather than once data arrives
+    super::utils::user_buffer_segments(proc, buffer, count, true)?;
+
+    proc.state = ProcessState::Waiting(WaitMode::ForIO((fd.0, count, buffer)));
+    proc.program_counter += 4;
+
+    let schedule = process::scheduler::schedule_next();
+    process::scheduler::schedule_jump(schedule);
+}
+
+/// shutdown Syscall
+pub fn syscall_shutdown(proc: &mut super::Process, fd: Fd, how: usize) -> Result<usize, usize>
+{
+    with_socket(proc, fd, |desc| desc.socket().shutdown(how))?;
+
+    Ok(0)
+}
+
+/// getsockname Syscall
+pub fn syscall_getsockname(proc: &mut super::Process, fd: Fd, addr: usize, length: UserPtr<u32>) -> Result<usize, usize>
+{
+    let address = with_socket(proc, fd, |desc| Ok(desc.socket().local_address()))?;
+    write_address(proc, address, addr, length)?;
+
+    Ok(0)
+}
+
+/// getpeername Syscall
+pub fn syscall_getpeername(proc: &mut super::Process, fd: Fd, addr: usize, length: UserPtr<u32>) -> Result<usize, usize>
+{
+    let address = with_socket(proc, fd, |desc| desc.socket().peer_address())?;
+    write_address(proc, address, addr, length)?;
+
+    Ok(0)
+}
@@ -0,0 +1,189 @@
+use crate::*;
+
+use process::descriptor::{SeekMode, O_NONBLOCK};
+use process::mmap::SharedDescriptor;
+
+use super::args::{Fd, Flags, UserPtr};
+use super::utils::is_error;
+
+// Must be kept in sync with syscalls.h
+pub const SPLICE_F_MOVE: usize =     1;
+pub const SPLICE_F_NONBLOCK: usize = 2;
+pub const SPLICE_F_MORE: usize =     4;
+pub const SPLICE_F_GIFT: usize =     8;
+
+pub type SpliceFlags = Flags<{ SPLICE_F_MOVE | SPLICE_F_NONBLOCK | SPLICE_F_MORE | SPLICE_F_GIFT }>;
+
+/// Run an operation on a file at the offset given by the user, leaving the
+/// position of the file unchanged and updating the offset, without an offset
+/// the current position is used
+fn at_offset<F: FnOnce() -> usize>(proc: &mut super::Process, desc: &SharedDescriptor, offset: Option<UserPtr<u64>>, op: F) -> Result<usize, usize>
+{
+    if let Some(offset) = offset
+    {
+        let position = offset.read(proc)? as usize;
+        let saved = desc.borrow_mut().seek(0, SeekMode::SeekCurrent);
+
+        if is_error(saved) || is_error(desc.borrow_mut().seek(position, SeekMode::SeekSet))
+        {
+            return Err(errno::ESPIPE);
+        }
+
+        let result = op();
+
+        let end = desc.borrow_mut().seek(0, SeekMode::SeekCurrent);
+        desc.borrow_mut().seek(saved, SeekMode::SeekSet);
+
+        offset.write(proc, end as u64)?;
+
+        Ok(result)
+    }
+    else
+    {
+        Ok(op())
+    }
+}
+
+/// splice Syscall
+///
+/// Move data between a pipe and another descriptor. Between two pipes the
+/// pages are moved from one pipe to the other, otherwise the file is read
+/// directly into the pages of the pipe or written directly from them, so the
+/// data is never copied through userspace. Offsets may only be given for the
+/// side which is not a pipe.
+pub fn syscall_splice(proc: &mut super::Process, fd_in: Fd, off_in: Option<UserPtr<u64>>, fd_out: Fd, off_out: Option<UserPtr<u64>>, length: usize, flags: SpliceFlags) -> Result<usize, usize>
+{
+    let input = proc.data.descriptors.get(&fd_in.0).unwrap();
+    let output = proc.data.descriptors.get(&fd_out.0).unwrap();
+
+    let in_pipe = input.borrow().get_pipe_end();
+    let out_pipe = output.borrow().get_pipe_end();
+
+    if in_pipe.as_ref().map(|end| end.writable).unwrap_or(false) || out_pipe.as_ref().map(|end| !end.writable).unwrap_or(false)
+    {
+        return Err(errno::EBADF);
+    }
+
+    if (in_pipe.is_some() && off_in.is_some()) || (out_pipe.is_some() && off_out.is_some())
+    {
+        return Err(errno::ESPIPE);
+    }
+
+    if let Some(dest) = &out_pipe
+    {
+        if dest.peer_closed
+        {
+            return Err(errno::EPIPE);
+        }
+    }
+
+    if length == 0
+    {
+        return Ok(0);
+    }
+
+    proc.ensure_fs();
+
+    let moved = match (&in_pipe, &out_pipe)
+    {
+        (Some(src), Some(dest)) =>
+        {
+            if alloc::sync::Arc::ptr_eq(&src.buffer, &dest.buffer)
+            {
+                return Err(errno::EINVAL);
+            }
+
+            if src.buffer.borrow().is_empty() && src.peer_closed
+            {
+                return Ok(0);
+            }
+
+            src.buffer.borrow_mut().transfer(&mut dest.buffer.borrow_mut(), length, true)
+        },
+        (Some(src), None) =>
+        {
+            if src.buffer.borrow().is_empty() && src.peer_closed
+            {
+                return Ok(0);
+            }
+
+            let fs = &mut **proc.fs_interface.as_mut().unwrap() as *mut fs::vfs::FilesystemInterface;
+            let mut error = None;
+
+            let moved = at_offset(proc, &output, off_out, ||
+                src.buffer.borrow_mut().drain(length, |data|
+                {
+                    let written = output.borrow_mut().write(unsafe { &mut *fs }, data.as_ptr() as *mut u8, data.len());
+
+                    if is_error(written)
+                    {
+                        error = Some(written);
+                        0
+                    }
+                    else
+                    {
+                        written
+                    }
+                }))?;
+
+            match error
+            {
+                Some(e) if moved == 0 => return Err(e),
+                _ => moved
+            }
+        },
+        (None, Some(dest)) =>
+        {
+            // A full pipe waits for the reader, reading nothing from a pipe
+            // with room in it is the end of the file
+            if dest.buffer.borrow().space() == 0
+            {
+                0
+            }
+            else
+            {
+                let fs = &mut **proc.fs_interface.as_mut().unwrap() as *mut fs::vfs::FilesystemInterface;
+                let mut error = None;
+
+                let moved = at_offset(proc, &input, off_in, ||
+                    dest.buffer.borrow_mut().fill(length, |buffer|
+                    {
+                        let read = input.borrow_mut().read(unsafe { &mut *fs }, buffer.as_mut_ptr(), buffer.len());
+
+                        if is_error(read)
+                        {
+                            error = Some(read);
+                            0
+                        }
+                        else
+                        {
+                            read
+                        }
+                    }))?;
+
+                match error
+                {
+                    Some(e) if moved == 0 => return Err(e),
+                    _ => return Ok(moved)
+                }
+            }
+        },
+        (None, None) => return Err(errno::EINVAL)
+    };
+
+    // Nothing could be moved as the input pipe is empty or the output pipe
+    // is full, so wait for the other end to catch up
+    if moved == 0
+    {
+        let nonblocking = [&input, &output].iter().any(|desc| desc.borrow().get_status_flags() & O_NONBLOCK > 0);
+
+        if flags.contains(SPLICE_F_NONBLOCK) || nonblocking
+        {
+            return Err(errno::EAGAIN);
+        }
+
+        super::utils::yield_and_retry(proc);
+    }
+
+    Ok(moved)
+}
@@ -1,3 +1,7 @@
+use libutils::paths::OwnedPath;
+
+use super::args::UserPtr;
+
 /// Stat structure
 #[repr(C)]
 pub struct OutputStatStruct
@@ -18,32 +22,26 @@ pub struct OutputStatStruct
 }
 
 /// Stat Syscall
-pub fn syscall_stat(proc: &mut super::Process, path_ptr: usize, buffer_ptr: usize) -> Result<usize, usize>
+pub fn syscall_stat(proc: &mut super::Process, path: OwnedPath, buffer: UserPtr<OutputStatStruct>) -> Result<usize, usize>
 {
-    let buffer_ptr = proc.map_mem(buffer_ptr).unwrap() as *mut OutputStatStruct;
-    
-    let expanded_path = super::utils::userspace_string_to_path(proc, path_ptr)?;
+    let stat_data = proc.stat(path)?;
 
-    let stat_data = proc.stat(expanded_path)?;
-
-    unsafe
+    buffer.write(proc, OutputStatStruct
     {
-        let buf = buffer_ptr.as_mut().unwrap();
-
-        buf.dev_id = stat_data.dev_id;
-        buf.inode = stat_data.inode;
-        buf.mode = stat_data.mode;
-        buf.links = stat_data.links;
-        buf.uid = stat_data.uid;
-        buf.gid = stat_data.gid;
-        buf.special_dev_id = stat_data.special_dev_id;
-        buf.size = stat_data.size;
-        buf.blk_size = stat_data.blk_size;
-        buf.blocks_alloced = stat_data.blocks_alloced;
-        buf.atime = stat_data.atime;
-        buf.mtime = stat_data.mtime;
-        buf.ctime = stat_data.ctime;
-    }
+        dev_id: stat_data.dev_id,
+        inode: stat_data.inode,
+        mode: stat_data.mode,
+        links: stat_data.links,
+        uid: stat_data.uid,
+        gid: stat_data.gid,
+        special_dev_id: stat_data.special_dev_id,
+        size: stat_data.size,
+        blk_size: stat_data.blk_size,
+        blocks_alloced: stat_data.blocks_alloced,
+        atime: stat_data.atime,
+        mtime: stat_data.mtime,
+        ctime: stat_data.ctime
+    })?;
 
     Ok(0)
 }
\ No newline at end of file
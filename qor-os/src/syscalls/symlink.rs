@@ -0,0 +1,24 @@
+use libutils::paths::OwnedPath;
+
+use super::utils::{string_from_user, userspace_path_at, MAX_PATH_LENGTH};
+
+/// symlink Syscall
+pub fn syscall_symlink(proc: &mut super::Process, target: usize, link_path: OwnedPath) -> Result<usize, usize>
+{
+    let target = string_from_user(proc, target, MAX_PATH_LENGTH)?;
+
+    proc.symlink(target, link_path)?;
+
+    Ok(0)
+}
+
+/// symlinkat Syscall
+pub fn syscall_symlinkat(proc: &mut super::Process, target: usize, dirfd: isize, link_path: usize) -> Result<usize, usize>
+{
+    let target = string_from_user(proc, target, MAX_PATH_LENGTH)?;
+    let link_path = userspace_path_at(proc, dirfd, link_path)?;
+
+    proc.symlink(target, link_path)?;
+
+    Ok(0)
+}
@@ -1,12 +1,44 @@
 use crate::*;
 
+use fs::fstrait::Filesystem;
+
+use super::args::Fd;
+
 /// sync Syscall
 pub fn syscall_sync(proc: &mut super::Process) -> usize
 {
     kdebugln!(Syscalls, "PID {} requests fs sync", proc.pid);
 
-    use fs::fstrait::Filesystem;
-    fs::vfs::get_vfs_reference().unwrap().sync().unwrap();
+    // sync cannot fail, errors are only reported to the kernel log
+    if let Err(e) = fs::vfs::get_vfs_reference().unwrap().sync()
+    {
+        kerrorln!("Unable to sync filesystems: {:?}", e);
+    }
 
     0
-}
\ No newline at end of file
+}
+
+/// fsync Syscall
+///
+/// Writes the data and metadata of the file behind the descriptor back to the
+/// disk, descriptors without an inode give `EINVAL`
+pub fn syscall_fsync(proc: &mut super::Process, fd: Fd) -> Result<usize, usize>
+{
+    kdebugln!(Syscalls, "PID {} requests fs sync of fd {}", proc.pid, fd.0);
+
+    let inode = proc.descriptor_inode(fd.0)?;
+
+    proc.ensure_fs();
+    proc.fs_interface.as_mut().unwrap().fsync_inode(inode).map_err(|e| e.to_errno())?;
+
+    Ok(0)
+}
+
+/// fdatasync Syscall
+///
+/// The inode is stored in the same blocks as the metadata needed to read the
+/// data back, so this is the same as fsync
+pub fn syscall_fdatasync(proc: &mut super::Process, fd: Fd) -> Result<usize, usize>
+{
+    syscall_fsync(proc, fd)
+}
@@ -0,0 +1,49 @@
+use crate::*;
+
+use super::args::Fd;
+use super::splice::{SpliceFlags, SPLICE_F_NONBLOCK};
+
+/// tee Syscall
+///
+/// Duplicate data from one pipe into another without consuming it, the
+/// output pipe is given references to the pages of the input pipe rather
+/// than a copy of the data
+pub fn syscall_tee(proc: &mut super::Process, fd_in: Fd, fd_out: Fd, length: usize, flags: SpliceFlags) -> Result<usize, usize>
+{
+    let src = proc.data.descriptors.get(&fd_in.0).unwrap().borrow().get_pipe_end().ok_or(errno::EINVAL)?;
+    let dest = proc.data.descriptors.get(&fd_out.0).unwrap().borrow().get_pipe_end().ok_or(errno::EINVAL)?;
+
+    if src.writable || !dest.writable
+    {
+        return Err(errno::EBADF);
+    }
+
+    if alloc::sync::Arc::ptr_eq(&src.buffer, &dest.buffer)
+    {
+        return Err(errno::EINVAL);
+    }
+
+    if dest.peer_closed
+    {
+        return Err(errno::EPIPE);
+    }
+
+    if length == 0 || (src.buffer.borrow().is_empty() && src.peer_closed)
+    {
+        return Ok(0);
+    }
+
+    let copied = src.buffer.borrow_mut().transfer(&mut dest.buffer.borrow_mut(), length, false);
+
+    if copied == 0
+    {
+        if flags.contains(SPLICE_F_NONBLOCK)
+        {
+            return Err(errno::EAGAIN);
+        }
+
+        super::utils::yield_and_retry(proc);
+    }
+
+    Ok(copied)
+}
@@ -0,0 +1,64 @@
+use crate::*;
+
+use process::stats::{to_clock_ticks, ResourceUsage};
+
+use super::args::UserPtr;
+
+// Must be kept in sync with syscalls.h
+pub const RUSAGE_SELF: isize = 0;
+pub const RUSAGE_CHILDREN: isize = -1;
+pub const RUSAGE_THREAD: isize = 1;
+
+/// Processor times in clock ticks, as written by times
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct ProcessTimes
+{
+    pub user: usize,
+    pub system: usize,
+    pub children_user: usize,
+    pub children_system: usize
+}
+
+/// times Syscall
+///
+/// Write the processor time used by the process and its waited for children
+/// in clock ticks, returning the number of clock ticks since boot
+pub fn syscall_times(proc: &mut super::Process, buffer: Option<UserPtr<ProcessTimes>>) -> Result<usize, usize>
+{
+    if let Some(buffer) = buffer
+    {
+        let times = proc.data.cpu_times;
+
+        buffer.write(proc, ProcessTimes
+        {
+            user: to_clock_ticks(times.user),
+            system: to_clock_ticks(times.system),
+            children_user: to_clock_ticks(times.children_user),
+            children_system: to_clock_ticks(times.children_system)
+        })?;
+    }
+
+    Ok(to_clock_ticks(drivers::timer::uptime()))
+}
+
+/// getrusage Syscall
+///
+/// Report the processor time used by the process, or by the children it has
+/// waited on. Each thread is its own process, so a thread reports the same
+/// usage as its process.
+pub fn syscall_getrusage(proc: &mut super::Process, who: isize, usage: UserPtr<ResourceUsage>) -> Result<usize, usize>
+{
+    let times = proc.data.cpu_times;
+
+    let report = match who
+    {
+        RUSAGE_SELF | RUSAGE_THREAD => ResourceUsage::new(times.user, times.system),
+        RUSAGE_CHILDREN => ResourceUsage::new(times.children_user, times.children_system),
+        _ => return Err(errno::EINVAL)
+    };
+
+    usage.write(proc, report)?;
+
+    Ok(0)
+}
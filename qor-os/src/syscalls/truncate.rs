@@ -0,0 +1,39 @@
+use crate::*;
+
+use fs::fstrait::Filesystem;
+use libutils::paths::OwnedPath;
+
+use super::args::Fd;
+
+/// truncate Syscall
+///
+/// Cut off the end of the file at the path, or extend it with zeros, to the
+/// given length
+pub fn syscall_truncate(proc: &mut super::Process, path: OwnedPath, length: isize) -> Result<usize, usize>
+{
+    if length < 0
+    {
+        return Err(errno::EINVAL);
+    }
+
+    let inode = proc.path_inode(&path)?;
+    proc.fs_interface.as_mut().unwrap().truncate_inode(inode, length as usize).map_err(|e| e.to_errno())?;
+
+    Ok(0)
+}
+
+/// ftruncate Syscall
+///
+/// Change the length of the file behind a descriptor, which must be open for
+/// writing
+pub fn syscall_ftruncate(proc: &mut super::Process, fd: Fd, length: isize) -> Result<usize, usize>
+{
+    if length < 0
+    {
+        return Err(errno::EINVAL);
+    }
+
+    proc.truncate(fd.0, length as usize)?;
+
+    Ok(0)
+}
@@ -0,0 +1,9 @@
+/// umask Syscall
+pub fn syscall_umask(proc: &mut super::Process, mask: usize) -> Result<usize, usize>
+{
+    let previous = proc.data.umask;
+
+    proc.data.umask = mask as u16 & 0o777;
+
+    Ok(previous as usize)
+}
@@ -0,0 +1,124 @@
+use crate::*;
+
+use fs::structures::FilesystemIndex;
+
+use libutils::paths::OwnedPath;
+
+use super::args::Flags;
+
+// Must be kept in sync with syscalls.h
+const UMOUNT_NOFOLLOW: usize = 8;
+
+/// Check if the process has any files open on, or its working or root
+/// directory within, the filesystem with the given mount id
+fn process_uses_mount(proc: &mut super::Process, vfs: &mut fs::vfs::FilesystemInterface, mount_id: usize) -> bool
+{
+    for descriptor in proc.data.descriptors.values()
+    {
+        match descriptor.try_borrow_mut()
+        {
+            Ok(mut descriptor) =>
+            {
+                if descriptor.get_inode().map(|inode| inode.mount_id == mount_id).unwrap_or(false)
+                {
+                    return true;
+                }
+            },
+            Err(_) => { return true; }
+        }
+    }
+
+    if proc.data.root.map(|root| root.mount_id == mount_id).unwrap_or(false)
+    {
+        return true;
+    }
+
+    fs::namespace::path_to_inode(&proc.data.mount_namespace, proc.data.root, vfs, &proc.data.cwd)
+        .map(|inode| inode.mount_id == mount_id)
+        .unwrap_or(false)
+}
+
+/// Check if any process is using the filesystem with the given root
+fn mount_in_use(proc: &mut super::Process, vfs: &mut fs::vfs::FilesystemInterface, root: FilesystemIndex) -> bool
+{
+    if process_uses_mount(proc, vfs, root.mount_id)
+    {
+        return true;
+    }
+
+    let manager = process::scheduler::get_process_manager().unwrap();
+
+    for (pid, other) in manager.processes.iter_mut()
+    {
+        if *pid != proc.pid && process_uses_mount(other, vfs, root.mount_id)
+        {
+            return true;
+        }
+    }
+
+    false
+}
+
+/// Check if any process has the filesystem with the given root mounted in its
+/// mount namespace
+fn mount_in_namespace(proc: &mut super::Process, root: FilesystemIndex) -> bool
+{
+    let mounted = |namespace: &Option<fs::namespace::SharedMountNamespace>|
+        namespace.as_ref().map(|namespace| namespace.borrow().mounts().any(|(_, mount)| *mount == root)).unwrap_or(false);
+
+    if mounted(&proc.data.mount_namespace)
+    {
+        return true;
+    }
+
+    let manager = process::scheduler::get_process_manager().unwrap();
+
+    manager.processes.iter().any(|(pid, other)| *pid != proc.pid && mounted(&other.data.mount_namespace))
+}
+
+/// umount2 Syscall
+///
+/// Unmount the filesystem mounted at the target, fails with EBUSY if any
+/// process has files open on it or is working within it. Processes in a
+/// mount namespace only remove the mount from their namespace, and the
+/// filesystem is released once no namespace holds it.
+pub fn syscall_umount2(proc: &mut super::Process, target: OwnedPath, _flags: Flags<UMOUNT_NOFOLLOW>) -> Result<usize, usize>
+{
+    proc.ensure_fs();
+    let vfs = fs::vfs::get_vfs_reference().ok_or(errno::ENODEV)?;
+
+    if let Some(namespace) = proc.data.mount_namespace.clone()
+    {
+        let root = namespace.borrow().mounted_at(&target).ok_or(errno::EINVAL)?;
+
+        if mount_in_use(proc, vfs, root)
+        {
+            return Err(errno::EBUSY);
+        }
+
+        kdebugln!(Syscalls, "PID {} unmounting {} from its namespace", proc.pid, target);
+
+        namespace.borrow_mut().unmount(&target);
+
+        // Copies of the namespace made by unshare may still hold the mount
+        if !mount_in_namespace(proc, root)
+        {
+            vfs.unregister_fs(root.mount_id);
+        }
+
+        return Ok(0);
+    }
+
+    let root = fs::namespace::path_to_inode(&None, proc.data.root, vfs, &target).map_err(|e| e.to_errno())?;
+
+    if mount_in_use(proc, vfs, root)
+    {
+        return Err(errno::EBUSY);
+    }
+
+    kdebugln!(Syscalls, "PID {} unmounting {}", proc.pid, target);
+
+    vfs.unmount_fs(root).map_err(|e| e.to_errno())?;
+
+    Ok(0)
+}
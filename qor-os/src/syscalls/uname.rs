@@ -0,0 +1,49 @@
+use super::args::UserPtr;
+
+// Length of each field of the utsname structure, including the terminator
+const UTSNAME_LENGTH: usize = 65;
+
+/// System identification as returned by uname
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct UtsName
+{
+    pub sysname: [u8; UTSNAME_LENGTH],
+    pub nodename: [u8; UTSNAME_LENGTH],
+    pub release: [u8; UTSNAME_LENGTH],
+    pub version: [u8; UTSNAME_LENGTH],
+    pub machine: [u8; UTSNAME_LENGTH],
+    pub domainname: [u8; UTSNAME_LENGTH]
+}
+
+/// Convert a string to a NUL terminated utsname field
+fn field(value: &str) -> [u8; UTSNAME_LENGTH]
+{
+    let mut result = [0; UTSNAME_LENGTH];
+    let length = value.len().min(UTSNAME_LENGTH - 1);
+
+    result[..length].copy_from_slice(&value.as_bytes()[..length]);
+
+    result
+}
+
+/// uname Syscall
+pub fn syscall_uname(proc: &mut super::Process, buf: UserPtr<UtsName>) -> Result<usize, usize>
+{
+    let name = UtsName
+    {
+        sysname: field("Qor"),
+        nodename: field("qor"),
+        release: field(env!("CARGO_PKG_VERSION")),
+        version: field(concat!("#1 ", env!("CARGO_PKG_NAME"), " ", env!("CARGO_PKG_VERSION"))),
+        machine: field("riscv64"),
+        domainname: field("(none)")
+    };
+
+    let bytes = unsafe { core::slice::from_raw_parts(&name as *const UtsName as *const u8, core::mem::size_of::<UtsName>()) };
+
+    // The structure spans multiple fields of 65 bytes, so it may cross a page
+    super::utils::copy_to_user(proc, buf.addr(), bytes)?;
+
+    Ok(0)
+}
@@ -1,9 +1,9 @@
+use libutils::paths::OwnedPath;
+
 /// unlink Syscall
-pub fn syscall_unlink(proc: &mut super::Process, path_ptr: usize) -> Result<usize, usize>
+pub fn syscall_unlink(proc: &mut super::Process, path: OwnedPath) -> Result<usize, usize>
 {
-    let expanded_path = super::utils::userspace_string_to_path(proc, path_ptr)?;
-
-    proc.unlink(expanded_path)?;
+    proc.unlink(path)?;
 
     Ok(0)
 }
\ No newline at end of file
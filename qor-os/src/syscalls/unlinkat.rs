@@ -0,0 +1,24 @@
+use super::args::Flags;
+use super::utils::userspace_path_at;
+
+// Must be kept in sync with syscalls.h
+pub const AT_REMOVEDIR: usize = 0x200;
+
+/// unlinkat Syscall
+///
+/// Remove a file, or a directory if `AT_REMOVEDIR` is given
+pub fn syscall_unlinkat(proc: &mut super::Process, dirfd: isize, path: usize, flags: Flags<AT_REMOVEDIR>) -> Result<usize, usize>
+{
+    let path = userspace_path_at(proc, dirfd, path)?;
+
+    if flags.contains(AT_REMOVEDIR)
+    {
+        proc.rmdir(path)?;
+    }
+    else
+    {
+        proc.unlink(path)?;
+    }
+
+    Ok(0)
+}
@@ -0,0 +1,23 @@
+use crate::*;
+
+use fs::namespace::MountNamespace;
+
+use super::args::Flags;
+
+// Must be kept in sync with syscalls.h
+const CLONE_NEWNS: usize = 0x20000;
+
+/// unshare Syscall
+///
+/// Mount namespaces are the only namespaces supported so far
+pub fn syscall_unshare(proc: &mut super::Process, flags: Flags<CLONE_NEWNS>) -> Result<usize, usize>
+{
+    if flags.contains(CLONE_NEWNS)
+    {
+        kdebugln!(Syscalls, "PID {} unsharing its mount namespace", proc.pid);
+
+        proc.data.mount_namespace = Some(MountNamespace::unshare(&proc.data.mount_namespace));
+    }
+
+    Ok(0)
+}
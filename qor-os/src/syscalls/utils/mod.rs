@@ -1,36 +1,252 @@
 use crate::*;
 use super::Process;
+use fs::fstrait::Filesystem;
 use libutils::paths::OwnedPath;
 
-// Constants for error handling with long paths
-pub const MAX_PATH_LENGTH: usize = 128;
+// Longest path accepted from userspace, the same as PATH_MAX on Linux
+pub const MAX_PATH_LENGTH: usize = 4096;
 
-/// Convert a userspace string into a canonicalized path
-pub fn userspace_string_to_path(proc: &mut Process, userspace_ptr: usize) -> Result<OwnedPath, usize>
+// Directory file descriptor referring to the working directory
+// Must be kept in sync with syscalls.h
+pub const AT_FDCWD: isize = -100;
+
+// Limits on the arguments and environment passed to execve
+pub const MAX_ARG_LENGTH: usize = 4096;
+pub const MAX_ARG_COUNT: usize = 256;
+
+/// Copy a NUL terminated string out of userspace, at most `max_length` bytes
+/// are read before the terminator must be found, returning `ENAMETOOLONG`
+/// otherwise. Each page is translated separately, so strings which cross a
+/// page boundary are read correctly and unmapped pages give `EFAULT`
+pub fn strncpy_from_user(proc: &Process, userspace_ptr: usize, max_length: usize) -> Result<Vec<u8>, usize>
 {
-    let path_ptr = proc.map_mem(userspace_ptr).map_err( |_| errno::EFAULT )? as *mut u8;
-    let mut path = String::new();
+    let mut result = Vec::new();
 
-    let mut i = 0; 
+    let mut addr = userspace_ptr;
+    let mut page_ptr = 0 as *const u8;
 
     loop
     {
-        if i > MAX_PATH_LENGTH
+        // Translate the address again at the start of every page
+        if page_ptr.is_null() || addr % mem::PAGE_SIZE == 0
+        {
+            page_ptr = proc.map_mem(addr).map_err( |_| errno::EFAULT )? as *const u8;
+        }
+        else
+        {
+            page_ptr = unsafe { page_ptr.add(1) };
+        }
+
+        let v = unsafe { page_ptr.read() };
+
+        if v == 0 { break; }
+
+        if result.len() == max_length
         {
             return Err(errno::ENAMETOOLONG);
         }
 
-        let v = unsafe { path_ptr.add(i).read() } as char;
+        result.push(v);
+
+        addr += 1;
+    }
+
+    Ok(result)
+}
+
+/// Copy a NUL terminated string out of userspace into a `String`
+pub fn string_from_user(proc: &Process, userspace_ptr: usize, max_length: usize) -> Result<String, usize>
+{
+    let bytes = strncpy_from_user(proc, userspace_ptr, max_length)?;
+
+    Ok(bytes.iter().map(|v| *v as char).collect())
+}
+
+/// Copy a buffer into userspace, translating each page separately. Every page
+/// must be writable by the process, which marks shared file pages the kernel
+/// fills as dirty so they are written back, otherwise `EFAULT` is returned
+pub fn copy_to_user(proc: &mut Process, userspace_ptr: usize, data: &[u8]) -> Result<(), usize>
+{
+    let mut addr = userspace_ptr;
+    let mut page_ptr = 0 as *mut u8;
 
-        if v == '\x00' { break; }
+    for v in data
+    {
+        if page_ptr.is_null() || addr % mem::PAGE_SIZE == 0
+        {
+            if !proc.prepare_user_page(addr, true)
+            {
+                return Err(errno::EFAULT);
+            }
 
-        path.push(v);
+            page_ptr = proc.map_mem(addr).map_err( |_| errno::EFAULT )? as *mut u8;
+        }
+        else
+        {
+            page_ptr = unsafe { page_ptr.add(1) };
+        }
 
-        i += 1;
+        unsafe { page_ptr.write(*v) };
+
+        addr += 1;
     }
 
+    Ok(())
+}
+
+/// Copy a buffer out of userspace, translating each page separately
+pub fn copy_from_user(proc: &Process, userspace_ptr: usize, data: &mut [u8]) -> Result<(), usize>
+{
+    let mut addr = userspace_ptr;
+    let mut page_ptr = 0 as *mut u8;
+
+    for v in data
+    {
+        if page_ptr.is_null() || addr % mem::PAGE_SIZE == 0
+        {
+            page_ptr = proc.map_mem(addr).map_err( |_| errno::EFAULT )? as *mut u8;
+        }
+        else
+        {
+            page_ptr = unsafe { page_ptr.add(1) };
+        }
+
+        *v = unsafe { page_ptr.read() };
+
+        addr += 1;
+    }
+
+    Ok(())
+}
+
+/// Translate a userspace buffer into the kernel address and length of each
+/// piece of it which falls within a single page, as consecutive pages are not
+/// guaranteed to be physically contiguous. Every page is checked before any
+/// data moves, unmapped pages, or read only pages the kernel would write to,
+/// give `EFAULT`
+pub fn user_buffer_segments(proc: &mut Process, userspace_ptr: usize, length: usize, write: bool) -> Result<Vec<(*mut u8, usize)>, usize>
+{
+    let end = userspace_ptr.checked_add(length).ok_or(errno::EFAULT)?;
+
+    let mut segments = Vec::new();
+    let mut addr = userspace_ptr;
+
+    while addr < end
+    {
+        let size = (mem::PAGE_SIZE - addr % mem::PAGE_SIZE).min(end - addr);
+
+        if !proc.prepare_user_page(addr, write)
+        {
+            return Err(errno::EFAULT);
+        }
+
+        let ptr = proc.map_mem(addr).map_err( |_| errno::EFAULT )? as *mut u8;
+        segments.push((ptr, size));
+
+        addr += size;
+    }
+
+    Ok(segments)
+}
+
+/// Check if a value returned by a descriptor is an errno
+pub fn is_error(value: usize) -> bool
+{
+    (value as isize) < 0
+}
+
+/// Run a descriptor transfer over each segment of a userspace buffer in turn,
+/// stopping after the first short transfer. An errno is only given back if
+/// nothing was transferred, otherwise the number of bytes moved is returned
+pub fn transfer_segments(segments: &[(*mut u8, usize)], mut transfer: impl FnMut(*mut u8, usize) -> usize) -> usize
+{
+    let mut total = 0;
+
+    for (ptr, length) in segments
+    {
+        let moved = transfer(*ptr, *length);
+
+        if is_error(moved)
+        {
+            return if total == 0 { moved } else { total };
+        }
+
+        total += moved;
+
+        if moved < *length
+        {
+            break;
+        }
+    }
+
+    total
+}
+
+/// Convert a userspace string into a canonicalized path
+pub fn userspace_string_to_path(proc: &mut Process, userspace_ptr: usize) -> Result<OwnedPath, usize>
+{
+    let path = string_from_user(proc, userspace_ptr, MAX_PATH_LENGTH)?;
+
     let mut expanded_path = OwnedPath::new(path);
     expanded_path.canonicalize(&proc.data.cwd);
 
     Ok(expanded_path)
-}
\ No newline at end of file
+}
+
+/// Get the path of the directory a directory file descriptor refers to
+fn directory_fd_path(proc: &mut Process, dirfd: isize) -> Result<OwnedPath, usize>
+{
+    proc.ensure_fs();
+
+    let inode = if let Some(descriptor) = proc.data.descriptors.get(&(dirfd as usize))
+    {
+        descriptor.borrow_mut().get_inode().ok_or(errno::ENOTDIR)?
+    }
+    else
+    {
+        return Err(errno::EBADF);
+    };
+
+    let vfs = proc.fs_interface.as_mut().unwrap();
+
+    if vfs.get_stat(inode).map_err(|e| e.to_errno())?.mode & 0x4000 == 0
+    {
+        return Err(errno::ENOTDIR);
+    }
+
+    let path = fs::namespace::inode_to_path(&proc.data.mount_namespace, proc.data.root, vfs, inode).map_err(|e| e.to_errno())?;
+
+    Ok(if path.as_str().len() == 0 { OwnedPath::new("/") } else { path })
+}
+
+/// Convert a userspace string into a canonicalized path relative to the given
+/// directory file descriptor, or to the working directory for `AT_FDCWD`
+pub fn userspace_path_at(proc: &mut Process, dirfd: isize, userspace_ptr: usize) -> Result<OwnedPath, usize>
+{
+    let path = string_from_user(proc, userspace_ptr, MAX_PATH_LENGTH)?;
+
+    let base = if dirfd == AT_FDCWD || path.starts_with('/')
+    {
+        proc.data.cwd.clone()
+    }
+    else
+    {
+        directory_fd_path(proc, dirfd)?
+    };
+
+    let mut expanded_path = OwnedPath::new(path);
+    expanded_path.canonicalize(&base);
+
+    Ok(expanded_path)
+}
+
+/// Give up the processor and run the syscall again the next time the process
+/// is scheduled, used by syscalls which block until another process makes
+/// progress
+pub fn yield_and_retry(_proc: &mut Process) -> !
+{
+    // The program counter is left on the ecall and the argument registers
+    // are untouched, so the syscall is made again with the same arguments
+    let schedule = process::scheduler::schedule_next();
+    process::scheduler::schedule_jump(schedule);
+}
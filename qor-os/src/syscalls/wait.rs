@@ -1,27 +1,46 @@
 use crate::*;
 
-/// Wait Syscall
-pub fn syscall_wait(proc: &mut super::Process, ptr: usize) -> usize
-{
-    let status = 
-        if ptr != 0
-        {
-            proc.map_mem(ptr).unwrap() as *mut u32
-        }
-        else
-        {
-            0 as *mut u32
-        };
+use process::process::{ProcessState, WaitMode};
+use process::wait::*;
 
-    if proc.data.children.len() == 0
+use super::args::Flags;
+
+// Must be kept in sync with syscalls.h
+pub const WUNTRACED: usize = WSTOPPED;
+
+/// wait4 Syscall
+///
+/// Wait for a child to exit, or to stop or continue if asked for. A pid of
+/// -1 waits on any child, zero on any child in the same process group, and a
+/// pid below -1 on any child in that process group. The status word is
+/// written to `status`, with WNOHANG zero is returned if no child has changed
+/// state. The processor time used by the child, along with its waited for
+/// children, is written to `rusage`.
+pub fn syscall_wait4(proc: &mut super::Process, pid: isize, status: usize, options: Flags<{ WNOHANG | WUNTRACED | WCONTINUED }>, rusage: usize) -> Result<usize, usize>
+{
+    let target = match pid
     {
-        return errno::ECHILD;
-    }
+        -1 => WaitTarget::Any,
+        0 => WaitTarget::Group(proc.data.process_group_id),
+        pid if pid < 0 => WaitTarget::Group((-pid) as process::PID),
+        pid => WaitTarget::Pid(pid as process::PID)
+    };
+
+    let request = WaitRequest { target, options: options.0 | WEXITED, report: WaitReport::Status { status, usage: rusage } };
 
-    proc.state = process::process::ProcessState::Waiting(process::process::WaitMode::ForChild);
-    proc.data.return_code_listener = unsafe { status.as_mut() };
-    proc.program_counter += 4;
+    let manager = process::scheduler::get_process_manager().unwrap();
 
-    let schedule = process::scheduler::schedule_next();
-    process::scheduler::schedule_jump(schedule);
-}
\ No newline at end of file
+    match poll_child_event(manager, proc.pid, &request)?
+    {
+        Some(child) => report_child_event(proc, &request, &child),
+        None if options.contains(WNOHANG) => Ok(0),
+        None =>
+        {
+            proc.state = ProcessState::Waiting(WaitMode::ForChildEvent(request));
+            proc.program_counter += 4;
+
+            let schedule = process::scheduler::schedule_next();
+            process::scheduler::schedule_jump(schedule);
+        }
+    }
+}
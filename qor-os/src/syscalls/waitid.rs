@@ -0,0 +1,45 @@
+use crate::*;
+
+use process::process::{ProcessState, WaitMode};
+use process::wait::*;
+
+use super::args::Flags;
+
+/// waitid Syscall
+///
+/// Wait for a child to exit, stop or continue, as selected by the options.
+/// The state change is written to `info` as a signal info structure, with
+/// WNOHANG a pid of zero is reported if no child has changed state.
+pub fn syscall_waitid(proc: &mut super::Process, idtype: usize, id: usize, info: usize, options: Flags<{ WNOHANG | WSTOPPED | WEXITED | WCONTINUED | WNOWAIT }>) -> Result<usize, usize>
+{
+    if !options.contains(WEXITED | WSTOPPED | WCONTINUED)
+    {
+        return Err(errno::EINVAL);
+    }
+
+    let target = match idtype
+    {
+        P_ALL => WaitTarget::Any,
+        P_PID => WaitTarget::Pid(id as process::PID),
+        P_PGID => WaitTarget::Group(if id == 0 { proc.data.process_group_id } else { id as process::PID }),
+        _ => { return Err(errno::EINVAL); }
+    };
+
+    let request = WaitRequest { target, options: options.0, report: WaitReport::SignalInfo(info) };
+
+    let manager = process::scheduler::get_process_manager().unwrap();
+
+    match poll_child_event(manager, proc.pid, &request)?
+    {
+        Some(child) => report_child_event(proc, &request, &child),
+        None if options.contains(WNOHANG) => report_child_event(proc, &request, &child_info(0, 0, 0)),
+        None =>
+        {
+            proc.state = ProcessState::Waiting(WaitMode::ForChildEvent(request));
+            proc.program_counter += 4;
+
+            let schedule = process::scheduler::schedule_next();
+            process::scheduler::schedule_jump(schedule);
+        }
+    }
+}
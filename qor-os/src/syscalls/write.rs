@@ -1,7 +1,26 @@
+use crate::*;
+
+use process::descriptor::O_NONBLOCK;
+
 /// Write Syscall
 pub fn syscall_write(proc: &mut super::Process, fd: usize, buffer: usize, count: usize) -> usize
 {
-    let ptr = proc.map_mem(buffer).unwrap() as *mut u8;
+    // Output to a terminal which has been stopped waits until it is started
+    let stopped = proc.data.descriptors.get(&fd).map(|desc|
+        {
+            let desc = desc.borrow();
+            desc.is_terminal() && !desc.check_writable()
+        }).unwrap_or(false);
+
+    if stopped
+    {
+        if proc.data.descriptors.get(&fd).unwrap().borrow().get_status_flags() & O_NONBLOCK > 0
+        {
+            return errno::EAGAIN;
+        }
+
+        super::utils::yield_and_retry(proc);
+    }
 
-    proc.write(fd, ptr, count)
-}
\ No newline at end of file
+    proc.write_user(fd, buffer, count)
+}
@@ -0,0 +1,120 @@
+//! Magic SysRq
+//!
+//! Sending a break on the console followed by a key runs one of the debugging
+//! actions below directly from the interrupt handler, so they still work when
+//! userspace has stopped reading the console
+
+use crate::*;
+
+use fs::fstrait::Filesystem;
+
+use process::process::ProcessState;
+use process::signals::{POSIXSignal, SignalType};
+use process::PID;
+
+/// Run the SysRq action for the given key, the foreground process group of
+/// the console is passed in for the kill action
+pub fn handle_sysrq(key: u8, foreground: PID)
+{
+    kprintln!();
+
+    match key
+    {
+        b'p' => dump_processes(),
+        b'm' => dump_memory(),
+        b's' => sync_filesystems(),
+        b'k' => kill_foreground(foreground),
+        b'e' => halt::request_shutdown(),
+        b'b' =>
+        {
+            kprintln!("SysRq: Rebooting");
+            unsafe { drivers::POWER_DRIVER.reboot() };
+        },
+        b'o' => halt::power_off(),
+        b'x' => kdb::enter(),
+        _ => show_help()
+    }
+}
+
+/// List the available actions
+fn show_help()
+{
+    kprintln!("SysRq: help: show-processes(p) show-memory(m) sync(s) kill-foreground(k) terminate-all(e) reboot(b) power-off(o) monitor(x)");
+}
+
+/// Print every process
+pub fn dump_processes()
+{
+    kprintln!("SysRq: Show Processes");
+
+    if let Some(manager) = process::scheduler::get_process_manager()
+    {
+        kprintln!("  PID  PPID  PGID  PRI  PAGES  STATE");
+
+        for proc in manager.processes.values()
+        {
+            kprintln!("{:>5} {:>5} {:>5} {:>4} {:>6}  {:?} {}",
+                proc.pid,
+                proc.data.parent_pid,
+                proc.data.process_group_id,
+                proc.data.priority,
+                proc.resident_pages(),
+                proc.get_state(),
+                proc.data.cmdline_args.join(" "));
+        }
+    }
+}
+
+/// Print the kernel memory usage
+pub fn dump_memory()
+{
+    kprintln!("SysRq: Show Memory");
+
+    let allocated = mem::allocated_kernel_pages();
+    let total = mem::total_kernel_pages();
+
+    kprintln!("  Pages: {} allocated, {} free, {} total", allocated, total - allocated, total);
+
+    if let Some(manager) = process::scheduler::get_process_manager()
+    {
+        let process_pages: usize = manager.processes.values().map(|proc| proc.resident_pages()).sum();
+
+        kprintln!("  Process pages: {} across {} processes", process_pages, manager.processes.len());
+    }
+}
+
+/// Sync every mounted filesystem
+pub fn sync_filesystems()
+{
+    kprintln!("SysRq: Emergency Sync");
+
+    if let Some(vfs) = fs::vfs::get_vfs_reference()
+    {
+        if let Err(e) = vfs.sync()
+        {
+            kerrorln!("Unable to sync filesystems: {:?}", e);
+            return;
+        }
+    }
+
+    kprintln!("SysRq: Sync Complete");
+}
+
+/// Kill the foreground process group of the console
+fn kill_foreground(foreground: PID)
+{
+    kprintln!("SysRq: Kill Foreground Group {}", foreground);
+
+    if let Some(manager) = process::scheduler::get_process_manager()
+    {
+        // The init process is never sent the signal, so the system is left
+        // with a process to run
+        let alive = manager.processes.values()
+            .any(|proc| proc.pid != 0 && proc.data.process_group_id == foreground && !matches!(proc.get_state(), ProcessState::Zombie | ProcessState::Dead));
+
+        if !alive || manager.send_signal_group(foreground, 0, POSIXSignal::new(foreground, 0, SignalType::SIGKILL)).is_err()
+        {
+            kwarnln!("SysRq: No processes in group {}", foreground);
+        }
+    }
+}
@@ -8,8 +8,20 @@ pub struct TrapFrame
 	pub satp:       usize,       // 512 - 519
 	pub trap_stack: *mut u8,     // 520
 	pub hartid:     usize,       // 528
+	pub fcsr:       usize,       // 536
+	pub fpu_state:  usize,       // 544
+	pub mstatus:    usize,       // 552
+	pub nested_frame: *mut TrapFrame, // 560
+	pub kernel_satp: usize,      // 568
 }
 
+// The FPU has not been used by the process, the first floating point
+// instruction will trap and enable it
+pub const FPU_STATE_OFF: usize = 0;
+
+// The floating point registers in the frame are valid
+pub const FPU_STATE_ENABLED: usize = 1;
+
 impl TrapFrame
 {
 	/// Create a new trap frame (and allocate space for its stack)
@@ -26,7 +38,12 @@ impl TrapFrame
 			fregs: [0; 32],
 			satp: 0,
 			trap_stack,
-			hartid: 0
+			hartid: 0,
+			fcsr: 0,
+			fpu_state: FPU_STATE_OFF,
+			mstatus: 0,
+			nested_frame: super::kernel_trap_frame(),
+			kernel_satp: crate::mem::kernel_satp()
 		}
 	}
 
@@ -39,7 +56,12 @@ impl TrapFrame
 			fregs: [0; 32],
 			satp: 0,
 			trap_stack: 0 as *mut u8,
-			hartid: 0
+			hartid: 0,
+			fcsr: 0,
+			fpu_state: FPU_STATE_OFF,
+			mstatus: 0,
+			nested_frame: 0 as *mut TrapFrame,
+			kernel_satp: 0
 		}
 	}
 }
\ No newline at end of file
@@ -13,10 +13,29 @@ pub fn dump_on_error()
 /// Switch to the next process
 pub fn switch_process()
 {
+    // Advance any shutdown in progress before picking the next process
+    halt::shutdown_tick();
+
     let schedule = process::scheduler::schedule_next();
 
-    // Prepare the timer for the next tick
-    unsafe { drivers::TIMER_DRIVER.trigger() }
+    // Prepare the timer for the next tick, weighting the time slice by the
+    // resource group of the process being scheduled
+    if let Some(proc) = process::scheduler::current_process()
+    {
+        let mut slice = process::rescg::time_slice(proc.pid, unsafe { drivers::TIMER_DRIVER.get_interval() });
+
+        // Cut the slice short if a sleeping process needs to be woken first
+        if let Some(wake_time) = process::scheduler::get_process_manager().map(|manager| manager.next_wakeup()).flatten()
+        {
+            slice = slice.min(wake_time - unsafe { &drivers::TIMER_DRIVER }.time());
+        }
+
+        unsafe { drivers::TIMER_DRIVER.set_remaining(slice) }
+    }
+    else
+    {
+        unsafe { drivers::TIMER_DRIVER.trigger() }
+    }
 
     process::scheduler::schedule_jump(schedule);
 }
@@ -42,15 +61,28 @@ pub fn interrupt_handler(interrupt_context: InterruptContext) -> usize
             // Get the next external interrupt
             if let Some(interrupt) = unsafe { drivers::PLIC_DRIVER.next_interrupt() }
             {
+                perf::record_since_trap_entry(perf::Measurement::InterruptLatency);
+                percpu::this_cpu().counters.interrupts += 1;
+
                 // Run the handler
                 super::extint::external_interrupt_handler(interrupt, &interrupt_context);
 
                 // Complete the interrupt
                 unsafe { drivers::PLIC_DRIVER.complete(interrupt) }; 
             }
+
+            // End the running time slice early when a terminal receives
+            // input, so a process waiting on it is boosted and run promptly
+            if process::runqueue::take_terminal_input() && process::runqueue::tty_wake_boost() > 0
+            {
+                unsafe { drivers::TIMER_DRIVER.set_remaining(drivers::timer::KernelTime(0)) }
+            }
         },
         InterruptType::UserEnvironmentCall =>
         {
+            perf::record_since_trap_entry(perf::Measurement::SyscallEntry);
+            percpu::this_cpu().counters.syscalls += 1;
+
             let result =syscalls::handle_syscall(process::scheduler::current_process().unwrap(),
                                                     interrupt_context.get_frame_mut().regs[17],
                                                     interrupt_context.get_frame_mut().regs[10],
@@ -63,17 +95,89 @@ pub fn interrupt_handler(interrupt_context: InterruptContext) -> usize
 
             interrupt_context.get_frame_mut().regs[10] = result;
 
+            perf::mark_syscall_exit();
+
+            // Act on any signals which arrived during the syscall, such as a
+            // process signalling itself, before returning to user mode
+            if let Some(proc) = process::scheduler::current_process()
+            {
+                if proc.get_state() == process::process::ProcessState::Running && proc.has_pending_signals()
+                {
+                    proc.program_counter = interrupt_context.instruction_address() + 4;
+
+                    switch_process();
+                }
+            }
+
             return interrupt_context.instruction_address() + 4;
         },
+        // The kernel cannot switch away from a trap which interrupted
+        // another trap, as the interrupted handler would never be resumed, so
+        // preemption is put off until the next tick
+        InterruptType::MachineTimerInterrupt if super::in_nested_trap() =>
+        {
+            kdebugln!(Interrupts, "Deferring preemption, timer fired during a nested trap");
+
+            process::stats::sample_tick(false);
+
+            unsafe { drivers::TIMER_DRIVER.set_remaining(drivers::TIMER_DRIVER.get_interval()) }
+        },
         InterruptType::MachineTimerInterrupt =>
         {
+            perf::record_since_trap_entry(perf::Measurement::InterruptLatency);
+            percpu::this_cpu().counters.interrupts += 1;
+
+            process::stats::sample_tick((interrupt_context.get_status() >> 11) & 3 == 0);
+
+            process::scheduler::wake_sleepers();
+            drivers::gpu::refresh_console();
+
             switch_process();
         },
+        // The first floating point instruction executed by a process traps as
+        // the FPU starts off, so enable it with a fresh state and retry
+        InterruptType::IllegalInstruction if (interrupt_context.get_status() >> 11) & 3 == 0 &&
+                                             (interrupt_context.get_status() >> 13) & 3 == 0 &&
+                                             interrupt_context.get_frame_mut().fpu_state == super::frame::FPU_STATE_OFF =>
+        {
+            kdebugln!(Interrupts, "Enabling the FPU for the current process");
+
+            let frame = interrupt_context.get_frame_mut();
+
+            frame.fregs = [0; 32];
+            frame.fcsr = 0;
+            frame.fpu_state = super::frame::FPU_STATE_ENABLED;
+
+            return interrupt_context.instruction_address();
+        },
+        // Any other trap taken while handling a trap is a fault in the kernel
+        default if super::in_nested_trap() =>
+        {
+            kerrorln!("{}", interrupt_context);
+            dump_on_error();
+            panic!("Unhandled Nested Trap: {:?}", default);
+        },
         default =>
         {
             // If the trap occured during a process, report it as a fatal fault
             if let Some(proc) = process::scheduler::current_process()
             {
+                // A signal handler installed without a restorer returns to an
+                // address which is never mapped, which acts as sigreturn
+                if matches!(default, InterruptType::InstructionPageFault) &&
+                   interrupt_context.instruction_address() == process::process::SIGNAL_RETURN_ADDRESS
+                {
+                    proc.return_from_signal();
+                }
+
+                // A store to a shared file mapping which was written back
+                // marks the page dirty again
+                if matches!(interrupt_context.get_cause(), InterruptType::StorePageFault) &&
+                   proc.address_space().handle_write_fault(interrupt_context.get_associated_value())
+                {
+                    return interrupt_context.instruction_address();
+                }
+
                 // Check if the interrupt is a Page Fault, if it is and the address is within the valid stack region, attempt to expand the stack
                 if matches!(interrupt_context.get_cause(), InterruptType::LoadPageFault | InterruptType::StorePageFault)
                 {
@@ -81,24 +185,34 @@ pub fn interrupt_handler(interrupt_context: InterruptContext) -> usize
 
                     if address >= process::process::STACK_START && address <= process::process::STACK_END
                     {
-                        proc.expand_stack(interrupt_context.get_associated_value());
-
-                        return interrupt_context.instruction_address();
+                        if proc.address_space().expand_stack(interrupt_context.get_associated_value())
+                        {
+                            return interrupt_context.instruction_address();
+                        }
                     }
                 }
 
                 kerrorln!("{}", interrupt_context);
 
+                let sig_type = match default
+                {
+                    InterruptType::InstructionPageFault | InterruptType::LoadPageFault | InterruptType::StorePageFault |
+                    InterruptType::InstructionAccessFault | InterruptType::LoadAccessFault | InterruptType::StoreAccessFault => SignalType::SIGSEGV,
+                    InterruptType::InstructionAddressMisaligned | InterruptType::LoadAddressMisaligned | InterruptType::StoreAddressMisaligned => SignalType::SIGBUS,
+                    InterruptType::IllegalInstruction => SignalType::SIGILL,
+                    _ => SignalType::SIGTRAP
+                };
+
                 if process::scheduler::get_process_manager().as_mut().unwrap().send_signal(
                     proc.pid, 
                             POSIXSignal
                             {
-                                sig_type: SignalType::SIGTRAP,
+                                sig_type,
                                 sending_pid: 0,
                                 dest_pid: proc.pid,
                             }).is_err()
                 {
-                    kwarnln!("Unable to send SIGTERM to PID {}", proc.pid);   
+                    kwarnln!("Unable to send {:?} to PID {}", sig_type, proc.pid);   
                 }
 
                 switch_process();
@@ -14,9 +14,95 @@ pub use context::InterruptType;
 
 pub use frame::TrapFrame;
 
+/// Get the frame used for nested traps, it saves the state of the kernel when
+/// a trap is taken while another trap is being handled, and has its own
+/// interrupt stack so the stack of the interrupted handler is left untouched
+pub fn kernel_trap_frame() -> *mut TrapFrame
+{
+    percpu!(kernel_trap_frame)
+}
+
+/// Get the number of traps currently being handled
+pub fn trap_depth() -> usize
+{
+    percpu!(trap_depth)
+}
+
+/// Check if the current trap was taken while handling another trap
+pub fn in_nested_trap() -> bool
+{
+    trap_depth() > 1
+}
+
+/// Record entry into a trap handler
+pub fn enter_trap()
+{
+    percpu!(trap_depth = trap_depth() + 1);
+}
+
+/// Record exit from a trap handler
+pub fn leave_trap()
+{
+    percpu!(trap_depth = trap_depth() - 1);
+}
+
+/// Reset the trap depth, used when the kernel leaves a trap handler without
+/// returning from it, such as when jumping to a new process
+pub fn reset_trap_depth()
+{
+    percpu!(trap_depth = 0);
+}
+
+/// Enable interrupts while handling a trap, this allows a long running
+/// syscall to be interrupted by device interrupts, which are handled on the
+/// kernel trap frame
+pub fn enable_kernel_interrupts()
+{
+    unsafe { riscv::register::mstatus::set_mie() };
+}
+
+/// Disable interrupts while handling a trap
+pub fn disable_kernel_interrupts()
+{
+    unsafe { riscv::register::mstatus::clear_mie() };
+}
+
+/// Run a function with interrupts enabled, restoring the disabled state
+/// afterwards. Interrupts are only enabled while handling a trap which did not
+/// interrupt another, as there is a single kernel trap frame, elsewhere the
+/// function is run as it is
+pub fn with_kernel_interrupts<T, F: FnOnce() -> T>(f: F) -> T
+{
+    if trap_depth() != 1
+    {
+        return f();
+    }
+
+    enable_kernel_interrupts();
+    let result = f();
+    disable_kernel_interrupts();
+
+    result
+}
+
 /// Initialize the trap frame into mscratch
 pub fn init_trap_frame()
 {
+    // Initialize the kernel trap frame first so the frames created after it
+    // can refer to it
+    let mut kernel_frame = TrapFrame::new(4);
+
+    // The kernel always has the FPU enabled, so its floating point state must
+    // be restored when a nested trap returns
+    kernel_frame.fpu_state = frame::FPU_STATE_ENABLED;
+
+    let kernel_frame = Box::leak(Box::new(kernel_frame)) as *mut TrapFrame;
+
+    // A trap taken while handling a nested trap is a fatal kernel fault, so the
+    // kernel frame is reused rather than nesting any deeper
+    unsafe { kernel_frame.as_mut().unwrap() }.nested_frame = kernel_frame;
+    percpu!(kernel_trap_frame = kernel_frame);
+
     // Initialize the trap frame
     let trap_frame = TrapFrame::new(2);
 
@@ -1,3 +1,5 @@
+use crate::*;
+
 /// Trap handler (only called from the trap handler in assembly)
 #[no_mangle]
 extern "C" fn m_trap(epc: usize,
@@ -8,6 +10,16 @@ extern "C" fn m_trap(epc: usize,
                      frame: &'static mut super::TrapFrame)
                      -> usize
 {
-    super::handler::interrupt_handler(
-        super::InterruptContext::new(epc, tval, cause, hart, status, frame))
+    perf::mark_trap_entry();
+    super::enter_trap();
+
+    percpu::this_cpu().counters.traps += 1;
+
+    let result = super::handler::interrupt_handler(
+        super::InterruptContext::new(epc, tval, cause, hart, status, frame));
+
+    super::leave_trap();
+    perf::finish_syscall_exit();
+
+    result
 }
\ No newline at end of file
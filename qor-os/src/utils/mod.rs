@@ -4,8 +4,7 @@
 pub mod memdump;
 pub use memdump::*;
 
-pub mod ringbuffer;
-pub use ringbuffer::*;
+pub use libutils::ringbuffer::*;
 
 pub mod blocking;
 pub use blocking::*;